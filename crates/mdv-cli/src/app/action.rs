@@ -4,9 +4,18 @@ use super::state::{PaneFocus, ThemeChoice};
 pub enum Action {
     ToggleFocus,
     ToggleHelp,
+    ToggleViewOnly,
+    ToggleMaximize,
+    /// Move the split divider to widen the focused pane.
+    GrowPane,
+    /// Move the split divider to narrow the focused pane.
+    ShrinkPane,
     ApplyPrefs {
         focus: PaneFocus,
         theme: ThemeChoice,
         no_color: bool,
     },
+    ToggleLineNumbers,
+    ToggleSoftWrap,
+    ToggleScrollSync,
 }
@@ -0,0 +1,167 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::resume;
+
+/// Backups kept per file; older ones are removed.
+const KEEP_BACKUPS: usize = 5;
+
+/// Copy `text`, which a reload of `path` is about to replace, to
+/// `<root>/<name>-<hash>/<timestamp>.md`, keeping the newest five backups of
+/// that file. A hash of the canonical path keeps two `notes.md` apart.
+pub(super) fn write_backup(
+    root: &Path,
+    path: &Path,
+    text: &str,
+    now: SystemTime,
+) -> io::Result<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no file name"))?
+        .to_string_lossy();
+    let key = resume::file_key(path);
+    let dir = root.join(format!(
+        "{name}-{:016x}",
+        fnv1a(key.as_os_str().as_encoded_bytes())
+    ));
+    fs::create_dir_all(&dir)?;
+    let target = dir.join(format!("{}.md", timestamp(now)));
+    fs::write(&target, text)?;
+    prune(&dir)?;
+    Ok(target)
+}
+
+/// Remove all but the newest [`KEEP_BACKUPS`] backups in `dir`.
+fn prune(dir: &Path) -> io::Result<()> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .and_then(|file| file.strip_suffix(".md"))
+                .is_some_and(is_timestamp)
+        })
+        .map(|entry| entry.path())
+        .collect();
+    // The timestamps sort in time order.
+    backups.sort();
+    let old = backups.len().saturating_sub(KEEP_BACKUPS);
+    for path in &backups[..old] {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// 64-bit FNV-1a. Unlike `DefaultHasher` it never changes between Rust
+/// releases, so a file keeps its backup folder across upgrades.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// UTC `YYYYMMDD-HHMMSS-mmm`.
+fn timestamp(time: SystemTime) -> String {
+    let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let (year, month, day) = civil_date(elapsed.as_secs() / 86_400);
+    let secs = elapsed.as_secs() % 86_400;
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}-{:03}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        elapsed.subsec_millis()
+    )
+}
+
+fn is_timestamp(text: &str) -> bool {
+    text.len() == 19
+        && text.bytes().enumerate().all(|(index, byte)| match index {
+            8 | 15 => byte == b'-',
+            _ => byte.is_ascii_digit(),
+        })
+}
+
+/// Year, month and day of the date `days` after 1970-01-01.
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    // Counted in 400-year eras from 0000-03-01, so leap days end a year.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{civil_date, fnv1a, timestamp, write_backup};
+
+    #[test]
+    fn timestamps_are_utc_dates_that_sort_in_time_order() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(11_016), (2000, 2, 29));
+        assert_eq!(civil_date(20_742), (2026, 10, 16));
+        let time = UNIX_EPOCH + Duration::from_millis(20_742 * 86_400_000 + 3_723_045);
+        assert_eq!(timestamp(time), "20261016-010203-045");
+    }
+
+    #[test]
+    fn folder_hashes_are_fixed_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn only_the_newest_five_backups_of_a_file_are_kept() {
+        let dir = std::env::temp_dir().join(format!("mdv-backup-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let root = dir.join("backups");
+        let path = dir.join("notes.md");
+        let other_path = dir.join("other").join("notes.md");
+        let other = write_backup(&root, &other_path, "other", UNIX_EPOCH).expect("other");
+
+        let mut written = Vec::new();
+        for round in 0..7u64 {
+            let now = UNIX_EPOCH + Duration::from_secs(1_000 + round);
+            written
+                .push(write_backup(&root, &path, &format!("text {round}"), now).expect("backup"));
+        }
+        let folder = written[6].parent().expect("folder");
+        assert_eq!(folder.parent(), Some(root.as_path()));
+        assert!(folder.to_string_lossy().contains("notes.md-"));
+        assert_eq!(
+            written[6].file_name().and_then(|name| name.to_str()),
+            Some("19700101-001646-000.md")
+        );
+        // A file of the same name elsewhere gets its own folder.
+        assert_ne!(other.parent(), Some(folder));
+        let mut kept: Vec<_> = fs::read_dir(folder)
+            .expect("read")
+            .map(|entry| entry.expect("entry").path())
+            .collect();
+        kept.sort();
+        assert_eq!(kept, written[2..].to_vec());
+        assert!(other.exists());
+        assert_eq!(fs::read_to_string(&written[6]).expect("read"), "text 6");
+        // Nothing is written next to the file.
+        assert_eq!(fs::read_dir(&dir).expect("dir").count(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
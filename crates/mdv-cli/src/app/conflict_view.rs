@@ -0,0 +1,341 @@
+use std::ops::Range;
+
+use mdv_core::{ConflictHunk, HunkKind, changed_span};
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+
+use super::preview_line::{ConflictSide, PreviewLineKind};
+use super::status::Severity;
+use super::{App, PaneFocus, clamp_scroll, pane_border_style};
+use crate::ui::layout::compute_conflict_columns;
+use crate::ui::theme::ThemeTokens;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum RowKind {
+    /// A line both sides share.
+    Context,
+    /// A line of hunk `n`; the shorter side is padded with `None`.
+    Hunk(usize),
+}
+
+/// One row of the side-by-side view: zero-based line numbers and text on
+/// each side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct ConflictRow<'a> {
+    pub(super) kind: RowKind,
+    pub(super) local: Option<(usize, &'a str)>,
+    pub(super) external: Option<(usize, &'a str)>,
+}
+
+/// Pair up the local and external lines: shared lines sit on the same row
+/// and each hunk takes as many rows as its longer side.
+pub(super) fn conflict_rows<'a>(local: &'a str, hunks: &'a [ConflictHunk]) -> Vec<ConflictRow<'a>> {
+    let mut lines: Vec<&str> = local.split('\n').collect();
+    if lines.len() > 1 && lines.last() == Some(&"") {
+        lines.pop();
+    }
+    let mut rows = Vec::with_capacity(lines.len());
+    let mut next = 0usize;
+    // External line number minus local line number for shared lines.
+    let mut shift = 0isize;
+
+    let push_context = |rows: &mut Vec<ConflictRow<'a>>, range: Range<usize>, shift: isize| {
+        for idx in range {
+            rows.push(ConflictRow {
+                kind: RowKind::Context,
+                local: Some((idx, lines[idx])),
+                external: Some((idx.saturating_add_signed(shift), lines[idx])),
+            });
+        }
+    };
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        let start = hunk.local_start.clamp(next, lines.len());
+        push_context(&mut rows, next..start, shift);
+        let height = hunk.local_lines.len().max(hunk.external_lines.len());
+        for offset in 0..height {
+            rows.push(ConflictRow {
+                kind: RowKind::Hunk(index),
+                local: hunk
+                    .local_lines
+                    .get(offset)
+                    .map(|line| (hunk.local_start + offset, line.as_str())),
+                external: hunk
+                    .external_lines
+                    .get(offset)
+                    .map(|line| (hunk.external_start + offset, line.as_str())),
+            });
+        }
+        next = (start + hunk.local_lines.len()).min(lines.len());
+        shift = (hunk.external_start + hunk.external_lines.len()) as isize - next as isize;
+    }
+    push_context(&mut rows, next..lines.len(), shift);
+    rows
+}
+
+/// The selected hunk before an external update computes the hunks again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct ConflictAnchor {
+    /// How many hunks there were.
+    pub(super) hunks: usize,
+    id: usize,
+    local_start: usize,
+    /// Rows between the top of the conflict view and the hunk.
+    offset: usize,
+}
+
+impl App {
+    /// The side-by-side view replaces the preview while a conflict has hunks,
+    /// unless it was switched off with `Ctrl+L`.
+    pub(super) fn conflict_view_active(&self) -> bool {
+        self.conflict_columns
+            && self
+                .editor
+                .conflict()
+                .is_some_and(|conflict| !conflict.hunks.is_empty())
+    }
+
+    pub(super) fn toggle_conflict_view(&mut self) {
+        if self.editor.conflict().is_none() {
+            self.push_status(Severity::Warn, "No conflict to show");
+            return;
+        }
+        self.conflict_columns = !self.conflict_columns;
+        self.preview_scroll = 0;
+        let layout = if self.conflict_columns {
+            "side by side"
+        } else {
+            "stacked blocks"
+        };
+        self.push_status(Severity::Info, format!("Conflict view: {layout}"));
+    }
+
+    /// Draw the local and external columns in the preview pane. Both columns
+    /// share `preview_scroll`, so they always scroll together.
+    pub(super) fn draw_conflict_view(
+        &mut self,
+        frame: &mut Frame<'_>,
+        area: Rect,
+        theme: &ThemeTokens,
+    ) {
+        let Some(conflict) = self.editor.conflict() else {
+            return;
+        };
+        let selected = self.selected_conflict_hunk;
+        let hunk_count = conflict.hunks.len();
+        let rows = conflict_rows(self.editor.text(), &conflict.hunks);
+        let digits = rows
+            .iter()
+            .flat_map(|row| [row.local, row.external])
+            .flatten()
+            .map(|(idx, _)| idx + 1)
+            .max()
+            .unwrap_or(1)
+            .to_string()
+            .len();
+        let anchor = rows
+            .iter()
+            .position(|row| row.kind == RowKind::Hunk(selected));
+        let dim = theme.plain.add_modifier(Modifier::DIM);
+        let style_for = |kind: RowKind, side: Style| match kind {
+            RowKind::Context => dim,
+            RowKind::Hunk(index) if index == selected => side,
+            RowKind::Hunk(_) => theme.plain,
+        };
+        // The characters that differ within a changed line stand out.
+        let changed = |range: Range<usize>, style: Style| {
+            (
+                range,
+                style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            )
+        };
+        let cell = |side: Option<(usize, &str)>,
+                    style: Style,
+                    mark: Option<(Range<usize>, Style)>|
+         -> Line<'static> {
+            let Some((idx, text)) = side else {
+                return Line::default().style(style);
+            };
+            let mut spans = vec![Span::styled(format!("{:>digits$} ", idx + 1), style)];
+            match mark {
+                Some((range, marked)) => spans.extend([
+                    Span::styled(text[..range.start].to_string(), style),
+                    Span::styled(text[range.clone()].to_string(), marked),
+                    Span::styled(text[range.end..].to_string(), style),
+                ]),
+                None => spans.push(Span::styled(text.to_string(), style)),
+            }
+            Line::from(spans).style(style)
+        };
+        let (local_lines, external_lines): (Vec<_>, Vec<_>) = rows
+            .iter()
+            .map(|row| {
+                let local_style = style_for(row.kind, theme.conflict_local);
+                let external_style = style_for(row.kind, theme.conflict_external);
+                let spans = match (row.kind, row.local, row.external) {
+                    (RowKind::Hunk(index), Some((_, local)), Some((_, external)))
+                        if conflict.hunks[index].kind == HunkKind::Change =>
+                    {
+                        Some(changed_span(local, external))
+                    }
+                    _ => None,
+                };
+                let (local_mark, external_mark) = match spans {
+                    Some((local, external)) => (
+                        Some(changed(local, local_style)),
+                        Some(changed(external, external_style)),
+                    ),
+                    None => (None, None),
+                };
+                (
+                    cell(row.local, local_style, local_mark),
+                    cell(row.external, external_style, external_mark),
+                )
+            })
+            .unzip();
+
+        let height = area.height.saturating_sub(2).max(1) as usize;
+        self.preview_height = height;
+        if let Some(anchor) = anchor {
+            if anchor < self.preview_scroll {
+                self.preview_scroll = anchor;
+            } else if anchor >= self.preview_scroll + height {
+                self.preview_scroll = anchor.saturating_sub(height / 2);
+            }
+        }
+        self.preview_scroll = clamp_scroll(self.preview_scroll, local_lines.len(), height);
+
+        let columns = compute_conflict_columns(area);
+        let border = pane_border_style(theme, self.ui.focus == PaneFocus::Preview);
+        let local_title = format!("Local [conflict {}/{hunk_count}]", selected + 1);
+        for (lines, title, rect) in [
+            (local_lines, local_title.as_str(), columns.local),
+            (external_lines, "External", columns.external),
+        ] {
+            let visible = lines
+                .into_iter()
+                .skip(self.preview_scroll)
+                .take(height)
+                .collect::<Vec<_>>();
+            let column = Paragraph::new(visible).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .border_style(border),
+            );
+            frame.render_widget(column, rect);
+        }
+    }
+
+    /// First row of hunk `index` in the conflict layout on screen: its row
+    /// in the side-by-side view, or the header of its local block below the
+    /// rendered document.
+    pub(super) fn conflict_hunk_row(&mut self, index: usize) -> Option<usize> {
+        let conflict = self.editor.conflict()?;
+        if self.conflict_view_active() {
+            return conflict_rows(self.editor.text(), &conflict.hunks)
+                .iter()
+                .position(|row| row.kind == RowKind::Hunk(index));
+        }
+        let preview_width = self.preview_area.width.saturating_sub(2).max(1);
+        self.preview_lines_cached(preview_width)
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| {
+                matches!(
+                    line,
+                    PreviewLineKind::ConflictHeader {
+                        side: ConflictSide::Local,
+                        ..
+                    }
+                )
+            })
+            .nth(index)
+            .map(|(row, _)| row)
+    }
+
+    pub(super) fn conflict_anchor(&mut self) -> Option<ConflictAnchor> {
+        let hunks = &self.editor.conflict()?.hunks;
+        let hunk = hunks.get(self.selected_conflict_hunk)?;
+        let (count, id, local_start) = (hunks.len(), hunk.id, hunk.local_start);
+        let row = self
+            .conflict_hunk_row(self.selected_conflict_hunk)
+            .unwrap_or(self.preview_scroll);
+        Some(ConflictAnchor {
+            hunks: count,
+            id,
+            local_start,
+            offset: row.saturating_sub(self.preview_scroll),
+        })
+    }
+
+    /// Select the hunk `anchor` was on once the hunks are computed again:
+    /// the same hunk when it is still there, else the one starting nearest
+    /// to it in the local text. The view scrolls so it stays on the same
+    /// row.
+    pub(super) fn reanchor_conflict_selection(&mut self, anchor: ConflictAnchor) {
+        let Some(conflict) = self.editor.conflict() else {
+            self.sync_conflict_hunk_selection();
+            return;
+        };
+        let same = conflict.hunks.iter().position(|hunk| hunk.id == anchor.id);
+        let nearest = || {
+            (0..conflict.hunks.len()).min_by_key(|&index| {
+                conflict.hunks[index]
+                    .local_start
+                    .abs_diff(anchor.local_start)
+            })
+        };
+        let Some(index) = same.or_else(nearest) else {
+            self.sync_conflict_hunk_selection();
+            return;
+        };
+        self.selected_conflict_hunk = index;
+        self.remember_conflict_selection();
+        if let Some(row) = self.conflict_hunk_row(index) {
+            self.preview_scroll = row.saturating_sub(anchor.offset);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mdv_core::compute_conflict_hunks;
+
+    use super::{RowKind, conflict_rows};
+
+    #[test]
+    fn rows_pad_the_shorter_side_of_each_hunk() {
+        let local = "same\nlocal one\nkeep\nkeep too\ngone\n";
+        let external = "same\nexternal one\nexternal two\nkeep\nkeep too\n";
+        let hunks = compute_conflict_hunks(local, external);
+        let rows = conflict_rows(local, &hunks);
+
+        let summary: Vec<_> = rows
+            .iter()
+            .map(|row| (row.kind, row.local, row.external))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (RowKind::Context, Some((0, "same")), Some((0, "same"))),
+                (
+                    RowKind::Hunk(0),
+                    Some((1, "local one")),
+                    Some((1, "external one"))
+                ),
+                (RowKind::Hunk(0), None, Some((2, "external two"))),
+                (RowKind::Context, Some((2, "keep")), Some((3, "keep"))),
+                (
+                    RowKind::Context,
+                    Some((3, "keep too")),
+                    Some((4, "keep too"))
+                ),
+                (RowKind::Hunk(1), Some((4, "gone")), None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::SystemTime;
+
+use super::{App, clock_hms, format_bytes};
+
+/// Size, modification time and git state of the file on disk, for the info
+/// line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct FileInfo {
+    size: usize,
+    modified: Option<SystemTime>,
+    /// `git:+2-1` and the like; `None` outside a repository or when git
+    /// cannot tell.
+    git: Option<String>,
+}
+
+impl FileInfo {
+    /// Look `path` up. Runs git, so it belongs off the UI thread.
+    pub(super) fn read(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(Self {
+            size: metadata.len() as usize,
+            modified: metadata.modified().ok(),
+            git: git_marker(
+                path,
+                &std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            ),
+        })
+    }
+
+    /// `4.2 KB · 09:31 UTC · git:+2-1`.
+    pub(super) fn segment(&self) -> String {
+        let mut parts = vec![format_bytes(self.size)];
+        if let Some(modified) = self.modified {
+            parts.push(format!("{} UTC", &clock_hms(modified)[..5]));
+        }
+        parts.extend(self.git.clone());
+        parts.join(" · ")
+    }
+}
+
+/// A lookup running on a worker thread.
+pub(super) struct FileInfoJob {
+    path: PathBuf,
+    rx: Receiver<Option<FileInfo>>,
+}
+
+/// Where `path` stands in git: `git:new` when untracked, `git:+A-D` with the
+/// lines added and removed since `HEAD` when changed, `git:clean` otherwise.
+/// Ignored files, paths outside a repository and any git failure give
+/// `None`. A relative `path` is taken from `cwd`.
+fn git_marker(path: &Path, cwd: &Path) -> Option<String> {
+    // `mdv notes.md` opens a bare name, whose parent is the empty path.
+    let dir = cwd.join(path.parent().unwrap_or(Path::new("")));
+    let name = path.file_name()?;
+    let status = git(&dir, &["status", "--porcelain", "--ignored", "--"], name)?;
+    let code = status
+        .lines()
+        .next()
+        .map_or("", |line| &line[..2.min(line.len())]);
+    match code {
+        "" => Some("git:clean".into()),
+        "??" => Some("git:new".into()),
+        "!!" => None,
+        _ => {
+            let numstat = git(&dir, &["diff", "--numstat", "HEAD", "--"], name).unwrap_or_default();
+            let mut counts = numstat.split_whitespace();
+            match (counts.next(), counts.next()) {
+                (Some(added), Some(removed)) => Some(format!("git:+{added}-{removed}")),
+                _ => Some(format!("git:{}", code.trim())),
+            }
+        }
+    }
+}
+
+/// Stdout of `git <args> <name>` run in `dir`, or `None` if it failed.
+fn git(dir: &Path, args: &[&str], name: &std::ffi::OsStr) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .arg(name)
+        .current_dir(dir)
+        // A status in the background must not take the index lock from a
+        // git command the user runs meanwhile.
+        .env("GIT_OPTIONAL_LOCKS", "0")
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+impl App {
+    /// Look the file up again on a worker, after a save or an outside
+    /// change. The info line keeps the last result until the new one is in.
+    pub(super) fn refresh_file_info(&mut self) {
+        self.file_info_job = self.path.clone().map(|path| {
+            let (tx, rx) = mpsc::channel();
+            let lookup = path.clone();
+            thread::spawn(move || {
+                let _ = tx.send(FileInfo::read(&lookup));
+            });
+            FileInfoJob { path, rx }
+        });
+    }
+
+    /// Take a finished lookup, or start one for a file that has none yet.
+    pub(super) fn handle_file_info_result(&mut self) {
+        let Some(job) = &self.file_info_job else {
+            if self.path.is_some() && self.file_info_for() != self.path.as_deref() {
+                self.refresh_file_info();
+            }
+            return;
+        };
+        let info = match job.rx.try_recv() {
+            Ok(info) => info,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => None,
+        };
+        let path = job.path.clone();
+        self.file_info_job = None;
+        self.file_info = Some((path, info));
+    }
+
+    fn file_info_for(&self) -> Option<&Path> {
+        self.file_info.as_ref().map(|(path, _)| path.as_path())
+    }
+
+    /// The info line segment for the open file, once it has been looked up.
+    pub(super) fn file_info_segment(&self) -> Option<String> {
+        match &self.file_info {
+            Some((path, Some(info))) if self.path.as_ref() == Some(path) => Some(info.segment()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::process::Command;
+
+    use super::{FileInfo, git_marker};
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=mdv", "-c", "user.email=mdv@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .expect("git");
+        assert!(status.status.success(), "git {args:?}: {status:?}");
+    }
+
+    #[test]
+    fn a_modified_file_in_a_repo_shows_its_line_counts() {
+        let dir = std::env::temp_dir().join(format!("mdv-file-info-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("dir");
+        let file = dir.join("notes.md");
+        fs::write(&file, "one\ntwo\nthree\n").expect("seed");
+        git(&dir, &["init", "-q"]);
+        let outside = std::env::temp_dir();
+        assert_eq!(git_marker(&file, &outside).as_deref(), Some("git:new"));
+        git(&dir, &["add", "notes.md"]);
+        git(&dir, &["commit", "-q", "-m", "seed"]);
+        assert_eq!(git_marker(&file, &outside).as_deref(), Some("git:clean"));
+
+        fs::write(&file, "one\n2\nthree\nfour\nfive\n").expect("edit");
+        assert_eq!(git_marker(&file, &outside).as_deref(), Some("git:+3-1"));
+        let info = FileInfo::read(&file).expect("info");
+        let segment = info.segment();
+        assert!(segment.starts_with("22 B · "), "{segment}");
+        assert!(segment.contains(" UTC · "), "{segment}");
+        assert!(segment.ends_with(" · git:+3-1"), "{segment}");
+
+        // Opened by a bare name from inside the repository.
+        assert_eq!(
+            git_marker(Path::new("notes.md"), &dir).as_deref(),
+            Some("git:+3-1")
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn a_file_outside_a_repo_has_no_git_part() {
+        let dir = std::env::temp_dir().join(format!("mdv-file-info-bare-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("dir");
+        let file = dir.join("notes.md");
+        fs::write(&file, "x").expect("seed");
+        // The temp dir is not in a repository unless the machine says so.
+        if Command::new("git")
+            .args(["rev-parse", "--git-dir"])
+            .current_dir(&dir)
+            .output()
+            .is_ok_and(|output| !output.status.success())
+        {
+            assert_eq!(git_marker(&file, &dir), None);
+            assert!(
+                !FileInfo::read(&file)
+                    .expect("info")
+                    .segment()
+                    .contains("git")
+            );
+        }
+        assert_eq!(FileInfo::read(&dir.join("gone.md")), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,131 @@
+use super::App;
+use super::status::Severity;
+
+const GOTO_HELP: &str = "Goto takes LINE, LINE:COL, +N, -N or N%";
+
+/// Characters the goto prompt accepts as they are typed.
+pub(super) fn is_goto_char(c: char) -> bool {
+    c.is_ascii_digit() || matches!(c, ':' | '+' | '-' | '%')
+}
+
+/// Where a goto query points, as a one-based line and column. Relative and
+/// percentage lines stay inside the document; an absolute line past the end
+/// is an error so a typo does not silently land somewhere else.
+fn parse_goto(query: &str, current: usize, line_count: usize) -> Result<(usize, usize), String> {
+    let invalid = || format!("Invalid goto: {query} ({GOTO_HELP})");
+    let last = line_count.max(1);
+    if let Some(percent) = query.strip_suffix('%') {
+        let percent = number(percent).ok_or_else(invalid)?;
+        if percent > 100 {
+            return Err(invalid());
+        }
+        // As in vim: round up, so 1% of a long file is not line 0.
+        return Ok(((percent * last).div_ceil(100).max(1), 1));
+    }
+
+    let (line, column) = match query.split_once(':') {
+        Some((line, column)) => (
+            line,
+            number(column).filter(|&col| col > 0).ok_or_else(invalid)?,
+        ),
+        None => (query, 1),
+    };
+    let line = if let Some(offset) = line.strip_prefix('+') {
+        current
+            .saturating_add(number(offset).ok_or_else(invalid)?)
+            .min(last)
+    } else if let Some(offset) = line.strip_prefix('-') {
+        current
+            .saturating_sub(number(offset).ok_or_else(invalid)?)
+            .max(1)
+    } else {
+        let line = number(line).ok_or_else(invalid)?;
+        if line == 0 || line > last {
+            return Err(format!("Line out of range: {query}"));
+        }
+        line
+    };
+    Ok((line, column))
+}
+
+/// Plain digits only; `str::parse` would also take a leading `+`.
+fn number(text: &str) -> Option<usize> {
+    if text.is_empty() || !text.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+impl App {
+    /// `Enter` in the goto prompt: jump and say where the cursor landed.
+    pub(super) fn run_goto(&mut self, query: &str) {
+        if query.is_empty() {
+            self.push_status(Severity::Info, "Goto line empty");
+            return;
+        }
+        let current = self.editor.line_col_at_cursor().0 + 1;
+        match parse_goto(query, current, self.editor.line_count()) {
+            Ok((line, column)) if self.editor.goto_line_col(line, column) => {
+                let (line, column) = self.editor.line_col_at_cursor();
+                self.push_status(
+                    Severity::Info,
+                    format!("Line {}, Col {}", line + 1, column + 1),
+                );
+            }
+            Ok(_) => self.push_status(Severity::Warn, format!("Line out of range: {query}")),
+            Err(message) => self.push_status(Severity::Warn, message),
+        }
+    }
+
+    /// A key the goto prompt does not take explains what it does take.
+    pub(super) fn reject_goto_char(&mut self, c: char) {
+        self.push_status(
+            Severity::Warn,
+            format!(
+                "Goto: {} ('{c}' is not allowed; {GOTO_HELP})",
+                self.goto_query
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_goto_char, parse_goto};
+
+    #[test]
+    fn goto_queries_resolve_to_lines_and_columns() {
+        // Cursor on line 50 of 200.
+        let goto = |query| parse_goto(query, 50, 200);
+        assert_eq!(goto("120"), Ok((120, 1)));
+        assert_eq!(goto("120:8"), Ok((120, 8)));
+        assert_eq!(goto("+5"), Ok((55, 1)));
+        assert_eq!(goto("-10:3"), Ok((40, 3)));
+        assert_eq!(goto("+500"), Ok((200, 1)));
+        assert_eq!(goto("-500"), Ok((1, 1)));
+        assert_eq!(goto("50%"), Ok((100, 1)));
+        assert_eq!(goto("0%"), Ok((1, 1)));
+        assert_eq!(goto("100%"), Ok((200, 1)));
+        assert_eq!(parse_goto("1%", 1, 250), Ok((3, 1)));
+
+        assert_eq!(goto("201"), Err("Line out of range: 201".into()));
+        assert_eq!(goto("0"), Err("Line out of range: 0".into()));
+        for bad in [
+            "10:", ":4", "10:0", "++5", "+-1", "5+", "1:2:3", "%", "101%", "5%:2", "-",
+        ] {
+            assert_eq!(
+                goto(bad),
+                Err(format!(
+                    "Invalid goto: {bad} (Goto takes LINE, LINE:COL, +N, -N or N%)"
+                )),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn only_goto_syntax_can_be_typed() {
+        assert!("0123456789:+-%".chars().all(is_goto_char));
+        assert!(!"ag ./".chars().any(is_goto_char));
+    }
+}
@@ -0,0 +1,113 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use mdv_core::collect_images;
+
+use super::links::{LinkTarget, has_scheme, link_target};
+use super::status::Severity;
+use super::{App, PaneFocus, cursor_rect};
+use crate::graphics::{self, GraphicsProtocol, KITTY_CLEAR};
+
+/// An image drawn over the preview by `Alt+I`.
+#[derive(Debug, Default)]
+pub(super) struct ImageView {
+    /// How this terminal draws pictures; without one images stay text.
+    pub(super) protocol: Option<GraphicsProtocol>,
+    /// An image covers the preview until the next key.
+    pub(super) shown: bool,
+    /// Escape sequences to write once the frame under them is drawn.
+    pending: String,
+    /// Repaint every cell on the next frame, wiping what the image left.
+    pub(super) redraw: bool,
+}
+
+impl App {
+    /// Draw the first image on or below the top preview line over the
+    /// preview pane. Anything in the way leaves the text form and says why.
+    pub(super) fn show_image_near(&mut self) {
+        if self.ui.focus == PaneFocus::Editor && !self.ui.view_only {
+            self.push_status(
+                Severity::Info,
+                "Images show from the preview: Shift+Tab to focus it",
+            );
+            return;
+        }
+        let from = self.preview_top_source_line();
+        let Some(image) = collect_images(self.editor.text())
+            .into_iter()
+            .find(|image| image.source_line >= from)
+        else {
+            self.push_status(Severity::Warn, format!("No images from line {}", from + 1));
+            return;
+        };
+        let base = self.path.as_deref().and_then(Path::parent);
+        let path = match link_target(&image.dest, base) {
+            LinkTarget::External(path) if !has_scheme(&image.dest) => PathBuf::from(path),
+            _ => {
+                self.push_status(
+                    Severity::Error,
+                    format!("Image error: {} is not a local file", image.dest),
+                );
+                return;
+            }
+        };
+        let Some(protocol) = self.image.protocol else {
+            self.push_status(
+                Severity::Error,
+                format!(
+                    "Image: {} (this terminal cannot draw images; set MDV_GRAPHICS if it can)",
+                    image.text
+                ),
+            );
+            return;
+        };
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                self.push_status(
+                    Severity::Error,
+                    format!("Image error: {}: {err}", path.display()),
+                );
+                return;
+            }
+        };
+
+        let area = cursor_rect(self.preview_area);
+        match graphics::image_sequence(protocol, &bytes, area.width.max(1), area.height.max(1)) {
+            Ok(sequence) => {
+                // Save the cursor, draw at the pane's top left, restore it.
+                self.image.pending =
+                    format!("\x1b7\x1b[{};{}H{sequence}\x1b8", area.y + 1, area.x + 1);
+                self.image.shown = true;
+                self.push_status(
+                    Severity::Info,
+                    format!("Image: {} (any key closes)", image.text),
+                );
+            }
+            Err(err) => self.push_status(Severity::Error, format!("Image error: {err}")),
+        }
+    }
+
+    pub(super) fn close_image(&mut self) {
+        if !self.image.shown {
+            return;
+        }
+        self.image.shown = false;
+        self.image.pending.clear();
+        if self.image.protocol == Some(GraphicsProtocol::Kitty) {
+            self.image.pending.push_str(KITTY_CLEAR);
+        }
+        self.image.redraw = true;
+        self.push_status(Severity::Info, "Image closed");
+    }
+
+    /// Write what the last key asked to draw or remove.
+    pub(super) fn flush_image(&mut self, out: &mut impl Write) -> io::Result<()> {
+        if self.image.pending.is_empty() {
+            return Ok(());
+        }
+        out.write_all(std::mem::take(&mut self.image.pending).as_bytes())?;
+        out.flush()
+    }
+}
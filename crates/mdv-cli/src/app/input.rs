@@ -1,69 +1,213 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventState, KeyModifiers};
 
 use super::action::Action;
+use super::keymap::KeyMap;
+use super::state::SettingsNav;
 
-pub fn map_global_key(key: KeyEvent) -> Option<Action> {
+/// Bring a key from a terminal with the kitty keyboard protocol into the
+/// shape legacy terminals send, so one set of bindings serves both: a letter
+/// typed with Shift (or Caps Lock) is uppercase and carries `SHIFT`.
+///
+/// With enhanced reporting `Ctrl+Shift+S` may arrive as `Ctrl+S` with the
+/// shifted `S`, or as `Ctrl+Shift+s`; both become `Ctrl+Shift+S`. Legacy
+/// terminals cannot send it at all, which is why every such binding has a
+/// fallback.
+pub fn normalize_key(mut key: KeyEvent) -> KeyEvent {
+    let KeyCode::Char(c) = key.code else {
+        return key;
+    };
+    let caps = key.state.contains(KeyEventState::CAPS_LOCK) && key.modifiers.is_empty();
+    if c.is_ascii_lowercase() && (caps || key.modifiers.contains(KeyModifiers::SHIFT)) {
+        key.code = KeyCode::Char(c.to_ascii_uppercase());
+        key.modifiers.insert(KeyModifiers::SHIFT);
+    } else if c.is_ascii_uppercase() {
+        key.modifiers.insert(KeyModifiers::SHIFT);
+    }
+    key
+}
+
+/// Layout keys, which work in every mode.
+pub fn map_global_key(keymap: &KeyMap, key: KeyEvent) -> Option<Action> {
+    keymap.command(key).and_then(|command| command.action())
+}
+
+/// Keys for the settings tab of the Docs + Settings overlay.
+pub fn map_settings_key(key: KeyEvent) -> Option<SettingsNav> {
     match (key.code, key.modifiers) {
-        (KeyCode::BackTab, KeyModifiers::NONE) => Some(Action::ToggleFocus),
-        (KeyCode::BackTab, KeyModifiers::SHIFT) => Some(Action::ToggleFocus),
-        (KeyCode::Char('t'), KeyModifiers::CONTROL) => Some(Action::ToggleFocus),
-        (KeyCode::Char(','), mods)
-            if mods.contains(KeyModifiers::CONTROL) || mods.contains(KeyModifiers::SUPER) =>
-        {
-            Some(Action::ToggleHelp)
+        (KeyCode::Up, _) | (KeyCode::Char('k'), KeyModifiers::NONE) => Some(SettingsNav::Up),
+        (KeyCode::Down, _) | (KeyCode::Char('j'), KeyModifiers::NONE) => Some(SettingsNav::Down),
+        (KeyCode::Enter, _) | (KeyCode::Right, _) | (KeyCode::Char(' '), KeyModifiers::NONE) => {
+            Some(SettingsNav::Next)
         }
+        (KeyCode::Left, _) => Some(SettingsNav::Prev),
         _ => None,
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use crossterm::event::{KeyCode, KeyEvent, KeyEventState, KeyModifiers};
 
     use crate::app::action::Action;
+    use crate::app::keymap::KeyMap;
+    use crate::app::state::SettingsNav;
 
-    use super::map_global_key;
+    use super::{map_global_key, map_settings_key, normalize_key};
+
+    fn global(key: KeyEvent) -> Option<Action> {
+        map_global_key(&KeyMap::default(), key)
+    }
 
     fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
         KeyEvent::new(code, modifiers)
     }
 
+    /// `KeyEvent`'s own `PartialEq` already folds case, so compare the parts.
+    fn parts(key: KeyEvent) -> (KeyCode, KeyModifiers) {
+        let key = normalize_key(key);
+        (key.code, key.modifiers)
+    }
+
+    #[test]
+    fn enhanced_and_legacy_keys_normalize_alike() {
+        let ctrl = KeyModifiers::CONTROL;
+        let ctrl_shift = KeyModifiers::CONTROL | KeyModifiers::SHIFT;
+        // Ctrl+Shift+S: shifted key reported, or the base key with Shift.
+        let save_as = (KeyCode::Char('S'), ctrl_shift);
+        assert_eq!(parts(key(KeyCode::Char('S'), ctrl)), save_as);
+        assert_eq!(parts(key(KeyCode::Char('s'), ctrl_shift)), save_as);
+        assert_eq!(parts(key(KeyCode::Char('S'), ctrl_shift)), save_as);
+        assert_eq!(
+            parts(key(KeyCode::Char('s'), ctrl)),
+            (KeyCode::Char('s'), ctrl)
+        );
+
+        // Plain typing: legacy `A` carries Shift, enhanced may not.
+        let upper = (KeyCode::Char('A'), KeyModifiers::SHIFT);
+        assert_eq!(parts(key(KeyCode::Char('A'), KeyModifiers::SHIFT)), upper);
+        assert_eq!(parts(key(KeyCode::Char('A'), KeyModifiers::NONE)), upper);
+        assert_eq!(parts(key(KeyCode::Char('a'), KeyModifiers::SHIFT)), upper);
+        let mut caps = key(KeyCode::Char('a'), KeyModifiers::NONE);
+        caps.state = KeyEventState::CAPS_LOCK;
+        assert_eq!(parts(caps), upper);
+        let mut caps_ctrl = key(KeyCode::Char('s'), ctrl);
+        caps_ctrl.state = KeyEventState::CAPS_LOCK;
+        assert_eq!(parts(caps_ctrl), (KeyCode::Char('s'), ctrl));
+
+        assert_eq!(
+            parts(key(KeyCode::Char('!'), KeyModifiers::NONE)),
+            (KeyCode::Char('!'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            parts(key(KeyCode::Enter, KeyModifiers::SHIFT)),
+            (KeyCode::Enter, KeyModifiers::SHIFT)
+        );
+    }
+
+    #[test]
+    fn enhanced_and_legacy_keys_map_to_the_same_actions() {
+        let pairs = [
+            // Legacy Shift+Tab, enhanced Shift+Tab.
+            (
+                key(KeyCode::BackTab, KeyModifiers::NONE),
+                key(KeyCode::BackTab, KeyModifiers::SHIFT),
+            ),
+            (
+                key(KeyCode::Char('o'), KeyModifiers::CONTROL),
+                key(KeyCode::Char('o'), KeyModifiers::CONTROL),
+            ),
+            (
+                key(KeyCode::Char(','), KeyModifiers::CONTROL),
+                key(
+                    KeyCode::Char(','),
+                    KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+                ),
+            ),
+        ];
+        for (legacy, enhanced) in pairs {
+            let action = global(normalize_key(legacy));
+            assert!(action.is_some(), "{legacy:?}");
+            assert_eq!(global(normalize_key(enhanced)), action);
+        }
+    }
+
     #[test]
     fn maps_new_ui_shortcuts() {
         assert_eq!(
-            map_global_key(key(KeyCode::BackTab, KeyModifiers::NONE)),
+            global(key(KeyCode::BackTab, KeyModifiers::NONE)),
             Some(Action::ToggleFocus)
         );
         assert_eq!(
-            map_global_key(key(KeyCode::BackTab, KeyModifiers::SHIFT)),
+            global(key(KeyCode::BackTab, KeyModifiers::SHIFT)),
             Some(Action::ToggleFocus)
         );
+        assert_eq!(global(key(KeyCode::Char('t'), KeyModifiers::CONTROL)), None);
         assert_eq!(
-            map_global_key(key(KeyCode::Char('t'), KeyModifiers::CONTROL)),
-            Some(Action::ToggleFocus)
+            global(key(KeyCode::Char('o'), KeyModifiers::CONTROL)),
+            Some(Action::ToggleViewOnly)
+        );
+        assert_eq!(
+            global(key(KeyCode::Char('z'), KeyModifiers::ALT)),
+            Some(Action::ToggleMaximize)
+        );
+        assert_eq!(
+            global(key(KeyCode::F(11), KeyModifiers::NONE)),
+            Some(Action::ToggleMaximize)
+        );
+        assert_eq!(
+            global(key(KeyCode::Char(']'), KeyModifiers::CONTROL)),
+            Some(Action::GrowPane)
+        );
+        assert_eq!(
+            global(key(KeyCode::Char('['), KeyModifiers::CONTROL)),
+            Some(Action::ShrinkPane)
+        );
+        assert_eq!(
+            global(key(KeyCode::Char('='), KeyModifiers::ALT)),
+            Some(Action::GrowPane)
         );
         assert_eq!(
-            map_global_key(key(KeyCode::Char(','), KeyModifiers::CONTROL)),
+            global(key(KeyCode::Char('-'), KeyModifiers::ALT)),
+            Some(Action::ShrinkPane)
+        );
+        assert_eq!(
+            global(key(KeyCode::Char(','), KeyModifiers::CONTROL)),
             Some(Action::ToggleHelp)
         );
         assert_eq!(
-            map_global_key(key(KeyCode::Char(','), KeyModifiers::SUPER)),
+            global(key(KeyCode::Char(','), KeyModifiers::SUPER)),
             Some(Action::ToggleHelp)
         );
+        assert_eq!(global(key(KeyCode::Char('/'), KeyModifiers::CONTROL)), None);
+        assert_eq!(global(key(KeyCode::Char(','), KeyModifiers::ALT)), None);
+        assert_eq!(global(key(KeyCode::Char('.'), KeyModifiers::ALT)), None);
+        assert_eq!(global(key(KeyCode::Char('w'), KeyModifiers::CONTROL)), None);
+    }
+
+    #[test]
+    fn maps_settings_keys() {
         assert_eq!(
-            map_global_key(key(KeyCode::Char('/'), KeyModifiers::CONTROL)),
-            None
+            map_settings_key(key(KeyCode::Up, KeyModifiers::NONE)),
+            Some(SettingsNav::Up)
         );
         assert_eq!(
-            map_global_key(key(KeyCode::Char(','), KeyModifiers::ALT)),
-            None
+            map_settings_key(key(KeyCode::Char('j'), KeyModifiers::NONE)),
+            Some(SettingsNav::Down)
         );
         assert_eq!(
-            map_global_key(key(KeyCode::Char('.'), KeyModifiers::ALT)),
-            None
+            map_settings_key(key(KeyCode::Enter, KeyModifiers::NONE)),
+            Some(SettingsNav::Next)
+        );
+        assert_eq!(
+            map_settings_key(key(KeyCode::Right, KeyModifiers::NONE)),
+            Some(SettingsNav::Next)
+        );
+        assert_eq!(
+            map_settings_key(key(KeyCode::Left, KeyModifiers::NONE)),
+            Some(SettingsNav::Prev)
         );
         assert_eq!(
-            map_global_key(key(KeyCode::Char('w'), KeyModifiers::CONTROL)),
+            map_settings_key(key(KeyCode::Char('x'), KeyModifiers::NONE)),
             None
         );
     }
@@ -0,0 +1,614 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::action::Action;
+use super::input::normalize_key;
+
+/// What a key chord can be bound to in the `[keys]` config table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Command {
+    Quit,
+    Save,
+    SaveAs,
+    Reload,
+    Search,
+    Replace,
+    Goto,
+    SearchNext,
+    SearchPrev,
+    Undo,
+    Redo,
+    Outline,
+    NextHeading,
+    PrevHeading,
+    OpenLink,
+    OpenChangedLink,
+    GotoDefinition,
+    JumpBack,
+    ShowImage,
+    NextMisspelling,
+    Follow,
+    ToggleFocus,
+    SyncPanes,
+    ToggleViewOnly,
+    ToggleMaximize,
+    GrowPane,
+    ShrinkPane,
+    ToggleHelp,
+    MessageLog,
+    Format,
+    RenumberList,
+    Snippets,
+    Review,
+    ConflictView,
+    ConflictNextHunk,
+    ConflictPrevHunk,
+    ConflictApplyHunk,
+    Merge,
+    ExportMerged,
+    NextFile,
+    PrevFile,
+    CloseFile,
+    FileSwitcher,
+}
+
+impl Command {
+    /// In the order the settings tab lists them.
+    pub const ALL: [Command; 43] = [
+        Command::Quit,
+        Command::Save,
+        Command::SaveAs,
+        Command::Reload,
+        Command::Search,
+        Command::Replace,
+        Command::Goto,
+        Command::SearchNext,
+        Command::SearchPrev,
+        Command::Undo,
+        Command::Redo,
+        Command::Outline,
+        Command::NextHeading,
+        Command::PrevHeading,
+        Command::OpenLink,
+        Command::OpenChangedLink,
+        Command::GotoDefinition,
+        Command::JumpBack,
+        Command::ShowImage,
+        Command::NextMisspelling,
+        Command::Follow,
+        Command::ToggleFocus,
+        Command::SyncPanes,
+        Command::ToggleViewOnly,
+        Command::ToggleMaximize,
+        Command::GrowPane,
+        Command::ShrinkPane,
+        Command::ToggleHelp,
+        Command::MessageLog,
+        Command::Format,
+        Command::RenumberList,
+        Command::Snippets,
+        Command::Review,
+        Command::ConflictView,
+        Command::ConflictNextHunk,
+        Command::ConflictPrevHunk,
+        Command::ConflictApplyHunk,
+        Command::Merge,
+        Command::ExportMerged,
+        Command::NextFile,
+        Command::PrevFile,
+        Command::CloseFile,
+        Command::FileSwitcher,
+    ];
+
+    /// The name a `[keys]` entry uses, such as `save = "ctrl+s"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Command::Quit => "quit",
+            Command::Save => "save",
+            Command::SaveAs => "save_as",
+            Command::Reload => "reload",
+            Command::Search => "search",
+            Command::Replace => "replace",
+            Command::Goto => "goto",
+            Command::SearchNext => "search_next",
+            Command::SearchPrev => "search_prev",
+            Command::Undo => "undo",
+            Command::Redo => "redo",
+            Command::Outline => "outline",
+            Command::NextHeading => "next_heading",
+            Command::PrevHeading => "prev_heading",
+            Command::OpenLink => "open_link",
+            Command::OpenChangedLink => "open_changed_link",
+            Command::GotoDefinition => "goto_definition",
+            Command::JumpBack => "jump_back",
+            Command::ShowImage => "show_image",
+            Command::NextMisspelling => "next_misspelling",
+            Command::Follow => "follow",
+            Command::ToggleFocus => "toggle_focus",
+            Command::SyncPanes => "sync_panes",
+            Command::ToggleViewOnly => "toggle_view_only",
+            Command::ToggleMaximize => "toggle_maximize",
+            Command::GrowPane => "grow_pane",
+            Command::ShrinkPane => "shrink_pane",
+            Command::ToggleHelp => "toggle_help",
+            Command::MessageLog => "message_log",
+            Command::Format => "format",
+            Command::RenumberList => "renumber_list",
+            Command::Snippets => "snippets",
+            Command::Review => "review",
+            Command::ConflictView => "conflict_view",
+            Command::ConflictNextHunk => "conflict_next_hunk",
+            Command::ConflictPrevHunk => "conflict_prev_hunk",
+            Command::ConflictApplyHunk => "conflict_apply_hunk",
+            Command::Merge => "merge",
+            Command::ExportMerged => "export_merged",
+            Command::NextFile => "next_file",
+            Command::PrevFile => "prev_file",
+            Command::CloseFile => "close_file",
+            Command::FileSwitcher => "file_switcher",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|command| command.name() == name)
+    }
+
+    /// The bindings mdv ships with.
+    fn default_chords(self) -> &'static [&'static str] {
+        match self {
+            Command::Quit => &["ctrl+q"],
+            Command::Save => &["ctrl+s"],
+            Command::SaveAs => &["ctrl+shift+s"],
+            Command::Reload => &["ctrl+r"],
+            Command::Search => &["ctrl+f"],
+            Command::Replace => &["ctrl+h"],
+            Command::Goto => &["ctrl+g"],
+            Command::SearchNext => &["f3", "ctrl+n"],
+            Command::SearchPrev => &["shift+f3", "ctrl+p"],
+            Command::Undo => &["ctrl+z"],
+            Command::Redo => &["ctrl+y", "super+z"],
+            Command::Outline => &["ctrl+t"],
+            Command::NextHeading => &["alt+n"],
+            Command::PrevHeading => &["alt+p"],
+            Command::OpenLink => &["alt+o"],
+            Command::OpenChangedLink => &["f5"],
+            Command::GotoDefinition => &["f12"],
+            Command::JumpBack => &["shift+f12"],
+            Command::ShowImage => &["alt+i"],
+            Command::NextMisspelling => &["f7"],
+            Command::Follow => &["ctrl+end"],
+            Command::ToggleFocus => &["shift+tab"],
+            Command::SyncPanes => &["alt+l"],
+            Command::ToggleViewOnly => &["ctrl+o"],
+            Command::ToggleMaximize => &["alt+z", "f11"],
+            // Legacy terminals send Ctrl+] as Ctrl+5 and Ctrl+[ as Esc.
+            Command::GrowPane => &["ctrl+]", "alt+="],
+            Command::ShrinkPane => &["ctrl+[", "alt+-"],
+            Command::ToggleHelp => &["ctrl+,", "ctrl+shift+,", "super+,"],
+            Command::MessageLog => &["alt+m"],
+            Command::Format => &["ctrl+alt+f"],
+            Command::RenumberList => &["ctrl+alt+l"],
+            // Most legacy terminals send Ctrl+. as a plain `.`.
+            Command::Snippets => &["ctrl+.", "alt+s"],
+            Command::Review => &["ctrl+shift+d"],
+            Command::ConflictView => &["ctrl+l"],
+            Command::ConflictNextHunk => &["ctrl+j"],
+            Command::ConflictPrevHunk => &["ctrl+u"],
+            Command::ConflictApplyHunk => &["ctrl+e"],
+            Command::Merge => &["ctrl+m"],
+            Command::ExportMerged => &["ctrl+shift+m"],
+            Command::NextFile => &["ctrl+pagedown"],
+            Command::PrevFile => &["ctrl+pageup"],
+            Command::CloseFile => &["ctrl+w"],
+            Command::FileSwitcher => &["ctrl+b"],
+        }
+    }
+
+    /// Layout commands work everywhere, even with Docs open or in a prompt.
+    pub fn action(self) -> Option<Action> {
+        match self {
+            Command::ToggleFocus => Some(Action::ToggleFocus),
+            Command::ToggleHelp => Some(Action::ToggleHelp),
+            Command::ToggleViewOnly => Some(Action::ToggleViewOnly),
+            Command::ToggleMaximize => Some(Action::ToggleMaximize),
+            Command::GrowPane => Some(Action::GrowPane),
+            Command::ShrinkPane => Some(Action::ShrinkPane),
+            _ => None,
+        }
+    }
+
+    /// Commands that still mean something in view-only mode.
+    pub fn reads_only(self) -> bool {
+        matches!(
+            self,
+            Command::Quit
+                | Command::Search
+                | Command::SearchNext
+                | Command::SearchPrev
+                | Command::Outline
+                | Command::NextHeading
+                | Command::PrevHeading
+                | Command::OpenLink
+                | Command::OpenChangedLink
+                | Command::ShowImage
+                | Command::Follow
+                | Command::SyncPanes
+                | Command::MessageLog
+                | Command::ConflictView
+        )
+    }
+}
+
+/// One key plus modifiers, in the shape [`normalize_key`] leaves keys in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Chord {
+    pub fn of(key: KeyEvent) -> Self {
+        let key = normalize_key(key);
+        let mut modifiers = key.modifiers;
+        // Legacy terminals send Shift+Tab without Shift.
+        if key.code == KeyCode::BackTab {
+            modifiers.insert(KeyModifiers::SHIFT);
+        }
+        Self {
+            code: key.code,
+            modifiers,
+        }
+    }
+
+    /// Parse `ctrl+s`, `shift+f3`, `alt+x` or `ctrl+shift+pagedown`. Case
+    /// does not matter; a letter is only uppercase with `shift`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let lower = text.trim().to_ascii_lowercase();
+        let mut rest = lower.as_str();
+        let mut modifiers = KeyModifiers::NONE;
+        // `ctrl++` binds the plus key itself.
+        while let Some((word, tail)) = rest.split_once('+').filter(|(_, tail)| !tail.is_empty()) {
+            modifiers |= match word {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "option" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                "super" | "cmd" | "command" => KeyModifiers::SUPER,
+                _ => return Err(format!("unknown modifier `{word}` in `{text}`")),
+            };
+            rest = tail;
+        }
+        let shift = modifiers.contains(KeyModifiers::SHIFT);
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if shift && c.is_ascii_lowercase() => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest {
+                "tab" if shift => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                _ => match rest.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=24) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key `{rest}` in `{text}`")),
+                },
+            },
+        };
+        let command_modifier = KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SUPER;
+        if !modifiers.intersects(command_modifier)
+            && !matches!(code, KeyCode::F(_) | KeyCode::BackTab)
+        {
+            return Err(format!(
+                "`{text}` needs ctrl, alt or super; plain keys are for typing"
+            ));
+        }
+        Ok(Self { code, modifiers })
+    }
+}
+
+/// The way the docs write keys: `Ctrl+Shift+S`, `F3`, `Shift+Tab`.
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl"),
+            (KeyModifiers::ALT, "Alt"),
+            (KeyModifiers::SHIFT, "Shift"),
+            (KeyModifiers::SUPER, "Super"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            KeyCode::Tab | KeyCode::BackTab => f.write_str("Tab"),
+            KeyCode::PageUp => f.write_str("PgUp"),
+            KeyCode::PageDown => f.write_str("PgDn"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+/// Chords the editor handles itself, after the keymap: binding one to a
+/// command would shadow what it does.
+const RESERVED: [(&str, &str); 12] = [
+    ("ctrl+k", "keep local / delete to line end"),
+    ("ctrl+d", "delete line"),
+    ("alt+d", "duplicate line"),
+    ("ctrl+space", "toggle task"),
+    ("ctrl+c", "copy"),
+    ("ctrl+shift+c", "copy"),
+    ("ctrl+x", "cut"),
+    ("ctrl+v", "paste"),
+    ("ctrl+a", "select all"),
+    ("super+a", "select all"),
+    ("alt+a", "add to dictionary"),
+    ("alt+r", "restore before reload"),
+];
+
+/// Which command each chord runs: the defaults, with `[keys]` entries
+/// replacing the chords of the commands they name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMap {
+    chords: HashMap<Command, Vec<Chord>>,
+    commands: HashMap<Chord, Command>,
+    /// Commands a `[keys]` entry already rebound.
+    custom: HashSet<Command>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let mut keymap = Self {
+            chords: HashMap::new(),
+            commands: HashMap::new(),
+            custom: HashSet::new(),
+        };
+        for command in Command::ALL {
+            for text in command.default_chords() {
+                let chord = Chord::parse(text).expect("default chords parse");
+                keymap.chords.entry(command).or_default().push(chord);
+                keymap.commands.insert(chord, command);
+            }
+        }
+        keymap
+    }
+}
+
+impl KeyMap {
+    pub fn command(&self, key: KeyEvent) -> Option<Command> {
+        self.commands.get(&Chord::of(key)).copied()
+    }
+
+    pub fn chords(&self, command: Command) -> &[Chord] {
+        self.chords.get(&command).map_or(&[], Vec::as_slice)
+    }
+
+    /// Bind `command` to `chords` in place of its defaults; an empty list
+    /// unbinds it. A chord another `[keys]` entry took, or one in
+    /// [`RESERVED`], is refused, while one only bound by default moves over
+    /// to `command`.
+    pub fn bind(&mut self, command: Command, chords: &[Chord]) -> Result<(), String> {
+        for chord in chords {
+            if let Some((_, what)) = RESERVED
+                .iter()
+                .find(|(text, _)| Chord::parse(text).ok() == Some(*chord))
+            {
+                return Err(format!("{chord} is reserved for {what}"));
+            }
+            match self.commands.get(chord) {
+                Some(&other) if other != command && self.custom.contains(&other) => {
+                    return Err(format!("{chord} is already bound to {}", other.name()));
+                }
+                _ => {}
+            }
+        }
+        for old in self.chords.remove(&command).unwrap_or_default() {
+            self.commands.remove(&old);
+        }
+        let mut bound = Vec::new();
+        for &chord in chords {
+            if let Some(other) = self.commands.insert(chord, command)
+                && let Some(taken) = self.chords.get_mut(&other)
+            {
+                taken.retain(|&old| old != chord);
+            }
+            if !bound.contains(&chord) {
+                bound.push(chord);
+            }
+        }
+        self.chords.insert(command, bound);
+        self.custom.insert(command);
+        Ok(())
+    }
+
+    /// Every command with the chords that run it, as the settings tab
+    /// lists them.
+    pub fn describe(&self) -> Vec<(&'static str, String)> {
+        Command::ALL
+            .into_iter()
+            .map(|command| {
+                let chords = self.chords(command);
+                let keys = if chords.is_empty() {
+                    "(unbound)".to_string()
+                } else {
+                    chords
+                        .iter()
+                        .map(Chord::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" / ")
+                };
+                (command.name(), keys)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::{Chord, Command, KeyMap};
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> Chord {
+        Chord { code, modifiers }
+    }
+
+    #[test]
+    fn chords_parse_from_config_strings() {
+        let ctrl = KeyModifiers::CONTROL;
+        assert_eq!(Chord::parse("ctrl+s"), Ok(chord(KeyCode::Char('s'), ctrl)));
+        assert_eq!(
+            Chord::parse(" Ctrl+S "),
+            Ok(chord(KeyCode::Char('s'), ctrl))
+        );
+        assert_eq!(
+            Chord::parse("ctrl+shift+s"),
+            Ok(chord(KeyCode::Char('S'), ctrl | KeyModifiers::SHIFT))
+        );
+        assert_eq!(
+            Chord::parse("f3"),
+            Ok(chord(KeyCode::F(3), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            Chord::parse("shift+f3"),
+            Ok(chord(KeyCode::F(3), KeyModifiers::SHIFT))
+        );
+        assert_eq!(
+            Chord::parse("alt+x"),
+            Ok(chord(KeyCode::Char('x'), KeyModifiers::ALT))
+        );
+        assert_eq!(Chord::parse("ctrl++"), Ok(chord(KeyCode::Char('+'), ctrl)));
+        assert_eq!(
+            Chord::parse("cmd+pagedown"),
+            Ok(chord(KeyCode::PageDown, KeyModifiers::SUPER))
+        );
+        assert_eq!(
+            Chord::parse("shift+tab"),
+            Ok(chord(KeyCode::BackTab, KeyModifiers::SHIFT))
+        );
+
+        assert_eq!(
+            Chord::parse("hyper+s"),
+            Err("unknown modifier `hyper` in `hyper+s`".into())
+        );
+        assert_eq!(
+            Chord::parse("ctrl+f25"),
+            Err("unknown key `f25` in `ctrl+f25`".into())
+        );
+        assert_eq!(
+            Chord::parse("shift+x"),
+            Err("`shift+x` needs ctrl, alt or super; plain keys are for typing".into())
+        );
+        assert!(Chord::parse("enter").is_err());
+        assert!(Chord::parse("").is_err());
+
+        for text in [
+            "Ctrl+Shift+S",
+            "Ctrl+Alt+F",
+            "Shift+F3",
+            "Shift+Tab",
+            "Ctrl+PgDn",
+        ] {
+            let parsed = Chord::parse(text).expect(text);
+            assert_eq!(parsed.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn keys_from_legacy_and_enhanced_terminals_find_the_same_command() {
+        let keymap = KeyMap::default();
+        let lookup = |code, modifiers| keymap.command(KeyEvent::new(code, modifiers));
+        let ctrl_shift = KeyModifiers::CONTROL | KeyModifiers::SHIFT;
+        assert_eq!(
+            lookup(KeyCode::Char('s'), KeyModifiers::CONTROL),
+            Some(Command::Save)
+        );
+        assert_eq!(
+            lookup(KeyCode::Char('s'), ctrl_shift),
+            Some(Command::SaveAs)
+        );
+        assert_eq!(
+            lookup(KeyCode::Char('S'), ctrl_shift),
+            Some(Command::SaveAs)
+        );
+        assert_eq!(
+            lookup(KeyCode::BackTab, KeyModifiers::NONE),
+            Some(Command::ToggleFocus)
+        );
+        assert_eq!(
+            lookup(KeyCode::BackTab, KeyModifiers::SHIFT),
+            Some(Command::ToggleFocus)
+        );
+        assert_eq!(lookup(KeyCode::Char('s'), KeyModifiers::NONE), None);
+    }
+
+    #[test]
+    fn rebinding_replaces_defaults_and_refuses_duplicates() {
+        let mut keymap = KeyMap::default();
+        let f2 = Chord::parse("f2").expect("f2");
+        let ctrl_f = Chord::parse("ctrl+f").expect("ctrl+f");
+        keymap.bind(Command::Save, &[f2]).expect("save");
+        assert_eq!(keymap.chords(Command::Save), [f2]);
+        assert_eq!(
+            keymap.command(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL)),
+            None
+        );
+
+        // A default chord moves to the command that asks for it...
+        keymap.bind(Command::Goto, &[ctrl_f]).expect("goto");
+        assert_eq!(keymap.chords(Command::Search), []);
+        assert_eq!(
+            keymap.command(KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL)),
+            Some(Command::Goto)
+        );
+
+        // ...but two entries cannot share one.
+        assert_eq!(
+            keymap.bind(Command::Replace, &[ctrl_f]),
+            Err("Ctrl+F is already bound to goto".into())
+        );
+        assert_eq!(
+            keymap.chords(Command::Replace),
+            [Chord::parse("ctrl+h").expect("h")]
+        );
+
+        // Chords the editor handles itself cannot be taken either.
+        let ctrl_k = Chord::parse("ctrl+k").expect("ctrl+k");
+        assert_eq!(
+            keymap.bind(Command::Save, &[ctrl_k]),
+            Err("Ctrl+K is reserved for keep local / delete to line end".into())
+        );
+        assert_eq!(keymap.chords(Command::Save), [f2]);
+
+        let described = keymap.describe();
+        assert_eq!(described[1], ("save", "F2".to_string()));
+        assert!(described.contains(&("search", "(unbound)".to_string())));
+        assert!(described.contains(&("search_next", "F3 / Ctrl+N".to_string())));
+    }
+
+    #[test]
+    fn commands_round_trip_through_their_names() {
+        for command in Command::ALL {
+            assert_eq!(Command::from_name(command.name()), Some(command));
+            assert!(!command.default_chords().is_empty());
+        }
+        assert_eq!(Command::from_name("teleport"), None);
+    }
+}
@@ -0,0 +1,161 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+
+use mdv_core::collect_links;
+use mdv_watch::{WatchMessage, WatchOptions, WatcherHandle, watch_file};
+
+use super::App;
+use super::keymap::Command;
+use super::links::{LinkTarget, link_target};
+use super::status::Severity;
+
+/// Most linked files `--watch-links` watches; each one is a watcher of its
+/// own.
+pub(super) const MAX_LINK_WATCHES: usize = 20;
+
+/// A Markdown file the document links to, watched for `--watch-links`.
+pub(super) struct LinkWatch {
+    pub(super) path: PathBuf,
+    /// How the status names it: relative to the document when it can be.
+    name: String,
+    _watcher: WatcherHandle,
+    rx: Receiver<WatchMessage>,
+}
+
+/// The local Markdown files `text` links to that exist, once each and in
+/// document order, up to [`MAX_LINK_WATCHES`]. `own` is left out so a
+/// table of contents linking back to itself does not watch itself.
+pub(super) fn linked_markdown(text: &str, base: Option<&Path>, own: Option<&Path>) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for link in collect_links(text) {
+        if paths.len() == MAX_LINK_WATCHES {
+            break;
+        }
+        if let LinkTarget::Markdown(path) = link_target(&link.dest, base)
+            && Some(path.as_path()) != own
+            && path.is_file()
+            && !paths.contains(&path)
+        {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+impl App {
+    /// `--watch-links`: also watch the Markdown files the document links to
+    /// and say when one of them changes.
+    pub fn set_watch_links(&mut self, on: bool) {
+        self.watch_links = on;
+        self.sync_link_watches();
+    }
+
+    /// Watch the files the text links to now. Called whenever the text is
+    /// loaded, saved or reloaded; the watchers are only rebuilt when the set
+    /// of linked files changed.
+    pub(super) fn sync_link_watches(&mut self) {
+        if !self.watch_links || !self.watch_enabled || self.stream_mode {
+            self.link_watches.clear();
+            return;
+        }
+        let base = self
+            .path
+            .as_deref()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf);
+        let paths = linked_markdown(self.editor.text(), base.as_deref(), self.path.as_deref());
+        if paths
+            .iter()
+            .eq(self.link_watches.iter().map(|watch| &watch.path))
+        {
+            return;
+        }
+        self.link_watches.clear();
+        for path in paths {
+            let name = base
+                .as_deref()
+                .and_then(|base| path.strip_prefix(base).ok())
+                .unwrap_or(&path)
+                .display()
+                .to_string();
+            match watch_file(&path, WatchOptions::default()) {
+                Ok((watcher, rx)) => self.link_watches.push(LinkWatch {
+                    path,
+                    name,
+                    _watcher: watcher,
+                    rx,
+                }),
+                Err(err) => {
+                    self.push_status(Severity::Error, format!("watch error: {name}: {err}"));
+                }
+            }
+        }
+    }
+
+    /// Report the last linked file that changed. Only the status changes;
+    /// the buffer is left alone.
+    pub(super) fn handle_link_watch_updates(&mut self) {
+        let mut changed = None;
+        for (index, watch) in self.link_watches.iter().enumerate() {
+            while let Ok(message) = watch.rx.try_recv() {
+                if matches!(message, WatchMessage::ExternalUpdate(_)) {
+                    changed = Some(index);
+                }
+            }
+        }
+        let Some(watch) = changed.map(|index| &self.link_watches[index]) else {
+            return;
+        };
+        let text = match self.keymap.chords(Command::OpenChangedLink).first() {
+            Some(chord) => format!(
+                "linked file changed: {} (press {chord} to open)",
+                watch.name
+            ),
+            None => format!("linked file changed: {}", watch.name),
+        };
+        self.changed_link = Some(watch.path.clone());
+        self.push_status(Severity::Info, text);
+    }
+
+    /// `F5`: open the linked file that last changed.
+    pub(super) fn open_changed_link(&mut self) {
+        match self.changed_link.clone() {
+            Some(path) => self.open_markdown(path),
+            None => self.push_status(Severity::Warn, "No linked file has changed"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::{MAX_LINK_WATCHES, linked_markdown};
+
+    #[test]
+    fn only_existing_local_markdown_links_count_once_each() {
+        let dir = std::env::temp_dir().join(format!("mdv-linked-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).expect("dir");
+        for name in ["index.md", "a.md", "sub/page.md"] {
+            fs::write(dir.join(name), "x").expect("seed");
+        }
+        let text = "[a](a.md) [again](a.md#top) [page](sub/page.md) [gone](gone.md)\n\
+                    [site](https://example.com/b.md) [img](pic.png) [self](index.md)\n";
+        let paths = linked_markdown(text, Some(&dir), Some(&dir.join("index.md")));
+        assert_eq!(paths, [dir.join("a.md"), dir.join("sub/page.md")]);
+
+        let many: String = (0..MAX_LINK_WATCHES + 5)
+            .map(|i| {
+                fs::write(dir.join(format!("n{i}.md")), "x").expect("seed");
+                format!("[n](n{i}.md)\n")
+            })
+            .collect();
+        assert_eq!(
+            linked_markdown(&many, Some(&dir), None).len(),
+            MAX_LINK_WATCHES
+        );
+        assert!(linked_markdown("[a](a.md)", Some(Path::new("/nowhere")), None).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
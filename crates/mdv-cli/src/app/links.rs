@@ -0,0 +1,337 @@
+use std::path::{Path, PathBuf};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use mdv_core::{
+    LinkRef, ReferenceKind, collect_links, collect_references, extract_outline, heading_slug,
+    link_at,
+};
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use super::session::display_name;
+use super::status::Severity;
+use super::{App, PaneFocus, centered_popup};
+use crate::ui::theme::ThemeTokens;
+
+/// Links offered by `Alt+O` and the row selected among them.
+/// Most `F12` jumps `Shift+F12` can retrace.
+const MAX_JUMPS: usize = 50;
+
+pub(super) struct LinkPicker {
+    links: Vec<LinkRef>,
+    selected: usize,
+}
+
+/// Where the link under the cursor in the text at `revision` points. Reader
+/// mode hides destinations in the preview, so the status line shows it.
+pub(super) struct CursorLink {
+    revision: u64,
+    cursor: usize,
+    pub(super) status: Option<String>,
+}
+
+/// What following a link does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum LinkTarget {
+    /// Another Markdown file, opened in mdv.
+    Markdown(PathBuf),
+    /// A heading in this document, as its `#slug`.
+    Anchor(String),
+    /// A URL or other file, handed to the system opener.
+    External(String),
+}
+
+/// Resolve a link destination; relative paths start next to `base`, the
+/// directory of the file on screen.
+pub(super) fn link_target(dest: &str, base: Option<&Path>) -> LinkTarget {
+    if let Some(anchor) = dest.strip_prefix('#') {
+        return LinkTarget::Anchor(anchor.to_string());
+    }
+    if has_scheme(dest) {
+        return LinkTarget::External(dest.to_string());
+    }
+    let file = dest.split(['#', '?']).next().unwrap_or(dest);
+    let path = match base {
+        Some(dir) if Path::new(file).is_relative() => dir.join(file),
+        _ => PathBuf::from(file),
+    };
+    let markdown = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"));
+    if markdown {
+        LinkTarget::Markdown(path)
+    } else {
+        LinkTarget::External(path.to_string_lossy().into_owned())
+    }
+}
+
+/// `https:`, `mailto:` and the like. A single letter before the colon is a
+/// Windows drive, not a scheme.
+pub(super) fn has_scheme(dest: &str) -> bool {
+    dest.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+impl App {
+    /// Look up the link under the cursor again when the cursor or the text
+    /// moved. Only while typing with reader mode on; large files skip it.
+    pub(super) fn refresh_cursor_link(&mut self) {
+        if !self.reader_mode
+            || self.ui.focus != PaneFocus::Editor
+            || self.ui.view_only
+            || self.large_file_mode()
+        {
+            self.cursor_link = None;
+            return;
+        }
+        let (revision, cursor) = (self.editor.revision(), self.editor.cursor());
+        if self
+            .cursor_link
+            .as_ref()
+            .is_some_and(|link| link.revision == revision && link.cursor == cursor)
+        {
+            return;
+        }
+        self.cursor_link = Some(CursorLink {
+            revision,
+            cursor,
+            status: link_at(self.editor.text(), cursor).map(|link| format!("Link: {}", link.dest)),
+        });
+    }
+
+    /// Line links are looked for from: the cursor while typing, otherwise
+    /// the source of the top preview row.
+    fn link_search_line(&self) -> usize {
+        if self.ui.focus == PaneFocus::Editor && !self.ui.view_only {
+            return self.editor.line_col_at_cursor().0;
+        }
+        self.preview_top_source_line()
+    }
+
+    /// Follow the link on or after the current line, or list them all when
+    /// there are several.
+    pub(super) fn open_link_near(&mut self) {
+        let from = self.link_search_line();
+        let links: Vec<LinkRef> = collect_links(self.editor.text())
+            .into_iter()
+            .filter(|link| link.source_line >= from)
+            .collect();
+        match links.as_slice() {
+            [] => self.push_status(Severity::Warn, format!("No links from line {}", from + 1)),
+            [link] => self.follow_link(&link.clone()),
+            _ => {
+                self.push_status(Severity::Info, format!("Links: {}", links.len()));
+                self.link_picker = Some(LinkPicker { links, selected: 0 });
+            }
+        }
+    }
+
+    fn follow_link(&mut self, link: &LinkRef) {
+        let base = self.path.as_deref().and_then(Path::parent);
+        match link_target(&link.dest, base) {
+            LinkTarget::Markdown(path) => self.open_markdown(path),
+            LinkTarget::Anchor(anchor) => self.jump_to_anchor(&anchor),
+            LinkTarget::External(target) => match self.opener.open(&target) {
+                Ok(()) => self.push_status(Severity::Info, format!("Opened {target}")),
+                Err(err) => self.push_status(Severity::Error, format!("Open error: {err}")),
+            },
+        }
+    }
+
+    /// Open a linked Markdown file in place of this one, unless that would
+    /// drop unsaved edits.
+    pub(super) fn open_markdown(&mut self, path: PathBuf) {
+        if self.stream_mode {
+            self.push_status(
+                Severity::Warn,
+                format!("Stream mode: run mdv {} to open it", path.display()),
+            );
+        } else if self.editor.dirty && self.path.is_some() && !self.readonly {
+            self.push_status(
+                Severity::Warn,
+                format!(
+                    "Unsaved changes: save before opening {}",
+                    display_name(&path)
+                ),
+            );
+        } else {
+            self.open_home_path(path);
+        }
+    }
+
+    /// `F12`: from a reference link or footnote reference under the cursor
+    /// to the line that defines it. `Shift+F12` comes back.
+    pub(super) fn goto_definition(&mut self) {
+        let references = collect_references(self.editor.text());
+        let Some(reference) = references.use_at(self.editor.cursor()) else {
+            self.push_status(Severity::Warn, "No reference under the cursor");
+            return;
+        };
+        let label = match reference.kind {
+            ReferenceKind::Link => reference.label.clone(),
+            ReferenceKind::Footnote => format!("^{}", reference.label),
+        };
+        let Some(line) = references.definition_line(reference) else {
+            self.push_status(Severity::Warn, format!("definition not found: {label}"));
+            return;
+        };
+        if self.jump_stack.len() == MAX_JUMPS {
+            self.jump_stack.remove(0);
+        }
+        self.jump_stack.push(self.editor.cursor());
+        self.clear_selection();
+        self.editor.goto_line(line + 1);
+        self.ensure_cursor_visible();
+        self.preview_scroll = self.preview_line_for_source(line);
+        self.push_status(Severity::Info, format!("Definition of [{label}]"));
+    }
+
+    /// `Shift+F12`: back to where the last `F12` jumped from.
+    pub(super) fn jump_back(&mut self) {
+        let Some(cursor) = self.jump_stack.pop() else {
+            self.push_status(Severity::Warn, "No jump to go back from");
+            return;
+        };
+        self.clear_selection();
+        self.editor.set_cursor(cursor);
+        self.ensure_cursor_visible();
+        let (line, _) = self.editor.line_col_at_cursor();
+        self.preview_scroll = self.preview_line_for_source(line);
+        self.push_status(Severity::Info, "Jumped back");
+    }
+
+    fn jump_to_anchor(&mut self, anchor: &str) {
+        let wanted = anchor.to_lowercase();
+        let Some(entry) = extract_outline(self.editor.text())
+            .into_iter()
+            .find(|entry| heading_slug(&entry.text) == wanted)
+        else {
+            self.push_status(Severity::Warn, format!("No heading for #{anchor}"));
+            return;
+        };
+        self.clear_selection();
+        self.editor.goto_line(entry.source_line + 1);
+        self.ensure_cursor_visible();
+        self.preview_scroll = self.preview_line_for_source(entry.source_line);
+        self.push_status(Severity::Info, format!("Jumped to: {}", entry.text));
+    }
+
+    pub(super) fn handle_link_picker_key(&mut self, key: KeyEvent, running: &mut bool) {
+        let Some(picker) = self.link_picker.as_mut() else {
+            return;
+        };
+        let last = picker.links.len() - 1;
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
+                self.link_picker = None;
+                self.request_quit(running);
+            }
+            (KeyCode::Esc, _) | (KeyCode::Char('o'), KeyModifiers::ALT) => {
+                self.link_picker = None;
+                self.push_status(Severity::Info, "Links closed");
+            }
+            (KeyCode::Up, _) => picker.selected = picker.selected.saturating_sub(1),
+            (KeyCode::Down, _) => picker.selected = (picker.selected + 1).min(last),
+            (KeyCode::Home, _) => picker.selected = 0,
+            (KeyCode::End, _) => picker.selected = last,
+            (KeyCode::Enter, _) => {
+                let link = picker.links[picker.selected].clone();
+                self.link_picker = None;
+                self.follow_link(&link);
+            }
+            _ => {}
+        }
+    }
+
+    pub(super) fn draw_link_picker(&self, frame: &mut Frame<'_>, area: Rect, theme: &ThemeTokens) {
+        let Some(picker) = &self.link_picker else {
+            return;
+        };
+        let wanted = (picker.links.len() as u16).saturating_add(2);
+        let popup = centered_popup(70, wanted.min(area.height.saturating_sub(2)), area);
+        frame.render_widget(Clear, popup);
+
+        let visible = popup.height.saturating_sub(2).max(1) as usize;
+        let scroll = picker.selected.saturating_sub(visible - 1);
+        let lines = picker
+            .links
+            .iter()
+            .enumerate()
+            .skip(scroll)
+            .take(visible)
+            .map(|(index, link)| {
+                let style = if index == picker.selected {
+                    theme.selection
+                } else {
+                    theme.plain
+                };
+                let text = if link.text == link.dest {
+                    format!(" {}", link.dest)
+                } else {
+                    format!(" {} - {}", link.text, link.dest)
+                };
+                Line::from(Span::styled(text, style))
+            })
+            .collect::<Vec<_>>();
+
+        let list = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Links")
+                .border_style(theme.pane_focus),
+        );
+        frame.render_widget(list, popup);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{LinkTarget, link_target};
+
+    #[test]
+    fn markdown_paths_open_in_mdv_and_the_rest_go_outside() {
+        let base = Some(Path::new("docs"));
+        assert_eq!(
+            link_target("guide.md#setup", base),
+            LinkTarget::Markdown(PathBuf::from("docs/guide.md"))
+        );
+        assert_eq!(
+            link_target("../README.MARKDOWN", base),
+            LinkTarget::Markdown(PathBuf::from("docs/../README.MARKDOWN"))
+        );
+        assert_eq!(
+            link_target("/abs/notes.md", base),
+            LinkTarget::Markdown(PathBuf::from("/abs/notes.md"))
+        );
+        assert_eq!(
+            link_target("notes.md", None),
+            LinkTarget::Markdown(PathBuf::from("notes.md"))
+        );
+        assert_eq!(
+            link_target("https://example.com/a.md", base),
+            LinkTarget::External("https://example.com/a.md".into())
+        );
+        assert_eq!(
+            link_target("mailto:me@example.com", base),
+            LinkTarget::External("mailto:me@example.com".into())
+        );
+        assert_eq!(
+            link_target("diagram.png", base),
+            LinkTarget::External(PathBuf::from("docs/diagram.png").display().to_string())
+        );
+        assert_eq!(
+            link_target("#getting-started", base),
+            LinkTarget::Anchor("getting-started".into())
+        );
+    }
+}
@@ -1,25 +1,44 @@
 pub mod action;
+mod backup;
+mod conflict_view;
+mod file_info;
+mod goto;
+mod image;
 pub mod input;
+pub mod keymap;
+mod link_watch;
+mod links;
+pub mod pager;
+mod preview_line;
+mod review;
+mod session;
+pub mod snippets;
+mod soft_wrap;
 pub mod state;
+mod status;
+mod terminal;
 pub mod update;
 
 use std::fs;
 use std::hash::{Hash, Hasher};
 use std::io::{self, IsTerminal};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::Result;
-use crossterm::ExecutableCommand;
-use crossterm::event::{
-    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
-    MouseButton, MouseEventKind,
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEventKind};
+use mdv_core::{
+    ConflictHunk, EditorBuffer, FrontMatterStyle, HunkKind, LineEnding, OutlineEntry, PreviewLine,
+    PreviewRenderer, RenderOptions, SaveOutcome, SavePolicy, SearchOptions, SegmentKind,
+    SpellChecker, decode_text, extract_outline, match_ranges_in, read_text, render,
+    renumber_ordered_list, word_count,
 };
-use crossterm::terminal::{
-    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
-};
-use mdv_core::{EditorBuffer, SegmentKind, render_preview_lines};
+use mdv_watch::{StreamMessage, WatchMessage, WatchOptions, WatcherHandle, watch_file};
+#[cfg(not(test))]
+use mdv_watch::{StreamOptions, stream_stdin};
 use ratatui::backend::{Backend, CrosstermBackend};
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
@@ -27,19 +46,61 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
 use ratatui::{Frame, Terminal};
 
-#[cfg(not(test))]
-use crate::stream;
-use crate::stream::StreamMessage;
+use crate::clipboard::{self, Clipboard};
+use crate::completion::{self, CompletionResult};
+use crate::editorconfig;
+use crate::formatter;
+use crate::graphics::{self, ProcessEnv};
+use crate::opener::{self, Opener};
+use crate::prefs::{self, Prefs};
+use crate::resume::{self, FilePosition};
+use crate::spelling;
 use crate::ui::docs;
-use crate::ui::layout::{LayoutKind, compute_pane_layout};
-use crate::ui::render::{StatusBarConfig, build_status_bar, compose_status, truncate_middle};
-use crate::ui::theme::{ThemeTokens, build_theme, style_for_segment};
-use crate::watcher::{self, WatchMessage};
+use crate::ui::layout::{PaneLayout, compute_pane_layout};
+use crate::ui::render::{
+    StatusBarConfig, build_status_bar, char_at_column, compose_status, position_stats,
+    slice_columns, truncate_middle,
+};
+use crate::ui::theme::{
+    Background, ThemeOverrides, ThemeTokens, build_theme, detect_terminal_background, resolve_auto,
+    style_for_segment,
+};
 use action::Action;
-use state::{HelpNavAction, UiState, apply_help_nav};
+use file_info::{FileInfo, FileInfoJob};
+use image::ImageView;
+use keymap::{Chord, Command, KeyMap};
+use link_watch::LinkWatch;
+use links::{CursorLink, LinkPicker};
+use preview_line::{
+    ConflictSide, PreviewLineKind, push_hunk_blocks, push_whole_sides, selected_hunk_row,
+};
+use review::Review;
+use session::FileSession;
+use signal_hook::consts::{SIGINT, SIGTERM};
+use snippets::{Snippet, SnippetPalette};
+use state::{
+    HelpNavAction, MAX_SPLIT_PERCENT, MIN_SPLIT_PERCENT, SettingItem, SettingsNav, UiState,
+    apply_help_nav,
+};
 pub use state::{PaneFocus, ThemeChoice};
+use status::{Severity, StatusLine};
+use terminal::TerminalGuard;
 
 const SCROLL_STEP_LINES: usize = 3;
+const DEFAULT_LARGE_FILE_BYTES: usize = 2 * 1024 * 1024;
+/// Source lines the large-file preview renders at once, and how many of
+/// them sit above the editor's first visible line.
+const LARGE_PREVIEW_WINDOW_LINES: usize = 400;
+const LARGE_PREVIEW_MARGIN_LINES: usize = 80;
+/// How far the window may widen to reach a blank line between blocks.
+const BLOCK_SNAP_LIMIT: usize = 200;
+const CODE_FENCE_LOOKBACK_LINES: usize = 2_000;
+const DEFAULT_WATCH_DEBOUNCE: Duration = Duration::from_millis(150);
+/// How long the file may stay gone before the status line reports it deleted.
+const FILE_MISSING_GRACE: Duration = Duration::from_secs(1);
+const FILE_MISSING_STATUS: &str = "File missing on disk (will be created on save)";
+/// Added to the save message when the save policy changed the buffer.
+const TIDIED_SUFFIX: &str = " (whitespace tidied)";
 
 pub struct App {
     path: Option<PathBuf>,
@@ -49,35 +110,163 @@ pub struct App {
     stream_mode: bool,
     perf_mode: bool,
     editor: EditorBuffer,
-    status: String,
-    _watcher: Option<notify::RecommendedWatcher>,
+    status: StatusLine,
+    /// The `Alt+M` list of recent status messages is open.
+    message_log: bool,
+    _watcher: Option<WatcherHandle>,
     watch_rx: Option<std::sync::mpsc::Receiver<WatchMessage>>,
     stream_rx: Option<std::sync::mpsc::Receiver<StreamMessage>>,
     editor_scroll: usize,
+    /// Display columns hidden off the left edge of the editor pane.
+    editor_hscroll: usize,
+    /// With soft wrap on, rows of the top line scrolled past; only a line
+    /// taller than the pane is scrolled partway.
+    editor_wrap_skip: usize,
     preview_scroll: usize,
     editor_height: usize,
     preview_height: usize,
     draw_time_us: u128,
     watch_event_count: u64,
+    /// When the watcher last saw the file disappear.
+    missing_since: Option<Instant>,
+    /// The file stayed gone; the buffer is all there is until a save.
+    file_missing: bool,
+    /// Open files other than the one on screen, in order without it.
+    parked_files: Vec<FileSession>,
+    /// Position of the file on screen among all open files.
+    active_file: usize,
+    /// Selected row while the open-files switcher is showing.
+    file_switcher: Option<usize>,
+    /// Cursor offsets `F12` jumped from, latest last.
+    jump_stack: Vec<usize>,
+    /// Links listed by `Alt+O` when more than one is in reach.
+    link_picker: Option<LinkPicker>,
+    /// The `Ctrl+.` snippet palette while it is open.
+    snippet_palette: Option<SnippetPalette>,
+    /// `[snippets]` from the config file, listed after the built-in ones.
+    user_snippets: Vec<Snippet>,
+    /// `--watch-links`: watch the Markdown files the document links to.
+    watch_links: bool,
+    link_watches: Vec<LinkWatch>,
+    /// The linked file that changed last, opened by `F5`.
+    changed_link: Option<PathBuf>,
+    /// Buffer-against-disk changes shown by `Ctrl+Shift+D`.
+    review: Option<Review>,
+    image: ImageView,
+    /// A first `Ctrl+W` on a file with unsaved edits; the next one closes it.
+    close_armed: bool,
+    /// First file still to check for unsaved edits while quitting.
+    quit_review_next: usize,
+    /// External updates that reached the buffer, out of `watch_event_count`.
+    watch_applied_count: u64,
+    watch_debounce: Duration,
+    /// Shell command `Ctrl+Alt+F` pipes the text through.
+    formatter: Option<String>,
+    /// A formatter run still going.
+    format_job: Option<FormatJob>,
+    /// Size, time and git state of the file on disk for the path it was
+    /// looked up for; `None` inside when the lookup failed.
+    file_info: Option<(PathBuf, Option<FileInfo>)>,
+    /// A file info lookup still going.
+    file_info_job: Option<FileInfoJob>,
+    /// Spell checking, when turned on with `--spell` or in Settings.
+    spell: Option<SpellState>,
+    /// `[editor] spell_dictionary`, or `None` for the system word list.
+    spell_dictionary: Option<PathBuf>,
+    /// Where `Alt+A` keeps added words.
+    user_dictionary: Option<PathBuf>,
+    /// How the preview shows front matter; toggled in Settings.
+    front_matter: FrontMatterStyle,
+    /// The preview shows link text and image alt text rather than their
+    /// Markdown; toggled in Settings.
+    reader_mode: bool,
+    cursor_link: Option<CursorLink>,
+    /// Latest external text and when it arrived, held until it settles.
+    pending_external: Option<(String, Instant)>,
     stream_event_count: u64,
+    /// Bytes the stream has trimmed from its head to stay under the limit.
+    stream_dropped: usize,
     stream_done: bool,
+    /// `--edit-stream`: once stdin closes, the captured text becomes an
+    /// unsaved buffer to edit.
+    edit_stream: bool,
     interactive_input: bool,
     search_mode: bool,
     search_query: String,
     last_search_query: String,
+    search_options: SearchOptions,
+    /// Where the last search of the preview landed.
+    preview_hit: Option<PreviewHit>,
     goto_mode: bool,
     goto_query: String,
+    save_as_mode: bool,
+    save_as_query: String,
+    quit_confirm_mode: bool,
+    outline_mode: bool,
+    outline_entries: Vec<OutlineEntry>,
+    outline_selected: usize,
+    theme_overrides: ThemeOverrides,
+    /// Terminal background found at startup, which `ThemeChoice::Auto`
+    /// follows; `ui.theme` keeps the choice itself.
+    background: Option<Background>,
+    prefs_path: Option<PathBuf>,
+    /// The prefs file plus the settings changed this run; flags and the
+    /// detected theme are not written back.
+    prefs: Prefs,
+    /// Where each file's cursor and scroll are kept between runs; `None`
+    /// with `--no-session`.
+    resume_path: Option<PathBuf>,
+    indent_width: usize,
+    /// Whitespace cleanups on save before `.editorconfig` has its say.
+    save_policy: SavePolicy,
+    /// Cleanups the command line turned on for every file.
+    forced_save_policy: SavePolicy,
+    continue_lists: bool,
+    /// Chords for commands, from the defaults and `[keys]`.
+    keymap: KeyMap,
+    /// Stash the text a clean external update replaces (`[editor]
+    /// keep_before_external`).
+    keep_before_external: bool,
+    /// What the last external update replaced, restored by `Alt+R`.
+    before_external: Option<String>,
+    /// Where `Ctrl+R` copies the text it replaces; `None` keeps no backups
+    /// (`[editor] reload_backups`).
+    backup_dir: Option<PathBuf>,
+    large_file_bytes: usize,
+    /// First source line of the large-file preview window.
+    preview_source_top: usize,
+    autosave_interval: Option<Duration>,
+    last_save_at: Instant,
     replace_find_mode: bool,
     replace_find_query: String,
     replace_with_mode: bool,
+    /// Matches `Ctrl+A` would replace, waiting for `Enter` to go ahead.
+    replace_all_pending: Option<usize>,
     replace_with_query: String,
     replace_target: String,
     home_query: String,
-    selection_anchor: Option<usize>,
+    /// Candidates of the last ambiguous `Tab` completion in the Home prompt
+    /// and the one the next `Tab` fills in.
+    home_completions: Option<(Vec<String>, usize)>,
     selected_conflict_hunk: usize,
+    /// Id of the selected hunk, so the selection follows it when the hunks
+    /// are computed again.
+    selected_conflict_id: Option<usize>,
+    /// Show conflicts as side-by-side columns instead of stacked blocks.
+    conflict_columns: bool,
+    /// Keep both panes on the last lines as updates arrive (`--follow`).
+    follow: bool,
+    /// Set by a manual scroll up; following resumes with `Ctrl+End`.
+    follow_paused: bool,
     preview_cache: Option<PreviewCache>,
+    search_matches: Option<SearchMatches>,
+    /// The preview's top line after the last draw, for keeping it in place
+    /// when the preview is rendered again.
+    preview_anchor: Option<PreviewAnchor>,
+    preview_renderer: PreviewRenderer,
     ui: UiState,
     term_width: u16,
+    term_height: u16,
     editor_area: Rect,
     preview_area: Rect,
     editor_text_area: Rect,
@@ -86,6 +275,12 @@ pub struct App {
     onboarding_seen: bool,
     onboarding_gate_checked: bool,
     mouse_drag_anchor: Option<usize>,
+    clipboard: Option<ClipboardEntry>,
+    system_clipboard: Box<dyn Clipboard>,
+    opener: Box<dyn Opener>,
+    last_saved_text: Option<String>,
+    /// Set by SIGTERM or SIGINT; the run loop stops at its next turn.
+    shutdown: Arc<AtomicBool>,
     #[cfg(test)]
     test_next_key: Option<KeyEvent>,
     #[cfg(test)]
@@ -96,18 +291,92 @@ pub struct App {
     test_preview_cache_hits: u64,
     #[cfg(test)]
     test_preview_cache_misses: u64,
+    #[cfg(test)]
+    test_preview_appends: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ClipboardEntry {
+    Text(String),
+    Line(String),
+}
+
+impl ClipboardEntry {
+    fn text(&self) -> &str {
+        match self {
+            ClipboardEntry::Text(text) | ClipboardEntry::Line(text) => text,
+        }
+    }
+}
+
+const CLIPBOARD_FALLBACK_NOTE: &str = " (system clipboard unavailable, using internal)";
+
+/// Every match of the last search in the text at `revision`, for
+/// highlighting and the `match 3/17` counter.
+struct SearchMatches {
+    revision: u64,
+    query: String,
+    options: SearchOptions,
+    ranges: Vec<(usize, usize)>,
+}
+
+/// The spell checker and the misspellings it found in the text at
+/// `revision`; `None` means the text has not been checked yet.
+struct SpellState {
+    checker: SpellChecker,
+    revision: Option<u64>,
+    misspelled: Vec<(usize, usize)>,
+}
+
+/// A formatter started on the text at `revision`.
+struct FormatJob {
+    revision: u64,
+    rx: Receiver<formatter::FormatResult>,
 }
 
 #[derive(Clone)]
 struct PreviewCache {
     key: u64,
-    lines: Arc<Vec<String>>,
-    selected_anchor: Option<usize>,
+    lines: Arc<Vec<PreviewLineKind>>,
+    /// Editor revision of `lines` when they are the whole text rendered with
+    /// nothing added, so appended text only needs its tail rendered.
+    appendable: Option<u64>,
+}
+
+/// The source line at the top of the preview and enough about it to find
+/// it again once lines above it are added or removed.
+struct PreviewAnchor {
+    /// `preview_scroll` when the anchor was taken; scrolling since then
+    /// makes the anchor stale.
+    scroll: usize,
+    source_line: usize,
+    /// Rows of a wrapped source line already scrolled past.
+    rows_into: usize,
+    /// Editor line count when the anchor was taken.
+    line_count: usize,
+    text: String,
 }
 
+/// A match of a preview search: the rendered row, the byte offset in its
+/// text, and the source line the row came from.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct PreviewHit {
+    row: usize,
+    start: usize,
+    source_line: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PreviewSearch {
+    FromTop,
+    Next,
+    Prev,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 enum InputEvent {
     Key(KeyEvent),
+    Paste(String),
     Scroll {
         direction: i8,
         column: u16,
@@ -123,6 +392,10 @@ enum InputEvent {
         row: u16,
     },
     MouseUp,
+    Resize {
+        width: u16,
+        height: u16,
+    },
 }
 
 impl App {
@@ -135,7 +408,7 @@ impl App {
     ) -> Result<Self> {
         let (onboarding_marker_path, onboarding_seen) = onboarding_marker_state();
         let (watcher, watch_rx) = if watch_enabled {
-            let (watcher, watch_rx) = watcher::start(&path)?;
+            let (watcher, watch_rx) = watch_file(&path, WatchOptions::default())?;
             (Some(watcher), Some(watch_rx))
         } else {
             (None, None)
@@ -149,35 +422,103 @@ impl App {
             stream_mode: false,
             perf_mode,
             editor: EditorBuffer::new(initial_text),
-            status: "Ready".into(),
+            status: StatusLine::default(),
             _watcher: watcher,
             watch_rx,
             stream_rx: None,
             editor_scroll: 0,
+            editor_hscroll: 0,
+            editor_wrap_skip: 0,
             preview_scroll: 0,
             editor_height: 1,
             preview_height: 1,
             draw_time_us: 0,
             watch_event_count: 0,
+            missing_since: None,
+            file_missing: false,
+            parked_files: Vec::new(),
+            active_file: 0,
+            file_switcher: None,
+            jump_stack: Vec::new(),
+            link_picker: None,
+            snippet_palette: None,
+            user_snippets: Vec::new(),
+            message_log: false,
+            watch_links: false,
+            link_watches: Vec::new(),
+            changed_link: None,
+            review: None,
+            close_armed: false,
+            quit_review_next: 0,
+            watch_applied_count: 0,
+            watch_debounce: DEFAULT_WATCH_DEBOUNCE,
+            formatter: None,
+            format_job: None,
+            file_info: None,
+            file_info_job: None,
+            image: ImageView::default(),
+            spell: None,
+            front_matter: FrontMatterStyle::Folded,
+            reader_mode: true,
+            cursor_link: None,
+            spell_dictionary: None,
+            user_dictionary: None,
+            pending_external: None,
             stream_event_count: 0,
+            stream_dropped: 0,
             stream_done: false,
+            edit_stream: false,
             interactive_input: io::stdin().is_terminal(),
             search_mode: false,
             search_query: String::new(),
             last_search_query: String::new(),
+            preview_hit: None,
+            search_options: SearchOptions::default(),
             goto_mode: false,
             goto_query: String::new(),
+            save_as_mode: false,
+            save_as_query: String::new(),
+            quit_confirm_mode: false,
+            outline_mode: false,
+            outline_entries: Vec::new(),
+            outline_selected: 0,
+            theme_overrides: ThemeOverrides::default(),
+            background: None,
+            prefs_path: None,
+            prefs: Prefs::default(),
+            resume_path: None,
+            indent_width: 2,
+            save_policy: SavePolicy::default(),
+            forced_save_policy: SavePolicy::default(),
+            continue_lists: true,
+            keymap: KeyMap::default(),
+            keep_before_external: false,
+            backup_dir: None,
+            before_external: None,
+            large_file_bytes: DEFAULT_LARGE_FILE_BYTES,
+            preview_source_top: 0,
+            autosave_interval: None,
+            last_save_at: Instant::now(),
             replace_find_mode: false,
             replace_find_query: String::new(),
             replace_with_mode: false,
+            replace_all_pending: None,
             replace_with_query: String::new(),
             replace_target: String::new(),
             home_query: String::new(),
-            selection_anchor: None,
+            home_completions: None,
             selected_conflict_hunk: 0,
+            selected_conflict_id: None,
+            conflict_columns: true,
+            follow: false,
+            follow_paused: false,
             preview_cache: None,
+            search_matches: None,
+            preview_anchor: None,
+            preview_renderer: PreviewRenderer::new(),
             ui: UiState::default(),
             term_width: 120,
+            term_height: 30,
             editor_area: Rect::default(),
             preview_area: Rect::default(),
             editor_text_area: Rect::default(),
@@ -186,6 +527,11 @@ impl App {
             onboarding_seen,
             onboarding_gate_checked: false,
             mouse_drag_anchor: None,
+            clipboard: None,
+            system_clipboard: clipboard::system(),
+            opener: opener::system(),
+            last_saved_text: None,
+            shutdown: Arc::new(AtomicBool::new(false)),
             #[cfg(test)]
             test_next_key: None,
             #[cfg(test)]
@@ -196,11 +542,14 @@ impl App {
             test_preview_cache_hits: 0,
             #[cfg(test)]
             test_preview_cache_misses: 0,
+            #[cfg(test)]
+            test_preview_appends: 0,
         })
     }
 
+    /// Render stdin as it arrives, keeping at most `buffer_bytes` of it.
     #[cfg(not(test))]
-    pub fn new_stream(perf_mode: bool) -> Result<Self> {
+    pub fn new_stream(perf_mode: bool, buffer_bytes: Option<usize>) -> Result<Self> {
         let (onboarding_marker_path, onboarding_seen) = onboarding_marker_state();
         Ok(Self {
             path: None,
@@ -210,35 +559,107 @@ impl App {
             stream_mode: true,
             perf_mode,
             editor: EditorBuffer::new(String::new()),
-            status: "Stream mode".into(),
+            status: StatusLine::default(),
             _watcher: None,
             watch_rx: None,
-            stream_rx: Some(stream::start()),
+            stream_rx: Some(stream_stdin(
+                buffer_bytes.map_or_else(StreamOptions::from_env, |max_bytes| {
+                    StreamOptions::default().with_max_bytes(max_bytes)
+                }),
+            )),
             editor_scroll: 0,
+            editor_hscroll: 0,
+            editor_wrap_skip: 0,
             preview_scroll: 0,
             editor_height: 1,
             preview_height: 1,
             draw_time_us: 0,
             watch_event_count: 0,
+            missing_since: None,
+            file_missing: false,
+            parked_files: Vec::new(),
+            active_file: 0,
+            file_switcher: None,
+            jump_stack: Vec::new(),
+            link_picker: None,
+            snippet_palette: None,
+            user_snippets: Vec::new(),
+            message_log: false,
+            watch_links: false,
+            link_watches: Vec::new(),
+            changed_link: None,
+            review: None,
+            close_armed: false,
+            quit_review_next: 0,
+            watch_applied_count: 0,
+            watch_debounce: DEFAULT_WATCH_DEBOUNCE,
+            formatter: None,
+            format_job: None,
+            file_info: None,
+            file_info_job: None,
+            image: ImageView::default(),
+            spell: None,
+            front_matter: FrontMatterStyle::Folded,
+            reader_mode: true,
+            cursor_link: None,
+            spell_dictionary: None,
+            user_dictionary: None,
+            pending_external: None,
             stream_event_count: 0,
+            stream_dropped: 0,
             stream_done: false,
+            edit_stream: false,
             interactive_input: io::stdin().is_terminal(),
             search_mode: false,
             search_query: String::new(),
             last_search_query: String::new(),
+            preview_hit: None,
+            search_options: SearchOptions::default(),
             goto_mode: false,
             goto_query: String::new(),
+            save_as_mode: false,
+            save_as_query: String::new(),
+            quit_confirm_mode: false,
+            outline_mode: false,
+            outline_entries: Vec::new(),
+            outline_selected: 0,
+            theme_overrides: ThemeOverrides::default(),
+            background: None,
+            prefs_path: None,
+            prefs: Prefs::default(),
+            resume_path: None,
+            indent_width: 2,
+            save_policy: SavePolicy::default(),
+            forced_save_policy: SavePolicy::default(),
+            continue_lists: true,
+            keymap: KeyMap::default(),
+            keep_before_external: false,
+            backup_dir: None,
+            before_external: None,
+            large_file_bytes: DEFAULT_LARGE_FILE_BYTES,
+            preview_source_top: 0,
+            autosave_interval: None,
+            last_save_at: Instant::now(),
             replace_find_mode: false,
             replace_find_query: String::new(),
             replace_with_mode: false,
+            replace_all_pending: None,
             replace_with_query: String::new(),
             replace_target: String::new(),
             home_query: String::new(),
-            selection_anchor: None,
+            home_completions: None,
             selected_conflict_hunk: 0,
+            selected_conflict_id: None,
+            conflict_columns: true,
+            follow: false,
+            follow_paused: false,
             preview_cache: None,
+            search_matches: None,
+            preview_anchor: None,
+            preview_renderer: PreviewRenderer::new(),
             ui: UiState::default(),
             term_width: 120,
+            term_height: 30,
             editor_area: Rect::default(),
             preview_area: Rect::default(),
             editor_text_area: Rect::default(),
@@ -247,6 +668,11 @@ impl App {
             onboarding_seen,
             onboarding_gate_checked: false,
             mouse_drag_anchor: None,
+            clipboard: None,
+            system_clipboard: clipboard::system(),
+            opener: opener::system(),
+            last_saved_text: None,
+            shutdown: Arc::new(AtomicBool::new(false)),
             #[cfg(test)]
             test_next_key: None,
             #[cfg(test)]
@@ -257,11 +683,13 @@ impl App {
             test_preview_cache_hits: 0,
             #[cfg(test)]
             test_preview_cache_misses: 0,
+            #[cfg(test)]
+            test_preview_appends: 0,
         })
     }
 
     #[cfg(test)]
-    pub fn new_stream(perf_mode: bool) -> Result<Self> {
+    pub fn new_stream(perf_mode: bool, _buffer_bytes: Option<usize>) -> Result<Self> {
         Ok(Self::new_stream_for_test(perf_mode))
     }
 
@@ -276,35 +704,103 @@ impl App {
             stream_mode: true,
             perf_mode,
             editor: EditorBuffer::new(String::new()),
-            status: "Stream mode".into(),
+            status: StatusLine::default(),
             _watcher: None,
             watch_rx: None,
             stream_rx: None,
             editor_scroll: 0,
+            editor_hscroll: 0,
+            editor_wrap_skip: 0,
             preview_scroll: 0,
             editor_height: 1,
             preview_height: 1,
             draw_time_us: 0,
             watch_event_count: 0,
+            missing_since: None,
+            file_missing: false,
+            parked_files: Vec::new(),
+            active_file: 0,
+            file_switcher: None,
+            jump_stack: Vec::new(),
+            link_picker: None,
+            snippet_palette: None,
+            user_snippets: Vec::new(),
+            message_log: false,
+            watch_links: false,
+            link_watches: Vec::new(),
+            changed_link: None,
+            review: None,
+            close_armed: false,
+            quit_review_next: 0,
+            watch_applied_count: 0,
+            watch_debounce: DEFAULT_WATCH_DEBOUNCE,
+            formatter: None,
+            format_job: None,
+            file_info: None,
+            file_info_job: None,
+            image: ImageView::default(),
+            spell: None,
+            front_matter: FrontMatterStyle::Folded,
+            reader_mode: true,
+            cursor_link: None,
+            spell_dictionary: None,
+            user_dictionary: None,
+            pending_external: None,
             stream_event_count: 0,
+            stream_dropped: 0,
             stream_done: false,
+            edit_stream: false,
             interactive_input: false,
             search_mode: false,
             search_query: String::new(),
             last_search_query: String::new(),
+            preview_hit: None,
+            search_options: SearchOptions::default(),
             goto_mode: false,
             goto_query: String::new(),
+            save_as_mode: false,
+            save_as_query: String::new(),
+            quit_confirm_mode: false,
+            outline_mode: false,
+            outline_entries: Vec::new(),
+            outline_selected: 0,
+            theme_overrides: ThemeOverrides::default(),
+            background: None,
+            prefs_path: None,
+            prefs: Prefs::default(),
+            resume_path: None,
+            indent_width: 2,
+            save_policy: SavePolicy::default(),
+            forced_save_policy: SavePolicy::default(),
+            continue_lists: true,
+            keymap: KeyMap::default(),
+            keep_before_external: false,
+            backup_dir: None,
+            before_external: None,
+            large_file_bytes: DEFAULT_LARGE_FILE_BYTES,
+            preview_source_top: 0,
+            autosave_interval: None,
+            last_save_at: Instant::now(),
             replace_find_mode: false,
             replace_find_query: String::new(),
             replace_with_mode: false,
+            replace_all_pending: None,
             replace_with_query: String::new(),
             replace_target: String::new(),
             home_query: String::new(),
-            selection_anchor: None,
+            home_completions: None,
             selected_conflict_hunk: 0,
+            selected_conflict_id: None,
+            conflict_columns: true,
+            follow: false,
+            follow_paused: false,
             preview_cache: None,
+            search_matches: None,
+            preview_anchor: None,
+            preview_renderer: PreviewRenderer::new(),
             ui: UiState::default(),
             term_width: 120,
+            term_height: 30,
             editor_area: Rect::default(),
             preview_area: Rect::default(),
             editor_text_area: Rect::default(),
@@ -313,11 +809,18 @@ impl App {
             onboarding_seen,
             onboarding_gate_checked: false,
             mouse_drag_anchor: None,
+            clipboard: None,
+            system_clipboard: clipboard::system(),
+            opener: opener::system(),
+            last_saved_text: None,
+            shutdown: Arc::new(AtomicBool::new(false)),
             test_next_key: None,
             test_next_key_result: None,
             test_draw_error: None,
             test_preview_cache_hits: 0,
             test_preview_cache_misses: 0,
+            #[cfg(test)]
+            test_preview_appends: 0,
         }
     }
 
@@ -331,35 +834,103 @@ impl App {
             stream_mode: false,
             perf_mode,
             editor: EditorBuffer::new(String::new()),
-            status: "Home".into(),
+            status: StatusLine::default(),
             _watcher: None,
             watch_rx: None,
             stream_rx: None,
             editor_scroll: 0,
+            editor_hscroll: 0,
+            editor_wrap_skip: 0,
             preview_scroll: 0,
             editor_height: 1,
             preview_height: 1,
             draw_time_us: 0,
             watch_event_count: 0,
+            missing_since: None,
+            file_missing: false,
+            parked_files: Vec::new(),
+            active_file: 0,
+            file_switcher: None,
+            jump_stack: Vec::new(),
+            link_picker: None,
+            snippet_palette: None,
+            user_snippets: Vec::new(),
+            message_log: false,
+            watch_links: false,
+            link_watches: Vec::new(),
+            changed_link: None,
+            review: None,
+            close_armed: false,
+            quit_review_next: 0,
+            watch_applied_count: 0,
+            watch_debounce: DEFAULT_WATCH_DEBOUNCE,
+            formatter: None,
+            format_job: None,
+            file_info: None,
+            file_info_job: None,
+            image: ImageView::default(),
+            spell: None,
+            front_matter: FrontMatterStyle::Folded,
+            reader_mode: true,
+            cursor_link: None,
+            spell_dictionary: None,
+            user_dictionary: None,
+            pending_external: None,
             stream_event_count: 0,
+            stream_dropped: 0,
             stream_done: false,
+            edit_stream: false,
             interactive_input: io::stdin().is_terminal(),
             search_mode: false,
             search_query: String::new(),
             last_search_query: String::new(),
+            preview_hit: None,
+            search_options: SearchOptions::default(),
             goto_mode: false,
             goto_query: String::new(),
+            save_as_mode: false,
+            save_as_query: String::new(),
+            quit_confirm_mode: false,
+            outline_mode: false,
+            outline_entries: Vec::new(),
+            outline_selected: 0,
+            theme_overrides: ThemeOverrides::default(),
+            background: None,
+            prefs_path: None,
+            prefs: Prefs::default(),
+            resume_path: None,
+            indent_width: 2,
+            save_policy: SavePolicy::default(),
+            forced_save_policy: SavePolicy::default(),
+            continue_lists: true,
+            keymap: KeyMap::default(),
+            keep_before_external: false,
+            backup_dir: None,
+            before_external: None,
+            large_file_bytes: DEFAULT_LARGE_FILE_BYTES,
+            preview_source_top: 0,
+            autosave_interval: None,
+            last_save_at: Instant::now(),
             replace_find_mode: false,
             replace_find_query: String::new(),
             replace_with_mode: false,
+            replace_all_pending: None,
             replace_with_query: String::new(),
             replace_target: String::new(),
             home_query: String::new(),
-            selection_anchor: None,
+            home_completions: None,
             selected_conflict_hunk: 0,
+            selected_conflict_id: None,
+            conflict_columns: true,
+            follow: false,
+            follow_paused: false,
             preview_cache: None,
+            search_matches: None,
+            preview_anchor: None,
+            preview_renderer: PreviewRenderer::new(),
             ui: UiState::default(),
             term_width: 120,
+            term_height: 30,
             editor_area: Rect::default(),
             preview_area: Rect::default(),
             editor_text_area: Rect::default(),
@@ -368,6 +939,11 @@ impl App {
             onboarding_seen,
             onboarding_gate_checked: false,
             mouse_drag_anchor: None,
+            clipboard: None,
+            system_clipboard: clipboard::system(),
+            opener: opener::system(),
+            last_saved_text: None,
+            shutdown: Arc::new(AtomicBool::new(false)),
             #[cfg(test)]
             test_next_key: None,
             #[cfg(test)]
@@ -378,6 +954,8 @@ impl App {
             test_preview_cache_hits: 0,
             #[cfg(test)]
             test_preview_cache_misses: 0,
+            #[cfg(test)]
+            test_preview_appends: 0,
         })
     }
 
@@ -416,6 +994,215 @@ impl App {
         );
     }
 
+    /// Editor share of the split view, from saved prefs; kept within 20-80.
+    pub fn set_split_percent(&mut self, percent: u16) {
+        self.ui.split_percent = percent.clamp(MIN_SPLIT_PERCENT, MAX_SPLIT_PERCENT);
+    }
+
+    pub fn set_theme_overrides(&mut self, overrides: ThemeOverrides) {
+        self.theme_overrides = overrides;
+    }
+
+    /// Where settings changed in the docs modal are remembered.
+    pub fn set_prefs_path(&mut self, path: Option<PathBuf>) {
+        self.prefs = path.as_deref().map(prefs::load).unwrap_or_default();
+        self.prefs_path = path;
+    }
+
+    /// Where files are reopened from the cursor and scroll they were left
+    /// at. The file on screen is restored right away.
+    pub fn set_resume_path(&mut self, path: Option<PathBuf>) {
+        self.resume_path = path;
+        self.restore_position();
+    }
+
+    /// Save cleanups from the config, and those forced on by flags.
+    pub fn set_save_policy(&mut self, config: SavePolicy, forced: SavePolicy) {
+        self.save_policy = config;
+        self.forced_save_policy = forced;
+    }
+
+    /// Spaces `Tab` inserts and `Shift+Tab` removes, from `[editor] indent`.
+    pub fn set_indent_width(&mut self, width: usize) {
+        self.indent_width = width.max(1);
+    }
+
+    /// `Enter` repeats list and blockquote markers unless this is off.
+    pub fn set_continue_lists(&mut self, on: bool) {
+        self.continue_lists = on;
+    }
+
+    pub fn set_snippets(&mut self, snippets: Vec<Snippet>) {
+        self.user_snippets = snippets;
+    }
+
+    pub fn set_keymap(&mut self, keymap: KeyMap) {
+        self.keymap = keymap;
+    }
+
+    pub fn set_backup_dir(&mut self, dir: Option<PathBuf>) {
+        self.backup_dir = dir;
+    }
+
+    pub fn set_keep_before_external(&mut self, on: bool) {
+        self.keep_before_external = on;
+        if !on {
+            self.before_external = None;
+        }
+    }
+
+    /// Wait for the watched file to be quiet this long before applying it.
+    pub fn set_watch_debounce(&mut self, debounce: Duration) {
+        self.watch_debounce = debounce;
+    }
+
+    pub fn set_formatter(&mut self, command: Option<String>) {
+        self.formatter = command;
+    }
+
+    /// The word list spell checking loads, `None` for the system one, and
+    /// the file `Alt+A` adds words to.
+    pub fn set_spell_dictionaries(&mut self, dictionary: Option<PathBuf>, user: Option<PathBuf>) {
+        self.spell_dictionary = dictionary;
+        self.user_dictionary = user;
+    }
+
+    /// Turn spell checking on or off. The word list loads when it is
+    /// turned on; if that fails it stays off and the status says why.
+    pub fn set_spell(&mut self, on: bool) {
+        if !on {
+            self.spell = None;
+            return;
+        }
+        if self.spell.is_some() {
+            return;
+        }
+        match spelling::load_checker(
+            self.spell_dictionary.as_deref(),
+            self.user_dictionary.as_deref(),
+        ) {
+            Ok(checker) => {
+                self.spell = Some(SpellState {
+                    checker,
+                    revision: None,
+                    misspelled: Vec::new(),
+                });
+            }
+            Err(err) => self.push_status(Severity::Error, format!("Spell check: {err}")),
+        }
+    }
+
+    /// Documents over `bytes` render the preview around the viewport only.
+    pub fn set_large_file_threshold(&mut self, bytes: usize) {
+        self.large_file_bytes = bytes;
+    }
+
+    fn large_file_mode(&self) -> bool {
+        self.editor.text().len() > self.large_file_bytes
+    }
+
+    /// Record how the opened file was decoded so saves keep its line endings.
+    pub fn set_file_encoding(&mut self, line_ending: LineEnding, invalid_sequences: usize) {
+        self.editor.set_line_ending(line_ending);
+        if invalid_sequences > 0 {
+            self.push_status(Severity::Info, invalid_utf8_status(invalid_sequences));
+        }
+    }
+
+    /// Start with the cursor and both panes at 1-based `line`, clamped to
+    /// the document.
+    pub fn set_initial_line(&mut self, line: usize) {
+        let line = line.clamp(1, self.editor.line_count().max(1));
+        self.editor.goto_line(line);
+        self.editor_scroll = line - 1;
+        self.preview_scroll = self.preview_line_for_source(line - 1);
+    }
+
+    /// Start at 1-based `line` and `col`, from `--line` or a `path:line:col`
+    /// argument. A line past the end opens at the last one, with a note.
+    pub fn open_at(&mut self, line: usize, col: Option<usize>) {
+        let last = self.editor.line_count().max(1);
+        self.set_initial_line(line);
+        if let Some(col) = col {
+            self.editor
+                .set_cursor_line_col(line.clamp(1, last) - 1, col.saturating_sub(1));
+        }
+        self.ensure_cursor_visible();
+        if line > last {
+            self.push_status(
+                Severity::Warn,
+                format!("Line {line} is past the end; opened at line {last}"),
+            );
+        }
+    }
+
+    pub fn set_edit_stream(&mut self, on: bool) {
+        self.edit_stream = on;
+    }
+
+    pub fn set_follow(&mut self, follow: bool) {
+        self.follow = follow;
+        self.follow_paused = false;
+        if follow {
+            self.scroll_to_end();
+        }
+    }
+
+    fn following(&self) -> bool {
+        self.follow && !self.follow_paused
+    }
+
+    /// `Ctrl+End`: start following, resume after a pause, or stop.
+    fn toggle_follow(&mut self) {
+        if self.following() {
+            self.follow = false;
+            self.push_status(Severity::Info, "Follow off");
+        } else {
+            self.set_follow(true);
+            self.push_status(Severity::Info, "Following end of file (Ctrl+End stops)");
+        }
+    }
+
+    fn pause_follow(&mut self) {
+        if self.following() {
+            self.follow_paused = true;
+            self.push_status(Severity::Info, "Follow paused (Ctrl+End resumes)");
+        }
+    }
+
+    /// Put the cursor and both panes on the last lines. The preview offset
+    /// is clamped to the last page on the next draw.
+    fn scroll_to_end(&mut self) {
+        self.clear_selection();
+        self.editor.move_doc_end();
+        self.ensure_cursor_visible();
+        self.preview_source_top = self.editor.line_count();
+        self.preview_scroll = usize::MAX;
+        self.slide_large_preview();
+    }
+
+    /// Surface config problems in the status line instead of failing startup.
+    pub fn show_config_warnings(&mut self, warnings: &[String]) {
+        if let Some(first) = warnings.first() {
+            let more = match warnings.len() {
+                1 => String::new(),
+                n => format!(" (+{} more)", n - 1),
+            };
+            self.push_status(Severity::Info, format!("Config warning: {first}{more}"));
+        }
+    }
+
+    /// Periodically save a dirty buffer; `0` disables autosave.
+    pub fn set_autosave(&mut self, seconds: u64) {
+        self.autosave_interval = (seconds > 0).then(|| Duration::from_secs(seconds));
+    }
+
+    pub fn set_view_only(&mut self, view_only: bool) {
+        if self.ui.view_only != view_only {
+            update::apply_action(&mut self.ui, Action::ToggleViewOnly, self.term_width);
+        }
+    }
+
     pub fn set_initial_focus(&mut self, focus: PaneFocus) {
         let theme = self.ui.theme;
         let no_color = self.ui.no_color;
@@ -436,26 +1223,28 @@ impl App {
     }
 
     pub fn run(&mut self) -> Result<()> {
-        let mut stdout = io::stdout();
-        stdout.execute(EnterAlternateScreen)?;
-        if self.interactive_input {
-            stdout.execute(EnableMouseCapture)?;
+        // SIGTERM and SIGINT end the loop below instead of killing the
+        // process with the terminal still in raw mode.
+        for signal in [SIGINT, SIGTERM] {
+            signal_hook::flag::register(signal, Arc::clone(&self.shutdown))?;
         }
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend)?;
-
-        toggle_raw_mode(self.interactive_input, enable_raw_mode)?;
+        // Declared first so it is dropped last, after the terminal.
+        let _guard = TerminalGuard::enter(self.interactive_input)?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
 
-        let loop_result = self.run_loop(&mut terminal);
-
-        toggle_raw_mode(self.interactive_input, disable_raw_mode)?;
+        if self.interactive_input && !self.ui.no_color && self.ui.theme == ThemeChoice::Auto {
+            self.background = detect_terminal_background();
+        }
         if self.interactive_input {
-            terminal.backend_mut().execute(DisableMouseCapture)?;
+            self.image.protocol = graphics::detect(&ProcessEnv);
+        }
+        // A test hook only; release builds never panic on purpose.
+        #[cfg(debug_assertions)]
+        if std::env::var("MDV_FORCE_PANIC").ok().as_deref() == Some("1") {
+            panic!("MDV_FORCE_PANIC is set");
         }
-        terminal.backend_mut().execute(LeaveAlternateScreen)?;
-        terminal.show_cursor()?;
 
-        loop_result
+        self.run_loop(&mut terminal)
     }
 
     fn run_loop<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
@@ -463,8 +1252,15 @@ impl App {
         self.maybe_open_onboarding();
 
         while running {
+            if self.shutdown.load(Ordering::Relaxed) {
+                break;
+            }
             self.handle_watch_updates();
+            self.handle_link_watch_updates();
             self.handle_stream_updates();
+            self.handle_format_result();
+            self.handle_file_info_result();
+            self.maybe_autosave(Instant::now());
 
             if !self.interactive_input && self.stream_mode && self.stream_done {
                 running = false;
@@ -475,8 +1271,13 @@ impl App {
             if let Some(err) = self.test_draw_error.take() {
                 return Err(err.into());
             }
+            if std::mem::take(&mut self.image.redraw) {
+                terminal.clear()?;
+            }
             terminal.draw(|frame| self.draw(frame))?;
             self.draw_time_us = started.elapsed().as_micros();
+            self.status.age();
+            self.flush_image(&mut io::stdout())?;
 
             if !self.interactive_input && !self.stream_mode {
                 running = false;
@@ -487,6 +1288,7 @@ impl App {
             {
                 match input_event {
                     InputEvent::Key(key) => self.handle_key(key, &mut running)?,
+                    InputEvent::Paste(text) => self.handle_paste(&text),
                     InputEvent::Scroll {
                         direction,
                         column,
@@ -498,14 +1300,81 @@ impl App {
                         modifiers,
                     } => self.handle_mouse_down(column, row, modifiers),
                     InputEvent::MouseDrag { column, row } => self.handle_mouse_drag(column, row),
-                    InputEvent::MouseUp => self.mouse_drag_anchor = None,
+                    InputEvent::MouseUp => self.handle_mouse_up(),
+                    InputEvent::Resize { width, height } => self.handle_resize(width, height),
                 }
             }
         }
 
+        self.remember_position();
         Ok(())
     }
 
+    /// Lay the panes out for the new size right away, so scroll offsets and
+    /// the preview never outlive the size they were computed for.
+    fn handle_resize(&mut self, width: u16, height: u16) {
+        self.close_image();
+        let was_compact = self
+            .pane_layout_for(self.term_width, self.term_height)
+            .kind
+            .is_compact();
+        self.term_width = width.max(1);
+        self.term_height = height;
+        self.preview_cache = None;
+
+        let layout = self.pane_layout_for(width, height);
+        self.editor_area = layout.editor;
+        self.preview_area = layout.preview;
+        if layout.editor.width > 0 && layout.editor.height > 0 {
+            self.editor_height = (layout.editor.height.saturating_sub(2) as usize).max(1);
+            let gutter_width = if self.ui.line_numbers {
+                line_number_gutter_width(self.editor.line_count())
+            } else {
+                0
+            };
+            let text_area = cursor_rect(layout.editor);
+            self.editor_text_area = Rect {
+                x: text_area.x + gutter_width,
+                width: text_area.width.saturating_sub(gutter_width),
+                ..text_area
+            };
+            self.editor_scroll = clamp_scroll(
+                self.editor_scroll,
+                self.editor.line_count(),
+                self.editor_height,
+            );
+            self.ensure_cursor_visible();
+        }
+        if layout.preview.width > 0 && layout.preview.height > 0 && !self.conflict_view_active() {
+            self.preview_height = (layout.preview.height.saturating_sub(2) as usize).max(1);
+            let lines = self.preview_lines_cached(layout.preview.width.saturating_sub(2));
+            self.preview_scroll =
+                clamp_scroll(self.preview_scroll, lines.len(), self.preview_height);
+        }
+
+        let compact = layout.kind.is_compact();
+        if compact != was_compact {
+            let layout = if compact {
+                "Compact layout: terminal is under 80x24"
+            } else {
+                "Full layout"
+            };
+            self.push_status(Severity::Info, layout);
+        }
+    }
+
+    /// The pane layout `draw` uses for a `width` x `height` terminal.
+    fn pane_layout_for(&self, width: u16, height: u16) -> PaneLayout {
+        let body = Rect::new(0, 1, width, height.saturating_sub(2));
+        compute_pane_layout(
+            body,
+            self.ui.focus,
+            self.ui.view_only,
+            self.ui.maximized,
+            self.ui.split_percent,
+        )
+    }
+
     fn handle_watch_updates(&mut self) {
         if !self.watch_enabled {
             return;
@@ -515,36 +1384,158 @@ impl App {
             return;
         };
 
-        let mut latest_external: Option<String> = None;
-
-        while let Ok(msg) = watch_rx.try_recv() {
+        let messages: Vec<WatchMessage> = watch_rx.try_iter().collect();
+        for msg in messages {
             self.watch_event_count += 1;
             match msg {
                 WatchMessage::ExternalUpdate(text) => {
-                    latest_external = Some(text);
+                    self.missing_since = None;
+                    self.file_missing = false;
+                    self.pending_external = Some((text, Instant::now()));
+                }
+                WatchMessage::Removed => {
+                    self.missing_since.get_or_insert_with(Instant::now);
+                }
+                WatchMessage::Unreadable(err) => {
+                    self.push_status(
+                        Severity::Error,
+                        format!("Cannot read the file on disk ({err}); buffer kept"),
+                    );
                 }
                 WatchMessage::Error(err) => {
-                    self.status = format!("watch error: {err}");
+                    self.push_status(Severity::Error, format!("watch error: {err}"));
                 }
             }
         }
 
-        if let Some(external) = latest_external {
-            if external == self.editor.text() {
+        if let Some(since) = self.missing_since
+            && since.elapsed() >= FILE_MISSING_GRACE
+        {
+            self.missing_since = None;
+            if self.path.as_ref().is_some_and(|path| !path.exists()) {
+                self.file_missing = true;
+                self.push_status(Severity::Warn, FILE_MISSING_STATUS);
+            }
+        }
+
+        // Only apply content that has been quiet for the debounce window, so a
+        // tool rewriting the file in bursts causes one refresh or conflict.
+        let settled = self
+            .pending_external
+            .as_ref()
+            .is_some_and(|(_, at)| at.elapsed() >= self.watch_debounce);
+        if settled && let Some((external, _)) = self.pending_external.take() {
+            // Our own saves echo back through the watcher; skip them.
+            if external == self.editor.text()
+                || self.last_saved_text.as_deref() == Some(external.as_str())
+            {
                 return;
             }
+            self.watch_applied_count += 1;
+            // Only the echo of our own save is skipped: once another write
+            // is applied, going back to the saved text is a real change.
+            self.last_saved_text = None;
+            // The review compared against the old disk text.
+            self.review = None;
+            let replaced = (self.keep_before_external && !self.editor.dirty)
+                .then(|| self.editor.text().to_string());
+            let anchor = self.conflict_anchor();
+            let (scroll, wrap_skip) = (self.editor_scroll, self.editor_wrap_skip);
             self.editor.on_external_change(external);
-            self.sync_conflict_hunk_selection();
+            match anchor {
+                Some(anchor) => self.reanchor_conflict_selection(anchor),
+                None => self.sync_conflict_hunk_selection(),
+            }
+            self.sync_link_watches();
+            self.refresh_file_info();
+            // Keep the editor viewport where it was, clamped when the file
+            // shrank; it only moves if the cursor line left the screen.
+            self.set_editor_scroll(scroll);
+            if self.editor_scroll == scroll {
+                self.editor_wrap_skip = wrap_skip;
+            }
             self.ensure_cursor_visible();
-            if self.editor.is_conflicted() {
-                self.status =
-                    "External update conflict: Ctrl+J/Ctrl+U hunk | Ctrl+E apply | Ctrl+K keep | Ctrl+R reload | Ctrl+M merge".into();
+            if self.following() && !self.editor.is_conflicted() {
+                self.scroll_to_end();
+            }
+            if let Some(anchor) = anchor
+                && let Some(conflict) = self.editor.conflict()
+            {
+                let count = conflict.hunks.len();
+                self.push_status(
+                    Severity::Warn,
+                    format!("Conflict updated: {count} hunks (was {})", anchor.hunks),
+                );
+            } else if self.editor.is_conflicted() {
+                let hint = self.conflict_hint();
+                self.push_status(Severity::Warn, format!("External update conflict: {hint}"));
+            } else if let Some(replaced) = replaced {
+                self.before_external = Some(replaced);
+                self.push_status(
+                    Severity::Info,
+                    "File refreshed from disk (Alt+R restores the previous text)",
+                );
             } else {
-                self.status = "File refreshed from disk".into();
+                self.push_status(Severity::Info, "File refreshed from disk");
             }
         }
     }
 
+    /// Copy the buffer to the backup dir before a reload replaces it with
+    /// `disk`, unless backups are off or nothing would be lost.
+    fn backup_before_reload(&self, disk: &str) -> Option<io::Result<PathBuf>> {
+        let path = self.path.as_ref()?;
+        let root = self.backup_dir.as_ref()?;
+        (self.editor.text() != disk)
+            .then(|| backup::write_backup(root, path, self.editor.text(), SystemTime::now()))
+    }
+
+    fn push_reload_status(&mut self, reloaded: &str, backup: Option<io::Result<PathBuf>>) {
+        match backup {
+            None => self.push_status(Severity::Info, reloaded),
+            Some(Ok(target)) => self.push_status(
+                Severity::Info,
+                format!(
+                    "{reloaded}; old text in {} (Ctrl+Z undoes)",
+                    target.display()
+                ),
+            ),
+            Some(Err(err)) => self.push_status(
+                Severity::Warn,
+                format!("{reloaded}; backup failed ({err}), Ctrl+Z undoes"),
+            ),
+        }
+    }
+
+    /// `Alt+R`: put back the text the last external update replaced, as an
+    /// edit that can be saved or undone.
+    fn restore_before_external(&mut self) {
+        if self.readonly {
+            self.push_status(Severity::Warn, "Readonly: edit disabled");
+            return;
+        }
+        let Some(text) = self.before_external.take() else {
+            let reason = if self.keep_before_external {
+                "No external update to undo"
+            } else {
+                "Set keep_before_external in [editor] to restore text after external updates"
+            };
+            self.push_status(Severity::Warn, reason);
+            return;
+        };
+        let (line, col) = self.editor.line_col_at_cursor();
+        self.clear_selection();
+        self.editor
+            .replace_range(0, self.editor.text().len(), &text);
+        self.editor.set_cursor_line_col(line, col);
+        self.sync_conflict_hunk_selection();
+        self.ensure_cursor_visible();
+        self.push_status(
+            Severity::Info,
+            "Restored the text from before the external update (Ctrl+S keeps it)",
+        );
+    }
+
     fn handle_stream_updates(&mut self) {
         if !self.stream_mode {
             return;
@@ -554,88 +1545,266 @@ impl App {
             return;
         };
 
-        let mut latest: Option<(String, bool)> = None;
+        // A full copy replaces everything before it; appends after it are
+        // added to that copy.
+        let mut latest: Option<(String, usize)> = None;
+        let mut appended = String::new();
 
-        while let Ok(msg) = stream_rx.try_recv() {
+        let messages: Vec<StreamMessage> = stream_rx.try_iter().collect();
+        let mut ended = false;
+        for msg in messages {
             self.stream_event_count += 1;
             match msg {
-                StreamMessage::Update { text, truncated } => {
-                    latest = Some((text, truncated));
+                StreamMessage::Append(chunk) => match &mut latest {
+                    Some((text, _)) => text.push_str(&chunk),
+                    None => appended.push_str(&chunk),
+                },
+                StreamMessage::Update { text, dropped } => {
+                    latest = Some((text, dropped));
+                    appended.clear();
                 }
                 StreamMessage::End => {
                     self.stream_done = true;
-                    self.status = "stdin closed | Ctrl+Q quit".into();
+                    ended = true;
+                    if !self.edit_stream {
+                        self.push_status(Severity::Info, "stdin closed | Ctrl+Q quit");
+                    }
                 }
                 StreamMessage::Error(err) => {
-                    self.status = format!("stream error: {err}");
+                    self.push_status(Severity::Error, format!("stream error: {err}"));
                 }
             }
         }
 
-        if let Some((text, truncated)) = latest {
-            if text == self.editor.text() {
-                return;
+        if let Some((text, dropped)) = latest {
+            let trimmed = dropped > self.stream_dropped;
+            self.stream_dropped = dropped;
+            if text != self.editor.text() {
+                self.editor.on_external_change(text);
+                self.sync_conflict_hunk_selection();
+                self.ensure_cursor_visible();
+                self.stream_updated(trimmed);
             }
+        } else if !appended.is_empty() && self.editor.append_external(&appended) {
+            // The cursor and scroll stay put while the stream grows.
+            self.stream_updated(false);
+        }
 
-            self.editor.on_external_change(text);
-            self.sync_conflict_hunk_selection();
-            self.ensure_cursor_visible();
+        if ended && self.edit_stream {
+            self.edit_streamed_text();
+        }
+    }
 
-            if !self.stream_done {
-                self.status = if truncated {
-                    "stream update received (trimmed)".into()
-                } else {
-                    "stream update received".into()
-                };
-            }
+    /// Leave stream mode with what stdin sent as an unsaved buffer: typing,
+    /// undo and save-as work on it like on a new file. Only once stdin has
+    /// closed, so no update can land on top of an edit.
+    fn edit_streamed_text(&mut self) {
+        self.stream_mode = false;
+        self.stream_rx = None;
+        self.readonly = false;
+        self.editor.dirty = true;
+        let save_as = self
+            .keymap
+            .chords(Command::Save)
+            .first()
+            .map(|chord| format!(", {chord} saves"))
+            .unwrap_or_default();
+        self.push_status(
+            Severity::Info,
+            format!("stream → editing: stdin closed{save_as}"),
+        );
+    }
+
+    /// The conflict keys as the keymap binds them; unbound commands drop out.
+    fn conflict_hint(&self) -> String {
+        let keys = |commands: &[Command]| {
+            commands
+                .iter()
+                .filter_map(|&command| self.keymap.chords(command).first())
+                .map(Chord::to_string)
+                .collect::<Vec<_>>()
+                .join("/")
+        };
+        [
+            (
+                keys(&[Command::ConflictNextHunk, Command::ConflictPrevHunk]),
+                "hunk",
+            ),
+            (keys(&[Command::ConflictApplyHunk]), "apply"),
+            ("Ctrl+K".to_string(), "keep"),
+            (keys(&[Command::Reload]), "reload"),
+            (keys(&[Command::Merge]), "merge"),
+            (keys(&[Command::ConflictView]), "layout"),
+        ]
+        .into_iter()
+        .filter(|(keys, _)| !keys.is_empty())
+        .map(|(keys, label)| format!("{keys} {label}"))
+        .collect::<Vec<_>>()
+        .join(" | ")
+    }
+
+    fn stream_updated(&mut self, trimmed: bool) {
+        if self.following() {
+            self.scroll_to_end();
+        }
+        if !self.stream_done {
+            let text = if trimmed {
+                "stream update received (trimmed)"
+            } else {
+                "stream update received"
+            };
+            self.push_status(Severity::Info, text);
         }
     }
 
     fn handle_key(&mut self, key: KeyEvent, running: &mut bool) -> Result<()> {
-        if key.code == KeyCode::Tab
-            && key.modifiers == KeyModifiers::NONE
-            && self.ui.focus == PaneFocus::Editor
-            && !self.home_mode
-            && !self.ui.help.open
-            && !self.search_mode
-            && !self.goto_mode
-            && !self.replace_find_mode
-            && !self.replace_with_mode
-            && !self.readonly
-        {
-            let _ = self.replace_selection("    ");
-            if self.selection_anchor.is_none() {
-                self.editor.insert_str("    ");
-                self.sync_conflict_hunk_selection();
+        self.status.acknowledge();
+        if self.message_log {
+            self.message_log = false;
+            if self.keymap.command(key) == Some(Command::Quit) {
+                self.request_quit(running);
+            }
+            return Ok(());
+        }
+        if self.image.shown {
+            self.close_image();
+            return Ok(());
+        }
+        if self.quit_confirm_mode {
+            match (key.code, key.modifiers) {
+                (KeyCode::Char('s' | 'S'), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                    self.quit_confirm_mode = false;
+                    match self.save_to_current_path() {
+                        Ok(()) => self.review_unsaved_files(self.quit_review_next, running),
+                        Err(err) => self.push_status(
+                            Severity::Error,
+                            format!("Save error: {err} (quit cancelled)"),
+                        ),
+                    }
+                }
+                (KeyCode::Char('q' | 'Q'), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                    self.quit_confirm_mode = false;
+                    self.review_unsaved_files(self.quit_review_next, running);
+                }
+                (KeyCode::Esc, _) => {
+                    self.quit_confirm_mode = false;
+                    self.push_status(Severity::Info, "Quit cancelled");
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        let indent = key.code == KeyCode::Tab && key.modifiers == KeyModifiers::NONE;
+        let dedent = key.code == KeyCode::BackTab && self.selection_range().is_some();
+        if (indent || dedent) && self.tab_edits_buffer() {
+            let width = self.indent_width;
+            if dedent {
+                let lines = self.editor.dedent_selection(width);
+                self.push_status(
+                    Severity::Info,
+                    format!("Dedented {lines} line{}", plural(lines)),
+                );
+            } else if self.selection_range().is_some() {
+                let lines = self.editor.indent_selection(&" ".repeat(width));
+                self.push_status(
+                    Severity::Info,
+                    format!("Indented {lines} line{}", plural(lines)),
+                );
+            } else {
+                self.editor.insert_str(&" ".repeat(width));
+                self.push_status(Severity::Info, "Tab inserted");
             }
-            self.status = "Tab inserted".into();
+            self.sync_conflict_hunk_selection();
             self.ensure_cursor_visible();
             return Ok(());
         }
 
-        if let Some(action) = input::map_global_key(key) {
+        if let Some(action) = input::map_global_key(&self.keymap, key) {
             match action {
+                Action::ToggleFocus if self.ui.view_only => {
+                    self.push_status(Severity::Info, "View-only: Ctrl+O to edit");
+                }
                 Action::ToggleFocus => {
+                    // Each pane keeps its own scroll; `Alt+L` lines them up.
                     update::apply_action(&mut self.ui, action, self.term_width);
-                    if self.ui.focus == PaneFocus::Preview {
-                        self.preview_scroll = self.editor_scroll;
-                    }
                     self.clear_selection();
-                    self.status = match self.ui.focus {
-                        PaneFocus::Editor => "Mode: editor".into(),
-                        PaneFocus::Preview => "Mode: view".into(),
+                    // A preview match puts the cursor on the line it came from.
+                    if self.ui.focus == PaneFocus::Editor
+                        && let Some(hit) = self.preview_hit.take()
+                    {
+                        self.editor.goto_line(hit.source_line + 1);
+                        self.ensure_cursor_visible();
+                    }
+                    let mode = match self.ui.focus {
+                        PaneFocus::Editor => "Mode: editor",
+                        PaneFocus::Preview => "Mode: view",
                     };
+                    self.push_status(Severity::Info, mode);
                 }
                 Action::ToggleHelp => {
                     if self.ui.help.open {
                         self.close_docs_modal();
-                        self.status = "Docs closed".into();
+                        self.push_status(Severity::Info, "Docs closed");
                     } else {
                         self.open_docs_modal();
-                        self.status = "Docs opened".into();
+                        self.push_status(Severity::Info, "Docs opened");
+                    }
+                }
+                Action::ToggleViewOnly => {
+                    update::apply_action(&mut self.ui, action, self.term_width);
+                    self.clear_selection();
+                    if self.ui.view_only {
+                        if !self.large_file_mode() {
+                            self.preview_scroll = self.editor_scroll;
+                        }
+                        self.push_status(Severity::Warn, "View-only: editing disabled");
+                    } else {
+                        self.push_status(Severity::Info, "Mode: editor");
+                    }
+                }
+                Action::ToggleMaximize if self.ui.view_only => {
+                    self.push_status(
+                        Severity::Warn,
+                        "View-only: the preview already fills the screen",
+                    );
+                }
+                Action::ToggleMaximize => {
+                    update::apply_action(&mut self.ui, action, self.term_width);
+                    let layout = match (self.ui.maximized, self.ui.focus) {
+                        (true, PaneFocus::Editor) => "Maximized: editor",
+                        (true, PaneFocus::Preview) => "Maximized: preview",
+                        (false, _) => "Panes restored",
+                    };
+                    self.push_status(Severity::Info, layout);
+                }
+                Action::GrowPane | Action::ShrinkPane if self.ui.view_only => {
+                    self.push_status(
+                        Severity::Warn,
+                        "View-only: the preview already fills the screen",
+                    );
+                }
+                Action::GrowPane | Action::ShrinkPane => {
+                    update::apply_action(&mut self.ui, action, self.term_width);
+                    self.prefs.split = Some(self.ui.split_percent);
+                    let label = format!(
+                        "split {}/{}",
+                        self.ui.split_percent,
+                        100 - self.ui.split_percent
+                    );
+                    match self.save_prefs() {
+                        Ok(true) => self.push_status(Severity::Info, format!("{label} (saved)")),
+                        Ok(false) => self.push_status(Severity::Info, label),
+                        Err(err) => self.push_status(
+                            Severity::Error,
+                            format!("{label} (prefs save error: {err})"),
+                        ),
                     }
                 }
-                Action::ApplyPrefs { .. } => {
+                Action::ApplyPrefs { .. }
+                | Action::ToggleLineNumbers
+                | Action::ToggleSoftWrap
+                | Action::ToggleScrollSync => {
                     update::apply_action(&mut self.ui, action, self.term_width);
                 }
             }
@@ -649,12 +1818,14 @@ impl App {
             match (key.code, key.modifiers) {
                 (KeyCode::Esc, _) => {
                     self.close_docs_modal();
-                    self.status = "Docs closed".into();
+                    self.push_status(Severity::Info, "Docs closed");
                 }
                 (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
                     self.close_docs_modal();
                     *running = false;
                 }
+                (KeyCode::Tab, _) if !self.ui.help.is_onboarding() => self.ui.help.switch_tab(),
+                _ if self.ui.help.is_settings() => self.handle_settings_key(key),
                 (KeyCode::Up, _) => apply_help_nav(
                     &mut self.ui.help,
                     HelpNavAction::Up,
@@ -754,20 +1925,22 @@ impl App {
         }
 
         if self.home_mode {
+            let cycle = self.home_completions.take();
             match (key.code, key.modifiers) {
                 (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
                     *running = false;
                 }
+                (KeyCode::Tab, _) => self.complete_home_query(cycle),
                 (KeyCode::Enter, _) => {
                     if self.home_query.trim().is_empty() {
-                        self.status = "Home: type a file name or path".into();
+                        self.push_status(Severity::Info, "Home: type a file name or path");
                     } else {
-                        self.open_home_path(PathBuf::from(self.home_query.trim()));
+                        self.open_home_path(expand_tilde(self.home_query.trim()));
                     }
                 }
                 (KeyCode::Esc, _) => {
                     self.home_query.clear();
-                    self.status = "Home: file field cleared".into();
+                    self.push_status(Severity::Info, "Home: file field cleared");
                 }
                 (KeyCode::Backspace, mods)
                     if mods.contains(KeyModifiers::SUPER)
@@ -790,15 +1963,15 @@ impl App {
                     {
                         self.home_query.pop();
                     }
-                    self.status = format!("Open/create: {}", self.home_query);
+                    self.push_status(Severity::Info, format!("Open/create: {}", self.home_query));
                 }
                 (KeyCode::Backspace, _) => {
                     self.home_query.pop();
-                    self.status = format!("Open/create: {}", self.home_query);
+                    self.push_status(Severity::Info, format!("Open/create: {}", self.home_query));
                 }
                 (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
                     self.home_query.push(c);
-                    self.status = format!("Open/create: {}", self.home_query);
+                    self.push_status(Severity::Info, format!("Open/create: {}", self.home_query));
                 }
                 _ => {}
             }
@@ -809,30 +1982,50 @@ impl App {
             match (key.code, key.modifiers) {
                 (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
                     self.clear_replace_mode();
-                    *running = false;
+                    self.request_quit(running);
                 }
                 (KeyCode::Esc, _) => {
                     self.clear_replace_mode();
-                    self.status = "Replace cancelled".into();
+                    self.push_status(Severity::Info, "Replace cancelled");
                 }
                 (KeyCode::Enter, _) => {
                     if self.replace_find_query.is_empty() {
-                        self.status = "Replace query empty".into();
+                        self.push_status(Severity::Info, "Replace query empty");
                     } else {
                         self.replace_find_mode = false;
                         self.replace_with_mode = true;
                         self.replace_target = std::mem::take(&mut self.replace_find_query);
                         self.replace_with_query.clear();
-                        self.status = "Replace with: ".into();
+                        self.push_status(Severity::Info, "Replace with: ");
                     }
                 }
+                (KeyCode::Char('c'), KeyModifiers::ALT) => {
+                    self.search_options.ignore_case = !self.search_options.ignore_case;
+                    self.push_status(
+                        Severity::Info,
+                        self.search_prompt("Replace find", &self.replace_find_query),
+                    );
+                }
+                (KeyCode::Char('w'), KeyModifiers::ALT) => {
+                    self.search_options.whole_word = !self.search_options.whole_word;
+                    self.push_status(
+                        Severity::Info,
+                        self.search_prompt("Replace find", &self.replace_find_query),
+                    );
+                }
                 (KeyCode::Backspace, _) => {
                     self.replace_find_query.pop();
-                    self.status = format!("Replace find: {}", self.replace_find_query);
+                    self.push_status(
+                        Severity::Info,
+                        self.search_prompt("Replace find", &self.replace_find_query),
+                    );
                 }
                 (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
                     self.replace_find_query.push(c);
-                    self.status = format!("Replace find: {}", self.replace_find_query);
+                    self.push_status(
+                        Severity::Info,
+                        self.search_prompt("Replace find", &self.replace_find_query),
+                    );
                 }
                 _ => {}
             }
@@ -840,29 +2033,51 @@ impl App {
             return Ok(());
         }
 
-        if self.replace_with_mode {
+        if self.replace_with_mode && self.replace_all_pending.is_some() {
             match (key.code, key.modifiers) {
                 (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
                     self.clear_replace_mode();
-                    *running = false;
+                    self.request_quit(running);
                 }
                 (KeyCode::Esc, _) => {
                     self.clear_replace_mode();
-                    self.status = "Replace cancelled".into();
-                }
-                (KeyCode::Enter, _) => {
-                    self.apply_replace_next();
+                    self.push_status(Severity::Info, "Replace all cancelled");
                 }
-                (KeyCode::Char('a'), KeyModifiers::CONTROL) => {
-                    self.apply_replace_all();
-                }
-                (KeyCode::Backspace, _) => {
-                    self.replace_with_query.pop();
-                    self.status = format!("Replace with: {}", self.replace_with_query);
+                (KeyCode::Enter, _) => self.apply_replace_all(),
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        if self.replace_with_mode {
+            match (key.code, key.modifiers) {
+                (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
+                    self.clear_replace_mode();
+                    self.request_quit(running);
+                }
+                (KeyCode::Esc, _) => {
+                    self.clear_replace_mode();
+                    self.push_status(Severity::Info, "Replace cancelled");
+                }
+                (KeyCode::Enter, _) => {
+                    self.apply_replace_next();
+                }
+                (KeyCode::Char('a'), KeyModifiers::CONTROL) => {
+                    self.confirm_replace_all();
+                }
+                (KeyCode::Backspace, _) => {
+                    self.replace_with_query.pop();
+                    self.push_status(
+                        Severity::Info,
+                        format!("Replace with: {}", self.replace_with_query),
+                    );
                 }
                 (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
                     self.replace_with_query.push(c);
-                    self.status = format!("Replace with: {}", self.replace_with_query);
+                    self.push_status(
+                        Severity::Info,
+                        format!("Replace with: {}", self.replace_with_query),
+                    );
                 }
                 _ => {}
             }
@@ -875,34 +2090,56 @@ impl App {
                 (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
                     self.search_mode = false;
                     self.search_query.clear();
-                    *running = false;
+                    self.request_quit(running);
                 }
                 (KeyCode::Esc, _) => {
                     self.search_mode = false;
                     self.search_query.clear();
-                    self.status = "Search cancelled".into();
+                    self.push_status(Severity::Info, "Search cancelled");
                 }
                 (KeyCode::Enter, _) => {
                     self.search_mode = false;
                     let query = std::mem::take(&mut self.search_query);
                     if query.is_empty() {
-                        self.status = "Search query empty".into();
+                        self.push_status(Severity::Info, "Search query empty");
                     } else {
                         self.last_search_query = query.clone();
-                        if self.editor.find_next(&query) {
-                            self.status = format!("Found: {query}");
+                        if self.searches_preview() {
+                            self.find_in_preview(&query, PreviewSearch::FromTop);
+                        } else if self.editor.find_next_with(&query, self.search_options) {
+                            self.push_status(Severity::Info, format!("Found: {query}"));
                         } else {
-                            self.status = format!("Not found: {query}");
+                            self.push_status(Severity::Warn, format!("Not found: {query}"));
                         }
                     }
                 }
+                (KeyCode::Char('c'), KeyModifiers::ALT) => {
+                    self.search_options.ignore_case = !self.search_options.ignore_case;
+                    self.push_status(
+                        Severity::Info,
+                        self.search_prompt("Search", &self.search_query),
+                    );
+                }
+                (KeyCode::Char('w'), KeyModifiers::ALT) => {
+                    self.search_options.whole_word = !self.search_options.whole_word;
+                    self.push_status(
+                        Severity::Info,
+                        self.search_prompt("Search", &self.search_query),
+                    );
+                }
                 (KeyCode::Backspace, _) => {
                     self.search_query.pop();
-                    self.status = format!("Search: {}", self.search_query);
+                    self.push_status(
+                        Severity::Info,
+                        self.search_prompt("Search", &self.search_query),
+                    );
                 }
                 (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
                     self.search_query.push(c);
-                    self.status = format!("Search: {}", self.search_query);
+                    self.push_status(
+                        Severity::Info,
+                        self.search_prompt("Search", &self.search_query),
+                    );
                 }
                 _ => {}
             }
@@ -910,42 +2147,108 @@ impl App {
             return Ok(());
         }
 
+        if self.save_as_mode {
+            match (key.code, key.modifiers) {
+                (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
+                    self.save_as_mode = false;
+                    self.save_as_query.clear();
+                    self.request_quit(running);
+                }
+                (KeyCode::Esc, _) => {
+                    self.save_as_mode = false;
+                    self.save_as_query.clear();
+                    self.push_status(Severity::Info, "Save as cancelled");
+                }
+                (KeyCode::Enter, _) => self.apply_save_as(),
+                (KeyCode::Backspace, _) => {
+                    self.save_as_query.pop();
+                    self.push_status(Severity::Info, format!("Save as: {}", self.save_as_query));
+                }
+                (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                    self.save_as_query.push(c);
+                    self.push_status(Severity::Info, format!("Save as: {}", self.save_as_query));
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        if self.file_switcher.is_some() {
+            self.handle_file_switcher_key(key, running);
+            return Ok(());
+        }
+
+        if self.link_picker.is_some() {
+            self.handle_link_picker_key(key, running);
+            return Ok(());
+        }
+
+        if self.snippet_palette.is_some() {
+            self.handle_snippet_palette_key(key, running);
+            return Ok(());
+        }
+
+        if self.review.is_some() {
+            self.handle_review_key(key, running);
+            return Ok(());
+        }
+
+        if self.outline_mode {
+            let last = self.outline_entries.len().saturating_sub(1);
+            match (key.code, key.modifiers) {
+                (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
+                    self.outline_mode = false;
+                    self.request_quit(running);
+                }
+                (KeyCode::Esc, _) | (KeyCode::Char('t'), KeyModifiers::CONTROL) => {
+                    self.outline_mode = false;
+                    self.push_status(Severity::Info, "Outline closed");
+                }
+                (KeyCode::Up, _) => self.outline_selected = self.outline_selected.saturating_sub(1),
+                (KeyCode::Down, _) => self.outline_selected = (self.outline_selected + 1).min(last),
+                (KeyCode::PageUp, _) => {
+                    self.outline_selected = self.outline_selected.saturating_sub(10);
+                }
+                (KeyCode::PageDown, _) => {
+                    self.outline_selected = (self.outline_selected + 10).min(last);
+                }
+                (KeyCode::Home, _) => self.outline_selected = 0,
+                (KeyCode::End, _) => self.outline_selected = last,
+                (KeyCode::Enter, _) => self.jump_to_outline_entry(),
+                _ => {}
+            }
+            return Ok(());
+        }
+
         if self.goto_mode {
             match (key.code, key.modifiers) {
                 (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
                     self.goto_mode = false;
                     self.goto_query.clear();
-                    *running = false;
+                    self.request_quit(running);
                 }
                 (KeyCode::Esc, _) => {
                     self.goto_mode = false;
                     self.goto_query.clear();
-                    self.status = "Goto cancelled".into();
+                    self.push_status(Severity::Info, "Goto cancelled");
                 }
                 (KeyCode::Enter, _) => {
                     self.goto_mode = false;
                     let query = std::mem::take(&mut self.goto_query);
-                    if query.is_empty() {
-                        self.status = "Goto line empty".into();
-                    } else if let Ok(line_number) = query.parse::<usize>() {
-                        if self.editor.goto_line(line_number) {
-                            self.status = format!("Line {line_number}");
-                        } else {
-                            self.status = format!("Line out of range: {query}");
-                        }
-                    } else {
-                        self.status = format!("Line out of range: {query}");
-                    }
+                    self.run_goto(&query);
                 }
                 (KeyCode::Backspace, _) => {
                     self.goto_query.pop();
-                    self.status = format!("Goto: {}", self.goto_query);
+                    self.push_status(Severity::Info, format!("Goto: {}", self.goto_query));
+                }
+                (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT)
+                    if goto::is_goto_char(c) =>
+                {
+                    self.goto_query.push(c);
+                    self.push_status(Severity::Info, format!("Goto: {}", self.goto_query));
                 }
                 (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
-                    if c.is_ascii_digit() {
-                        self.goto_query.push(c);
-                        self.status = format!("Goto: {}", self.goto_query);
-                    }
+                    self.reject_goto_char(c);
                 }
                 _ => {}
             }
@@ -953,130 +2256,141 @@ impl App {
             return Ok(());
         }
 
+        if self.handle_file_key(key) {
+            return Ok(());
+        }
+
+        if self.ui.view_only {
+            return self.handle_view_only_key(key, running);
+        }
+
+        if let Some(command) = self.keymap.command(key) {
+            self.run_command(command, running)?;
+            self.ensure_cursor_visible();
+            return Ok(());
+        }
+
         match (key.code, key.modifiers) {
-            (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
-                *running = false;
-            }
-            (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
-                if self.readonly {
-                    self.status = "Readonly: save disabled".into();
-                } else if let Some(path) = &self.path {
-                    self.editor.save_to_path(path)?;
-                    self.status = "Saved".into();
-                } else {
-                    self.status = "No path: save disabled".into();
-                }
-            }
-            (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
-                if self.stream_mode {
-                    self.status = "Stream mode: reload disabled".into();
-                } else if self.editor.is_conflicted() {
-                    self.editor.reload_external();
-                    self.sync_conflict_hunk_selection();
-                    self.status = "Reloaded external".into();
-                } else if let Some(path) = &self.path {
-                    let disk = fs::read_to_string(path).unwrap_or_default();
-                    self.editor.on_external_change(disk);
-                    self.sync_conflict_hunk_selection();
-                    self.status = "Reloaded from disk".into();
-                }
-            }
             (KeyCode::Char('k'), KeyModifiers::CONTROL) => {
                 if self.editor.is_conflicted() {
                     self.editor.keep_local();
                     self.sync_conflict_hunk_selection();
-                    self.status = "Kept local".into();
+                    self.push_status(Severity::Info, "Kept local");
                 } else if !self.readonly {
                     if !self.replace_selection("") {
                         self.editor.delete_to_line_end();
                     }
                     self.sync_conflict_hunk_selection();
-                    self.status = "Deleted to line end".into();
+                    self.push_status(Severity::Info, "Deleted to line end");
                 } else {
-                    self.status = "Readonly: edit disabled".into();
+                    self.push_status(Severity::Warn, "Readonly: edit disabled");
                 }
             }
-            (KeyCode::Char('m'), KeyModifiers::CONTROL) => {
-                if self.editor.is_conflicted() {
-                    self.editor.merge_external();
-                    self.sync_conflict_hunk_selection();
-                    self.status = "Merged with conflict markers".into();
+            (KeyCode::Char('d'), KeyModifiers::CONTROL) => {
+                if self.readonly {
+                    self.push_status(Severity::Warn, "Readonly: edit disabled");
                 } else {
-                    self.status = "No conflict to merge".into();
-                }
-            }
-            (KeyCode::Char('f'), KeyModifiers::CONTROL) => {
-                self.search_mode = true;
-                self.goto_mode = false;
-                self.clear_replace_mode();
-                self.search_query.clear();
-                self.status = "Search: ".into();
-            }
-            (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
-                self.search_mode = false;
-                self.goto_mode = false;
-                self.replace_find_mode = true;
-                self.replace_with_mode = false;
-                self.replace_find_query.clear();
-                self.replace_with_query.clear();
-                self.replace_target.clear();
-                self.status = "Replace find: ".into();
-            }
-            (KeyCode::Char('g'), KeyModifiers::CONTROL) => {
-                self.goto_mode = true;
-                self.search_mode = false;
-                self.clear_replace_mode();
-                self.goto_query.clear();
-                self.status = "Goto: ".into();
-            }
-            (KeyCode::Char('z'), KeyModifiers::CONTROL) => {
-                self.clear_selection();
-                if self.editor.undo() {
+                    self.clear_selection();
+                    self.editor.delete_line();
                     self.sync_conflict_hunk_selection();
-                    self.status = "Undo".into();
-                } else {
-                    self.status = "Nothing to undo".into();
+                    self.push_status(Severity::Info, "Deleted line");
                 }
             }
-            (KeyCode::Char('y'), KeyModifiers::CONTROL) => {
-                self.clear_selection();
-                if self.editor.redo() {
-                    self.sync_conflict_hunk_selection();
-                    self.status = "Redo".into();
+            (KeyCode::Char('d'), KeyModifiers::ALT) => {
+                if self.readonly {
+                    self.push_status(Severity::Warn, "Readonly: edit disabled");
                 } else {
-                    self.status = "Nothing to redo".into();
+                    let lines = self.editor.duplicate_line();
+                    self.sync_conflict_hunk_selection();
+                    let text = if lines == 1 {
+                        "Duplicated line".to_string()
+                    } else {
+                        format!("Duplicated {lines} lines")
+                    };
+                    self.push_status(Severity::Info, text);
                 }
             }
-            (KeyCode::Char('z'), KeyModifiers::SUPER | KeyModifiers::SHIFT) => {
-                self.clear_selection();
-                if self.editor.redo() {
-                    self.sync_conflict_hunk_selection();
-                    self.status = "Redo".into();
+            (KeyCode::Char(' '), KeyModifiers::CONTROL) => {
+                if self.readonly {
+                    self.push_status(Severity::Warn, "Readonly: edit disabled");
                 } else {
-                    self.status = "Nothing to redo".into();
+                    match self.editor.toggle_task() {
+                        Some(true) => self.push_status(Severity::Info, "Task checked"),
+                        Some(false) => self.push_status(Severity::Info, "Task unchecked"),
+                        None => self.push_status(Severity::Warn, "Not a task item"),
+                    }
+                    self.sync_conflict_hunk_selection();
                 }
             }
-            (KeyCode::F(3), KeyModifiers::NONE) | (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
-                self.repeat_search_next();
-            }
-            (KeyCode::F(3), KeyModifiers::SHIFT) | (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
-                self.repeat_search_prev();
+            (KeyCode::Char('c' | 'C'), mods)
+                if mods == KeyModifiers::CONTROL
+                    || mods == (KeyModifiers::CONTROL | KeyModifiers::SHIFT) =>
+            {
+                let (entry, label) = match self.editor.copy_selection() {
+                    Some(text) => {
+                        let label = format!("Copied {} chars", text.chars().count());
+                        (ClipboardEntry::Text(text), label)
+                    }
+                    None => (
+                        ClipboardEntry::Line(self.editor.current_line().to_string()),
+                        "Copied line".to_string(),
+                    ),
+                };
+                let note = self.store_clipboard(entry);
+                self.push_status(Severity::Info, format!("{label}{note}"));
             }
-            (KeyCode::Char('j'), KeyModifiers::CONTROL) => {
-                self.move_conflict_hunk(1);
+            (KeyCode::Char('x'), KeyModifiers::CONTROL) => {
+                if self.readonly {
+                    self.push_status(Severity::Warn, "Readonly: edit disabled");
+                } else {
+                    let (entry, label) = match self.editor.cut_selection() {
+                        Some(text) => {
+                            let label = format!("Cut {} chars", text.chars().count());
+                            (ClipboardEntry::Text(text), label)
+                        }
+                        None => (
+                            ClipboardEntry::Line(self.editor.cut_line()),
+                            "Cut line".to_string(),
+                        ),
+                    };
+                    self.sync_conflict_hunk_selection();
+                    let note = self.store_clipboard(entry);
+                    self.push_status(Severity::Info, format!("{label}{note}"));
+                }
             }
-            (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
-                self.move_conflict_hunk(-1);
+            (KeyCode::Char('v'), KeyModifiers::CONTROL) => {
+                if self.readonly {
+                    self.push_status(Severity::Warn, "Readonly: edit disabled");
+                } else {
+                    let (entry, note) = self.load_clipboard();
+                    match entry {
+                        Some(ClipboardEntry::Text(text)) => {
+                            self.editor.paste(&text);
+                            self.sync_conflict_hunk_selection();
+                            self.push_status(Severity::Info, format!("Pasted{note}"));
+                        }
+                        Some(ClipboardEntry::Line(line)) => {
+                            self.clear_selection();
+                            self.editor.paste_line(&line);
+                            self.sync_conflict_hunk_selection();
+                            self.push_status(Severity::Info, format!("Pasted line{note}"));
+                        }
+                        None => self.push_status(Severity::Warn, "Nothing to paste"),
+                    }
+                }
             }
-            (KeyCode::Char('e'), KeyModifiers::CONTROL) => {
-                self.apply_selected_conflict_hunk();
+            (KeyCode::Esc, KeyModifiers::NONE) if self.editor.has_extra_cursors() => {
+                self.editor.collapse_cursors();
             }
+            (KeyCode::Esc, KeyModifiers::NONE) => self.clear_search(),
+            (KeyCode::Char('a'), KeyModifiers::ALT) => self.add_word_to_dictionary(),
+            (KeyCode::Char('r'), KeyModifiers::ALT) => self.restore_before_external(),
             (KeyCode::Char('a'), mods)
                 if (mods.contains(KeyModifiers::CONTROL) || mods.contains(KeyModifiers::SUPER))
                     && !mods.contains(KeyModifiers::ALT)
                     && !mods.contains(KeyModifiers::SHIFT) =>
             {
-                self.selection_anchor = Some(0);
+                self.editor.set_selection_anchor(0);
                 self.editor.move_doc_end();
                 self.update_selection_after_move();
             }
@@ -1164,12 +2478,12 @@ impl App {
             }
             (KeyCode::Up, KeyModifiers::SHIFT) => {
                 self.start_selection();
-                self.editor.move_up();
+                self.move_cursor_visual(false);
                 self.update_selection_after_move();
             }
             (KeyCode::Down, KeyModifiers::SHIFT) => {
                 self.start_selection();
-                self.editor.move_down();
+                self.move_cursor_visual(true);
                 self.update_selection_after_move();
             }
             (KeyCode::Left, mods) if mods == KeyModifiers::ALT || mods == KeyModifiers::CONTROL => {
@@ -1190,7 +2504,35 @@ impl App {
                     self.editor.move_word_right();
                 }
             }
-            (KeyCode::Up, mods) if mods == KeyModifiers::ALT || mods == KeyModifiers::CONTROL => {
+            (KeyCode::Up, mods)
+                if mods == (KeyModifiers::CONTROL | KeyModifiers::ALT) && !self.readonly =>
+            {
+                self.clear_selection();
+                if self.editor.add_cursor_above() {
+                    self.report_cursor_count();
+                }
+            }
+            (KeyCode::Down, mods)
+                if mods == (KeyModifiers::CONTROL | KeyModifiers::ALT) && !self.readonly =>
+            {
+                self.clear_selection();
+                if self.editor.add_cursor_below() {
+                    self.report_cursor_count();
+                }
+            }
+            (KeyCode::Up, KeyModifiers::CONTROL) | (KeyCode::Down, KeyModifiers::CONTROL) => {
+                if self.readonly {
+                    self.push_status(Severity::Warn, "Readonly: edit disabled");
+                } else {
+                    if key.code == KeyCode::Up {
+                        self.editor.move_line_up();
+                    } else {
+                        self.editor.move_line_down();
+                    }
+                    self.sync_conflict_hunk_selection();
+                }
+            }
+            (KeyCode::Up, KeyModifiers::ALT) => {
                 if let Some((start, _)) = self.selection_range() {
                     self.editor.set_cursor(start);
                     self.clear_selection();
@@ -1198,7 +2540,7 @@ impl App {
                     self.editor.move_paragraph_up();
                 }
             }
-            (KeyCode::Down, mods) if mods == KeyModifiers::ALT || mods == KeyModifiers::CONTROL => {
+            (KeyCode::Down, KeyModifiers::ALT) => {
                 if let Some((_, end)) = self.selection_range() {
                     self.editor.set_cursor(end);
                     self.clear_selection();
@@ -1219,7 +2561,7 @@ impl App {
                     self.editor.set_cursor(start);
                     self.clear_selection();
                 } else {
-                    self.editor.move_line_start();
+                    self.editor.move_line_start_smart();
                 }
             }
             (KeyCode::Right, mods) if mods == KeyModifiers::SUPER => {
@@ -1239,10 +2581,12 @@ impl App {
                 }
             }
             (KeyCode::Up, mods) if mods == KeyModifiers::SUPER => {
+                self.pause_follow();
                 self.clear_selection();
                 self.editor.move_doc_start();
             }
             (KeyCode::Home, mods) if mods == KeyModifiers::CONTROL => {
+                self.pause_follow();
                 self.clear_selection();
                 self.editor.move_doc_start();
             }
@@ -1250,10 +2594,6 @@ impl App {
                 self.clear_selection();
                 self.editor.move_doc_end();
             }
-            (KeyCode::End, mods) if mods == KeyModifiers::CONTROL => {
-                self.clear_selection();
-                self.editor.move_doc_end();
-            }
             (KeyCode::Left, _) => {
                 if let Some((start, _)) = self.selection_range() {
                     self.editor.set_cursor(start);
@@ -1271,11 +2611,12 @@ impl App {
                 }
             }
             (KeyCode::Up, _) => {
+                self.pause_follow();
                 if let Some((start, _)) = self.selection_range() {
                     self.editor.set_cursor(start);
                     self.clear_selection();
                 } else {
-                    self.editor.move_up();
+                    self.move_cursor_visual(false);
                 }
             }
             (KeyCode::Down, _) => {
@@ -1283,87 +2624,83 @@ impl App {
                     self.editor.set_cursor(end);
                     self.clear_selection();
                 } else {
-                    self.editor.move_down();
+                    self.move_cursor_visual(true);
                 }
             }
             (KeyCode::PageUp, _) => {
+                self.pause_follow();
                 if self.ui.focus == PaneFocus::Editor {
-                    self.editor_scroll =
-                        self.editor_scroll.saturating_sub(self.editor_height.max(1));
+                    self.set_editor_scroll(
+                        self.editor_scroll.saturating_sub(self.editor_height.max(1)),
+                    );
                 } else {
                     self.preview_scroll = self
                         .preview_scroll
                         .saturating_sub(self.preview_height.max(1));
+                    self.slide_large_preview();
                 }
             }
             (KeyCode::PageDown, _) => {
                 if self.ui.focus == PaneFocus::Editor {
-                    self.editor_scroll += self.editor_height.max(1);
+                    self.set_editor_scroll(self.editor_scroll + self.editor_height.max(1));
                 } else {
                     self.preview_scroll += self.preview_height.max(1);
+                    self.slide_large_preview();
                 }
             }
-            (KeyCode::Enter, _) => {
-                if !self.readonly {
-                    if !self.replace_selection("\n") {
+            (KeyCode::Enter, _) if !self.readonly => {
+                if !self.replace_selection("\n") {
+                    if self.continue_lists {
+                        self.editor.insert_newline_smart();
+                    } else {
                         self.editor.insert_newline();
                     }
-                    self.sync_conflict_hunk_selection();
                 }
+                self.sync_conflict_hunk_selection();
             }
             (KeyCode::Delete, mods)
-                if mods.contains(KeyModifiers::SUPER)
+                if (mods.contains(KeyModifiers::SUPER)
                     || mods.contains(KeyModifiers::ALT)
-                    || mods.contains(KeyModifiers::CONTROL) =>
+                    || mods.contains(KeyModifiers::CONTROL))
+                    && !self.readonly =>
             {
-                if !self.readonly {
-                    if !self.replace_selection("") {
-                        self.editor.delete_word_forward();
-                    }
-                    self.sync_conflict_hunk_selection();
+                if !self.replace_selection("") {
+                    self.editor.delete_word_forward();
                 }
+                self.sync_conflict_hunk_selection();
             }
-            (KeyCode::Delete, _) => {
-                if !self.readonly {
-                    if !self.replace_selection("") {
-                        self.editor.delete_forward();
-                    }
-                    self.sync_conflict_hunk_selection();
+            (KeyCode::Delete, _) if !self.readonly => {
+                if !self.replace_selection("") {
+                    self.editor.delete_forward();
                 }
+                self.sync_conflict_hunk_selection();
             }
-            (KeyCode::Backspace, mods) if mods.contains(KeyModifiers::SUPER) => {
-                if !self.readonly {
-                    if !self.replace_selection("") {
-                        self.editor.delete_to_line_start();
-                    }
-                    self.sync_conflict_hunk_selection();
+            (KeyCode::Backspace, mods) if mods.contains(KeyModifiers::SUPER) && !self.readonly => {
+                if !self.replace_selection("") {
+                    self.editor.delete_to_line_start();
                 }
+                self.sync_conflict_hunk_selection();
             }
             (KeyCode::Backspace, mods)
-                if mods.contains(KeyModifiers::ALT) || mods.contains(KeyModifiers::CONTROL) =>
+                if (mods.contains(KeyModifiers::ALT) || mods.contains(KeyModifiers::CONTROL))
+                    && !self.readonly =>
             {
-                if !self.readonly {
-                    if !self.replace_selection("") {
-                        self.editor.delete_word_back();
-                    }
-                    self.sync_conflict_hunk_selection();
+                if !self.replace_selection("") {
+                    self.editor.delete_word_back();
                 }
+                self.sync_conflict_hunk_selection();
             }
-            (KeyCode::Backspace, _) => {
-                if !self.readonly {
-                    if !self.replace_selection("") {
-                        self.editor.backspace();
-                    }
-                    self.sync_conflict_hunk_selection();
+            (KeyCode::Backspace, _) if !self.readonly => {
+                if !self.replace_selection("") {
+                    self.editor.backspace();
                 }
+                self.sync_conflict_hunk_selection();
             }
-            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
-                if !self.readonly {
-                    if !self.replace_selection(&c.to_string()) {
-                        self.editor.insert_char(c);
-                    }
-                    self.sync_conflict_hunk_selection();
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) if !self.readonly => {
+                if !self.replace_selection(&c.to_string()) {
+                    self.editor.insert_char(c);
                 }
+                self.sync_conflict_hunk_selection();
             }
             _ => {}
         }
@@ -1372,2473 +2709,6531 @@ impl App {
         Ok(())
     }
 
-    fn next_input_event(&mut self) -> Result<Option<InputEvent>> {
-        #[cfg(test)]
+    /// Reader keys: scrolling and search move the preview, editing is ignored.
+    fn handle_view_only_key(&mut self, key: KeyEvent, running: &mut bool) -> Result<()> {
+        if let Some(command) = self
+            .keymap
+            .command(key)
+            .filter(|command| command.reads_only())
         {
-            if let Some(result) = self.test_next_key_result.take() {
-                return result
-                    .map(|event| event.map(InputEvent::Key))
-                    .map_err(Into::into);
+            return self.run_command(command, running);
+        }
+        let page = self.preview_height.max(1);
+        match (key.code, key.modifiers) {
+            (KeyCode::Esc, KeyModifiers::NONE) => self.clear_search(),
+            (KeyCode::Up, _) => {
+                self.pause_follow();
+                self.preview_scroll = self.preview_scroll.saturating_sub(1);
             }
-            if let Some(key) = self.test_next_key.take() {
-                return Ok(Some(InputEvent::Key(key)));
+            (KeyCode::Down, _) => self.preview_scroll = self.preview_scroll.saturating_add(1),
+            (KeyCode::PageUp, _) => {
+                self.pause_follow();
+                self.preview_scroll = self.preview_scroll.saturating_sub(page);
             }
+            (KeyCode::PageDown, _) => {
+                self.preview_scroll = self.preview_scroll.saturating_add(page);
+            }
+            (KeyCode::Home, _) => {
+                self.pause_follow();
+                self.preview_source_top = 0;
+                self.preview_scroll = 0;
+            }
+            // Clamped to the last page on the next draw.
+            (KeyCode::End, _) => {
+                self.preview_source_top = self.editor.line_count();
+                self.preview_scroll = usize::MAX;
+            }
+            _ => {}
         }
-        next_terminal_input(event::poll, event::read)
-    }
-
-    fn ensure_cursor_visible(&mut self) {
-        let (cursor_line, _) = self.editor.line_col_at_cursor();
-        if cursor_line < self.editor_scroll {
-            self.editor_scroll = cursor_line;
-        } else if cursor_line >= self.editor_scroll + self.editor_height {
-            self.editor_scroll = cursor_line.saturating_sub(self.editor_height.saturating_sub(1));
-        }
-    }
-
-    fn start_selection(&mut self) {
-        if self.selection_anchor.is_none() {
-            self.selection_anchor = Some(self.editor.cursor());
-        }
-    }
-
-    fn update_selection_after_move(&mut self) {
-        if self.selection_range().is_none() {
-            self.selection_anchor = None;
-        }
-    }
-
-    fn clear_selection(&mut self) {
-        self.selection_anchor = None;
-    }
-
-    fn selection_range(&self) -> Option<(usize, usize)> {
-        let anchor = self.selection_anchor?;
-        let cursor = self.editor.cursor();
-        if anchor == cursor {
-            None
-        } else if anchor < cursor {
-            Some((anchor, cursor))
-        } else {
-            Some((cursor, anchor))
-        }
-    }
-
-    fn replace_selection(&mut self, replacement: &str) -> bool {
-        let Some((start, end)) = self.selection_range() else {
-            return false;
-        };
-        let changed = self.editor.replace_range(start, end, replacement);
-        self.clear_selection();
-        changed
+        self.slide_large_preview();
+        Ok(())
     }
 
-    fn open_home_path(&mut self, path: PathBuf) {
-        let existed = path.exists();
-        let text = match fs::read_to_string(&path) {
-            Ok(text) => text,
-            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
-            Err(err) => {
-                self.status = format!("open error: {err}");
-                return;
-            }
-        };
-
-        if self.watch_enabled && existed {
-            match watcher::start(&path) {
-                Ok((watcher, rx)) => {
-                    self._watcher = Some(watcher);
-                    self.watch_rx = Some(rx);
+    /// Run what a key bound in the [`KeyMap`] stands for, in the editor or,
+    /// for [`Command::reads_only`] ones, in view-only mode.
+    fn run_command(&mut self, command: Command, running: &mut bool) -> Result<()> {
+        match command {
+            Command::Quit => self.request_quit(running),
+            Command::SaveAs => self.open_save_as_prompt(),
+            Command::Save => {
+                if self.readonly && !self.stream_mode {
+                    self.push_status(Severity::Warn, "Readonly: save disabled");
+                } else if self.path.is_none() {
+                    self.open_save_as_prompt();
+                } else if self.stream_mode {
+                    self.push_status(Severity::Warn, "Stream mode: use Ctrl+Shift+S to save as");
+                } else {
+                    self.save_to_current_path()?;
                 }
-                Err(err) => {
-                    self._watcher = None;
-                    self.watch_rx = None;
-                    self.status = format!("watch error: {err}");
+            }
+            Command::Reload => {
+                if self.stream_mode {
+                    self.push_status(Severity::Warn, "Stream mode: reload disabled");
+                } else if let Some(conflict) = self.editor.conflict() {
+                    let backup = self.backup_before_reload(&conflict.external);
+                    self.editor.reload_external();
+                    self.sync_conflict_hunk_selection();
+                    self.push_reload_status("Reloaded external", backup);
+                } else if let Some(path) = &self.path {
+                    // A failed read is not an empty file: keep the buffer.
+                    match read_text(path) {
+                        Ok(file) => {
+                            self.file_missing = false;
+                            // With local edits this opens a conflict instead.
+                            let backup = (!self.editor.dirty)
+                                .then(|| self.backup_before_reload(&file.text))
+                                .flatten();
+                            self.editor.on_external_change(file.text);
+                            self.sync_conflict_hunk_selection();
+                            self.sync_link_watches();
+                            self.refresh_file_info();
+                            self.push_reload_status("Reloaded from disk", backup);
+                        }
+                        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                            self.file_missing = true;
+                            self.push_status(
+                                Severity::Warn,
+                                format!("Not reloaded: {FILE_MISSING_STATUS}"),
+                            );
+                        }
+                        Err(err) => {
+                            self.push_status(
+                                Severity::Warn,
+                                format!("Not reloaded ({err}); buffer kept"),
+                            );
+                        }
+                    }
                 }
             }
-        } else {
-            self._watcher = None;
-            self.watch_rx = None;
-        }
-
-        self.path = Some(path.clone());
-        self.editor = EditorBuffer::new(text);
-        self.preview_cache = None;
-        self.home_mode = false;
-        self.clear_selection();
-        self.search_mode = false;
-        self.goto_mode = false;
-        self.clear_replace_mode();
-        self.home_query.clear();
-        self.editor_scroll = 0;
-        self.preview_scroll = 0;
-        self.sync_conflict_hunk_selection();
-        self.status = if existed {
-            format!("Opened {}", path.display())
-        } else {
-            format!("New file {}", path.display())
-        };
-    }
-
-    fn scroll_active_viewport(&mut self, direction: i8) {
-        if self.ui.focus == PaneFocus::Editor {
-            self.scroll_editor(direction);
-            return;
+            Command::Search => self.open_search_prompt(),
+            Command::Replace => {
+                self.editor.collapse_cursors();
+                self.search_mode = false;
+                self.goto_mode = false;
+                self.replace_find_mode = true;
+                self.replace_with_mode = false;
+                self.replace_find_query.clear();
+                self.replace_with_query.clear();
+                self.replace_target.clear();
+                self.push_status(Severity::Info, self.search_prompt("Replace find", ""));
+            }
+            Command::Goto => {
+                self.goto_mode = true;
+                self.search_mode = false;
+                self.clear_replace_mode();
+                self.goto_query.clear();
+                self.push_status(Severity::Info, "Goto: ");
+            }
+            Command::SearchNext => self.repeat_search_next(),
+            Command::SearchPrev => self.repeat_search_prev(),
+            Command::Undo => {
+                self.clear_selection();
+                if self.editor.undo() {
+                    self.sync_conflict_hunk_selection();
+                    self.push_status(Severity::Info, format!("Undo | {}", self.history_depth()));
+                } else {
+                    self.push_status(Severity::Warn, "Nothing to undo");
+                }
+            }
+            Command::Redo => {
+                self.clear_selection();
+                if self.editor.redo() {
+                    self.sync_conflict_hunk_selection();
+                    self.push_status(Severity::Info, format!("Redo | {}", self.history_depth()));
+                } else {
+                    self.push_status(Severity::Warn, "Nothing to redo");
+                }
+            }
+            Command::Outline => self.open_outline(),
+            Command::NextHeading => self.jump_to_heading(true),
+            Command::PrevHeading => self.jump_to_heading(false),
+            Command::OpenLink => self.open_link_near(),
+            Command::OpenChangedLink => self.open_changed_link(),
+            Command::GotoDefinition => self.goto_definition(),
+            Command::JumpBack => self.jump_back(),
+            Command::ShowImage => self.show_image_near(),
+            Command::NextMisspelling => self.next_misspelling(),
+            Command::Follow => self.toggle_follow(),
+            Command::SyncPanes => self.sync_panes(),
+            Command::Snippets => self.open_snippet_palette(),
+            Command::Format => self.start_format(),
+            Command::RenumberList => self.renumber_list(),
+            Command::Review => self.open_review(),
+            // Without a conflict to lay out, the key shows the message log.
+            Command::MessageLog => self.open_message_log(),
+            Command::ConflictView => self.toggle_conflict_view(),
+            Command::ConflictNextHunk => self.move_conflict_hunk(1),
+            Command::ConflictPrevHunk => self.move_conflict_hunk(-1),
+            Command::ConflictApplyHunk => self.apply_selected_conflict_hunk(),
+            Command::Merge => {
+                if self.editor.is_conflicted() {
+                    self.editor.merge_external();
+                    self.sync_conflict_hunk_selection();
+                    self.push_status(Severity::Warn, "Merged with conflict markers");
+                } else {
+                    self.push_status(Severity::Warn, "No conflict to merge");
+                }
+            }
+            Command::ExportMerged => self.export_merged(),
+            // Layout and file keys are handled before the editor sees them.
+            Command::ToggleFocus
+            | Command::ToggleViewOnly
+            | Command::ToggleMaximize
+            | Command::GrowPane
+            | Command::ShrinkPane
+            | Command::ToggleHelp
+            | Command::NextFile
+            | Command::PrevFile
+            | Command::CloseFile
+            | Command::FileSwitcher => {}
         }
-        self.scroll_preview(direction);
+        Ok(())
     }
 
-    fn scroll_viewport_at(&mut self, column: u16, row: u16, direction: i8) {
-        if point_in_rect(column, row, self.editor_area) {
-            self.scroll_editor(direction);
-            return;
-        }
-        if point_in_rect(column, row, self.preview_area) {
-            self.scroll_preview(direction);
-            return;
+    /// Source line of the top preview row as of the last draw.
+    fn preview_top_source_line(&self) -> usize {
+        if self.large_file_mode() {
+            return self.preview_source_top;
         }
-        self.scroll_active_viewport(direction);
+        self.preview_cache.as_ref().map_or(0, |cache| {
+            cache
+                .lines
+                .get(self.preview_scroll)
+                .or(cache.lines.last())
+                .map_or(0, PreviewLineKind::source_line)
+        })
     }
 
-    fn scroll_editor(&mut self, direction: i8) {
-        let amount = SCROLL_STEP_LINES;
-        let total = to_lines(self.editor.text()).len();
-        if direction < 0 {
-            self.editor_scroll = self.editor_scroll.saturating_sub(amount);
+    /// `Alt+N`/`Alt+P`: the next or previous heading, for the cursor while
+    /// typing, otherwise for the top of the preview.
+    fn jump_to_heading(&mut self, forward: bool) {
+        let in_preview = self.ui.focus == PaneFocus::Preview || self.ui.view_only;
+        let from = if in_preview {
+            self.preview_top_source_line()
         } else {
-            self.editor_scroll = self.editor_scroll.saturating_add(amount);
-        }
-        self.editor_scroll = clamp_scroll(self.editor_scroll, total, self.editor_height.max(1));
-    }
-
-    fn scroll_preview(&mut self, direction: i8) {
-        let amount = SCROLL_STEP_LINES;
-        let preview_width = self.preview_area.width.saturating_sub(2).max(1);
-        let (preview_lines, _) = self.preview_lines_cached(preview_width);
-        if direction < 0 {
-            self.preview_scroll = self.preview_scroll.saturating_sub(amount);
+            self.editor.line_col_at_cursor().0
+        };
+        let target = if forward {
+            self.editor.find_next_heading(from)
         } else {
-            self.preview_scroll = self.preview_scroll.saturating_add(amount);
+            self.editor.find_prev_heading(from)
+        };
+        let Some(line) = target else {
+            let direction = if forward { "below" } else { "above" };
+            self.push_status(Severity::Warn, format!("No heading {direction}"));
+            return;
+        };
+        if in_preview {
+            self.pause_follow();
+            self.preview_scroll = self.preview_line_for_source(line);
+        } else {
+            self.clear_selection();
+            self.editor.goto_line(line + 1);
         }
-        self.preview_scroll = clamp_scroll(
-            self.preview_scroll,
-            preview_lines.len(),
-            self.preview_height.max(1),
+        self.push_status(
+            Severity::Info,
+            format!("Heading: {}", self.editor.line_text(line).trim()),
         );
     }
 
-    fn handle_mouse_down(&mut self, column: u16, row: u16, modifiers: KeyModifiers) {
-        if self.home_mode || self.ui.help.open {
+    /// `Ctrl+Alt+F`: pipe the text through the formatter on a worker thread;
+    /// [`handle_format_result`](Self::handle_format_result) applies it.
+    /// `Ctrl+Alt+L`: number the ordered list around the cursor again, as
+    /// one undo step.
+    fn renumber_list(&mut self) {
+        if self.readonly {
+            self.push_status(Severity::Warn, "Readonly: edit disabled");
             return;
         }
-        if point_in_rect(column, row, self.preview_area) {
-            self.ui.focus = PaneFocus::Preview;
-        }
-        if point_in_rect(column, row, self.editor_area) {
-            self.ui.focus = PaneFocus::Editor;
+        let (line, col) = self.editor.line_col_at_cursor();
+        let Some((range, list)) = renumber_ordered_list(self.editor.text(), line) else {
+            self.push_status(Severity::Warn, "Not in an ordered list");
+            return;
+        };
+        if self.editor.text()[range.clone()] == list {
+            self.push_status(Severity::Info, "List already numbered");
+            return;
         }
-        if self.ui.focus != PaneFocus::Editor {
+        self.clear_selection();
+        self.editor.replace_range(range.start, range.end, &list);
+        self.editor.set_cursor_line_col(line, col);
+        self.sync_conflict_hunk_selection();
+        self.push_status(Severity::Info, "Renumbered list");
+    }
+
+    fn start_format(&mut self) {
+        if self.readonly {
+            self.push_status(Severity::Warn, "Readonly: formatting disabled");
             return;
         }
-        if !point_in_rect(column, row, self.editor_text_area) {
+        if self.editor.is_conflicted() {
+            self.push_status(Severity::Warn, "Resolve the conflict before formatting");
             return;
         }
-
-        let prior_cursor = self.editor.cursor();
-        if !self.set_editor_cursor_from_mouse(column, row) {
+        let Some(command) = &self.formatter else {
+            self.push_status(
+                Severity::Warn,
+                "No formatter: set --formatter or [editor] formatter",
+            );
+            return;
+        };
+        if self.format_job.is_some() {
+            self.push_status(Severity::Warn, "Already formatting");
             return;
         }
+        self.format_job = Some(FormatJob {
+            revision: self.editor.revision(),
+            rx: formatter::start(
+                command,
+                self.editor.text().to_string(),
+                formatter::FORMAT_TIMEOUT,
+            ),
+        });
+        self.push_status(Severity::Info, "Formatting...");
+    }
 
-        if modifiers.contains(KeyModifiers::SHIFT) {
-            if self.selection_anchor.is_none() {
-                self.selection_anchor = Some(prior_cursor);
+    /// Swap in the formatted text as one undo step, unless the text changed
+    /// while the formatter ran. Failures leave the text alone.
+    fn handle_format_result(&mut self) {
+        let Some(job) = &self.format_job else {
+            return;
+        };
+        let result = match job.rx.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => Err("the formatter thread stopped".into()),
+        };
+        let revision = job.revision;
+        self.format_job = None;
+        let text = match result {
+            Ok(text) => text,
+            Err(err) => {
+                self.push_status(Severity::Error, format!("Format error: {err}"));
+                return;
             }
+        };
+        if self.editor.revision() != revision {
+            self.push_status(
+                Severity::Info,
+                "Format skipped: the text changed while formatting",
+            );
+        } else if text == self.editor.text() {
+            self.push_status(Severity::Info, "Formatted: no changes");
         } else {
+            let (line, col) = self.editor.line_col_at_cursor();
             self.clear_selection();
+            self.editor
+                .replace_range(0, self.editor.text().len(), &text);
+            // Past the new end this lands on the last line.
+            self.editor.set_cursor_line_col(line, col);
+            self.ensure_cursor_visible();
+            self.push_status(Severity::Info, "Formatted");
         }
-
-        self.mouse_drag_anchor = Some(self.selection_anchor.unwrap_or(self.editor.cursor()));
-        self.update_selection_after_move();
-        self.ensure_cursor_visible();
     }
 
-    fn handle_mouse_drag(&mut self, column: u16, row: u16) {
-        if self.ui.focus != PaneFocus::Editor || self.home_mode || self.ui.help.open {
+    fn open_outline(&mut self) {
+        self.outline_entries = extract_outline(self.editor.text());
+        if self.outline_entries.is_empty() {
+            self.push_status(Severity::Warn, "No headings");
             return;
         }
-        let Some(anchor) = self.mouse_drag_anchor else {
+        // Start on the heading that contains the cursor.
+        let (line, _) = self.editor.line_col_at_cursor();
+        self.outline_selected = self
+            .outline_entries
+            .iter()
+            .rposition(|entry| entry.source_line <= line)
+            .unwrap_or(0);
+        self.outline_mode = true;
+        self.push_status(
+            Severity::Info,
+            format!("Outline: {} headings", self.outline_entries.len()),
+        );
+    }
+
+    fn jump_to_outline_entry(&mut self) {
+        self.outline_mode = false;
+        let Some(entry) = self.outline_entries.get(self.outline_selected).cloned() else {
             return;
         };
-        if !self.set_editor_cursor_from_mouse(column, row) {
-            return;
-        }
-        self.selection_anchor = Some(anchor);
-        self.update_selection_after_move();
+        self.clear_selection();
+        self.editor.goto_line(entry.source_line + 1);
         self.ensure_cursor_visible();
+        self.preview_scroll = self.preview_line_for_source(entry.source_line);
+        self.push_status(Severity::Info, format!("Jumped to: {}", entry.text));
+    }
+
+    /// `Alt+L`: scroll the preview to the block under the editor cursor.
+    fn sync_panes(&mut self) {
+        let (line, _) = self.editor.line_col_at_cursor();
+        self.pause_follow();
+        self.preview_scroll = self.preview_line_for_source(line);
+        self.push_status(
+            Severity::Info,
+            format!("Preview synced to line {}", line + 1),
+        );
     }
 
-    fn set_editor_cursor_from_mouse(&mut self, column: u16, row: u16) -> bool {
-        if !point_in_rect(column, row, self.editor_text_area) {
-            return false;
-        }
-        let rel_line = row.saturating_sub(self.editor_text_area.y) as usize;
-        let rel_col = column.saturating_sub(self.editor_text_area.x) as usize;
-        let line = self.editor_scroll.saturating_add(rel_line);
-        self.editor.set_cursor_line_col(line, rel_col);
-        true
+    /// Rendered preview row where the block starting at `source_line` begins.
+    fn preview_line_for_source(&mut self, source_line: usize) -> usize {
+        let width = if self.preview_area.width > 2 {
+            self.preview_area.width - 2
+        } else {
+            self.term_width.max(1)
+        };
+        let window_offset = self.large_file_mode().then(|| {
+            self.preview_source_top = source_line;
+            self.large_preview_offset(source_line, width)
+        });
+        let lines = self.preview_lines_cached(width);
+        let before = window_offset.unwrap_or_else(|| {
+            lines
+                .iter()
+                .position(|line| line.source_line() >= source_line)
+                .unwrap_or(lines.len())
+        });
+        lines
+            .iter()
+            .skip(before)
+            .position(|line| !line.text().trim().is_empty())
+            .map_or(before, |idx| before + idx)
     }
 
-    fn clear_replace_mode(&mut self) {
-        self.replace_find_mode = false;
-        self.replace_with_mode = false;
-        self.replace_find_query.clear();
-        self.replace_with_query.clear();
-        self.replace_target.clear();
+    /// Say how many cursors typing now goes to after `Ctrl+Alt+Up`/`Down`.
+    fn report_cursor_count(&mut self) {
+        let count = self.editor.extra_cursors().len() + 1;
+        self.push_status(Severity::Info, format!("{count} cursors (Esc keeps one)"));
     }
 
-    fn apply_replace_next(&mut self) {
-        let find = self.replace_target.clone();
-        let replacement = std::mem::take(&mut self.replace_with_query);
+    fn open_search_prompt(&mut self) {
+        self.editor.collapse_cursors();
+        self.search_mode = true;
+        self.goto_mode = false;
         self.clear_replace_mode();
+        self.search_query.clear();
+        self.push_status(Severity::Info, self.search_prompt("Search", ""));
+    }
 
-        if find.is_empty() {
-            self.status = "Replace query empty".into();
-            return;
-        }
-        if self.readonly {
-            self.status = "Readonly: replace disabled".into();
-            return;
-        }
+    /// `Ctrl+F` and `F3` go through the rendered text while the preview
+    /// has focus, so they find what is shown rather than what is typed.
+    fn searches_preview(&self) -> bool {
+        self.ui.view_only || self.ui.focus == PaneFocus::Preview
+    }
 
-        if self.editor.replace_next(&find, &replacement) {
-            self.last_search_query = find.clone();
-            self.sync_conflict_hunk_selection();
-            self.status = format!("Replaced: {find} -> {replacement}");
+    /// Scroll the preview to the next match of `query` in the rendered
+    /// text, wrapping.
+    fn find_in_preview(&mut self, query: &str, direction: PreviewSearch) -> bool {
+        let width = if self.preview_area.width > 2 {
+            self.preview_area.width - 2
         } else {
-            self.status = format!("Not found: {find}");
+            self.term_width.max(1)
+        };
+        let lines = self.preview_lines_cached(width);
+        let options = self.search_options;
+        let hits: Vec<PreviewHit> = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(row, line)| {
+                let source_line = line.source_line();
+                match_ranges_in(&line.text(), query, options)
+                    .into_iter()
+                    .map(move |(start, _)| PreviewHit {
+                        row,
+                        start,
+                        source_line,
+                    })
+            })
+            .collect();
+        if hits.is_empty() {
+            self.preview_hit = None;
+            self.push_status(Severity::Warn, format!("Not found: {query}"));
+            return false;
         }
-    }
 
-    fn apply_replace_all(&mut self) {
-        let find = self.replace_target.clone();
-        let replacement = std::mem::take(&mut self.replace_with_query);
-        self.clear_replace_mode();
+        let at = |hit: &PreviewHit| (hit.row, hit.start);
+        let from = self
+            .preview_hit
+            .as_ref()
+            .map_or((self.preview_scroll, 0), at);
+        let index = match direction {
+            PreviewSearch::FromTop => 0,
+            PreviewSearch::Next => hits.iter().position(|hit| at(hit) > from).unwrap_or(0),
+            PreviewSearch::Prev => hits
+                .iter()
+                .rposition(|hit| at(hit) < from)
+                .unwrap_or(hits.len() - 1),
+        };
+        let hit = hits[index];
+        self.preview_hit = Some(hit);
+        self.preview_scroll = hit.row;
+        self.push_status(
+            Severity::Info,
+            format!("preview match {}/{}: {query}", index + 1, hits.len()),
+        );
+        true
+    }
 
-        if find.is_empty() {
-            self.status = "Replace query empty".into();
+    fn handle_paste(&mut self, text: &str) {
+        if self.home_mode
+            || self.ui.help.open
+            || self.ui.focus != PaneFocus::Editor
+            || self.search_mode
+            || self.goto_mode
+            || self.save_as_mode
+            || self.replace_find_mode
+            || self.replace_with_mode
+        {
             return;
         }
         if self.readonly {
-            self.status = "Readonly: replace disabled".into();
+            self.push_status(Severity::Warn, "Readonly: edit disabled");
             return;
         }
 
-        let count = self.editor.replace_all(&find, &replacement);
-        if count > 0 {
-            self.last_search_query = find.clone();
-            self.sync_conflict_hunk_selection();
-            self.status = format!("Replaced all {count}: {find} -> {replacement}");
-        } else {
-            self.status = format!("Not found: {find}");
-        }
-    }
-
-    fn repeat_search_next(&mut self) {
-        if self.last_search_query.is_empty() {
-            self.status = "No prior search".into();
+        // Terminals deliver pasted line breaks as CR.
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        if text.is_empty() {
             return;
         }
+        self.editor.paste(&text);
+        self.sync_conflict_hunk_selection();
+        self.push_status(
+            Severity::Info,
+            format!("Pasted {} chars", text.chars().count()),
+        );
+        self.ensure_cursor_visible();
+    }
 
-        if self.editor.find_next(&self.last_search_query) {
-            self.status = format!("Found next: {}", self.last_search_query);
-            self.ensure_cursor_visible();
-        } else {
-            self.status = format!("Not found: {}", self.last_search_query);
+    fn next_input_event(&mut self) -> Result<Option<InputEvent>> {
+        #[cfg(test)]
+        {
+            if let Some(result) = self.test_next_key_result.take() {
+                return result
+                    .map(|event| event.map(InputEvent::Key))
+                    .map_err(Into::into);
+            }
+            if let Some(key) = self.test_next_key.take() {
+                return Ok(Some(InputEvent::Key(key)));
+            }
         }
+        next_terminal_input(event::poll, event::read)
     }
 
-    fn repeat_search_prev(&mut self) {
-        if self.last_search_query.is_empty() {
-            self.status = "No prior search".into();
+    fn ensure_cursor_visible(&mut self) {
+        if let Some(width) = self.wrap_width() {
+            self.ensure_cursor_visible_wrapped(width);
             return;
         }
+        let (cursor_line, _) = self.editor.line_col_at_cursor();
+        if cursor_line < self.editor_scroll {
+            self.editor_scroll = cursor_line;
+        } else if cursor_line >= self.editor_scroll + self.editor_height {
+            self.editor_scroll = cursor_line.saturating_sub(self.editor_height.saturating_sub(1));
+        }
 
-        if self.editor.find_prev(&self.last_search_query) {
-            self.status = format!("Found previous: {}", self.last_search_query);
-            self.ensure_cursor_visible();
-        } else {
-            self.status = format!("Not found: {}", self.last_search_query);
+        let width = self.editor_text_area.width as usize;
+        if width == 0 {
+            return;
+        }
+        let column = self.editor.display_col_at_cursor();
+        if column < self.editor_hscroll {
+            self.editor_hscroll = column;
+        } else if column >= self.editor_hscroll + width {
+            self.editor_hscroll = column + 1 - width;
         }
     }
 
-    fn move_conflict_hunk(&mut self, direction: i32) {
-        let Some(conflict) = self.editor.conflict() else {
-            self.status = "No conflict hunks".into();
-            return;
-        };
-        if conflict.hunks.is_empty() {
-            self.status = "No conflict hunks".into();
-            return;
+    fn start_selection(&mut self) {
+        self.editor.start_selection();
+    }
+
+    fn update_selection_after_move(&mut self) {
+        if self.selection_range().is_none() {
+            self.editor.clear_selection();
         }
+    }
 
-        let len = conflict.hunks.len();
-        if direction < 0 {
-            if self.selected_conflict_hunk == 0 {
-                self.selected_conflict_hunk = len - 1;
-            } else {
-                self.selected_conflict_hunk -= 1;
-            }
+    fn clear_selection(&mut self) {
+        self.editor.clear_selection();
+    }
+
+    /// `Tab`/`Shift+Tab` indent text only while typing in the editor; every
+    /// prompt and overlay leaves them alone.
+    fn tab_edits_buffer(&self) -> bool {
+        self.ui.focus == PaneFocus::Editor
+            && !self.ui.view_only
+            && !self.home_mode
+            && !self.ui.help.open
+            && !self.outline_mode
+            && self.link_picker.is_none()
+            && self.snippet_palette.is_none()
+            && self.review.is_none()
+            && !self.search_mode
+            && !self.goto_mode
+            && !self.save_as_mode
+            && !self.replace_find_mode
+            && !self.replace_with_mode
+            && !self.readonly
+    }
+
+    fn selection_range(&self) -> Option<(usize, usize)> {
+        self.editor.selection_range()
+    }
+
+    /// Keep `entry` internally and mirror it to the system clipboard.
+    /// Returns a status suffix when the system clipboard is unavailable.
+    fn store_clipboard(&mut self, entry: ClipboardEntry) -> &'static str {
+        let mirrored = self.system_clipboard.set_text(entry.text()).is_ok();
+        self.clipboard = Some(entry);
+        if mirrored {
+            ""
         } else {
-            self.selected_conflict_hunk = (self.selected_conflict_hunk + 1) % len;
+            CLIPBOARD_FALLBACK_NOTE
         }
-        self.status = format!("Conflict hunk {}/{}", self.selected_conflict_hunk + 1, len);
     }
 
-    fn apply_selected_conflict_hunk(&mut self) {
-        if !self.editor.is_conflicted() {
-            self.status = "No conflict hunks".into();
-            return;
+    /// Prefer system clipboard text, keeping line semantics when it still
+    /// matches what mdv last stored. A working clipboard without text (an
+    /// image, say) has nothing to paste.
+    fn load_clipboard(&mut self) -> (Option<ClipboardEntry>, &'static str) {
+        match self.system_clipboard.get_text() {
+            Ok(None) => (None, ""),
+            Ok(Some(text)) => match self.clipboard.take() {
+                Some(internal) if internal.text() == text => {
+                    self.clipboard = Some(internal.clone());
+                    (Some(internal), "")
+                }
+                internal if text.is_empty() => {
+                    self.clipboard = internal.clone();
+                    (internal, "")
+                }
+                _ => {
+                    let entry = ClipboardEntry::Text(text);
+                    self.clipboard = Some(entry.clone());
+                    (Some(entry), "")
+                }
+            },
+            Err(_) => (self.clipboard.clone(), CLIPBOARD_FALLBACK_NOTE),
         }
+    }
 
-        if self.editor.apply_external_hunk(self.selected_conflict_hunk) {
-            self.sync_conflict_hunk_selection();
-            self.ensure_cursor_visible();
-            if self.editor.is_conflicted() {
-                self.status = "Applied external hunk".into();
-            } else {
-                self.status = "Resolved conflict from hunks".into();
-            }
-        } else {
-            self.status = "No conflict hunks".into();
+    fn replace_selection(&mut self, replacement: &str) -> bool {
+        let Some((start, end)) = self.selection_range() else {
+            return false;
+        };
+        let changed = self.editor.replace_range(start, end, replacement);
+        self.clear_selection();
+        changed
+    }
+
+    fn save_to_current_path(&mut self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let tidied = self.editor.apply_save_policy(self.save_policy_for(path));
+        let outcome = self.editor.save_to_path(path)?;
+        self.last_saved_text = Some(self.editor.text().to_string());
+        self.file_missing = false;
+        self.last_save_at = Instant::now();
+        self.remember_position();
+        self.sync_link_watches();
+        self.refresh_file_info();
+        let (severity, mut text) = match outcome {
+            SaveOutcome::Atomic => (Severity::Info, "Saved".to_string()),
+            SaveOutcome::DirectWrite { reason } => (
+                Severity::Warn,
+                format!("Saved (in place, atomic save failed: {reason})"),
+            ),
+        };
+        if tidied {
+            text.push_str(TIDIED_SUFFIX);
         }
+        self.push_status(severity, text);
+        Ok(())
     }
 
-    fn sync_conflict_hunk_selection(&mut self) {
-        let Some(conflict) = self.editor.conflict() else {
-            self.selected_conflict_hunk = 0;
+    /// Write the buffer with conflict markers around each hunk to
+    /// `<path>.mdv-merge`, leaving the buffer and the conflict untouched.
+    fn export_merged(&mut self) {
+        let Some(merged) = self.editor.conflict_markers() else {
+            self.push_status(Severity::Warn, "No conflict to export");
             return;
         };
-        if conflict.hunks.is_empty() {
-            self.selected_conflict_hunk = 0;
-        } else {
-            self.selected_conflict_hunk = self
-                .selected_conflict_hunk
-                .min(conflict.hunks.len().saturating_sub(1));
+        let Some(path) = &self.path else {
+            self.push_status(Severity::Warn, "Save the file first to export the merge");
+            return;
+        };
+        let target = merge_export_path(path);
+        match fs::write(&target, merged) {
+            Ok(()) => self.push_status(Severity::Info, format!("Wrote {}", target.display())),
+            Err(err) => self.push_status(Severity::Error, format!("Merge export failed: {err}")),
         }
     }
 
-    fn build_preview_lines(&self, preview_width: u16) -> (Vec<String>, Option<usize>) {
-        let mut preview_lines = render_preview_lines(self.editor.text(), preview_width);
-        let mut selected_anchor = None;
+    fn maybe_autosave(&mut self, now: Instant) {
+        let Some(interval) = self.autosave_interval else {
+            return;
+        };
+        // A pending conflict means disk holds changes we have not merged yet.
+        if !self.editor.dirty || self.readonly || self.stream_mode || self.editor.is_conflicted() {
+            return;
+        }
+        let Some(path) = &self.path else {
+            return;
+        };
+        if now.saturating_duration_since(self.last_save_at) < interval {
+            return;
+        }
 
-        if let Some(conflict) = self.editor.conflict() {
-            preview_lines.push(String::new());
-            if conflict.hunks.is_empty() {
-                preview_lines.push("--- Local block ---".into());
-                preview_lines.extend(self.editor.text().lines().map(ToString::to_string));
-                preview_lines.push("--- External block ---".into());
-                preview_lines.extend(conflict.external.lines().map(ToString::to_string));
-            } else {
-                for (idx, hunk) in conflict.hunks.iter().enumerate() {
-                    let selected = idx == self.selected_conflict_hunk;
-                    if selected {
-                        selected_anchor = Some(preview_lines.len());
-                        preview_lines
-                            .push(format!(">>> Local block @L{} <<<", hunk.local_start + 1));
-                    } else {
-                        preview_lines
-                            .push(format!("--- Local block @L{} ---", hunk.local_start + 1));
-                    }
-                    if hunk.local_lines.is_empty() {
-                        preview_lines.push("(no local lines)".into());
-                    } else {
-                        preview_lines.extend(hunk.local_lines.iter().cloned());
-                    }
-                    if selected {
-                        preview_lines.push(format!(
-                            ">>> External block @L{} <<<",
-                            hunk.external_start + 1
-                        ));
-                    } else {
-                        preview_lines.push(format!(
-                            "--- External block @L{} ---",
-                            hunk.external_start + 1
-                        ));
-                    }
-                    if hunk.external_lines.is_empty() {
-                        preview_lines.push("(no external lines)".into());
-                    } else {
-                        preview_lines.extend(hunk.external_lines.iter().cloned());
-                    }
-                    preview_lines.push(String::new());
-                }
+        self.last_save_at = now;
+        match self.editor.save_to_path(path) {
+            Ok(_) => {
+                self.last_saved_text = Some(self.editor.text().to_string());
+                self.file_missing = false;
+                self.push_status(
+                    Severity::Info,
+                    format!("Autosaved {} UTC", clock_hms(SystemTime::now())),
+                );
             }
+            Err(err) => self.push_status(Severity::Error, format!("Autosave error: {err}")),
         }
+    }
 
-        (preview_lines, selected_anchor)
+    /// Quit now, or ask first about each open file with unsaved edits.
+    fn request_quit(&mut self, running: &mut bool) {
+        self.review_unsaved_files(0, running);
     }
 
-    fn preview_cache_key(&self, preview_width: u16) -> u64 {
-        let mut hasher = std::collections::hash_map::DefaultHasher::new();
-        preview_width.hash(&mut hasher);
-        self.selected_conflict_hunk.hash(&mut hasher);
-        self.editor.text().hash(&mut hasher);
-        if let Some(conflict) = self.editor.conflict() {
-            conflict.external.hash(&mut hasher);
-            for hunk in &conflict.hunks {
-                hunk.local_start.hash(&mut hasher);
-                hunk.external_start.hash(&mut hasher);
-                for line in &hunk.local_lines {
-                    line.hash(&mut hasher);
-                }
-                for line in &hunk.external_lines {
-                    line.hash(&mut hasher);
-                }
-            }
+    fn open_save_as_prompt(&mut self) {
+        if self.readonly && !self.stream_mode {
+            self.push_status(Severity::Warn, "Readonly: save disabled");
+            return;
+        }
+        self.search_mode = false;
+        self.goto_mode = false;
+        self.clear_replace_mode();
+        self.save_as_mode = true;
+        self.save_as_query = self
+            .path
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        self.push_status(Severity::Info, format!("Save as: {}", self.save_as_query));
+    }
+
+    /// Whitespace cleanups for saving to `path`: the config, then any
+    /// `.editorconfig`, then the command-line flags, which only turn them on.
+    fn save_policy_for(&self, path: &Path) -> SavePolicy {
+        let policy = editorconfig::save_policy(path, self.save_policy);
+        SavePolicy {
+            final_newline: policy.final_newline || self.forced_save_policy.final_newline,
+            trim_trailing_whitespace: policy.trim_trailing_whitespace
+                || self.forced_save_policy.trim_trailing_whitespace,
         }
-        hasher.finish()
     }
 
-    fn preview_lines_cached(&mut self, preview_width: u16) -> (Arc<Vec<String>>, Option<usize>) {
-        let key = self.preview_cache_key(preview_width);
-        if let Some(cache) = &self.preview_cache
-            && cache.key == key
-        {
-            #[cfg(test)]
-            {
-                self.test_preview_cache_hits += 1;
-            }
-            return (Arc::clone(&cache.lines), cache.selected_anchor);
+    fn apply_save_as(&mut self) {
+        let query = self.save_as_query.trim();
+        if query.is_empty() {
+            self.push_status(Severity::Info, "Save as: path empty");
+            return;
         }
+        let path = expand_tilde(query);
+        let tidied = self.editor.apply_save_policy(self.save_policy_for(&path));
+        let outcome = match self.editor.save_to_path(&path) {
+            Ok(outcome) => outcome,
+            Err(err) => {
+                // Keep the prompt open so the path can be corrected.
+                self.push_status(Severity::Error, format!("Save as error: {err}"));
+                return;
+            }
+        };
 
-        let (lines, selected_anchor) = self.build_preview_lines(preview_width);
-        self.preview_cache = Some(PreviewCache {
-            key,
-            lines: Arc::new(lines),
-            selected_anchor,
-        });
-        #[cfg(test)]
-        {
-            self.test_preview_cache_misses += 1;
+        self.save_as_mode = false;
+        self.save_as_query.clear();
+        self.last_saved_text = Some(self.editor.text().to_string());
+        self.file_missing = false;
+        self.last_save_at = Instant::now();
+        if self.stream_mode {
+            self.stream_mode = false;
+            self.stream_rx = None;
+            self.readonly = false;
         }
-        (
-            Arc::clone(
-                &self
-                    .preview_cache
-                    .as_ref()
-                    .expect("preview cache populated")
-                    .lines,
+        self.home_mode = false;
+        let (severity, mut text) = match outcome {
+            SaveOutcome::Atomic => (Severity::Info, format!("Saved as {}", path.display())),
+            SaveOutcome::DirectWrite { reason } => (
+                Severity::Warn,
+                format!(
+                    "Saved as {} (in place, atomic save failed: {reason})",
+                    path.display()
+                ),
             ),
-            selected_anchor,
-        )
+        };
+        if tidied {
+            text.push_str(TIDIED_SUFFIX);
+        }
+        self.push_status(severity, text);
+        self.start_watching(&path);
+        self.path = Some(path);
+        self.sync_link_watches();
+        self.refresh_file_info();
     }
 
-    fn active_docs_catalog(&self) -> &'static docs::DocCatalog {
-        if self.ui.help.is_onboarding() {
-            docs::onboarding_catalog()
-        } else {
-            docs::user_docs_catalog()
+    fn start_watching(&mut self, path: &Path) {
+        self._watcher = None;
+        self.watch_rx = None;
+        if !self.watch_enabled || !path.exists() {
+            return;
+        }
+        match watch_file(path, WatchOptions::default()) {
+            Ok((watcher, rx)) => {
+                self._watcher = Some(watcher);
+                self.watch_rx = Some(rx);
+            }
+            Err(err) => {
+                self.push_status(Severity::Error, format!("watch error: {err}"));
+            }
         }
     }
 
-    fn active_help_section(&self) -> docs::DocSection {
-        docs::section(self.active_docs_catalog(), self.ui.help.section_idx)
-    }
+    /// `Tab` in the Home prompt: complete the path, or step through the
+    /// candidates of an ambiguous completion on repeated presses.
+    fn complete_home_query(&mut self, cycle: Option<(Vec<String>, usize)>) {
+        if let Some((candidates, next)) = cycle {
+            self.home_query = candidates[next].clone();
+            self.push_status(
+                Severity::Info,
+                format!(
+                    "Open/create: {} ({}/{})",
+                    self.home_query,
+                    next + 1,
+                    candidates.len()
+                ),
+            );
+            let after = (next + 1) % candidates.len();
+            self.home_completions = Some((candidates, after));
+            return;
+        }
 
-    fn active_help_line_count(&self) -> usize {
-        docs::section_line_count(&self.active_help_section())
+        match completion::complete_path(&self.home_query) {
+            CompletionResult::NoMatch => {
+                self.push_status(
+                    Severity::Info,
+                    format!("Open/create: {} (no match)", self.home_query),
+                );
+            }
+            CompletionResult::Unique(path) => {
+                self.home_query = path;
+                self.push_status(Severity::Info, format!("Open/create: {}", self.home_query));
+            }
+            CompletionResult::Ambiguous { common, candidates } => {
+                self.home_query = common;
+                let names: Vec<&str> = candidates
+                    .iter()
+                    .map(|candidate| completion::file_part(candidate))
+                    .collect();
+                self.push_status(
+                    Severity::Info,
+                    format!(
+                        "{} matches, Tab cycles: {}",
+                        candidates.len(),
+                        names.join("  ")
+                    ),
+                );
+                self.home_completions = Some((candidates, 0));
+            }
+        }
     }
 
-    fn open_docs_modal(&mut self) {
-        self.ui.help.open_docs();
-        let sections = docs::section_count(self.active_docs_catalog());
-        if sections > 0 {
-            self.ui.help.section_idx = self.ui.help.section_idx.min(sections.saturating_sub(1));
+    fn open_home_path(&mut self, path: PathBuf) {
+        let existed = path.exists();
+        let file = match read_text(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => decode_text(b""),
+            Err(err) => {
+                self.push_status(Severity::Error, format!("open error: {err}"));
+                return;
+            }
+        };
+
+        self.start_watching(&path);
+        self.remember_position();
+
+        self.path = Some(path.clone());
+        self.editor = EditorBuffer::new(file.text);
+        self.editor.set_line_ending(file.line_ending);
+        self.last_saved_text = None;
+        self.before_external = None;
+        self.preview_cache = None;
+        self.reset_text_caches();
+        self.home_mode = false;
+        self.clear_selection();
+        self.search_mode = false;
+        self.goto_mode = false;
+        self.clear_replace_mode();
+        self.home_query.clear();
+        self.editor_scroll = 0;
+        self.editor_wrap_skip = 0;
+        self.preview_scroll = 0;
+        self.restore_position();
+        self.sync_conflict_hunk_selection();
+        self.changed_link = None;
+        self.jump_stack.clear();
+        self.sync_link_watches();
+        if file.invalid_sequences > 0 {
+            self.push_status(Severity::Warn, invalid_utf8_status(file.invalid_sequences));
+        } else if existed {
+            self.push_status(Severity::Info, format!("Opened {}", path.display()));
         } else {
-            self.ui.help.section_idx = 0;
+            self.push_status(Severity::Info, format!("New file {}", path.display()));
         }
-        self.ui.help.scroll = 0;
     }
 
-    fn close_docs_modal(&mut self) {
-        let was_onboarding = self.ui.help.is_onboarding();
-        self.ui.help.close();
-        if was_onboarding {
-            self.persist_onboarding_marker();
+    fn scroll_active_viewport(&mut self, direction: i8) {
+        if self.ui.focus == PaneFocus::Editor {
+            self.scroll_editor(direction);
+            return;
         }
+        self.scroll_preview(direction);
     }
 
-    fn maybe_open_onboarding(&mut self) {
-        if self.onboarding_gate_checked {
+    fn scroll_viewport_at(&mut self, column: u16, row: u16, direction: i8) {
+        if point_in_rect(column, row, self.editor_area) {
+            self.scroll_editor(direction);
             return;
         }
-        self.onboarding_gate_checked = true;
-        if self.home_mode && self.interactive_input && !self.onboarding_seen {
-            self.ui.help.open_onboarding();
-            self.status = "Beginner guide opened".into();
+        if point_in_rect(column, row, self.preview_area) {
+            self.scroll_preview(direction);
+            return;
         }
+        self.scroll_active_viewport(direction);
     }
 
-    fn advance_onboarding_step(&mut self) {
-        let total = docs::section_count(docs::onboarding_catalog());
-        let step = self
-            .ui
-            .help
-            .onboarding_step
-            .unwrap_or(self.ui.help.section_idx)
-            .min(total.saturating_sub(1));
-        if step + 1 >= total {
-            self.close_docs_modal();
-            self.status = "Guide complete".into();
-            return;
+    fn scroll_editor(&mut self, direction: i8) {
+        let amount = SCROLL_STEP_LINES;
+        if direction < 0 {
+            self.pause_follow();
+            self.set_editor_scroll(self.editor_scroll.saturating_sub(amount));
+        } else {
+            self.set_editor_scroll(self.editor_scroll.saturating_add(amount));
         }
-        self.ui.help.section_idx = step + 1;
-        self.ui.help.onboarding_step = Some(step + 1);
-        self.ui.help.scroll = 0;
     }
 
-    fn persist_onboarding_marker(&mut self) {
-        let Some(path) = &self.onboarding_marker_path else {
-            self.onboarding_seen = false;
-            return;
-        };
-        let mut ok = true;
-        if let Some(parent) = path.parent()
-            && fs::create_dir_all(parent).is_err()
-        {
-            ok = false;
-        }
-        if ok && fs::write(path, b"1\n").is_err() {
-            ok = false;
+    fn scroll_preview(&mut self, direction: i8) {
+        let amount = SCROLL_STEP_LINES;
+        let preview_width = self.preview_area.width.saturating_sub(2).max(1);
+        if direction < 0 {
+            self.pause_follow();
+            self.preview_scroll = self.preview_scroll.saturating_sub(amount);
+        } else {
+            self.preview_scroll = self.preview_scroll.saturating_add(amount);
         }
-        self.onboarding_seen = ok;
+        self.slide_large_preview();
+        let preview_lines = self.preview_lines_cached(preview_width);
+        self.preview_scroll = clamp_scroll(
+            self.preview_scroll,
+            preview_lines.len(),
+            self.preview_height.max(1),
+        );
     }
 
-    fn draw_docs_modal(&mut self, frame: &mut Frame<'_>, area: Rect, theme: &ThemeTokens) {
-        let popup = docs_modal_rect(area);
-        frame.render_widget(Clear, popup);
-        frame.render_widget(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(self.active_docs_catalog().title)
-                .border_style(theme.pane_focus),
-            popup,
-        );
-        if popup.width <= 2 || popup.height <= 2 {
+    fn handle_mouse_down(&mut self, column: u16, row: u16, modifiers: KeyModifiers) {
+        if self.home_mode || self.ui.help.open {
             return;
         }
-
-        let inner = Rect {
-            x: popup.x + 1,
-            y: popup.y + 1,
-            width: popup.width.saturating_sub(2),
-            height: popup.height.saturating_sub(2),
-        };
-        if inner.width == 0 || inner.height == 0 {
+        if point_in_rect(column, row, self.preview_area) {
+            self.ui.focus = PaneFocus::Preview;
+        }
+        if point_in_rect(column, row, self.editor_area) {
+            self.ui.focus = PaneFocus::Editor;
+        }
+        if self.ui.focus != PaneFocus::Editor {
+            return;
+        }
+        if !point_in_rect(column, row, self.editor_text_area) {
             return;
         }
 
-        let rows = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(1),
-                Constraint::Min(1),
-                Constraint::Length(1),
-            ])
-            .split(inner);
-        let body = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Length(
-                    (inner.width / 3)
-                        .max(22)
-                        .min(inner.width.saturating_sub(10)),
-                ),
-                Constraint::Min(8),
-            ])
-            .split(rows[1]);
-
-        let catalog = self.active_docs_catalog();
-        let section_count = docs::section_count(catalog);
-        if section_count == 0 {
+        let prior_cursor = self.editor.cursor();
+        if !self.set_editor_cursor_from_mouse(column, row) {
             return;
         }
-        self.ui.help.section_idx = self
-            .ui
-            .help
-            .section_idx
-            .min(section_count.saturating_sub(1));
-        let section = docs::section(catalog, self.ui.help.section_idx);
-        let lines = docs::render_section(&section, theme);
-        self.help_page_lines = body[1].height.max(1) as usize;
-        let max_scroll = lines.len().saturating_sub(self.help_page_lines.max(1));
-        self.ui.help.scroll = self.ui.help.scroll.min(max_scroll);
-        let total_pages = ((lines.len() + self.help_page_lines.saturating_sub(1))
-            / self.help_page_lines.max(1))
-        .max(1);
-        let current_page = (self.ui.help.scroll / self.help_page_lines.max(1)).saturating_add(1);
-        let top = format!(
-            "{} ({}) | {}/{} | page {}/{}",
-            section.title,
-            section.id,
-            self.ui.help.section_idx + 1,
-            section_count,
-            current_page.min(total_pages),
-            total_pages
-        );
-        frame.render_widget(Paragraph::new(top).style(theme.top_bar), rows[0]);
+        self.editor.collapse_cursors();
 
-        let index_lines = catalog
-            .sections
-            .iter()
-            .enumerate()
-            .map(|(idx, sec)| {
-                let selected = idx == self.ui.help.section_idx;
-                let prefix = if selected { "› " } else { "  " };
-                let style = if selected && self.ui.help.index_focus {
-                    theme.pane_focus.add_modifier(Modifier::BOLD)
-                } else if selected {
-                    theme.heading
-                } else {
-                    theme.help
-                };
-                Line::from(Span::styled(format!("{prefix}{}", sec.title), style))
-            })
-            .collect::<Vec<_>>();
+        if modifiers.contains(KeyModifiers::SHIFT) {
+            if self.editor.selection_anchor().is_none() {
+                self.editor.set_selection_anchor(prior_cursor);
+            }
+        } else {
+            self.clear_selection();
+        }
 
-        let index = Paragraph::new(index_lines).block(
-            Block::default()
-                .title("Sections")
-                .borders(Borders::RIGHT)
-                .border_style(theme.pane_border),
+        self.mouse_drag_anchor = Some(
+            self.editor
+                .selection_anchor()
+                .unwrap_or(self.editor.cursor()),
         );
-        frame.render_widget(index, body[0]);
+        self.update_selection_after_move();
+        self.ensure_cursor_visible();
+    }
 
-        let content_visible = lines
-            .into_iter()
-            .skip(self.ui.help.scroll)
-            .take(self.help_page_lines.max(1))
-            .collect::<Vec<_>>();
-        let content = Paragraph::new(content_visible)
-            .style(theme.help)
-            .wrap(Wrap { trim: false });
-        frame.render_widget(content, body[1]);
+    fn handle_mouse_drag(&mut self, column: u16, row: u16) {
+        if self.ui.focus != PaneFocus::Editor || self.home_mode || self.ui.help.open {
+            return;
+        }
+        let Some(anchor) = self.mouse_drag_anchor else {
+            return;
+        };
+        let area = self.editor_text_area;
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+        // Dragging past the top or bottom border scrolls one line per event.
+        let bottom = area.y + area.height - 1;
+        if row < area.y {
+            self.set_editor_scroll(self.editor_scroll.saturating_sub(1));
+        } else if row > bottom {
+            self.set_editor_scroll(self.editor_scroll + 1);
+        }
+        let column = column.clamp(area.x, area.x + area.width - 1);
+        let row = row.clamp(area.y, bottom);
+        if !self.set_editor_cursor_from_mouse(column, row) {
+            return;
+        }
+        self.editor.set_selection_anchor(anchor);
+        self.update_selection_after_move();
+        self.ensure_cursor_visible();
+    }
 
-        let hint = if self.ui.help.is_onboarding() {
-            "Esc skip guide | Up/Down step | Enter next | Tab/Left/Right focus"
+    fn handle_mouse_up(&mut self) {
+        if self.mouse_drag_anchor.take().is_none() {
+            return;
+        }
+        if let Some((start, end)) = self.selection_range() {
+            let chars = self.editor.text()[start..end].chars().count();
+            self.push_status(Severity::Info, format!("Selected {chars} chars"));
+        }
+    }
+
+    fn set_editor_cursor_from_mouse(&mut self, column: u16, row: u16) -> bool {
+        if !point_in_rect(column, row, self.editor_text_area) {
+            return false;
+        }
+        let rel_line = row.saturating_sub(self.editor_text_area.y) as usize;
+        let rel_col = column.saturating_sub(self.editor_text_area.x) as usize;
+        if let Some(width) = self.wrap_width() {
+            let (line, offset) = self.wrapped_position_at(rel_line, rel_col, width);
+            self.editor
+                .set_cursor(self.editor.line_start(line) + offset);
+            return true;
+        }
+        let line = self.editor_scroll.saturating_add(rel_line);
+        let text = self.editor.text().split('\n').nth(line).unwrap_or("");
+        let col = char_at_column(text, self.editor_hscroll + rel_col);
+        self.editor.set_cursor_line_col(line, col);
+        true
+    }
+
+    fn search_prompt(&self, label: &str, query: &str) -> String {
+        let mut flags = String::new();
+        if self.search_options.ignore_case {
+            flags.push('i');
+        }
+        if self.search_options.whole_word {
+            flags.push('w');
+        }
+        let prompt = if flags.is_empty() {
+            format!("{label}: {query}")
         } else {
-            "Esc close | Enter open | Tab/Left/Right focus | PgUp/PgDn/Home/End/j/k scroll"
+            format!("{label} [{flags}]: {query}")
         };
-        frame.render_widget(Paragraph::new(hint).style(theme.help), rows[2]);
+        // Counting rescans the whole text on every key, so documents big
+        // enough for large-file mode only get a count on `Enter`.
+        if query.is_empty() || self.large_file_mode() {
+            return prompt;
+        }
+        match self.editor.count_matches(query, self.search_options) {
+            0 => format!("{prompt} (no matches)"),
+            count => format!(
+                "{prompt} ({count} match{})",
+                if count == 1 { "" } else { "es" }
+            ),
+        }
     }
 
-    fn draw(&mut self, frame: &mut Frame<'_>) {
-        let area = frame.area();
-        self.term_width = area.width.max(1);
-        let theme = build_theme(self.ui.theme, self.ui.no_color);
-
-        let vertical = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(1),
-                Constraint::Min(1),
-                Constraint::Length(1),
-            ])
-            .split(area);
+    fn clear_replace_mode(&mut self) {
+        self.replace_find_mode = false;
+        self.replace_with_mode = false;
+        self.replace_find_query.clear();
+        self.replace_with_query.clear();
+        self.replace_target.clear();
+        self.replace_all_pending = None;
+    }
 
-        let info = self.info_line(vertical[0].width as usize);
-        frame.render_widget(Paragraph::new(info).style(theme.top_bar), vertical[0]);
+    fn apply_replace_next(&mut self) {
+        let find = self.replace_target.clone();
+        let replacement = std::mem::take(&mut self.replace_with_query);
+        self.clear_replace_mode();
 
-        let pane_layout = compute_pane_layout(vertical[1], self.ui.focus);
-        self.editor_area = pane_layout.editor;
-        self.preview_area = pane_layout.preview;
-        self.editor_text_area = Rect::default();
+        if find.is_empty() {
+            self.push_status(Severity::Info, "Replace query empty");
+            return;
+        }
+        if self.readonly {
+            self.push_status(Severity::Warn, "Readonly: replace disabled");
+            return;
+        }
 
-        if self.home_mode {
-            self.draw_home(frame, vertical[1], &theme);
+        if self
+            .editor
+            .replace_next_with(&find, &replacement, self.search_options)
+        {
+            self.last_search_query = find.clone();
+            self.sync_conflict_hunk_selection();
+            self.push_status(Severity::Info, format!("Replaced: {find} -> {replacement}"));
         } else {
-            if pane_layout.editor.width > 0 && pane_layout.editor.height > 0 {
-                let editor_height = pane_layout.editor.height.saturating_sub(2) as usize;
-                self.editor_height = editor_height.max(1);
-                self.editor_text_area = cursor_rect(pane_layout.editor);
-                let editor_lines = to_lines(self.editor.text());
-                self.editor_scroll =
-                    clamp_scroll(self.editor_scroll, editor_lines.len(), self.editor_height);
-                let (current_line, _) = self.editor.line_col_at_cursor();
-                let gutter_width = line_number_gutter_width(editor_lines.len());
-                let editor_visible = styled_editor_lines(
-                    self.editor.text(),
-                    self.editor_scroll,
-                    self.editor_height,
-                    self.selection_range(),
-                    pane_layout.editor.width.saturating_sub(2),
-                    &theme,
-                    current_line,
-                );
+            self.push_status(Severity::Warn, format!("Not found: {find}"));
+        }
+    }
 
-                // Update editor text area to account for gutter
-                self.editor_text_area = Rect {
-                    x: pane_layout.editor.x + 1 + gutter_width,
-                    y: pane_layout.editor.y + 1,
-                    width: pane_layout.editor.width.saturating_sub(2 + gutter_width),
-                    height: pane_layout.editor.height.saturating_sub(2),
-                };
+    /// `Ctrl+A` in the replace prompt: say how many matches would change
+    /// and wait for `Enter` before touching the text.
+    fn confirm_replace_all(&mut self) {
+        if self.replace_target.is_empty() || self.readonly {
+            self.apply_replace_all();
+            return;
+        }
+        let find = &self.replace_target;
+        let count = self.editor.count_matches(find, self.search_options);
+        if count == 0 {
+            self.push_status(Severity::Warn, format!("Not found: {find}"));
+            self.clear_replace_mode();
+            return;
+        }
+        self.push_status(
+            Severity::Info,
+            format!(
+                "Replace {count} occurrence{} of '{find}'? Enter confirm / Esc cancel",
+                plural(count)
+            ),
+        );
+        self.replace_all_pending = Some(count);
+    }
 
-                // Build editor title with scroll indicator
-                let editor_title = editor_title_with_scroll(
-                    editor_lines.len(),
-                    self.editor_scroll,
-                    self.editor_height,
-                );
+    fn apply_replace_all(&mut self) {
+        let find = self.replace_target.clone();
+        let replacement = std::mem::take(&mut self.replace_with_query);
+        self.clear_replace_mode();
 
-                let editor_border = pane_border_style(&theme, self.ui.focus == PaneFocus::Editor);
-                let editor = Paragraph::new(editor_visible).block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title(editor_title)
-                        .border_style(editor_border),
-                );
-                frame.render_widget(editor, pane_layout.editor);
-            }
+        if find.is_empty() {
+            self.push_status(Severity::Info, "Replace query empty");
+            return;
+        }
+        if self.readonly {
+            self.push_status(Severity::Warn, "Readonly: replace disabled");
+            return;
+        }
 
-            if pane_layout.preview.width > 0 && pane_layout.preview.height > 0 {
-                let preview_height = pane_layout.preview.height.saturating_sub(2) as usize;
-                self.preview_height = preview_height.max(1);
-                let preview_width = pane_layout.preview.width.saturating_sub(2);
-                let (preview_lines, selected_anchor) = self.preview_lines_cached(preview_width);
+        let count = self
+            .editor
+            .replace_all_with(&find, &replacement, self.search_options);
+        if count > 0 {
+            self.last_search_query = find.clone();
+            self.sync_conflict_hunk_selection();
+            self.push_status(
+                Severity::Info,
+                format!("Replaced all {count}: {find} -> {replacement}"),
+            );
+        } else {
+            self.push_status(Severity::Warn, format!("Not found: {find}"));
+        }
+    }
 
-                if let Some(anchor) = selected_anchor {
-                    if anchor < self.preview_scroll {
-                        self.preview_scroll = anchor;
-                    } else if anchor >= self.preview_scroll + preview_height.max(1) {
-                        self.preview_scroll = anchor.saturating_sub(preview_height / 2);
-                    }
-                }
-                self.preview_scroll = clamp_scroll(
-                    self.preview_scroll,
-                    preview_lines.len(),
-                    self.preview_height,
-                );
+    /// Forget the last search, which also drops its highlights.
+    fn clear_search(&mut self) {
+        if self.last_search_query.is_empty() {
+            return;
+        }
+        self.last_search_query.clear();
+        self.search_matches = None;
+        self.preview_hit = None;
+        self.push_status(Severity::Info, "Search cleared");
+    }
 
-                let mut in_code = code_open_before(preview_lines.as_ref(), self.preview_scroll);
-                let preview_visible = preview_lines
-                    .iter()
-                    .skip(self.preview_scroll)
-                    .take(self.preview_height)
-                    .map(|line| styled_preview_line(line, preview_width, &theme, &mut in_code))
-                    .collect::<Vec<_>>();
+    /// Recompute the matches of the last search when the text, query or
+    /// options changed since they were found.
+    fn refresh_search_matches(&mut self) {
+        if self.last_search_query.is_empty() {
+            self.search_matches = None;
+            return;
+        }
+        let revision = self.editor.revision();
+        let fresh = self.search_matches.as_ref().is_some_and(|matches| {
+            matches.revision == revision
+                && matches.options == self.search_options
+                && matches.query == self.last_search_query
+        });
+        if !fresh {
+            self.search_matches = Some(SearchMatches {
+                revision,
+                query: self.last_search_query.clone(),
+                options: self.search_options,
+                ranges: self
+                    .editor
+                    .match_ranges(&self.last_search_query, self.search_options),
+            });
+        }
+    }
 
-                // Build preview title with scroll indicator and conflict info
-                let preview_title = preview_title_with_scroll(
-                    self.selected_conflict_hunk,
-                    self.editor.conflict(),
-                    preview_lines.len(),
-                    self.preview_scroll,
-                    self.preview_height,
-                );
+    /// Drop results computed for the text that was on screen before another
+    /// file replaced it; revisions of different buffers are not comparable.
+    fn reset_text_caches(&mut self) {
+        self.search_matches = None;
+        self.cursor_link = None;
+        if let Some(spell) = self.spell.as_mut() {
+            spell.revision = None;
+        }
+    }
 
-                let preview_border = pane_border_style(&theme, self.ui.focus == PaneFocus::Preview);
-                let preview = Paragraph::new(preview_visible)
-                    .block(
-                        Block::default()
-                            .borders(Borders::ALL)
-                            .title(preview_title)
-                            .border_style(preview_border),
-                    )
-                    .wrap(Wrap { trim: false });
-                frame.render_widget(preview, pane_layout.preview);
-            }
+    /// Check the text again when it changed since the last check. Lines
+    /// that did not change reuse their earlier result.
+    fn refresh_spell(&mut self) {
+        let revision = self.editor.revision();
+        let Some(spell) = self.spell.as_mut() else {
+            return;
+        };
+        if spell.revision != Some(revision) {
+            spell.misspelled = spell.checker.check(self.editor.text());
+            spell.revision = Some(revision);
         }
+    }
 
-        // Build the styled status bar
-        let (line, col) = self.editor.line_col_at_cursor();
-        let total_lines = self.editor.text().lines().count().max(1);
-        let scroll_percent = if total_lines <= 1 {
-            100
-        } else {
-            ((self.editor_scroll as f64 / (total_lines.saturating_sub(1)) as f64) * 100.0)
-                .min(100.0) as u8
+    fn misspelled_ranges(&self) -> &[(usize, usize)] {
+        self.spell.as_ref().map_or(&[], |spell| &spell.misspelled)
+    }
+
+    /// Move to the first misspelling after the cursor, wrapping around.
+    fn next_misspelling(&mut self) {
+        if self.spell.is_none() {
+            self.push_status(Severity::Info, "Spell check is off: --spell or Settings");
+            return;
+        }
+        self.refresh_spell();
+        let ranges = self.misspelled_ranges();
+        if ranges.is_empty() {
+            self.push_status(Severity::Warn, "No misspellings");
+            return;
+        }
+        let cursor = self.editor.cursor();
+        let index = ranges
+            .iter()
+            .position(|&(start, _)| start > cursor)
+            .unwrap_or(0);
+        let (start, end) = ranges[index];
+        let total = ranges.len();
+        let word = self.editor.text()[start..end].to_string();
+        self.clear_selection();
+        self.editor.set_cursor(start);
+        self.ensure_cursor_visible();
+        self.push_status(
+            Severity::Info,
+            format!("Misspelled: {word} ({}/{total})", index + 1),
+        );
+    }
+
+    /// Teach the checker the misspelled word under the cursor and keep it
+    /// in the user dictionary.
+    fn add_word_to_dictionary(&mut self) {
+        if self.spell.is_none() {
+            self.push_status(Severity::Info, "Spell check is off: --spell or Settings");
+            return;
+        }
+        self.refresh_spell();
+        let cursor = self.editor.cursor();
+        let Some(&(start, end)) = self
+            .misspelled_ranges()
+            .iter()
+            .find(|&&(start, end)| start <= cursor && cursor <= end)
+        else {
+            self.push_status(Severity::Warn, "No misspelled word at the cursor");
+            return;
+        };
+        let word = self.editor.text()[start..end].to_string();
+        if let Some(spell) = self.spell.as_mut() {
+            spell.checker.add_word(&word);
+            spell.revision = None;
+        }
+        let added = match self.user_dictionary.as_deref() {
+            Some(path) => spelling::add_to_user_dictionary(path, &word)
+                .map(|()| format!("Added '{word}' to the dictionary"))
+                .map_err(|err| {
+                    format!("Added '{word}' for this session (dictionary error: {err})")
+                }),
+            None => Ok(format!("Added '{word}' for this session")),
         };
+        match added {
+            Ok(text) => self.push_status(Severity::Info, text),
+            Err(text) => self.push_status(Severity::Error, text),
+        }
+    }
 
-        let filename = self
-            .path
+    /// Mark the last search's matches on preview row `row`; the preview
+    /// match `F3` is on stands out.
+    fn highlight_preview_matches(
+        &self,
+        mut line: Line<'static>,
+        row: usize,
+        theme: &ThemeTokens,
+    ) -> Line<'static> {
+        if self.last_search_query.is_empty() {
+            return line;
+        }
+        let text: String = line
+            .spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect();
+        let current = self
+            .preview_hit
+            .filter(|hit| hit.row == row)
+            .map(|hit| hit.start);
+        for (start, end) in match_ranges_in(&text, &self.last_search_query, self.search_options) {
+            let style = if Some(start) == current {
+                theme.current_match
+            } else {
+                theme.search_match
+            };
+            line.spans =
+                apply_selection_to_styled_spans(std::mem::take(&mut line.spans), start, end, style);
+        }
+        line
+    }
+
+    fn search_match_ranges(&self) -> &[(usize, usize)] {
+        self.search_matches
             .as_ref()
-            .map(|p| {
-                p.file_name()
-                    .map(|n| n.to_string_lossy().to_string())
-                    .unwrap_or_else(|| p.display().to_string())
-            })
-            .unwrap_or_else(|| {
-                if self.home_mode {
-                    "<home>".into()
-                } else if self.stream_mode {
-                    "<stdin>".into()
-                } else {
-                    "<new>".into()
-                }
-            });
+            .map_or(&[], |matches| &matches.ranges)
+    }
 
-        let mode = mode_label(self);
-        let is_error = self.status.contains("error") || self.status.contains("Error");
-        let is_warning = self.editor.is_conflicted() || self.status.contains("conflict");
+    /// `match 3/17` when the cursor sits on a match, otherwise how many
+    /// there are.
+    fn search_counter(&self) -> Option<String> {
+        let ranges = &self.search_matches.as_ref()?.ranges;
+        let cursor = self.editor.cursor();
+        Some(
+            match ranges.iter().position(|&(start, _)| start == cursor) {
+                Some(index) => format!("match {}/{}", index + 1, ranges.len()),
+                None if ranges.len() == 1 => "1 match".into(),
+                None => format!("{} matches", ranges.len()),
+            },
+        )
+    }
 
-        // For compact layout, use simple status bar
-        if pane_layout.kind == LayoutKind::Compact {
-            let base_status = format!("compact | {}", self.status);
-            let right_hint = self.status_hint();
-            let status_text = compose_status(&base_status, &right_hint, vertical[2].width as usize);
-            frame.render_widget(
-                Paragraph::new(status_text).style(status_style(
-                    &theme,
-                    self.editor.is_conflicted(),
-                    &self.status,
-                )),
-                vertical[2],
+    fn repeat_search_next(&mut self) {
+        if self.last_search_query.is_empty() {
+            self.push_status(Severity::Warn, "No prior search");
+            return;
+        }
+        if self.searches_preview() {
+            let query = self.last_search_query.clone();
+            self.find_in_preview(&query, PreviewSearch::Next);
+            return;
+        }
+
+        if self
+            .editor
+            .find_next_with(&self.last_search_query, self.search_options)
+        {
+            self.push_status(
+                Severity::Info,
+                format!("Found next: {}", self.last_search_query),
             );
+            self.ensure_cursor_visible();
         } else {
-            // Use rich styled status bar for normal/split layouts
-            let mut message = self.status.clone();
-            if self.perf_mode {
-                message = format!(
-                    "{} | draw={}us watch={} stream={}",
-                    message, self.draw_time_us, self.watch_event_count, self.stream_event_count
-                );
-            }
+            self.push_status(
+                Severity::Warn,
+                format!("Not found: {}", self.last_search_query),
+            );
+        }
+    }
 
-            let status_config = StatusBarConfig {
-                mode,
-                filename: &filename,
-                dirty: self.editor.dirty,
-                readonly: self.readonly,
-                line,
-                col,
-                total_lines,
-                scroll_percent,
-                message: &message,
-                hint: &self.status_hint(),
-                is_error,
-                is_warning,
-                width: vertical[2].width as usize,
-            };
+    fn repeat_search_prev(&mut self) {
+        if self.last_search_query.is_empty() {
+            self.push_status(Severity::Warn, "No prior search");
+            return;
+        }
+        if self.searches_preview() {
+            let query = self.last_search_query.clone();
+            self.find_in_preview(&query, PreviewSearch::Prev);
+            return;
+        }
 
-            let status_line = build_status_bar(&status_config, &theme);
-            frame.render_widget(
-                Paragraph::new(status_line).style(theme.status_bg),
-                vertical[2],
+        if self
+            .editor
+            .find_prev_with(&self.last_search_query, self.search_options)
+        {
+            self.push_status(
+                Severity::Info,
+                format!("Found previous: {}", self.last_search_query),
+            );
+            self.ensure_cursor_visible();
+        } else {
+            self.push_status(
+                Severity::Warn,
+                format!("Not found: {}", self.last_search_query),
             );
         }
+    }
 
-        if self.ui.help.open {
-            self.draw_docs_modal(frame, area, &theme);
+    /// Undo and redo steps left, as `undo 12 / redo 3`.
+    fn history_depth(&self) -> String {
+        format!(
+            "undo {} / redo {}",
+            self.editor.undo_depth(),
+            self.editor.redo_depth()
+        )
+    }
+
+    fn move_conflict_hunk(&mut self, direction: i32) {
+        let Some(conflict) = self.editor.conflict() else {
+            self.push_status(Severity::Warn, "No conflict hunks");
+            return;
+        };
+        if conflict.hunks.is_empty() {
+            self.push_status(Severity::Warn, "No conflict hunks");
+            return;
         }
 
-        if !self.ui.help.open {
-            if self.home_mode {
-                let popup = centered_popup(60, 16, vertical[1]);
-                if popup.width > 10 && popup.height > 7 {
-                    // Cursor position: "  Path: " is 8 chars, on row 7 (0-indexed: 6)
-                    let x = popup
-                        .x
-                        .saturating_add(1)
-                        .saturating_add(8)
-                        .saturating_add(self.home_query.chars().count() as u16)
-                        .min(popup.x + popup.width.saturating_sub(2));
-                    let y = popup.y + 7;
-                    frame.set_cursor_position((x, y));
-                }
-            } else if !self.readonly
-                && self.ui.focus == PaneFocus::Editor
-                && pane_layout.editor.width > 0
-            {
-                let cursor_area = self.editor_text_area;
-                let (line, col) = self.editor.line_col_at_cursor();
-                let visible_line = line.saturating_sub(self.editor_scroll) as u16;
-                if visible_line < cursor_area.height {
-                    let x = (cursor_area.x + col as u16)
-                        .min(cursor_area.x + cursor_area.width.saturating_sub(1));
-                    let y = cursor_area.y + visible_line;
-                    frame.set_cursor_position((x, y));
-                }
+        let len = conflict.hunks.len();
+        if direction < 0 {
+            if self.selected_conflict_hunk == 0 {
+                self.selected_conflict_hunk = len - 1;
+            } else {
+                self.selected_conflict_hunk -= 1;
             }
+        } else {
+            self.selected_conflict_hunk = (self.selected_conflict_hunk + 1) % len;
         }
+        let hunk = &conflict.hunks[self.selected_conflict_hunk];
+        let hint = match hunk.kind {
+            HunkKind::Move => " (moved: Ctrl+E accepts the move)",
+            _ if hunk.is_whitespace_only() => " (whitespace only)",
+            _ => "",
+        };
+        self.remember_conflict_selection();
+        self.push_status(
+            Severity::Warn,
+            format!(
+                "Conflict hunk {}/{len}{hint}",
+                self.selected_conflict_hunk + 1
+            ),
+        );
     }
 
-    fn draw_home(&mut self, frame: &mut Frame<'_>, area: Rect, theme: &ThemeTokens) {
-        let popup = centered_popup(64, 18, area);
-        frame.render_widget(Clear, popup);
+    fn apply_selected_conflict_hunk(&mut self) {
+        if !self.editor.is_conflicted() {
+            self.push_status(Severity::Warn, "No conflict hunks");
+            return;
+        }
 
-        // ASCII art logo
-        let logo_style = theme.heading;
-        let subtitle_style = theme.help;
-        let input_label_style = theme.list_bullet;
-        let input_style = theme.plain;
-        let hint_style = Style::default().fg(Color::Rgb(92, 99, 112));
-        let key_style = theme.code;
+        // The hunk after the applied one takes over the selection.
+        let conflict = self.editor.conflict();
+        let next = |offset: usize| {
+            conflict.and_then(|conflict| conflict.hunks.get(self.selected_conflict_hunk + offset))
+        };
+        let moved = next(0).is_some_and(|hunk| hunk.kind == HunkKind::Move);
+        self.selected_conflict_id = next(1).map(|hunk| hunk.id);
+        if self.editor.apply_external_hunk(self.selected_conflict_hunk) {
+            self.sync_conflict_hunk_selection();
+            self.ensure_cursor_visible();
+            if self.editor.is_conflicted() {
+                let applied = if moved { "move" } else { "hunk" };
+                self.push_status(Severity::Info, format!("Applied external {applied}"));
+            } else {
+                self.push_status(Severity::Info, "Resolved conflict from hunks");
+            }
+        } else {
+            self.push_status(Severity::Warn, "No conflict hunks");
+        }
+    }
 
-        let lines = vec![
-            Line::from(""),
-            Line::from(vec![
-                Span::styled("  m", logo_style),
-                Span::styled("arkdown ", subtitle_style),
-                Span::styled("v", logo_style),
-                Span::styled("iewer", subtitle_style),
-            ]),
-            Line::from(Span::styled(
-                "  Write notes in your terminal with live preview",
-                hint_style,
-            )),
-            Line::from(""),
-            Line::from(Span::styled(
-                "  ─────────────────────────────────────",
-                hint_style,
-            )),
-            Line::from(""),
-            Line::from(Span::styled(
-                "  New here? Type a file name like notes.md and press Enter.",
-                hint_style,
-            )),
-            Line::from(Span::styled(
-                "  mdv creates the file on first save if it does not exist yet.",
-                hint_style,
-            )),
-            Line::from(""),
-            Line::from(vec![
-                Span::styled("  File: ", input_label_style),
-                Span::styled(
-                    if self.home_query.is_empty() {
-                        "notes.md"
-                    } else {
-                        &self.home_query
-                    },
-                    if self.home_query.is_empty() {
-                        hint_style
-                    } else {
-                        input_style
-                    },
-                ),
-            ]),
-            Line::from(""),
-            Line::from(Span::styled(
-                "  ─────────────────────────────────────",
-                hint_style,
-            )),
-            Line::from(""),
-            Line::from(vec![
-                Span::styled("  Enter", key_style),
-                Span::styled(" open/create  ", hint_style),
-                Span::styled("Ctrl+,", key_style),
-                Span::styled(" help  ", hint_style),
-                Span::styled("Ctrl+Q", key_style),
-                Span::styled(" quit", hint_style),
-            ]),
-            Line::from(""),
-        ];
+    /// Keep the selection on the same hunk after the hunks were computed
+    /// again, or on the same position when that hunk is gone.
+    fn sync_conflict_hunk_selection(&mut self) {
+        let Some(conflict) = self.editor.conflict() else {
+            self.selected_conflict_hunk = 0;
+            self.selected_conflict_id = None;
+            return;
+        };
+        let same = self
+            .selected_conflict_id
+            .and_then(|id| conflict.hunks.iter().position(|hunk| hunk.id == id));
+        self.selected_conflict_hunk = match same {
+            Some(index) => index,
+            None => self
+                .selected_conflict_hunk
+                .min(conflict.hunks.len().saturating_sub(1)),
+        };
+        self.remember_conflict_selection();
+    }
 
-        let home = Paragraph::new(lines).block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(theme.pane_focus),
-        );
-        frame.render_widget(home, popup);
+    pub(super) fn remember_conflict_selection(&mut self) {
+        self.selected_conflict_id = self
+            .editor
+            .conflict()
+            .and_then(|conflict| conflict.hunks.get(self.selected_conflict_hunk))
+            .map(|hunk| hunk.id);
     }
 
-    fn info_line(&self, width: usize) -> String {
-        let path = self
-            .path
-            .as_ref()
-            .map(|p| p.display().to_string())
-            .unwrap_or_else(|| {
-                if self.home_mode {
-                    "<home>".into()
-                } else {
-                    "<stream>".into()
-                }
-            });
-        let mode = mode_label(self);
-        let ro = if self.readonly { "RO" } else { "RW" };
-        let dirty = if self.editor.dirty { "dirty" } else { "clean" };
-        let view_mode = match self.ui.focus {
-            PaneFocus::Editor => "editor",
-            PaneFocus::Preview => "view",
+    /// Source lines `start..end` the preview renders in large-file mode: a
+    /// window from `preview_source_top`, widened to the blank lines around
+    /// it so blocks are not cut in half.
+    fn large_preview_window(&self) -> Option<(usize, usize)> {
+        if !self.large_file_mode() {
+            return None;
+        }
+        let total = self.editor.line_count();
+        let top = self.preview_source_top.min(total.saturating_sub(1));
+        let size = LARGE_PREVIEW_WINDOW_LINES.max(self.preview_height * 4);
+        let start = block_boundary(&self.editor, top, false);
+        let end = block_boundary(&self.editor, top.saturating_add(size), true);
+        Some((start, end))
+    }
+
+    /// Rendered rows the large-file window produces above `source_line`.
+    fn large_preview_offset(&self, source_line: usize, width: u16) -> usize {
+        let Some((start, _)) = self.large_preview_window() else {
+            return 0;
         };
-        let line = format!(
-            "{} | {ro} | {dirty} | mode={mode} | view={view_mode}",
-            truncate_middle(&path, width.saturating_sub(32).max(12))
-        );
-        if line.chars().count() > width {
-            truncate_middle(&line, width).into_owned()
-        } else {
-            line
+        let from = self.editor.line_start(start);
+        let to = self.editor.line_start(source_line.max(start));
+        if from == to {
+            return 0;
         }
+        let options = RenderOptions::new(width)
+            .with_emoji(true)
+            .with_reader(self.reader_mode)
+            .with_front_matter(self.front_matter);
+        render(&self.editor.text()[from..to], &options).lines.len()
     }
 
-    fn status_hint(&self) -> String {
-        let base = if self.replace_find_mode {
-            "replace: type text to find"
-        } else if self.replace_with_mode {
-            "replace: type replacement | Ctrl+A all"
-        } else if self.search_mode {
-            "search: type text + Enter"
-        } else if self.goto_mode {
-            "goto: type line number + Enter"
-        } else if self.ui.help.open {
-            "Esc close help"
-        } else if self.home_mode {
-            "home: type a file name or path + Enter"
-        } else if self.ui.focus == PaneFocus::Editor && !self.readonly && !self.stream_mode {
-            "Type to edit | Shift+Arrows select | Cmd/Alt+Backspace word | Shift+Tab switch panes"
-        } else {
-            "Shift+Tab switch panes | Cmd+,/Ctrl+, help"
+    /// Keep the large-file window around the editor viewport, lining the
+    /// preview up with the editor's first visible line.
+    fn follow_editor_in_large_preview(&mut self, width: u16) {
+        let Some((start, end)) = self.large_preview_window() else {
+            return;
         };
+        let anchor = self.editor_scroll;
+        let past_end = anchor + self.editor_height > end && end < self.editor.line_count();
+        if anchor < start || past_end {
+            self.preview_source_top = anchor.saturating_sub(LARGE_PREVIEW_MARGIN_LINES);
+        }
+        self.preview_scroll = self.large_preview_offset(anchor, width);
+    }
 
-        if let Some(conflict) = self.editor.conflict()
-            && !conflict.hunks.is_empty()
-        {
-            return format!(
-                "{base} | hunk {}/{}",
-                self.selected_conflict_hunk + 1,
-                conflict.hunks.len()
-            );
+    /// Move the large-file window by half when preview scrolling runs off
+    /// either end, adjusting the scroll so the same rows stay on screen.
+    fn slide_large_preview(&mut self) {
+        let Some((start, end)) = self.large_preview_window() else {
+            return;
+        };
+        let width = self.preview_area.width.saturating_sub(2).max(1);
+        let lines = self.preview_lines_cached(width);
+        let half = (end - start) / 2;
+        let bottom = self.preview_scroll.saturating_add(self.preview_height);
+        if bottom > lines.len() && end < self.editor.line_count() {
+            let next_start = block_boundary(&self.editor, start + half, false);
+            let shift = self.large_preview_offset(next_start, width);
+            self.preview_source_top = start + half;
+            self.preview_scroll = self.preview_scroll.min(lines.len()).saturating_sub(shift);
+        } else if self.preview_scroll == 0 && start > 0 {
+            self.preview_source_top = start.saturating_sub(half);
+            self.preview_scroll = self.large_preview_offset(start, width);
         }
-        base.into()
     }
-}
 
-fn mode_label(app: &App) -> &'static str {
-    if app.home_mode {
-        "home"
-    } else if app.replace_find_mode || app.replace_with_mode {
-        "replace"
-    } else if app.search_mode {
-        "search"
-    } else if app.goto_mode {
-        "goto"
-    } else if app.stream_mode {
-        "stream"
-    } else if app.editor.is_conflicted() {
-        "conflict"
-    } else {
-        "normal"
+    fn build_preview_lines(&mut self, preview_width: u16) -> Vec<PreviewLineKind> {
+        let window = self.large_preview_window();
+        let text = self.editor.text();
+        let source = match window {
+            Some((start, end)) => &text[self.editor.line_start(start)..self.editor.line_start(end)],
+            None => text,
+        };
+        let mut preview_lines = Vec::new();
+        if let Some(review) = &self.review {
+            push_hunk_blocks(
+                &mut preview_lines,
+                &review.hunks,
+                review.selected,
+                ConflictSide::Disk,
+            );
+            return preview_lines;
+        }
+        preview_lines.extend(
+            self.preview_renderer
+                .render(source, preview_width)
+                .iter()
+                .cloned()
+                .map(PreviewLineKind::Markdown),
+        );
+
+        if let Some(conflict) = self.editor.conflict() {
+            preview_lines.push(PreviewLineKind::Blank);
+            if conflict.hunks.is_empty() {
+                push_whole_sides(&mut preview_lines, self.editor.text(), &conflict.external);
+            } else {
+                push_hunk_blocks(
+                    &mut preview_lines,
+                    &conflict.hunks,
+                    self.selected_conflict_hunk,
+                    ConflictSide::External,
+                );
+            }
+        }
+        preview_lines
     }
-}
 
-fn pane_border_style(theme: &ThemeTokens, focused: bool) -> Style {
-    if focused {
-        theme.pane_focus
-    } else {
-        theme.pane_border
+    fn preview_cache_key(&self, preview_width: u16) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        preview_width.hash(&mut hasher);
+        self.selected_conflict_hunk.hash(&mut hasher);
+        // Every edit bumps the revision, so the text itself is never hashed;
+        // the renderer reuses unchanged blocks when the revision moves.
+        self.editor.revision().hash(&mut hasher);
+        self.large_preview_window().hash(&mut hasher);
+        if let Some(review) = &self.review {
+            review.selected.hash(&mut hasher);
+            hash_hunks(&review.hunks, &mut hasher);
+        }
+        if let Some(conflict) = self.editor.conflict() {
+            conflict.external.hash(&mut hasher);
+            hash_hunks(&conflict.hunks, &mut hasher);
+        }
+        hasher.finish()
     }
-}
 
-fn status_style(theme: &ThemeTokens, conflicted: bool, status: &str) -> Style {
-    if status.contains("error") || status.contains("Error") {
-        return theme.status_error;
+    fn preview_lines_cached(&mut self, preview_width: u16) -> Arc<Vec<PreviewLineKind>> {
+        let key = self.preview_cache_key(preview_width);
+        if let Some(cache) = &self.preview_cache
+            && cache.key == key
+        {
+            #[cfg(test)]
+            {
+                self.test_preview_cache_hits += 1;
+            }
+            return Arc::clone(&cache.lines);
+        }
+
+        let whole_text = self.large_preview_window().is_none()
+            && self.editor.conflict().is_none()
+            && self.review.is_none();
+        let appended = if whole_text {
+            self.appended_preview_lines(preview_width)
+        } else {
+            None
+        };
+        let lines = match appended {
+            Some(lines) => lines,
+            None => Arc::new(self.build_preview_lines(preview_width)),
+        };
+        self.preview_cache = Some(PreviewCache {
+            key,
+            lines: Arc::clone(&lines),
+            appendable: whole_text.then(|| self.editor.revision()),
+        });
+        #[cfg(test)]
+        {
+            self.test_preview_cache_misses += 1;
+        }
+        lines
     }
-    if conflicted || status.contains("conflict") {
-        return theme.status_warn;
+
+    /// Where to scroll a freshly rendered preview so the source line that was
+    /// at the top stays there, or `None` to leave the scroll alone.
+    fn anchored_preview_scroll(&self, lines: &[PreviewLineKind]) -> Option<usize> {
+        if self.following() || self.large_file_mode() || self.editor.conflict().is_some() {
+            return None;
+        }
+        let anchor = self
+            .preview_anchor
+            .as_ref()
+            .filter(|anchor| anchor.scroll == self.preview_scroll)?;
+        // The same text on the anchor's line means the edit came after it;
+        // otherwise lines added or removed above have moved it.
+        let delta = self.editor.line_count() as isize - anchor.line_count as isize;
+        let source_line = [
+            Some(anchor.source_line),
+            anchor.source_line.checked_add_signed(delta),
+        ]
+        .into_iter()
+        .flatten()
+        .find(|&line| line < self.editor.line_count() && self.editor.line_text(line) == anchor.text)
+        .unwrap_or(anchor.source_line);
+        let first = lines
+            .iter()
+            .position(|line| line.source_line() == source_line)
+            .or_else(|| {
+                lines
+                    .iter()
+                    .position(|line| line.source_line() >= source_line)
+            })?;
+        let rows = lines[first..]
+            .iter()
+            .take_while(|line| line.source_line() == lines[first].source_line())
+            .count();
+        Some(first + anchor.rows_into.min(rows - 1))
+    }
+
+    fn remember_preview_anchor(&mut self, lines: &[PreviewLineKind]) {
+        let plain_text = !self.large_file_mode() && self.editor.conflict().is_none();
+        self.preview_anchor = lines
+            .get(self.preview_scroll)
+            .filter(|_| plain_text)
+            .map(|top| PreviewAnchor {
+                scroll: self.preview_scroll,
+                source_line: top.source_line(),
+                rows_into: lines[..self.preview_scroll]
+                    .iter()
+                    .rev()
+                    .take_while(|line| line.source_line() == top.source_line())
+                    .count(),
+                line_count: self.editor.line_count(),
+                text: self.editor.line_text(top.source_line()).to_string(),
+            });
     }
-    theme.status_ok
-}
 
-/// Generate a scroll indicator string like "━━━━━━━━━░░" representing scroll position
-fn scroll_indicator_bar(total_lines: usize, scroll: usize, visible_height: usize) -> String {
-    const BAR_WIDTH: usize = 8;
+    /// The cached lines extended with what was appended to the text since
+    /// they were rendered, or `None` when the text changed some other way.
+    fn appended_preview_lines(&mut self, preview_width: u16) -> Option<Arc<Vec<PreviewLineKind>>> {
+        let revision = self.preview_cache.as_ref()?.appendable?;
+        if !self.editor.only_appended_since(revision) {
+            return None;
+        }
+        let mut lines = self.preview_cache.take()?.lines;
+        let (kept, rendered) = self
+            .preview_renderer
+            .render_appended(self.editor.text(), preview_width);
+        let cached = Arc::make_mut(&mut lines);
+        cached.truncate(kept);
+        cached.extend(
+            rendered[kept..]
+                .iter()
+                .cloned()
+                .map(PreviewLineKind::Markdown),
+        );
+        #[cfg(test)]
+        {
+            self.test_preview_appends += 1;
+        }
+        Some(lines)
+    }
 
-    if total_lines <= visible_height {
-        return "────────".to_string();
+    fn active_docs_catalog(&self) -> &'static docs::DocCatalog {
+        if self.ui.help.is_onboarding() {
+            docs::onboarding_catalog()
+        } else {
+            docs::user_docs_catalog()
+        }
     }
 
-    let scrollable_lines = total_lines.saturating_sub(visible_height);
-    let scroll_ratio = if scrollable_lines > 0 {
-        (scroll as f64 / scrollable_lines as f64).clamp(0.0, 1.0)
-    } else {
-        0.0
-    };
+    fn active_help_section(&self) -> docs::DocSection {
+        docs::section(self.active_docs_catalog(), self.ui.help.section_idx)
+    }
 
-    // Calculate thumb position and size
-    let visible_ratio = (visible_height as f64 / total_lines as f64).clamp(0.1, 1.0);
-    let thumb_size = ((BAR_WIDTH as f64) * visible_ratio).ceil().max(1.0) as usize;
-    let thumb_start = ((BAR_WIDTH - thumb_size) as f64 * scroll_ratio).round() as usize;
+    fn active_help_line_count(&self) -> usize {
+        docs::section_line_count(&self.active_help_section())
+    }
 
-    let mut bar = String::new();
-    for i in 0..BAR_WIDTH {
-        if i >= thumb_start && i < thumb_start + thumb_size {
-            bar.push('━');
+    fn open_docs_modal(&mut self) {
+        self.ui.help.open_docs();
+        let sections = docs::section_count(self.active_docs_catalog());
+        if sections > 0 {
+            self.ui.help.section_idx = self.ui.help.section_idx.min(sections.saturating_sub(1));
         } else {
-            bar.push('─');
+            self.ui.help.section_idx = 0;
         }
+        self.ui.help.scroll = 0;
     }
-    bar
-}
 
-fn editor_title_with_scroll(total_lines: usize, scroll: usize, visible_height: usize) -> String {
-    if total_lines <= visible_height {
-        "Editor".into()
-    } else {
-        let bar = scroll_indicator_bar(total_lines, scroll, visible_height);
-        format!("Editor {}", bar)
+    fn close_docs_modal(&mut self) {
+        let was_onboarding = self.ui.help.is_onboarding();
+        self.ui.help.close();
+        if was_onboarding {
+            self.persist_onboarding_marker();
+        }
     }
-}
 
-fn preview_title_with_scroll(
-    selected_conflict_hunk: usize,
-    conflict: Option<&mdv_core::ConflictState>,
-    total_lines: usize,
-    scroll: usize,
-    visible_height: usize,
-) -> String {
-    let base = if let Some(conflict) = conflict
-        && !conflict.hunks.is_empty()
-    {
-        format!(
-            "Preview [conflict {}/{}]",
-            selected_conflict_hunk + 1,
-            conflict.hunks.len()
-        )
-    } else {
-        "Preview".into()
-    };
-
-    if total_lines <= visible_height {
-        base
-    } else {
-        let bar = scroll_indicator_bar(total_lines, scroll, visible_height);
-        format!("{} {}", base, bar)
+    fn handle_settings_key(&mut self, key: KeyEvent) {
+        let Some(nav) = input::map_settings_key(key) else {
+            return;
+        };
+        match nav {
+            SettingsNav::Up | SettingsNav::Down => self.ui.help.move_setting(nav),
+            SettingsNav::Next | SettingsNav::Prev => {
+                self.change_setting(self.ui.help.selected_setting(), nav == SettingsNav::Next);
+            }
+        }
     }
-}
 
-fn styled_preview_line(
-    line: &str,
-    width: u16,
-    theme: &ThemeTokens,
-    in_code_block: &mut bool,
-) -> Line<'static> {
-    // Handle code block fences
-    if line.trim_start().starts_with("```") || line.trim() == "$$" {
-        *in_code_block = !*in_code_block;
-        return Line::from(Span::styled(
-            line.to_string(),
-            style_for_segment(theme, SegmentKind::Code),
-        ));
-    }
-    if *in_code_block {
-        return Line::from(Span::styled(
-            line.to_string(),
-            style_for_segment(theme, SegmentKind::Code),
-        ));
-    }
+    /// Apply one settings-tab change right away. Theme, colors, and focus are
+    /// also written to the prefs file.
+    fn change_setting(&mut self, item: SettingItem, forward: bool) {
+        let mut theme = self.ui.theme;
+        let mut no_color = self.ui.no_color;
+        let mut focus = self.ui.focus;
+        match item {
+            SettingItem::Theme => theme = cycle_theme(theme, forward),
+            SettingItem::Colors => no_color = !no_color,
+            SettingItem::Focus if self.ui.view_only => {
+                self.push_status(Severity::Info, "View-only: Ctrl+O to edit");
+                return;
+            }
+            SettingItem::Focus => {
+                focus = match focus {
+                    PaneFocus::Editor => PaneFocus::Preview,
+                    PaneFocus::Preview => PaneFocus::Editor,
+                };
+            }
+            SettingItem::Watch => {
+                self.toggle_watch();
+                return;
+            }
+            SettingItem::ContinueLists => {
+                self.continue_lists = !self.continue_lists;
+                self.push_status(
+                    Severity::Info,
+                    format!("{}: {}", item.label(), self.setting_value(item)),
+                );
+                return;
+            }
+            SettingItem::Spell => {
+                let on = self.spell.is_none();
+                self.set_spell(on);
+                // A word list that failed to load left its reason instead.
+                if self.spell.is_some() == on {
+                    self.push_status(
+                        Severity::Info,
+                        format!("{}: {}", item.label(), self.setting_value(item)),
+                    );
+                }
+                return;
+            }
+            SettingItem::ReaderMode => {
+                self.reader_mode = !self.reader_mode;
+                self.preview_renderer.set_reader(self.reader_mode);
+                self.preview_cache = None;
+                self.push_status(
+                    Severity::Info,
+                    format!("{}: {}", item.label(), self.setting_value(item)),
+                );
+                return;
+            }
+            SettingItem::FrontMatter => {
+                self.front_matter = match self.front_matter {
+                    FrontMatterStyle::Folded => FrontMatterStyle::Expanded,
+                    _ => FrontMatterStyle::Folded,
+                };
+                self.preview_renderer.set_front_matter(self.front_matter);
+                self.preview_cache = None;
+                self.push_status(
+                    Severity::Info,
+                    format!("{}: {}", item.label(), self.setting_value(item)),
+                );
+                return;
+            }
+            SettingItem::LineNumbers | SettingItem::SoftWrap | SettingItem::ScrollSync => {
+                let action = match item {
+                    SettingItem::LineNumbers => Action::ToggleLineNumbers,
+                    SettingItem::SoftWrap => Action::ToggleSoftWrap,
+                    _ => Action::ToggleScrollSync,
+                };
+                update::apply_action(&mut self.ui, action, self.term_width);
+                self.push_status(
+                    Severity::Info,
+                    format!("{}: {}", item.label(), self.setting_value(item)),
+                );
+                return;
+            }
+        }
+        update::apply_action(
+            &mut self.ui,
+            Action::ApplyPrefs {
+                focus,
+                theme,
+                no_color,
+            },
+            self.term_width,
+        );
+        match item {
+            SettingItem::Theme => self.prefs.theme = Some(theme),
+            SettingItem::Colors => self.prefs.no_color = Some(no_color),
+            _ => self.prefs.focus = Some(focus),
+        }
 
-    // Conflict markers
-    if line.contains("Local block") {
-        return Line::from(Span::styled(
-            line.to_string(),
-            style_for_segment(theme, SegmentKind::ConflictLocal),
-        ));
-    }
-    if line.contains("External block") {
-        return Line::from(Span::styled(
-            line.to_string(),
-            style_for_segment(theme, SegmentKind::ConflictExternal),
-        ));
+        let label = format!("{}: {}", item.label(), self.setting_value(item));
+        match self.save_prefs() {
+            Ok(true) => self.push_status(Severity::Info, format!("{label} (saved)")),
+            Ok(false) => self.push_status(Severity::Info, label),
+            Err(err) => self.push_status(
+                Severity::Error,
+                format!("{label} (prefs save error: {err})"),
+            ),
+        }
     }
 
-    // Horizontal rule
-    let trimmed = line.trim();
-    if trimmed == "---" || trimmed == "***" || trimmed == "___" {
-        let hr_char = "─".repeat(width.saturating_sub(2) as usize);
-        return Line::from(Span::styled(hr_char, theme.hr));
+    fn setting_value(&self, item: SettingItem) -> String {
+        let on_off = |on: bool| if on { "on" } else { "off" }.to_string();
+        match item {
+            SettingItem::Theme => prefs::theme_name(self.ui.theme).into(),
+            SettingItem::Colors => on_off(!self.ui.no_color),
+            SettingItem::Focus => prefs::focus_name(self.ui.focus).into(),
+            SettingItem::Watch => on_off(self.watch_enabled),
+            SettingItem::ContinueLists => on_off(self.continue_lists),
+            SettingItem::LineNumbers => on_off(self.ui.line_numbers),
+            SettingItem::SoftWrap => on_off(self.ui.soft_wrap),
+            SettingItem::ScrollSync => on_off(self.ui.scroll_sync),
+            SettingItem::Spell => on_off(self.spell.is_some()),
+            SettingItem::ReaderMode => on_off(self.reader_mode),
+            SettingItem::FrontMatter => match self.front_matter {
+                FrontMatterStyle::Folded => "folded".into(),
+                _ => "expanded".into(),
+            },
+        }
     }
 
-    // Headings (# Heading)
-    if trimmed.starts_with('#') {
-        let level = trimmed.chars().take_while(|&c| c == '#').count();
-        if level <= 6 && trimmed.chars().nth(level) == Some(' ') {
-            let prefix = "#".repeat(level);
-            let content = trimmed[level..].trim_start();
-            return Line::from(vec![
-                Span::styled(format!("{} ", prefix), theme.heading),
-                Span::styled(content.to_string(), theme.heading),
-            ]);
+    fn toggle_watch(&mut self) {
+        self.watch_enabled = !self.watch_enabled;
+        self.push_status(
+            Severity::Info,
+            format!("Watch file: {}", self.setting_value(SettingItem::Watch)),
+        );
+        if let Some(path) = self.path.clone() {
+            self.start_watching(&path);
         }
     }
 
-    // Block quotes (> text)
-    if trimmed.starts_with('>') {
-        let quote_depth = trimmed
-            .chars()
-            .take_while(|&c| c == '>' || c == ' ')
-            .count();
-        let content = &trimmed[quote_depth.min(trimmed.len())..];
-        let prefix = "> ".repeat(quote_depth.div_ceil(2));
-        return Line::from(vec![
-            Span::styled(prefix, theme.quote),
-            Span::styled(content.to_string(), theme.quote),
-        ]);
+    /// Record where the file on screen was left. Failures are ignored: a
+    /// read-only state directory should not get in the way of quitting.
+    fn remember_position(&self) {
+        let (Some(store), Some(path)) = (&self.resume_path, &self.path) else {
+            return;
+        };
+        let (line, column) = self.editor.line_col_at_cursor();
+        let position = FilePosition {
+            path: resume::file_key(path),
+            modified: resume::modified_millis(path),
+            line,
+            column,
+            editor_scroll: self.editor_scroll,
+            preview_scroll: self.preview_scroll,
+            focus: self.ui.focus,
+        };
+        let _ = resume::remember(store, position);
     }
 
-    // Task lists
-    if let Some(stripped) = trimmed
-        .strip_prefix("- [x]")
-        .or_else(|| trimmed.strip_prefix("- [X]"))
-    {
-        let content = stripped.trim_start();
-        return Line::from(vec![
-            Span::styled("  ", theme.plain),
-            Span::styled(" ", theme.task_done),
-            Span::styled(format!(" {}", content), theme.strikethrough),
-        ]);
+    /// Put the cursor, scroll and focus back where the file was left. When
+    /// the file changed since, the cursor is clamped to it and the preview
+    /// follows the editor, since old preview rows no longer line up.
+    fn restore_position(&mut self) {
+        let (Some(store), Some(path)) = (&self.resume_path, &self.path) else {
+            return;
+        };
+        let Some(saved) = resume::find(store, path) else {
+            return;
+        };
+        let unchanged = saved.modified.is_some() && saved.modified == resume::modified_millis(path);
+        let last_line = self.editor.line_count().saturating_sub(1);
+        let line = saved.line.min(last_line);
+        self.editor.set_cursor_line_col(line, saved.column);
+        self.editor_scroll = saved.editor_scroll.min(line);
+        self.preview_scroll = if unchanged {
+            saved.preview_scroll
+        } else {
+            self.preview_line_for_source(self.editor_scroll)
+        };
+        if !self.ui.view_only {
+            self.set_initial_focus(saved.focus);
+        }
     }
-    if let Some(stripped) = trimmed.strip_prefix("- [ ]") {
-        let content = stripped.trim_start();
-        return Line::from(vec![
-            Span::styled("  ", theme.plain),
-            Span::styled(" ", theme.task_pending),
-            Span::styled(format!(" {}", content), theme.plain),
-        ]);
+
+    fn save_prefs(&self) -> io::Result<bool> {
+        let Some(path) = &self.prefs_path else {
+            return Ok(false);
+        };
+        prefs::save(path, &self.prefs)?;
+        Ok(true)
     }
 
-    // Unordered list items (- item)
-    if let Some(stripped) = trimmed.strip_prefix("- ") {
-        let styled_content = style_inline_formatting(stripped, theme);
-        let mut spans = vec![Span::styled("  - ", theme.list_bullet)];
-        spans.extend(styled_content);
-        return Line::from(spans);
+    fn maybe_open_onboarding(&mut self) {
+        if self.onboarding_gate_checked {
+            return;
+        }
+        self.onboarding_gate_checked = true;
+        if self.home_mode && self.interactive_input && !self.onboarding_seen {
+            self.ui.help.open_onboarding();
+            self.push_status(Severity::Info, "Beginner guide opened");
+        }
     }
 
-    // Ordered list items (1. item)
-    if let Some((num, rest)) = trimmed.split_once(". ")
-        && num.chars().all(|c| c.is_ascii_digit())
-    {
-        let styled_content = style_inline_formatting(rest, theme);
-        let mut spans = vec![Span::styled(format!(" {}. ", num), theme.list_bullet)];
-        spans.extend(styled_content);
-        return Line::from(spans);
+    fn advance_onboarding_step(&mut self) {
+        let total = docs::section_count(docs::onboarding_catalog());
+        let step = self
+            .ui
+            .help
+            .onboarding_step
+            .unwrap_or(self.ui.help.section_idx)
+            .min(total.saturating_sub(1));
+        if step + 1 >= total {
+            self.close_docs_modal();
+            self.push_status(Severity::Info, "Guide complete");
+            return;
+        }
+        self.ui.help.section_idx = step + 1;
+        self.ui.help.onboarding_step = Some(step + 1);
+        self.ui.help.scroll = 0;
     }
 
-    // Table header detection (| Col1 | Col2 |)
-    if trimmed.starts_with('|') && trimmed.ends_with('|') {
-        // Check if it's a separator row
-        let inner = &trimmed[1..trimmed.len() - 1];
-        if inner
-            .chars()
-            .all(|c| c == '-' || c == '|' || c == ':' || c == ' ')
+    fn persist_onboarding_marker(&mut self) {
+        let Some(path) = &self.onboarding_marker_path else {
+            self.onboarding_seen = false;
+            return;
+        };
+        let mut ok = true;
+        if let Some(parent) = path.parent()
+            && fs::create_dir_all(parent).is_err()
         {
-            return Line::from(Span::styled(line.to_string(), theme.hr));
+            ok = false;
         }
-        return Line::from(Span::styled(line.to_string(), theme.table_header));
+        if ok && fs::write(path, b"1\n").is_err() {
+            ok = false;
+        }
+        self.onboarding_seen = ok;
     }
 
-    // Regular text with inline formatting
-    let styled_content = style_inline_formatting(line, theme);
-    Line::from(styled_content)
-}
+    fn draw_docs_modal(&mut self, frame: &mut Frame<'_>, area: Rect, theme: &ThemeTokens) {
+        let popup = docs_modal_rect(area);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(self.active_docs_catalog().title)
+                .border_style(theme.pane_focus),
+            popup,
+        );
+        if popup.width <= 2 || popup.height <= 2 {
+            return;
+        }
 
-/// Style inline markdown formatting (bold, italic, code, links)
-fn style_inline_formatting(text: &str, theme: &ThemeTokens) -> Vec<Span<'static>> {
-    let mut spans = Vec::new();
-    let mut current = String::new();
-    let mut chars = text.chars().peekable();
+        let inner = Rect {
+            x: popup.x + 1,
+            y: popup.y + 1,
+            width: popup.width.saturating_sub(2),
+            height: popup.height.saturating_sub(2),
+        };
+        if inner.width == 0 || inner.height == 0 {
+            return;
+        }
 
-    while let Some(c) = chars.next() {
-        match c {
-            // Inline code `code`
-            '`' => {
-                if !current.is_empty() {
-                    spans.push(Span::styled(std::mem::take(&mut current), theme.plain));
-                }
-                let mut code = String::new();
-                while let Some(&next) = chars.peek() {
-                    if next == '`' {
-                        chars.next();
-                        break;
-                    }
-                    code.push(chars.next().unwrap());
-                }
-                spans.push(Span::styled(format!("`{}`", code), theme.code));
-            }
-            // Bold **text** or __text__
-            '*' | '_' if chars.peek() == Some(&c) => {
-                if !current.is_empty() {
-                    spans.push(Span::styled(std::mem::take(&mut current), theme.plain));
-                }
-                chars.next(); // consume second * or _
-                let mut bold = String::new();
-                while let Some(&next) = chars.peek() {
-                    if next == c {
-                        chars.next();
-                        if chars.peek() == Some(&c) {
-                            chars.next();
-                            break;
-                        }
-                        bold.push(c);
-                        continue;
-                    }
-                    bold.push(chars.next().unwrap());
-                }
-                spans.push(Span::styled(bold, theme.strong));
-            }
-            // Italic *text* or _text_
-            '*' | '_' => {
-                if !current.is_empty() {
-                    spans.push(Span::styled(std::mem::take(&mut current), theme.plain));
-                }
-                let delim = c;
-                let mut italic = String::new();
-                while let Some(&next) = chars.peek() {
-                    if next == delim {
-                        chars.next();
-                        break;
-                    }
-                    italic.push(chars.next().unwrap());
-                }
-                spans.push(Span::styled(italic, theme.emphasis));
-            }
-            // Links [text](url)
-            '[' => {
-                if !current.is_empty() {
-                    spans.push(Span::styled(std::mem::take(&mut current), theme.plain));
-                }
-                let mut link_text = String::new();
-                let mut found_close = false;
-                while let Some(&next) = chars.peek() {
-                    if next == ']' {
-                        chars.next();
-                        found_close = true;
-                        break;
-                    }
-                    link_text.push(chars.next().unwrap());
-                }
-                if found_close && chars.peek() == Some(&'(') {
-                    chars.next(); // consume (
-                    let mut url = String::new();
-                    while let Some(&next) = chars.peek() {
-                        if next == ')' {
-                            chars.next();
-                            break;
-                        }
-                        url.push(chars.next().unwrap());
-                    }
-                    spans.push(Span::styled(link_text, theme.link));
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Min(1),
+                Constraint::Length(1),
+            ])
+            .split(inner);
+        if self.ui.help.is_settings() {
+            self.draw_settings_tab(frame, &rows, theme);
+            return;
+        }
+        let body = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(
+                    (inner.width / 3)
+                        .max(22)
+                        .min(inner.width.saturating_sub(10)),
+                ),
+                Constraint::Min(8),
+            ])
+            .split(rows[1]);
+
+        let catalog = self.active_docs_catalog();
+        let section_count = docs::section_count(catalog);
+        if section_count == 0 {
+            return;
+        }
+        self.ui.help.section_idx = self
+            .ui
+            .help
+            .section_idx
+            .min(section_count.saturating_sub(1));
+        let section = docs::section(catalog, self.ui.help.section_idx);
+        let lines = docs::render_section(&section, theme);
+        self.help_page_lines = body[1].height.max(1) as usize;
+        let max_scroll = lines.len().saturating_sub(self.help_page_lines.max(1));
+        self.ui.help.scroll = self.ui.help.scroll.min(max_scroll);
+        let total_pages = ((lines.len() + self.help_page_lines.saturating_sub(1))
+            / self.help_page_lines.max(1))
+        .max(1);
+        let current_page = (self.ui.help.scroll / self.help_page_lines.max(1)).saturating_add(1);
+        let top = format!(
+            "{} ({}) | {}/{} | page {}/{}",
+            section.title,
+            section.id,
+            self.ui.help.section_idx + 1,
+            section_count,
+            current_page.min(total_pages),
+            total_pages
+        );
+        frame.render_widget(Paragraph::new(top).style(theme.top_bar), rows[0]);
+
+        let index_lines = catalog
+            .sections
+            .iter()
+            .enumerate()
+            .map(|(idx, sec)| {
+                let selected = idx == self.ui.help.section_idx;
+                let prefix = if selected { "› " } else { "  " };
+                let style = if selected && self.ui.help.index_focus {
+                    theme.pane_focus.add_modifier(Modifier::BOLD)
+                } else if selected {
+                    theme.heading
                 } else {
-                    // Not a proper link, output as-is
-                    current.push('[');
-                    current.push_str(&link_text);
-                    if found_close {
-                        current.push(']');
-                    }
-                }
+                    theme.help
+                };
+                Line::from(Span::styled(format!("{prefix}{}", sec.title), style))
+            })
+            .collect::<Vec<_>>();
+
+        let index = Paragraph::new(index_lines).block(
+            Block::default()
+                .title("Sections")
+                .borders(Borders::RIGHT)
+                .border_style(theme.pane_border),
+        );
+        frame.render_widget(index, body[0]);
+
+        let content_visible = lines
+            .into_iter()
+            .skip(self.ui.help.scroll)
+            .take(self.help_page_lines.max(1))
+            .collect::<Vec<_>>();
+        let content = Paragraph::new(content_visible)
+            .style(theme.help)
+            .wrap(Wrap { trim: false });
+        frame.render_widget(content, body[1]);
+
+        let hint = if self.ui.help.is_onboarding() {
+            "Esc skip guide | Up/Down step | Enter next | Tab/Left/Right focus".to_string()
+        } else {
+            "Esc close | Tab settings | Enter open | Left/Right focus | PgUp/PgDn/Home/End/j/k scroll"
+                .to_string()
+        };
+        frame.render_widget(Paragraph::new(hint).style(theme.help), rows[2]);
+    }
+
+    fn draw_settings_tab(&self, frame: &mut Frame<'_>, rows: &[Rect], theme: &ThemeTokens) {
+        let top = format!(
+            "Settings | {}/{} | Tab docs",
+            self.ui.help.setting_idx + 1,
+            SettingItem::ALL.len()
+        );
+        frame.render_widget(Paragraph::new(top).style(theme.top_bar), rows[0]);
+
+        let items = SettingItem::ALL
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| {
+                let selected = idx == self.ui.help.setting_idx;
+                let prefix = if selected { "› " } else { "  " };
+                let style = if selected {
+                    theme.pane_focus.add_modifier(Modifier::BOLD)
+                } else {
+                    theme.help
+                };
+                Line::from(Span::styled(
+                    format!("{prefix}{:<14}{}", item.label(), self.setting_value(*item)),
+                    style,
+                ))
+            })
+            .collect::<Vec<Line<'_>>>();
+        let mut lines = items;
+        lines.push(Line::default());
+        lines.push(Line::from(Span::styled(
+            "Keys ([keys] in config.toml)",
+            theme.heading,
+        )));
+        lines.extend(
+            keymap_rows(&self.keymap, rows[1].width as usize)
+                .into_iter()
+                .map(|row| Line::from(Span::styled(row, theme.help))),
+        );
+        frame.render_widget(Paragraph::new(lines), rows[1]);
+
+        let hint = "Up/Down select | Enter/Left/Right change | Tab docs | Esc close";
+        frame.render_widget(Paragraph::new(hint).style(theme.help), rows[2]);
+    }
+
+    fn draw(&mut self, frame: &mut Frame<'_>) {
+        let area = frame.area();
+        self.term_width = area.width.max(1);
+        self.term_height = area.height;
+        let theme = build_theme(
+            resolve_auto(self.ui.theme, self.background),
+            self.ui.no_color,
+            Some(&self.theme_overrides),
+        );
+        self.refresh_search_matches();
+        self.refresh_spell();
+        self.refresh_cursor_link();
+
+        let vertical = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Min(1),
+                Constraint::Length(1),
+            ])
+            .split(area);
+
+        let info = self.info_line(vertical[0].width as usize);
+        frame.render_widget(Paragraph::new(info).style(theme.top_bar), vertical[0]);
+
+        let pane_layout = compute_pane_layout(
+            vertical[1],
+            self.ui.focus,
+            self.ui.view_only,
+            self.ui.maximized,
+            self.ui.split_percent,
+        );
+        self.editor_area = pane_layout.editor;
+        self.preview_area = pane_layout.preview;
+        self.editor_text_area = Rect::default();
+
+        if self.home_mode {
+            self.draw_home(frame, vertical[1], &theme);
+        } else {
+            if pane_layout.editor.width > 0 && pane_layout.editor.height > 0 {
+                let editor_height = pane_layout.editor.height.saturating_sub(2) as usize;
+                self.editor_height = editor_height.max(1);
+                self.editor_text_area = cursor_rect(pane_layout.editor);
+                let editor_line_count = self.editor.line_count();
+                self.editor_scroll =
+                    clamp_scroll(self.editor_scroll, editor_line_count, self.editor_height);
+                let (current_line, _) = self.editor.line_col_at_cursor();
+                let gutter_width = if self.ui.line_numbers {
+                    line_number_gutter_width(editor_line_count)
+                } else {
+                    0
+                };
+                let editor_visible = styled_editor_lines(
+                    &self.editor,
+                    EditorViewport {
+                        scroll: self.editor_scroll,
+                        hscroll: self.editor_hscroll,
+                        height: self.editor_height,
+                        width: pane_layout.editor.width.saturating_sub(2),
+                        code_open: code_open_at(
+                            &self.editor,
+                            self.editor_scroll,
+                            self.large_file_mode(),
+                        ),
+                        wrap: self.ui.soft_wrap,
+                        wrap_skip: self.editor_wrap_skip,
+                    },
+                    self.selection_range(),
+                    self.misspelled_ranges(),
+                    self.search_match_ranges(),
+                    &theme,
+                    self.ui.line_numbers.then_some(current_line),
+                );
+
+                // Update editor text area to account for gutter
+                self.editor_text_area = Rect {
+                    x: pane_layout.editor.x + 1 + gutter_width,
+                    y: pane_layout.editor.y + 1,
+                    width: pane_layout.editor.width.saturating_sub(2 + gutter_width),
+                    height: pane_layout.editor.height.saturating_sub(2),
+                };
+
+                // Build editor title with scroll indicator
+                let editor_title = editor_title_with_scroll(
+                    editor_line_count,
+                    self.editor_scroll,
+                    self.editor_height,
+                );
+
+                let editor_border = pane_border_style(&theme, self.ui.focus == PaneFocus::Editor);
+                let editor = Paragraph::new(editor_visible).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(editor_title)
+                        .border_style(editor_border),
+                );
+                frame.render_widget(editor, pane_layout.editor);
             }
-            // Strikethrough ~~text~~
-            '~' if chars.peek() == Some(&'~') => {
-                if !current.is_empty() {
-                    spans.push(Span::styled(std::mem::take(&mut current), theme.plain));
+
+            let preview_visible = pane_layout.preview.width > 0 && pane_layout.preview.height > 0;
+            if preview_visible && self.conflict_view_active() {
+                self.draw_conflict_view(frame, pane_layout.preview, &theme);
+            } else if preview_visible {
+                let preview_height = pane_layout.preview.height.saturating_sub(2) as usize;
+                self.preview_height = preview_height.max(1);
+                let preview_width = pane_layout.preview.width.saturating_sub(2);
+                if self.ui.focus == PaneFocus::Editor && !self.ui.view_only {
+                    self.follow_editor_in_large_preview(preview_width);
                 }
-                chars.next(); // consume second ~
-                let mut strike = String::new();
-                while let Some(&next) = chars.peek() {
-                    if next == '~' {
-                        chars.next();
-                        if chars.peek() == Some(&'~') {
-                            chars.next();
-                            break;
-                        }
-                        strike.push('~');
-                        continue;
+                let cached_key = self.preview_cache.as_ref().map(|cache| cache.key);
+                let preview_lines = self.preview_lines_cached(preview_width);
+                let rerendered = cached_key != self.preview_cache.as_ref().map(|cache| cache.key);
+
+                if let Some(anchor) = selected_hunk_row(&preview_lines) {
+                    if anchor < self.preview_scroll {
+                        self.preview_scroll = anchor;
+                    } else if anchor >= self.preview_scroll + preview_height.max(1) {
+                        self.preview_scroll = anchor.saturating_sub(preview_height / 2);
                     }
-                    strike.push(chars.next().unwrap());
+                } else if self.ui.scroll_sync
+                    && self.ui.focus == PaneFocus::Editor
+                    && !self.large_file_mode()
+                {
+                    self.preview_scroll = synced_scroll(
+                        self.editor_scroll,
+                        self.editor.line_count(),
+                        self.editor_height,
+                        preview_lines.len(),
+                        self.preview_height,
+                    );
+                } else if rerendered && let Some(row) = self.anchored_preview_scroll(&preview_lines)
+                {
+                    self.preview_scroll = row;
                 }
-                spans.push(Span::styled(strike, theme.strikethrough));
-            }
-            _ => current.push(c),
+                self.preview_scroll = clamp_scroll(
+                    self.preview_scroll,
+                    preview_lines.len(),
+                    self.preview_height,
+                );
+                self.remember_preview_anchor(&preview_lines);
+
+                let preview_visible = preview_lines
+                    .iter()
+                    .enumerate()
+                    .skip(self.preview_scroll)
+                    .take(self.preview_height)
+                    .map(|(row, line)| {
+                        self.highlight_preview_matches(
+                            line.styled(preview_width, &theme),
+                            row,
+                            &theme,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                // Build preview title with scroll indicator and conflict info
+                let preview_title = preview_title_with_scroll(
+                    self.selected_conflict_hunk,
+                    self.editor.conflict(),
+                    preview_lines.len(),
+                    self.preview_scroll,
+                    self.preview_height,
+                );
+
+                let preview_border = pane_border_style(&theme, self.ui.focus == PaneFocus::Preview);
+                let preview = Paragraph::new(preview_visible)
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(preview_title)
+                            .border_style(preview_border),
+                    )
+                    .wrap(Wrap { trim: false });
+                frame.render_widget(preview, pane_layout.preview);
+            }
+        }
+
+        // Build the styled status bar
+        let (line, col) = self.editor.line_col_at_cursor();
+        let total_lines = self.editor.line_count();
+        // Counting words would rescan the whole buffer on every draw.
+        let words = (!self.large_file_mode()).then(|| word_count(self.editor.text()));
+        let scroll_percent = if total_lines <= 1 {
+            100
+        } else {
+            ((self.editor_scroll as f64 / (total_lines.saturating_sub(1)) as f64) * 100.0)
+                .min(100.0) as u8
+        };
+
+        let mut filename = self
+            .path
+            .as_deref()
+            .map(session::display_name)
+            .unwrap_or_else(|| {
+                if self.home_mode {
+                    "<home>".into()
+                } else if self.stream_mode {
+                    "<stdin>".into()
+                } else {
+                    "<new>".into()
+                }
+            });
+
+        if self.file_count() > 1 {
+            filename = format!(
+                "[{}/{}] {filename}",
+                self.active_file + 1,
+                self.file_count()
+            );
+        }
+
+        let mode = mode_label(self);
+        let severity = self.status.severity();
+        let is_error = severity == Severity::Error;
+        let is_warning = self.editor.is_conflicted() || severity == Severity::Warn;
+
+        // For compact layout, use simple status bar
+        if pane_layout.kind.is_compact() {
+            let base_status = format!("compact | {}", self.status_text());
+            let right_hint = self.status_hint();
+            let stats = position_stats(line, col, total_lines, words);
+            let status_text = compose_status(
+                &base_status,
+                &right_hint,
+                &stats,
+                vertical[2].width as usize,
+            );
+            frame.render_widget(
+                Paragraph::new(status_text).style(status_style(
+                    &theme,
+                    self.editor.is_conflicted(),
+                    severity,
+                )),
+                vertical[2],
+            );
+        } else {
+            // Use rich styled status bar for normal/split layouts
+            let mut message = self.status_text().to_string();
+            if self.perf_mode {
+                message = format!(
+                    "{} | draw={}us watch={} applied={} stream={}",
+                    message,
+                    self.draw_time_us,
+                    self.watch_event_count,
+                    self.watch_applied_count,
+                    self.stream_event_count
+                );
+            }
+
+            let status_config = StatusBarConfig {
+                mode,
+                filename: &filename,
+                dirty: self.editor.dirty,
+                readonly: self.readonly,
+                line,
+                col,
+                total_lines,
+                words,
+                line_ending: self.editor.line_ending(),
+                scroll_percent,
+                message: &message,
+                hint: &self.status_hint(),
+                is_error,
+                is_warning,
+                width: vertical[2].width as usize,
+            };
+
+            let status_line = build_status_bar(&status_config, &theme);
+            frame.render_widget(
+                Paragraph::new(status_line).style(theme.status_bg),
+                vertical[2],
+            );
+        }
+
+        if self.outline_mode && !self.ui.help.open {
+            self.draw_outline(frame, vertical[1], &theme);
+        }
+
+        if self.file_switcher.is_some() && !self.ui.help.open {
+            self.draw_file_switcher(frame, vertical[1], &theme);
+        }
+
+        if self.link_picker.is_some() && !self.ui.help.open {
+            self.draw_link_picker(frame, vertical[1], &theme);
+        }
+
+        if self.snippet_palette.is_some() && !self.ui.help.open {
+            self.draw_snippet_palette(frame, vertical[1], &theme);
+        }
+
+        if self.message_log {
+            self.draw_message_log(frame, vertical[1], &theme);
+        }
+
+        if self.image.shown {
+            frame.render_widget(Clear, cursor_rect(self.preview_area));
+        }
+
+        if self.ui.help.open {
+            self.draw_docs_modal(frame, area, &theme);
+        }
+
+        if !self.ui.help.open {
+            if self.home_mode {
+                let popup = centered_popup(60, 16, vertical[1]);
+                if popup.width > 10 && popup.height > 7 {
+                    // Cursor position: "  Path: " is 8 chars, on row 7 (0-indexed: 6)
+                    let x = popup
+                        .x
+                        .saturating_add(1)
+                        .saturating_add(8)
+                        .saturating_add(self.home_query.chars().count() as u16)
+                        .min(popup.x + popup.width.saturating_sub(2));
+                    let y = popup.y + 7;
+                    frame.set_cursor_position((x, y));
+                }
+            } else if self.ui.focus == PaneFocus::Editor && pane_layout.editor.width > 0 {
+                let cursor_area = self.editor_text_area;
+                let (line, _) = self.editor.line_col_at_cursor();
+                let (visible_line, visible_col) = match self.wrap_width() {
+                    Some(width) => self
+                        .cursor_visual_position(width)
+                        .map_or((u16::MAX, 0), |(row, col)| (row as u16, col)),
+                    None => (
+                        line.saturating_sub(self.editor_scroll) as u16,
+                        self.editor
+                            .display_col_at_cursor()
+                            .saturating_sub(self.editor_hscroll),
+                    ),
+                };
+                if visible_line < cursor_area.height {
+                    let x = (cursor_area.x as usize + visible_col)
+                        .min((cursor_area.x + cursor_area.width.saturating_sub(1)) as usize)
+                        as u16;
+                    let y = cursor_area.y + visible_line;
+                    if self.readonly {
+                        // A block instead of the text caret: the cursor
+                        // still moves, but nothing can be typed there.
+                        frame.buffer_mut()[(x, y)]
+                            .set_style(Style::default().add_modifier(Modifier::REVERSED));
+                    } else {
+                        frame.set_cursor_position((x, y));
+                    }
+                }
+            }
+        }
+    }
+
+    fn draw_outline(&self, frame: &mut Frame<'_>, area: Rect, theme: &ThemeTokens) {
+        let wanted = (self.outline_entries.len() as u16).saturating_add(2);
+        let popup = centered_popup(60, wanted.min(area.height.saturating_sub(2)), area);
+        frame.render_widget(Clear, popup);
+
+        let visible = popup.height.saturating_sub(2).max(1) as usize;
+        let scroll = self.outline_selected.saturating_sub(visible - 1);
+        let lines = self
+            .outline_entries
+            .iter()
+            .enumerate()
+            .skip(scroll)
+            .take(visible)
+            .map(|(idx, entry)| {
+                let indent = "  ".repeat(entry.level.saturating_sub(1) as usize);
+                let style = if idx == self.outline_selected {
+                    theme.selection
+                } else if entry.level == 1 {
+                    theme.heading
+                } else {
+                    theme.plain
+                };
+                Line::from(Span::styled(format!(" {indent}{}", entry.text), style))
+            })
+            .collect::<Vec<_>>();
+
+        let outline = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Outline")
+                .border_style(theme.pane_focus),
+        );
+        frame.render_widget(outline, popup);
+    }
+
+    fn draw_home(&mut self, frame: &mut Frame<'_>, area: Rect, theme: &ThemeTokens) {
+        let popup = centered_popup(64, 18, area);
+        frame.render_widget(Clear, popup);
+
+        // ASCII art logo
+        let logo_style = theme.heading;
+        let subtitle_style = theme.help;
+        let input_label_style = theme.list_bullet;
+        let input_style = theme.plain;
+        let hint_style = Style::default().fg(Color::Rgb(92, 99, 112));
+        let key_style = theme.code;
+
+        let lines = vec![
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("  m", logo_style),
+                Span::styled("arkdown ", subtitle_style),
+                Span::styled("v", logo_style),
+                Span::styled("iewer", subtitle_style),
+            ]),
+            Line::from(Span::styled(
+                "  Write notes in your terminal with live preview",
+                hint_style,
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                "  ─────────────────────────────────────",
+                hint_style,
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                "  New here? Type a file name like notes.md and press Enter.",
+                hint_style,
+            )),
+            Line::from(Span::styled(
+                "  mdv creates the file on first save if it does not exist yet.",
+                hint_style,
+            )),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("  File: ", input_label_style),
+                Span::styled(
+                    if self.home_query.is_empty() {
+                        "notes.md"
+                    } else {
+                        &self.home_query
+                    },
+                    if self.home_query.is_empty() {
+                        hint_style
+                    } else {
+                        input_style
+                    },
+                ),
+            ]),
+            Line::from(""),
+            Line::from(Span::styled(
+                "  ─────────────────────────────────────",
+                hint_style,
+            )),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("  Enter", key_style),
+                Span::styled(" open/create  ", hint_style),
+                Span::styled("Tab", key_style),
+                Span::styled(" complete  ", hint_style),
+                Span::styled("Ctrl+,", key_style),
+                Span::styled(" help  ", hint_style),
+                Span::styled("Ctrl+Q", key_style),
+                Span::styled(" quit", hint_style),
+            ]),
+            Line::from(""),
+        ];
+
+        let home = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.pane_focus),
+        );
+        frame.render_widget(home, popup);
+    }
+
+    fn info_line(&self, width: usize) -> String {
+        let path = self
+            .path
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| {
+                if self.home_mode {
+                    "<home>".into()
+                } else {
+                    "<stream>".into()
+                }
+            });
+        let mode = mode_label(self);
+        // Readonly text is never saved, so clean/dirty does not apply.
+        let access = if self.readonly {
+            "🔒 RO"
+        } else if self.editor.dirty {
+            "RW | dirty"
+        } else {
+            "RW | clean"
+        };
+        let view_mode = match self.ui.focus {
+            PaneFocus::Editor => "editor",
+            PaneFocus::Preview => "view",
+        };
+        let mut line = format!(
+            "{} | {access} | mode={mode} | view={view_mode}",
+            truncate_middle(&path, width.saturating_sub(32).max(12))
+        );
+        if self.file_missing {
+            line.push_str(" | ⚠ missing on disk");
+        }
+        if let Some(review) = &self.review {
+            line.push_str(&format!(" | review {}", review.summary()));
+        }
+        if self.edit_stream && self.stream_done && self.path.is_none() {
+            line.push_str(" | stream → editing");
+        }
+        if self.stream_dropped > 0 {
+            line.push_str(&format!(
+                " | ⚠ {} trimmed",
+                format_bytes(self.stream_dropped)
+            ));
+        }
+        // Dropped first when the line is too long.
+        if let Some(segment) = self.file_info_segment() {
+            let segment = format!(" | {segment}");
+            if line.chars().count() + segment.chars().count() <= width {
+                line.push_str(&segment);
+            }
+        }
+        if line.chars().count() > width {
+            truncate_middle(&line, width).into_owned()
+        } else {
+            line
+        }
+    }
+
+    fn status_hint(&self) -> String {
+        let base = if self.replace_find_mode {
+            "replace: type text to find"
+        } else if self.replace_all_pending.is_some() {
+            "replace all: Enter confirm | Esc cancel"
+        } else if self.replace_with_mode {
+            "replace: type replacement | Ctrl+A all"
+        } else if self.search_mode {
+            "search: type text + Enter"
+        } else if self.goto_mode {
+            "goto: LINE, LINE:COL, +N, -N or N% + Enter"
+        } else if self.save_as_mode {
+            "save as: type a path + Enter | Esc cancel"
+        } else if self.quit_confirm_mode {
+            "s save and quit | q discard | Esc cancel"
+        } else if self.outline_mode {
+            "outline: Up/Down select | Enter jump | Esc close"
+        } else if self.file_switcher.is_some() {
+            "files: Up/Down select | Enter switch | Esc close"
+        } else if self.message_log {
+            "messages: any key closes"
+        } else if self.link_picker.is_some() {
+            "links: Up/Down select | Enter open | Esc close"
+        } else if self.snippet_palette.is_some() {
+            "snippets: type to filter | Up/Down select | Enter insert | Esc close"
+        } else if self.review.is_some() {
+            "review: Ctrl+J/Ctrl+U change | Esc close"
+        } else if self.ui.help.open {
+            "Esc close help"
+        } else if self.ui.view_only && !self.home_mode {
+            "view-only: arrows/PgUp/PgDn scroll | Ctrl+F search | Ctrl+O edit"
+        } else if self.home_mode {
+            "home: type a file name or path + Enter"
+        } else if self.ui.focus == PaneFocus::Editor && !self.readonly && !self.stream_mode {
+            "Type to edit | Shift+Arrows select | Cmd/Alt+Backspace word | Shift+Tab switch panes"
+        } else if self.ui.focus == PaneFocus::Editor && self.readonly && !self.stream_mode {
+            "readonly: arrows move the cursor | Ctrl+F search | Shift+Tab switch panes"
+        } else {
+            "Shift+Tab switch panes | Cmd+,/Ctrl+, help"
+        };
+
+        if let Some(conflict) = self.editor.conflict()
+            && !conflict.hunks.is_empty()
+        {
+            return format!(
+                "{base} | hunk {}/{}",
+                self.selected_conflict_hunk + 1,
+                conflict.hunks.len()
+            );
+        }
+        if let Some(review) = &self.review {
+            return format!(
+                "{base} | change {}/{}",
+                review.selected + 1,
+                review.hunks.len()
+            );
+        }
+        if let Some(counter) = self.search_counter() {
+            return format!("{base} | {counter} | Esc clear");
+        }
+        base.into()
+    }
+}
+
+/// `512 B`, `1.5 KB`, `10.0 MB`, in powers of 1024.
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+fn mode_label(app: &App) -> &'static str {
+    if app.home_mode {
+        "home"
+    } else if app.replace_find_mode || app.replace_with_mode {
+        "replace"
+    } else if app.search_mode {
+        "search"
+    } else if app.goto_mode {
+        "goto"
+    } else if app.save_as_mode {
+        "save as"
+    } else if app.quit_confirm_mode {
+        "quit"
+    } else if app.outline_mode {
+        "outline"
+    } else if app.file_switcher.is_some() {
+        "files"
+    } else if app.link_picker.is_some() {
+        "links"
+    } else if app.snippet_palette.is_some() {
+        "snippets"
+    } else if app.review.is_some() {
+        "review"
+    } else if app.ui.view_only {
+        "view-only"
+    } else if app.stream_mode {
+        "stream"
+    } else if app.editor.is_conflicted() {
+        "conflict"
+    } else {
+        "normal"
+    }
+}
+
+fn pane_border_style(theme: &ThemeTokens, focused: bool) -> Style {
+    if focused {
+        theme.pane_focus
+    } else {
+        theme.pane_border
+    }
+}
+
+fn status_style(theme: &ThemeTokens, conflicted: bool, severity: Severity) -> Style {
+    if severity == Severity::Error {
+        return theme.status_error;
+    }
+    if conflicted || severity == Severity::Warn {
+        return theme.status_warn;
+    }
+    theme.status_ok
+}
+
+/// Generate a scroll indicator string like "━━━━━━━━━░░" representing scroll position
+fn scroll_indicator_bar(total_lines: usize, scroll: usize, visible_height: usize) -> String {
+    const BAR_WIDTH: usize = 8;
+
+    if total_lines <= visible_height {
+        return "────────".to_string();
+    }
+
+    let scrollable_lines = total_lines.saturating_sub(visible_height);
+    let scroll_ratio = if scrollable_lines > 0 {
+        (scroll as f64 / scrollable_lines as f64).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    // Calculate thumb position and size
+    let visible_ratio = (visible_height as f64 / total_lines as f64).clamp(0.1, 1.0);
+    let thumb_size = ((BAR_WIDTH as f64) * visible_ratio).ceil().max(1.0) as usize;
+    let thumb_start = ((BAR_WIDTH - thumb_size) as f64 * scroll_ratio).round() as usize;
+
+    let mut bar = String::new();
+    for i in 0..BAR_WIDTH {
+        if i >= thumb_start && i < thumb_start + thumb_size {
+            bar.push('━');
+        } else {
+            bar.push('─');
+        }
+    }
+    bar
+}
+
+fn editor_title_with_scroll(total_lines: usize, scroll: usize, visible_height: usize) -> String {
+    if total_lines <= visible_height {
+        "Editor".into()
+    } else {
+        let bar = scroll_indicator_bar(total_lines, scroll, visible_height);
+        format!("Editor {}", bar)
+    }
+}
+
+fn preview_title_with_scroll(
+    selected_conflict_hunk: usize,
+    conflict: Option<&mdv_core::ConflictState>,
+    total_lines: usize,
+    scroll: usize,
+    visible_height: usize,
+) -> String {
+    let base = if let Some(conflict) = conflict
+        && !conflict.hunks.is_empty()
+    {
+        format!(
+            "Preview [conflict {}/{}]",
+            selected_conflict_hunk + 1,
+            conflict.hunks.len()
+        )
+    } else {
+        "Preview".into()
+    };
+
+    if total_lines <= visible_height {
+        base
+    } else {
+        let bar = scroll_indicator_bar(total_lines, scroll, visible_height);
+        format!("{} {}", base, bar)
+    }
+}
+
+/// Draw a rendered preview line straight from its segment kinds.
+fn styled_preview_segments(line: &PreviewLine, width: u16, theme: &ThemeTokens) -> Line<'static> {
+    if let [segment] = line.segments.as_slice()
+        && segment.kind == SegmentKind::Rule
+    {
+        let hr_char = "─".repeat(width.saturating_sub(2) as usize);
+        return Line::from(Span::styled(hr_char, theme.hr));
+    }
+    Line::from(
+        line.segments
+            .iter()
+            .map(|segment| {
+                Span::styled(segment.text.clone(), style_for_segment(theme, segment.kind))
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn styled_preview_line(
+    line: &str,
+    width: u16,
+    theme: &ThemeTokens,
+    in_code_block: &mut bool,
+) -> Line<'static> {
+    // Handle code block fences
+    if line.trim_start().starts_with("```") || line.trim() == "$$" {
+        *in_code_block = !*in_code_block;
+        return Line::from(Span::styled(
+            line.to_string(),
+            style_for_segment(theme, SegmentKind::Code),
+        ));
+    }
+    if *in_code_block {
+        return Line::from(Span::styled(
+            line.to_string(),
+            style_for_segment(theme, SegmentKind::Code),
+        ));
+    }
+
+    // Horizontal rule
+    let trimmed = line.trim();
+    if trimmed == "---" || trimmed == "***" || trimmed == "___" {
+        let hr_char = "─".repeat(width.saturating_sub(2) as usize);
+        return Line::from(Span::styled(hr_char, theme.hr));
+    }
+
+    // Headings (# Heading)
+    if trimmed.starts_with('#') {
+        let level = trimmed.chars().take_while(|&c| c == '#').count();
+        if level <= 6 && trimmed.chars().nth(level) == Some(' ') {
+            let prefix = "#".repeat(level);
+            let content = trimmed[level..].trim_start();
+            return Line::from(vec![
+                Span::styled(format!("{} ", prefix), theme.heading),
+                Span::styled(content.to_string(), theme.heading),
+            ]);
+        }
+    }
+
+    // Block quotes (> text)
+    if trimmed.starts_with('>') {
+        let quote_depth = trimmed
+            .chars()
+            .take_while(|&c| c == '>' || c == ' ')
+            .count();
+        let content = &trimmed[quote_depth.min(trimmed.len())..];
+        let prefix = "> ".repeat(quote_depth.div_ceil(2));
+        return Line::from(vec![
+            Span::styled(prefix, theme.quote),
+            Span::styled(content.to_string(), theme.quote),
+        ]);
+    }
+
+    // Task lists
+    if let Some(stripped) = trimmed
+        .strip_prefix("- [x]")
+        .or_else(|| trimmed.strip_prefix("- [X]"))
+    {
+        let content = stripped.trim_start();
+        return Line::from(vec![
+            Span::styled("  ", theme.plain),
+            Span::styled(" ", theme.task_done),
+            Span::styled(format!(" {}", content), theme.strikethrough),
+        ]);
+    }
+    if let Some(stripped) = trimmed.strip_prefix("- [ ]") {
+        let content = stripped.trim_start();
+        return Line::from(vec![
+            Span::styled("  ", theme.plain),
+            Span::styled(" ", theme.task_pending),
+            Span::styled(format!(" {}", content), theme.plain),
+        ]);
+    }
+
+    // Unordered list items (- item)
+    if let Some(stripped) = trimmed.strip_prefix("- ") {
+        let styled_content = style_inline_formatting(stripped, theme);
+        let mut spans = vec![Span::styled("  - ", theme.list_bullet)];
+        spans.extend(styled_content);
+        return Line::from(spans);
+    }
+
+    // Ordered list items (1. item)
+    if let Some((num, rest)) = trimmed.split_once(". ")
+        && num.chars().all(|c| c.is_ascii_digit())
+    {
+        let styled_content = style_inline_formatting(rest, theme);
+        let mut spans = vec![Span::styled(format!(" {}. ", num), theme.list_bullet)];
+        spans.extend(styled_content);
+        return Line::from(spans);
+    }
+
+    // Table header detection (| Col1 | Col2 |)
+    if trimmed.starts_with('|') && trimmed.ends_with('|') {
+        // Check if it's a separator row
+        let inner = &trimmed[1..trimmed.len() - 1];
+        if inner
+            .chars()
+            .all(|c| c == '-' || c == '|' || c == ':' || c == ' ')
+        {
+            return Line::from(Span::styled(line.to_string(), theme.hr));
+        }
+        return Line::from(Span::styled(line.to_string(), theme.table_header));
+    }
+
+    // Regular text with inline formatting
+    let styled_content = style_inline_formatting(line, theme);
+    Line::from(styled_content)
+}
+
+/// Style inline markdown formatting (bold, italic, code, links)
+fn style_inline_formatting(text: &str, theme: &ThemeTokens) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            // Inline code `code`
+            '`' => {
+                if !current.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut current), theme.plain));
+                }
+                let mut code = String::new();
+                while let Some(&next) = chars.peek() {
+                    if next == '`' {
+                        chars.next();
+                        break;
+                    }
+                    code.push(chars.next().unwrap());
+                }
+                spans.push(Span::styled(format!("`{}`", code), theme.code));
+            }
+            // Bold **text** or __text__
+            '*' | '_' if chars.peek() == Some(&c) => {
+                if !current.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut current), theme.plain));
+                }
+                chars.next(); // consume second * or _
+                let mut bold = String::new();
+                while let Some(&next) = chars.peek() {
+                    if next == c {
+                        chars.next();
+                        if chars.peek() == Some(&c) {
+                            chars.next();
+                            break;
+                        }
+                        bold.push(c);
+                        continue;
+                    }
+                    bold.push(chars.next().unwrap());
+                }
+                spans.push(Span::styled(bold, theme.strong));
+            }
+            // Italic *text* or _text_
+            '*' | '_' => {
+                if !current.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut current), theme.plain));
+                }
+                let delim = c;
+                let mut italic = String::new();
+                while let Some(&next) = chars.peek() {
+                    if next == delim {
+                        chars.next();
+                        break;
+                    }
+                    italic.push(chars.next().unwrap());
+                }
+                spans.push(Span::styled(italic, theme.emphasis));
+            }
+            // Links [text](url)
+            '[' => {
+                if !current.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut current), theme.plain));
+                }
+                let mut link_text = String::new();
+                let mut found_close = false;
+                while let Some(&next) = chars.peek() {
+                    if next == ']' {
+                        chars.next();
+                        found_close = true;
+                        break;
+                    }
+                    link_text.push(chars.next().unwrap());
+                }
+                if found_close && chars.peek() == Some(&'(') {
+                    chars.next(); // consume (
+                    let mut url = String::new();
+                    while let Some(&next) = chars.peek() {
+                        if next == ')' {
+                            chars.next();
+                            break;
+                        }
+                        url.push(chars.next().unwrap());
+                    }
+                    spans.push(Span::styled(link_text, theme.link));
+                } else {
+                    // Not a proper link, output as-is
+                    current.push('[');
+                    current.push_str(&link_text);
+                    if found_close {
+                        current.push(']');
+                    }
+                }
+            }
+            // Strikethrough ~~text~~
+            '~' if chars.peek() == Some(&'~') => {
+                if !current.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut current), theme.plain));
+                }
+                chars.next(); // consume second ~
+                let mut strike = String::new();
+                while let Some(&next) = chars.peek() {
+                    if next == '~' {
+                        chars.next();
+                        if chars.peek() == Some(&'~') {
+                            chars.next();
+                            break;
+                        }
+                        strike.push('~');
+                        continue;
+                    }
+                    strike.push(chars.next().unwrap());
+                }
+                spans.push(Span::styled(strike, theme.strikethrough));
+            }
+            _ => current.push(c),
+        }
+    }
+
+    if !current.is_empty() {
+        spans.push(Span::styled(current, theme.plain));
+    }
+
+    if spans.is_empty() {
+        spans.push(Span::styled(String::new(), theme.plain));
+    }
+
+    spans
+}
+
+/// Whether a fenced block is open at `line`. Large files only look back
+/// `CODE_FENCE_LOOKBACK_LINES`, so shading can be off deep inside a long block.
+fn code_open_at(editor: &EditorBuffer, line: usize, large_file: bool) -> bool {
+    let from = if large_file {
+        line.saturating_sub(CODE_FENCE_LOOKBACK_LINES)
+    } else {
+        0
+    };
+    (from..line)
+        .filter(|&idx| toggles_code_block(editor.line_text(idx)))
+        .count()
+        % 2
+        == 1
+}
+
+fn toggles_code_block(line: &str) -> bool {
+    line.trim_start().starts_with("```") || line.trim() == "$$"
+}
+
+fn centered_popup(width_percent: u16, height: u16, area: Rect) -> Rect {
+    let popup_width = area.width.saturating_mul(width_percent) / 100;
+    let popup_height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width.saturating_sub(popup_width)) / 2,
+        y: area.y + (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width.max(10),
+        height: popup_height.max(3),
+    }
+}
+
+fn docs_modal_rect(area: Rect) -> Rect {
+    let max_w = area.width.saturating_sub(4);
+    let width = if max_w >= 80 {
+        area.width.saturating_mul(92).div_ceil(100).clamp(80, max_w)
+    } else {
+        area.width.saturating_sub(2).max(1)
+    };
+    let max_h = area.height.saturating_sub(4);
+    let height = if max_h >= 20 {
+        area.height
+            .saturating_mul(80)
+            .div_ceil(100)
+            .clamp(20, max_h)
+    } else {
+        area.height.saturating_sub(2).max(1)
+    };
+    Rect {
+        x: area.x + area.width.saturating_sub(width) / 2,
+        y: area.y + area.height.saturating_sub(height) / 2,
+        width,
+        height,
+    }
+}
+
+fn onboarding_marker_state() -> (Option<PathBuf>, bool) {
+    let path = onboarding_marker_path();
+    let seen = path.as_ref().is_some_and(|p| p.exists());
+    (path, seen)
+}
+
+fn onboarding_marker_path() -> Option<PathBuf> {
+    if let Some(root) = std::env::var_os("MDV_INSTALL_ROOT")
+        && !root.is_empty()
+    {
+        return Some(PathBuf::from(root).join("state").join("onboarding_seen"));
+    }
+    Some(
+        home_dir()?
+            .join(".mdv")
+            .join("state")
+            .join("onboarding_seen"),
+    )
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+/// Wall-clock `HH:MM:SS` (UTC) for status messages.
+fn clock_hms(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+        % 86_400;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Where `Ctrl+Shift+M` writes the merge of `path`: `notes.md.mdv-merge`.
+fn merge_export_path(path: &Path) -> PathBuf {
+    let mut target = path.as_os_str().to_owned();
+    target.push(".mdv-merge");
+    PathBuf::from(target)
+}
+
+pub(crate) fn expand_tilde(input: &str) -> PathBuf {
+    let rest = match input.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') || rest.starts_with('\\') => rest,
+        _ => return PathBuf::from(input),
+    };
+    match home_dir() {
+        Some(home) => home.join(rest.trim_start_matches(['/', '\\'])),
+        None => PathBuf::from(input),
+    }
+}
+
+/// Nearest line at or around `line` that starts a block (follows a blank
+/// line), searching `forward` or back at most `BLOCK_SNAP_LIMIT` lines.
+fn block_boundary(editor: &EditorBuffer, line: usize, forward: bool) -> usize {
+    let total = editor.line_count();
+    let mut line = line.min(total);
+    for _ in 0..BLOCK_SNAP_LIMIT {
+        if line == 0 || line >= total || editor.line_text(line - 1).trim().is_empty() {
+            break;
+        }
+        if forward {
+            line += 1;
+        } else {
+            line -= 1;
+        }
+    }
+    line
+}
+
+fn clamp_scroll(scroll: usize, total: usize, height: usize) -> usize {
+    if total <= height {
+        0
+    } else {
+        scroll.min(total - height)
+    }
+}
+
+/// Map the editor's scroll position onto the preview proportionally.
+fn synced_scroll(
+    editor_scroll: usize,
+    editor_total: usize,
+    editor_height: usize,
+    preview_total: usize,
+    preview_height: usize,
+) -> usize {
+    let editor_max = editor_total.saturating_sub(editor_height);
+    if editor_max == 0 {
+        return 0;
+    }
+    let preview_max = preview_total.saturating_sub(preview_height);
+    editor_scroll.min(editor_max) * preview_max / editor_max
+}
+
+fn hash_hunks(hunks: &[ConflictHunk], hasher: &mut impl Hasher) {
+    for hunk in hunks {
+        hunk.local_start.hash(hasher);
+        hunk.external_start.hash(hasher);
+        for line in &hunk.local_lines {
+            line.hash(hasher);
+        }
+        for line in &hunk.external_lines {
+            line.hash(hasher);
+        }
+    }
+}
+
+/// The effective bindings as `name  keys` cells, read down then across in
+/// as many columns as `width` fits.
+fn keymap_rows(keymap: &KeyMap, width: usize) -> Vec<String> {
+    let entries = keymap.describe();
+    let name_width = entries
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    let cells = entries
+        .iter()
+        .map(|(name, keys)| format!("  {name:<name_width$}  {keys}"))
+        .collect::<Vec<_>>();
+    let cell_width = cells
+        .iter()
+        .map(|cell| cell.chars().count())
+        .max()
+        .unwrap_or(0)
+        + 2;
+    let columns = (width / cell_width.max(1)).max(1);
+    let rows = cells.len().div_ceil(columns);
+    (0..rows)
+        .map(|row| {
+            let mut line = String::new();
+            for cell in cells.iter().skip(row).step_by(rows) {
+                line.push_str(&format!("{cell:<cell_width$}"));
+            }
+            line.trim_end().to_string()
+        })
+        .collect()
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 { "" } else { "s" }
+}
+
+fn invalid_utf8_status(count: usize) -> String {
+    format!(
+        "Opened with {count} invalid UTF-8 sequence{} replaced",
+        plural(count)
+    )
+}
+
+fn cycle_theme(theme: ThemeChoice, forward: bool) -> ThemeChoice {
+    const ORDER: [ThemeChoice; 4] = [
+        ThemeChoice::Auto,
+        ThemeChoice::Default,
+        ThemeChoice::HighContrast,
+        ThemeChoice::Light,
+    ];
+    let idx = ORDER
+        .iter()
+        .position(|choice| *choice == theme)
+        .unwrap_or(0);
+    let step = if forward { 1 } else { ORDER.len() - 1 };
+    ORDER[(idx + step) % ORDER.len()]
+}
+
+/// Width needed for line number gutter: digits for max line + 1 space separator
+fn line_number_gutter_width(total_lines: usize) -> u16 {
+    let digits = if total_lines == 0 {
+        1
+    } else {
+        (total_lines as f64).log10().floor() as u16 + 1
+    };
+    digits + 2 // digits + " │"
+}
+
+/// Which part of the buffer the editor pane shows; `width` includes the gutter.
+#[derive(Debug, Clone, Copy)]
+struct EditorViewport {
+    scroll: usize,
+    hscroll: usize,
+    height: usize,
+    width: u16,
+    /// Whether a fenced block is open at the first visible line.
+    code_open: bool,
+    /// Soft wrap: long lines take several rows and `hscroll` is ignored.
+    wrap: bool,
+    /// Rows of the first line scrolled past, with `wrap` on.
+    wrap_skip: usize,
+}
+
+/// `misspelled` and `matches` are spell check and search hits in document
+/// order; the search hit starting at the cursor is the current one. `line_numbers` is the cursor line to highlight
+/// in the gutter; `None` hides the gutter.
+fn styled_editor_lines(
+    editor: &EditorBuffer,
+    viewport: EditorViewport,
+    selection: Option<(usize, usize)>,
+    misspelled: &[(usize, usize)],
+    matches: &[(usize, usize)],
+    theme: &ThemeTokens,
+    line_numbers: Option<usize>,
+) -> Vec<Line<'static>> {
+    let EditorViewport {
+        scroll,
+        hscroll,
+        height,
+        width,
+        code_open,
+        wrap,
+        wrap_skip,
+    } = viewport;
+    let total_lines = editor.line_count();
+    let gutter_width = if line_numbers.is_some() {
+        line_number_gutter_width(total_lines)
+    } else {
+        0
+    };
+    let content_width = width.saturating_sub(gutter_width);
+
+    let mut in_code = code_open;
+    let selected_style = theme.selection;
+    (scroll..total_lines.min(scroll.saturating_add(height)))
+        .flat_map(|line_idx| {
+            let line_start = editor.line_start(line_idx);
+            let line = editor.line_text(line_idx);
+            // Line number
+            let line_num_span = line_numbers.map(|current_line| {
+                let line_num_style = if line_idx == current_line {
+                    theme.line_number_current
+                } else {
+                    theme.line_number
+                };
+                let gutter_digits = gutter_width.saturating_sub(2) as usize;
+                let line_num = format!("{:>width$} │", line_idx + 1, width = gutter_digits);
+                Span::styled(line_num, line_num_style)
+            });
+
+            // Content
+            let base = styled_preview_line(line, content_width, theme, &mut in_code);
+            let line_end = line_start + line.len();
+            let mut base_spans = base.spans;
+            let first = misspelled.partition_point(|&(_, end)| end <= line_start);
+            for &(start, end) in misspelled[first..]
+                .iter()
+                .take_while(|&&(start, _)| start < line_end)
+            {
+                base_spans = apply_selection_to_styled_spans(
+                    base_spans,
+                    start - line_start,
+                    end - line_start,
+                    theme.misspelled,
+                );
+            }
+            let first = matches.partition_point(|&(_, end)| end <= line_start);
+            for &(start, end) in matches[first..]
+                .iter()
+                .take_while(|&&(start, _)| start < line_end)
+            {
+                let style = if start == editor.cursor() {
+                    theme.current_match
+                } else {
+                    theme.search_match
+                };
+                base_spans = apply_selection_to_styled_spans(
+                    base_spans,
+                    start.saturating_sub(line_start),
+                    end.saturating_sub(line_start).min(line.len()),
+                    style,
+                );
+            }
+            let mut content_spans = if let Some((sel_start, sel_end)) = selection {
+                if sel_end <= line_start || sel_start >= line_end {
+                    base_spans
+                } else {
+                    let local_start = sel_start.saturating_sub(line_start).min(line.len());
+                    let local_end = sel_end.saturating_sub(line_start).min(line.len());
+                    apply_selection_to_styled_spans(
+                        base_spans,
+                        local_start,
+                        local_end,
+                        selected_style,
+                    )
+                }
+            } else {
+                base_spans
+            };
+            // Secondary cursors are drawn as blocks; the terminal cursor is
+            // the primary one.
+            let cursor_style = Style::default().add_modifier(Modifier::REVERSED);
+            for &cursor in editor
+                .extra_cursors()
+                .iter()
+                .filter(|&&cursor| (line_start..=line_end).contains(&cursor))
+            {
+                let local = cursor - line_start;
+                match line[local..].chars().next() {
+                    Some(ch) => {
+                        content_spans = apply_selection_to_styled_spans(
+                            content_spans,
+                            local,
+                            local + ch.len_utf8(),
+                            cursor_style,
+                        );
+                    }
+                    None => content_spans.push(Span::styled(" ", cursor_style)),
+                }
+            }
+
+            // Combine: line number + content
+            let mut spans = line_num_span.into_iter().collect::<Vec<_>>();
+            if !wrap {
+                spans.extend(slice_spans(content_spans, hscroll, content_width as usize));
+                return vec![Line::from(spans)];
+            }
+            let starts = soft_wrap::wrap_starts(line, content_width as usize);
+            let rows = soft_wrap::wrap_spans(content_spans, &soft_wrap::row_columns(line, &starts));
+            let continuation = (gutter_width > 0)
+                .then(|| Span::styled(" ".repeat(gutter_width as usize), theme.line_number));
+            rows.into_iter()
+                .enumerate()
+                .map(|(row, content)| {
+                    let mut row_spans = if row == 0 {
+                        std::mem::take(&mut spans)
+                    } else {
+                        continuation.clone().into_iter().collect()
+                    };
+                    row_spans.extend(content);
+                    Line::from(row_spans)
+                })
+                .collect()
+        })
+        .skip(if wrap { wrap_skip } else { 0 })
+        .take(height)
+        .collect()
+}
+
+/// Keep the styled text that falls in display columns `start..start + width`.
+fn slice_spans(spans: Vec<Span<'static>>, start: usize, width: usize) -> Vec<Span<'static>> {
+    if start == 0 {
+        return spans;
+    }
+    let end = start + width;
+    let mut col = 0usize;
+    let mut out = Vec::new();
+    for span in spans {
+        let span_start = col;
+        col += span.width();
+        if col <= start || span_start >= end {
+            continue;
+        }
+        let local_start = start.saturating_sub(span_start);
+        let local_width = end.min(col) - span_start.max(start);
+        let visible = slice_columns(&span.content, local_start, local_width).into_owned();
+        if !visible.is_empty() {
+            out.push(Span::styled(visible, span.style));
+        }
+    }
+    out
+}
+
+fn apply_selection_to_styled_spans(
+    spans: Vec<Span<'static>>,
+    local_start: usize,
+    local_end: usize,
+    selected_style: Style,
+) -> Vec<Span<'static>> {
+    let mut out = Vec::new();
+    let mut offset = 0usize;
+
+    for span in spans {
+        let content = span.content.to_string();
+        let span_len = content.len();
+        let span_start = offset;
+        let span_end = span_start + span_len;
+        offset = span_end;
+
+        if local_end <= span_start || local_start >= span_end {
+            out.push(span);
+            continue;
+        }
+
+        let rel_start = local_start.saturating_sub(span_start).min(span_len);
+        let rel_end = local_end.saturating_sub(span_start).min(span_len);
+        let style = span.style;
+        if rel_start > 0 {
+            out.push(Span::styled(content[..rel_start].to_string(), style));
+        }
+        if rel_end > rel_start {
+            out.push(Span::styled(
+                content[rel_start..rel_end].to_string(),
+                style.patch(selected_style),
+            ));
+        }
+        if rel_end < span_len {
+            out.push(Span::styled(content[rel_end..].to_string(), style));
+        }
+    }
+
+    if out.is_empty() {
+        out.push(Span::raw(String::new()));
+    }
+    out
+}
+
+fn cursor_rect(area: Rect) -> Rect {
+    Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
+    }
+}
+
+fn point_in_rect(column: u16, row: u16, rect: Rect) -> bool {
+    if rect.width == 0 || rect.height == 0 {
+        return false;
+    }
+    column >= rect.x
+        && column < rect.x.saturating_add(rect.width)
+        && row >= rect.y
+        && row < rect.y.saturating_add(rect.height)
+}
+
+fn toggle_raw_mode<F>(interactive: bool, mut f: F) -> Result<()>
+where
+    F: FnMut() -> io::Result<()>,
+{
+    if interactive {
+        f()?;
+    }
+    Ok(())
+}
+
+fn next_terminal_input<P, R>(mut poll: P, mut read: R) -> Result<Option<InputEvent>>
+where
+    P: FnMut(Duration) -> io::Result<bool>,
+    R: FnMut() -> io::Result<Event>,
+{
+    if !poll(Duration::from_millis(30))? {
+        return Ok(None);
+    }
+    let event = read()?;
+    match event {
+        // Lone modifier presses only arrive with enhanced key reporting.
+        Event::Key(key) if matches!(key.code, KeyCode::Modifier(_)) => Ok(None),
+        Event::Key(key) if key.kind == event::KeyEventKind::Press => {
+            Ok(Some(InputEvent::Key(input::normalize_key(key))))
+        }
+        Event::Paste(text) => Ok(Some(InputEvent::Paste(text))),
+        Event::Mouse(mouse) if mouse.kind == MouseEventKind::ScrollUp => {
+            Ok(Some(InputEvent::Scroll {
+                direction: -1,
+                column: mouse.column,
+                row: mouse.row,
+            }))
+        }
+        Event::Mouse(mouse) if mouse.kind == MouseEventKind::ScrollDown => {
+            Ok(Some(InputEvent::Scroll {
+                direction: 1,
+                column: mouse.column,
+                row: mouse.row,
+            }))
+        }
+        Event::Mouse(mouse) if mouse.kind == MouseEventKind::Down(MouseButton::Left) => {
+            Ok(Some(InputEvent::MouseDown {
+                column: mouse.column,
+                row: mouse.row,
+                modifiers: mouse.modifiers,
+            }))
+        }
+        Event::Mouse(mouse) if mouse.kind == MouseEventKind::Drag(MouseButton::Left) => {
+            Ok(Some(InputEvent::MouseDrag {
+                column: mouse.column,
+                row: mouse.row,
+            }))
+        }
+        Event::Mouse(mouse) if mouse.kind == MouseEventKind::Up(MouseButton::Left) => {
+            Ok(Some(InputEvent::MouseUp))
+        }
+        Event::Resize(width, height) => Ok(Some(InputEvent::Resize { width, height })),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+fn next_pressed_key<P, R>(poll: P, read: R) -> Result<Option<KeyEvent>>
+where
+    P: FnMut(Duration) -> io::Result<bool>,
+    R: FnMut() -> io::Result<Event>,
+{
+    match next_terminal_input(poll, read)? {
+        Some(InputEvent::Key(key)) => Ok(Some(key)),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::ffi::OsString;
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use std::rc::Rc;
+    use std::sync::atomic::Ordering;
+    use std::sync::{Mutex, mpsc};
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+    use crate::ui::docs;
+    use crossterm::event::{
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, MouseButton,
+        MouseEvent, MouseEventKind,
+    };
+    use mdv_watch::{StreamMessage, WatchMessage};
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+    use ratatui::style::Modifier;
+    use ratatui::text::Span;
+
+    use crate::graphics::{GraphicsProtocol, KITTY_CLEAR};
+    use crate::opener::MemoryOpener;
+    use crate::prefs::{self, Prefs};
+    use crate::ui::theme::build_theme;
+
+    use super::{
+        Action, App, Chord, ClipboardEntry, Command, ConflictSide, DEFAULT_WATCH_DEBOUNCE,
+        EditorBuffer, EditorViewport, FILE_MISSING_GRACE, FILE_MISSING_STATUS, InputEvent, KeyMap,
+        LARGE_PREVIEW_MARGIN_LINES, LineEnding, PaneFocus, PreviewLineKind, SavePolicy, Severity,
+        ThemeChoice, centered_popup, clamp_scroll, clock_hms, cursor_rect, docs_modal_rect,
+        expand_tilde, format_bytes, keymap_rows,
+        link_watch::MAX_LINK_WATCHES,
+        merge_export_path, mode_label, next_pressed_key, next_terminal_input,
+        onboarding_marker_path, pane_border_style, preview_title_with_scroll, scroll_indicator_bar,
+        selected_hunk_row, session, slice_spans,
+        snippets::Snippet,
+        soft_wrap::wrap_starts,
+        state::{HelpTab, SettingItem},
+        status::STATUS_TTL_FRAMES,
+        status_style, styled_editor_lines, styled_preview_line, styled_preview_segments,
+        toggle_raw_mode, update,
+    };
+
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    /// The newest message in the log, shown or not.
+    fn last_logged(app: &App) -> &str {
+        app.status
+            .log()
+            .last()
+            .map_or("", |entry| entry.text.as_str())
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        std::env::temp_dir().join(format!("mdv-app-test-{name}-{nanos}.md"))
+    }
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn set_env_var(key: &str, value: &str) {
+        unsafe { std::env::set_var(key, value) };
+    }
+
+    fn restore_env_var(key: &str, prev: Option<OsString>) {
+        if let Some(value) = prev {
+            unsafe { std::env::set_var(key, value) };
+        } else {
+            unsafe { std::env::remove_var(key) };
+        }
+    }
+
+    #[test]
+    fn clamp_scroll_bounds() {
+        assert_eq!(clamp_scroll(0, 3, 5), 0);
+        assert_eq!(clamp_scroll(1, 10, 4), 1);
+        assert_eq!(clamp_scroll(20, 10, 4), 6);
+    }
+
+    #[test]
+    fn line_count_has_single_empty_line_for_empty_text() {
+        assert_eq!(EditorBuffer::new(String::new()).line_count(), 1);
+    }
+
+    #[test]
+    fn line_count_keeps_trailing_blank_line() {
+        let editor = EditorBuffer::new("a\n".into());
+        assert_eq!(editor.line_count(), 2);
+        assert_eq!(editor.line_text(1), "");
+    }
+
+    #[test]
+    fn remapped_keys_run_their_commands_and_show_in_settings() {
+        let path = temp_path("keymap");
+        fs::write(&path, "one").expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, "one".into()).expect("app");
+        app.interactive_input = false;
+        let loaded = crate::config::parse("[keys]\nsave = \"f2\"\nsearch = \"alt+s\"\n");
+        assert!(loaded.warnings.is_empty(), "{:?}", loaded.warnings);
+        app.set_keymap(loaded.config.keys);
+        let mut running = true;
+
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
+            .expect("type");
+        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::CONTROL), &mut running)
+            .expect("old save");
+        assert_eq!(fs::read_to_string(&path).expect("unsaved"), "one");
+        app.handle_key(key(KeyCode::F(2), KeyModifiers::NONE), &mut running)
+            .expect("save");
+        assert_eq!(fs::read_to_string(&path).expect("saved"), "onex");
+        assert_eq!(app.status_text(), "Saved");
+
+        app.handle_key(key(KeyCode::Char('f'), KeyModifiers::CONTROL), &mut running)
+            .expect("old search");
+        assert!(!app.search_mode);
+        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::ALT), &mut running)
+            .expect("search");
+        assert!(app.search_mode);
+        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
+            .expect("cancel");
+
+        app.handle_key(key(KeyCode::Char(','), KeyModifiers::CONTROL), &mut running)
+            .expect("settings");
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).expect("terminal");
+        let screen = draw_text(&mut app, &mut terminal);
+        assert!(screen.contains("Keys ([keys] in config.toml)"), "{screen}");
+        assert!(screen.contains("save "), "{screen}");
+        assert!(screen.contains("F2"), "{screen}");
+        assert!(screen.contains("Alt+S"), "{screen}");
+
+        let rows = keymap_rows(&KeyMap::default(), 60);
+        assert_eq!(rows.len(), Command::ALL.len());
+        assert!(rows[1].starts_with("  save "), "{:?}", rows[1]);
+        assert!(rows[1].ends_with(" Ctrl+S"), "{:?}", rows[1]);
+        assert!(keymap_rows(&KeyMap::default(), 200).len() < rows.len());
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn handle_key_save_reload_merge_and_keep() {
+        let path = temp_path("save");
+        fs::write(&path, "one").expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, "one".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
+
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
+            .expect("type");
+        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::CONTROL), &mut running)
+            .expect("save");
+        assert_eq!(fs::read_to_string(&path).expect("saved"), "onex");
+        assert_eq!(app.status_text(), "Saved");
+
+        app.editor.insert_char('!');
+        app.editor.on_external_change("disk".into());
+        assert!(app.editor.is_conflicted());
+        app.handle_key(key(KeyCode::Char('k'), KeyModifiers::CONTROL), &mut running)
+            .expect("keep");
+        assert_eq!(app.status_text(), "Kept local");
+
+        app.editor.on_external_change("external".into());
+        app.handle_key(key(KeyCode::Char('m'), KeyModifiers::CONTROL), &mut running)
+            .expect("merge");
+        assert!(app.editor.text().contains("<<<<<<< local"));
+        assert_eq!(app.status_text(), "Merged with conflict markers");
+
+        app.handle_key(key(KeyCode::Char('r'), KeyModifiers::CONTROL), &mut running)
+            .expect("reload");
+        assert_eq!(app.status_text(), "Reloaded from disk");
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn ctrl_shift_m_exports_per_hunk_markers_next_to_the_file() {
+        let path = temp_path("export-merged");
+        let local = "# T\nlocal a\nkeep\nkeep too\nlocal b\n";
+        fs::write(&path, local).expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, local.into()).expect("app");
+        let mut running = true;
+        let ctrl_shift = KeyModifiers::CONTROL | KeyModifiers::SHIFT;
+
+        app.handle_key(key(KeyCode::Char('M'), ctrl_shift), &mut running)
+            .expect("export");
+        assert_eq!(app.status_text(), "No conflict to export");
+
+        app.editor.dirty = true;
+        app.editor
+            .on_external_change("# T\nexternal a\nkeep\nkeep too\nexternal b\n".into());
+        app.handle_key(key(KeyCode::Char('M'), ctrl_shift), &mut running)
+            .expect("export");
+        let target = merge_export_path(&path);
+        assert_eq!(app.status_text(), format!("Wrote {}", target.display()));
+        assert!(target.to_string_lossy().ends_with(".md.mdv-merge"));
+        let merged = fs::read_to_string(&target).expect("merged");
+        assert_eq!(merged.matches("<<<<<<< local").count(), 2);
+        assert!(merged.contains(">>>>>>> external\nkeep\nkeep too\n<<<<<<< local"));
+        assert!(app.editor.is_conflicted());
+        assert_eq!(app.editor.text(), local);
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(&target);
+    }
+
+    #[test]
+    fn handle_key_toggles_task_checkbox() {
+        let path = temp_path("task-toggle");
+        let mut app =
+            App::new_file(path, false, false, false, "- [ ] one\n- two".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
+        let ctrl_space = || key(KeyCode::Char(' '), KeyModifiers::CONTROL);
+
+        app.editor.move_doc_start();
+        app.handle_key(ctrl_space(), &mut running).expect("check");
+        assert_eq!(app.editor.text(), "- [x] one\n- two");
+        assert_eq!(app.status_text(), "Task checked");
+        assert!(app.editor.dirty);
+
+        app.handle_key(ctrl_space(), &mut running).expect("uncheck");
+        assert_eq!(app.editor.text(), "- [ ] one\n- two");
+        assert_eq!(app.status_text(), "Task unchecked");
+
+        app.editor.move_doc_end();
+        app.handle_key(ctrl_space(), &mut running)
+            .expect("plain item");
+        assert_eq!(app.editor.text(), "- [ ] one\n- two");
+        assert_eq!(app.status_text(), "Not a task item");
+
+        app.readonly = true;
+        app.editor.move_doc_start();
+        app.handle_key(ctrl_space(), &mut running)
+            .expect("readonly");
+        assert_eq!(app.editor.text(), "- [ ] one\n- two");
+        assert_eq!(app.status_text(), "Readonly: edit disabled");
+    }
+
+    #[test]
+    fn handle_key_delete_cut_and_paste_line() {
+        let path = temp_path("line-clipboard");
+        let mut app =
+            App::new_file(path, false, false, false, "one\ntwo\nthree".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
+
+        app.handle_key(key(KeyCode::Char('v'), KeyModifiers::CONTROL), &mut running)
+            .expect("paste empty");
+        assert_eq!(app.status_text(), "Nothing to paste");
+
+        app.editor.move_doc_start();
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::CONTROL), &mut running)
+            .expect("cut");
+        assert_eq!(app.editor.text(), "two\nthree");
+        assert_eq!(app.status_text(), "Cut line");
+
+        app.handle_key(key(KeyCode::Char('d'), KeyModifiers::CONTROL), &mut running)
+            .expect("delete");
+        assert_eq!(app.editor.text(), "three");
+        assert_eq!(app.status_text(), "Deleted line");
+
+        app.handle_key(key(KeyCode::Char('v'), KeyModifiers::CONTROL), &mut running)
+            .expect("paste");
+        assert_eq!(app.editor.text(), "one\nthree");
+        assert_eq!(app.status_text(), "Pasted line");
+
+        app.readonly = true;
+        app.handle_key(key(KeyCode::Char('d'), KeyModifiers::CONTROL), &mut running)
+            .expect("readonly");
+        assert_eq!(app.editor.text(), "one\nthree");
+        assert_eq!(app.status_text(), "Readonly: edit disabled");
+    }
+
+    #[test]
+    fn quit_with_dirty_buffer_asks_and_esc_cancels() {
+        let path = temp_path("quit-confirm-cancel");
+        fs::write(&path, "x").expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, "x".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
+
+        app.handle_key(key(KeyCode::Char('y'), KeyModifiers::NONE), &mut running)
+            .expect("edit");
+        app.handle_key(key(KeyCode::Char('q'), KeyModifiers::CONTROL), &mut running)
+            .expect("quit");
+        assert!(running);
+        assert!(app.quit_confirm_mode);
+        assert_eq!(mode_label(&app), "quit");
+        assert_eq!(
+            app.status_text(),
+            "Unsaved changes: [s]ave and quit / [q]uit without saving / [Esc] cancel"
+        );
+
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
+            .expect("ignored key");
+        assert_eq!(app.editor.text(), "xy");
+        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
+            .expect("cancel");
+        assert!(running);
+        assert!(!app.quit_confirm_mode);
+        assert_eq!(app.status_text(), "Quit cancelled");
+
+        let _ = fs::remove_file(&path);
+    }
+
+    fn two_files(name: &str) -> (App, PathBuf, PathBuf) {
+        let first = temp_path(&format!("{name}-readme"));
+        let second = temp_path(&format!("{name}-changelog"));
+        fs::write(&first, "readme").expect("seed first");
+        fs::write(&second, "changes").expect("seed second");
+        let mut app =
+            App::new_file(first.clone(), false, false, false, "readme".into()).expect("app");
+        app.interactive_input = false;
+        app.add_file(second.clone(), mdv_core::decode_text(b"changes"))
+            .expect("add file");
+        (app, first, second)
+    }
+
+    #[test]
+    fn ctrl_page_keys_cycle_files_with_their_own_state() {
+        let (mut app, first, second) = two_files("multi-cycle");
+        let mut running = true;
+        app.editor.move_doc_end();
+        app.handle_key(key(KeyCode::Char('!'), KeyModifiers::NONE), &mut running)
+            .expect("edit first");
+        app.editor_scroll = 3;
+
+        app.handle_key(key(KeyCode::PageDown, KeyModifiers::CONTROL), &mut running)
+            .expect("next file");
+        assert_eq!(app.path.as_deref(), Some(second.as_path()));
+        assert_eq!(app.editor.text(), "changes");
+        assert!(!app.editor.dirty);
+        assert_eq!(app.editor_scroll, 0);
+        assert_eq!(
+            app.status_text(),
+            format!("[2/2] {}", session::display_name(&second))
+        );
+
+        app.handle_key(key(KeyCode::PageDown, KeyModifiers::CONTROL), &mut running)
+            .expect("wraps around");
+        assert_eq!(app.path.as_deref(), Some(first.as_path()));
+        assert_eq!(app.editor.text(), "readme!");
+        assert!(app.editor.dirty);
+        assert_eq!(app.editor_scroll, 3);
+
+        app.handle_key(key(KeyCode::PageUp, KeyModifiers::CONTROL), &mut running)
+            .expect("previous file");
+        assert_eq!(app.editor.text(), "changes");
+
+        let _ = fs::remove_file(&first);
+        let _ = fs::remove_file(&second);
+    }
+
+    #[test]
+    fn status_bar_shows_the_file_position() {
+        let (mut app, first, second) = two_files("multi-status");
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).expect("terminal");
+        let text = draw_text(&mut app, &mut terminal);
+        assert!(text.contains("[1/2] mdv-"), "{text}");
+
+        let _ = fs::remove_file(&first);
+        let _ = fs::remove_file(&second);
+    }
+
+    #[test]
+    fn alt_o_opens_urls_outside_and_markdown_links_in_mdv() {
+        let dir = temp_path("links").with_extension("");
+        fs::create_dir_all(&dir).expect("mkdir");
+        let path = dir.join("index.md");
+        let text = "# Index\n\n[Next](next.md) and [site](https://example.com)\n\n[Top](#index)\n";
+        fs::write(&path, text).expect("seed");
+        fs::write(dir.join("next.md"), "# Next\n").expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, text.into()).expect("app");
+        let opened = Rc::new(RefCell::new(Vec::new()));
+        app.opener = Box::new(MemoryOpener {
+            opened: Rc::clone(&opened),
+            unavailable: false,
+        });
+        let mut running = true;
+        let alt_o = key(KeyCode::Char('o'), KeyModifiers::ALT);
+
+        app.editor.goto_line(5);
+        app.handle_key(alt_o, &mut running).expect("anchor");
+        assert_eq!(app.status_text(), "Jumped to: Index");
+        assert_eq!(app.editor.line_col_at_cursor().0, 0);
+
+        app.handle_key(alt_o, &mut running).expect("picker");
+        assert_eq!(app.status_text(), "Links: 3");
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).expect("terminal");
+        assert!(draw_text(&mut app, &mut terminal).contains("site - https://example.com"));
+        app.handle_key(key(KeyCode::Down, KeyModifiers::NONE), &mut running)
+            .expect("down");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("open url");
+        assert!(app.link_picker.is_none());
+        assert_eq!(*opened.borrow(), ["https://example.com"]);
+        assert_eq!(app.status_text(), "Opened https://example.com");
+
+        app.editor.dirty = true;
+        app.handle_key(alt_o, &mut running).expect("picker");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("blocked");
+        assert_eq!(
+            app.status_text(),
+            "Unsaved changes: save before opening next.md"
+        );
+        assert_eq!(app.path.as_deref(), Some(path.as_path()));
+
+        app.editor.dirty = false;
+        app.handle_key(alt_o, &mut running).expect("picker");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("open markdown");
+        assert_eq!(app.path.as_deref(), Some(dir.join("next.md").as_path()));
+        assert_eq!(app.editor.text(), "# Next\n");
+        assert_eq!(opened.borrow().len(), 1);
+
+        app.handle_key(alt_o, &mut running).expect("no links");
+        assert_eq!(app.status_text(), "No links from line 2");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn alt_o_reports_when_nothing_can_open_a_url() {
+        let text = "[site](https://example.com)\n";
+        let mut app =
+            App::new_file(temp_path("link-fail"), false, false, false, text.into()).expect("app");
+        app.opener = Box::new(MemoryOpener {
+            unavailable: true,
+            ..MemoryOpener::default()
+        });
+        app.set_initial_focus(PaneFocus::Preview);
+        let mut running = true;
+        app.handle_key(key(KeyCode::Char('o'), KeyModifiers::ALT), &mut running)
+            .expect("open");
+        assert_eq!(app.status_text(), "Open error: xdg-open: not found");
+    }
+
+    #[test]
+    fn alt_i_draws_local_images_and_falls_back_to_text() {
+        let dir = std::env::temp_dir().join(format!("mdv-app-image-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("dir");
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        png.extend([0, 0, 0, 200, 0, 0, 0, 50]);
+        fs::write(dir.join("shot.png"), &png).expect("png");
+        let text = "# Shots\n\n![Main window](shot.png)\n\n![gone](missing.png)\n\n\
+                    ![remote](https://example.com/a.png)\n";
+        let mut app =
+            App::new_file(dir.join("doc.md"), false, false, false, text.into()).expect("app");
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).expect("terminal");
+        draw_text(&mut app, &mut terminal);
+        let mut running = true;
+        let alt_i = key(KeyCode::Char('i'), KeyModifiers::ALT);
+
+        app.handle_key(alt_i, &mut running).expect("editor focus");
+        assert_eq!(
+            app.status_text(),
+            "Images show from the preview: Shift+Tab to focus it"
+        );
+        app.set_initial_focus(PaneFocus::Preview);
+
+        // Without a capable terminal the preview keeps the text form.
+        app.handle_key(alt_i, &mut running).expect("no protocol");
+        assert!(
+            app.status_text()
+                .starts_with("Image: Main window (this terminal cannot draw images")
+        );
+        assert!(!app.image.shown);
+        assert!(draw_text(&mut app, &mut terminal).contains("Main window"));
+
+        app.image.protocol = Some(GraphicsProtocol::Kitty);
+        app.handle_key(alt_i, &mut running).expect("kitty");
+        assert_eq!(app.status_text(), "Image: Main window (any key closes)");
+        draw_text(&mut app, &mut terminal);
+        let preview = cursor_rect(app.preview_area);
+        let buffer = terminal.backend().buffer();
+        assert!(
+            (preview.top()..preview.bottom())
+                .flat_map(|y| (preview.left()..preview.right()).map(move |x| (x, y)))
+                .all(|(x, y)| buffer[(x, y)].symbol() == " ")
+        );
+        let mut out = Vec::new();
+        app.flush_image(&mut out).expect("flush");
+        let out = String::from_utf8(out).expect("utf8");
+        let at = format!("\x1b7\x1b[{};{}H", preview.y + 1, preview.x + 1);
+        assert!(out.starts_with(&format!("{at}\x1b_Ga=T,f=100,")), "{out:?}");
+        assert!(out.ends_with("\x1b\\\x1b8"));
+
+        app.handle_key(key(KeyCode::Down, KeyModifiers::NONE), &mut running)
+            .expect("close");
+        assert!(!app.image.shown);
+        assert!(app.image.redraw);
+        assert_eq!(app.status_text(), "Image closed");
+        let mut out = Vec::new();
+        app.flush_image(&mut out).expect("flush");
+        assert_eq!(out, KITTY_CLEAR.as_bytes());
+        assert_eq!(app.preview_scroll, 0);
+
+        app.preview_scroll = app.preview_line_for_source(4);
+        app.handle_key(alt_i, &mut running).expect("missing");
+        assert!(app.status_text().starts_with(&format!(
+            "Image error: {}: ",
+            dir.join("missing.png").display()
+        )));
+        app.preview_scroll = app.preview_line_for_source(6);
+        app.handle_key(alt_i, &mut running).expect("remote");
+        assert_eq!(
+            app.status_text(),
+            "Image error: https://example.com/a.png is not a local file"
+        );
+        assert!(!app.image.shown);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn file_switcher_lists_files_and_switches() {
+        let (mut app, first, second) = two_files("multi-switcher");
+        let mut running = true;
+
+        app.handle_key(key(KeyCode::Char('b'), KeyModifiers::CONTROL), &mut running)
+            .expect("open switcher");
+        assert_eq!(mode_label(&app), "files");
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).expect("terminal");
+        let text = draw_text(&mut app, &mut terminal);
+        assert!(text.contains("Open files"));
+        assert!(text.contains(&format!("2. {}", session::display_name(&second))));
+
+        app.handle_key(key(KeyCode::Down, KeyModifiers::NONE), &mut running)
+            .expect("select");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("switch");
+        assert!(app.file_switcher.is_none());
+        assert_eq!(app.editor.text(), "changes");
+
+        app.handle_key(key(KeyCode::Char('b'), KeyModifiers::CONTROL), &mut running)
+            .expect("open again");
+        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
+            .expect("close");
+        assert!(app.file_switcher.is_none());
+        assert_eq!(app.editor.text(), "changes");
+
+        let _ = fs::remove_file(&first);
+        let _ = fs::remove_file(&second);
+    }
+
+    #[test]
+    fn ctrl_w_closes_a_file_and_asks_twice_when_dirty() {
+        let (mut app, first, second) = two_files("multi-close");
+        let mut running = true;
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
+            .expect("edit");
+
+        app.handle_key(key(KeyCode::Char('w'), KeyModifiers::CONTROL), &mut running)
+            .expect("close dirty");
+        assert_eq!(app.file_count(), 2);
+        assert!(app.status_text().starts_with("Unsaved changes in"));
+
+        app.handle_key(key(KeyCode::Char('w'), KeyModifiers::CONTROL), &mut running)
+            .expect("close anyway");
+        assert_eq!(app.file_count(), 1);
+        assert_eq!(app.editor.text(), "changes");
+        assert_eq!(app.path.as_deref(), Some(second.as_path()));
+
+        app.handle_key(key(KeyCode::Char('w'), KeyModifiers::CONTROL), &mut running)
+            .expect("last file");
+        assert_eq!(app.file_count(), 1);
+        assert_eq!(app.status_text(), "Only one file open (Ctrl+Q quits)");
+        assert_eq!(fs::read_to_string(&first).expect("read"), "readme");
+
+        let _ = fs::remove_file(&first);
+        let _ = fs::remove_file(&second);
+    }
+
+    #[test]
+    fn quit_asks_about_each_dirty_file() {
+        let (mut app, first, second) = two_files("multi-quit");
+        let mut running = true;
+        app.handle_key(key(KeyCode::PageDown, KeyModifiers::CONTROL), &mut running)
+            .expect("second file");
+        app.handle_key(key(KeyCode::Char('!'), KeyModifiers::NONE), &mut running)
+            .expect("edit second");
+        app.handle_key(key(KeyCode::PageUp, KeyModifiers::CONTROL), &mut running)
+            .expect("first file");
+
+        app.handle_key(key(KeyCode::Char('q'), KeyModifiers::CONTROL), &mut running)
+            .expect("quit");
+        assert!(running);
+        assert!(app.quit_confirm_mode);
+        assert_eq!(app.path.as_deref(), Some(second.as_path()));
+        assert!(app.status_text().starts_with(&format!(
+            "Unsaved changes in {}:",
+            session::display_name(&second)
+        )));
+
+        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::NONE), &mut running)
+            .expect("save and quit");
+        assert!(!running);
+        assert_eq!(fs::read_to_string(&second).expect("read"), "changes!");
+        assert_eq!(fs::read_to_string(&first).expect("read"), "readme");
+
+        let _ = fs::remove_file(&first);
+        let _ = fs::remove_file(&second);
+    }
+
+    #[test]
+    fn quit_confirm_save_writes_then_exits_and_discard_exits() {
+        let path = temp_path("quit-confirm-save");
+        fs::write(&path, "x").expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, "x".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
+        app.handle_key(key(KeyCode::Char('y'), KeyModifiers::NONE), &mut running)
+            .expect("edit");
+        app.handle_key(key(KeyCode::Char('q'), KeyModifiers::CONTROL), &mut running)
+            .expect("quit");
+        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::NONE), &mut running)
+            .expect("save and quit");
+        assert!(!running);
+        assert_eq!(fs::read_to_string(&path).expect("read"), "xy");
+
+        let mut discard =
+            App::new_file(path.clone(), false, false, false, "xy".into()).expect("app");
+        discard.interactive_input = false;
+        let mut running = true;
+        discard
+            .handle_key(key(KeyCode::Char('z'), KeyModifiers::NONE), &mut running)
+            .expect("edit");
+        discard
+            .handle_key(key(KeyCode::Char('q'), KeyModifiers::CONTROL), &mut running)
+            .expect("quit");
+        discard
+            .handle_key(key(KeyCode::Char('q'), KeyModifiers::NONE), &mut running)
+            .expect("discard");
+        assert!(!running);
+        assert_eq!(fs::read_to_string(&path).expect("read"), "xy");
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn quit_confirm_save_error_cancels_quit() {
+        let dir = temp_path("quit-confirm-dir");
+        fs::create_dir_all(&dir).expect("mkdir");
+        let mut app = App::new_file(dir.clone(), false, false, false, "x".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
+        app.handle_key(key(KeyCode::Char('y'), KeyModifiers::NONE), &mut running)
+            .expect("edit");
+        app.handle_key(key(KeyCode::Char('q'), KeyModifiers::CONTROL), &mut running)
+            .expect("quit");
+        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::NONE), &mut running)
+            .expect("save fails");
+        assert!(running);
+        assert!(!app.quit_confirm_mode);
+        assert!(
+            app.status_text().starts_with("Save error:"),
+            "{}",
+            app.status_text()
+        );
+        assert!(app.status_text().ends_with("(quit cancelled)"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn quit_skips_confirmation_for_readonly_and_stream() {
+        let path = temp_path("quit-readonly");
+        let mut readonly = App::new_file(path, true, false, false, "x".into()).expect("app");
+        readonly.interactive_input = false;
+        readonly.editor.dirty = true;
+        let mut running = true;
+        readonly
+            .handle_key(key(KeyCode::Char('q'), KeyModifiers::CONTROL), &mut running)
+            .expect("quit readonly");
+        assert!(!running);
+
+        let mut stream = App::new_stream_for_test(false);
+        stream.editor.dirty = true;
+        let mut running = true;
+        stream
+            .handle_key(key(KeyCode::Char('q'), KeyModifiers::CONTROL), &mut running)
+            .expect("quit stream");
+        assert!(!running);
+    }
+
+    #[test]
+    fn save_as_writes_stream_buffer_and_switches_to_file_mode() {
+        let dir = temp_path("save-as-dir");
+        fs::create_dir_all(&dir).expect("mkdir");
+        let target = dir.join("out.md");
+        let mut app = App::new_stream_for_test(false);
+        app.interactive_input = false;
+        app.editor = EditorBuffer::new("# streamed".into());
+        let mut running = true;
+
+        app.handle_key(
+            key(
+                KeyCode::Char('S'),
+                KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+            ),
+            &mut running,
+        )
+        .expect("open save as");
+        assert!(app.save_as_mode);
+        assert_eq!(mode_label(&app), "save as");
+
+        for c in dir.to_string_lossy().chars() {
+            app.handle_key(key(KeyCode::Char(c), KeyModifiers::NONE), &mut running)
+                .expect("type dir");
+        }
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("save to directory");
+        assert!(app.save_as_mode);
+        assert!(
+            app.status_text().starts_with("Save as error:"),
+            "{}",
+            app.status_text()
+        );
+
+        for c in "/out.md".chars() {
+            app.handle_key(key(KeyCode::Char(c), KeyModifiers::NONE), &mut running)
+                .expect("type name");
         }
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("save as");
+        assert!(!app.save_as_mode);
+        assert!(!app.stream_mode);
+        assert!(!app.readonly);
+        assert_eq!(app.path.as_deref(), Some(target.as_path()));
+        assert_eq!(fs::read_to_string(&target).expect("read"), "# streamed");
+        assert_eq!(app.status_text(), format!("Saved as {}", target.display()));
+
+        app.handle_key(key(KeyCode::Char('!'), KeyModifiers::NONE), &mut running)
+            .expect("edit");
+        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::CONTROL), &mut running)
+            .expect("save");
+        assert_eq!(fs::read_to_string(&target).expect("read"), "# streamed!");
+
+        let _ = fs::remove_dir_all(&dir);
     }
 
-    if !current.is_empty() {
-        spans.push(Span::styled(current, theme.plain));
+    #[test]
+    fn expand_tilde_uses_home_dir() {
+        let _guard = ENV_LOCK.lock().expect("env lock");
+        let prior_home = std::env::var_os("HOME");
+        set_env_var("HOME", "/tmp/mdv-tilde-home");
+        assert_eq!(
+            expand_tilde("~/notes/a.md"),
+            PathBuf::from("/tmp/mdv-tilde-home/notes/a.md")
+        );
+        assert_eq!(expand_tilde("~"), PathBuf::from("/tmp/mdv-tilde-home"));
+        assert_eq!(expand_tilde("~other/a.md"), PathBuf::from("~other/a.md"));
+        assert_eq!(expand_tilde("rel/a.md"), PathBuf::from("rel/a.md"));
+        restore_env_var("HOME", prior_home);
     }
 
-    if spans.is_empty() {
-        spans.push(Span::styled(String::new(), theme.plain));
+    #[test]
+    fn handle_key_copy_cut_paste_selection() {
+        let path = temp_path("selection-clipboard");
+        let mut app =
+            App::new_file(path, false, false, false, "first\nsecond".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
+
+        app.editor.move_doc_start();
+
+        for _ in 0..3 {
+            app.handle_key(key(KeyCode::Right, KeyModifiers::SHIFT), &mut running)
+                .expect("extend");
+        }
+        app.handle_key(key(KeyCode::Down, KeyModifiers::SHIFT), &mut running)
+            .expect("extend down");
+        assert_eq!(app.selection_range(), Some((0, 9)));
+        app.handle_key(key(KeyCode::Char('c'), KeyModifiers::CONTROL), &mut running)
+            .expect("copy");
+        assert_eq!(app.status_text(), "Copied 9 chars");
+
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::CONTROL), &mut running)
+            .expect("cut");
+        assert_eq!(app.editor.text(), "ond");
+        assert_eq!(app.status_text(), "Cut 9 chars");
+        assert!(app.selection_range().is_none());
+
+        app.handle_key(key(KeyCode::End, KeyModifiers::NONE), &mut running)
+            .expect("end");
+        app.handle_key(key(KeyCode::Char('v'), KeyModifiers::CONTROL), &mut running)
+            .expect("paste");
+        assert_eq!(app.editor.text(), "ondfirst\nsec");
+        assert_eq!(app.status_text(), "Pasted");
+
+        app.handle_key(key(KeyCode::Left, KeyModifiers::SHIFT), &mut running)
+            .expect("select one");
+        app.handle_key(key(KeyCode::Left, KeyModifiers::NONE), &mut running)
+            .expect("collapse");
+        assert!(app.selection_range().is_none());
+
+        app.editor.set_selection_anchor(0);
+        app.editor.set_cursor(3);
+        app.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE), &mut running)
+            .expect("backspace selection");
+        assert_eq!(app.editor.text(), "first\nsec");
     }
 
-    spans
-}
+    #[test]
+    fn handle_key_clipboard_round_trips_with_system_clipboard() {
+        let path = temp_path("system-clipboard");
+        let mut app = App::new_file(path, false, false, false, "one\ntwo".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
+
+        app.editor.move_doc_start();
+        app.handle_key(key(KeyCode::Char('c'), KeyModifiers::CONTROL), &mut running)
+            .expect("copy line");
+        assert_eq!(app.status_text(), "Copied line");
+        assert_eq!(app.system_clipboard.get_text(), Ok(Some("one".to_string())));
+
+        app.handle_key(key(KeyCode::Char('v'), KeyModifiers::CONTROL), &mut running)
+            .expect("paste own line");
+        assert_eq!(app.editor.text(), "one\none\ntwo");
+        assert_eq!(app.status_text(), "Pasted line");
+
+        app.system_clipboard
+            .set_text("from browser")
+            .expect("seed system clipboard");
+        app.editor.move_doc_end();
+        app.handle_key(key(KeyCode::Char('v'), KeyModifiers::CONTROL), &mut running)
+            .expect("paste system");
+        assert_eq!(app.editor.text(), "one\none\ntwofrom browser");
+        assert_eq!(app.status_text(), "Pasted");
+        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::CONTROL), &mut running)
+            .expect("undo paste");
+        assert_eq!(app.editor.text(), "one\none\ntwo");
+
+        app.editor.set_selection_anchor(0);
+        app.editor.set_cursor(3);
+        app.handle_key(
+            key(
+                KeyCode::Char('C'),
+                KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+            ),
+            &mut running,
+        )
+        .expect("ctrl+shift+c");
+        assert_eq!(app.status_text(), "Copied 3 chars");
+    }
+
+    #[test]
+    fn handle_key_clipboard_falls_back_to_internal_when_unavailable() {
+        let path = temp_path("clipboard-fallback");
+        let mut app = App::new_file(path, false, false, false, "abc".into()).expect("app");
+        app.interactive_input = false;
+        app.system_clipboard = Box::new(crate::clipboard::MemoryClipboard {
+            text: None,
+            unavailable: true,
+        });
+        let mut running = true;
+
+        app.editor.set_selection_anchor(0);
+        app.editor.set_cursor(2);
+        app.handle_key(key(KeyCode::Char('c'), KeyModifiers::CONTROL), &mut running)
+            .expect("copy");
+        assert_eq!(
+            app.status_text(),
+            "Copied 2 chars (system clipboard unavailable, using internal)"
+        );
+        app.editor.clear_selection();
+        app.editor.move_doc_end();
+        app.handle_key(key(KeyCode::Char('v'), KeyModifiers::CONTROL), &mut running)
+            .expect("paste");
+        assert_eq!(app.editor.text(), "abcab");
+        assert_eq!(
+            app.status_text(),
+            "Pasted (system clipboard unavailable, using internal)"
+        );
+        assert!(running);
+    }
+
+    #[test]
+    fn a_clipboard_without_text_pastes_nothing() {
+        let path = temp_path("clipboard-image");
+        let mut app = App::new_file(path, false, false, false, "abc".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
+        app.clipboard = Some(ClipboardEntry::Text("stale".into()));
+        // Another app put an image on the clipboard: it works, but has no
+        // text for mdv.
+        app.system_clipboard = Box::new(crate::clipboard::MemoryClipboard::default());
+
+        app.handle_key(key(KeyCode::Char('v'), KeyModifiers::CONTROL), &mut running)
+            .expect("paste");
+        assert_eq!(app.editor.text(), "abc");
+        assert_eq!(app.status_text(), "Nothing to paste");
+    }
 
-fn code_open_before(lines: &[String], scroll: usize) -> bool {
-    let mut open = false;
-    for line in lines.iter().take(scroll) {
-        if line.trim_start().starts_with("```") || line.trim() == "$$" {
-            open = !open;
+    #[test]
+    fn handle_key_search_toggles_case_and_whole_word() {
+        let path = temp_path("search-options");
+        let mut app = App::new_file(path, false, false, false, "intro\nTables and Table".into())
+            .expect("app");
+        app.interactive_input = false;
+        let mut running = true;
+
+        app.editor.move_doc_start();
+        app.handle_key(key(KeyCode::Char('f'), KeyModifiers::CONTROL), &mut running)
+            .expect("search");
+        app.handle_key(key(KeyCode::Char('c'), KeyModifiers::ALT), &mut running)
+            .expect("toggle case");
+        assert_eq!(app.status_text(), "Search [i]: ");
+        app.handle_key(key(KeyCode::Char('w'), KeyModifiers::ALT), &mut running)
+            .expect("toggle word");
+        assert_eq!(app.status_text(), "Search [iw]: ");
+        for c in "table".chars() {
+            app.handle_key(key(KeyCode::Char(c), KeyModifiers::NONE), &mut running)
+                .expect("type");
         }
+        assert_eq!(app.status_text(), "Search [iw]: table (1 match)");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("enter");
+        assert_eq!(app.editor.line_col_at_cursor(), (1, 11));
+        assert_eq!(app.status_text(), "Found: table");
+
+        app.handle_key(key(KeyCode::Char('h'), KeyModifiers::CONTROL), &mut running)
+            .expect("replace");
+        assert_eq!(app.status_text(), "Replace find [iw]: ");
     }
-    open
-}
 
-fn centered_popup(width_percent: u16, height: u16, area: Rect) -> Rect {
-    let popup_width = area.width.saturating_mul(width_percent) / 100;
-    let popup_height = height.min(area.height);
-    Rect {
-        x: area.x + (area.width.saturating_sub(popup_width)) / 2,
-        y: area.y + (area.height.saturating_sub(popup_height)) / 2,
-        width: popup_width.max(10),
-        height: popup_height.max(3),
+    #[test]
+    fn handle_key_home_uses_smart_line_start_and_ctrl_jumps_doc() {
+        let path = temp_path("smart-home");
+        let mut app =
+            App::new_file(path, false, false, false, "top\n    indented\n".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
+
+        app.editor.set_cursor_line_col(1, 8);
+        app.handle_key(key(KeyCode::Home, KeyModifiers::NONE), &mut running)
+            .expect("home");
+        assert_eq!(app.editor.line_col_at_cursor(), (1, 4));
+        app.handle_key(key(KeyCode::Home, KeyModifiers::NONE), &mut running)
+            .expect("home again");
+        assert_eq!(app.editor.line_col_at_cursor(), (1, 0));
+        app.handle_key(key(KeyCode::End, KeyModifiers::CONTROL), &mut running)
+            .expect("ctrl end");
+        assert_eq!(app.editor.line_col_at_cursor(), (2, 0));
+        app.handle_key(key(KeyCode::Home, KeyModifiers::CONTROL), &mut running)
+            .expect("ctrl home");
+        assert_eq!(app.editor.cursor(), 0);
+    }
+
+    #[test]
+    fn handle_key_ctrl_arrows_jump_words() {
+        let path = temp_path("word-jump");
+        let mut app = App::new_file(path, false, false, false, "héllo, wörld".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
+
+        app.editor.move_doc_start();
+        app.handle_key(key(KeyCode::Right, KeyModifiers::CONTROL), &mut running)
+            .expect("ctrl right");
+        assert_eq!(&app.editor.text()[..app.editor.cursor()], "héllo");
+        app.handle_key(key(KeyCode::Right, KeyModifiers::CONTROL), &mut running)
+            .expect("ctrl right 2");
+        assert_eq!(app.editor.cursor(), app.editor.text().len());
+        app.handle_key(key(KeyCode::Left, KeyModifiers::CONTROL), &mut running)
+            .expect("ctrl left");
+        assert_eq!(&app.editor.text()[app.editor.cursor()..], "wörld");
+    }
+
+    #[test]
+    fn handle_key_undo_redo() {
+        let path = temp_path("undo-redo");
+        fs::write(&path, "x").expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, "x".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
+
+        app.handle_key(key(KeyCode::Char('a'), KeyModifiers::NONE), &mut running)
+            .expect("insert");
+        assert_eq!(app.editor.text(), "xa");
+
+        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::CONTROL), &mut running)
+            .expect("undo");
+        assert_eq!(app.editor.text(), "x");
+        assert_eq!(app.status_text(), "Undo | undo 0 / redo 1");
+
+        app.handle_key(key(KeyCode::Char('y'), KeyModifiers::CONTROL), &mut running)
+            .expect("redo");
+        assert_eq!(app.editor.text(), "xa");
+        assert_eq!(app.status_text(), "Redo | undo 1 / redo 0");
+
+        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::CONTROL), &mut running)
+            .expect("undo 2");
+        app.handle_key(key(KeyCode::Char('b'), KeyModifiers::NONE), &mut running)
+            .expect("insert 2");
+        app.handle_key(key(KeyCode::Char('y'), KeyModifiers::CONTROL), &mut running)
+            .expect("redo empty");
+        assert_eq!(app.status_text(), "Nothing to redo");
+        assert_eq!(app.editor.text(), "xb");
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn handle_key_search_mode() {
+        let path = temp_path("search");
+        fs::write(&path, "one two one").expect("seed");
+        let mut app =
+            App::new_file(path.clone(), false, false, false, "one two one".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
+
+        app.handle_key(key(KeyCode::Char('f'), KeyModifiers::CONTROL), &mut running)
+            .expect("start search");
+        app.handle_key(key(KeyCode::Char('o'), KeyModifiers::NONE), &mut running)
+            .expect("q1");
+        app.handle_key(key(KeyCode::Char('n'), KeyModifiers::NONE), &mut running)
+            .expect("q2");
+        app.handle_key(key(KeyCode::Char('e'), KeyModifiers::NONE), &mut running)
+            .expect("q3");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("exec search");
+        assert_eq!(app.status_text(), "Found: one");
+        assert_eq!(app.editor.cursor(), 0);
+
+        app.handle_key(key(KeyCode::Char('f'), KeyModifiers::CONTROL), &mut running)
+            .expect("start search 2");
+        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::NONE), &mut running)
+            .expect("qz");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("exec search 2");
+        assert_eq!(app.status_text(), "Not found: z");
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn handle_key_repeat_search_next_prev() {
+        let path = temp_path("search-repeat");
+        fs::write(&path, "one two one").expect("seed");
+        let mut app =
+            App::new_file(path.clone(), false, false, false, "one two one".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
+
+        app.handle_key(key(KeyCode::F(3), KeyModifiers::NONE), &mut running)
+            .expect("repeat empty");
+        assert_eq!(app.status_text(), "No prior search");
+
+        app.handle_key(key(KeyCode::Char('f'), KeyModifiers::CONTROL), &mut running)
+            .expect("start search");
+        app.handle_key(key(KeyCode::Char('o'), KeyModifiers::NONE), &mut running)
+            .expect("q1");
+        app.handle_key(key(KeyCode::Char('n'), KeyModifiers::NONE), &mut running)
+            .expect("q2");
+        app.handle_key(key(KeyCode::Char('e'), KeyModifiers::NONE), &mut running)
+            .expect("q3");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("exec search");
+
+        app.handle_key(key(KeyCode::F(3), KeyModifiers::NONE), &mut running)
+            .expect("repeat next");
+        assert_eq!(app.editor.cursor(), 8);
+        assert_eq!(app.status_text(), "Found next: one");
+
+        app.handle_key(key(KeyCode::F(3), KeyModifiers::SHIFT), &mut running)
+            .expect("repeat prev");
+        assert_eq!(app.editor.cursor(), 0);
+        assert_eq!(app.status_text(), "Found previous: one");
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn search_counter_follows_the_cursor_through_the_matches() {
+        let text = "ana banana\nbanana\n";
+        let mut app = App::new_file(
+            temp_path("search-counter"),
+            false,
+            false,
+            false,
+            text.into(),
+        )
+        .expect("app");
+        let mut terminal = Terminal::new(TestBackend::new(80, 12)).expect("terminal");
+        let mut running = true;
+
+        app.handle_key(key(KeyCode::Char('f'), KeyModifiers::CONTROL), &mut running)
+            .expect("search");
+        for c in "ana".chars() {
+            app.handle_key(key(KeyCode::Char(c), KeyModifiers::NONE), &mut running)
+                .expect("type");
+        }
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("find");
+        draw_text(&mut app, &mut terminal);
+        // "banana" holds "ana" twice, but they overlap, so each line counts once.
+        assert_eq!(app.search_match_ranges(), [(0, 3), (5, 8), (12, 15)]);
+        assert!(app.status_hint().ends_with("| match 1/3 | Esc clear"));
+
+        let mut counters = Vec::new();
+        for (code, mods) in [
+            (KeyCode::F(3), KeyModifiers::NONE),
+            (KeyCode::Char('n'), KeyModifiers::CONTROL),
+            (KeyCode::F(3), KeyModifiers::NONE),
+            (KeyCode::Char('p'), KeyModifiers::CONTROL),
+            (KeyCode::Right, KeyModifiers::NONE),
+        ] {
+            app.handle_key(key(code, mods), &mut running).expect("move");
+            draw_text(&mut app, &mut terminal);
+            counters.push(app.search_counter().expect("counter"));
+        }
+        assert_eq!(
+            counters,
+            [
+                "match 2/3",
+                "match 3/3",
+                "match 1/3",
+                "match 3/3",
+                "3 matches"
+            ]
+        );
+
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
+            .expect("edit");
+        draw_text(&mut app, &mut terminal);
+        assert_eq!(app.search_match_ranges(), [(0, 3), (5, 8), (15, 18)]);
+
+        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
+            .expect("clear");
+        assert_eq!(app.status_text(), "Search cleared");
+        draw_text(&mut app, &mut terminal);
+        assert!(app.search_match_ranges().is_empty());
+        assert!(!app.status_hint().contains("match"));
+        app.handle_key(key(KeyCode::F(3), KeyModifiers::NONE), &mut running)
+            .expect("repeat");
+        assert_eq!(app.status_text(), "No prior search");
     }
-}
 
-fn docs_modal_rect(area: Rect) -> Rect {
-    let max_w = area.width.saturating_sub(4);
-    let width = if max_w >= 80 {
-        area.width.saturating_mul(92).div_ceil(100).clamp(80, max_w)
-    } else {
-        area.width.saturating_sub(2).max(1)
-    };
-    let max_h = area.height.saturating_sub(4);
-    let height = if max_h >= 20 {
-        area.height
-            .saturating_mul(80)
-            .div_ceil(100)
-            .clamp(20, max_h)
-    } else {
-        area.height.saturating_sub(2).max(1)
-    };
-    Rect {
-        x: area.x + area.width.saturating_sub(width) / 2,
-        y: area.y + area.height.saturating_sub(height) / 2,
-        width,
-        height,
+    #[test]
+    fn search_matches_are_highlighted_in_the_editor() {
+        let text = "one two one\nnone\n";
+        let mut app = App::new_file(
+            temp_path("search-highlight"),
+            false,
+            false,
+            false,
+            text.into(),
+        )
+        .expect("app");
+        let mut terminal = Terminal::new(TestBackend::new(80, 12)).expect("terminal");
+        app.last_search_query = "one".into();
+        app.editor.set_cursor(8);
+        draw_text(&mut app, &mut terminal);
+
+        let theme = build_theme(app.ui.theme, app.ui.no_color, None);
+        let area = app.editor_text_area;
+        let bg = |x: u16, y: u16| terminal.backend().buffer()[(area.x + x, area.y + y)].bg;
+        let match_bg = theme.search_match.bg.expect("match bg");
+        let current_bg = theme.current_match.bg.expect("current bg");
+        assert_eq!([bg(0, 0), bg(1, 0), bg(2, 0)], [match_bg; 3]);
+        assert_ne!(bg(3, 0), match_bg);
+        assert_ne!(bg(4, 0), match_bg);
+        assert_eq!([bg(8, 0), bg(9, 0), bg(10, 0)], [current_bg; 3]);
+        assert_eq!([bg(1, 1), bg(2, 1), bg(3, 1)], [match_bg; 3]);
+        assert_ne!(bg(0, 1), match_bg);
     }
-}
 
-fn onboarding_marker_state() -> (Option<PathBuf>, bool) {
-    let path = onboarding_marker_path();
-    let seen = path.as_ref().is_some_and(|p| p.exists());
-    (path, seen)
-}
+    #[test]
+    fn front_matter_setting_folds_and_expands_the_preview() {
+        let text = "---\ntitle: Notes\ndraft: true\n---\n# Body\n";
+        let mut app = App::new_file(temp_path("front-matter"), false, false, false, text.into())
+            .expect("app");
+        let preview = |app: &mut App| {
+            let lines = app.preview_lines_cached(80);
+            lines.iter().map(|line| line.text()).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            preview(&mut app),
+            ["--- front matter: 2 keys ---", "# Body"]
+        );
 
-fn onboarding_marker_path() -> Option<PathBuf> {
-    if let Some(root) = std::env::var_os("MDV_INSTALL_ROOT")
-        && !root.is_empty()
-    {
-        return Some(PathBuf::from(root).join("state").join("onboarding_seen"));
+        app.change_setting(SettingItem::FrontMatter, true);
+        assert_eq!(app.status_text(), "Front matter: expanded");
+        assert_eq!(
+            preview(&mut app),
+            ["---", "title: Notes", "draft: true", "---", "# Body"]
+        );
+        app.change_setting(SettingItem::FrontMatter, false);
+        assert_eq!(app.status_text(), "Front matter: folded");
+        assert_eq!(preview(&mut app).len(), 2);
     }
-    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
-    Some(
-        PathBuf::from(home)
-            .join(".mdv")
-            .join("state")
-            .join("onboarding_seen"),
-    )
-}
 
-fn to_lines(text: &str) -> Vec<String> {
-    text.split('\n').map(ToString::to_string).collect()
-}
+    #[test]
+    fn reader_mode_hides_link_syntax_and_shows_the_destination_under_the_cursor() {
+        let text = "See [the guide](https://example.com/guide) first.\n\n![A cat](cat.png)\n";
+        let mut app =
+            App::new_file(temp_path("reader-mode"), false, false, false, text.into()).expect("app");
+        let preview = |app: &mut App| {
+            let lines = app.preview_lines_cached(80);
+            lines.iter().map(|line| line.text()).collect::<Vec<_>>()
+        };
+        assert_eq!(preview(&mut app), ["See the guide first.", "🖼 A cat"]);
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 12)).expect("terminal");
+        app.editor
+            .set_cursor(text.find("guide]").expect("link text"));
+        draw_text(&mut app, &mut terminal);
+        assert_eq!(app.status_text(), "Link: https://example.com/guide");
+        app.editor.set_cursor(text.find("cat.png").expect("image"));
+        draw_text(&mut app, &mut terminal);
+        assert_eq!(app.status_text(), "Link: cat.png");
+        app.editor.set_cursor(0);
+        draw_text(&mut app, &mut terminal);
+        assert_eq!(app.status_text(), "Ready");
 
-fn clamp_scroll(scroll: usize, total: usize, height: usize) -> usize {
-    if total <= height {
-        0
-    } else {
-        scroll.min(total - height)
+        app.change_setting(SettingItem::ReaderMode, true);
+        assert_eq!(app.status_text(), "Reader mode: off");
+        assert_eq!(
+            preview(&mut app),
+            [
+                "See [the guide](https://example.com/guide) first.",
+                "![A cat](cat.png)"
+            ]
+        );
+        app.editor
+            .set_cursor(text.find("guide]").expect("link text"));
+        draw_text(&mut app, &mut terminal);
+        assert_ne!(app.status_text(), "Link: https://example.com/guide");
     }
-}
-
-/// Width needed for line number gutter: digits for max line + 1 space separator
-fn line_number_gutter_width(total_lines: usize) -> u16 {
-    let digits = if total_lines == 0 {
-        1
-    } else {
-        (total_lines as f64).log10().floor() as u16 + 1
-    };
-    digits + 2 // digits + " │"
-}
 
-fn styled_editor_lines(
-    text: &str,
-    scroll: usize,
-    height: usize,
-    selection: Option<(usize, usize)>,
-    width: u16,
-    theme: &ThemeTokens,
-    current_line: usize,
-) -> Vec<Line<'static>> {
-    let lines = to_lines(text);
-    let total_lines = lines.len();
-    let gutter_width = line_number_gutter_width(total_lines);
-    let content_width = width.saturating_sub(gutter_width);
+    #[test]
+    fn spell_check_underlines_cycles_and_learns_words() {
+        let words = temp_path("spell-words");
+        let user = temp_path("spell-user");
+        fs::write(&words, "the\ncat\nsat\n").expect("words");
+        let text = "teh cat sat\n```\nbiuld\n```\nthe cta\n";
+        let mut app =
+            App::new_file(temp_path("spell"), false, false, false, text.into()).expect("app");
+        let mut terminal = Terminal::new(TestBackend::new(120, 20)).expect("terminal");
+        let mut running = true;
 
-    let mut in_code = code_open_before(&lines, scroll);
-    let mut line_starts = Vec::with_capacity(lines.len());
-    let mut idx = 0usize;
-    for (i, line) in lines.iter().enumerate() {
-        line_starts.push(idx);
-        idx += line.len();
-        if i + 1 < lines.len() {
-            idx += 1;
-        }
-    }
+        app.handle_key(key(KeyCode::F(7), KeyModifiers::NONE), &mut running)
+            .expect("spell off");
+        assert_eq!(app.status_text(), "Spell check is off: --spell or Settings");
+        app.set_spell_dictionaries(Some(words.clone()), Some(user.clone()));
+        app.set_spell(true);
+        app.editor.set_cursor(0);
+        app.editor_scroll = 0;
+        draw_text(&mut app, &mut terminal);
 
-    let selected_style = Style::default().add_modifier(Modifier::REVERSED);
-    lines
-        .iter()
-        .enumerate()
-        .skip(scroll)
-        .take(height)
-        .map(|(line_idx, line)| {
-            // Line number
-            let is_current = line_idx == current_line;
-            let line_num_style = if is_current {
-                theme.line_number_current
-            } else {
-                theme.line_number
-            };
-            let gutter_digits = gutter_width.saturating_sub(2) as usize;
-            let line_num = format!("{:>width$} │", line_idx + 1, width = gutter_digits);
-            let line_num_span = Span::styled(line_num, line_num_style);
+        let theme = build_theme(app.ui.theme, app.ui.no_color, None);
+        let area = app.editor_text_area;
+        let fg = |terminal: &Terminal<TestBackend>, x: u16, y: u16| {
+            terminal.backend().buffer()[(area.x + x, area.y + y)].fg
+        };
+        let misspelled = theme.misspelled.fg.expect("misspelled fg");
+        assert_eq!(fg(&terminal, 1, 0), misspelled);
+        assert_ne!(fg(&terminal, 4, 0), misspelled);
+        assert_ne!(fg(&terminal, 0, 2), misspelled);
+        assert_eq!(fg(&terminal, 4, 4), misspelled);
+
+        app.handle_key(key(KeyCode::F(7), KeyModifiers::NONE), &mut running)
+            .expect("next");
+        assert_eq!(app.status_text(), "Misspelled: cta (2/2)");
+        app.handle_key(key(KeyCode::F(7), KeyModifiers::NONE), &mut running)
+            .expect("wrap");
+        assert_eq!(app.status_text(), "Misspelled: teh (1/2)");
+        assert_eq!(app.editor.cursor(), 0);
 
-            // Content
-            let base = styled_preview_line(line, content_width, theme, &mut in_code);
-            let content_spans = if let Some((sel_start, sel_end)) = selection {
-                let line_start = line_starts[line_idx];
-                let line_end = line_start + line.len();
-                if sel_end <= line_start || sel_start >= line_end {
-                    base.spans
-                } else {
-                    let local_start = sel_start.saturating_sub(line_start).min(line.len());
-                    let local_end = sel_end.saturating_sub(line_start).min(line.len());
-                    apply_selection_to_styled_spans(
-                        base.spans,
-                        local_start,
-                        local_end,
-                        selected_style,
-                    )
-                }
-            } else {
-                base.spans
-            };
+        app.handle_key(key(KeyCode::Char('a'), KeyModifiers::ALT), &mut running)
+            .expect("add");
+        assert_eq!(app.status_text(), "Added 'teh' to the dictionary");
+        assert_eq!(fs::read_to_string(&user).expect("user words"), "teh\n");
+        draw_text(&mut app, &mut terminal);
+        assert_ne!(fg(&terminal, 1, 0), misspelled);
+        app.handle_key(key(KeyCode::Char('a'), KeyModifiers::ALT), &mut running)
+            .expect("add again");
+        assert_eq!(app.status_text(), "No misspelled word at the cursor");
 
-            // Combine: line number + content
-            let mut spans = vec![line_num_span];
-            spans.extend(content_spans);
-            Line::from(spans)
-        })
-        .collect()
-}
+        app.change_setting(SettingItem::Spell, true);
+        assert_eq!(last_logged(&app), "Spell check: off");
+        draw_text(&mut app, &mut terminal);
+        assert_ne!(fg(&terminal, 4, 4), misspelled);
 
-fn apply_selection_to_styled_spans(
-    spans: Vec<Span<'static>>,
-    local_start: usize,
-    local_end: usize,
-    selected_style: Style,
-) -> Vec<Span<'static>> {
-    let mut out = Vec::new();
-    let mut offset = 0usize;
+        let _ = fs::remove_file(&words);
+        let _ = fs::remove_file(&user);
+    }
 
-    for span in spans {
-        let content = span.content.to_string();
-        let span_len = content.len();
-        let span_start = offset;
-        let span_end = span_start + span_len;
-        offset = span_end;
+    #[test]
+    fn handle_key_replace_mode_replace_next_and_all() {
+        let path = temp_path("replace-mode");
+        fs::write(&path, "one two one").expect("seed");
+        let mut app =
+            App::new_file(path.clone(), false, false, false, "one two one".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
 
-        if local_end <= span_start || local_start >= span_end {
-            out.push(span);
-            continue;
-        }
+        app.handle_key(key(KeyCode::Char('h'), KeyModifiers::CONTROL), &mut running)
+            .expect("start replace");
+        app.handle_key(key(KeyCode::Char('o'), KeyModifiers::NONE), &mut running)
+            .expect("find1");
+        app.handle_key(key(KeyCode::Char('n'), KeyModifiers::NONE), &mut running)
+            .expect("find2");
+        app.handle_key(key(KeyCode::Char('e'), KeyModifiers::NONE), &mut running)
+            .expect("find3");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("to replacement");
+        assert_eq!(app.status_text(), "Replace with: ");
 
-        let rel_start = local_start.saturating_sub(span_start).min(span_len);
-        let rel_end = local_end.saturating_sub(span_start).min(span_len);
-        let style = span.style;
-        if rel_start > 0 {
-            out.push(Span::styled(content[..rel_start].to_string(), style));
-        }
-        if rel_end > rel_start {
-            out.push(Span::styled(
-                content[rel_start..rel_end].to_string(),
-                style.patch(selected_style),
-            ));
-        }
-        if rel_end < span_len {
-            out.push(Span::styled(content[rel_end..].to_string(), style));
-        }
-    }
+        app.handle_key(key(KeyCode::Char('O'), KeyModifiers::SHIFT), &mut running)
+            .expect("rep1");
+        app.handle_key(key(KeyCode::Char('N'), KeyModifiers::SHIFT), &mut running)
+            .expect("rep2");
+        app.handle_key(key(KeyCode::Char('E'), KeyModifiers::SHIFT), &mut running)
+            .expect("rep3");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("replace next");
+        assert_eq!(app.editor.text(), "ONE two one");
+        assert_eq!(app.status_text(), "Replaced: one -> ONE");
 
-    if out.is_empty() {
-        out.push(Span::raw(String::new()));
-    }
-    out
-}
+        app.handle_key(key(KeyCode::Char('h'), KeyModifiers::CONTROL), &mut running)
+            .expect("start replace all");
+        app.handle_key(key(KeyCode::Char('o'), KeyModifiers::NONE), &mut running)
+            .expect("find all1");
+        app.handle_key(key(KeyCode::Char('n'), KeyModifiers::NONE), &mut running)
+            .expect("find all2");
+        app.handle_key(key(KeyCode::Char('e'), KeyModifiers::NONE), &mut running)
+            .expect("find all3");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("to replacement all");
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
+            .expect("replacement all");
+        app.handle_key(key(KeyCode::Char('a'), KeyModifiers::CONTROL), &mut running)
+            .expect("replace all");
+        assert_eq!(app.editor.text(), "ONE two one");
+        assert_eq!(
+            app.status_text(),
+            "Replace 1 occurrence of 'one'? Enter confirm / Esc cancel"
+        );
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("confirm replace all");
+        assert_eq!(app.editor.text(), "ONE two x");
+        assert_eq!(app.status_text(), "Replaced all 1: one -> x");
 
-fn cursor_rect(area: Rect) -> Rect {
-    Rect {
-        x: area.x + 1,
-        y: area.y + 1,
-        width: area.width.saturating_sub(2),
-        height: area.height.saturating_sub(2),
+        let _ = fs::remove_file(&path);
     }
-}
 
-fn point_in_rect(column: u16, row: u16, rect: Rect) -> bool {
-    if rect.width == 0 || rect.height == 0 {
-        return false;
-    }
-    column >= rect.x
-        && column < rect.x.saturating_add(rect.width)
-        && row >= rect.y
-        && row < rect.y.saturating_add(rect.height)
-}
+    #[test]
+    fn handle_key_replace_mode_readonly_and_cancel() {
+        let path = temp_path("replace-readonly");
+        fs::write(&path, "one").expect("seed");
+        let mut app = App::new_file(path.clone(), true, false, false, "one".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
 
-fn toggle_raw_mode<F>(interactive: bool, mut f: F) -> Result<()>
-where
-    F: FnMut() -> io::Result<()>,
-{
-    if interactive {
-        f()?;
-    }
-    Ok(())
-}
+        app.handle_key(key(KeyCode::Char('h'), KeyModifiers::CONTROL), &mut running)
+            .expect("start replace");
+        app.handle_key(key(KeyCode::Char('o'), KeyModifiers::NONE), &mut running)
+            .expect("find");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("to replacement");
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
+            .expect("rep");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("replace blocked");
+        assert_eq!(app.status_text(), "Readonly: replace disabled");
+        assert_eq!(app.editor.text(), "one");
 
-fn next_terminal_input<P, R>(mut poll: P, mut read: R) -> Result<Option<InputEvent>>
-where
-    P: FnMut(Duration) -> io::Result<bool>,
-    R: FnMut() -> io::Result<Event>,
-{
-    if !poll(Duration::from_millis(30))? {
-        return Ok(None);
+        app.handle_key(key(KeyCode::Char('h'), KeyModifiers::CONTROL), &mut running)
+            .expect("start replace 2");
+        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
+            .expect("cancel replace");
+        assert_eq!(app.status_text(), "Replace cancelled");
+
+        let _ = fs::remove_file(&path);
     }
-    let event = read()?;
-    match event {
-        Event::Key(key) if key.kind == event::KeyEventKind::Press => Ok(Some(InputEvent::Key(key))),
-        Event::Mouse(mouse) if mouse.kind == MouseEventKind::ScrollUp => {
-            Ok(Some(InputEvent::Scroll {
-                direction: -1,
-                column: mouse.column,
-                row: mouse.row,
-            }))
-        }
-        Event::Mouse(mouse) if mouse.kind == MouseEventKind::ScrollDown => {
-            Ok(Some(InputEvent::Scroll {
-                direction: 1,
-                column: mouse.column,
-                row: mouse.row,
-            }))
-        }
-        Event::Mouse(mouse) if mouse.kind == MouseEventKind::Down(MouseButton::Left) => {
-            Ok(Some(InputEvent::MouseDown {
-                column: mouse.column,
-                row: mouse.row,
-                modifiers: mouse.modifiers,
-            }))
-        }
-        Event::Mouse(mouse) if mouse.kind == MouseEventKind::Drag(MouseButton::Left) => {
-            Ok(Some(InputEvent::MouseDrag {
-                column: mouse.column,
-                row: mouse.row,
-            }))
+
+    #[test]
+    fn replace_all_waits_for_confirmation() {
+        let text = "foo bar foo\nfood foo\n";
+        let mut app = App::new_file(
+            temp_path("replace-all-confirm"),
+            false,
+            false,
+            false,
+            text.into(),
+        )
+        .expect("app");
+        let mut running = true;
+        let mut press = |app: &mut App, code: KeyCode, mods: KeyModifiers| {
+            app.handle_key(key(code, mods), &mut running).expect("key");
+        };
+
+        press(&mut app, KeyCode::Char('h'), KeyModifiers::CONTROL);
+        for c in "foo".chars() {
+            press(&mut app, KeyCode::Char(c), KeyModifiers::NONE);
         }
-        Event::Mouse(mouse) if mouse.kind == MouseEventKind::Up(MouseButton::Left) => {
-            Ok(Some(InputEvent::MouseUp))
+        assert_eq!(app.status_text(), "Replace find: foo (4 matches)");
+        press(&mut app, KeyCode::Char('w'), KeyModifiers::ALT);
+        assert_eq!(app.status_text(), "Replace find [w]: foo (3 matches)");
+        press(&mut app, KeyCode::Char('z'), KeyModifiers::NONE);
+        assert_eq!(app.status_text(), "Replace find [w]: fooz (no matches)");
+        press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Char('x'), KeyModifiers::NONE);
+
+        press(&mut app, KeyCode::Char('a'), KeyModifiers::CONTROL);
+        assert_eq!(
+            app.status_text(),
+            "Replace 3 occurrences of 'foo'? Enter confirm / Esc cancel"
+        );
+        assert_eq!(app.status_hint(), "replace all: Enter confirm | Esc cancel");
+        press(&mut app, KeyCode::Char('y'), KeyModifiers::NONE);
+        press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(app.status_text(), "Replace all cancelled");
+        assert_eq!(app.editor.text(), text);
+        assert!(!app.editor.dirty);
+        assert!(!app.replace_with_mode);
+
+        press(&mut app, KeyCode::Char('h'), KeyModifiers::CONTROL);
+        for c in "foo".chars() {
+            press(&mut app, KeyCode::Char(c), KeyModifiers::NONE);
         }
-        _ => Ok(None),
-    }
-}
-
-#[cfg(test)]
-fn next_pressed_key<P, R>(poll: P, read: R) -> Result<Option<KeyEvent>>
-where
-    P: FnMut(Duration) -> io::Result<bool>,
-    R: FnMut() -> io::Result<Event>,
-{
-    match next_terminal_input(poll, read)? {
-        Some(InputEvent::Key(key)) => Ok(Some(key)),
-        _ => Ok(None),
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Char('x'), KeyModifiers::NONE);
+        press(&mut app, KeyCode::Char('a'), KeyModifiers::CONTROL);
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(app.editor.text(), "x bar x\nfood x\n");
+        assert_eq!(app.status_text(), "Replaced all 3: foo -> x");
+        press(&mut app, KeyCode::Char('z'), KeyModifiers::CONTROL);
+        assert_eq!(app.editor.text(), text);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::ffi::OsString;
-    use std::fs;
-    use std::io;
-    use std::path::PathBuf;
-    use std::sync::{Mutex, mpsc};
-    use std::time::{SystemTime, UNIX_EPOCH};
+    #[test]
+    fn handle_key_goto_mode() {
+        let path = temp_path("goto");
+        fs::write(&path, "a\nb\nc").expect("seed");
+        let mut app =
+            App::new_file(path.clone(), false, false, false, "a\nb\nc".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
 
-    use crate::ui::docs;
-    use crossterm::event::{
-        Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, MouseButton,
-        MouseEvent, MouseEventKind,
-    };
-    use ratatui::Terminal;
-    use ratatui::backend::TestBackend;
-    use ratatui::style::Modifier;
+        app.handle_key(key(KeyCode::Char('g'), KeyModifiers::CONTROL), &mut running)
+            .expect("start goto");
+        app.handle_key(key(KeyCode::Char('2'), KeyModifiers::NONE), &mut running)
+            .expect("line");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("exec goto");
+        assert_eq!(app.editor.line_col_at_cursor(), (1, 0));
+        assert_eq!(app.status_text(), "Line 2, Col 1");
 
-    use crate::stream::StreamMessage;
-    use crate::ui::theme::build_theme;
-    use crate::watcher::WatchMessage;
+        app.handle_key(key(KeyCode::Char('g'), KeyModifiers::CONTROL), &mut running)
+            .expect("start goto 2");
+        app.handle_key(key(KeyCode::Char('9'), KeyModifiers::NONE), &mut running)
+            .expect("line 9");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("exec goto 2");
+        assert_eq!(app.status_text(), "Line out of range: 9");
 
-    use super::{
-        App, InputEvent, PaneFocus, ThemeChoice, centered_popup, clamp_scroll, code_open_before,
-        cursor_rect, docs_modal_rect, mode_label, next_pressed_key, next_terminal_input,
-        onboarding_marker_path, pane_border_style, preview_title_with_scroll, scroll_indicator_bar,
-        status_style, styled_editor_lines, styled_preview_line, to_lines, toggle_raw_mode,
-    };
+        app.handle_key(key(KeyCode::Char('g'), KeyModifiers::CONTROL), &mut running)
+            .expect("start goto 3");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("exec goto 3");
+        assert_eq!(app.status_text(), "Goto line empty");
+
+        let mut goto = |query: &str| {
+            app.handle_key(key(KeyCode::Char('g'), KeyModifiers::CONTROL), &mut running)
+                .expect("start goto");
+            for c in query.chars() {
+                app.handle_key(key(KeyCode::Char(c), KeyModifiers::NONE), &mut running)
+                    .expect("type");
+            }
+            app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+                .expect("exec goto");
+            (
+                app.editor.line_col_at_cursor(),
+                app.status_text().to_string(),
+            )
+        };
+        assert_eq!(goto("1:9"), ((0, 1), "Line 1, Col 2".into()));
+        assert_eq!(goto("+1"), ((1, 0), "Line 2, Col 1".into()));
+        assert_eq!(goto("100%"), ((2, 0), "Line 3, Col 1".into()));
+        assert_eq!(goto("-5:1"), ((0, 0), "Line 1, Col 1".into()));
+        // A typo is refused and the cursor stays.
+        assert_eq!(
+            goto("10:"),
+            (
+                (0, 0),
+                "Invalid goto: 10: (Goto takes LINE, LINE:COL, +N, -N or N%)".into()
+            )
+        );
+        assert!(goto("++5").1.starts_with("Invalid goto: ++5"));
 
-    static ENV_LOCK: Mutex<()> = Mutex::new(());
+        app.handle_key(key(KeyCode::Char('g'), KeyModifiers::CONTROL), &mut running)
+            .expect("start goto");
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
+            .expect("garbage");
+        assert_eq!(
+            app.status_text(),
+            "Goto:  ('x' is not allowed; Goto takes LINE, LINE:COL, +N, -N or N%)"
+        );
+        assert!(app.goto_query.is_empty());
 
-    fn temp_path(name: &str) -> PathBuf {
-        let nanos = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("clock")
-            .as_nanos();
-        std::env::temp_dir().join(format!("mdv-app-test-{name}-{nanos}.md"))
+        let _ = fs::remove_file(&path);
     }
 
-    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
-        KeyEvent::new(code, modifiers)
+    #[test]
+    fn initial_line_moves_cursor_and_scroll() {
+        let text = "# Top\n\nalpha\n\nbeta\n";
+        let mut app = App::new_file(temp_path("initial-line"), false, false, false, text.into())
+            .expect("app");
+        app.set_initial_line(5);
+        assert_eq!(app.editor.line_col_at_cursor(), (4, 0));
+        assert_eq!(app.editor_scroll, 4);
+        assert_eq!(app.preview_scroll, 2);
+
+        app.set_initial_line(99);
+        assert_eq!(app.editor.line_col_at_cursor(), (5, 0));
+        app.set_initial_line(0);
+        assert_eq!(app.editor.line_col_at_cursor(), (0, 0));
     }
 
-    fn set_env_var(key: &str, value: &str) {
-        unsafe { std::env::set_var(key, value) };
+    #[test]
+    fn open_at_lands_on_the_line_and_column_or_clamps_with_a_note() {
+        let text: String = (1..=200).map(|n| format!("line {n}\n")).collect();
+        let mut app = App::new_file(temp_path("open-at"), false, false, false, text).expect("app");
+        app.open_at(120, Some(4));
+        assert_eq!(app.editor.line_col_at_cursor(), (119, 3));
+        assert_eq!(app.editor_scroll, 119);
+        let preview_line = app.preview_line_for_source(119);
+        assert_eq!(app.preview_scroll, preview_line);
+        assert!(preview_line > 0);
+        assert_eq!(app.status_text(), "Ready");
+
+        app.open_at(500, None);
+        assert_eq!(app.editor.line_col_at_cursor(), (200, 0));
+        assert_eq!(
+            app.status_text(),
+            "Line 500 is past the end; opened at line 201"
+        );
     }
 
-    fn restore_env_var(key: &str, prev: Option<OsString>) {
-        if let Some(value) = prev {
-            unsafe { std::env::set_var(key, value) };
-        } else {
-            unsafe { std::env::remove_var(key) };
-        }
+    #[test]
+    fn files_reopen_where_they_were_left() {
+        let path = temp_path("resume.md");
+        let store = temp_path("resume-store.toml");
+        let _ = fs::remove_file(&store);
+        let text: String = (1..=40).map(|n| format!("line {n}\n")).collect();
+        fs::write(&path, &text).expect("seed");
+
+        let mut app = App::new_file(path.clone(), false, false, false, text.clone()).expect("app");
+        let start = app.editor.line_col_at_cursor();
+        app.set_resume_path(Some(store.clone()));
+        assert_eq!(app.editor.line_col_at_cursor(), start);
+        app.editor.set_cursor_line_col(30, 3);
+        app.editor_scroll = 25;
+        app.preview_scroll = 27;
+        app.set_initial_focus(PaneFocus::Preview);
+        app.remember_position();
+
+        let mut fresh =
+            App::new_file(path.clone(), false, false, false, text.clone()).expect("off");
+        fresh.set_resume_path(None);
+        assert_eq!(fresh.editor.line_col_at_cursor(), start);
+
+        let mut app = App::new_file(path.clone(), false, false, false, text).expect("reopen");
+        app.set_resume_path(Some(store.clone()));
+        assert_eq!(app.editor.line_col_at_cursor(), (30, 3));
+        assert_eq!(app.editor_scroll, 25);
+        assert_eq!(app.preview_scroll, 27);
+        assert_eq!(app.ui.focus, PaneFocus::Preview);
+
+        // The file shrank behind mdv's back: clamp instead of failing.
+        let short = "# Short\n\nlast\n";
+        fs::write(&path, short).expect("shrink");
+        let later = SystemTime::now() + Duration::from_secs(5);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(later))
+            .expect("touch");
+        let mut app =
+            App::new_file(path.clone(), false, false, false, short.into()).expect("short");
+        app.set_resume_path(Some(store.clone()));
+        assert_eq!(app.editor.line_col_at_cursor(), (3, 0));
+        assert_eq!(app.editor_scroll, 3);
+        let follows_editor = app.preview_line_for_source(3);
+        assert_eq!(app.preview_scroll, follows_editor);
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(&store);
     }
 
     #[test]
-    fn clamp_scroll_bounds() {
-        assert_eq!(clamp_scroll(0, 3, 5), 0);
-        assert_eq!(clamp_scroll(1, 10, 4), 1);
-        assert_eq!(clamp_scroll(20, 10, 4), 6);
+    fn handle_key_quit_works_in_prompt_modes() {
+        let path = temp_path("quit-prompts");
+        fs::write(&path, "x").expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, "x".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
+
+        app.handle_key(key(KeyCode::Char('f'), KeyModifiers::CONTROL), &mut running)
+            .expect("search mode");
+        app.handle_key(key(KeyCode::Char('q'), KeyModifiers::CONTROL), &mut running)
+            .expect("quit search mode");
+        assert!(!running);
+
+        let mut app2 = App::new_file(path.clone(), false, false, false, "x".into()).expect("app2");
+        app2.interactive_input = false;
+        let mut running2 = true;
+        app2.handle_key(
+            key(KeyCode::Char('g'), KeyModifiers::CONTROL),
+            &mut running2,
+        )
+        .expect("goto mode");
+        app2.handle_key(
+            key(KeyCode::Char('q'), KeyModifiers::CONTROL),
+            &mut running2,
+        )
+        .expect("quit goto mode");
+        assert!(!running2);
+
+        let mut app3 = App::new_file(path.clone(), false, false, false, "x".into()).expect("app3");
+        app3.interactive_input = false;
+        let mut running3 = true;
+        app3.handle_key(
+            key(KeyCode::Char('h'), KeyModifiers::CONTROL),
+            &mut running3,
+        )
+        .expect("replace mode");
+        app3.handle_key(
+            key(KeyCode::Char('q'), KeyModifiers::CONTROL),
+            &mut running3,
+        )
+        .expect("quit replace mode");
+        assert!(!running3);
+
+        let _ = fs::remove_file(&path);
     }
 
     #[test]
-    fn to_lines_returns_single_empty_line_for_empty_text() {
-        assert_eq!(to_lines(""), vec![String::new()]);
+    fn settings_tab_changes_persist_prefs_to_config_dir() {
+        let _guard = ENV_LOCK.lock().expect("env lock");
+        let prior = std::env::var_os("MDV_CONFIG_DIR");
+        let dir = std::env::temp_dir().join(format!(
+            "mdv-prefs-roundtrip-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("clock")
+                .as_nanos()
+        ));
+        set_env_var("MDV_CONFIG_DIR", dir.to_str().expect("dir utf8"));
+
+        let state = prefs::state_path().expect("state path");
+        assert_eq!(state, dir.join("state.toml"));
+        let mut app = App::new_stream_for_test(false);
+        app.set_prefs_path(Some(state.clone()));
+        app.ui.theme = ThemeChoice::Default;
+        app.open_docs_modal();
+        let mut running = true;
+
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("cycle theme");
+        app.handle_key(key(KeyCode::Down, KeyModifiers::NONE), &mut running)
+            .expect("select colors");
+        app.handle_key(key(KeyCode::Right, KeyModifiers::NONE), &mut running)
+            .expect("toggle color");
+        assert_eq!(app.ui.theme, ThemeChoice::HighContrast);
+        assert!(app.ui.no_color);
+        assert!(
+            app.status_text().contains("(saved)"),
+            "{}",
+            app.status_text()
+        );
+
+        let saved = prefs::load(&state);
+        assert_eq!(saved.theme, Some(ThemeChoice::HighContrast));
+        assert_eq!(saved.no_color, Some(true));
+        // Focus was not touched, so it is not pinned.
+        assert_eq!(saved.focus, None);
+
+        let mut next = App::new_stream_for_test(false);
+        let flags = Prefs {
+            no_color: Some(false),
+            ..Prefs::default()
+        };
+        let merged = saved.overlaid_with(flags);
+        let action = Action::ApplyPrefs {
+            focus: merged.focus.unwrap_or(next.ui.focus),
+            theme: merged.theme.unwrap_or(next.ui.theme),
+            no_color: merged.no_color.unwrap_or(next.ui.no_color),
+        };
+        update::apply_action(&mut next.ui, action, next.term_width);
+        assert_eq!(next.ui.theme, ThemeChoice::HighContrast);
+        assert!(!next.ui.no_color);
+
+        restore_env_var("MDV_CONFIG_DIR", prior);
+        let _ = fs::remove_dir_all(&dir);
     }
 
     #[test]
-    fn to_lines_keeps_trailing_blank_line() {
-        assert_eq!(to_lines("a\n"), vec!["a".to_string(), String::new()]);
+    fn resizing_the_split_saves_only_the_split() {
+        let dir = std::env::temp_dir().join(format!("mdv-prefs-split-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let state = dir.join("state.toml");
+        fs::create_dir_all(&dir).expect("dir");
+        fs::write(&state, "theme = \"auto\"\n").expect("seed");
+        let mut app = App::new_stream_for_test(false);
+        app.set_prefs_path(Some(state.clone()));
+        // A light terminal and `--focus preview` on this run.
+        app.background = Some(crate::ui::theme::Background::Light);
+        app.set_initial_focus(PaneFocus::Preview);
+        app.term_width = 120;
+        let mut running = true;
+
+        app.handle_key(key(KeyCode::Char(']'), KeyModifiers::CONTROL), &mut running)
+            .expect("grow");
+        assert!(
+            app.status_text().contains("(saved)"),
+            "{}",
+            app.status_text()
+        );
+        assert_eq!(app.ui.theme, ThemeChoice::Auto);
+        let saved = prefs::load(&state);
+        assert_eq!(saved.theme, Some(ThemeChoice::Auto));
+        assert_eq!(saved.focus, None);
+        assert_eq!(saved.split, Some(app.ui.split_percent));
+        let _ = fs::remove_dir_all(&dir);
     }
 
     #[test]
-    fn handle_key_save_reload_merge_and_keep() {
-        let path = temp_path("save");
-        fs::write(&path, "one").expect("seed");
-        let mut app = App::new_file(path.clone(), false, false, false, "one".into()).expect("app");
-        app.interactive_input = false;
+    fn help_overlay_navigation_and_onboarding_keys_work() {
+        let mut app = App::new_stream_for_test(false);
+        app.open_docs_modal();
+        app.ui.help.tab = HelpTab::Docs;
+        app.help_page_lines = 3;
+        app.ui.help.section_idx = 2;
         let mut running = true;
 
-        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
-            .expect("type");
-        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::CONTROL), &mut running)
-            .expect("save");
-        assert_eq!(fs::read_to_string(&path).expect("saved"), "onex");
-        assert_eq!(app.status, "Saved");
+        app.handle_key(key(KeyCode::Up, KeyModifiers::NONE), &mut running)
+            .expect("help up");
+        assert_eq!(app.ui.help.section_idx, 1);
+
+        app.handle_key(key(KeyCode::Down, KeyModifiers::NONE), &mut running)
+            .expect("help down");
+        assert_eq!(app.ui.help.section_idx, 2);
 
-        app.editor.insert_char('!');
-        app.editor.on_external_change("disk".into());
-        assert!(app.editor.is_conflicted());
-        app.handle_key(key(KeyCode::Char('k'), KeyModifiers::CONTROL), &mut running)
-            .expect("keep");
-        assert_eq!(app.status, "Kept local");
+        app.handle_key(key(KeyCode::Right, KeyModifiers::NONE), &mut running)
+            .expect("focus right");
+        assert!(!app.ui.help.index_focus);
 
-        app.editor.on_external_change("external".into());
-        app.handle_key(key(KeyCode::Char('m'), KeyModifiers::CONTROL), &mut running)
-            .expect("merge");
-        assert!(app.editor.text().contains("<<<<<<< local"));
-        assert_eq!(app.status, "Merged with conflict markers");
+        app.handle_key(key(KeyCode::PageDown, KeyModifiers::NONE), &mut running)
+            .expect("page down");
+        assert!(app.ui.help.scroll > 0);
 
-        app.handle_key(key(KeyCode::Char('r'), KeyModifiers::CONTROL), &mut running)
-            .expect("reload");
-        assert_eq!(app.status, "Reloaded from disk");
+        app.handle_key(key(KeyCode::End, KeyModifiers::NONE), &mut running)
+            .expect("end");
+        assert!(app.ui.help.scroll >= app.help_page_lines);
 
-        let _ = fs::remove_file(&path);
-    }
+        app.handle_key(key(KeyCode::Home, KeyModifiers::NONE), &mut running)
+            .expect("home");
+        assert_eq!(app.ui.help.scroll, 0);
 
-    #[test]
-    fn handle_key_undo_redo() {
-        let path = temp_path("undo-redo");
-        fs::write(&path, "x").expect("seed");
-        let mut app = App::new_file(path.clone(), false, false, false, "x".into()).expect("app");
-        app.interactive_input = false;
-        let mut running = true;
+        app.handle_key(key(KeyCode::Left, KeyModifiers::NONE), &mut running)
+            .expect("focus left");
+        assert!(app.ui.help.index_focus);
 
-        app.handle_key(key(KeyCode::Char('a'), KeyModifiers::NONE), &mut running)
-            .expect("insert");
-        assert_eq!(app.editor.text(), "xa");
+        app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE), &mut running)
+            .expect("tab to settings");
+        assert!(app.ui.help.is_settings());
+        assert!(app.ui.help.index_focus);
+        app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE), &mut running)
+            .expect("tab to docs");
+        assert_eq!(app.ui.help.tab, HelpTab::Docs);
 
-        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::CONTROL), &mut running)
-            .expect("undo");
-        assert_eq!(app.editor.text(), "x");
-        assert_eq!(app.status, "Undo");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("open section");
+        assert!(!app.ui.help.index_focus);
 
-        app.handle_key(key(KeyCode::Char('y'), KeyModifiers::CONTROL), &mut running)
-            .expect("redo");
-        assert_eq!(app.editor.text(), "xa");
-        assert_eq!(app.status, "Redo");
+        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
+            .expect("close docs");
+        assert!(!app.ui.help.open);
+        assert_eq!(app.status_text(), "Docs closed");
 
-        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::CONTROL), &mut running)
-            .expect("undo 2");
-        app.handle_key(key(KeyCode::Char('b'), KeyModifiers::NONE), &mut running)
-            .expect("insert 2");
-        app.handle_key(key(KeyCode::Char('y'), KeyModifiers::CONTROL), &mut running)
-            .expect("redo empty");
-        assert_eq!(app.status, "Nothing to redo");
-        assert_eq!(app.editor.text(), "xb");
+        app.open_docs_modal();
+        let mut quit_running = true;
+        app.handle_key(
+            key(KeyCode::Char('q'), KeyModifiers::CONTROL),
+            &mut quit_running,
+        )
+        .expect("quit docs");
+        assert!(!quit_running);
 
-        let _ = fs::remove_file(&path);
+        let mut home = App::new_home_for_test(false, false, false);
+        home.ui.help.open_onboarding();
+        home.ui.help.index_focus = false;
+        let mut onboarding_running = true;
+        home.handle_key(
+            key(KeyCode::Enter, KeyModifiers::NONE),
+            &mut onboarding_running,
+        )
+        .expect("advance onboarding");
+        assert_eq!(home.ui.help.onboarding_step, Some(1));
+
+        home.ui.help.section_idx = docs::section_count(docs::onboarding_catalog()) - 1;
+        home.ui.help.onboarding_step = Some(docs::section_count(docs::onboarding_catalog()) - 1);
+        home.handle_key(
+            key(KeyCode::Enter, KeyModifiers::NONE),
+            &mut onboarding_running,
+        )
+        .expect("finish onboarding");
+        assert!(!home.ui.help.open);
+        assert_eq!(home.status_text(), "Guide complete");
     }
 
     #[test]
-    fn handle_key_search_mode() {
-        let path = temp_path("search");
-        fs::write(&path, "one two one").expect("seed");
+    fn handle_key_prompt_backspace_empty_and_cancel_paths() {
+        let path = temp_path("prompt-branches");
+        fs::write(&path, "alpha\n\nbeta").expect("seed");
         let mut app =
-            App::new_file(path.clone(), false, false, false, "one two one".into()).expect("app");
+            App::new_file(path.clone(), false, false, false, "alpha\n\nbeta".into()).expect("app");
         app.interactive_input = false;
         let mut running = true;
 
         app.handle_key(key(KeyCode::Char('f'), KeyModifiers::CONTROL), &mut running)
-            .expect("start search");
-        app.handle_key(key(KeyCode::Char('o'), KeyModifiers::NONE), &mut running)
-            .expect("q1");
-        app.handle_key(key(KeyCode::Char('n'), KeyModifiers::NONE), &mut running)
-            .expect("q2");
-        app.handle_key(key(KeyCode::Char('e'), KeyModifiers::NONE), &mut running)
-            .expect("q3");
+            .expect("search start");
+        app.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE), &mut running)
+            .expect("search backspace");
+        assert_eq!(app.status_text(), "Search: ");
         app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
-            .expect("exec search");
-        assert_eq!(app.status, "Found: one");
-        assert_eq!(app.editor.cursor(), 0);
+            .expect("search empty");
+        assert_eq!(app.status_text(), "Search query empty");
 
         app.handle_key(key(KeyCode::Char('f'), KeyModifiers::CONTROL), &mut running)
-            .expect("start search 2");
-        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::NONE), &mut running)
-            .expect("qz");
+            .expect("search start 2");
+        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
+            .expect("search cancel");
+        assert_eq!(app.status_text(), "Search cancelled");
+
+        app.handle_key(key(KeyCode::Char('g'), KeyModifiers::CONTROL), &mut running)
+            .expect("goto start");
+        app.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE), &mut running)
+            .expect("goto backspace");
+        assert_eq!(app.status_text(), "Goto: ");
+        app.goto_query = "oops".into();
         app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
-            .expect("exec search 2");
-        assert_eq!(app.status, "Not found: z");
+            .expect("goto invalid");
+        assert_eq!(
+            app.status_text(),
+            "Invalid goto: oops (Goto takes LINE, LINE:COL, +N, -N or N%)"
+        );
+
+        app.handle_key(key(KeyCode::Char('g'), KeyModifiers::CONTROL), &mut running)
+            .expect("goto start 2");
+        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
+            .expect("goto cancel");
+        assert_eq!(app.status_text(), "Goto cancelled");
+
+        app.handle_key(key(KeyCode::Char('h'), KeyModifiers::CONTROL), &mut running)
+            .expect("replace start");
+        app.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE), &mut running)
+            .expect("replace find backspace");
+        assert_eq!(app.status_text(), "Replace find: ");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("replace empty");
+        assert_eq!(app.status_text(), "Replace query empty");
+
+        app.handle_key(key(KeyCode::Char('h'), KeyModifiers::CONTROL), &mut running)
+            .expect("replace start 2");
+        app.handle_key(key(KeyCode::Char('a'), KeyModifiers::NONE), &mut running)
+            .expect("replace find char");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("replace to with");
+        app.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE), &mut running)
+            .expect("replace with backspace");
+        assert_eq!(app.status_text(), "Replace with: ");
+        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
+            .expect("replace cancel");
+        assert_eq!(app.status_text(), "Replace cancelled");
 
         let _ = fs::remove_file(&path);
     }
 
     #[test]
-    fn handle_key_repeat_search_next_prev() {
-        let path = temp_path("search-repeat");
-        fs::write(&path, "one two one").expect("seed");
-        let mut app =
-            App::new_file(path.clone(), false, false, false, "one two one".into()).expect("app");
+    fn handle_key_navigation_and_status_fallbacks_cover_remaining_paths() {
+        let path = temp_path("nav-branches");
+        fs::write(&path, "one two\n\nthree four").expect("seed");
+        let mut app = App::new_file(
+            path.clone(),
+            false,
+            false,
+            false,
+            "one two\n\nthree four".into(),
+        )
+        .expect("app");
         app.interactive_input = false;
+        app.editor_height = 2;
+        app.preview_height = 2;
         let mut running = true;
 
-        app.handle_key(key(KeyCode::F(3), KeyModifiers::NONE), &mut running)
-            .expect("repeat empty");
-        assert_eq!(app.status, "No prior search");
+        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::CONTROL), &mut running)
+            .expect("undo empty");
+        assert_eq!(app.status_text(), "Nothing to undo");
+        app.handle_key(key(KeyCode::Char('y'), KeyModifiers::CONTROL), &mut running)
+            .expect("redo empty");
+        assert_eq!(app.status_text(), "Nothing to redo");
+        app.handle_key(
+            key(
+                KeyCode::Char('z'),
+                KeyModifiers::SUPER | KeyModifiers::SHIFT,
+            ),
+            &mut running,
+        )
+        .expect("redo super shift");
+        // The key dismissed the warning and did nothing else.
+        assert_eq!(app.status_text(), "Ready");
 
-        app.handle_key(key(KeyCode::Char('f'), KeyModifiers::CONTROL), &mut running)
-            .expect("start search");
-        app.handle_key(key(KeyCode::Char('o'), KeyModifiers::NONE), &mut running)
-            .expect("q1");
-        app.handle_key(key(KeyCode::Char('n'), KeyModifiers::NONE), &mut running)
-            .expect("q2");
-        app.handle_key(key(KeyCode::Char('e'), KeyModifiers::NONE), &mut running)
-            .expect("q3");
-        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
-            .expect("exec search");
+        app.handle_key(key(KeyCode::Char('m'), KeyModifiers::CONTROL), &mut running)
+            .expect("merge empty");
+        assert_eq!(app.status_text(), "No conflict to merge");
+
+        app.ui.focus = PaneFocus::Preview;
+        app.preview_scroll = 3;
+        app.handle_key(key(KeyCode::PageUp, KeyModifiers::NONE), &mut running)
+            .expect("preview page up");
+        assert_eq!(app.preview_scroll, 1);
+        app.handle_key(key(KeyCode::PageDown, KeyModifiers::NONE), &mut running)
+            .expect("preview page down");
+        assert_eq!(app.preview_scroll, 3);
+
+        app.ui.focus = PaneFocus::Editor;
+        app.editor.set_cursor(app.editor.text().len());
+        app.handle_key(key(KeyCode::Up, KeyModifiers::ALT), &mut running)
+            .expect("paragraph up");
+        let paragraph_cursor = app.editor.cursor();
+        assert!(paragraph_cursor < app.editor.text().len());
+        app.handle_key(key(KeyCode::Down, KeyModifiers::ALT), &mut running)
+            .expect("paragraph down");
+        assert!(app.editor.cursor() > paragraph_cursor);
+
+        app.handle_key(key(KeyCode::Left, KeyModifiers::SUPER), &mut running)
+            .expect("line start super");
+        assert_eq!(app.editor.line_col_at_cursor().1, 0);
+        app.handle_key(key(KeyCode::Right, KeyModifiers::SUPER), &mut running)
+            .expect("line end super");
+        assert_eq!(app.editor.line_col_at_cursor(), (2, 10));
+        app.handle_key(key(KeyCode::Home, KeyModifiers::NONE), &mut running)
+            .expect("home");
+        assert_eq!(app.editor.line_col_at_cursor().1, 0);
+        app.handle_key(key(KeyCode::End, KeyModifiers::NONE), &mut running)
+            .expect("end");
+        assert_eq!(app.editor.line_col_at_cursor(), (2, 10));
+
+        let mut edit_app =
+            App::new_file(path.clone(), false, false, false, "abc def".into()).expect("edit app");
+        edit_app.interactive_input = false;
+        edit_app.editor.set_cursor(0);
+        edit_app
+            .handle_key(key(KeyCode::Delete, KeyModifiers::CONTROL), &mut running)
+            .expect("delete word forward");
+        assert_eq!(edit_app.editor.text(), " def");
+        edit_app
+            .handle_key(key(KeyCode::Delete, KeyModifiers::NONE), &mut running)
+            .expect("delete forward");
+        assert_eq!(edit_app.editor.text(), "def");
+        edit_app.editor.set_cursor(edit_app.editor.text().len());
+        edit_app
+            .handle_key(key(KeyCode::Backspace, KeyModifiers::SUPER), &mut running)
+            .expect("delete to line start");
+        assert_eq!(edit_app.editor.text(), "");
+
+        let mut word_back =
+            App::new_file(path.clone(), false, false, false, "abc def".into()).expect("word back");
+        word_back.interactive_input = false;
+        word_back.editor.set_cursor(word_back.editor.text().len());
+        word_back
+            .handle_key(key(KeyCode::Backspace, KeyModifiers::ALT), &mut running)
+            .expect("delete word back");
+        assert_eq!(word_back.editor.text(), "abc ");
+
+        let mut readonly =
+            App::new_file(path.clone(), true, false, false, "abc".into()).expect("readonly");
+        readonly.interactive_input = false;
+        readonly
+            .handle_key(key(KeyCode::Char('k'), KeyModifiers::CONTROL), &mut running)
+            .expect("readonly ctrl-k");
+        assert_eq!(readonly.status_text(), "Readonly: edit disabled");
 
-        app.handle_key(key(KeyCode::F(3), KeyModifiers::NONE), &mut running)
-            .expect("repeat next");
-        assert_eq!(app.editor.cursor(), 8);
-        assert_eq!(app.status, "Found next: one");
+        let _ = fs::remove_file(&path);
+    }
 
-        app.handle_key(key(KeyCode::F(3), KeyModifiers::SHIFT), &mut running)
-            .expect("repeat prev");
-        assert_eq!(app.editor.cursor(), 0);
-        assert_eq!(app.status, "Found previous: one");
+    #[test]
+    fn save_applies_editorconfig_and_flag_cleanups_and_stays_clean() {
+        let dir = temp_path("editorconfig").with_extension("");
+        fs::create_dir_all(&dir).expect("mkdir");
+        fs::write(
+            dir.join(".editorconfig"),
+            "root = true\n[*.md]\ninsert_final_newline = true\ntrim_trailing_whitespace = true\n",
+        )
+        .expect("editorconfig");
+        let path = dir.join("notes.md");
+        let text = "Hard  \nbreak \n\n```\ncode  \n```";
+        fs::write(&path, text).expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, text.into()).expect("app");
+        let mut running = true;
 
-        let _ = fs::remove_file(&path);
+        app.editor.insert_char('!');
+        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::CONTROL), &mut running)
+            .expect("save");
+        let saved = "Hard  \nbreak\n\n```\ncode  \n```!\n";
+        assert_eq!(fs::read_to_string(&path).expect("read"), saved);
+        assert_eq!(app.editor.text(), saved);
+        assert!(!app.editor.dirty);
+        assert_eq!(app.last_saved_text.as_deref(), Some(saved));
+        assert_eq!(app.status_text(), "Saved (whitespace tidied)");
+
+        // Nothing left to tidy, so the message is the plain one.
+        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::CONTROL), &mut running)
+            .expect("save again");
+        assert_eq!(app.status_text(), "Saved");
+
+        // Flags turn a cleanup on even where `.editorconfig` turns it off.
+        fs::write(
+            dir.join(".editorconfig"),
+            "[*]\ninsert_final_newline = false\n",
+        )
+        .expect("editorconfig off");
+        app.editor = EditorBuffer::new("plain".into());
+        app.set_save_policy(
+            SavePolicy::default(),
+            SavePolicy {
+                final_newline: true,
+                trim_trailing_whitespace: false,
+            },
+        );
+        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::CONTROL), &mut running)
+            .expect("forced");
+        assert_eq!(fs::read_to_string(&path).expect("read"), "plain\n");
+
+        let _ = fs::remove_dir_all(&dir);
     }
 
     #[test]
-    fn handle_key_replace_mode_replace_next_and_all() {
-        let path = temp_path("replace-mode");
-        fs::write(&path, "one two one").expect("seed");
-        let mut app =
-            App::new_file(path.clone(), false, false, false, "one two one".into()).expect("app");
+    fn handle_key_readonly_and_no_path_branches() {
+        let path = temp_path("readonly");
+        fs::write(&path, "x").expect("seed");
+        let mut app = App::new_file(path.clone(), true, false, false, "x".into()).expect("app");
         app.interactive_input = false;
         let mut running = true;
 
-        app.handle_key(key(KeyCode::Char('h'), KeyModifiers::CONTROL), &mut running)
-            .expect("start replace");
-        app.handle_key(key(KeyCode::Char('o'), KeyModifiers::NONE), &mut running)
-            .expect("find1");
-        app.handle_key(key(KeyCode::Char('n'), KeyModifiers::NONE), &mut running)
-            .expect("find2");
-        app.handle_key(key(KeyCode::Char('e'), KeyModifiers::NONE), &mut running)
-            .expect("find3");
-        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
-            .expect("to replacement");
-        assert_eq!(app.status, "Replace with: ");
+        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::CONTROL), &mut running)
+            .expect("save");
+        assert_eq!(app.status_text(), "Readonly: save disabled");
 
-        app.handle_key(key(KeyCode::Char('O'), KeyModifiers::SHIFT), &mut running)
-            .expect("rep1");
-        app.handle_key(key(KeyCode::Char('N'), KeyModifiers::SHIFT), &mut running)
-            .expect("rep2");
-        app.handle_key(key(KeyCode::Char('E'), KeyModifiers::SHIFT), &mut running)
-            .expect("rep3");
         app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
-            .expect("replace next");
-        assert_eq!(app.editor.text(), "ONE two one");
-        assert_eq!(app.status, "Replaced: one -> ONE");
+            .expect("enter");
+        assert_eq!(app.editor.text(), "x");
 
-        app.handle_key(key(KeyCode::Char('h'), KeyModifiers::CONTROL), &mut running)
-            .expect("start replace all");
-        app.handle_key(key(KeyCode::Char('o'), KeyModifiers::NONE), &mut running)
-            .expect("find all1");
-        app.handle_key(key(KeyCode::Char('n'), KeyModifiers::NONE), &mut running)
-            .expect("find all2");
-        app.handle_key(key(KeyCode::Char('e'), KeyModifiers::NONE), &mut running)
-            .expect("find all3");
-        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
-            .expect("to replacement all");
-        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
-            .expect("replacement all");
-        app.handle_key(key(KeyCode::Char('a'), KeyModifiers::CONTROL), &mut running)
-            .expect("replace all");
-        assert_eq!(app.editor.text(), "ONE two x");
-        assert_eq!(app.status, "Replaced all 1: one -> x");
+        app.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE), &mut running)
+            .expect("backspace");
+        assert_eq!(app.editor.text(), "x");
+
+        let mut stream_app = App::new_stream_for_test(false);
+        stream_app.readonly = false;
+        stream_app.interactive_input = false;
+        stream_app
+            .handle_key(key(KeyCode::Char('s'), KeyModifiers::CONTROL), &mut running)
+            .expect("save no path");
+        assert_eq!(stream_app.status_text(), "Save as: ");
+        stream_app
+            .handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
+            .expect("cancel save as");
+        assert_eq!(stream_app.status_text(), "Save as cancelled");
+
+        stream_app
+            .handle_key(key(KeyCode::Char('r'), KeyModifiers::CONTROL), &mut running)
+            .expect("reload stream");
+        assert_eq!(stream_app.status_text(), "Stream mode: reload disabled");
 
         let _ = fs::remove_file(&path);
     }
 
     #[test]
-    fn handle_key_replace_mode_readonly_and_cancel() {
-        let path = temp_path("replace-readonly");
-        fs::write(&path, "one").expect("seed");
-        let mut app = App::new_file(path.clone(), true, false, false, "one".into()).expect("app");
+    fn handle_key_ctrl_k_deletes_line_end_when_not_conflicted() {
+        let path = temp_path("no-conflict-actions");
+        fs::write(&path, "abc\ndef").expect("seed");
+        let mut app =
+            App::new_file(path.clone(), false, false, false, "abc\ndef".into()).expect("app");
         app.interactive_input = false;
         let mut running = true;
 
-        app.handle_key(key(KeyCode::Char('h'), KeyModifiers::CONTROL), &mut running)
-            .expect("start replace");
-        app.handle_key(key(KeyCode::Char('o'), KeyModifiers::NONE), &mut running)
-            .expect("find");
-        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
-            .expect("to replacement");
-        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
-            .expect("rep");
-        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
-            .expect("replace blocked");
-        assert_eq!(app.status, "Readonly: replace disabled");
-        assert_eq!(app.editor.text(), "one");
+        app.editor.set_cursor(1);
+        app.handle_key(key(KeyCode::Char('k'), KeyModifiers::CONTROL), &mut running)
+            .expect("delete line end");
+        assert_eq!(app.editor.text(), "a\ndef");
+        assert_eq!(app.status_text(), "Deleted to line end");
 
-        app.handle_key(key(KeyCode::Char('h'), KeyModifiers::CONTROL), &mut running)
-            .expect("start replace 2");
-        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
-            .expect("cancel replace");
-        assert_eq!(app.status, "Replace cancelled");
+        app.handle_key(key(KeyCode::Char('m'), KeyModifiers::CONTROL), &mut running)
+            .expect("merge");
+        assert_eq!(app.status_text(), "No conflict to merge");
 
         let _ = fs::remove_file(&path);
     }
 
     #[test]
-    fn handle_key_goto_mode() {
-        let path = temp_path("goto");
-        fs::write(&path, "a\nb\nc").expect("seed");
+    fn handle_key_conflict_hunk_nav_and_apply() {
+        let path = temp_path("conflict-hunk-nav");
+        fs::write(&path, "a\nb\nc\nc").expect("seed");
         let mut app =
-            App::new_file(path.clone(), false, false, false, "a\nb\nc".into()).expect("app");
-        app.interactive_input = false;
+            App::new_file(path.clone(), false, false, false, "a\nb\nc\nc".into()).expect("app");
+        app.editor.dirty = true;
+        app.editor.on_external_change("a\nB\nc\nc\nd".into());
+        assert!(app.editor.is_conflicted());
+        assert_eq!(app.editor.conflict().expect("conflict").hunks.len(), 2);
+
         let mut running = true;
+        app.handle_key(key(KeyCode::Char('j'), KeyModifiers::CONTROL), &mut running)
+            .expect("next hunk");
+        assert_eq!(app.status_text(), "Conflict hunk 2/2");
 
-        app.handle_key(key(KeyCode::Char('g'), KeyModifiers::CONTROL), &mut running)
-            .expect("start goto");
-        app.handle_key(key(KeyCode::Char('2'), KeyModifiers::NONE), &mut running)
-            .expect("line");
-        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
-            .expect("exec goto");
-        assert_eq!(app.editor.line_col_at_cursor(), (1, 0));
-        assert_eq!(app.status, "Line 2");
+        app.handle_key(key(KeyCode::Char('e'), KeyModifiers::CONTROL), &mut running)
+            .expect("apply hunk");
+        assert_eq!(app.status_text(), "Applied external hunk");
+        assert!(app.editor.text().contains("\nd"));
+        assert_eq!(app.editor.conflict().expect("conflict").hunks.len(), 1);
 
-        app.handle_key(key(KeyCode::Char('g'), KeyModifiers::CONTROL), &mut running)
-            .expect("start goto 2");
-        app.handle_key(key(KeyCode::Char('9'), KeyModifiers::NONE), &mut running)
-            .expect("line 9");
-        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
-            .expect("exec goto 2");
-        assert_eq!(app.status, "Line out of range: 9");
+        app.handle_key(key(KeyCode::Char('u'), KeyModifiers::CONTROL), &mut running)
+            .expect("prev hunk");
+        assert_eq!(app.status_text(), "Conflict hunk 1/1");
 
-        app.handle_key(key(KeyCode::Char('g'), KeyModifiers::CONTROL), &mut running)
-            .expect("start goto 3");
-        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
-            .expect("exec goto 3");
-        assert_eq!(app.status, "Goto line empty");
+        app.handle_key(key(KeyCode::Char('e'), KeyModifiers::CONTROL), &mut running)
+            .expect("apply final hunk");
+        assert_eq!(app.status_text(), "Resolved conflict from hunks");
+        assert!(!app.editor.is_conflicted());
 
         let _ = fs::remove_file(&path);
     }
 
     #[test]
-    fn handle_key_quit_works_in_prompt_modes() {
-        let path = temp_path("quit-prompts");
-        fs::write(&path, "x").expect("seed");
-        let mut app = App::new_file(path.clone(), false, false, false, "x".into()).expect("app");
-        app.interactive_input = false;
+    fn conflict_selection_follows_its_hunk_and_shows_context() {
+        let local = "a\nb\nc1\nc2\nd\ne1\ne2\nf\ng";
+        let mut app = App::new_file(temp_path("conflict-ids"), false, false, false, local.into())
+            .expect("app");
+        app.editor.dirty = true;
+        app.editor
+            .on_external_change("a\nB\nc1\nc2\nD\ne1\ne2\nF\ng".into());
         let mut running = true;
+        app.handle_key(key(KeyCode::Char('j'), KeyModifiers::CONTROL), &mut running)
+            .expect("next hunk");
+        assert_eq!(app.status_text(), "Conflict hunk 2/3");
+
+        // Another hunk is applied; the selection stays on "d".
+        assert!(app.editor.apply_external_hunk(0));
+        app.sync_conflict_hunk_selection();
+        assert_eq!(app.selected_conflict_hunk, 0);
+        let hunk = &app.editor.conflict().expect("conflict").hunks[0];
+        assert_eq!(hunk.local_lines, ["d"]);
+        assert_eq!(hunk.context_before, ["c1", "c2"]);
+        assert_eq!(hunk.context_after, ["e1", "e2"]);
+
+        let lines = app.build_preview_lines(80);
+        let context = |text: &str| {
+            lines
+                .iter()
+                .any(|line| matches!(line, PreviewLineKind::ConflictContext(t) if t == text))
+        };
+        assert!(context("c2") && context("e1") && context("g"));
 
-        app.handle_key(key(KeyCode::Char('f'), KeyModifiers::CONTROL), &mut running)
-            .expect("search mode");
-        app.handle_key(key(KeyCode::Char('q'), KeyModifiers::CONTROL), &mut running)
-            .expect("quit search mode");
-        assert!(!running);
-
-        let mut app2 = App::new_file(path.clone(), false, false, false, "x".into()).expect("app2");
-        app2.interactive_input = false;
-        let mut running2 = true;
-        app2.handle_key(
-            key(KeyCode::Char('g'), KeyModifiers::CONTROL),
-            &mut running2,
-        )
-        .expect("goto mode");
-        app2.handle_key(
-            key(KeyCode::Char('q'), KeyModifiers::CONTROL),
-            &mut running2,
-        )
-        .expect("quit goto mode");
-        assert!(!running2);
+        // Applying the selected hunk moves the selection to the next one.
+        app.handle_key(key(KeyCode::Char('e'), KeyModifiers::CONTROL), &mut running)
+            .expect("apply");
+        assert_eq!(app.selected_conflict_hunk, 0);
+        let hunks = &app.editor.conflict().expect("conflict").hunks;
+        assert_eq!((hunks.len(), hunks[0].id), (1, 2));
+        assert_eq!(app.selected_conflict_id, Some(2));
+    }
 
-        let mut app3 = App::new_file(path.clone(), false, false, false, "x".into()).expect("app3");
-        app3.interactive_input = false;
-        let mut running3 = true;
-        app3.handle_key(
-            key(KeyCode::Char('h'), KeyModifiers::CONTROL),
-            &mut running3,
-        )
-        .expect("replace mode");
-        app3.handle_key(
-            key(KeyCode::Char('q'), KeyModifiers::CONTROL),
-            &mut running3,
+    #[test]
+    fn moved_blocks_apply_with_one_key_and_changes_are_marked_inline() {
+        let local = "# Doc\n\nMoved line.\n\nOne.\nTwo.\nThree.\n";
+        let external = "# Doc\n\nOne.\nTwo.\nThree.\n\nMoved line.\n";
+        let mut app = App::new_file(
+            temp_path("conflict-move"),
+            false,
+            false,
+            false,
+            local.into(),
         )
-        .expect("quit replace mode");
-        assert!(!running3);
+        .expect("app");
+        app.editor.dirty = true;
+        app.editor.on_external_change(external.into());
+        let mut running = true;
+        app.handle_key(key(KeyCode::Char('j'), KeyModifiers::CONTROL), &mut running)
+            .expect("next hunk");
+        assert_eq!(
+            app.status_text(),
+            "Conflict hunk 2/2 (moved: Ctrl+E accepts the move)"
+        );
+        app.conflict_columns = false;
+        let lines = app.build_preview_lines(80);
+        let texts: Vec<String> = lines.iter().map(|line| line.text()).collect();
+        assert!(
+            texts
+                .iter()
+                .any(|text| text == "--- Local block @L3 (moved away) ---")
+        );
+        assert!(
+            texts
+                .iter()
+                .any(|text| text == ">>> Local block @L9 (moved here) <<<")
+        );
 
-        let _ = fs::remove_file(&path);
+        app.handle_key(key(KeyCode::Char('e'), KeyModifiers::CONTROL), &mut running)
+            .expect("accept move");
+        assert_eq!(app.status_text(), "Resolved conflict from hunks");
+        assert_eq!(app.editor.text(), external);
+
+        app.editor
+            .on_external_change("# Doc\n\nOne.\nTwo!\nThree.\n\nMoved line.\n".into());
+        app.conflict_columns = true;
+        let mut terminal = Terminal::new(TestBackend::new(140, 30)).expect("terminal");
+        let text = draw_text(&mut app, &mut terminal);
+        let width = terminal.backend().buffer().area.width as usize;
+        let at = text[..text.rfind("Two!").expect("external line")]
+            .chars()
+            .count()
+            + 3;
+        let cell = &terminal.backend().buffer()[((at % width) as u16, (at / width) as u16)];
+        assert_eq!(cell.symbol(), "!");
+        assert!(
+            cell.modifier
+                .contains(Modifier::BOLD | Modifier::UNDERLINED)
+        );
+        let before = &terminal.backend().buffer()[((at % width) as u16 - 1, (at / width) as u16)];
+        assert!(!before.modifier.contains(Modifier::UNDERLINED));
     }
 
     #[test]
-    fn help_overlay_navigation_and_onboarding_keys_work() {
-        let mut app = App::new_stream_for_test(false);
-        app.open_docs_modal();
-        app.help_page_lines = 3;
-        app.ui.help.section_idx = 2;
+    fn handle_key_navigation_and_quit() {
+        let path = temp_path("nav");
+        fs::write(&path, "ab\ncd").expect("seed");
+        let mut app =
+            App::new_file(path.clone(), false, false, false, "ab\ncd".into()).expect("app");
+        app.interactive_input = false;
         let mut running = true;
 
+        app.handle_key(key(KeyCode::Left, KeyModifiers::NONE), &mut running)
+            .expect("left");
+        app.handle_key(key(KeyCode::Right, KeyModifiers::NONE), &mut running)
+            .expect("right");
         app.handle_key(key(KeyCode::Up, KeyModifiers::NONE), &mut running)
-            .expect("help up");
-        assert_eq!(app.ui.help.section_idx, 1);
-
+            .expect("up");
         app.handle_key(key(KeyCode::Down, KeyModifiers::NONE), &mut running)
-            .expect("help down");
-        assert_eq!(app.ui.help.section_idx, 2);
+            .expect("down");
+        app.handle_key(key(KeyCode::PageDown, KeyModifiers::NONE), &mut running)
+            .expect("pgdn");
+        app.handle_key(key(KeyCode::PageUp, KeyModifiers::NONE), &mut running)
+            .expect("pgup");
+        app.handle_key(key(KeyCode::Char('q'), KeyModifiers::CONTROL), &mut running)
+            .expect("quit");
+        assert!(!running);
 
-        app.handle_key(key(KeyCode::Right, KeyModifiers::NONE), &mut running)
-            .expect("focus right");
-        assert!(!app.ui.help.index_focus);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn config_warnings_show_in_status() {
+        let mut app = App::new_stream_for_test(false);
+        app.show_config_warnings(&[]);
+        assert_eq!(app.status_text(), "Stream mode");
+        app.show_config_warnings(&["unknown theme key `x`".into()]);
+        assert_eq!(app.status_text(), "Config warning: unknown theme key `x`");
+        app.show_config_warnings(&["a".into(), "b".into(), "c".into()]);
+        assert_eq!(app.status_text(), "Config warning: a (+2 more)");
+    }
 
-        app.handle_key(key(KeyCode::PageDown, KeyModifiers::NONE), &mut running)
-            .expect("page down");
-        assert!(app.ui.help.scroll > 0);
+    #[test]
+    fn autosave_writes_dirty_buffer_after_interval() {
+        let path = temp_path("autosave");
+        fs::write(&path, "x").expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, "x".into()).expect("app");
+        app.set_autosave(5);
+        let start = app.last_save_at;
 
-        app.handle_key(key(KeyCode::End, KeyModifiers::NONE), &mut running)
-            .expect("end");
-        assert!(app.ui.help.scroll >= app.help_page_lines);
+        app.maybe_autosave(start + Duration::from_secs(10));
+        assert_eq!(app.status_text(), "Ready", "clean buffer is not autosaved");
 
-        app.handle_key(key(KeyCode::Home, KeyModifiers::NONE), &mut running)
-            .expect("home");
-        assert_eq!(app.ui.help.scroll, 0);
+        app.editor.insert_char('y');
+        app.maybe_autosave(start + Duration::from_secs(4));
+        assert_eq!(fs::read_to_string(&path).expect("read"), "x");
 
-        app.handle_key(key(KeyCode::Left, KeyModifiers::NONE), &mut running)
-            .expect("focus left");
-        assert!(app.ui.help.index_focus);
+        app.maybe_autosave(start + Duration::from_secs(5));
+        assert_eq!(fs::read_to_string(&path).expect("read"), "xy");
+        assert!(
+            app.status_text().starts_with("Autosaved ") && app.status_text().ends_with(" UTC"),
+            "{}",
+            app.status_text()
+        );
+        assert!(!app.editor.dirty);
+        assert_eq!(app.last_saved_text.as_deref(), Some("xy"));
 
-        app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE), &mut running)
-            .expect("tab focus");
-        assert!(!app.ui.help.index_focus);
+        app.set_autosave(0);
+        app.editor.insert_char('z');
+        app.maybe_autosave(start + Duration::from_secs(60));
+        assert_eq!(fs::read_to_string(&path).expect("read"), "xy");
 
-        app.ui.help.index_focus = true;
-        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
-            .expect("open section");
-        assert!(!app.ui.help.index_focus);
+        let _ = fs::remove_file(&path);
+    }
 
-        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
-            .expect("close docs");
-        assert!(!app.ui.help.open);
-        assert_eq!(app.status, "Docs closed");
+    #[test]
+    fn autosave_skips_conflict_readonly_and_stream() {
+        let path = temp_path("autosave-conflict");
+        fs::write(&path, "local").expect("seed");
+        let mut app =
+            App::new_file(path.clone(), false, false, false, "local".into()).expect("app");
+        app.set_autosave(1);
+        app.editor.insert_char('!');
+        app.editor.on_external_change("disk".into());
+        assert!(app.editor.is_conflicted());
+        let later = app.last_save_at + Duration::from_secs(5);
+        app.maybe_autosave(later);
+        assert_eq!(fs::read_to_string(&path).expect("read"), "local");
 
-        app.open_docs_modal();
-        let mut quit_running = true;
-        app.handle_key(
-            key(KeyCode::Char('q'), KeyModifiers::CONTROL),
-            &mut quit_running,
-        )
-        .expect("quit docs");
-        assert!(!quit_running);
+        let mut readonly =
+            App::new_file(path.clone(), true, false, false, "local".into()).expect("app");
+        readonly.set_autosave(1);
+        readonly.editor.dirty = true;
+        readonly.maybe_autosave(later);
+        assert_eq!(fs::read_to_string(&path).expect("read"), "local");
+
+        let mut stream = App::new_stream_for_test(false);
+        stream.set_autosave(1);
+        stream.path = Some(path.clone());
+        stream.editor.insert_char('s');
+        stream.maybe_autosave(stream.last_save_at + Duration::from_secs(5));
+        assert_eq!(fs::read_to_string(&path).expect("read"), "local");
 
-        let mut home = App::new_home_for_test(false, false, false);
-        home.ui.help.open_onboarding();
-        home.ui.help.index_focus = false;
-        let mut onboarding_running = true;
-        home.handle_key(
-            key(KeyCode::Enter, KeyModifiers::NONE),
-            &mut onboarding_running,
-        )
-        .expect("advance onboarding");
-        assert_eq!(home.ui.help.onboarding_step, Some(1));
+        let _ = fs::remove_file(&path);
+    }
 
-        home.ui.help.section_idx = docs::section_count(docs::onboarding_catalog()) - 1;
-        home.ui.help.onboarding_step = Some(docs::section_count(docs::onboarding_catalog()) - 1);
-        home.handle_key(
-            key(KeyCode::Enter, KeyModifiers::NONE),
-            &mut onboarding_running,
-        )
-        .expect("finish onboarding");
-        assert!(!home.ui.help.open);
-        assert_eq!(home.status, "Guide complete");
+    #[test]
+    fn clock_hms_formats_time_of_day() {
+        let time = UNIX_EPOCH + Duration::from_secs(86_400 * 3 + 3600 * 13 + 60 * 7 + 9);
+        assert_eq!(clock_hms(time), "13:07:09");
+        assert_eq!(clock_hms(UNIX_EPOCH), "00:00:00");
     }
 
     #[test]
-    fn handle_key_prompt_backspace_empty_and_cancel_paths() {
-        let path = temp_path("prompt-branches");
-        fs::write(&path, "alpha\n\nbeta").expect("seed");
+    fn handle_watch_updates_sets_status_and_conflict() {
+        let path = temp_path("watch");
+        fs::write(&path, "local").expect("seed");
         let mut app =
-            App::new_file(path.clone(), false, false, false, "alpha\n\nbeta".into()).expect("app");
-        app.interactive_input = false;
-        let mut running = true;
-
-        app.handle_key(key(KeyCode::Char('f'), KeyModifiers::CONTROL), &mut running)
-            .expect("search start");
-        app.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE), &mut running)
-            .expect("search backspace");
-        assert_eq!(app.status, "Search: ");
-        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
-            .expect("search empty");
-        assert_eq!(app.status, "Search query empty");
+            App::new_file(path.clone(), false, false, false, "local".into()).expect("app");
+        app.watch_enabled = true;
+        app.set_watch_debounce(Duration::ZERO);
+        let (tx, rx) = mpsc::channel();
+        app.watch_rx = Some(rx);
 
-        app.handle_key(key(KeyCode::Char('f'), KeyModifiers::CONTROL), &mut running)
-            .expect("search start 2");
-        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
-            .expect("search cancel");
-        assert_eq!(app.status, "Search cancelled");
+        tx.send(WatchMessage::ExternalUpdate("local".into()))
+            .expect("send same");
+        app.handle_watch_updates();
+        assert_eq!(app.status_text(), "Ready");
 
-        app.handle_key(key(KeyCode::Char('g'), KeyModifiers::CONTROL), &mut running)
-            .expect("goto start");
-        app.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE), &mut running)
-            .expect("goto backspace");
-        assert_eq!(app.status, "Goto: ");
-        app.goto_query = "oops".into();
-        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
-            .expect("goto invalid");
-        assert_eq!(app.status, "Line out of range: oops");
+        tx.send(WatchMessage::ExternalUpdate("disk".into()))
+            .expect("send update");
+        app.handle_watch_updates();
+        assert_eq!(app.status_text(), "File refreshed from disk");
+        assert_eq!(app.editor.text(), "disk");
 
-        app.handle_key(key(KeyCode::Char('g'), KeyModifiers::CONTROL), &mut running)
-            .expect("goto start 2");
-        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
-            .expect("goto cancel");
-        assert_eq!(app.status, "Goto cancelled");
+        app.editor.insert_char('!');
+        tx.send(WatchMessage::ExternalUpdate("disk2".into()))
+            .expect("send conflict");
+        app.handle_watch_updates();
+        assert!(app.editor.is_conflicted());
+        assert_eq!(
+            app.status_text(),
+            "External update conflict: Ctrl+J/Ctrl+U hunk | Ctrl+E apply | Ctrl+K keep | Ctrl+R reload | Ctrl+M merge | Ctrl+L layout"
+        );
 
-        app.handle_key(key(KeyCode::Char('h'), KeyModifiers::CONTROL), &mut running)
-            .expect("replace start");
-        app.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE), &mut running)
-            .expect("replace find backspace");
-        assert_eq!(app.status, "Replace find: ");
-        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
-            .expect("replace empty");
-        assert_eq!(app.status, "Replace query empty");
+        app.last_saved_text = Some("saved".into());
+        tx.send(WatchMessage::ExternalUpdate("saved".into()))
+            .expect("send own save");
+        app.handle_watch_updates();
+        assert_ne!(app.editor.text(), "saved");
 
-        app.handle_key(key(KeyCode::Char('h'), KeyModifiers::CONTROL), &mut running)
-            .expect("replace start 2");
-        app.handle_key(key(KeyCode::Char('a'), KeyModifiers::NONE), &mut running)
-            .expect("replace find char");
-        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
-            .expect("replace to with");
-        app.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE), &mut running)
-            .expect("replace with backspace");
-        assert_eq!(app.status, "Replace with: ");
-        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
-            .expect("replace cancel");
-        assert_eq!(app.status, "Replace cancelled");
+        tx.send(WatchMessage::Error("bad".into()))
+            .expect("send error");
+        app.handle_watch_updates();
+        assert!(app.status_text().contains("watch error"));
 
         let _ = fs::remove_file(&path);
     }
 
     #[test]
-    fn handle_key_navigation_and_status_fallbacks_cover_remaining_paths() {
-        let path = temp_path("nav-branches");
-        fs::write(&path, "one two\n\nthree four").expect("seed");
-        let mut app = App::new_file(
-            path.clone(),
-            false,
-            false,
-            false,
-            "one two\n\nthree four".into(),
-        )
-        .expect("app");
+    fn the_conflict_hint_follows_the_keymap() {
+        let mut app = App::new_stream_for_test(false);
+        let mut keymap = KeyMap::default();
+        let f9 = Chord::parse("f9").expect("f9");
+        keymap
+            .bind(Command::ConflictApplyHunk, &[f9])
+            .expect("apply");
+        keymap.bind(Command::Merge, &[]).expect("merge");
+        app.set_keymap(keymap);
+        assert_eq!(
+            app.conflict_hint(),
+            "Ctrl+J/Ctrl+U hunk | F9 apply | Ctrl+K keep | Ctrl+R reload | Ctrl+L layout"
+        );
+    }
+
+    #[test]
+    fn an_external_revert_to_the_saved_text_is_applied() {
+        let path = temp_path("watch-revert");
+        fs::write(&path, "draft").expect("seed");
+        let mut app =
+            App::new_file(path.clone(), false, false, false, "draft".into()).expect("app");
         app.interactive_input = false;
-        app.editor_height = 2;
-        app.preview_height = 2;
+        app.watch_enabled = true;
+        app.set_watch_debounce(Duration::ZERO);
+        let (tx, rx) = mpsc::channel();
+        app.watch_rx = Some(rx);
         let mut running = true;
 
-        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::CONTROL), &mut running)
-            .expect("undo empty");
-        assert_eq!(app.status, "Nothing to undo");
-        app.handle_key(key(KeyCode::Char('y'), KeyModifiers::CONTROL), &mut running)
-            .expect("redo empty");
-        assert_eq!(app.status, "Nothing to redo");
-        app.handle_key(
-            key(
-                KeyCode::Char('z'),
-                KeyModifiers::SUPER | KeyModifiers::SHIFT,
-            ),
-            &mut running,
-        )
-        .expect("redo super shift");
-        assert_eq!(app.status, "Nothing to redo");
+        app.editor.set_cursor(5);
+        app.handle_key(key(KeyCode::Char('!'), KeyModifiers::NONE), &mut running)
+            .expect("type");
+        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::CONTROL), &mut running)
+            .expect("save");
+        let saved = fs::read_to_string(&path).expect("saved");
+        assert_eq!(saved, "draft!");
+        let update = |app: &mut App, text: &str| {
+            tx.send(WatchMessage::ExternalUpdate(text.into()))
+                .expect("send update");
+            app.handle_watch_updates();
+        };
 
-        app.handle_key(key(KeyCode::Char('m'), KeyModifiers::CONTROL), &mut running)
-            .expect("merge empty");
-        assert_eq!(app.status, "No conflict to merge");
+        update(&mut app, &saved);
+        assert_eq!(app.watch_applied_count, 0, "our own save is skipped");
+        update(&mut app, "rewritten");
+        assert_eq!(app.editor.text(), "rewritten");
+        update(&mut app, &saved);
+        assert_eq!(app.editor.text(), saved);
+        assert!(!app.editor.dirty);
 
-        app.ui.focus = PaneFocus::Preview;
-        app.preview_scroll = 3;
-        app.handle_key(key(KeyCode::PageUp, KeyModifiers::NONE), &mut running)
-            .expect("preview page up");
-        assert_eq!(app.preview_scroll, 1);
-        app.handle_key(key(KeyCode::PageDown, KeyModifiers::NONE), &mut running)
-            .expect("preview page down");
-        assert_eq!(app.preview_scroll, 3);
+        let _ = fs::remove_file(&path);
+    }
 
-        app.ui.focus = PaneFocus::Editor;
-        app.editor.set_cursor(app.editor.text().len());
-        app.handle_key(key(KeyCode::Up, KeyModifiers::ALT), &mut running)
-            .expect("paragraph up");
-        let paragraph_cursor = app.editor.cursor();
-        assert!(paragraph_cursor < app.editor.text().len());
-        app.handle_key(key(KeyCode::Down, KeyModifiers::CONTROL), &mut running)
-            .expect("paragraph down");
-        assert!(app.editor.cursor() > paragraph_cursor);
+    #[test]
+    fn a_second_external_update_keeps_the_selection_on_the_same_hunk() {
+        keeps_the_selected_hunk_on_its_row(true);
+    }
 
-        app.handle_key(key(KeyCode::Left, KeyModifiers::SUPER), &mut running)
-            .expect("line start super");
-        assert_eq!(app.editor.line_col_at_cursor().1, 0);
-        app.handle_key(key(KeyCode::Right, KeyModifiers::SUPER), &mut running)
-            .expect("line end super");
-        assert_eq!(app.editor.line_col_at_cursor(), (2, 10));
-        app.handle_key(key(KeyCode::Home, KeyModifiers::NONE), &mut running)
-            .expect("home");
-        assert_eq!(app.editor.line_col_at_cursor().1, 0);
-        app.handle_key(key(KeyCode::End, KeyModifiers::NONE), &mut running)
-            .expect("end");
-        assert_eq!(app.editor.line_col_at_cursor(), (2, 10));
+    #[test]
+    fn a_second_external_update_keeps_the_stacked_hunk_on_its_row() {
+        keeps_the_selected_hunk_on_its_row(false);
+    }
+
+    fn keeps_the_selected_hunk_on_its_row(columns: bool) {
+        let lines = |edits: &[(usize, &str)]| {
+            (1..=30)
+                .map(|n| {
+                    let edit = edits.iter().find(|(line, _)| *line == n);
+                    edit.map_or(format!("line {n}\n"), |(_, text)| format!("{text}\n"))
+                })
+                .collect::<String>()
+        };
+        let path = temp_path("watch-reanchor");
+        let mut app = App::new_file(path, false, false, false, lines(&[])).expect("app");
+        app.watch_enabled = true;
+        app.set_watch_debounce(Duration::ZERO);
+        app.conflict_columns = columns;
+        app.preview_area = ratatui::layout::Rect::new(0, 0, 60, 20);
+        let (tx, rx) = mpsc::channel();
+        app.watch_rx = Some(rx);
+        app.editor.set_cursor_line_col(29, 0);
+        app.editor.insert_char('!');
+        let update = |app: &mut App, edits: &[(usize, &str)]| {
+            tx.send(WatchMessage::ExternalUpdate(lines(edits)))
+                .expect("send update");
+            app.handle_watch_updates();
+        };
 
-        let mut edit_app =
-            App::new_file(path.clone(), false, false, false, "abc def".into()).expect("edit app");
-        edit_app.interactive_input = false;
-        edit_app.editor.set_cursor(0);
-        edit_app
-            .handle_key(key(KeyCode::Delete, KeyModifiers::CONTROL), &mut running)
-            .expect("delete word forward");
-        assert_eq!(edit_app.editor.text(), " def");
-        edit_app
-            .handle_key(key(KeyCode::Delete, KeyModifiers::NONE), &mut running)
-            .expect("delete forward");
-        assert_eq!(edit_app.editor.text(), "def");
-        edit_app.editor.set_cursor(edit_app.editor.text().len());
-        edit_app
-            .handle_key(key(KeyCode::Backspace, KeyModifiers::SUPER), &mut running)
-            .expect("delete to line start");
-        assert_eq!(edit_app.editor.text(), "");
+        update(&mut app, &[(4, "four"), (12, "twelve"), (20, "twenty")]);
+        assert_eq!(app.editor.conflict().expect("conflict").hunks.len(), 4);
+        app.selected_conflict_hunk = 1;
+        app.remember_conflict_selection();
+        let hunk_row = app.conflict_hunk_row(1).expect("row");
+        app.preview_scroll = hunk_row - 3;
+        // Where the selected hunk is drawn, relative to the top of the view.
+        let row = |app: &mut App| {
+            let row = if columns {
+                app.conflict_hunk_row(app.selected_conflict_hunk)
+            } else {
+                selected_hunk_row(&app.preview_lines_cached(58))
+            };
+            row.expect("row") - app.preview_scroll
+        };
+        let offset = row(&mut app);
+
+        // Line 12 changed again and the hunk at line 4 is gone: raw
+        // clamping would land on the hunk at line 20.
+        update(&mut app, &[(12, "twelve!"), (20, "twenty")]);
+        let conflict = app.editor.conflict().expect("conflict");
+        assert_eq!(conflict.hunks.len(), 3);
+        assert_eq!(app.selected_conflict_hunk, 0);
+        assert_eq!(conflict.hunks[0].local_start, 11);
+        assert_eq!(row(&mut app), offset);
+        assert_eq!(app.status_text(), "Conflict updated: 3 hunks (was 4)");
+
+        // The same hunk keeps its place when others appear before it.
+        update(
+            &mut app,
+            &[(1, "one"), (4, "four"), (12, "twelve!"), (20, "twenty")],
+        );
+        let conflict = app.editor.conflict().expect("conflict");
+        assert_eq!(app.selected_conflict_hunk, 2);
+        assert_eq!(conflict.hunks[2].external_lines, ["twelve!"]);
+        assert_eq!(row(&mut app), offset);
+        assert_eq!(app.status_text(), "Conflict updated: 5 hunks (was 3)");
+    }
+
+    #[test]
+    fn handle_watch_updates_applies_only_the_settled_burst() {
+        let path = temp_path("watch-burst");
+        let mut app = App::new_file(path, false, false, false, "local".into()).expect("app");
+        app.watch_enabled = true;
+        let (tx, rx) = mpsc::channel();
+        app.watch_rx = Some(rx);
+        app.editor.insert_char('!');
+
+        for i in 0..20 {
+            tx.send(WatchMessage::ExternalUpdate(format!("build {i}")))
+                .expect("send update");
+            app.handle_watch_updates();
+        }
+        assert!(!app.editor.is_conflicted());
+        assert_eq!(app.watch_applied_count, 0);
+
+        let pending = app.pending_external.as_mut().expect("pending");
+        pending.1 = Instant::now()
+            .checked_sub(DEFAULT_WATCH_DEBOUNCE)
+            .expect("past");
+        app.handle_watch_updates();
+        app.handle_watch_updates();
+
+        assert_eq!(app.watch_event_count, 20);
+        assert_eq!(app.watch_applied_count, 1);
+        assert!(app.pending_external.is_none());
+        let conflict = app.editor.conflict().expect("conflict");
+        assert_eq!(conflict.external, "build 19");
+    }
+
+    #[test]
+    fn handle_watch_updates_reports_a_file_that_stays_deleted() {
+        let path = temp_path("watch-removed");
+        let mut app = App::new_file(path.clone(), false, false, false, "kept".into()).expect("app");
+        app.watch_enabled = true;
+        app.set_watch_debounce(Duration::ZERO);
+        let (tx, rx) = mpsc::channel();
+        app.watch_rx = Some(rx);
 
-        let mut word_back =
-            App::new_file(path.clone(), false, false, false, "abc def".into()).expect("word back");
-        word_back.interactive_input = false;
-        word_back.editor.set_cursor(word_back.editor.text().len());
-        word_back
-            .handle_key(key(KeyCode::Backspace, KeyModifiers::ALT), &mut running)
-            .expect("delete word back");
-        assert_eq!(word_back.editor.text(), "abc ");
+        tx.send(WatchMessage::Removed).expect("send removed");
+        app.handle_watch_updates();
+        assert_eq!(app.status_text(), "Ready");
+        assert!(app.missing_since.is_some());
 
-        let mut readonly =
-            App::new_file(path.clone(), true, false, false, "abc".into()).expect("readonly");
-        readonly.interactive_input = false;
-        readonly
-            .handle_key(key(KeyCode::Char('k'), KeyModifiers::CONTROL), &mut running)
-            .expect("readonly ctrl-k");
-        assert_eq!(readonly.status, "Readonly: edit disabled");
+        tx.send(WatchMessage::ExternalUpdate("back".into()))
+            .expect("send recreated");
+        app.handle_watch_updates();
+        assert!(app.missing_since.is_none());
+        assert_eq!(app.editor.text(), "back");
 
-        let _ = fs::remove_file(&path);
+        tx.send(WatchMessage::Removed).expect("send removed again");
+        app.handle_watch_updates();
+        app.missing_since = Instant::now().checked_sub(FILE_MISSING_GRACE);
+        app.handle_watch_updates();
+        assert_eq!(app.status_text(), FILE_MISSING_STATUS);
+        assert_eq!(app.editor.text(), "back");
+        assert!(app.missing_since.is_none());
+        assert!(app.file_missing);
+        assert!(app.info_line(200).contains(" | ⚠ missing on disk"));
+
+        // Coming back is an ordinary external update.
+        tx.send(WatchMessage::ExternalUpdate("recreated".into()))
+            .expect("send recreated again");
+        app.handle_watch_updates();
+        assert!(!app.file_missing);
+        assert_eq!(app.editor.text(), "recreated");
+        assert_eq!(last_logged(&app), "File refreshed from disk");
+        assert!(!app.info_line(200).contains("missing"));
     }
 
     #[test]
-    fn handle_key_readonly_and_no_path_branches() {
-        let path = temp_path("readonly");
-        fs::write(&path, "x").expect("seed");
-        let mut app = App::new_file(path.clone(), true, false, false, "x".into()).expect("app");
+    fn external_updates_keep_the_cursor_line_and_can_be_undone_or_restored() {
+        let path = temp_path("external-cursor");
+        let text = "# Notes\n\none\ntwo\nthree\n";
+        let mut app = App::new_file(path.clone(), false, false, false, text.into()).expect("app");
         app.interactive_input = false;
+        app.watch_enabled = true;
+        app.set_watch_debounce(Duration::ZERO);
+        let (tx, rx) = mpsc::channel();
+        app.watch_rx = Some(rx);
         let mut running = true;
+        app.editor_height = 3;
+        app.editor.set_cursor_line_col(3, 2);
+        app.editor_scroll = 2;
+        app.preview_scroll = 1;
+
+        let longer = "# Notes\n\none\ntwo!\nthree\nfour\nfive\n";
+        tx.send(WatchMessage::ExternalUpdate(longer.into()))
+            .expect("send longer");
+        app.handle_watch_updates();
+        assert_eq!(app.editor.text(), longer);
+        assert_eq!(app.editor.line_col_at_cursor(), (3, 2));
+        assert_eq!((app.editor_scroll, app.preview_scroll), (2, 1));
+        assert_eq!(app.status_text(), "File refreshed from disk");
 
-        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::CONTROL), &mut running)
-            .expect("save");
-        assert_eq!(app.status, "Readonly: save disabled");
-
-        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
-            .expect("enter");
-        assert_eq!(app.editor.text(), "x");
-
-        app.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE), &mut running)
-            .expect("backspace");
-        assert_eq!(app.editor.text(), "x");
-
-        let mut stream_app = App::new_stream_for_test(false);
-        stream_app.readonly = false;
-        stream_app.interactive_input = false;
-        stream_app
-            .handle_key(key(KeyCode::Char('s'), KeyModifiers::CONTROL), &mut running)
-            .expect("save no path");
-        assert_eq!(stream_app.status, "No path: save disabled");
+        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::CONTROL), &mut running)
+            .expect("undo");
+        assert_eq!(app.editor.text(), text);
+        assert!(app.editor.dirty, "the file on disk still has the update");
 
-        stream_app
-            .handle_key(key(KeyCode::Char('r'), KeyModifiers::CONTROL), &mut running)
-            .expect("reload stream");
-        assert_eq!(stream_app.status, "Stream mode: reload disabled");
+        app.handle_key(key(KeyCode::Char('r'), KeyModifiers::ALT), &mut running)
+            .expect("restore unset");
+        assert!(
+            app.status_text().contains("keep_before_external"),
+            "{}",
+            app.status_text()
+        );
 
-        let _ = fs::remove_file(&path);
+        app.set_keep_before_external(true);
+        app.editor.dirty = false;
+        let before = app.editor.text().to_string();
+        tx.send(WatchMessage::ExternalUpdate("regenerated\n".into()))
+            .expect("send regenerated");
+        app.handle_watch_updates();
+        // The warning stays up until a key; the update is in the log.
+        assert!(app.status_text().contains("keep_before_external"));
+        assert_eq!(
+            last_logged(&app),
+            "File refreshed from disk (Alt+R restores the previous text)"
+        );
+        app.handle_key(key(KeyCode::Char('r'), KeyModifiers::ALT), &mut running)
+            .expect("restore");
+        assert_eq!(app.editor.text(), before);
+        assert!(app.editor.dirty);
+        app.handle_key(key(KeyCode::Char('r'), KeyModifiers::ALT), &mut running)
+            .expect("restore again");
+        assert_eq!(app.status_text(), "No external update to undo");
     }
 
     #[test]
-    fn handle_key_ctrl_k_deletes_line_end_when_not_conflicted() {
-        let path = temp_path("no-conflict-actions");
-        fs::write(&path, "abc\ndef").expect("seed");
+    fn ctrl_r_backs_up_the_discarded_text_and_ctrl_z_brings_it_back() {
+        let dir = std::env::temp_dir().join(format!("mdv-reload-backup-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("dir");
+        let path = dir.join("notes.md");
+        let backups = dir.join("state").join("backups");
+        fs::write(&path, "saved\n").expect("seed");
         let mut app =
-            App::new_file(path.clone(), false, false, false, "abc\ndef".into()).expect("app");
+            App::new_file(path.clone(), false, false, false, "saved\n".into()).expect("app");
         app.interactive_input = false;
+        app.set_backup_dir(Some(backups.clone()));
         let mut running = true;
 
-        app.editor.set_cursor(1);
-        app.handle_key(key(KeyCode::Char('k'), KeyModifiers::CONTROL), &mut running)
-            .expect("delete line end");
-        assert_eq!(app.editor.text(), "a\ndef");
-        assert_eq!(app.status, "Deleted to line end");
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
+            .expect("type");
+        let edited = app.editor.text().to_string();
+        fs::write(&path, "disk\n").expect("outside write");
+        // The first Ctrl+R opens a conflict and the second one reloads.
+        for _ in 0..2 {
+            app.handle_key(key(KeyCode::Char('r'), KeyModifiers::CONTROL), &mut running)
+                .expect("reload");
+        }
+        assert_eq!(app.editor.text(), "disk\n");
+        assert!(!app.editor.dirty);
+        let status = app.status_text().to_string();
+        let target = status
+            .strip_prefix("Reloaded external; old text in ")
+            .and_then(|rest| rest.strip_suffix(" (Ctrl+Z undoes)"))
+            .map(PathBuf::from)
+            .expect(&status);
+        assert!(target.starts_with(&backups));
+        let folder = target.parent().expect("folder").to_path_buf();
+        assert_eq!(fs::read_to_string(&target).expect("backup"), edited);
 
-        app.handle_key(key(KeyCode::Char('m'), KeyModifiers::CONTROL), &mut running)
-            .expect("merge");
-        assert_eq!(app.status, "No conflict to merge");
+        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::CONTROL), &mut running)
+            .expect("undo");
+        assert_eq!(app.editor.text(), edited);
+        assert!(app.editor.dirty);
+        assert!(app.editor.is_conflicted());
 
-        let _ = fs::remove_file(&path);
+        // Nothing is lost reloading text that matches the disk.
+        app.editor.reload_external();
+        app.handle_key(key(KeyCode::Char('r'), KeyModifiers::CONTROL), &mut running)
+            .expect("reload same");
+        assert_eq!(app.status_text(), "Reloaded from disk");
+
+        // Backups can be turned off; on, they cover a clean buffer the disk
+        // no longer matches too.
+        fs::write(&path, "newer\n").expect("newer");
+        app.set_backup_dir(None);
+        app.handle_key(key(KeyCode::Char('r'), KeyModifiers::CONTROL), &mut running)
+            .expect("reload without backup");
+        assert_eq!(app.status_text(), "Reloaded from disk");
+        assert_eq!(fs::read_dir(&folder).expect("dir").count(), 1);
+        app.set_backup_dir(Some(backups));
+        fs::write(&path, "newest\n").expect("newest");
+        app.handle_key(key(KeyCode::Char('r'), KeyModifiers::CONTROL), &mut running)
+            .expect("reload clean");
+        assert!(
+            app.status_text()
+                .starts_with("Reloaded from disk; old text in ")
+        );
+        assert_eq!(fs::read_dir(&folder).expect("dir").count(), 2);
+        // The file's own directory holds only the file.
+        assert_eq!(fs::read_dir(&dir).expect("dir").count(), 2);
+        let _ = fs::remove_dir_all(&dir);
     }
 
     #[test]
-    fn handle_key_conflict_hunk_nav_and_apply() {
-        let path = temp_path("conflict-hunk-nav");
-        fs::write(&path, "a\nb\nc").expect("seed");
+    fn a_deleted_file_keeps_the_buffer_and_save_recreates_it() {
+        let path = temp_path("deleted-then-saved");
+        fs::write(&path, "draft").expect("seed");
         let mut app =
-            App::new_file(path.clone(), false, false, false, "a\nb\nc".into()).expect("app");
-        app.editor.dirty = true;
-        app.editor.on_external_change("a\nB\nc\nd".into());
-        assert!(app.editor.is_conflicted());
-        assert_eq!(app.editor.conflict().expect("conflict").hunks.len(), 2);
-
+            App::new_file(path.clone(), false, false, false, "draft".into()).expect("app");
+        app.interactive_input = false;
+        app.watch_enabled = true;
+        let (tx, rx) = mpsc::channel();
+        app.watch_rx = Some(rx);
         let mut running = true;
-        app.handle_key(key(KeyCode::Char('j'), KeyModifiers::CONTROL), &mut running)
-            .expect("next hunk");
-        assert_eq!(app.status, "Conflict hunk 2/2");
 
-        app.handle_key(key(KeyCode::Char('e'), KeyModifiers::CONTROL), &mut running)
-            .expect("apply hunk");
-        assert_eq!(app.status, "Applied external hunk");
-        assert!(app.editor.text().contains("\nd"));
-        assert_eq!(app.editor.conflict().expect("conflict").hunks.len(), 1);
+        fs::remove_file(&path).expect("delete");
+        tx.send(WatchMessage::Removed).expect("send removed");
+        app.handle_watch_updates();
+        app.missing_since = Instant::now().checked_sub(FILE_MISSING_GRACE);
+        app.handle_watch_updates();
+        assert!(app.file_missing);
 
-        app.handle_key(key(KeyCode::Char('u'), KeyModifiers::CONTROL), &mut running)
-            .expect("prev hunk");
-        assert_eq!(app.status, "Conflict hunk 1/1");
+        app.handle_key(key(KeyCode::Char('r'), KeyModifiers::CONTROL), &mut running)
+            .expect("reload");
+        assert_eq!(app.editor.text(), "draft");
+        assert_eq!(
+            app.status_text(),
+            format!("Not reloaded: {FILE_MISSING_STATUS}")
+        );
 
-        app.handle_key(key(KeyCode::Char('e'), KeyModifiers::CONTROL), &mut running)
-            .expect("apply final hunk");
-        assert_eq!(app.status, "Resolved conflict from hunks");
-        assert!(!app.editor.is_conflicted());
+        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::CONTROL), &mut running)
+            .expect("save");
+        assert_eq!(fs::read_to_string(&path).expect("recreated"), "draft");
+        assert_eq!(app.status_text(), "Saved");
+        assert!(!app.file_missing);
+
+        // An empty file on disk is a real reload, not an error.
+        fs::write(&path, "").expect("empty");
+        app.handle_key(key(KeyCode::Char('r'), KeyModifiers::CONTROL), &mut running)
+            .expect("reload empty");
+        assert_eq!(app.editor.text(), "");
+        assert_eq!(app.status_text(), "Reloaded from disk");
 
         let _ = fs::remove_file(&path);
     }
 
     #[test]
-    fn handle_key_navigation_and_quit() {
-        let path = temp_path("nav");
-        fs::write(&path, "ab\ncd").expect("seed");
-        let mut app =
-            App::new_file(path.clone(), false, false, false, "ab\ncd".into()).expect("app");
+    fn unreadable_files_never_replace_the_buffer() {
+        let path = temp_path("unreadable");
+        fs::create_dir(&path).expect("dir in place of the file");
+        let mut app = App::new_file(path.clone(), false, false, false, "mine".into()).expect("app");
         app.interactive_input = false;
         let mut running = true;
 
-        app.handle_key(key(KeyCode::Left, KeyModifiers::NONE), &mut running)
-            .expect("left");
-        app.handle_key(key(KeyCode::Right, KeyModifiers::NONE), &mut running)
-            .expect("right");
-        app.handle_key(key(KeyCode::Up, KeyModifiers::NONE), &mut running)
-            .expect("up");
-        app.handle_key(key(KeyCode::Down, KeyModifiers::NONE), &mut running)
-            .expect("down");
-        app.handle_key(key(KeyCode::PageDown, KeyModifiers::NONE), &mut running)
-            .expect("pgdn");
-        app.handle_key(key(KeyCode::PageUp, KeyModifiers::NONE), &mut running)
-            .expect("pgup");
-        app.handle_key(key(KeyCode::Char('q'), KeyModifiers::CONTROL), &mut running)
-            .expect("quit");
-        assert!(!running);
+        app.handle_key(key(KeyCode::Char('r'), KeyModifiers::CONTROL), &mut running)
+            .expect("reload");
+        assert_eq!(app.editor.text(), "mine");
+        assert!(
+            app.status_text().starts_with("Not reloaded ("),
+            "{}",
+            app.status_text()
+        );
+        assert!(
+            app.status_text().ends_with("); buffer kept"),
+            "{}",
+            app.status_text()
+        );
+        assert!(!app.file_missing);
+        fs::remove_dir(&path).expect("remove dir");
 
-        let _ = fs::remove_file(&path);
-    }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::write(&path, "disk").expect("seed");
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o000)).expect("chmod");
+            // Root reads through permissions; there is nothing to deny then.
+            if fs::read(&path).is_err() {
+                app.handle_key(key(KeyCode::Char('r'), KeyModifiers::CONTROL), &mut running)
+                    .expect("reload denied");
+                assert_eq!(app.editor.text(), "mine");
+                assert!(
+                    app.status_text().contains("ermission denied"),
+                    "{}",
+                    app.status_text()
+                );
+            }
+            let _ = fs::remove_file(&path);
+        }
 
-    #[test]
-    fn handle_watch_updates_sets_status_and_conflict() {
-        let path = temp_path("watch");
-        fs::write(&path, "local").expect("seed");
-        let mut app =
-            App::new_file(path.clone(), false, false, false, "local".into()).expect("app");
         app.watch_enabled = true;
         let (tx, rx) = mpsc::channel();
         app.watch_rx = Some(rx);
-
-        tx.send(WatchMessage::ExternalUpdate("local".into()))
-            .expect("send same");
-        app.handle_watch_updates();
-        assert_eq!(app.status, "Ready");
-
-        tx.send(WatchMessage::ExternalUpdate("disk".into()))
-            .expect("send update");
-        app.handle_watch_updates();
-        assert_eq!(app.status, "File refreshed from disk");
-        assert_eq!(app.editor.text(), "disk");
-
-        app.editor.insert_char('!');
-        tx.send(WatchMessage::ExternalUpdate("disk2".into()))
-            .expect("send conflict");
+        tx.send(WatchMessage::Unreadable(
+            "Permission denied (os error 13)".into(),
+        ))
+        .expect("send unreadable");
         app.handle_watch_updates();
-        assert!(app.editor.is_conflicted());
+        assert_eq!(app.editor.text(), "mine");
         assert_eq!(
-            app.status,
-            "External update conflict: Ctrl+J/Ctrl+U hunk | Ctrl+E apply | Ctrl+K keep | Ctrl+R reload | Ctrl+M merge"
+            app.status_text(),
+            "Cannot read the file on disk (Permission denied (os error 13)); buffer kept"
         );
-
-        tx.send(WatchMessage::Error("bad".into()))
-            .expect("send error");
-        app.handle_watch_updates();
-        assert!(app.status.contains("watch error"));
-
-        let _ = fs::remove_file(&path);
     }
 
     #[test]
@@ -3850,21 +9245,188 @@ mod tests {
 
         tx.send(StreamMessage::Update {
             text: "one".into(),
-            truncated: false,
+            dropped: 0,
         })
         .expect("send update");
         app.handle_stream_updates();
         assert_eq!(app.editor.text(), "one");
-        assert_eq!(app.status, "stream update received");
+        assert_eq!(app.status_text(), "stream update received");
 
         tx.send(StreamMessage::Error("e".into())).expect("send err");
         app.handle_stream_updates();
-        assert!(app.status.contains("stream error"));
+        assert!(app.status_text().contains("stream error"));
 
         tx.send(StreamMessage::End).expect("send end");
         app.handle_stream_updates();
         assert!(app.stream_done);
-        assert_eq!(app.status, "stdin closed | Ctrl+Q quit");
+        assert!(app.status_text().contains("stream error"));
+        assert_eq!(last_logged(&app), "stdin closed | Ctrl+Q quit");
+    }
+
+    #[test]
+    fn edit_stream_turns_the_captured_text_into_a_buffer_once_stdin_closes() {
+        let target = temp_path("edit-stream");
+        let mut app = App::new_stream_for_test(false);
+        app.set_edit_stream(true);
+        let (tx, rx) = mpsc::channel();
+        app.stream_rx = Some(rx);
+        let mut running = true;
+
+        tx.send(StreamMessage::Append("# Notes\n".into()))
+            .expect("send");
+        app.handle_stream_updates();
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
+            .expect("type while streaming");
+        assert_eq!(app.editor.text(), "# Notes\n");
+        assert_eq!(mode_label(&app), "stream");
+
+        tx.send(StreamMessage::Append("- one\n".into()))
+            .expect("send");
+        tx.send(StreamMessage::End).expect("send end");
+        app.handle_stream_updates();
+        assert!(!app.stream_mode && !app.readonly);
+        assert_eq!(mode_label(&app), "normal");
+        assert_eq!(
+            app.status_text(),
+            "stream → editing: stdin closed, Ctrl+S saves"
+        );
+        assert!(app.info_line(200).contains("RW | dirty | mode=normal"));
+        assert!(app.info_line(200).contains("stream → editing"));
+
+        app.handle_key(key(KeyCode::End, KeyModifiers::CONTROL), &mut running)
+            .expect("end");
+        for c in "- two".chars() {
+            app.handle_key(key(KeyCode::Char(c), KeyModifiers::NONE), &mut running)
+                .expect("type");
+        }
+        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::CONTROL), &mut running)
+            .expect("undo");
+        assert_ne!(app.editor.text(), "# Notes\n- one\n- two");
+        app.handle_key(key(KeyCode::Char('y'), KeyModifiers::CONTROL), &mut running)
+            .expect("redo");
+        assert_eq!(app.editor.text(), "# Notes\n- one\n- two");
+
+        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::CONTROL), &mut running)
+            .expect("save");
+        assert!(app.save_as_mode);
+        for c in target.to_string_lossy().chars() {
+            app.handle_key(key(KeyCode::Char(c), KeyModifiers::NONE), &mut running)
+                .expect("type path");
+        }
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("save as");
+        assert_eq!(
+            fs::read_to_string(&target).expect("saved"),
+            "# Notes\n- one\n- two"
+        );
+        assert!(!app.info_line(200).contains("stream → editing"));
+        let _ = fs::remove_file(&target);
+    }
+
+    #[test]
+    fn follow_tracks_stream_updates_until_scrolled_up() {
+        let mut app = App::new_stream_for_test(false);
+        app.interactive_input = false;
+        let (tx, rx) = mpsc::channel();
+        app.stream_rx = Some(rx);
+        app.set_initial_focus(PaneFocus::Preview);
+        app.set_follow(true);
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).expect("terminal");
+
+        let mut text = String::new();
+        for round in 0..3 {
+            for line in 0..40 {
+                text.push_str(&format!("line {round}-{line}\n\n"));
+            }
+            tx.send(StreamMessage::Update {
+                text: text.clone(),
+                dropped: 0,
+            })
+            .expect("send update");
+            app.handle_stream_updates();
+            draw_text(&mut app, &mut terminal);
+
+            let total = app.editor.line_count();
+            assert_eq!(app.editor_scroll, total - app.editor_height);
+            let preview_total = app.preview_lines_cached(app.preview_area.width - 2).len();
+            assert_eq!(app.preview_scroll, preview_total - app.preview_height);
+        }
+
+        app.scroll_preview(-1);
+        assert_eq!(app.status_text(), "Follow paused (Ctrl+End resumes)");
+        let preview_scroll = app.preview_scroll;
+        text.push_str("more\n");
+        tx.send(StreamMessage::Update {
+            text: text.clone(),
+            dropped: 0,
+        })
+        .expect("send update");
+        app.handle_stream_updates();
+        draw_text(&mut app, &mut terminal);
+        assert_eq!(app.preview_scroll, preview_scroll);
+
+        let mut running = true;
+        app.handle_key(key(KeyCode::End, KeyModifiers::CONTROL), &mut running)
+            .expect("resume");
+        assert!(app.following());
+        app.handle_key(key(KeyCode::End, KeyModifiers::CONTROL), &mut running)
+            .expect("stop");
+        assert!(!app.following());
+        assert_eq!(app.status_text(), "Follow off");
+    }
+
+    #[test]
+    fn stream_appends_render_only_the_new_tail() {
+        let mut app = App::new_stream_for_test(false);
+        app.interactive_input = false;
+        let (tx, rx) = mpsc::channel();
+        app.stream_rx = Some(rx);
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).expect("terminal");
+
+        let mut text = String::new();
+        for part in 0..50 {
+            let chunk = format!("## Part {part}\n\nBody of *part* {part}\n\n");
+            text.push_str(&chunk);
+            tx.send(StreamMessage::Append(chunk)).expect("send append");
+            app.handle_stream_updates();
+            draw_text(&mut app, &mut terminal);
+        }
+        assert_eq!(app.editor.text(), text);
+        assert_eq!(app.editor.cursor(), 0);
+        assert_eq!(app.editor_scroll, 0);
+        assert_eq!(app.status_text(), "stream update received");
+        assert_eq!(app.test_preview_cache_misses, 50);
+        assert_eq!(app.test_preview_appends, 49);
+
+        let width = app.preview_area.width - 2;
+        let lines = app.preview_lines_cached(width);
+        let rendered: Vec<_> = mdv_core::render_preview_segments(&text, width)
+            .into_iter()
+            .map(PreviewLineKind::Markdown)
+            .collect();
+        assert_eq!(*lines, rendered);
+
+        // A full copy that matches the buffer changes nothing.
+        let revision = app.editor.revision();
+        tx.send(StreamMessage::Update {
+            text: text.clone(),
+            dropped: 0,
+        })
+        .expect("send update");
+        app.handle_stream_updates();
+        assert_eq!(app.editor.revision(), revision);
+
+        // Appends after a full copy land on top of it.
+        tx.send(StreamMessage::Update {
+            text: "fresh\n".into(),
+            dropped: 1200,
+        })
+        .expect("send update");
+        tx.send(StreamMessage::Append("tail\n".into()))
+            .expect("send append");
+        app.handle_stream_updates();
+        assert_eq!(app.editor.text(), "fresh\ntail\n");
+        assert_eq!(app.status_text(), "stream update received (trimmed)");
     }
 
     #[test]
@@ -3876,12 +9438,34 @@ mod tests {
 
         tx.send(StreamMessage::Update {
             text: "trimmed".into(),
-            truncated: true,
+            dropped: 1_300_000,
         })
         .expect("send update");
         app.handle_stream_updates();
         assert_eq!(app.editor.text(), "trimmed");
-        assert_eq!(app.status, "stream update received (trimmed)");
+        assert_eq!(app.status_text(), "stream update received (trimmed)");
+        assert!(app.info_line(200).ends_with(" | ⚠ 1.2 MB trimmed"));
+
+        // A periodic full copy that trimmed nothing new keeps the marker.
+        tx.send(StreamMessage::Update {
+            text: "trimmed\nmore".into(),
+            dropped: 1_300_000,
+        })
+        .expect("send update");
+        app.handle_stream_updates();
+        assert_eq!(app.status_text(), "stream update received");
+        assert_eq!(app.stream_dropped, 1_300_000);
+        assert!(app.info_line(200).contains("⚠ 1.2 MB trimmed"));
+        assert!(!App::new_stream_for_test(false).info_line(200).contains('⚠'));
+    }
+
+    #[test]
+    fn format_bytes_picks_a_unit() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(10 * 1024 * 1024), "10.0 MB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GB");
     }
 
     #[test]
@@ -3906,6 +9490,173 @@ mod tests {
         let _ = fs::remove_file(&path);
     }
 
+    #[test]
+    fn view_only_hides_editor_and_ignores_typing() {
+        let path = temp_path("view-only");
+        fs::write(&path, "# Title\nbody").expect("seed");
+        let mut app =
+            App::new_file(path.clone(), false, false, false, "# Title\nbody".into()).expect("app");
+        app.interactive_input = false;
+        app.set_view_only(true);
+        let mut running = true;
+
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
+            .expect("type");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("enter");
+        app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE), &mut running)
+            .expect("tab");
+        app.handle_key(key(KeyCode::Char('d'), KeyModifiers::CONTROL), &mut running)
+            .expect("delete line");
+        app.handle_paste("pasted");
+        assert_eq!(app.editor.text(), "# Title\nbody");
+        assert!(!app.editor.dirty);
+        assert_eq!(mode_label(&app), "view-only");
+        assert!(app.info_line(200).contains("mode=view-only"));
+
+        let mut terminal = Terminal::new(TestBackend::new(160, 30)).expect("terminal");
+        terminal.draw(|frame| app.draw(frame)).expect("draw");
+        let buffer = terminal.backend().buffer();
+        let rows = (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        assert!(!rows.iter().any(|row| row.contains("Editor")));
+        assert!(rows.iter().any(|row| row.contains("Preview")));
+        assert_eq!(app.editor_area.width, 0);
+        assert_eq!(app.preview_area.width, 160);
+
+        app.handle_key(key(KeyCode::BackTab, KeyModifiers::SHIFT), &mut running)
+            .expect("switch panes");
+        assert_eq!(app.ui.focus, PaneFocus::Preview);
+
+        app.handle_key(key(KeyCode::Char('o'), KeyModifiers::CONTROL), &mut running)
+            .expect("leave view-only");
+        assert!(!app.ui.view_only);
+        assert_eq!(app.ui.focus, PaneFocus::Editor);
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
+            .expect("type");
+        assert!(app.editor.dirty);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn view_only_scrolls_and_searches_preview() {
+        let text = (0..60)
+            .map(|idx| format!("line {idx}\n"))
+            .collect::<String>()
+            + "needle here\n";
+        let path = temp_path("view-only-search");
+        let mut app = App::new_file(path, false, false, false, text.clone()).expect("app");
+        app.interactive_input = false;
+        app.handle_key(key(KeyCode::Char('o'), KeyModifiers::CONTROL), &mut true)
+            .expect("view-only");
+        assert!(app.ui.view_only);
+        assert_eq!(app.status_text(), "View-only: editing disabled");
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).expect("terminal");
+        terminal.draw(|frame| app.draw(frame)).expect("draw");
+        app.preview_scroll = 0;
+        let mut running = true;
+
+        app.handle_key(key(KeyCode::Down, KeyModifiers::NONE), &mut running)
+            .expect("down");
+        assert_eq!(app.preview_scroll, 1);
+        app.handle_key(key(KeyCode::PageDown, KeyModifiers::NONE), &mut running)
+            .expect("page down");
+        assert_eq!(app.preview_scroll, 1 + app.preview_height);
+        app.handle_key(key(KeyCode::Home, KeyModifiers::NONE), &mut running)
+            .expect("home");
+        assert_eq!(app.preview_scroll, 0);
+        assert_eq!(app.editor.cursor(), text.len());
+
+        app.handle_key(key(KeyCode::Char('f'), KeyModifiers::CONTROL), &mut running)
+            .expect("search");
+        for ch in "needle".chars() {
+            app.handle_key(key(KeyCode::Char(ch), KeyModifiers::NONE), &mut running)
+                .expect("query");
+        }
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("find");
+        let found = app.preview_scroll;
+        assert!(found > 0);
+        assert_eq!(app.status_text(), "preview match 1/1: needle");
+        assert_eq!(app.editor.text(), text);
+
+        app.handle_key(key(KeyCode::Char('n'), KeyModifiers::CONTROL), &mut running)
+            .expect("find next wraps");
+        assert_eq!(app.preview_scroll, found);
+
+        app.last_search_query = "missing".into();
+        app.handle_key(key(KeyCode::Char('p'), KeyModifiers::CONTROL), &mut running)
+            .expect("find prev");
+        assert_eq!(app.status_text(), "Not found: missing");
+    }
+
+    #[test]
+    fn preview_focus_searches_the_rendered_text() {
+        let text: String = (1..=5)
+            .map(|n| format!("## Table {n}\n\n| a | b |\n|---|---|\n| {n} | x |\n\n"))
+            .collect();
+        let mut app =
+            App::new_file(temp_path("preview-search"), false, false, false, text).expect("app");
+        app.editor.set_cursor(0);
+        let mut terminal = Terminal::new(TestBackend::new(100, 12)).expect("terminal");
+        draw_text(&mut app, &mut terminal);
+        let mut running = true;
+        let shift_tab = key(KeyCode::BackTab, KeyModifiers::SHIFT);
+        app.handle_key(shift_tab, &mut running).expect("to preview");
+
+        // The source says `|---|`; only the rendered separator has spaces.
+        app.handle_key(key(KeyCode::Char('f'), KeyModifiers::CONTROL), &mut running)
+            .expect("search");
+        for c in "| - |".chars() {
+            app.handle_key(key(KeyCode::Char(c), KeyModifiers::NONE), &mut running)
+                .expect("query");
+        }
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("find");
+        assert_eq!(app.status_text(), "preview match 1/5: | - |");
+        app.handle_key(key(KeyCode::F(3), KeyModifiers::NONE), &mut running)
+            .expect("next");
+        assert_eq!(app.status_text(), "preview match 2/5: | - |");
+        let lines = app.preview_lines_cached(app.preview_area.width - 2);
+        assert!(lines[app.preview_scroll].text().contains("| - |"));
+        assert!(
+            lines[..app.preview_scroll]
+                .iter()
+                .any(|line| line.text().contains("Table 2"))
+        );
+
+        let screen = draw_text(&mut app, &mut terminal);
+        assert!(screen.contains("| - | - |"));
+        let preview = cursor_rect(app.preview_area);
+        let buffer = terminal.backend().buffer();
+        let current_bg = build_theme(app.ui.theme, app.ui.no_color, None)
+            .current_match
+            .bg;
+        assert!(
+            (preview.left()..preview.right())
+                .any(|x| buffer[(x, preview.top())].bg == current_bg.expect("current bg"))
+        );
+
+        app.handle_key(key(KeyCode::F(3), KeyModifiers::SHIFT), &mut running)
+            .expect("prev");
+        assert_eq!(app.status_text(), "preview match 1/5: | - |");
+        app.handle_key(key(KeyCode::F(3), KeyModifiers::SHIFT), &mut running)
+            .expect("prev wraps");
+        assert_eq!(app.status_text(), "preview match 5/5: | - |");
+
+        // Back in the editor the cursor is on the table the match is in.
+        app.handle_key(shift_tab, &mut running).expect("to editor");
+        let (line, _) = app.editor.line_col_at_cursor();
+        assert!((24..=28).contains(&line), "{line}");
+        assert_eq!(app.editor.text().matches("| - |").count(), 0);
+    }
+
     #[test]
     fn draw_handles_empty_hunks_branch() {
         let path = temp_path("draw-empty");
@@ -3990,7 +9741,7 @@ mod tests {
         let mut running = true;
         app.handle_key(key(KeyCode::Char('r'), KeyModifiers::CONTROL), &mut running)
             .expect("reload conflict");
-        assert_eq!(app.status, "Reloaded external");
+        assert_eq!(app.status_text(), "Reloaded external");
         assert_eq!(app.editor.text(), "disk");
 
         app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
@@ -4021,23 +9772,82 @@ mod tests {
         app.handle_key(key(KeyCode::BackTab, KeyModifiers::SHIFT), &mut running)
             .expect("shift+tab");
 
-        assert_eq!(app.status, "Mode: editor");
+        assert_eq!(app.status_text(), "Mode: editor");
 
         let _ = fs::remove_file(&path);
     }
 
     #[test]
-    fn ctrl_t_switches_mode_from_editor() {
-        let path = temp_path("mode-toggle-ctrlt");
-        fs::write(&path, "x").expect("seed");
-        let mut app = App::new_file(path.clone(), false, false, false, "x".into()).expect("app");
+    fn ctrl_t_outline_enter_jumps_to_heading() {
+        let text = "# Intro\n\nhello\n\nSetup\n-----\n\n> ## Quoted\n\n## Setup\nend";
+        let path = temp_path("outline-jump");
+        let mut app = App::new_file(path, false, false, false, text.into()).expect("app");
+        app.interactive_input = false;
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).expect("terminal");
+        terminal.draw(|frame| app.draw(frame)).expect("draw");
         let mut running = true;
 
         app.handle_key(key(KeyCode::Char('t'), KeyModifiers::CONTROL), &mut running)
             .expect("ctrl+t");
+        assert!(app.outline_mode);
+        assert_eq!(mode_label(&app), "outline");
+        assert_eq!(app.outline_entries.len(), 4);
+        assert_eq!(
+            app.outline_selected, 3,
+            "starts at the heading under the cursor"
+        );
+        terminal
+            .draw(|frame| app.draw(frame))
+            .expect("draw outline");
 
-        assert_eq!(app.status, "Mode: view");
-        let _ = fs::remove_file(&path);
+        app.handle_key(key(KeyCode::Home, KeyModifiers::NONE), &mut running)
+            .expect("home");
+        app.handle_key(key(KeyCode::Down, KeyModifiers::NONE), &mut running)
+            .expect("down");
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
+            .expect("ignored");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("jump");
+        assert!(!app.outline_mode);
+        assert_eq!(app.editor.line_col_at_cursor(), (4, 0));
+        assert_eq!(app.status_text(), "Jumped to: Setup");
+        assert_eq!(app.editor.text(), text);
+        let width = app.preview_area.width - 2;
+        let lines = app.preview_lines_cached(width);
+        assert!(lines[app.preview_scroll].text().contains("Setup"));
+
+        app.handle_key(key(KeyCode::Char('t'), KeyModifiers::CONTROL), &mut running)
+            .expect("reopen");
+        assert_eq!(app.outline_selected, 1);
+        app.handle_key(key(KeyCode::End, KeyModifiers::NONE), &mut running)
+            .expect("end");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("jump duplicate");
+        assert_eq!(app.editor.line_col_at_cursor(), (9, 0));
+
+        app.handle_key(key(KeyCode::Char('t'), KeyModifiers::CONTROL), &mut running)
+            .expect("reopen");
+        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
+            .expect("close");
+        assert!(!app.outline_mode);
+        assert_eq!(app.status_text(), "Outline closed");
+    }
+
+    #[test]
+    fn ctrl_t_without_headings_reports_status() {
+        let mut app = App::new_file(
+            temp_path("outline-empty"),
+            false,
+            false,
+            false,
+            "plain".into(),
+        )
+        .expect("app");
+        let mut running = true;
+        app.handle_key(key(KeyCode::Char('t'), KeyModifiers::CONTROL), &mut running)
+            .expect("ctrl+t");
+        assert!(!app.outline_mode);
+        assert_eq!(app.status_text(), "No headings");
     }
 
     #[test]
@@ -4050,8 +9860,174 @@ mod tests {
         app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE), &mut running)
             .expect("tab insert");
 
-        assert_eq!(app.editor.text(), "x    ");
-        assert_eq!(app.status, "Tab inserted");
+        assert_eq!(app.editor.text(), "x  ");
+        assert_eq!(app.status_text(), "Tab inserted");
+
+        app.set_indent_width(4);
+        app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE), &mut running)
+            .expect("tab insert");
+        assert_eq!(app.editor.text(), "x      ");
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn tab_and_shift_tab_indent_and_dedent_selected_lines() {
+        let path = temp_path("tab-indent");
+        let text = "a
+   b
+	c
+d";
+        fs::write(&path, text).expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, text.into()).expect("app");
+        let mut running = true;
+
+        app.editor.set_selection_anchor(0);
+        app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE), &mut running)
+            .expect("indent");
+        assert_eq!(app.editor.text(), "  a\n     b\n  \tc\n  d");
+        assert_eq!(app.status_text(), "Indented 4 lines");
+        assert!(app.selection_range().is_some());
+
+        app.handle_key(key(KeyCode::BackTab, KeyModifiers::SHIFT), &mut running)
+            .expect("dedent");
+        assert_eq!(app.editor.text(), text);
+        assert_eq!(app.status_text(), "Dedented 4 lines");
+
+        app.handle_key(key(KeyCode::BackTab, KeyModifiers::SHIFT), &mut running)
+            .expect("dedent partial");
+        assert_eq!(app.editor.text(), "a\n b\nc\nd");
+        assert_eq!(app.status_text(), "Dedented 2 lines");
+        assert_eq!(app.ui.focus, PaneFocus::Editor);
+        let _ = fs::remove_file(&path);
+    }
+
+    fn large_document(bytes: usize) -> String {
+        let block = "## Section\n\nSome *prose* with a [link](https://example.com) and `code`.\n\n\
+                     - item one\n- item two\n\n```\nlet x = 1;\n```\n\n";
+        block.repeat(bytes / block.len() + 1)
+    }
+
+    fn draw_text(app: &mut App, terminal: &mut Terminal<TestBackend>) -> String {
+        terminal.draw(|frame| app.draw(frame)).expect("draw");
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect()
+    }
+
+    #[test]
+    fn large_file_mode_draws_a_10mb_document_quickly() {
+        let text = large_document(10 * 1024 * 1024);
+        let mut app =
+            App::new_file(temp_path("large-file"), false, false, false, text).expect("app");
+        app.editor.set_cursor(0);
+        let mut terminal = Terminal::new(TestBackend::new(140, 40)).expect("terminal");
+        let mut running = true;
+
+        // Opening pays for the line index once; drawing must not rescan.
+        assert!(app.editor.line_count() > 500_000);
+
+        let started = Instant::now();
+        let screen = draw_text(&mut app, &mut terminal);
+        app.handle_key(key(KeyCode::End, KeyModifiers::CONTROL), &mut running)
+            .expect("ctrl+end");
+        draw_text(&mut app, &mut terminal);
+        app.handle_key(key(KeyCode::Up, KeyModifiers::NONE), &mut running)
+            .expect("up");
+        let bottom = draw_text(&mut app, &mut terminal);
+        let elapsed = started.elapsed();
+
+        assert!(app.large_file_mode());
+        assert!(screen.contains("large file mode"), "{screen}");
+        assert!(screen.contains("Section"));
+        assert!(bottom.contains("let x = 1;"));
+        assert!(
+            elapsed < Duration::from_millis(100),
+            "three draws took {elapsed:?}"
+        );
+    }
+
+    #[test]
+    fn large_file_preview_window_follows_editor_and_slides() {
+        let text = large_document(64 * 1024);
+        let mut app =
+            App::new_file(temp_path("large-window"), false, false, false, text).expect("app");
+        app.set_large_file_threshold(16 * 1024);
+        app.editor.set_cursor(0);
+        let mut terminal = Terminal::new(TestBackend::new(140, 40)).expect("terminal");
+        draw_text(&mut app, &mut terminal);
+        let (start, end) = app.large_preview_window().expect("window");
+        assert_eq!(start, 0);
+        assert!(end < app.editor.line_count());
+
+        app.editor_scroll = 2_000;
+        draw_text(&mut app, &mut terminal);
+        let (start, _) = app.large_preview_window().expect("window");
+        // The margin sits above the editor's first line, widened to a block start.
+        assert!(start <= 2_000 && 2_000 - start <= LARGE_PREVIEW_MARGIN_LINES + 12);
+
+        app.ui.focus = PaneFocus::Preview;
+        let before = app.large_preview_window().expect("window");
+        for _ in 0..200 {
+            app.scroll_preview(1);
+        }
+        let after = app.large_preview_window().expect("window");
+        assert!(after.0 > before.0, "{before:?} -> {after:?}");
+        let rows = draw_text(&mut app, &mut terminal);
+        assert!(rows.contains("Section") || rows.contains("item"));
+    }
+
+    #[test]
+    fn enter_continues_lists_unless_turned_off() {
+        let path = temp_path("enter-list");
+        fs::write(&path, "- first").expect("seed");
+        let mut app =
+            App::new_file(path.clone(), false, false, false, "- first".into()).expect("app");
+        let mut running = true;
+
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("enter");
+        assert_eq!(app.editor.text(), "- first\n- ");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("enter on empty item");
+        assert_eq!(app.editor.text(), "- first\n");
+
+        app.change_setting(SettingItem::ContinueLists, true);
+        assert_eq!(app.status_text(), "Continue lists: off");
+        app.editor.insert_str("- next");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("plain enter");
+        assert_eq!(app.editor.text(), "- first\n- next\n");
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn shift_tab_without_selection_toggles_focus_and_prompts_ignore_tab() {
+        let path = temp_path("tab-focus");
+        fs::write(&path, "x").expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, "x".into()).expect("app");
+        let mut running = true;
+
+        app.handle_key(key(KeyCode::BackTab, KeyModifiers::SHIFT), &mut running)
+            .expect("toggle focus");
+        assert_eq!(app.ui.focus, PaneFocus::Preview);
+        app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE), &mut running)
+            .expect("tab in preview");
+        assert_eq!(app.editor.text(), "x");
+        app.handle_key(key(KeyCode::BackTab, KeyModifiers::SHIFT), &mut running)
+            .expect("toggle back");
+        assert_eq!(app.ui.focus, PaneFocus::Editor);
+
+        app.handle_key(key(KeyCode::Char('f'), KeyModifiers::CONTROL), &mut running)
+            .expect("search");
+        app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE), &mut running)
+            .expect("tab in prompt");
+        assert!(app.search_mode);
+        assert_eq!(app.search_query, "");
+        assert_eq!(app.editor.text(), "x");
         let _ = fs::remove_file(&path);
     }
 
@@ -4098,7 +10074,7 @@ mod tests {
 
         app.handle_key(key(KeyCode::Char(','), KeyModifiers::CONTROL), &mut running)
             .expect("open settings");
-        assert_eq!(app.status, "Docs opened");
+        assert_eq!(app.status_text(), "Docs opened");
 
         app.handle_key(key(KeyCode::Char('z'), KeyModifiers::NONE), &mut running)
             .expect("type while settings open");
@@ -4141,6 +10117,9 @@ mod tests {
         app.handle_key(key(KeyCode::Char(','), KeyModifiers::CONTROL), &mut running)
             .expect("open docs");
         assert!(app.ui.help.open);
+        assert!(app.ui.help.is_settings());
+        app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE), &mut running)
+            .expect("docs tab");
         assert!(app.ui.help.index_focus);
         assert_eq!(app.ui.help.section_idx, 0);
 
@@ -4162,8 +10141,8 @@ mod tests {
         app.handle_key(key(KeyCode::Char('j'), KeyModifiers::NONE), &mut running)
             .expect("j line down");
 
-        app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE), &mut running)
-            .expect("tab focus");
+        app.handle_key(key(KeyCode::Left, KeyModifiers::NONE), &mut running)
+            .expect("focus index");
         assert!(app.ui.help.index_focus);
 
         app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
@@ -4312,35 +10291,35 @@ mod tests {
         )
         .expect("shift+ctrl+end");
 
-        app.selection_anchor = Some(1);
+        app.editor.set_selection_anchor(1);
         app.editor.set_cursor(6);
         app.handle_key(key(KeyCode::Left, KeyModifiers::ALT), &mut running)
             .expect("alt left collapse");
-        app.selection_anchor = Some(1);
+        app.editor.set_selection_anchor(1);
         app.editor.set_cursor(6);
         app.handle_key(key(KeyCode::Right, KeyModifiers::CONTROL), &mut running)
             .expect("ctrl right collapse");
-        app.selection_anchor = Some(1);
+        app.editor.set_selection_anchor(1);
         app.editor.set_cursor(6);
         app.handle_key(key(KeyCode::Up, KeyModifiers::ALT), &mut running)
             .expect("alt up collapse");
-        app.selection_anchor = Some(1);
+        app.editor.set_selection_anchor(1);
         app.editor.set_cursor(6);
         app.handle_key(key(KeyCode::Down, KeyModifiers::CONTROL), &mut running)
             .expect("ctrl down collapse");
-        app.selection_anchor = Some(1);
+        app.editor.set_selection_anchor(1);
         app.editor.set_cursor(6);
         app.handle_key(key(KeyCode::Left, KeyModifiers::SUPER), &mut running)
             .expect("cmd left collapse");
-        app.selection_anchor = Some(1);
+        app.editor.set_selection_anchor(1);
         app.editor.set_cursor(6);
         app.handle_key(key(KeyCode::Home, KeyModifiers::NONE), &mut running)
             .expect("home collapse");
-        app.selection_anchor = Some(1);
+        app.editor.set_selection_anchor(1);
         app.editor.set_cursor(6);
         app.handle_key(key(KeyCode::Right, KeyModifiers::SUPER), &mut running)
             .expect("cmd right collapse");
-        app.selection_anchor = Some(1);
+        app.editor.set_selection_anchor(1);
         app.editor.set_cursor(6);
         app.handle_key(key(KeyCode::End, KeyModifiers::NONE), &mut running)
             .expect("end collapse");
@@ -4393,11 +10372,11 @@ mod tests {
             .expect("alt backspace");
 
         app.editor.set_cursor(2);
-        app.selection_anchor = Some(0);
+        app.editor.set_selection_anchor(0);
         app.handle_key(key(KeyCode::Delete, KeyModifiers::NONE), &mut running)
             .expect("delete selection");
         app.editor.set_cursor(2);
-        app.selection_anchor = Some(0);
+        app.editor.set_selection_anchor(0);
         app.handle_key(key(KeyCode::Backspace, KeyModifiers::CONTROL), &mut running)
             .expect("backspace selection");
 
@@ -4415,7 +10394,7 @@ mod tests {
         app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
             .expect("esc");
         assert!(!app.ui.help.open);
-        assert_eq!(app.status, "Docs closed");
+        assert_eq!(app.status_text(), "Docs closed");
         assert!(running);
 
         app.ui.help.open = true;
@@ -4447,11 +10426,11 @@ mod tests {
         app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
             .expect("esc");
         assert_eq!(app.home_query, "");
-        assert_eq!(app.status, "Home: file field cleared");
+        assert_eq!(app.status_text(), "Home: file field cleared");
 
         app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
             .expect("enter empty");
-        assert_eq!(app.status, "Home: type a file name or path");
+        assert_eq!(app.status_text(), "Home: type a file name or path");
 
         app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
             .expect("type x");
@@ -4626,53 +10605,193 @@ mod tests {
         assert!(app.preview_scroll > 0);
         assert_eq!(app.editor.line_col_at_cursor().0, cursor_before);
 
-        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn wheel_scroll_in_view_mode_moves_viewport_not_cursor() {
+        let path = temp_path("wheel-view-scroll");
+        let text = (0..80)
+            .map(|n| format!("line {n}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        fs::write(&path, &text).expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, text).expect("app");
+        app.set_initial_focus(PaneFocus::Preview);
+        let cursor_before = app.editor.line_col_at_cursor().0;
+        app.preview_height = 12;
+
+        app.scroll_active_viewport(1);
+
+        assert!(app.preview_scroll > 0);
+        assert_eq!(app.editor.line_col_at_cursor().0, cursor_before);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn mouse_click_and_drag_updates_cursor_and_selection() {
+        let path = temp_path("mouse-select");
+        fs::write(&path, "hello world").expect("seed");
+        let mut app =
+            App::new_file(path.clone(), false, false, false, "hello world".into()).expect("app");
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).expect("terminal");
+        terminal.draw(|frame| app.draw(frame)).expect("draw");
+
+        let y = app.editor_text_area.y;
+        let x_start = app.editor_text_area.x + 2;
+        let x_end = app.editor_text_area.x + 6;
+
+        app.handle_mouse_down(x_start, y, KeyModifiers::NONE);
+        assert_eq!(app.editor.line_col_at_cursor(), (0, 2));
+        assert!(app.selection_range().is_none());
+
+        app.handle_mouse_drag(x_end, y);
+        assert_eq!(app.editor.line_col_at_cursor(), (0, 6));
+        assert_eq!(app.selection_range(), Some((2, 6)));
+
+        app.handle_mouse_down(x_end, y, KeyModifiers::SHIFT);
+        assert_eq!(app.selection_range(), Some((2, 6)));
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn mouse_press_drag_release_selects_and_click_clears() {
+        let path = temp_path("mouse-drag-release");
+        let text = "alpha\nbravo\ncharlie\n";
+        fs::write(&path, text).expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, text.into()).expect("app");
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).expect("terminal");
+        terminal.draw(|frame| app.draw(frame)).expect("draw");
+        let area = app.editor_text_area;
+
+        app.handle_mouse_down(area.x + 1, area.y, KeyModifiers::NONE);
+        app.handle_mouse_drag(area.x + 3, area.y + 1);
+        app.handle_mouse_drag(area.x + 4, area.y + 2);
+        app.handle_mouse_up();
+        assert_eq!(app.selection_range(), Some((1, 16)));
+        assert_eq!(&app.editor.text()[1..16], "lpha\nbravo\nchar");
+        assert_eq!(app.status_text(), "Selected 15 chars");
+        assert!(app.mouse_drag_anchor.is_none());
+
+        // Stray motion after release does not extend the selection.
+        app.handle_mouse_drag(area.x, area.y);
+        assert_eq!(app.selection_range(), Some((1, 16)));
+
+        terminal
+            .draw(|frame| app.draw(frame))
+            .expect("draw selection");
+        let theme = build_theme(app.ui.theme, app.ui.no_color, None);
+        let cell = &terminal.backend().buffer()[(area.x + 2, area.y)];
+        assert_eq!(cell.symbol(), "p");
+        assert_eq!(cell.bg, theme.selection.bg.expect("selection bg"));
+
+        app.handle_mouse_down(area.x + 2, area.y + 1, KeyModifiers::NONE);
+        app.handle_mouse_up();
+        assert!(app.selection_range().is_none());
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn horizontal_scroll_follows_cursor_across_long_line() {
+        let path = temp_path("hscroll");
+        let text = format!("{}\nshort\n", "abcdefghij".repeat(50));
+        let mut app = App::new_file(path, false, false, false, text).expect("app");
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).expect("terminal");
+        terminal.draw(|frame| app.draw(frame)).expect("draw");
+        app.editor.set_cursor(0);
+        let width = app.editor_text_area.width as usize;
+        let mut running = true;
+
+        let visible = |app: &App| {
+            let column = app.editor.display_col_at_cursor();
+            column >= app.editor_hscroll && column < app.editor_hscroll + width
+        };
+        for _ in 0..500 {
+            app.handle_key(key(KeyCode::Right, KeyModifiers::NONE), &mut running)
+                .expect("right");
+            assert!(visible(&app), "col {}", app.editor.display_col_at_cursor());
+        }
+        assert_eq!(app.editor_hscroll, 500 + 1 - width);
+
+        terminal
+            .draw(|frame| app.draw(frame))
+            .expect("draw scrolled");
+        let row = app.editor_text_area.y;
+        let first = &terminal.backend().buffer()[(app.editor_text_area.x, row)];
+        let expected = "abcdefghij"
+            .chars()
+            .nth(app.editor_hscroll % 10)
+            .expect("char");
+        assert_eq!(first.symbol(), expected.to_string());
+
+        for _ in 0..500 {
+            app.handle_key(key(KeyCode::Left, KeyModifiers::NONE), &mut running)
+                .expect("left");
+            assert!(visible(&app), "col {}", app.editor.display_col_at_cursor());
+        }
+        assert_eq!(app.editor_hscroll, 0);
     }
 
     #[test]
-    fn wheel_scroll_in_view_mode_moves_viewport_not_cursor() {
-        let path = temp_path("wheel-view-scroll");
-        let text = (0..80)
-            .map(|n| format!("line {n}"))
-            .collect::<Vec<_>>()
-            .join("\n");
-        fs::write(&path, &text).expect("seed");
-        let mut app = App::new_file(path.clone(), false, false, false, text).expect("app");
-        app.set_initial_focus(PaneFocus::Preview);
-        let cursor_before = app.editor.line_col_at_cursor().0;
-        app.preview_height = 12;
+    fn mouse_click_maps_columns_through_hscroll_and_wide_glyphs() {
+        let path = temp_path("hscroll-click");
+        let mut app = App::new_file(path, false, false, false, "ab日本語cd".into()).expect("app");
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).expect("terminal");
+        terminal.draw(|frame| app.draw(frame)).expect("draw");
+        let area = app.editor_text_area;
 
-        app.scroll_active_viewport(1);
+        app.editor_hscroll = 2;
+        app.handle_mouse_down(area.x + 4, area.y, KeyModifiers::NONE);
+        assert_eq!(app.editor.line_col_at_cursor(), (0, 4));
+        assert_eq!(app.editor.display_col_at_cursor(), 6);
+    }
 
-        assert!(app.preview_scroll > 0);
-        assert_eq!(app.editor.line_col_at_cursor().0, cursor_before);
+    #[test]
+    fn slice_spans_keeps_styles_across_the_scroll_edge() {
+        let theme = build_theme(ThemeChoice::Default, false, None);
+        let spans = vec![
+            Span::styled("**bold**", theme.strong),
+            Span::styled(" tail", theme.plain),
+        ];
+        let sliced = slice_spans(spans.clone(), 0, 4);
+        assert_eq!(sliced, spans);
 
-        let _ = fs::remove_file(&path);
+        let sliced = slice_spans(spans, 6, 4);
+        assert_eq!(sliced.len(), 2);
+        assert_eq!(sliced[0].content.as_ref(), "**");
+        assert_eq!(sliced[0].style, theme.strong);
+        assert_eq!(sliced[1].content.as_ref(), " t");
+        assert_eq!(sliced[1].style, theme.plain);
     }
 
     #[test]
-    fn mouse_click_and_drag_updates_cursor_and_selection() {
-        let path = temp_path("mouse-select");
-        fs::write(&path, "hello world").expect("seed");
-        let mut app =
-            App::new_file(path.clone(), false, false, false, "hello world".into()).expect("app");
+    fn mouse_drag_past_pane_edges_auto_scrolls() {
+        let path = temp_path("mouse-drag-scroll");
+        let text = (0..60).map(|n| format!("row {n}\n")).collect::<String>();
+        fs::write(&path, &text).expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, text).expect("app");
+        app.editor.set_cursor(0);
         let mut terminal = Terminal::new(TestBackend::new(80, 20)).expect("terminal");
         terminal.draw(|frame| app.draw(frame)).expect("draw");
+        let area = app.editor_text_area;
+        assert_eq!(app.editor_scroll, 0);
 
-        let y = app.editor_text_area.y;
-        let x_start = app.editor_text_area.x + 2;
-        let x_end = app.editor_text_area.x + 6;
-
-        app.handle_mouse_down(x_start, y, KeyModifiers::NONE);
-        assert_eq!(app.editor.line_col_at_cursor(), (0, 2));
-        assert!(app.selection_range().is_none());
-
-        app.handle_mouse_drag(x_end, y);
-        assert_eq!(app.editor.line_col_at_cursor(), (0, 6));
-        assert_eq!(app.selection_range(), Some((2, 6)));
+        app.handle_mouse_down(area.x, area.y, KeyModifiers::NONE);
+        for _ in 0..3 {
+            app.handle_mouse_drag(area.x, area.y + area.height + 2);
+        }
+        assert_eq!(app.editor_scroll, 3);
+        let (line, _) = app.editor.line_col_at_cursor();
+        assert_eq!(line, 3 + area.height as usize - 1);
+        assert_eq!(app.selection_range().map(|(start, _)| start), Some(0));
 
-        app.handle_mouse_down(x_end, y, KeyModifiers::SHIFT);
-        assert_eq!(app.selection_range(), Some((2, 6)));
+        app.handle_mouse_drag(area.x, area.y.saturating_sub(1));
+        assert_eq!(app.editor_scroll, 2);
+        assert_eq!(app.editor.line_col_at_cursor().0, 2);
+        app.handle_mouse_up();
 
         let _ = fs::remove_file(&path);
     }
@@ -4686,6 +10805,15 @@ mod tests {
         app.run_loop(&mut terminal).expect("run loop stream done");
     }
 
+    #[test]
+    fn run_loop_stops_when_shutdown_is_requested() {
+        let mut app = App::new_stream_for_test(false);
+        app.interactive_input = false;
+        app.shutdown.store(true, Ordering::Relaxed);
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).expect("terminal");
+        app.run_loop(&mut terminal).expect("run loop shutdown");
+    }
+
     #[test]
     fn run_loop_interactive_consumes_queued_key() {
         let path = temp_path("interactive-loop");
@@ -4875,6 +11003,367 @@ mod tests {
         assert_eq!(up_release, Some(InputEvent::MouseUp));
     }
 
+    #[test]
+    fn next_terminal_input_normalizes_enhanced_keys() {
+        let shifted = next_terminal_input(
+            |_| Ok(true),
+            || {
+                Ok(Event::Key(KeyEvent::new(
+                    KeyCode::Char('S'),
+                    KeyModifiers::CONTROL,
+                )))
+            },
+        )
+        .expect("key");
+        let Some(InputEvent::Key(key)) = shifted else {
+            panic!("expected key, got {shifted:?}");
+        };
+        assert_eq!(key.code, KeyCode::Char('S'));
+        assert_eq!(key.modifiers, KeyModifiers::CONTROL | KeyModifiers::SHIFT);
+
+        let modifier = next_terminal_input(
+            |_| Ok(true),
+            || {
+                Ok(Event::Key(KeyEvent::new(
+                    KeyCode::Modifier(crossterm::event::ModifierKeyCode::LeftShift),
+                    KeyModifiers::SHIFT,
+                )))
+            },
+        )
+        .expect("modifier");
+        assert_eq!(modifier, None);
+
+        let mut app = App::new_file(
+            temp_path("enhanced-save-as"),
+            false,
+            false,
+            false,
+            "x".into(),
+        )
+        .expect("app");
+        let mut running = true;
+        app.handle_key(key, &mut running).expect("save as");
+        assert!(app.save_as_mode);
+    }
+
+    #[test]
+    fn next_terminal_input_maps_resize() {
+        let resize =
+            next_terminal_input(|_| Ok(true), || Ok(Event::Resize(60, 15))).expect("resize");
+        assert_eq!(
+            resize,
+            Some(InputEvent::Resize {
+                width: 60,
+                height: 15
+            })
+        );
+    }
+
+    #[test]
+    fn resize_clamps_scrolls_before_the_next_draw() {
+        let text = (1..=100).map(|n| format!("line {n}\n")).collect::<String>();
+        let mut app = App::new_file(temp_path("resize"), false, false, false, text).expect("app");
+        let mut terminal = Terminal::new(TestBackend::new(200, 50)).expect("terminal");
+        draw_text(&mut app, &mut terminal);
+        app.editor.goto_line(46);
+        app.ensure_cursor_visible();
+        assert_eq!(app.editor_scroll, 0);
+
+        app.handle_resize(60, 15);
+        assert_eq!(app.term_width, 60);
+        // Stacked: the editor gets the top 6 of the 13 body rows.
+        assert_eq!(app.editor_height, 4);
+        assert_eq!(app.editor_scroll, 42);
+        assert_eq!(app.status_text(), "Compact layout: terminal is under 80x24");
+        terminal.backend_mut().resize(60, 15);
+        let screen = draw_text(&mut app, &mut terminal);
+        assert!(screen.contains("line 46"));
+        assert_eq!(app.editor_scroll, 42);
+
+        app.editor.goto_line(100);
+        app.ensure_cursor_visible();
+        app.preview_scroll = 200;
+        app.handle_resize(200, 50);
+        assert_eq!(
+            app.editor_scroll,
+            app.editor.line_count() - app.editor_height
+        );
+        assert!(app.preview_scroll <= 100 - app.preview_height);
+        assert_eq!(app.status_text(), "Full layout");
+    }
+
+    #[test]
+    fn compact_terminal_stacks_panes_and_alt_z_maximizes() {
+        let mut app = App::new_file(
+            temp_path("stacked"),
+            false,
+            false,
+            false,
+            "# Title
+body
+"
+            .into(),
+        )
+        .expect("app");
+        let mut running = true;
+        let mut terminal = Terminal::new(TestBackend::new(70, 22)).expect("terminal");
+        let screen = draw_text(&mut app, &mut terminal);
+        assert_eq!(app.editor_area, ratatui::layout::Rect::new(0, 1, 70, 10));
+        assert_eq!(app.preview_area, ratatui::layout::Rect::new(0, 11, 70, 10));
+        assert!(screen.contains("Editor"));
+        assert!(screen.contains("Preview"));
+        assert!(screen.contains("compact |"));
+
+        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::ALT), &mut running)
+            .expect("maximize");
+        assert_eq!(app.status_text(), "Maximized: editor");
+        draw_text(&mut app, &mut terminal);
+        assert_eq!(app.editor_area, ratatui::layout::Rect::new(0, 1, 70, 20));
+        assert_eq!(app.preview_area.width, 0);
+
+        // Switching focus hands the whole area to the other pane.
+        app.handle_key(key(KeyCode::BackTab, KeyModifiers::SHIFT), &mut running)
+            .expect("focus preview");
+        draw_text(&mut app, &mut terminal);
+        assert_eq!(app.preview_area, ratatui::layout::Rect::new(0, 1, 70, 20));
+        assert_eq!(app.editor_area.width, 0);
+
+        app.handle_key(key(KeyCode::F(11), KeyModifiers::NONE), &mut running)
+            .expect("restore");
+        assert_eq!(app.status_text(), "Panes restored");
+        draw_text(&mut app, &mut terminal);
+        assert_eq!(app.editor_area, ratatui::layout::Rect::new(0, 1, 70, 10));
+        assert!(running);
+    }
+
+    #[test]
+    fn ctrl_brackets_move_the_split_divider() {
+        let mut app = App::new_file(temp_path("split"), false, false, false, "# Title\n".into())
+            .expect("app");
+        let mut running = true;
+        let mut terminal = Terminal::new(TestBackend::new(160, 40)).expect("terminal");
+        draw_text(&mut app, &mut terminal);
+        assert_eq!(app.editor_area.width, 80);
+
+        for _ in 0..2 {
+            app.handle_key(key(KeyCode::Char(']'), KeyModifiers::CONTROL), &mut running)
+                .expect("grow editor");
+        }
+        assert_eq!(app.status_text(), "split 60/40");
+        draw_text(&mut app, &mut terminal);
+        assert_eq!(app.editor_area, ratatui::layout::Rect::new(0, 1, 96, 38));
+        assert_eq!(app.preview_area, ratatui::layout::Rect::new(96, 1, 64, 38));
+
+        // With the preview focused, growing it narrows the editor.
+        app.handle_key(key(KeyCode::BackTab, KeyModifiers::SHIFT), &mut running)
+            .expect("focus preview");
+        app.handle_key(key(KeyCode::Char(']'), KeyModifiers::CONTROL), &mut running)
+            .expect("grow preview");
+        assert_eq!(app.status_text(), "split 55/45");
+        for _ in 0..10 {
+            app.handle_key(key(KeyCode::Char('['), KeyModifiers::CONTROL), &mut running)
+                .expect("shrink preview");
+        }
+        assert_eq!(app.status_text(), "split 80/20");
+        draw_text(&mut app, &mut terminal);
+        assert_eq!(app.preview_area.width, 32);
+        assert!(running);
+    }
+
+    #[test]
+    fn preview_keeps_its_top_heading_when_lines_above_are_deleted() {
+        let mut text = String::new();
+        for n in 0..49 {
+            text.push_str(&format!("- item {n}\n"));
+        }
+        text.push_str("\n## Target\n\n");
+        for n in 0..100 {
+            text.push_str(&format!("- after {n}\n"));
+        }
+        let mut app = App::new_file(temp_path("anchor"), false, false, false, text).expect("app");
+        let mut running = true;
+        let mut terminal = Terminal::new(TestBackend::new(160, 40)).expect("terminal");
+        app.handle_key(key(KeyCode::BackTab, KeyModifiers::SHIFT), &mut running)
+            .expect("focus preview");
+        draw_text(&mut app, &mut terminal);
+        app.preview_scroll = app.preview_line_for_source(50);
+        let top_line = |app: &App| {
+            let lines = &app.preview_cache.as_ref().expect("cache").lines;
+            lines[app.preview_scroll].text()
+        };
+        draw_text(&mut app, &mut terminal);
+        assert_eq!(top_line(&app), "## Target");
+
+        app.editor.goto_line(11);
+        for _ in 0..10 {
+            app.editor.delete_line();
+        }
+        assert_eq!(app.editor.line_text(40), "## Target");
+        let screen = draw_text(&mut app, &mut terminal);
+        assert_eq!(top_line(&app), "## Target");
+        assert!(screen.contains("## Target"));
+
+        // Edits below the top leave the scroll where it was.
+        let scroll = app.preview_scroll;
+        app.editor.goto_line(60);
+        app.editor.delete_line();
+        draw_text(&mut app, &mut terminal);
+        assert_eq!(app.preview_scroll, scroll);
+        assert_eq!(top_line(&app), "## Target");
+    }
+
+    #[test]
+    fn alt_n_and_alt_p_jump_between_headings() {
+        let text = "# Title\n\nIntro\n\n```sh\n# not a heading\n```\n\n## Usage\n\nRun it.\n\n- one\n- two\n- three\n- four\n- five\n- six\n";
+        let mut app =
+            App::new_file(temp_path("headings"), false, false, false, text.into()).expect("app");
+        let mut running = true;
+        let mut terminal = Terminal::new(TestBackend::new(160, 12)).expect("terminal");
+        app.editor.move_doc_start();
+        let alt = |c| key(KeyCode::Char(c), KeyModifiers::ALT);
+
+        app.handle_key(alt('n'), &mut running)
+            .expect("next heading");
+        assert_eq!(app.editor.line_col_at_cursor(), (8, 0));
+        assert_eq!(app.status_text(), "Heading: ## Usage");
+        app.handle_key(alt('n'), &mut running)
+            .expect("no next heading");
+        assert_eq!(app.status_text(), "No heading below");
+        assert_eq!(app.editor.line_col_at_cursor(), (8, 0));
+        app.handle_key(alt('p'), &mut running)
+            .expect("previous heading");
+        assert_eq!(app.editor.line_col_at_cursor(), (0, 0));
+        assert_eq!(app.status_text(), "Heading: # Title");
+
+        // With the preview focused the preview scrolls and the cursor stays.
+        app.handle_key(key(KeyCode::BackTab, KeyModifiers::SHIFT), &mut running)
+            .expect("focus preview");
+        draw_text(&mut app, &mut terminal);
+        app.handle_key(alt('n'), &mut running)
+            .expect("preview next heading");
+        assert_eq!(app.status_text(), "Heading: ## Usage");
+        assert_eq!(app.editor.line_col_at_cursor(), (0, 0));
+        let screen = draw_text(&mut app, &mut terminal);
+        let lines = &app.preview_cache.as_ref().expect("cache").lines;
+        assert_eq!(lines[app.preview_scroll].text(), "## Usage");
+        assert!(screen.contains("## Usage"));
+        app.handle_key(alt('p'), &mut running)
+            .expect("preview previous heading");
+        assert_eq!(app.preview_scroll, 0);
+    }
+
+    /// Poll like the run loop until the formatter has finished.
+    fn wait_for_format(app: &mut App) {
+        let started = Instant::now();
+        while app.format_job.is_some() && started.elapsed() < Duration::from_secs(5) {
+            app.handle_format_result();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ctrl_alt_f_pipes_the_text_through_the_formatter() {
+        let ctrl_alt_f = key(
+            KeyCode::Char('f'),
+            KeyModifiers::CONTROL | KeyModifiers::ALT,
+        );
+        let mut app = App::new_file(
+            temp_path("format"),
+            false,
+            false,
+            false,
+            "# title\nsome body\n".into(),
+        )
+        .expect("app");
+        let mut running = true;
+        app.handle_key(ctrl_alt_f, &mut running)
+            .expect("no formatter");
+        assert_eq!(
+            app.status_text(),
+            "No formatter: set --formatter or [editor] formatter"
+        );
+
+        app.set_formatter(Some("tr a-z A-Z".into()));
+        app.editor.set_cursor_line_col(1, 4);
+        app.handle_key(ctrl_alt_f, &mut running).expect("format");
+        assert_eq!(app.status_text(), "Formatting...");
+        wait_for_format(&mut app);
+        assert_eq!(app.status_text(), "Formatted");
+        assert_eq!(app.editor.text(), "# TITLE\nSOME BODY\n");
+        assert!(app.editor.dirty);
+        assert_eq!(app.editor.line_col_at_cursor(), (1, 4));
+
+        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::CONTROL), &mut running)
+            .expect("undo");
+        assert_eq!(app.editor.text(), "# title\nsome body\n");
+
+        app.set_formatter(Some("echo 'line 3: bad list' >&2; exit 1".into()));
+        app.handle_key(ctrl_alt_f, &mut running)
+            .expect("failing format");
+        wait_for_format(&mut app);
+        assert_eq!(app.status_text(), "Format error: line 3: bad list");
+        assert_eq!(app.editor.text(), "# title\nsome body\n");
+
+        // Typing while the formatter runs wins over its output.
+        app.set_formatter(Some("sleep 0.2; tr a-z A-Z".into()));
+        app.handle_key(ctrl_alt_f, &mut running)
+            .expect("slow format");
+        app.handle_key(ctrl_alt_f, &mut running)
+            .expect("second format");
+        assert_eq!(app.status_text(), "Already formatting");
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
+            .expect("type");
+        wait_for_format(&mut app);
+        assert_eq!(
+            app.status_text(),
+            "Format skipped: the text changed while formatting"
+        );
+        assert!(app.editor.text().starts_with("# title"));
+
+        let mut readonly =
+            App::new_file(temp_path("format-ro"), true, false, false, "x\n".into()).expect("app");
+        readonly.set_formatter(Some("tr a-z A-Z".into()));
+        readonly
+            .handle_key(ctrl_alt_f, &mut running)
+            .expect("readonly");
+        assert_eq!(readonly.status_text(), "Readonly: formatting disabled");
+        assert!(readonly.format_job.is_none());
+    }
+
+    #[test]
+    fn next_terminal_input_maps_bracketed_paste() {
+        let paste =
+            next_terminal_input(|_| Ok(true), || Ok(Event::Paste("héllo".into()))).expect("paste");
+        assert_eq!(paste, Some(InputEvent::Paste("héllo".into())));
+    }
+
+    #[test]
+    fn handle_paste_inserts_once_and_undoes_in_one_step() {
+        let path = temp_path("bracketed-paste");
+        let mut app = App::new_file(path, false, false, false, "start ".into()).expect("app");
+        app.interactive_input = false;
+        let mut running = true;
+
+        let big = "é日x".repeat(3_334);
+        app.handle_paste(&big);
+        assert_eq!(app.editor.text(), format!("start {big}"));
+        assert_eq!(app.editor.cursor(), app.editor.text().len());
+        assert_eq!(app.status_text(), "Pasted 10002 chars");
+
+        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::CONTROL), &mut running)
+            .expect("undo");
+        assert_eq!(app.editor.text(), "start ");
+
+        app.handle_paste("a\r\nb\rc");
+        assert_eq!(app.editor.text(), "start a\nb\nc");
+
+        app.readonly = true;
+        app.handle_paste("nope");
+        assert_eq!(app.editor.text(), "start a\nb\nc");
+        assert_eq!(app.status_text(), "Readonly: edit disabled");
+    }
+
     #[test]
     fn next_key_event_falls_back_to_terminal_poll() {
         let mut app = App::new_stream_for_test(false);
@@ -4884,7 +11373,7 @@ mod tests {
 
     #[test]
     fn new_stream_builds_stream_mode_app() {
-        let app = App::new_stream(false).expect("app");
+        let app = App::new_stream(false, None).expect("app");
         assert!(app.stream_mode);
     }
 
@@ -4908,28 +11397,323 @@ mod tests {
     }
 
     #[test]
-    fn handle_key_char_readonly_does_not_insert() {
-        let path = temp_path("readonly-char");
-        fs::write(&path, "x").expect("seed");
-        let mut app = App::new_file(path.clone(), true, false, false, "x".into()).expect("app");
+    fn handle_key_char_readonly_does_not_insert() {
+        let path = temp_path("readonly-char");
+        fs::write(&path, "x").expect("seed");
+        let mut app = App::new_file(path.clone(), true, false, false, "x".into()).expect("app");
+        let mut running = true;
+        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::NONE), &mut running)
+            .expect("readonly char");
+        assert_eq!(app.editor.text(), "x");
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn draw_sets_cursor_when_visible() {
+        let path = temp_path("draw-cursor");
+        fs::write(&path, "x").expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, "x".into()).expect("app");
+        app.editor_scroll = 0;
+        app.preview_scroll = 0;
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).expect("terminal");
+        terminal.draw(|frame| app.draw(frame)).expect("draw");
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn readonly_draws_a_block_cursor_that_follows_navigation() {
+        let mut app = App::new_file(
+            temp_path("readonly-cursor"),
+            true,
+            false,
+            false,
+            "abc\ndef".into(),
+        )
+        .expect("app");
+        app.editor.set_cursor(1);
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).expect("terminal");
+        let mut running = true;
+        draw_text(&mut app, &mut terminal);
+        let area = app.editor_text_area;
+        let reversed = |terminal: &Terminal<TestBackend>, x: u16, y: u16| {
+            terminal.backend().buffer()[(area.x + x, area.y + y)]
+                .modifier
+                .contains(Modifier::REVERSED)
+        };
+        assert!(reversed(&terminal, 1, 0));
+        assert!(!reversed(&terminal, 0, 0));
+
+        app.handle_key(key(KeyCode::Down, KeyModifiers::NONE), &mut running)
+            .expect("down");
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
+            .expect("type");
+        draw_text(&mut app, &mut terminal);
+        assert!(reversed(&terminal, 1, 1));
+        assert!(!reversed(&terminal, 1, 0));
+        assert_eq!(app.editor.text(), "abc\ndef");
+        assert!(!app.editor.dirty);
+
+        let info = app.info_line(200);
+        assert!(info.contains(" | 🔒 RO | mode="), "{info}");
+        assert!(!info.contains("clean") && !info.contains("dirty"));
+        assert!(
+            app.status_hint()
+                .starts_with("readonly: arrows move the cursor")
+        );
+    }
+
+    #[test]
+    fn draw_places_cursor_by_display_width_for_cjk() {
+        let path = temp_path("draw-cursor-cjk");
+        let mut app = App::new_file(path, false, false, false, "你好世界".into()).expect("app");
+        app.editor.set_cursor("你好".len());
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).expect("terminal");
+        terminal.draw(|frame| app.draw(frame)).expect("draw");
+        let cursor = terminal.get_cursor_position().expect("cursor");
+        assert_eq!(cursor.x, app.editor_text_area.x + 4);
+        assert_eq!(cursor.y, app.editor_text_area.y);
+        let cell = &terminal.backend().buffer()[(cursor.x, cursor.y)];
+        assert_eq!(cell.symbol(), "世");
+    }
+
+    #[test]
+    fn ctrl_up_and_down_move_the_cursor_line() {
+        let path = temp_path("move-line");
+        let text = "- a\n- b\n- c\n";
+        let mut app = App::new_file(path.clone(), false, false, false, text.into()).expect("app");
+        app.editor.set_cursor(1);
+        let mut running = true;
+        app.handle_key(key(KeyCode::Down, KeyModifiers::CONTROL), &mut running)
+            .expect("key");
+        app.handle_key(key(KeyCode::Down, KeyModifiers::CONTROL), &mut running)
+            .expect("key");
+        assert_eq!(app.editor.text(), "- b\n- c\n- a\n");
+        app.handle_key(key(KeyCode::Up, KeyModifiers::CONTROL), &mut running)
+            .expect("key");
+        assert_eq!(app.editor.text(), "- b\n- a\n- c\n");
+        assert_eq!(app.editor.line_col_at_cursor(), (1, 1));
+        // Alt keeps jumping by paragraph.
+        app.handle_key(key(KeyCode::Down, KeyModifiers::ALT), &mut running)
+            .expect("key");
+        assert_eq!(app.editor.text(), "- b\n- a\n- c\n");
+
+        let mut readonly = App::new_file(path, true, false, false, text.into()).expect("app");
+        readonly
+            .handle_key(key(KeyCode::Up, KeyModifiers::CONTROL), &mut running)
+            .expect("key");
+        assert_eq!(readonly.editor.text(), text);
+        assert_eq!(readonly.status_text(), "Readonly: edit disabled");
+    }
+
+    #[test]
+    fn f12_jumps_to_definitions_and_shift_f12_retraces_them() {
+        let path = temp_path("goto-definition");
+        let text = "See [spec][RFC], a note[^1] and [lost][nope].\n\n\
+                    [rfc]: https://rfc.example\n\n\
+                    [^1]: The note, citing [again][rfc].\n";
+        let mut app = App::new_file(path, false, false, false, text.into()).expect("app");
+        let mut running = true;
+        let mut press = |app: &mut App, mods: KeyModifiers| {
+            app.handle_key(key(KeyCode::F(12), mods), &mut running)
+                .expect("key");
+        };
+        let note = text.find("^1]").expect("note");
+        app.editor.set_cursor(note);
+        press(&mut app, KeyModifiers::NONE);
+        assert_eq!(app.editor.line_col_at_cursor(), (4, 0));
+        assert_eq!(app.status_text(), "Definition of [^1]");
+
+        let again = text.find("again").expect("again");
+        app.editor.set_cursor(again);
+        press(&mut app, KeyModifiers::NONE);
+        assert_eq!(app.editor.line_col_at_cursor(), (2, 0));
+
+        press(&mut app, KeyModifiers::SHIFT);
+        assert_eq!(app.editor.cursor(), again);
+        press(&mut app, KeyModifiers::SHIFT);
+        assert_eq!(app.editor.cursor(), note);
+        press(&mut app, KeyModifiers::SHIFT);
+        assert_eq!(app.editor.cursor(), note);
+        assert_eq!(app.status_text(), "No jump to go back from");
+
+        app.editor.set_cursor(text.find("lost").expect("lost"));
+        press(&mut app, KeyModifiers::NONE);
+        assert_eq!(app.status_text(), "definition not found: nope");
+        app.editor.set_cursor(0);
+        press(&mut app, KeyModifiers::NONE);
+        assert_eq!(app.status_text(), "No reference under the cursor");
+    }
+
+    #[test]
+    fn alt_d_duplicates_the_cursor_line() {
+        let path = temp_path("duplicate-line");
+        let mut app = App::new_file(path, false, false, false, "a\nb".into()).expect("app");
+        app.editor.set_cursor(3);
+        let mut running = true;
+        app.handle_key(key(KeyCode::Char('d'), KeyModifiers::ALT), &mut running)
+            .expect("key");
+        assert_eq!(app.editor.text(), "a\nb\nb");
+        assert_eq!(app.status_text(), "Duplicated line");
+        assert!(app.editor.dirty);
+    }
+
+    #[test]
+    fn ctrl_alt_l_renumbers_the_list_as_one_undo_step() {
+        let path = temp_path("renumber-list");
+        let text = "1. one\n1. two\n   4. a\n   4. b\n1. three\n";
+        let mut app = App::new_file(path, false, false, false, text.into()).expect("app");
+        app.editor.set_cursor_line_col(3, 5);
+        let mut running = true;
+        let ctrl_alt = KeyModifiers::CONTROL | KeyModifiers::ALT;
+        app.handle_key(key(KeyCode::Char('l'), ctrl_alt), &mut running)
+            .expect("key");
+        assert_eq!(
+            app.editor.text(),
+            "1. one\n2. two\n   4. a\n   5. b\n3. three\n"
+        );
+        assert_eq!(app.status_text(), "Renumbered list");
+        assert_eq!(app.editor.line_col_at_cursor(), (3, 5));
+
+        app.handle_key(key(KeyCode::Char('l'), ctrl_alt), &mut running)
+            .expect("key");
+        assert_eq!(app.status_text(), "List already numbered");
+        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::CONTROL), &mut running)
+            .expect("key");
+        assert_eq!(app.editor.text(), text);
+
+        app.editor.set_cursor(app.editor.text().len());
+        app.handle_key(key(KeyCode::Char('l'), ctrl_alt), &mut running)
+            .expect("key");
+        assert_eq!(app.status_text(), "Not in an ordered list");
+    }
+
+    #[test]
+    fn extra_cursors_type_at_every_line_and_undo_together() {
+        let path = temp_path("multi-cursor");
+        let text = "one\ntwo\nthree\nfour\n";
+        let mut app = App::new_file(path, false, false, false, text.into()).expect("app");
+        app.editor.set_cursor(0);
+        let mut running = true;
+        let ctrl_alt = KeyModifiers::CONTROL | KeyModifiers::ALT;
+        app.handle_key(key(KeyCode::Down, ctrl_alt), &mut running)
+            .expect("key");
+        app.handle_key(key(KeyCode::Down, ctrl_alt), &mut running)
+            .expect("key");
+        assert_eq!(app.status_text(), "3 cursors (Esc keeps one)");
+
+        // The primary is the terminal cursor; the others are drawn as
+        // reversed cells.
+        let mut terminal = Terminal::new(TestBackend::new(80, 12)).expect("terminal");
+        terminal.draw(|frame| app.draw(frame)).expect("draw");
+        let area = app.editor_text_area;
+        let cursor = terminal.get_cursor_position().expect("cursor");
+        assert_eq!((cursor.x, cursor.y), (area.x, area.y));
+        for row in [1, 2] {
+            let cell = &terminal.backend().buffer()[(area.x, area.y + row)];
+            assert_eq!(cell.symbol(), "t");
+            assert!(cell.modifier.contains(Modifier::REVERSED));
+        }
+
+        for c in "- ".chars() {
+            app.handle_key(key(KeyCode::Char(c), KeyModifiers::NONE), &mut running)
+                .expect("key");
+        }
+        assert_eq!(app.editor.text(), "- one\n- two\n- three\nfour\n");
+
+        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::CONTROL), &mut running)
+            .expect("undo");
+        assert_eq!(app.editor.text(), text);
+        assert!(!app.editor.has_extra_cursors());
+
+        app.handle_key(key(KeyCode::Down, ctrl_alt), &mut running)
+            .expect("key");
+        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
+            .expect("esc");
+        assert!(!app.editor.has_extra_cursors());
+    }
+
+    #[test]
+    fn soft_wrap_draws_the_cursor_on_its_wrapped_row() {
+        let path = temp_path("draw-cursor-wrap");
+        let long = "wörd ".repeat(40);
+        let text = format!("short\n{long}\nend\n");
+        let mut app = App::new_file(path, false, false, false, text.clone()).expect("app");
+        app.set_soft_wrap(true);
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).expect("terminal");
+        terminal.draw(|frame| app.draw(frame)).expect("draw");
+        let area = app.editor_text_area;
+        let starts = wrap_starts(&long, area.width as usize);
+        assert!(starts.len() >= 3, "{starts:?}");
+
+        // Two characters into the second row of line 2.
+        let line_start = "short\n".len();
+        app.editor.set_cursor(line_start + starts[1] + "wö".len());
+        terminal.draw(|frame| app.draw(frame)).expect("draw");
+        let cursor = terminal.get_cursor_position().expect("cursor");
+        assert_eq!((cursor.x, cursor.y), (area.x + 2, area.y + 2));
+        let cell = &terminal.backend().buffer()[(cursor.x, cursor.y)];
+        assert_eq!(cell.symbol(), "r");
+        let gutter = &terminal.backend().buffer()[(area.x - 3, cursor.y)];
+        assert_eq!(gutter.symbol(), " ");
+
+        // Up and Down go by visual row; End stays on the buffer line.
         let mut running = true;
-        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::NONE), &mut running)
-            .expect("readonly char");
-        assert_eq!(app.editor.text(), "x");
-        let _ = fs::remove_file(&path);
+        let mut press = |app: &mut App, code: KeyCode| {
+            app.handle_key(key(code, KeyModifiers::NONE), &mut running)
+                .expect("key");
+        };
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.editor.cursor(), line_start + "wö".len());
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.editor.cursor(), 2);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.editor.cursor(), line_start + starts[2] + "wö".len());
+        press(&mut app, KeyCode::End);
+        assert_eq!(app.editor.cursor(), line_start + long.len());
+        press(&mut app, KeyCode::Home);
+        assert_eq!(app.editor.cursor(), line_start);
     }
 
     #[test]
-    fn draw_sets_cursor_when_visible() {
-        let path = temp_path("draw-cursor");
-        fs::write(&path, "x").expect("seed");
-        let mut app = App::new_file(path.clone(), false, false, false, "x".into()).expect("app");
-        app.editor_scroll = 0;
-        app.preview_scroll = 0;
+    fn soft_wrap_scrolls_by_visual_rows() {
+        let path = temp_path("wrap-scroll");
+        let long = "lorem ipsum dolor sit amet ".repeat(40);
+        let text = format!("{long}\n{long}\nlast\n");
+        let mut app = App::new_file(path, false, false, false, text).expect("app");
+        app.set_soft_wrap(true);
+        app.editor.set_cursor(0);
+        let mut terminal = Terminal::new(TestBackend::new(80, 12)).expect("terminal");
+        terminal.draw(|frame| app.draw(frame)).expect("draw");
+        let rows = wrap_starts(&long, app.editor_text_area.width as usize).len();
+        assert!(rows > app.editor_height, "{rows} rows");
 
-        let mut terminal = Terminal::new(TestBackend::new(80, 20)).expect("terminal");
+        // Down through a line taller than the pane scrolls partway into it.
+        let mut running = true;
+        for _ in 0..rows - 1 {
+            app.handle_key(key(KeyCode::Down, KeyModifiers::NONE), &mut running)
+                .expect("down");
+        }
+        assert_eq!(app.editor_scroll, 0);
+        assert_eq!(app.editor_wrap_skip, rows - app.editor_height);
         terminal.draw(|frame| app.draw(frame)).expect("draw");
-        let _ = fs::remove_file(&path);
+        let cursor = terminal.get_cursor_position().expect("cursor");
+        assert_eq!(
+            cursor.y,
+            app.editor_text_area.y + app.editor_height as u16 - 1
+        );
+
+        app.handle_key(key(KeyCode::Down, KeyModifiers::NONE), &mut running)
+            .expect("down");
+        assert_eq!(app.editor.line_col_at_cursor().0, 1);
+        assert_eq!((app.editor_scroll, app.editor_wrap_skip), (1, 0));
+        app.handle_key(key(KeyCode::Up, KeyModifiers::NONE), &mut running)
+            .expect("up");
+        assert_eq!(app.editor.line_col_at_cursor().0, 0);
+        assert_eq!((app.editor_scroll, app.editor_wrap_skip), (0, rows - 1));
     }
 
     #[test]
@@ -4983,7 +11767,7 @@ mod tests {
         app.editor.on_external_change("same".into());
         tx.send(StreamMessage::Update {
             text: "same".into(),
-            truncated: false,
+            dropped: 0,
         })
         .expect("send");
         app.handle_stream_updates();
@@ -5046,8 +11830,8 @@ mod tests {
         let path = temp_path("preview-cache-arc");
         fs::write(&path, "one").expect("seed");
         let mut app = App::new_file(path.clone(), false, false, false, "one".into()).expect("app");
-        let (first, _) = app.preview_lines_cached(40);
-        let (second, _) = app.preview_lines_cached(40);
+        let first = app.preview_lines_cached(40);
+        let second = app.preview_lines_cached(40);
         assert!(std::sync::Arc::ptr_eq(&first, &second));
         let _ = fs::remove_file(&path);
     }
@@ -5092,6 +11876,79 @@ mod tests {
         let _ = fs::remove_file(&path);
     }
 
+    #[test]
+    fn home_open_keeps_crlf_and_tolerates_invalid_utf8() {
+        let path = temp_path("home-crlf");
+        fs::write(&path, b"# h\r\nbad \xff\r\ntail\r\n").expect("seed");
+        let mut app = App::new_home_for_test(false, false, false);
+        app.open_home_path(path.clone());
+
+        assert_eq!(app.editor.text(), "# h\nbad \u{fffd}\ntail\n");
+        assert_eq!(app.editor.line_ending(), LineEnding::CrLf);
+        assert_eq!(
+            app.status_text(),
+            "Opened with 1 invalid UTF-8 sequence replaced"
+        );
+
+        app.editor.move_doc_start();
+        app.editor.insert_char('#');
+        app.save_to_current_path().expect("save");
+        assert_eq!(
+            fs::read(&path).expect("read"),
+            "## h\r\nbad \u{fffd}\r\ntail\r\n".as_bytes()
+        );
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn home_tab_completes_and_cycles_paths() {
+        let dir = temp_path("home-complete").with_extension("");
+        fs::create_dir_all(&dir).expect("mkdir");
+        fs::write(dir.join("notes-a.md"), "# A\n").expect("seed");
+        fs::write(dir.join("notes-b.md"), "# B\n").expect("seed");
+        fs::write(dir.join("todo.md"), "# Todo\n").expect("seed");
+        let base = format!("{}/", dir.display());
+        let mut app = App::new_home_for_test(false, false, false);
+        let mut running = true;
+
+        app.home_query = format!("{base}to");
+        app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE), &mut running)
+            .expect("key");
+        assert_eq!(app.home_query, format!("{base}todo.md"));
+
+        app.home_query = format!("{base}zz");
+        app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE), &mut running)
+            .expect("key");
+        assert_eq!(app.home_query, format!("{base}zz"));
+        assert!(app.status_text().ends_with("(no match)"));
+
+        app.home_query = format!("{base}n");
+        app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE), &mut running)
+            .expect("key");
+        assert_eq!(app.home_query, format!("{base}notes-"));
+        assert_eq!(
+            app.status_text(),
+            "2 matches, Tab cycles: notes-a.md  notes-b.md"
+        );
+        app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE), &mut running)
+            .expect("key");
+        assert_eq!(app.home_query, format!("{base}notes-a.md"));
+        app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE), &mut running)
+            .expect("key");
+        assert_eq!(app.home_query, format!("{base}notes-b.md"));
+        app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE), &mut running)
+            .expect("key");
+        assert_eq!(app.home_query, format!("{base}notes-a.md"));
+
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("key");
+        assert!(!app.home_mode);
+        assert_eq!(app.editor.text(), "# A\n");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn draw_home_renders_branding_and_search_prompt() {
         let mut app = App::new_home_for_test(false, false, false);
@@ -5121,7 +11978,10 @@ mod tests {
         app.search_mode = false;
         app.goto_mode = true;
         assert_eq!(mode_label(&app), "goto");
-        assert_eq!(app.status_hint(), "goto: type line number + Enter");
+        assert_eq!(
+            app.status_hint(),
+            "goto: LINE, LINE:COL, +N, -N or N% + Enter"
+        );
 
         app.goto_mode = false;
         app.replace_find_mode = true;
@@ -5143,6 +12003,31 @@ mod tests {
         assert!(app.status_hint().contains("Type to edit"));
     }
 
+    #[test]
+    fn file_info_joins_the_info_line_when_it_fits() {
+        let path = temp_path("file-info");
+        fs::write(&path, "abc").expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, "abc".into()).expect("app");
+        app.handle_file_info_result();
+        let info = loop {
+            app.handle_file_info_result();
+            if let Some((_, info)) = &app.file_info {
+                break info.clone().expect("info");
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        };
+        let segment = format!(" | {}", info.segment());
+        assert!(segment.starts_with(" | 3 B · "), "{segment}");
+        assert!(app.info_line(300).ends_with(&segment));
+
+        // A narrow line drops it before cutting the path.
+        let without = app.info_line(300).len() - segment.len();
+        let narrow = app.info_line(without);
+        assert!(!narrow.contains(" · "), "{narrow}");
+        assert!(!narrow.contains('…'), "{narrow}");
+        let _ = fs::remove_file(&path);
+    }
+
     #[test]
     fn helper_info_line_title_and_styles() {
         let path = temp_path("helpers");
@@ -5159,15 +12044,25 @@ mod tests {
         assert!(title.contains("Preview [conflict 1/"));
         assert_eq!(preview_title_with_scroll(0, None, 1, 0, 10), "Preview");
 
-        let theme = build_theme(ThemeChoice::Default, false);
+        let theme = build_theme(ThemeChoice::Default, false, None);
         assert_eq!(pane_border_style(&theme, true).fg, theme.pane_focus.fg);
         assert_eq!(pane_border_style(&theme, false).fg, theme.pane_border.fg);
         assert_eq!(
-            status_style(&theme, false, "watch error: x").fg,
+            status_style(&theme, false, Severity::Error).fg,
             theme.status_error.fg
         );
-        assert_eq!(status_style(&theme, true, "ok").fg, theme.status_warn.fg);
-        assert_eq!(status_style(&theme, false, "ok").fg, theme.status_ok.fg);
+        assert_eq!(
+            status_style(&theme, false, Severity::Warn).fg,
+            theme.status_warn.fg
+        );
+        assert_eq!(
+            status_style(&theme, true, Severity::Info).fg,
+            theme.status_warn.fg
+        );
+        assert_eq!(
+            status_style(&theme, false, Severity::Info).fg,
+            theme.status_ok.fg
+        );
         let _ = fs::remove_file(&path);
     }
 
@@ -5204,7 +12099,7 @@ mod tests {
 
     #[test]
     fn helper_preview_line_code_and_list_paths() {
-        let theme = build_theme(ThemeChoice::Default, false);
+        let theme = build_theme(ThemeChoice::Default, false, None);
         let mut in_code = false;
 
         let fence = styled_preview_line("```rs", 80, &theme, &mut in_code);
@@ -5231,17 +12126,434 @@ mod tests {
     }
 
     #[test]
-    fn helper_code_open_and_popup() {
-        let lines = vec![
-            "a".to_string(),
-            "```".to_string(),
-            "code".to_string(),
-            "```".to_string(),
-        ];
-        assert!(!code_open_before(&lines, 1));
-        assert!(code_open_before(&lines, 3));
-        assert!(!code_open_before(&lines, 4));
+    fn preview_lines_are_styled_from_their_segments() {
+        let theme = build_theme(ThemeChoice::Default, false, None);
+        let rendered =
+            mdv_core::render_preview_segments("- **bold**, *it* and ~~x*y*~~\n\n***", 80);
+        let line = styled_preview_segments(&rendered[0], 80, &theme);
+        let spans: Vec<_> = line
+            .spans
+            .iter()
+            .map(|span| (span.content.as_ref(), span.style))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                ("- ", theme.list_bullet),
+                ("bold", theme.strong),
+                (", ", theme.plain),
+                ("it", theme.emphasis),
+                (" and ", theme.plain),
+                ("x", theme.strikethrough),
+                ("y", theme.emphasis),
+            ]
+        );
+
+        let rule = styled_preview_segments(&rendered[1], 12, &theme);
+        assert_eq!(rule.spans[0].content, "─".repeat(10));
+        assert_eq!(rule.spans[0].style, theme.hr);
+    }
+
+    #[test]
+    fn conflict_lines_carry_their_own_kinds() {
+        let path = temp_path("conflict-kinds");
+        fs::write(&path, "same\nlocal\n").expect("seed");
+        let mut app =
+            App::new_file(path.clone(), false, false, false, "same\nlocal\n".into()).expect("app");
+        app.editor.insert_char('!');
+        app.editor
+            .on_external_change("same\nExternal block text\n".into());
+        let lines = app.preview_lines_cached(80);
+        let anchor = selected_hunk_row(&lines).expect("selected hunk");
+        assert!(matches!(
+            lines[anchor],
+            PreviewLineKind::ConflictHeader {
+                side: ConflictSide::Local,
+                selected: true,
+                ..
+            }
+        ));
+        assert!(lines.contains(&PreviewLineKind::ConflictBody {
+            side: ConflictSide::External,
+            text: Some("External block text".into()),
+        }));
+
+        let theme = build_theme(ThemeChoice::Default, false, None);
+        let style = |line: &PreviewLineKind| line.styled(80, &theme).spans[0].style;
+        assert_eq!(style(&lines[anchor]), theme.conflict_local);
+        let external = lines
+            .iter()
+            .find(|line| line.text().starts_with(">>> External block"))
+            .expect("external header");
+        assert_eq!(style(external), theme.conflict_external);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn conflict_marker_text_in_a_document_is_plain_markdown() {
+        let text = "# Notes\n\n--- Local block ---\n\n>>> External block @L2 <<<\n";
+        let path = temp_path("conflict-marker-text");
+        fs::write(&path, text).expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, text.into()).expect("app");
+        let theme = build_theme(ThemeChoice::Default, false, None);
+        let lines = app.preview_lines_cached(80);
+        assert!(
+            lines
+                .iter()
+                .all(|line| matches!(line, PreviewLineKind::Markdown(_)))
+        );
+        let marker = lines
+            .iter()
+            .find(|line| line.text().contains("Local block"))
+            .expect("marker line");
+        let styled = marker.styled(80, &theme);
+        assert!(
+            styled
+                .spans
+                .iter()
+                .all(|span| span.style != theme.conflict_local)
+        );
+
+        let editor_line = styled_preview_line("--- Local block ---", 80, &theme, &mut false);
+        assert!(
+            editor_line
+                .spans
+                .iter()
+                .all(|span| span.style != theme.conflict_local)
+        );
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn watched_links_report_changes_and_open_with_f5() {
+        let dir = temp_path("watch-links");
+        fs::create_dir_all(dir.join("sub")).expect("dir");
+        let index = dir.join("index.md");
+        let page = dir.join("sub/page.md");
+        fs::write(&page, "# Page\n").expect("seed page");
+        let text = "# Index\n\n[Page](sub/page.md)\n";
+        fs::write(&index, text).expect("seed index");
+        let mut app = App::new_file(index.clone(), false, true, false, text.into()).expect("app");
+        app.set_watch_links(true);
+        let watched: Vec<_> = app.link_watches.iter().map(|w| w.path.clone()).collect();
+        assert_eq!(watched, std::slice::from_ref(&page));
+
+        fs::write(&page, "# Page\n\nMore.\n").expect("change page");
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while app.changed_link.is_none() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+            app.handle_link_watch_updates();
+        }
+        assert_eq!(
+            app.status_text(),
+            format!(
+                "linked file changed: {} (press F5 to open)",
+                Path::new("sub/page.md").display()
+            )
+        );
+        assert_eq!(app.editor.text(), text);
+
+        // Saving a new link set rebuilds the watchers, up to the cap.
+        let mut more = text.to_string();
+        for i in 0..MAX_LINK_WATCHES + 3 {
+            fs::write(dir.join(format!("n{i}.md")), "x").expect("seed");
+            more.push_str(&format!("[n](n{i}.md)\n"));
+        }
+        app.editor = EditorBuffer::new(more);
+        let mut running = true;
+        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::CONTROL), &mut running)
+            .expect("save");
+        assert_eq!(app.link_watches.len(), MAX_LINK_WATCHES);
+        assert_eq!(app.link_watches[0].path, page);
+
+        app.handle_key(key(KeyCode::F(5), KeyModifiers::NONE), &mut running)
+            .expect("open");
+        assert_eq!(app.path.as_deref(), Some(page.as_path()));
+        assert_eq!(app.editor.text(), "# Page\n\nMore.\n");
+        assert!(app.link_watches.is_empty());
+        app.handle_key(key(KeyCode::F(5), KeyModifiers::NONE), &mut running)
+            .expect("nothing to open");
+        assert_eq!(app.status_text(), "No linked file has changed");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn sticky_statuses_outlast_info_and_alt_m_lists_them() {
+        let path = temp_path("status-log");
+        let mut app =
+            App::new_file(path.clone(), true, false, false, "# Doc\n".into()).expect("app");
+        let mut running = true;
+        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::CONTROL), &mut running)
+            .expect("save");
+        assert_eq!(app.status_text(), "Readonly: save disabled");
+        app.push_status(Severity::Error, "watch error: gone");
+        app.push_status(Severity::Info, "stream update received");
+        for _ in 0..STATUS_TTL_FRAMES {
+            app.status.age();
+        }
+        assert_eq!(app.status_text(), "watch error: gone");
+
+        // Any key dismisses the error; Ctrl+L only switches conflict layouts.
+        app.handle_key(key(KeyCode::Char('l'), KeyModifiers::CONTROL), &mut running)
+            .expect("layout");
+        assert!(!app.message_log);
+        assert_eq!(app.status_text(), "No conflict to show");
+        app.handle_key(key(KeyCode::Char('m'), KeyModifiers::ALT), &mut running)
+            .expect("log");
+        assert!(app.message_log);
+        assert_eq!(app.status_text(), "Ready");
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).expect("terminal");
+        let text = draw_text(&mut app, &mut terminal);
+        assert!(text.contains("Messages"));
+        let at = |needle: &str| text.find(needle).expect(needle);
+        assert!(at("stream update received") < at("watch error: gone"));
+        assert!(at("watch error: gone") < at("Readonly: save disabled"));
+
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
+            .expect("close");
+        assert!(!app.message_log);
+        assert!(!app.editor.text().contains('x'));
+
+        app.push_status(Severity::Info, "Saved");
+        for _ in 1..STATUS_TTL_FRAMES {
+            app.status.age();
+        }
+        assert_eq!(app.status_text(), "Saved");
+        app.status.age();
+        assert_eq!(app.status_text(), "Ready");
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn panes_keep_their_own_scroll_until_alt_l_syncs_them() {
+        let text: String = (1..=40)
+            .map(|n| format!("## Section {n}\n\nBody {n}.\n\n"))
+            .collect();
+        let mut app =
+            App::new_file(temp_path("sync-panes"), false, false, false, text).expect("app");
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).expect("terminal");
+        let mut running = true;
+        app.editor.set_cursor(0);
+        draw_text(&mut app, &mut terminal);
+        let editor_scroll = app.editor_scroll;
+
+        let shift_tab = key(KeyCode::BackTab, KeyModifiers::SHIFT);
+        app.handle_key(shift_tab, &mut running).expect("to preview");
+        for _ in 0..2 {
+            app.handle_key(key(KeyCode::PageDown, KeyModifiers::NONE), &mut running)
+                .expect("scroll preview");
+        }
+        draw_text(&mut app, &mut terminal);
+        let scrolled = app.preview_scroll;
+        assert!(scrolled > 0);
+        app.handle_key(shift_tab, &mut running).expect("to editor");
+        assert_eq!(app.status_text(), "Mode: editor");
+        draw_text(&mut app, &mut terminal);
+        app.handle_key(shift_tab, &mut running)
+            .expect("back to preview");
+        draw_text(&mut app, &mut terminal);
+        assert_eq!(app.preview_scroll, scrolled);
+        assert_eq!(app.editor_scroll, editor_scroll);
+
+        app.handle_key(shift_tab, &mut running).expect("to editor");
+        app.editor.goto_line(81);
+        app.handle_key(key(KeyCode::Char('l'), KeyModifiers::ALT), &mut running)
+            .expect("sync");
+        assert_eq!(app.status_text(), "Preview synced to line 81");
+        draw_text(&mut app, &mut terminal);
+        let preview = cursor_rect(app.preview_area);
+        let buffer = terminal.backend().buffer();
+        let first_row: String = (preview.left()..preview.right())
+            .map(|x| buffer[(x, preview.top())].symbol())
+            .collect();
+        assert!(first_row.contains("Section 21"), "{first_row}");
+    }
+
+    #[test]
+    fn snippet_palette_filters_asks_and_inserts_as_one_undo_step() {
+        let mut app =
+            App::new_file(temp_path("snippets"), false, false, false, "Intro".into()).expect("app");
+        app.set_snippets(vec![Snippet::text(
+            "Note".into(),
+            "> **Note:** $CURSOR".into(),
+        )]);
+        let mut running = true;
+        let ctrl_dot = key(KeyCode::Char('.'), KeyModifiers::CONTROL);
+        let type_text = |app: &mut App, running: &mut bool, text: &str| {
+            for c in text.chars() {
+                app.handle_key(key(KeyCode::Char(c), KeyModifiers::NONE), running)
+                    .expect("type");
+            }
+        };
+        let enter = key(KeyCode::Enter, KeyModifiers::NONE);
+
+        app.editor.set_cursor(5);
+        app.handle_key(ctrl_dot, &mut running).expect("open");
+        assert_eq!(mode_label(&app), "snippets");
+        type_text(&mut app, &mut running, "tab");
+        let mut terminal = Terminal::new(TestBackend::new(100, 24)).expect("terminal");
+        let text = draw_text(&mut app, &mut terminal);
+        assert!(text.contains("> tab") && text.contains("Table"));
+        assert!(!text.contains("Code block"));
+        app.handle_key(enter, &mut running).expect("pick");
+        type_text(&mut app, &mut running, "2x9x");
+        app.handle_key(enter, &mut running).expect("bad size");
+        assert!(app.status_text().starts_with("Table size looks like 2x3"));
+        for _ in 0..4 {
+            app.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE), &mut running)
+                .expect("erase");
+        }
+        type_text(&mut app, &mut running, "2x3");
+        app.handle_key(enter, &mut running).expect("insert");
+        assert!(app.snippet_palette.is_none());
+        let table = "|     |     |     |\n\
+                     | --- | --- | --- |\n\
+                     |     |     |     |\n\
+                     |     |     |     |\n";
+        assert_eq!(app.editor.text(), format!("Intro\n{table}"));
+        assert_eq!(app.editor.line_col_at_cursor(), (1, 2));
+
+        app.handle_key(key(KeyCode::Char('z'), KeyModifiers::CONTROL), &mut running)
+            .expect("undo");
+        assert_eq!(app.editor.text(), "Intro");
+
+        app.editor.set_cursor(0);
+        app.handle_key(ctrl_dot, &mut running).expect("open");
+        type_text(&mut app, &mut running, "NOTE");
+        app.handle_key(enter, &mut running).expect("insert note");
+        type_text(&mut app, &mut running, "hi");
+        assert_eq!(app.editor.text(), "> **Note:** hiIntro");
+
+        // Alt+S opens it where Ctrl+. arrives as a plain `.`.
+        app.handle_key(key(KeyCode::Char('s'), KeyModifiers::ALT), &mut running)
+            .expect("open");
+        assert!(app.snippet_palette.is_some());
+        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
+            .expect("close");
+        assert!(app.snippet_palette.is_none());
+        assert_eq!(app.status_text(), "Snippets closed");
+    }
+
+    #[test]
+    fn review_shows_buffer_changes_against_disk() {
+        let path = temp_path("review");
+        let disk = "# Title\none\ntwo\nthree\nfour\nfive\nsix\n";
+        fs::write(&path, disk).expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, disk.into()).expect("app");
+        let mut running = true;
+        app.editor.goto_line(3);
+        app.editor.insert_char('!');
+        app.editor.goto_line(7);
+        app.handle_key(key(KeyCode::Char('d'), KeyModifiers::CONTROL), &mut running)
+            .expect("delete line");
+
+        let review_key = key(
+            KeyCode::Char('D'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        );
+        app.handle_key(review_key, &mut running).expect("review");
+        assert_eq!(
+            app.status_text(),
+            "Reviewing 2 changes against disk: Ctrl+J/Ctrl+U move | Esc close"
+        );
+        assert_eq!(mode_label(&app), "review");
+        assert!(app.info_line(200).ends_with(" | review +1 −2 lines"));
+        let lines = app.preview_lines_cached(80);
+        let anchor = selected_hunk_row(&lines);
+        let texts: Vec<_> = lines.iter().map(|line| line.text()).collect();
+        assert_eq!(texts[anchor.expect("selected")], ">>> Local block @L3 <<<");
+        assert!(texts.contains(&"!two".into()));
+        assert!(texts.contains(&">>> Disk block @L3 <<<".into()));
+        assert!(texts.contains(&"--- Disk block @L7 ---".into()));
+        assert!(texts.contains(&"(no local lines)".into()));
+        // Context lines are source text, not the rendered preview.
+        assert_eq!(texts[anchor.expect("selected") - 2], "# Title");
+
+        app.handle_key(key(KeyCode::Char('j'), KeyModifiers::CONTROL), &mut running)
+            .expect("next");
+        assert_eq!(app.status_text(), "Change 2/2");
+        assert_eq!(
+            app.status_hint(),
+            "review: Ctrl+J/Ctrl+U change | Esc close | change 2/2"
+        );
+        let lines = app.preview_lines_cached(80);
+        assert_eq!(
+            lines[selected_hunk_row(&lines).expect("selected")].text(),
+            ">>> Local block @L7 <<<"
+        );
+        app.handle_key(key(KeyCode::Char('x'), KeyModifiers::NONE), &mut running)
+            .expect("type");
+        assert_eq!(
+            app.status_text(),
+            "Review is read-only: Esc returns to editing"
+        );
+        assert!(!app.editor.text().contains('x'));
+
+        app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE), &mut running)
+            .expect("close");
+        assert_eq!(app.status_text(), "Review closed");
+        assert!(!app.info_line(200).contains("| review "));
+        let lines = app.preview_lines_cached(80);
+        assert_eq!(selected_hunk_row(&lines), None);
+        assert!(lines.iter().any(|line| line.text().contains("Title")));
+        assert!(!lines.iter().any(|line| line.text().contains("Disk block")));
+
+        // Without a file on disk every line is new.
+        fs::remove_file(&path).expect("remove");
+        app.handle_key(review_key, &mut running).expect("review");
+        assert!(app.info_line(200).ends_with(" | review +6 −0 lines"));
+        app.handle_key(review_key, &mut running).expect("close");
+        assert!(app.review.is_none());
+    }
+
+    #[test]
+    fn conflict_view_draws_local_and_external_columns() {
+        let path = temp_path("conflict-columns");
+        let local = "# Doc\nlocal first\nshared\nstill shared\nlocal second\n";
+        fs::write(&path, local).expect("seed");
+        let mut app = App::new_file(path.clone(), false, false, false, local.into()).expect("app");
+        app.editor.dirty = true;
+        app.editor.on_external_change(
+            "# Doc\nexternal first\nshared\nstill shared\nexternal second\n".into(),
+        );
+        app.move_conflict_hunk(1);
+        assert_eq!(app.selected_conflict_hunk, 1);
+
+        let theme = build_theme(ThemeChoice::Default, false, None);
+        let mut terminal = Terminal::new(TestBackend::new(140, 30)).expect("terminal");
+        let text = draw_text(&mut app, &mut terminal);
+        assert!(text.contains("Local [conflict 2/2]"));
+        assert!(text.contains("External"));
+        assert!(!text.contains("Local block"));
+
+        let buffer = terminal.backend().buffer();
+        let row_style = |needle: &str| {
+            let width = buffer.area.width as usize;
+            // The editor pane on the left shows the same local lines.
+            let at = text[..text.rfind(needle).expect(needle)].chars().count();
+            let (x, y) = ((at % width) as u16, (at / width) as u16);
+            buffer[(x, y)].style()
+        };
+        assert_eq!(row_style("local second").fg, theme.conflict_local.fg);
+        assert_eq!(row_style("external second").fg, theme.conflict_external.fg);
+        assert_eq!(row_style("local first").fg, theme.plain.fg);
+        assert!(
+            row_style("shared")
+                .add_modifier
+                .contains(ratatui::style::Modifier::DIM)
+        );
+
+        let mut running = true;
+        app.handle_key(key(KeyCode::Char('l'), KeyModifiers::CONTROL), &mut running)
+            .expect("toggle");
+        assert_eq!(app.status_text(), "Conflict view: stacked blocks");
+        assert!(draw_text(&mut app, &mut terminal).contains("Local block"));
+
+        let _ = fs::remove_file(&path);
+    }
 
+    #[test]
+    fn helper_centered_popup() {
         let popup = centered_popup(
             60,
             40,
@@ -5276,10 +12588,30 @@ mod tests {
         assert_eq!(compact_docs.height, 10);
     }
 
+    fn viewport(scroll: usize, height: usize) -> EditorViewport {
+        EditorViewport {
+            scroll,
+            hscroll: 0,
+            height,
+            width: 80,
+            code_open: false,
+            wrap: false,
+            wrap_skip: 0,
+        }
+    }
+
     #[test]
     fn styled_editor_lines_marks_selected_span() {
-        let theme = build_theme(ThemeChoice::Default, false);
-        let rendered = styled_editor_lines("hello", 0, 1, Some((1, 4)), 80, &theme, 0);
+        let theme = build_theme(ThemeChoice::Default, false, None);
+        let rendered = styled_editor_lines(
+            &EditorBuffer::new("hello".into()),
+            viewport(0, 1),
+            Some((1, 4)),
+            &[],
+            &[],
+            &theme,
+            Some(0),
+        );
         assert_eq!(rendered.len(), 1);
         // First span is line number, then content spans
         assert!(rendered[0].spans.len() >= 3);
@@ -5288,26 +12620,44 @@ mod tests {
         assert_eq!(rendered[0].spans[content_start].content.as_ref(), "h");
         assert_eq!(rendered[0].spans[content_start + 1].content.as_ref(), "ell");
         assert_eq!(rendered[0].spans[content_start + 2].content.as_ref(), "o");
-        assert!(
-            rendered[0].spans[content_start + 1]
-                .style
-                .add_modifier
-                .contains(Modifier::REVERSED)
+        assert_eq!(
+            rendered[0].spans[content_start + 1].style.bg,
+            theme.selection.bg
+        );
+        assert_ne!(
+            rendered[0].spans[content_start].style.bg,
+            theme.selection.bg
         );
     }
 
     #[test]
     fn styled_editor_lines_uses_semantic_styles() {
-        let theme = build_theme(ThemeChoice::Default, false);
-        let rendered = styled_editor_lines("# Heading", 0, 1, None, 80, &theme, 0);
+        let theme = build_theme(ThemeChoice::Default, false, None);
+        let rendered = styled_editor_lines(
+            &EditorBuffer::new("# Heading".into()),
+            viewport(0, 1),
+            None,
+            &[],
+            &[],
+            &theme,
+            Some(0),
+        );
         // First span is line number, second span (index 1) is heading content
         assert_eq!(rendered[0].spans[1].style.fg, theme.heading.fg);
     }
 
     #[test]
     fn styled_editor_lines_shows_current_line_highlight() {
-        let theme = build_theme(ThemeChoice::Default, false);
-        let rendered = styled_editor_lines("line1\nline2\nline3", 0, 3, None, 80, &theme, 1);
+        let theme = build_theme(ThemeChoice::Default, false, None);
+        let rendered = styled_editor_lines(
+            &EditorBuffer::new("line1\nline2\nline3".into()),
+            viewport(0, 3),
+            None,
+            &[],
+            &[],
+            &theme,
+            Some(1),
+        );
         // Line 0 (not current)
         assert_eq!(rendered[0].spans[0].style.fg, theme.line_number.fg);
         // Line 1 (current)
@@ -5331,9 +12681,108 @@ mod tests {
             .map(|cell| cell.symbol())
             .collect::<String>();
         assert!(rendered.contains("Docs + Settings"));
+        assert!(rendered.contains("Line numbers"));
+        assert!(rendered.contains("Esc close"));
+
+        app.ui.help.switch_tab();
+        terminal.draw(|frame| app.draw(frame)).expect("draw docs");
+        let rendered = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect::<String>();
         assert!(rendered.contains("Sections"));
         assert!(rendered.contains("Welcome"));
-        assert!(rendered.contains("Esc close"));
+    }
+
+    #[test]
+    fn settings_tab_no_color_toggle_repaints_immediately() {
+        let mut app = App::new_file(
+            temp_path("settings-color"),
+            false,
+            false,
+            false,
+            "# Title\n\n[link](x)\n".into(),
+        )
+        .expect("app");
+        app.ui.theme = ThemeChoice::Default;
+        let backend = TestBackend::new(100, 20);
+        let mut terminal = Terminal::new(backend).expect("terminal");
+        let colored_cells = |terminal: &Terminal<TestBackend>| {
+            terminal
+                .backend()
+                .buffer()
+                .content()
+                .iter()
+                .filter(|cell| matches!(cell.fg, ratatui::style::Color::Rgb(..)))
+                .count()
+        };
+
+        terminal.draw(|frame| app.draw(frame)).expect("draw");
+        assert!(colored_cells(&terminal) > 0);
+
+        let mut running = true;
+        app.handle_key(key(KeyCode::Char(','), KeyModifiers::CONTROL), &mut running)
+            .expect("open settings");
+        app.handle_key(key(KeyCode::Down, KeyModifiers::NONE), &mut running)
+            .expect("select colors");
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE), &mut running)
+            .expect("toggle colors");
+        assert!(app.ui.no_color);
+        assert_eq!(app.status_text(), "Colors: off");
+
+        terminal.draw(|frame| app.draw(frame)).expect("redraw");
+        assert_eq!(colored_cells(&terminal), 0);
+    }
+
+    #[test]
+    fn settings_tab_toggles_watch_line_numbers_wrap_and_scroll_sync() {
+        let path = temp_path("settings-toggles");
+        let text = (1..=60)
+            .map(|n| format!("para {n}\n\n"))
+            .collect::<String>();
+        fs::write(&path, &text).expect("seed");
+        let mut app = App::new_file(path.clone(), false, true, false, text).expect("app");
+        app.ui.help.open_docs();
+        let mut running = true;
+        let mut press = |app: &mut App, code: KeyCode| {
+            app.handle_key(key(code, KeyModifiers::NONE), &mut running)
+                .expect("settings key");
+        };
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert!(!app.watch_enabled);
+        assert_eq!(app.status_text(), "Watch file: off");
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert!(!app.continue_lists);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert!(!app.ui.line_numbers);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert!(app.ui.soft_wrap);
+        assert_eq!(app.status_text(), "Soft wrap: on");
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Left);
+        assert!(app.ui.scroll_sync);
+        assert_eq!(app.status_text(), "Scroll sync: on");
+        press(&mut app, KeyCode::Esc);
+
+        app.editor_scroll = 40;
+        let backend = TestBackend::new(140, 30);
+        let mut terminal = Terminal::new(backend).expect("terminal");
+        terminal.draw(|frame| app.draw(frame)).expect("draw");
+        assert_eq!(app.editor_text_area.x, app.editor_area.x + 1);
+        assert!(app.preview_scroll > 0);
+
+        let _ = fs::remove_file(&path);
     }
 
     #[test]
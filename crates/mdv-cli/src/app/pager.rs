@@ -0,0 +1,451 @@
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::Result;
+use crossterm::event::{self, KeyCode, KeyEvent, KeyModifiers};
+use mdv_core::{PreviewLine, RenderOptions, render};
+use ratatui::Frame;
+use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::Rect;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Paragraph;
+use signal_hook::consts::{SIGINT, SIGTERM};
+
+use super::terminal::TerminalGuard;
+use super::{InputEvent, ThemeChoice, next_terminal_input, styled_preview_segments};
+use crate::ui::theme::{
+    ThemeOverrides, ThemeTokens, build_theme, detect_terminal_background, resolve_auto,
+};
+
+/// Rendered lines asked for up front; the budget doubles whenever the
+/// pager needs lines past it, so a huge file opens as fast as a small one.
+const FIRST_BUDGET: usize = 256;
+/// Lines one wheel notch scrolls.
+const WHEEL_LINES: usize = 3;
+
+/// `--pager`: the rendered document in a read-only scroller, like `less`.
+/// Lines are rendered as scrolling and searching reach them.
+pub struct Pager {
+    name: String,
+    text: String,
+    width: u16,
+    /// Rows of document on screen, without the status line.
+    height: usize,
+    lines: Vec<PreviewLine>,
+    /// Rendering stopped at the budget; more of the document follows.
+    truncated: bool,
+    budget: usize,
+    top: usize,
+    /// What is being typed after `/`.
+    prompt: Option<String>,
+    query: Option<String>,
+    message: Option<String>,
+}
+
+impl Pager {
+    pub fn new(name: impl Into<String>, text: String) -> Self {
+        Self {
+            name: name.into(),
+            text,
+            width: 0,
+            height: 1,
+            lines: Vec::new(),
+            truncated: true,
+            budget: FIRST_BUDGET,
+            top: 0,
+            prompt: None,
+            query: None,
+            message: None,
+        }
+    }
+
+    /// Lay the document out for a `width` by `height` page, status line
+    /// included. A new width renders again and keeps the same source line
+    /// on top.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.height = usize::from(height.saturating_sub(1)).max(1);
+        if width != self.width {
+            let source = self.lines.get(self.top).map_or(0, |line| line.source_line);
+            self.width = width;
+            self.lines.clear();
+            self.truncated = true;
+            self.render_to(self.budget);
+            let top = loop {
+                match self
+                    .lines
+                    .iter()
+                    .position(|line| line.source_line >= source)
+                {
+                    Some(top) => break top,
+                    None if self.truncated => self.render_to(self.lines.len() * 2),
+                    None => break self.lines.len(),
+                }
+            };
+            self.set_top(top);
+        } else {
+            self.set_top(self.top);
+        }
+    }
+
+    /// Render until there are at least `count` lines or the document ends.
+    fn render_to(&mut self, count: usize) {
+        if !self.truncated || self.lines.len() >= count {
+            return;
+        }
+        // After a resize the same budget renders again.
+        if !self.lines.is_empty() {
+            self.budget = self.budget.saturating_mul(2);
+        }
+        self.budget = self.budget.max(count);
+        let max_lines = (self.budget != usize::MAX).then_some(self.budget);
+        let options = RenderOptions::new(self.width)
+            .with_emoji(true)
+            .with_reader(true)
+            .with_max_lines(max_lines);
+        let doc = render(&self.text, &options);
+        self.truncated = doc.truncated;
+        self.lines = doc
+            .lines
+            .into_iter()
+            .map(|line| PreviewLine {
+                segments: line.segments,
+                source_line: line.source_line,
+            })
+            .collect();
+    }
+
+    /// Scroll so `top` is the first line shown, stopping with the last line
+    /// at the bottom of the page.
+    fn set_top(&mut self, top: usize) {
+        self.render_to(top.saturating_add(self.height));
+        self.top = top.min(self.lines.len().saturating_sub(self.height));
+    }
+
+    fn scroll(&mut self, lines: isize) {
+        self.set_top(self.top.saturating_add_signed(lines));
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent, running: &mut bool) {
+        self.message = None;
+        if let Some(prompt) = &mut self.prompt {
+            match key.code {
+                KeyCode::Esc => self.prompt = None,
+                KeyCode::Enter => {
+                    let query = self.prompt.take().unwrap_or_default();
+                    if !query.is_empty() {
+                        self.query = Some(query);
+                    }
+                    self.search(true);
+                }
+                KeyCode::Backspace => {
+                    prompt.pop();
+                }
+                KeyCode::Char('c') if key.modifiers == KeyModifiers::CONTROL => {
+                    self.prompt = None;
+                }
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    prompt.push(c);
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        let page = self.height as isize;
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('q') | KeyCode::Esc, _)
+            | (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+                *running = false;
+            }
+            (KeyCode::Down | KeyCode::Enter | KeyCode::Char('j'), _) => self.scroll(1),
+            (KeyCode::Up | KeyCode::Char('k'), _) => self.scroll(-1),
+            (KeyCode::PageDown | KeyCode::Char(' ' | 'f'), _) => self.scroll(page),
+            (KeyCode::PageUp | KeyCode::Char('b'), _) => self.scroll(-page),
+            (KeyCode::Char('d'), _) => self.scroll(page / 2),
+            (KeyCode::Char('u'), _) => self.scroll(-page / 2),
+            (KeyCode::Home | KeyCode::Char('g'), _) => self.set_top(0),
+            (KeyCode::End | KeyCode::Char('G'), _) => self.set_top(usize::MAX),
+            (KeyCode::Char('/'), _) => self.prompt = Some(String::new()),
+            (KeyCode::Char('n'), _) => self.search(true),
+            (KeyCode::Char('N'), _) => self.search(false),
+            _ => {}
+        }
+    }
+
+    /// Move the next line below the top, or the previous one above it, that
+    /// contains the query to the top. Case is ignored unless the query has
+    /// capitals.
+    fn search(&mut self, forward: bool) {
+        let Some(query) = self.query.clone() else {
+            self.message = Some("No search yet: / searches".into());
+            return;
+        };
+        let ignore_case = !query.chars().any(char::is_uppercase);
+        let matches = |line: &PreviewLine| {
+            let text = line.text();
+            if ignore_case {
+                text.to_lowercase().contains(&query)
+            } else {
+                text.contains(&query)
+            }
+        };
+        let found = if forward {
+            let mut from = self.top + 1;
+            loop {
+                if let Some(offset) = self.lines.iter().skip(from).position(matches) {
+                    break Some(from + offset);
+                }
+                if !self.truncated {
+                    break None;
+                }
+                from = from.max(self.lines.len());
+                self.render_to(self.lines.len() * 2);
+            }
+        } else {
+            self.lines[..self.top.min(self.lines.len())]
+                .iter()
+                .rposition(matches)
+        };
+        match found {
+            Some(line) => {
+                self.set_top(line);
+                self.message = Some(format!("/{query}: line {}", line + 1));
+            }
+            None => self.message = Some(format!("Pattern not found: {query}")),
+        }
+    }
+
+    /// The status line: the prompt while one is open, else the last message
+    /// or where the page is.
+    fn status(&self) -> String {
+        if let Some(prompt) = &self.prompt {
+            return format!("/{prompt}");
+        }
+        if let Some(message) = &self.message {
+            return message.clone();
+        }
+        let last = (self.top + self.height).min(self.lines.len());
+        let total = if self.truncated {
+            format!("{}+", self.lines.len())
+        } else {
+            self.lines.len().to_string()
+        };
+        let end = if !self.truncated && last == self.lines.len() {
+            " (END)"
+        } else {
+            ""
+        };
+        format!(
+            "{}  lines {}-{last} of {total}{end}  q quits, / searches",
+            self.name,
+            (self.top + 1).min(last.max(1)),
+        )
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame<'_>, theme: &ThemeTokens) {
+        let area = frame.area();
+        self.resize(area.width, area.height);
+        let page: Vec<Line> = self
+            .lines
+            .iter()
+            .skip(self.top)
+            .take(self.height)
+            .map(|line| styled_preview_segments(line, area.width, theme))
+            .collect();
+        let body = Rect {
+            height: area.height.saturating_sub(1),
+            ..area
+        };
+        frame.render_widget(Paragraph::new(page).style(theme.plain), body);
+        let status = Rect {
+            y: area.y + body.height,
+            height: area.height.min(1),
+            ..area
+        };
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(self.status(), theme.status_bg))),
+            status,
+        );
+        if let Some(prompt) = &self.prompt {
+            let x = status.x + 1 + prompt.chars().count() as u16;
+            frame.set_cursor_position((x.min(area.right().saturating_sub(1)), status.y));
+        }
+    }
+
+    /// Page through the document until `q`, in the alternate screen.
+    pub fn run(
+        &mut self,
+        theme: ThemeChoice,
+        no_color: bool,
+        overrides: &ThemeOverrides,
+    ) -> Result<()> {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        for signal in [SIGINT, SIGTERM] {
+            signal_hook::flag::register(signal, Arc::clone(&shutdown))?;
+        }
+        let _guard = TerminalGuard::enter(true)?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+        let choice = if no_color {
+            theme
+        } else {
+            resolve_auto(theme, detect_terminal_background())
+        };
+        let theme = build_theme(choice, no_color, Some(overrides));
+
+        let mut running = true;
+        while running && !shutdown.load(Ordering::Relaxed) {
+            terminal.draw(|frame| self.draw(frame, &theme))?;
+            match next_terminal_input(event::poll, event::read)? {
+                Some(InputEvent::Key(key)) => self.handle_key(key, &mut running),
+                Some(InputEvent::Scroll { direction, .. }) => {
+                    self.scroll(direction as isize * WHEEL_LINES as isize);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    use super::{FIRST_BUDGET, Pager};
+    use crate::ui::theme::build_theme;
+
+    fn press(pager: &mut Pager, code: KeyCode) -> bool {
+        let mut running = true;
+        pager.handle_key(KeyEvent::new(code, KeyModifiers::NONE), &mut running);
+        running
+    }
+
+    fn type_search(pager: &mut Pager, query: &str) {
+        press(pager, KeyCode::Char('/'));
+        for c in query.chars() {
+            press(pager, KeyCode::Char(c));
+        }
+        press(pager, KeyCode::Enter);
+    }
+
+    /// `count` paragraphs, every tenth one saying "needle".
+    fn document(count: usize) -> String {
+        (0..count)
+            .map(|index| {
+                if index % 10 == 5 {
+                    format!("Paragraph {index} has a Needle.\n\n")
+                } else {
+                    format!("Paragraph {index}.\n\n")
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn search_moves_matches_to_the_top_both_ways() {
+        let mut pager = Pager::new("doc.md", document(40));
+        pager.resize(60, 11);
+        type_search(&mut pager, "needle");
+        // Each paragraph renders as one line.
+        assert_eq!(pager.top, 5);
+        assert_eq!(pager.message.as_deref(), Some("/needle: line 6"));
+        press(&mut pager, KeyCode::Char('n'));
+        assert_eq!(pager.top, 15);
+        press(&mut pager, KeyCode::Char('n'));
+        assert_eq!(pager.top, 25);
+        press(&mut pager, KeyCode::Char('N'));
+        assert_eq!(pager.top, 15);
+
+        // Capitals make the search match case.
+        type_search(&mut pager, "NEEDLE");
+        assert_eq!(pager.top, 15);
+        assert_eq!(pager.message.as_deref(), Some("Pattern not found: NEEDLE"));
+        type_search(&mut pager, "Needle");
+        assert_eq!(pager.top, 25);
+        // The last match cannot scroll past the end of the document.
+        press(&mut pager, KeyCode::Char('n'));
+        assert_eq!(pager.top, 30);
+        assert_eq!(pager.message.as_deref(), Some("/Needle: line 36"));
+    }
+
+    #[test]
+    fn g_and_shift_g_reach_both_ends_rendering_only_what_they_need() {
+        let mut pager = Pager::new("doc.md", document(1000));
+        pager.resize(60, 21);
+        assert!(pager.truncated);
+        assert_eq!(pager.lines.len(), FIRST_BUDGET);
+
+        press(&mut pager, KeyCode::PageDown);
+        assert_eq!(pager.top, 20);
+        press(&mut pager, KeyCode::Char('G'));
+        assert!(!pager.truncated);
+        assert_eq!(pager.lines.len(), 1000);
+        assert_eq!(pager.top, 980);
+        press(&mut pager, KeyCode::Down);
+        assert_eq!(pager.top, 980);
+        assert!(pager.status().contains("(END)"), "{}", pager.status());
+
+        press(&mut pager, KeyCode::Char('g'));
+        assert_eq!(pager.top, 0);
+        press(&mut pager, KeyCode::Up);
+        assert_eq!(pager.top, 0);
+        press(&mut pager, KeyCode::End);
+        press(&mut pager, KeyCode::Home);
+        assert_eq!(pager.top, 0);
+    }
+
+    #[test]
+    fn a_search_past_the_rendered_lines_renders_more() {
+        let mut text = document(2000);
+        text.push_str("The last word.\n");
+        let mut pager = Pager::new("doc.md", text);
+        pager.resize(60, 21);
+        assert!(pager.truncated);
+        type_search(&mut pager, "last word");
+        assert_eq!(pager.top, pager.lines.len() - 20);
+        assert!(!pager.truncated);
+    }
+
+    #[test]
+    fn q_quits_unless_typed_into_the_prompt() {
+        let mut pager = Pager::new("doc.md", document(3));
+        pager.resize(40, 10);
+        press(&mut pager, KeyCode::Char('/'));
+        assert!(press(&mut pager, KeyCode::Char('q')));
+        assert!(press(&mut pager, KeyCode::Esc));
+        assert!(!press(&mut pager, KeyCode::Char('q')));
+        let mut running = true;
+        pager.handle_key(
+            KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            &mut running,
+        );
+        assert!(!running);
+    }
+
+    #[test]
+    fn the_page_shows_the_rendered_document_and_a_status_line() {
+        let mut pager = Pager::new("doc.md", "# Title\n\nSome **bold** text.\n".into());
+        let theme = build_theme(crate::app::ThemeChoice::Default, false, None);
+        let mut terminal = Terminal::new(TestBackend::new(40, 5)).expect("terminal");
+        terminal
+            .draw(|frame| pager.draw(frame, &theme))
+            .expect("draw");
+        let rows: Vec<String> = (0..5)
+            .map(|y| {
+                (0..40)
+                    .map(|x| terminal.backend().buffer()[(x, y)].symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(rows[0], "# Title");
+        assert_eq!(rows[1], "Some bold text.");
+        assert_eq!(rows[2], "");
+        assert_eq!(rows[4], "doc.md  lines 1-2 of 2 (END)  q quits, /");
+    }
+}
@@ -0,0 +1,182 @@
+use mdv_core::{ConflictHunk, HunkKind, PreviewLine, SegmentKind};
+use ratatui::text::{Line, Span};
+
+use super::styled_preview_segments;
+use crate::ui::theme::{ThemeTokens, style_for_segment};
+
+/// Which side of a conflict or review block a row belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ConflictSide {
+    Local,
+    External,
+    /// The file on disk, in review mode.
+    Disk,
+}
+
+impl ConflictSide {
+    fn label(self) -> &'static str {
+        match self {
+            ConflictSide::Local => "Local",
+            ConflictSide::External => "External",
+            ConflictSide::Disk => "Disk",
+        }
+    }
+
+    fn segment_kind(self) -> SegmentKind {
+        match self {
+            ConflictSide::Local => SegmentKind::ConflictLocal,
+            ConflictSide::External | ConflictSide::Disk => SegmentKind::ConflictExternal,
+        }
+    }
+}
+
+/// One row of the preview pane. Conflict and review rows say what they are,
+/// so drawing and scrolling never go by their text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum PreviewLineKind {
+    /// A rendered line of the document.
+    Markdown(PreviewLine),
+    /// The line above one side of a hunk; no line number when the whole
+    /// file is shown.
+    ConflictHeader {
+        side: ConflictSide,
+        line_no: Option<usize>,
+        selected: bool,
+        note: &'static str,
+    },
+    /// A source line of one side, or `None` when that side has no lines.
+    ConflictBody {
+        side: ConflictSide,
+        text: Option<String>,
+    },
+    /// Unchanged source around a hunk.
+    ConflictContext(String),
+    Blank,
+}
+
+impl PreviewLineKind {
+    /// The row as plain text, which is what preview search matches.
+    pub(super) fn text(&self) -> String {
+        match self {
+            PreviewLineKind::Markdown(line) => line.text(),
+            PreviewLineKind::ConflictHeader {
+                side,
+                line_no,
+                selected,
+                note,
+            } => {
+                let (open, close) = if *selected {
+                    (">>>", "<<<")
+                } else {
+                    ("---", "---")
+                };
+                let at = line_no.map(|line| format!(" @L{line}")).unwrap_or_default();
+                format!("{open} {} block{at}{note} {close}", side.label())
+            }
+            PreviewLineKind::ConflictBody {
+                text: Some(text), ..
+            } => text.clone(),
+            PreviewLineKind::ConflictBody { side, text: None } => {
+                format!("(no {} lines)", side.label().to_lowercase())
+            }
+            PreviewLineKind::ConflictContext(text) => text.clone(),
+            PreviewLineKind::Blank => String::new(),
+        }
+    }
+
+    /// Conflict rows sit below the document and, like
+    /// [`PreviewLine::new`], count as source line 0.
+    pub(super) fn source_line(&self) -> usize {
+        match self {
+            PreviewLineKind::Markdown(line) => line.source_line,
+            _ => 0,
+        }
+    }
+
+    pub(super) fn styled(&self, width: u16, theme: &ThemeTokens) -> Line<'static> {
+        let kind = match self {
+            PreviewLineKind::Markdown(line) => return styled_preview_segments(line, width, theme),
+            PreviewLineKind::Blank => return Line::default(),
+            PreviewLineKind::ConflictHeader { side, .. } => side.segment_kind(),
+            PreviewLineKind::ConflictBody { .. } => SegmentKind::Plain,
+            PreviewLineKind::ConflictContext(_) => SegmentKind::ConflictContext,
+        };
+        Line::from(Span::styled(self.text(), style_for_segment(theme, kind)))
+    }
+}
+
+/// The preview row of the selected hunk's header, which the preview keeps
+/// in view.
+pub(super) fn selected_hunk_row(lines: &[PreviewLineKind]) -> Option<usize> {
+    lines
+        .iter()
+        .position(|line| matches!(line, PreviewLineKind::ConflictHeader { selected: true, .. }))
+}
+
+/// Both whole texts, for a conflict that has no hunks to show.
+pub(super) fn push_whole_sides(lines: &mut Vec<PreviewLineKind>, local: &str, external: &str) {
+    for (side, text) in [
+        (ConflictSide::Local, local),
+        (ConflictSide::External, external),
+    ] {
+        lines.push(PreviewLineKind::ConflictHeader {
+            side,
+            line_no: None,
+            selected: false,
+            note: "",
+        });
+        lines.extend(text.lines().map(|line| PreviewLineKind::ConflictBody {
+            side,
+            text: Some(line.to_string()),
+        }));
+    }
+}
+
+/// The stacked blocks of each hunk, local side first, with its context
+/// dimmed around it.
+pub(super) fn push_hunk_blocks(
+    lines: &mut Vec<PreviewLineKind>,
+    hunks: &[ConflictHunk],
+    selected: usize,
+    other: ConflictSide,
+) {
+    let context = |text: &String| PreviewLineKind::ConflictContext(text.clone());
+    for (idx, hunk) in hunks.iter().enumerate() {
+        lines.extend(hunk.context_before.iter().map(context));
+        for (side, start, body, note) in [
+            (
+                ConflictSide::Local,
+                hunk.local_start,
+                &hunk.local_lines,
+                hunk_note(hunk),
+            ),
+            (other, hunk.external_start, &hunk.external_lines, ""),
+        ] {
+            lines.push(PreviewLineKind::ConflictHeader {
+                side,
+                line_no: Some(start + 1),
+                selected: idx == selected,
+                note,
+            });
+            if body.is_empty() {
+                lines.push(PreviewLineKind::ConflictBody { side, text: None });
+            }
+            lines.extend(body.iter().map(|line| PreviewLineKind::ConflictBody {
+                side,
+                text: Some(line.clone()),
+            }));
+        }
+        lines.extend(hunk.context_after.iter().map(context));
+        lines.push(PreviewLineKind::Blank);
+    }
+}
+
+/// What the stacked conflict view says after a block's line number.
+fn hunk_note(hunk: &ConflictHunk) -> &'static str {
+    match hunk.kind {
+        HunkKind::Move if hunk.local_lines.is_empty() => " (moved here)",
+        HunkKind::Move => " (moved away)",
+        _ if hunk.is_whitespace_only() => " (whitespace only)",
+        _ => "",
+    }
+}
@@ -0,0 +1,151 @@
+use std::io;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use mdv_core::{ConflictHunk, compute_conflict_hunks, read_text};
+
+use super::App;
+use super::keymap::Command;
+use super::status::Severity;
+
+/// The changes `Ctrl+Shift+D` lists: the buffer against the file on disk,
+/// computed once when the review opens.
+pub(super) struct Review {
+    pub(super) hunks: Vec<ConflictHunk>,
+    pub(super) selected: usize,
+    /// Lines only the buffer has.
+    pub(super) added: usize,
+    /// Lines only the file on disk has.
+    pub(super) removed: usize,
+}
+
+impl Review {
+    /// `disk` is `None` when there is no file yet, so every line is added.
+    pub(super) fn new(local: &str, disk: Option<&str>) -> Self {
+        let mut hunks = compute_conflict_hunks(local, disk.unwrap_or_default());
+        if disk.is_none() {
+            // An empty string still splits into one empty line; a missing
+            // file has none.
+            for hunk in &mut hunks {
+                hunk.external_lines.clear();
+            }
+        }
+        let added = hunks.iter().map(|hunk| hunk.local_lines.len()).sum();
+        let removed = hunks.iter().map(|hunk| hunk.external_lines.len()).sum();
+        Self {
+            hunks,
+            selected: 0,
+            added,
+            removed,
+        }
+    }
+
+    /// What the info line shows while reviewing.
+    pub(super) fn summary(&self) -> String {
+        format!("+{} −{} lines", self.added, self.removed)
+    }
+}
+
+impl App {
+    /// `Ctrl+Shift+D`: compare the buffer with the file on disk in the
+    /// preview, read-only, until `Esc`.
+    pub(super) fn open_review(&mut self) {
+        if self.stream_mode {
+            self.push_status(Severity::Warn, "Stream mode: nothing on disk to compare");
+            return;
+        }
+        if self.editor.is_conflicted() {
+            self.push_status(
+                Severity::Warn,
+                "Resolve the conflict first: it already shows the disk version",
+            );
+            return;
+        }
+        let Some(path) = &self.path else {
+            self.push_status(Severity::Warn, "Not saved yet: nothing on disk to compare");
+            return;
+        };
+        let disk = match read_text(path) {
+            Ok(file) => Some(file.text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                self.push_status(
+                    Severity::Error,
+                    format!("Cannot read the file on disk ({err})"),
+                );
+                return;
+            }
+        };
+        let review = Review::new(self.editor.text(), disk.as_deref());
+        if review.hunks.is_empty() {
+            self.push_status(Severity::Warn, "No changes against the file on disk");
+            return;
+        }
+        self.push_status(
+            Severity::Info,
+            format!(
+                "Reviewing {} change{} against disk: Ctrl+J/Ctrl+U move | Esc close",
+                review.hunks.len(),
+                super::plural(review.hunks.len())
+            ),
+        );
+        self.review = Some(review);
+        self.preview_scroll = 0;
+    }
+
+    pub(super) fn handle_review_key(&mut self, key: KeyEvent, running: &mut bool) {
+        let Some(review) = self.review.as_mut() else {
+            return;
+        };
+        let len = review.hunks.len();
+        if key.code == KeyCode::Esc {
+            self.close_review();
+            return;
+        }
+        match self.keymap.command(key) {
+            Some(Command::Quit) => {
+                self.review = None;
+                self.request_quit(running);
+            }
+            Some(Command::Review) => self.close_review(),
+            Some(Command::ConflictNextHunk) => {
+                review.selected = (review.selected + 1) % len;
+                let text = format!("Change {}/{len}", review.selected + 1);
+                self.push_status(Severity::Info, text);
+            }
+            Some(Command::ConflictPrevHunk) => {
+                review.selected = review.selected.checked_sub(1).unwrap_or(len - 1);
+                let text = format!("Change {}/{len}", review.selected + 1);
+                self.push_status(Severity::Info, text);
+            }
+            _ => self.push_status(
+                Severity::Warn,
+                "Review is read-only: Esc returns to editing",
+            ),
+        }
+    }
+
+    fn close_review(&mut self) {
+        self.review = None;
+        self.preview_scroll = 0;
+        self.push_status(Severity::Info, "Review closed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Review;
+
+    #[test]
+    fn counts_lines_on_each_side() {
+        let review = Review::new("a\nB\nc\nd\nnew\n", Some("a\nb\nc\nd\n"));
+        assert_eq!(review.hunks.len(), 2);
+        assert_eq!((review.added, review.removed), (2, 1));
+        assert_eq!(review.summary(), "+2 −1 lines");
+
+        let review = Review::new("one\ntwo\n", None);
+        assert_eq!(review.hunks.len(), 1);
+        assert_eq!(review.summary(), "+2 −0 lines");
+
+        assert!(Review::new("same\n", Some("same\n")).hunks.is_empty());
+    }
+}
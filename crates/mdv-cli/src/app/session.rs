@@ -0,0 +1,358 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+use std::time::Instant;
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use mdv_core::{EditorBuffer, LoadedText, PreviewRenderer};
+use mdv_watch::{WatchMessage, WatchOptions, WatcherHandle, watch_file};
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use super::keymap::Command;
+use super::status::Severity;
+use super::{App, PreviewCache, centered_popup, invalid_utf8_status};
+use crate::ui::theme::ThemeTokens;
+
+/// State of an open file that is not on screen. The file on screen keeps the
+/// same fields directly on [`App`]; switching files swaps them.
+pub(super) struct FileSession {
+    path: Option<PathBuf>,
+    editor: EditorBuffer,
+    watcher: Option<WatcherHandle>,
+    watch_rx: Option<Receiver<WatchMessage>>,
+    editor_scroll: usize,
+    editor_hscroll: usize,
+    preview_scroll: usize,
+    preview_source_top: usize,
+    selected_conflict_hunk: usize,
+    preview_cache: Option<PreviewCache>,
+    preview_renderer: PreviewRenderer,
+    last_saved_text: Option<String>,
+    missing_since: Option<Instant>,
+    file_missing: bool,
+    before_external: Option<String>,
+    pending_external: Option<(String, Instant)>,
+}
+
+impl FileSession {
+    fn has_unsaved_edits(&self) -> bool {
+        self.editor.dirty && self.path.is_some()
+    }
+}
+
+/// Name shown for a file in the status bar and the file switcher.
+pub(super) fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+impl App {
+    /// Open another file after the ones already open, without showing it.
+    pub fn add_file(&mut self, path: PathBuf, file: LoadedText) -> Result<()> {
+        let (watcher, watch_rx) = if self.watch_enabled {
+            let (watcher, watch_rx) = watch_file(&path, WatchOptions::default())?;
+            (Some(watcher), Some(watch_rx))
+        } else {
+            (None, None)
+        };
+        let mut editor = EditorBuffer::new(file.text);
+        editor.set_line_ending(file.line_ending);
+        if file.invalid_sequences > 0 {
+            self.push_status(
+                Severity::Info,
+                format!(
+                    "{}: {}",
+                    display_name(&path),
+                    invalid_utf8_status(file.invalid_sequences)
+                ),
+            );
+        }
+        self.parked_files.push(FileSession {
+            path: Some(path),
+            editor,
+            watcher,
+            watch_rx,
+            editor_scroll: 0,
+            editor_hscroll: 0,
+            preview_scroll: 0,
+            preview_source_top: 0,
+            selected_conflict_hunk: 0,
+            preview_cache: None,
+            preview_renderer: PreviewRenderer::new(),
+            last_saved_text: None,
+            missing_since: None,
+            file_missing: false,
+            before_external: None,
+            pending_external: None,
+        });
+        Ok(())
+    }
+
+    pub(super) fn file_count(&self) -> usize {
+        self.parked_files.len() + 1
+    }
+
+    /// Parked session of file `index`, which must not be the active one.
+    fn parked(&self, index: usize) -> &FileSession {
+        let slot = if index < self.active_file {
+            index
+        } else {
+            index - 1
+        };
+        &self.parked_files[slot]
+    }
+
+    pub(super) fn file_label(&self, index: usize) -> String {
+        let path = if index == self.active_file {
+            self.path.as_deref()
+        } else {
+            self.parked(index).path.as_deref()
+        };
+        path.map(display_name).unwrap_or_else(|| "<new>".into())
+    }
+
+    pub(super) fn file_has_unsaved_edits(&self, index: usize) -> bool {
+        if index == self.active_file {
+            self.editor.dirty && self.path.is_some()
+        } else {
+            self.parked(index).has_unsaved_edits()
+        }
+    }
+
+    fn take_session(&mut self) -> FileSession {
+        FileSession {
+            path: self.path.take(),
+            editor: std::mem::replace(&mut self.editor, EditorBuffer::new(String::new())),
+            watcher: self._watcher.take(),
+            watch_rx: self.watch_rx.take(),
+            editor_scroll: self.editor_scroll,
+            editor_hscroll: self.editor_hscroll,
+            preview_scroll: self.preview_scroll,
+            preview_source_top: self.preview_source_top,
+            selected_conflict_hunk: self.selected_conflict_hunk,
+            preview_cache: self.preview_cache.take(),
+            preview_renderer: std::mem::take(&mut self.preview_renderer),
+            last_saved_text: self.last_saved_text.take(),
+            missing_since: self.missing_since.take(),
+            file_missing: std::mem::take(&mut self.file_missing),
+            before_external: self.before_external.take(),
+            pending_external: self.pending_external.take(),
+        }
+    }
+
+    fn restore_session(&mut self, session: FileSession) {
+        self.path = session.path;
+        self.editor = session.editor;
+        self._watcher = session.watcher;
+        self.watch_rx = session.watch_rx;
+        self.editor_scroll = session.editor_scroll;
+        self.editor_hscroll = session.editor_hscroll;
+        self.preview_scroll = session.preview_scroll;
+        self.preview_source_top = session.preview_source_top;
+        self.selected_conflict_hunk = session.selected_conflict_hunk;
+        self.remember_conflict_selection();
+        self.preview_cache = session.preview_cache;
+        self.preview_renderer = session.preview_renderer;
+        self.last_saved_text = session.last_saved_text;
+        self.missing_since = session.missing_since;
+        self.file_missing = session.file_missing;
+        self.before_external = session.before_external;
+        self.pending_external = session.pending_external;
+        self.outline_mode = false;
+        self.review = None;
+        self.mouse_drag_anchor = None;
+        self.reset_text_caches();
+        self.changed_link = None;
+        self.jump_stack.clear();
+        self.sync_link_watches();
+    }
+
+    pub(super) fn switch_to_file(&mut self, index: usize) {
+        if index == self.active_file || index >= self.file_count() {
+            return;
+        }
+        let current = self.take_session();
+        self.parked_files.insert(self.active_file, current);
+        let next = self.parked_files.remove(index);
+        self.restore_session(next);
+        self.active_file = index;
+        self.push_status(Severity::Info, self.file_position());
+    }
+
+    /// `[2/3] notes.md`, for the status line.
+    pub(super) fn file_position(&self) -> String {
+        format!(
+            "[{}/{}] {}",
+            self.active_file + 1,
+            self.file_count(),
+            self.file_label(self.active_file)
+        )
+    }
+
+    fn cycle_file(&mut self, forward: bool) {
+        let count = self.file_count();
+        if count == 1 {
+            self.push_status(Severity::Info, "Only one file open");
+            return;
+        }
+        let next = if forward {
+            (self.active_file + 1) % count
+        } else {
+            (self.active_file + count - 1) % count
+        };
+        self.switch_to_file(next);
+    }
+
+    /// Close the file on screen and show its neighbour. Unsaved edits need a
+    /// second `Ctrl+W` to be dropped.
+    fn close_active_file(&mut self, confirmed: bool) {
+        if self.file_count() == 1 {
+            self.push_status(Severity::Info, "Only one file open (Ctrl+Q quits)");
+            return;
+        }
+        if self.file_has_unsaved_edits(self.active_file) && !self.readonly && !confirmed {
+            self.close_armed = true;
+            self.push_status(
+                Severity::Warn,
+                format!(
+                    "Unsaved changes in {}: Ctrl+S saves, Ctrl+W again closes without saving",
+                    self.file_label(self.active_file)
+                ),
+            );
+            return;
+        }
+
+        let closed = self.file_label(self.active_file);
+        let next = self.active_file.min(self.parked_files.len() - 1);
+        let session = self.parked_files.remove(next);
+        // Dropping the session stops its watcher.
+        drop(self.take_session());
+        self.restore_session(session);
+        self.active_file = next;
+        self.push_status(
+            Severity::Info,
+            format!("Closed {closed} | {}", self.file_position()),
+        );
+    }
+
+    /// Keys that manage open files; returns whether `key` was one of them.
+    pub(super) fn handle_file_key(&mut self, key: KeyEvent) -> bool {
+        let close_armed = std::mem::take(&mut self.close_armed);
+        match self.keymap.command(key) {
+            Some(Command::NextFile) => self.cycle_file(true),
+            Some(Command::PrevFile) => self.cycle_file(false),
+            Some(Command::CloseFile) => self.close_active_file(close_armed),
+            Some(Command::FileSwitcher) => {
+                self.file_switcher = Some(self.active_file);
+                self.push_status(Severity::Info, format!("Open files: {}", self.file_count()));
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    pub(super) fn handle_file_switcher_key(&mut self, key: KeyEvent, running: &mut bool) {
+        let Some(selected) = self.file_switcher else {
+            return;
+        };
+        let last = self.file_count() - 1;
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
+                self.file_switcher = None;
+                self.request_quit(running);
+            }
+            _ if key.code == KeyCode::Esc
+                || self.keymap.command(key) == Some(Command::FileSwitcher) =>
+            {
+                self.file_switcher = None;
+                self.push_status(Severity::Info, "File switcher closed");
+            }
+            (KeyCode::Up, _) => self.file_switcher = Some(selected.saturating_sub(1)),
+            (KeyCode::Down, _) => self.file_switcher = Some((selected + 1).min(last)),
+            (KeyCode::Home, _) => self.file_switcher = Some(0),
+            (KeyCode::End, _) => self.file_switcher = Some(last),
+            (KeyCode::Enter, _) => {
+                self.file_switcher = None;
+                self.switch_to_file(selected);
+                self.push_status(Severity::Info, self.file_position());
+            }
+            _ => {}
+        }
+    }
+
+    /// Walk the open files from `from` and ask about the first one with
+    /// unsaved edits; quit once none are left.
+    pub(super) fn review_unsaved_files(&mut self, from: usize, running: &mut bool) {
+        let next = if self.readonly || self.stream_mode {
+            None
+        } else {
+            (from..self.file_count()).find(|&index| self.file_has_unsaved_edits(index))
+        };
+        let Some(index) = next else {
+            *running = false;
+            return;
+        };
+
+        self.switch_to_file(index);
+        self.quit_confirm_mode = true;
+        self.quit_review_next = index + 1;
+        let file = if self.file_count() > 1 {
+            format!(" in {}", self.file_label(index))
+        } else {
+            String::new()
+        };
+        self.push_status(
+            Severity::Warn,
+            format!(
+                "Unsaved changes{file}: [s]ave and quit / [q]uit without saving / [Esc] cancel"
+            ),
+        );
+    }
+
+    pub(super) fn draw_file_switcher(
+        &self,
+        frame: &mut Frame<'_>,
+        area: Rect,
+        theme: &ThemeTokens,
+    ) {
+        let Some(selected) = self.file_switcher else {
+            return;
+        };
+        let wanted = (self.file_count() as u16).saturating_add(2);
+        let popup = centered_popup(60, wanted.min(area.height.saturating_sub(2)), area);
+        frame.render_widget(Clear, popup);
+
+        let visible = popup.height.saturating_sub(2).max(1) as usize;
+        let scroll = selected.saturating_sub(visible - 1);
+        let lines = (0..self.file_count())
+            .skip(scroll)
+            .take(visible)
+            .map(|index| {
+                let dirty = if self.file_has_unsaved_edits(index) {
+                    " [+]"
+                } else {
+                    ""
+                };
+                let style = if index == selected {
+                    theme.selection
+                } else {
+                    theme.plain
+                };
+                let text = format!(" {}. {}{dirty}", index + 1, self.file_label(index));
+                Line::from(Span::styled(text, style))
+            })
+            .collect::<Vec<_>>();
+
+        let switcher = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Open files")
+                .border_style(theme.pane_focus),
+        );
+        frame.render_widget(switcher, popup);
+    }
+}
@@ -0,0 +1,389 @@
+use std::borrow::Cow;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use super::keymap::Command;
+use super::status::Severity;
+use super::{App, centered_popup};
+use crate::ui::theme::ThemeTokens;
+
+/// Where the cursor goes in a snippet body; without one it ends up after
+/// the inserted text.
+pub const CURSOR_MARKER: &str = "$CURSOR";
+/// Largest table the size prompt builds.
+const MAX_TABLE_ROWS: usize = 100;
+const MAX_TABLE_COLS: usize = 20;
+/// What an empty answer to the size prompt means.
+const DEFAULT_TABLE_SIZE: (usize, usize) = (2, 3);
+
+/// What picking a snippet inserts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Template {
+    /// Text as is, with an optional [`CURSOR_MARKER`].
+    Text(Cow<'static, str>),
+    /// A fenced code block; asks for the language first.
+    CodeFence,
+    /// A table; asks for its size first.
+    Table,
+}
+
+/// One entry of the `Ctrl+.` palette.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    pub name: Cow<'static, str>,
+    pub template: Template,
+}
+
+impl Snippet {
+    /// A `[snippets]` entry from the config file.
+    pub fn text(name: String, body: String) -> Self {
+        Self {
+            name: Cow::Owned(name),
+            template: Template::Text(Cow::Owned(body)),
+        }
+    }
+
+    const fn builtin(name: &'static str, template: Template) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            template,
+        }
+    }
+}
+
+/// The snippets mdv ships with, listed before the config file's own.
+pub const BUILTIN: &[Snippet] = &[
+    Snippet::builtin("Code block", Template::CodeFence),
+    Snippet::builtin("Table", Template::Table),
+    Snippet::builtin("Link", Template::Text(Cow::Borrowed("[$CURSOR](https://)"))),
+    Snippet::builtin(
+        "Image",
+        Template::Text(Cow::Borrowed("![$CURSOR](image.png)")),
+    ),
+    Snippet::builtin("Task item", Template::Text(Cow::Borrowed("- [ ] $CURSOR"))),
+    Snippet::builtin(
+        "Details",
+        Template::Text(Cow::Borrowed(
+            "<details>\n<summary>$CURSOR</summary>\n\n\n\n</details>\n",
+        )),
+    ),
+];
+
+/// `body` without its first [`CURSOR_MARKER`], and the byte offset the
+/// marker was at.
+pub fn place_cursor(body: &str) -> (String, usize) {
+    match body.find(CURSOR_MARKER) {
+        Some(at) => {
+            let mut text = body.to_string();
+            text.replace_range(at..at + CURSOR_MARKER.len(), "");
+            (text, at)
+        }
+        None => (body.to_string(), body.len()),
+    }
+}
+
+/// A fence for `language`, with the cursor on its empty first line.
+pub fn code_fence(language: &str) -> (String, usize) {
+    place_cursor(&format!("```{}\n{CURSOR_MARKER}\n```\n", language.trim()))
+}
+
+/// An empty table with a header row and `rows` body rows, with the cursor
+/// in the first header cell.
+pub fn table(rows: usize, cols: usize) -> (String, usize) {
+    let row = |cell: &str| format!("|{}\n", format!(" {cell} |").repeat(cols));
+    let mut text = row("   ");
+    text.push_str(&row("---"));
+    text.push_str(&row("   ").repeat(rows));
+    (text, "| ".len())
+}
+
+/// `2x3`, `2 x 3` or `2×3` as body rows and columns; empty means
+/// [`DEFAULT_TABLE_SIZE`].
+pub fn parse_table_size(text: &str) -> Option<(usize, usize)> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Some(DEFAULT_TABLE_SIZE);
+    }
+    let (rows, cols) = text.split_once(['x', 'X', '×', '*'])?;
+    let rows = rows
+        .trim()
+        .parse()
+        .ok()
+        .filter(|n| (1..=MAX_TABLE_ROWS).contains(n))?;
+    let cols = cols
+        .trim()
+        .parse()
+        .ok()
+        .filter(|n| (1..=MAX_TABLE_COLS).contains(n))?;
+    Some((rows, cols))
+}
+
+/// What the palette asks before a [`Template::CodeFence`] or
+/// [`Template::Table`] can be inserted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Question {
+    Language,
+    TableSize,
+}
+
+impl Question {
+    fn prompt(self) -> &'static str {
+        match self {
+            Question::Language => "Language (empty for none)",
+            Question::TableSize => "Rows x columns (empty for 2x3)",
+        }
+    }
+}
+
+/// The open `Ctrl+.` palette: what has been typed to filter it, the row
+/// selected among the matches, and the question being answered, if any.
+#[derive(Debug, Default)]
+pub(super) struct SnippetPalette {
+    query: String,
+    selected: usize,
+    question: Option<(Question, String)>,
+}
+
+impl App {
+    /// Built-in snippets, then the config file's.
+    fn snippets(&self) -> impl Iterator<Item = &Snippet> {
+        BUILTIN.iter().chain(&self.user_snippets)
+    }
+
+    /// The snippets whose name contains the typed filter, ignoring case.
+    fn matching_snippets(&self, query: &str) -> Vec<&Snippet> {
+        let query = query.to_lowercase();
+        self.snippets()
+            .filter(|snippet| snippet.name.to_lowercase().contains(&query))
+            .collect()
+    }
+
+    /// `Ctrl+.`: list snippets to insert at the cursor.
+    pub(super) fn open_snippet_palette(&mut self) {
+        if self.readonly {
+            self.push_status(Severity::Warn, "Readonly: edit disabled");
+            return;
+        }
+        self.snippet_palette = Some(SnippetPalette::default());
+        self.push_status(Severity::Info, "Snippets: type to filter");
+    }
+
+    pub(super) fn handle_snippet_palette_key(&mut self, key: KeyEvent, running: &mut bool) {
+        let command = self.keymap.command(key);
+        let last = self.snippet_palette.as_ref().map_or(0, |palette| {
+            self.matching_snippets(&palette.query)
+                .len()
+                .saturating_sub(1)
+        });
+        let Some(palette) = self.snippet_palette.as_mut() else {
+            return;
+        };
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
+                self.snippet_palette = None;
+                self.request_quit(running);
+            }
+            _ if key.code == KeyCode::Esc || command == Some(Command::Snippets) => {
+                self.snippet_palette = None;
+                self.push_status(Severity::Info, "Snippets closed");
+            }
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                match &mut palette.question {
+                    Some((_, answer)) => answer.push(c),
+                    None => {
+                        palette.query.push(c);
+                        palette.selected = 0;
+                    }
+                }
+            }
+            (KeyCode::Backspace, _) => match &mut palette.question {
+                Some((_, answer)) => {
+                    answer.pop();
+                }
+                None => {
+                    palette.query.pop();
+                    palette.selected = 0;
+                }
+            },
+            (KeyCode::Up, _) if palette.question.is_none() => {
+                palette.selected = palette.selected.saturating_sub(1);
+            }
+            (KeyCode::Down, _) if palette.question.is_none() => {
+                palette.selected = (palette.selected + 1).min(last);
+            }
+            (KeyCode::Enter, _) => self.choose_snippet(),
+            _ => {}
+        }
+    }
+
+    /// Insert the selected snippet, or ask what it needs first.
+    fn choose_snippet(&mut self) {
+        let Some(palette) = self.snippet_palette.as_mut() else {
+            return;
+        };
+        if let Some((question, answer)) = palette.question.take() {
+            let inserted = match question {
+                Question::Language => Some(code_fence(&answer)),
+                Question::TableSize => {
+                    parse_table_size(&answer).map(|(rows, cols)| table(rows, cols))
+                }
+            };
+            match inserted {
+                Some((text, cursor)) => {
+                    self.snippet_palette = None;
+                    self.insert_snippet(&text, cursor, true);
+                }
+                None => {
+                    palette.question = Some((question, answer));
+                    self.push_status(
+                        Severity::Warn,
+                        format!(
+                            "Table size looks like 2x3 (at most {MAX_TABLE_ROWS}x{MAX_TABLE_COLS})"
+                        ),
+                    );
+                }
+            }
+            return;
+        }
+
+        let query = palette.query.clone();
+        let selected = palette.selected;
+        let Some(template) = self
+            .matching_snippets(&query)
+            .get(selected)
+            .map(|snippet| snippet.template.clone())
+        else {
+            self.push_status(Severity::Warn, format!("No snippet matches {query:?}"));
+            return;
+        };
+        let question = match template {
+            Template::Text(body) => {
+                self.snippet_palette = None;
+                let (text, cursor) = place_cursor(&body);
+                self.insert_snippet(&text, cursor, text.contains('\n'));
+                return;
+            }
+            Template::CodeFence => Question::Language,
+            Template::Table => Question::TableSize,
+        };
+        if let Some(palette) = self.snippet_palette.as_mut() {
+            palette.question = Some((question, String::new()));
+        }
+        self.push_status(Severity::Info, format!("{}, then Enter", question.prompt()));
+    }
+
+    /// Insert `text` at the cursor as one undo step and put the cursor
+    /// `cursor` bytes into it. A block starts on a line of its own.
+    fn insert_snippet(&mut self, text: &str, cursor: usize, block: bool) {
+        let (_, col) = self.editor.line_col_at_cursor();
+        let (text, cursor) = if block && col > 0 {
+            (format!("\n{text}"), cursor + 1)
+        } else {
+            (text.to_string(), cursor)
+        };
+        self.clear_selection();
+        let start = self.editor.cursor();
+        self.editor.insert_str(&text);
+        self.editor.set_cursor(start + cursor);
+        self.sync_conflict_hunk_selection();
+        self.ensure_cursor_visible();
+        self.push_status(Severity::Info, "Snippet inserted");
+    }
+
+    pub(super) fn draw_snippet_palette(
+        &self,
+        frame: &mut Frame<'_>,
+        area: Rect,
+        theme: &ThemeTokens,
+    ) {
+        let Some(palette) = &self.snippet_palette else {
+            return;
+        };
+        let (input, title) = match &palette.question {
+            Some((question, answer)) => (format!(" {}: {answer}", question.prompt()), "Snippet"),
+            None => (format!(" > {}", palette.query), "Snippets"),
+        };
+        let mut rows = Vec::new();
+        if palette.question.is_none() {
+            let matches = self.matching_snippets(&palette.query);
+            if matches.is_empty() {
+                rows.push(Line::from(Span::styled(
+                    " No matching snippet",
+                    theme.plain,
+                )));
+            }
+            rows.extend(matches.iter().enumerate().map(|(index, snippet)| {
+                let style = if index == palette.selected {
+                    theme.selection
+                } else {
+                    theme.plain
+                };
+                Line::from(Span::styled(format!(" {}", snippet.name), style))
+            }));
+        }
+
+        // The input line stays on top while the rows below it scroll.
+        let wanted = (rows.len() as u16).saturating_add(3);
+        let popup = centered_popup(50, wanted.min(area.height.saturating_sub(2)), area);
+        frame.render_widget(Clear, popup);
+        let visible = popup.height.saturating_sub(3).max(1) as usize;
+        let scroll = palette.selected.saturating_sub(visible - 1);
+        let lines: Vec<Line> = std::iter::once(Line::from(Span::styled(input, theme.plain)))
+            .chain(rows.into_iter().skip(scroll))
+            .collect();
+        let list = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_style(theme.pane_focus),
+        );
+        frame.render_widget(list, popup);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BUILTIN, Template, code_fence, parse_table_size, place_cursor, table};
+
+    #[test]
+    fn a_two_by_three_table_has_a_header_separator_and_two_rows() {
+        let (text, cursor) = table(2, 3);
+        assert_eq!(
+            text,
+            "|     |     |     |\n\
+             | --- | --- | --- |\n\
+             |     |     |     |\n\
+             |     |     |     |\n"
+        );
+        assert_eq!(&text[..cursor], "| ");
+    }
+
+    #[test]
+    fn the_cursor_marker_is_removed_and_remembered() {
+        assert_eq!(place_cursor("[$CURSOR](url)"), ("[](url)".into(), 1));
+        assert_eq!(place_cursor("- [ ] "), ("- [ ] ".into(), 6));
+        assert_eq!(
+            place_cursor("$CURSOR and $CURSOR"),
+            (" and $CURSOR".into(), 0)
+        );
+        assert_eq!(code_fence(" rust "), ("```rust\n\n```\n".into(), 8));
+        assert!(BUILTIN.iter().all(|snippet| match &snippet.template {
+            Template::Text(body) => body.contains("$CURSOR"),
+            _ => true,
+        }));
+    }
+
+    #[test]
+    fn table_sizes_parse_with_a_default_and_limits() {
+        assert_eq!(parse_table_size(""), Some((2, 3)));
+        assert_eq!(parse_table_size("4x2"), Some((4, 2)));
+        assert_eq!(parse_table_size(" 3 × 5 "), Some((3, 5)));
+        assert_eq!(parse_table_size("0x2"), None);
+        assert_eq!(parse_table_size("2x21"), None);
+        assert_eq!(parse_table_size("two"), None);
+    }
+}
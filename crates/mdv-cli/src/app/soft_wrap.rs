@@ -0,0 +1,312 @@
+use ratatui::text::Span;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::{App, clamp_scroll};
+use crate::ui::render::slice_columns;
+
+/// Byte offsets where each visual row of `line` starts once it is wrapped
+/// at `width` cells; always starts with 0. Rows break after the last
+/// whitespace that fits and mid-word only when a word is wider than the
+/// row. A space that overflows hangs at the end of its row so the next row
+/// starts with the next word.
+pub(super) fn wrap_starts(line: &str, width: usize) -> Vec<usize> {
+    let mut starts = vec![0];
+    if width == 0 {
+        return starts;
+    }
+    let mut col = 0usize;
+    // Just past the last whitespace of the current row.
+    let mut word_start = None;
+    for (idx, ch) in line.char_indices() {
+        let cells = ch.width().unwrap_or(0);
+        if ch.is_whitespace() {
+            if col + cells > width && col > 0 {
+                starts.push(idx + ch.len_utf8());
+                col = 0;
+                word_start = None;
+            } else {
+                col += cells;
+                word_start = Some(idx + ch.len_utf8());
+            }
+            continue;
+        }
+        if col + cells > width && col > 0 {
+            let start = word_start.filter(|&start| start < idx).unwrap_or(idx);
+            starts.push(start);
+            col = line[start..idx].width();
+            word_start = None;
+        }
+        col += cells;
+    }
+    // A hanging space can end the line; it opens no row of its own.
+    if starts.len() > 1 && starts.last() == Some(&line.len()) {
+        starts.pop();
+    }
+    starts
+}
+
+/// The visual row byte `offset` of `line` is on and its display column in
+/// that row.
+pub(super) fn visual_position(line: &str, starts: &[usize], offset: usize) -> (usize, usize) {
+    let row = starts
+        .partition_point(|&start| start <= offset)
+        .saturating_sub(1);
+    (row, line[starts[row]..offset].width())
+}
+
+/// The byte offset in `line` under display column `col` of visual `row`. A
+/// column past the row's end stops on its last character, or at the end of
+/// the line on the last row.
+pub(super) fn offset_at_visual(line: &str, starts: &[usize], row: usize, col: usize) -> usize {
+    let row = row.min(starts.len() - 1);
+    let start = starts[row];
+    let end = starts.get(row + 1).copied();
+    let text = &line[start..end.unwrap_or(line.len())];
+    let mut cells = 0usize;
+    let mut last = None;
+    for (idx, ch) in text.char_indices() {
+        cells += ch.width().unwrap_or(0);
+        if cells > col {
+            return start + idx;
+        }
+        last = Some(idx);
+    }
+    match (end, last) {
+        (Some(_), Some(idx)) => start + idx,
+        _ => start + text.len(),
+    }
+}
+
+/// Cut a styled line into the rows starting at display columns `row_cols`.
+pub(super) fn wrap_spans(spans: Vec<Span<'static>>, row_cols: &[usize]) -> Vec<Vec<Span<'static>>> {
+    let mut rows = vec![Vec::new(); row_cols.len()];
+    let mut col = 0usize;
+    for span in spans {
+        let span_start = col;
+        col += span.width();
+        for (index, row) in rows.iter_mut().enumerate() {
+            let row_start = row_cols[index];
+            let row_end = row_cols.get(index + 1).copied().unwrap_or(usize::MAX);
+            if col <= row_start || span_start >= row_end {
+                continue;
+            }
+            let local_start = row_start.saturating_sub(span_start);
+            let local_width = row_end.min(col) - span_start.max(row_start);
+            let visible = slice_columns(&span.content, local_start, local_width).into_owned();
+            if !visible.is_empty() {
+                row.push(Span::styled(visible, span.style));
+            }
+        }
+    }
+    rows
+}
+
+/// Display columns where each of the rows `starts` found begins.
+pub(super) fn row_columns(line: &str, starts: &[usize]) -> Vec<usize> {
+    starts.iter().map(|&start| line[..start].width()).collect()
+}
+
+impl App {
+    /// `--wrap`: wrap long lines in the editor pane instead of scrolling
+    /// sideways.
+    pub fn set_soft_wrap(&mut self, on: bool) {
+        self.ui.soft_wrap = on;
+    }
+
+    /// The width editor lines wrap at, once the pane has been laid out with
+    /// wrapping on.
+    pub(super) fn wrap_width(&self) -> Option<usize> {
+        let width = self.editor_text_area.width as usize;
+        (self.ui.soft_wrap && width > 0).then_some(width)
+    }
+
+    fn line_wrap_starts(&self, line: usize, width: usize) -> Vec<usize> {
+        wrap_starts(self.editor.line_text(line), width)
+    }
+
+    /// The cursor's visual row and column, counted from the top of the
+    /// editor pane; `None` when it is above the pane.
+    pub(super) fn cursor_visual_position(&self, width: usize) -> Option<(usize, usize)> {
+        let (line, _) = self.editor.line_col_at_cursor();
+        let text = self.editor.line_text(line);
+        let starts = wrap_starts(text, width);
+        let (row, col) = visual_position(
+            text,
+            &starts,
+            self.editor.cursor() - self.editor.line_start(line),
+        );
+        let top = self.editor_scroll;
+        if line < top || (line == top && row < self.editor_wrap_skip) {
+            return None;
+        }
+        let above: usize = (top..line)
+            .map(|line| self.line_wrap_starts(line, width).len())
+            .sum();
+        Some((above + row - self.editor_wrap_skip, col))
+    }
+
+    /// `Up`/`Down` with wrapping on: move one visual row, keeping the
+    /// display column where the row is long enough.
+    pub(super) fn move_cursor_visual(&mut self, down: bool) {
+        let Some(width) = self.wrap_width() else {
+            if down {
+                self.editor.move_down();
+            } else {
+                self.editor.move_up();
+            }
+            return;
+        };
+        let (line, _) = self.editor.line_col_at_cursor();
+        let text = self.editor.line_text(line);
+        let starts = wrap_starts(text, width);
+        let (row, col) = visual_position(
+            text,
+            &starts,
+            self.editor.cursor() - self.editor.line_start(line),
+        );
+        let (line, row) = if down && row + 1 < starts.len() {
+            (line, row + 1)
+        } else if down && line + 1 < self.editor.text().lines().count() {
+            (line + 1, 0)
+        } else if !down && row > 0 {
+            (line, row - 1)
+        } else if !down && line > 0 {
+            (line - 1, self.line_wrap_starts(line - 1, width).len() - 1)
+        } else {
+            return;
+        };
+        let text = self.editor.line_text(line);
+        let offset = offset_at_visual(text, &wrap_starts(text, width), row, col);
+        self.editor
+            .set_cursor(self.editor.line_start(line) + offset);
+    }
+
+    /// Scroll so the cursor's visual row is in the pane. Lines are scrolled
+    /// in whole unless one is taller than the pane.
+    pub(super) fn ensure_cursor_visible_wrapped(&mut self, width: usize) {
+        self.editor_hscroll = 0;
+        let height = self.editor_height.max(1);
+        let (line, _) = self.editor.line_col_at_cursor();
+        let text = self.editor.line_text(line);
+        let starts = wrap_starts(text, width);
+        let (row, _) = visual_position(
+            text,
+            &starts,
+            self.editor.cursor() - self.editor.line_start(line),
+        );
+        let first_row = |rows: usize| if rows <= height { 0 } else { row };
+        if self.cursor_visual_position(width).is_none() {
+            self.editor_scroll = line;
+            self.editor_wrap_skip = first_row(starts.len());
+            return;
+        }
+        let mut top_rows = self.line_wrap_starts(self.editor_scroll, width).len();
+        self.editor_wrap_skip = self.editor_wrap_skip.min(top_rows - 1);
+        while let Some((visual_row, _)) = self.cursor_visual_position(width)
+            && visual_row >= height
+        {
+            if self.editor_scroll < line {
+                self.editor_scroll += 1;
+                self.editor_wrap_skip = 0;
+                top_rows = self.line_wrap_starts(self.editor_scroll, width).len();
+                if self.editor_scroll == line {
+                    self.editor_wrap_skip = first_row(top_rows).saturating_sub(height - 1);
+                }
+            } else {
+                self.editor_wrap_skip = row + 1 - height;
+            }
+        }
+    }
+
+    /// The line and byte offset in it under a click `rel_row` rows and
+    /// `rel_col` columns into the wrapped editor text.
+    pub(super) fn wrapped_position_at(
+        &self,
+        rel_row: usize,
+        rel_col: usize,
+        width: usize,
+    ) -> (usize, usize) {
+        let last = self.editor.line_count().saturating_sub(1);
+        let mut rel_row = rel_row + self.editor_wrap_skip;
+        for line in self.editor_scroll..=last {
+            let text = self.editor.line_text(line);
+            let starts = wrap_starts(text, width);
+            if rel_row < starts.len() || line == last {
+                return (line, offset_at_visual(text, &starts, rel_row, rel_col));
+            }
+            rel_row -= starts.len();
+        }
+        (last, self.editor.line_text(last).len())
+    }
+
+    /// Scroll the editor by whole lines, as the wheel and `PageUp`/`PageDown`
+    /// do, dropping any rows of a tall line scrolled past.
+    pub(super) fn set_editor_scroll(&mut self, scroll: usize) {
+        self.editor_scroll =
+            clamp_scroll(scroll, self.editor.line_count(), self.editor_height.max(1));
+        self.editor_wrap_skip = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::text::Span;
+
+    use super::{offset_at_visual, row_columns, visual_position, wrap_spans, wrap_starts};
+
+    #[test]
+    fn rows_break_after_spaces_and_inside_long_words() {
+        assert_eq!(wrap_starts("", 10), [0]);
+        assert_eq!(wrap_starts("short line", 10), [0]);
+        assert_eq!(wrap_starts("the quick brown fox", 10), [0, 10]);
+        assert_eq!(wrap_starts("abcdefghijkl", 5), [0, 5, 10]);
+        assert_eq!(wrap_starts("aaaa bbbb", 4), [0, 5]);
+        assert_eq!(wrap_starts("aaaa ", 4), [0]);
+        assert_eq!(wrap_starts("anything", 0), [0]);
+    }
+
+    #[test]
+    fn multibyte_and_wide_glyphs_wrap_by_cells() {
+        // "é" and "ö" are two bytes and one cell each.
+        let line = "héllo wörld again";
+        let starts = wrap_starts(line, 12);
+        assert_eq!(starts, [0, 14]);
+        assert_eq!(&line[starts[1]..], "again");
+        assert_eq!(visual_position(line, &starts, line.len()), (1, 5));
+        assert_eq!(visual_position(line, &starts, "héllo w".len()), (0, 7));
+
+        // Each CJK glyph is three bytes and two cells.
+        let line = "日本語のテキスト";
+        let starts = wrap_starts(line, 5);
+        assert_eq!(starts, [0, 6, 12, 18]);
+        assert_eq!(visual_position(line, &starts, 9), (1, 2));
+        assert_eq!(offset_at_visual(line, &starts, 1, 3), 9);
+        assert_eq!(offset_at_visual(line, &starts, 1, 0), 6);
+        assert_eq!(row_columns(line, &starts), [0, 4, 8, 12]);
+    }
+
+    #[test]
+    fn columns_past_a_row_stop_before_the_break() {
+        let line = "one two three";
+        let starts = wrap_starts(line, 8);
+        assert_eq!(starts, [0, 8]);
+        // Row 0 is "one two " and ends on its hanging space.
+        assert_eq!(offset_at_visual(line, &starts, 0, 20), 7);
+        assert_eq!(offset_at_visual(line, &starts, 1, 20), line.len());
+        assert_eq!(offset_at_visual(line, &starts, 1, 2), 10);
+        assert_eq!(visual_position(line, &starts, 8), (1, 0));
+    }
+
+    #[test]
+    fn spans_split_at_row_columns() {
+        let spans = vec![Span::raw("héllo "), Span::raw("wörld again")];
+        let rows = wrap_spans(spans, &[0, 12]);
+        let text = |row: &[Span]| {
+            row.iter()
+                .map(|span| span.content.as_ref())
+                .collect::<String>()
+        };
+        assert_eq!(text(&rows[0]), "héllo wörld ");
+        assert_eq!(text(&rows[1]), "again");
+    }
+}
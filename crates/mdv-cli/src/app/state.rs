@@ -9,11 +9,76 @@ pub enum ThemeChoice {
     Auto,
     Default,
     HighContrast,
+    Light,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HelpTab {
+    Settings,
+    Docs,
+}
+
+/// Rows of the settings tab, in display order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingItem {
+    Theme,
+    Colors,
+    Focus,
+    Watch,
+    ContinueLists,
+    LineNumbers,
+    SoftWrap,
+    ScrollSync,
+    Spell,
+    ReaderMode,
+    FrontMatter,
+}
+
+impl SettingItem {
+    pub const ALL: [SettingItem; 11] = [
+        SettingItem::Theme,
+        SettingItem::Colors,
+        SettingItem::Focus,
+        SettingItem::Watch,
+        SettingItem::ContinueLists,
+        SettingItem::LineNumbers,
+        SettingItem::SoftWrap,
+        SettingItem::ScrollSync,
+        SettingItem::Spell,
+        SettingItem::ReaderMode,
+        SettingItem::FrontMatter,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SettingItem::Theme => "Theme",
+            SettingItem::Colors => "Colors",
+            SettingItem::Focus => "Focus",
+            SettingItem::Watch => "Watch file",
+            SettingItem::ContinueLists => "Continue lists",
+            SettingItem::LineNumbers => "Line numbers",
+            SettingItem::SoftWrap => "Soft wrap",
+            SettingItem::ScrollSync => "Scroll sync",
+            SettingItem::Spell => "Spell check",
+            SettingItem::ReaderMode => "Reader mode",
+            SettingItem::FrontMatter => "Front matter",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsNav {
+    Up,
+    Down,
+    Next,
+    Prev,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HelpState {
     pub open: bool,
+    pub tab: HelpTab,
+    pub setting_idx: usize,
     pub index_focus: bool,
     pub section_idx: usize,
     pub scroll: usize,
@@ -24,6 +89,8 @@ impl Default for HelpState {
     fn default() -> Self {
         Self {
             open: false,
+            tab: HelpTab::Settings,
+            setting_idx: 0,
             index_focus: true,
             section_idx: 0,
             scroll: 0,
@@ -41,6 +108,7 @@ impl HelpState {
 
     pub fn open_onboarding(&mut self) {
         self.open = true;
+        self.tab = HelpTab::Docs;
         self.index_focus = false;
         self.section_idx = 0;
         self.scroll = 0;
@@ -57,6 +125,30 @@ impl HelpState {
     pub fn is_onboarding(&self) -> bool {
         self.onboarding_step.is_some()
     }
+
+    pub fn is_settings(&self) -> bool {
+        self.tab == HelpTab::Settings && !self.is_onboarding()
+    }
+
+    pub fn switch_tab(&mut self) {
+        self.tab = match self.tab {
+            HelpTab::Settings => HelpTab::Docs,
+            HelpTab::Docs => HelpTab::Settings,
+        };
+    }
+
+    pub fn selected_setting(&self) -> SettingItem {
+        SettingItem::ALL[self.setting_idx.min(SettingItem::ALL.len() - 1)]
+    }
+
+    pub fn move_setting(&mut self, nav: SettingsNav) {
+        let last = SettingItem::ALL.len() - 1;
+        self.setting_idx = match nav {
+            SettingsNav::Up => self.setting_idx.saturating_sub(1),
+            SettingsNav::Down => (self.setting_idx + 1).min(last),
+            SettingsNav::Next | SettingsNav::Prev => self.setting_idx.min(last),
+        };
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -133,12 +225,28 @@ pub fn apply_help_nav(
     help.scroll = help.scroll.min(max_scroll);
 }
 
+pub const DEFAULT_SPLIT_PERCENT: u16 = 50;
+/// Neither pane of the split view gets less than a fifth of the width.
+pub const MIN_SPLIT_PERCENT: u16 = 20;
+pub const MAX_SPLIT_PERCENT: u16 = 80;
+/// How far one `Ctrl+[` or `Ctrl+]` moves the divider.
+pub const SPLIT_STEP: u16 = 5;
+
 #[derive(Debug, Clone)]
 pub struct UiState {
     pub focus: PaneFocus,
     pub help: HelpState,
     pub theme: ThemeChoice,
     pub no_color: bool,
+    pub view_only: bool,
+    /// The focused pane fills the content area.
+    pub maximized: bool,
+    /// Share of the split view's width given to the editor, in percent.
+    pub split_percent: u16,
+    pub line_numbers: bool,
+    /// Wrap long lines in the editor pane instead of scrolling sideways.
+    pub soft_wrap: bool,
+    pub scroll_sync: bool,
 }
 
 impl Default for UiState {
@@ -148,13 +256,22 @@ impl Default for UiState {
             help: HelpState::default(),
             theme: ThemeChoice::Auto,
             no_color: false,
+            view_only: false,
+            maximized: false,
+            split_percent: DEFAULT_SPLIT_PERCENT,
+            line_numbers: true,
+            soft_wrap: false,
+            scroll_sync: false,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{HelpNavAction, HelpState, PaneFocus, ThemeChoice, UiState, apply_help_nav};
+    use super::{
+        HelpNavAction, HelpState, HelpTab, PaneFocus, SettingItem, SettingsNav, ThemeChoice,
+        UiState, apply_help_nav,
+    };
 
     #[test]
     fn default_ui_state_prefers_editor_mode() {
@@ -163,6 +280,32 @@ mod tests {
         assert_eq!(ui.theme, ThemeChoice::Auto);
         assert!(!ui.no_color);
         assert!(!ui.help.open);
+        assert!(ui.line_numbers);
+        assert!(!ui.soft_wrap);
+        assert!(!ui.scroll_sync);
+    }
+
+    #[test]
+    fn settings_tab_selection_stays_in_bounds() {
+        let mut help = HelpState::default();
+        help.open_docs();
+        assert!(help.is_settings());
+        assert_eq!(help.selected_setting(), SettingItem::Theme);
+
+        help.move_setting(SettingsNav::Up);
+        assert_eq!(help.setting_idx, 0);
+        for _ in 0..10 {
+            help.move_setting(SettingsNav::Down);
+        }
+        assert_eq!(help.selected_setting(), SettingItem::FrontMatter);
+
+        help.switch_tab();
+        assert_eq!(help.tab, HelpTab::Docs);
+        assert!(!help.is_settings());
+
+        help.switch_tab();
+        help.open_onboarding();
+        assert_eq!(help.tab, HelpTab::Docs);
     }
 
     #[test]
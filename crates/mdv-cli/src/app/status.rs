@@ -0,0 +1,259 @@
+use std::collections::VecDeque;
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use super::{App, centered_popup};
+use crate::ui::theme::ThemeTokens;
+
+/// How many drawn frames an info message stays up. The loop draws at least
+/// every 30 ms while idle, so this is about three seconds.
+pub(super) const STATUS_TTL_FRAMES: u32 = 100;
+/// Messages the `Alt+M` log keeps.
+pub(super) const STATUS_LOG_LEN: usize = 50;
+
+/// How much a status message matters. Warnings and errors stay up until a
+/// key is pressed; an info message cannot push them out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) enum Severity {
+    Info,
+    Warn,
+    Error,
+}
+
+impl Severity {
+    fn label(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lifetime {
+    Transient {
+        frames_left: u32,
+    },
+    /// Until acknowledged by a key or replaced by a message at least as
+    /// severe.
+    Sticky,
+}
+
+#[derive(Debug)]
+struct Shown {
+    text: String,
+    severity: Severity,
+    lifetime: Lifetime,
+}
+
+/// One entry of the message log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct LoggedStatus {
+    /// Wall-clock `HH:MM:SS`.
+    pub(super) at: String,
+    pub(super) severity: Severity,
+    pub(super) text: String,
+}
+
+/// The message on the status line and the ones before it.
+#[derive(Debug, Default)]
+pub(super) struct StatusLine {
+    shown: Option<Shown>,
+    log: VecDeque<LoggedStatus>,
+}
+
+impl StatusLine {
+    /// Log `text` and show it, unless a sticky message that is more severe
+    /// is up.
+    pub(super) fn push(&mut self, severity: Severity, text: String, at: String) {
+        if self.log.len() == STATUS_LOG_LEN {
+            self.log.pop_front();
+        }
+        self.log.push_back(LoggedStatus {
+            at,
+            severity,
+            text: text.clone(),
+        });
+        if let Some(shown) = &self.shown
+            && shown.lifetime == Lifetime::Sticky
+            && shown.severity > severity
+        {
+            return;
+        }
+        let lifetime = if severity == Severity::Info {
+            Lifetime::Transient {
+                frames_left: STATUS_TTL_FRAMES,
+            }
+        } else {
+            Lifetime::Sticky
+        };
+        self.shown = Some(Shown {
+            text,
+            severity,
+            lifetime,
+        });
+    }
+
+    /// One frame was drawn; a transient message that has had its time goes.
+    pub(super) fn age(&mut self) {
+        if let Some(Shown {
+            lifetime: Lifetime::Transient { frames_left },
+            ..
+        }) = &mut self.shown
+        {
+            *frames_left = frames_left.saturating_sub(1);
+            if *frames_left == 0 {
+                self.shown = None;
+            }
+        }
+    }
+
+    /// A key was pressed, so a sticky message has been read.
+    pub(super) fn acknowledge(&mut self) {
+        if self
+            .shown
+            .as_ref()
+            .is_some_and(|shown| shown.lifetime == Lifetime::Sticky)
+        {
+            self.shown = None;
+        }
+    }
+
+    pub(super) fn text(&self) -> Option<&str> {
+        self.shown.as_ref().map(|shown| shown.text.as_str())
+    }
+
+    pub(super) fn severity(&self) -> Severity {
+        self.shown
+            .as_ref()
+            .map_or(Severity::Info, |shown| shown.severity)
+    }
+
+    /// Oldest first.
+    pub(super) fn log(&self) -> impl DoubleEndedIterator<Item = &LoggedStatus> + ExactSizeIterator {
+        self.log.iter()
+    }
+}
+
+impl App {
+    /// Show `text` on the status line and add it to the message log.
+    pub(super) fn push_status(&mut self, severity: Severity, text: impl Into<String>) {
+        let at = super::clock_hms(std::time::SystemTime::now());
+        self.status.push(severity, text.into(), at);
+    }
+
+    /// The message on the status line, or where the link under the cursor
+    /// points, or what mode mdv is in once it has gone.
+    pub(super) fn status_text(&self) -> &str {
+        let link = self
+            .cursor_link
+            .as_ref()
+            .and_then(|link| link.status.as_deref());
+        self.status.text().or(link).unwrap_or(if self.home_mode {
+            "Home"
+        } else if self.stream_mode {
+            "Stream mode"
+        } else {
+            "Ready"
+        })
+    }
+
+    /// `Alt+M`: list recent messages, newest first, until the next key.
+    pub(super) fn open_message_log(&mut self) {
+        self.message_log = true;
+    }
+
+    pub(super) fn draw_message_log(&self, frame: &mut Frame<'_>, area: Rect, theme: &ThemeTokens) {
+        let rows = self.status.log().len().max(1) as u16;
+        let popup = centered_popup(80, rows.saturating_add(2).min(area.height), area);
+        frame.render_widget(Clear, popup);
+        let mut lines: Vec<Line> = self
+            .status
+            .log()
+            .rev()
+            .map(|entry| {
+                let style = match entry.severity {
+                    Severity::Info => theme.plain,
+                    Severity::Warn => theme.status_warn,
+                    Severity::Error => theme.status_error,
+                };
+                Line::from(Span::styled(
+                    format!(" {} {:<5} {}", entry.at, entry.severity.label(), entry.text),
+                    style,
+                ))
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push(Line::from(Span::styled(" No messages yet", theme.plain)));
+        }
+        let log = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Messages, times in UTC (any key closes)")
+                .border_style(theme.pane_focus),
+        );
+        frame.render_widget(log, popup);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{STATUS_LOG_LEN, STATUS_TTL_FRAMES, Severity, StatusLine};
+
+    fn push(status: &mut StatusLine, severity: Severity, text: &str) {
+        status.push(severity, text.into(), "12:00:00".into());
+    }
+
+    #[test]
+    fn errors_outlast_info_until_a_key_or_another_error() {
+        let mut status = StatusLine::default();
+        push(&mut status, Severity::Error, "Save error: disk full");
+        push(&mut status, Severity::Info, "File refreshed from disk");
+        assert_eq!(status.text(), Some("Save error: disk full"));
+        for _ in 0..STATUS_TTL_FRAMES * 2 {
+            status.age();
+        }
+        assert_eq!(status.severity(), Severity::Error);
+
+        push(&mut status, Severity::Warn, "Readonly: edit disabled");
+        assert_eq!(status.text(), Some("Save error: disk full"));
+        push(&mut status, Severity::Error, "watch error: gone");
+        assert_eq!(status.text(), Some("watch error: gone"));
+
+        status.acknowledge();
+        assert_eq!(status.text(), None);
+        assert_eq!(status.severity(), Severity::Info);
+    }
+
+    #[test]
+    fn info_expires_after_its_frames_and_ignores_keys() {
+        let mut status = StatusLine::default();
+        push(&mut status, Severity::Info, "Saved");
+        status.acknowledge();
+        for _ in 1..STATUS_TTL_FRAMES {
+            status.age();
+        }
+        assert_eq!(status.text(), Some("Saved"));
+        status.age();
+        assert_eq!(status.text(), None);
+    }
+
+    #[test]
+    fn the_log_keeps_the_latest_messages() {
+        let mut status = StatusLine::default();
+        for index in 0..STATUS_LOG_LEN + 5 {
+            push(&mut status, Severity::Info, &format!("message {index}"));
+        }
+        let log: Vec<_> = status.log().map(|entry| entry.text.as_str()).collect();
+        assert_eq!(log.len(), STATUS_LOG_LEN);
+        assert_eq!(log[0], "message 5");
+        assert_eq!(
+            log[STATUS_LOG_LEN - 1],
+            format!("message {}", STATUS_LOG_LEN + 4)
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use std::io;
+use std::sync::Once;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::Result;
+use crossterm::ExecutableCommand;
+use crossterm::cursor::Show;
+use crossterm::event::{
+    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::terminal::{
+    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+    supports_keyboard_enhancement,
+};
+
+use super::toggle_raw_mode;
+
+/// Set while the alternate screen is up; whoever clears it restores the
+/// terminal, so the panic hook and the guard never both do.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static INTERACTIVE: AtomicBool = AtomicBool::new(false);
+static ENHANCED_KEYS: AtomicBool = AtomicBool::new(false);
+static PANIC_HOOK: Once = Once::new();
+
+/// Puts the terminal into full-screen mode and takes it out again when
+/// dropped, however [`App::run`](super::App::run) ends.
+pub(super) struct TerminalGuard;
+
+impl TerminalGuard {
+    pub(super) fn enter(interactive: bool) -> Result<Self> {
+        install_panic_hook();
+        INTERACTIVE.store(interactive, Ordering::SeqCst);
+        ACTIVE.store(true, Ordering::SeqCst);
+        // From here on a failure still leaves the screen through `Drop`.
+        let guard = Self;
+        let mut stdout = io::stdout();
+        stdout.execute(EnterAlternateScreen)?;
+        if interactive {
+            stdout.execute(EnableMouseCapture)?;
+            stdout.execute(EnableBracketedPaste)?;
+        }
+        toggle_raw_mode(interactive, enable_raw_mode)?;
+        if interactive && supports_keyboard_enhancement().unwrap_or(false) {
+            // Report every key as an escape code so Ctrl+Shift+<letter> is
+            // not collapsed into Ctrl+<letter>; alternate keys keep Shift+1
+            // arriving as `!`. `input::normalize_key` folds the difference.
+            stdout.execute(PushKeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                    | KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
+                    | KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS,
+            ))?;
+            ENHANCED_KEYS.store(true, Ordering::SeqCst);
+        }
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Undo [`TerminalGuard::enter`] once. Errors are ignored: this runs while
+/// unwinding or on the way out, where there is nobody left to report to.
+fn restore() {
+    if !ACTIVE.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let interactive = INTERACTIVE.load(Ordering::SeqCst);
+    let mut stdout = io::stdout();
+    if ENHANCED_KEYS.swap(false, Ordering::SeqCst) {
+        let _ = stdout.execute(PopKeyboardEnhancementFlags);
+    }
+    let _ = toggle_raw_mode(interactive, disable_raw_mode);
+    if interactive {
+        let _ = stdout.execute(DisableBracketedPaste);
+        let _ = stdout.execute(DisableMouseCapture);
+    }
+    let _ = stdout.execute(LeaveAlternateScreen);
+    let _ = stdout.execute(Show);
+}
+
+/// Restore the terminal before the default hook prints a panic, so the
+/// message lands on the normal screen instead of the discarded one.
+fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore();
+            previous(info);
+        }));
+    });
+}
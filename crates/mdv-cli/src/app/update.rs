@@ -1,8 +1,10 @@
 use super::action::Action;
-use super::state::{PaneFocus, UiState};
+use super::state::{MAX_SPLIT_PERCENT, MIN_SPLIT_PERCENT, PaneFocus, SPLIT_STEP, UiState};
 
 pub fn apply_action(ui: &mut UiState, action: Action, _term_width: u16) {
     match action {
+        // View-only pins focus to the preview until it is turned off.
+        Action::ToggleFocus if ui.view_only => {}
         Action::ToggleFocus => {
             ui.focus = match ui.focus {
                 PaneFocus::Editor => PaneFocus::Preview,
@@ -10,6 +12,25 @@ pub fn apply_action(ui: &mut UiState, action: Action, _term_width: u16) {
             };
         }
         Action::ToggleHelp => ui.help.open = !ui.help.open,
+        Action::ToggleViewOnly => {
+            ui.view_only = !ui.view_only;
+            ui.focus = if ui.view_only {
+                PaneFocus::Preview
+            } else {
+                PaneFocus::Editor
+            };
+        }
+        Action::ToggleMaximize => ui.maximized = !ui.maximized,
+        Action::GrowPane | Action::ShrinkPane => {
+            // Growing the preview is shrinking the editor.
+            let grow_editor = (action == Action::GrowPane) == (ui.focus == PaneFocus::Editor);
+            let percent = if grow_editor {
+                ui.split_percent + SPLIT_STEP
+            } else {
+                ui.split_percent.saturating_sub(SPLIT_STEP)
+            };
+            ui.split_percent = percent.clamp(MIN_SPLIT_PERCENT, MAX_SPLIT_PERCENT);
+        }
         Action::ApplyPrefs {
             focus,
             theme,
@@ -19,6 +40,9 @@ pub fn apply_action(ui: &mut UiState, action: Action, _term_width: u16) {
             ui.theme = theme;
             ui.no_color = no_color;
         }
+        Action::ToggleLineNumbers => ui.line_numbers = !ui.line_numbers,
+        Action::ToggleSoftWrap => ui.soft_wrap = !ui.soft_wrap,
+        Action::ToggleScrollSync => ui.scroll_sync = !ui.scroll_sync,
     }
 }
 
@@ -39,6 +63,54 @@ mod tests {
         assert!(ui.help.open);
     }
 
+    #[test]
+    fn view_only_pins_focus_to_preview() {
+        let mut ui = UiState::default();
+        apply_action(&mut ui, Action::ToggleViewOnly, 120);
+        assert!(ui.view_only);
+        assert_eq!(ui.focus, PaneFocus::Preview);
+
+        apply_action(&mut ui, Action::ToggleFocus, 120);
+        assert_eq!(ui.focus, PaneFocus::Preview);
+
+        apply_action(&mut ui, Action::ToggleViewOnly, 120);
+        assert!(!ui.view_only);
+        assert_eq!(ui.focus, PaneFocus::Editor);
+    }
+
+    #[test]
+    fn maximize_toggles_back_and_forth() {
+        let mut ui = UiState::default();
+        apply_action(&mut ui, Action::ToggleMaximize, 120);
+        assert!(ui.maximized);
+        apply_action(&mut ui, Action::ToggleMaximize, 120);
+        assert!(!ui.maximized);
+    }
+
+    #[test]
+    fn resizing_moves_the_divider_for_the_focused_pane() {
+        let mut ui = UiState::default();
+        apply_action(&mut ui, Action::GrowPane, 160);
+        assert_eq!(ui.split_percent, 55);
+
+        ui.focus = PaneFocus::Preview;
+        apply_action(&mut ui, Action::GrowPane, 160);
+        apply_action(&mut ui, Action::GrowPane, 160);
+        assert_eq!(ui.split_percent, 45);
+        apply_action(&mut ui, Action::ShrinkPane, 160);
+        assert_eq!(ui.split_percent, 50);
+
+        for _ in 0..10 {
+            apply_action(&mut ui, Action::ShrinkPane, 160);
+        }
+        assert_eq!(ui.split_percent, 80);
+        ui.focus = PaneFocus::Editor;
+        for _ in 0..20 {
+            apply_action(&mut ui, Action::ShrinkPane, 160);
+        }
+        assert_eq!(ui.split_percent, 20);
+    }
+
     #[test]
     fn set_theme_focus_and_no_color() {
         let mut ui = UiState::default();
@@ -56,4 +128,15 @@ mod tests {
         assert!(ui.no_color);
         assert_eq!(ui.focus, PaneFocus::Preview);
     }
+
+    #[test]
+    fn toggles_line_numbers_soft_wrap_and_scroll_sync() {
+        let mut ui = UiState::default();
+        apply_action(&mut ui, Action::ToggleLineNumbers, 120);
+        assert!(!ui.line_numbers);
+        apply_action(&mut ui, Action::ToggleSoftWrap, 120);
+        assert!(ui.soft_wrap);
+        apply_action(&mut ui, Action::ToggleScrollSync, 120);
+        assert!(ui.scroll_sync);
+    }
 }
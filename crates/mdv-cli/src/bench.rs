@@ -0,0 +1,238 @@
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use mdv_core::{PreviewRenderer, render_preview_lines, render_preview_segments};
+
+/// Timings from `--bench-render` for one document at one width.
+#[derive(Debug)]
+pub struct BenchReport {
+    /// The file path, or `synthetic` for a `--bench-size` document.
+    pub source: String,
+    pub bytes: usize,
+    pub source_lines: usize,
+    pub width: u16,
+    pub iterations: usize,
+    pub passes: Vec<PassTiming>,
+}
+
+/// One timed render path and a sample per iteration.
+#[derive(Debug)]
+pub struct PassTiming {
+    pub name: &'static str,
+    pub rendered_lines: usize,
+    pub samples: Vec<Duration>,
+}
+
+impl PassTiming {
+    fn min(&self) -> Duration {
+        self.samples.iter().min().copied().unwrap_or_default()
+    }
+
+    fn mean(&self) -> Duration {
+        let total: Duration = self.samples.iter().sum();
+        total / self.samples.len().max(1) as u32
+    }
+
+    fn p95(&self) -> Duration {
+        let mut sorted = self.samples.clone();
+        sorted.sort_unstable();
+        let index = ((sorted.len() as f64) * 0.95).ceil() as usize;
+        sorted
+            .get(index.saturating_sub(1))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn lines_per_sec(&self) -> u64 {
+        let secs = self.mean().max(Duration::from_nanos(1)).as_secs_f64();
+        (self.rendered_lines as f64 / secs).round() as u64
+    }
+}
+
+/// A document of about `bytes` mixing headings, prose, lists and code, so
+/// every block kind the preview styles is timed.
+pub fn synthetic_document(bytes: usize) -> String {
+    let block = "## Section\n\nSome *prose* with a [link](https://example.com), `code` and \
+                 **bold** words that wrap at narrow widths.\n\n- item one\n- item two\n  \
+                 - nested\n\n> quoted\n\n```rust\nlet x = 1;\n```\n\n| a | b |\n|---|---|\n\
+                 | 1 | 2 |\n\n";
+    let mut doc = block.repeat(bytes / block.len() + 1);
+    doc.truncate(bytes);
+    doc
+}
+
+/// Time `iterations` runs of each preview render path over `markdown`.
+/// The `cached` pass is what a redraw costs once the preview cache misses
+/// but no block changed: the renderer hashes every block and reuses it.
+pub fn run(source: &str, markdown: &str, width: u16, iterations: usize) -> BenchReport {
+    let lines = time(iterations, || render_preview_lines(markdown, width).len());
+    let segments = time(iterations, || {
+        render_preview_segments(markdown, width).len()
+    });
+    let mut renderer = PreviewRenderer::new();
+    renderer.render(markdown, width);
+    let cached = time(iterations, || renderer.render(markdown, width).len());
+    BenchReport {
+        source: source.to_string(),
+        bytes: markdown.len(),
+        source_lines: markdown.lines().count(),
+        width,
+        iterations,
+        passes: vec![
+            PassTiming {
+                name: "lines",
+                rendered_lines: lines.0,
+                samples: lines.1,
+            },
+            PassTiming {
+                name: "segments",
+                rendered_lines: segments.0,
+                samples: segments.1,
+            },
+            PassTiming {
+                name: "cached",
+                rendered_lines: cached.0,
+                samples: cached.1,
+            },
+        ],
+    }
+}
+
+fn time(iterations: usize, mut render: impl FnMut() -> usize) -> (usize, Vec<Duration>) {
+    let mut rendered = 0;
+    let samples = (0..iterations)
+        .map(|_| {
+            let started = Instant::now();
+            rendered = render();
+            started.elapsed()
+        })
+        .collect();
+    (rendered, samples)
+}
+
+/// One `key=value` line for the document, then one per pass.
+pub fn write_text(report: &BenchReport, mut out: impl Write) -> io::Result<()> {
+    writeln!(
+        out,
+        "source={} bytes={} source_lines={} width={} iterations={}",
+        report.source, report.bytes, report.source_lines, report.width, report.iterations
+    )?;
+    for pass in &report.passes {
+        writeln!(
+            out,
+            "pass={} rendered_lines={} min_us={} mean_us={} p95_us={} lines_per_sec={}",
+            pass.name,
+            pass.rendered_lines,
+            pass.min().as_micros(),
+            pass.mean().as_micros(),
+            pass.p95().as_micros(),
+            pass.lines_per_sec()
+        )?;
+    }
+    out.flush()
+}
+
+/// The same fields as [`write_text`] as one JSON object.
+pub fn write_json(report: &BenchReport, mut out: impl Write) -> io::Result<()> {
+    write!(
+        out,
+        "{{\"source\":{},\"bytes\":{},\"source_lines\":{},\"width\":{},\"iterations\":{},\"passes\":[",
+        json_string(&report.source),
+        report.bytes,
+        report.source_lines,
+        report.width,
+        report.iterations
+    )?;
+    for (index, pass) in report.passes.iter().enumerate() {
+        if index > 0 {
+            out.write_all(b",")?;
+        }
+        write!(
+            out,
+            "{{\"name\":\"{}\",\"rendered_lines\":{},\"min_us\":{},\"mean_us\":{},\"p95_us\":{},\"lines_per_sec\":{}}}",
+            pass.name,
+            pass.rendered_lines,
+            pass.min().as_micros(),
+            pass.mean().as_micros(),
+            pass.p95().as_micros(),
+            pass.lines_per_sec()
+        )?;
+    }
+    out.write_all(b"]}\n")?;
+    out.flush()
+}
+
+pub fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            ch if ch < ' ' => quoted.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{PassTiming, json_string, run, synthetic_document, write_json, write_text};
+
+    #[test]
+    fn every_pass_runs_the_requested_iterations() {
+        let doc = synthetic_document(4096);
+        assert!(doc.len() <= 4096 && doc.len() > 4000);
+        let report = run("synthetic", &doc, 60, 4);
+        assert_eq!(report.iterations, 4);
+        assert_eq!(
+            report
+                .passes
+                .iter()
+                .map(|pass| (pass.name, pass.samples.len()))
+                .collect::<Vec<_>>(),
+            [("lines", 4), ("segments", 4), ("cached", 4)]
+        );
+        assert!(report.passes.iter().all(|pass| pass.rendered_lines > 0));
+
+        let mut text = Vec::new();
+        write_text(&report, &mut text).expect("text");
+        let text = String::from_utf8(text).expect("utf8");
+        assert_eq!(text.lines().count(), 4);
+        assert!(text.starts_with("source=synthetic bytes="));
+        assert!(text.contains("\npass=cached rendered_lines="));
+    }
+
+    #[test]
+    fn stats_and_json_output() {
+        let pass = PassTiming {
+            name: "lines",
+            rendered_lines: 500,
+            samples: [4, 1, 3, 2, 10].map(Duration::from_millis).to_vec(),
+        };
+        assert_eq!(pass.min(), Duration::from_millis(1));
+        assert_eq!(pass.mean(), Duration::from_millis(4));
+        assert_eq!(pass.p95(), Duration::from_millis(10));
+        assert_eq!(pass.lines_per_sec(), 125_000);
+
+        assert_eq!(
+            json_string("a \"b\"\\\n\t"),
+            "\"a \\\"b\\\"\\\\\\n\\u0009\""
+        );
+        let mut report = run("notes.md", "# Hi\n", 40, 1);
+        report.passes.truncate(1);
+        let mut out = Vec::new();
+        write_json(&report, &mut out).expect("json");
+        let out = String::from_utf8(out).expect("utf8");
+        assert!(out.starts_with(
+            "{\"source\":\"notes.md\",\"bytes\":5,\"source_lines\":1,\"width\":40,\
+             \"iterations\":1,\"passes\":[{\"name\":\"lines\",\"rendered_lines\":1,\"min_us\":"
+        ));
+        assert!(out.ends_with("}]}\n"));
+    }
+}
@@ -0,0 +1,76 @@
+pub trait Clipboard {
+    /// `Ok(None)` when the clipboard works but holds no text, such as when
+    /// it is empty or has an image on it.
+    fn get_text(&mut self) -> Result<Option<String>, String>;
+    fn set_text(&mut self, text: &str) -> Result<(), String>;
+}
+
+/// OS clipboard, opened lazily so headless sessions only fail on use.
+#[derive(Default)]
+#[cfg_attr(test, allow(dead_code))]
+pub struct SystemClipboard {
+    inner: Option<arboard::Clipboard>,
+}
+
+#[cfg_attr(test, allow(dead_code))]
+impl SystemClipboard {
+    fn handle(&mut self) -> Result<&mut arboard::Clipboard, String> {
+        if self.inner.is_none() {
+            self.inner = Some(arboard::Clipboard::new().map_err(|err| err.to_string())?);
+        }
+        Ok(self.inner.as_mut().expect("clipboard initialized"))
+    }
+}
+
+impl Clipboard for SystemClipboard {
+    fn get_text(&mut self) -> Result<Option<String>, String> {
+        match self.handle()?.get_text() {
+            Ok(text) => Ok(Some(text)),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), String> {
+        self.handle()?
+            .set_text(text.to_string())
+            .map_err(|err| err.to_string())
+    }
+}
+
+/// In-process clipboard used by tests; `unavailable` simulates a headless host.
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryClipboard {
+    pub text: Option<String>,
+    pub unavailable: bool,
+}
+
+#[cfg(test)]
+impl Clipboard for MemoryClipboard {
+    fn get_text(&mut self) -> Result<Option<String>, String> {
+        if self.unavailable {
+            return Err("clipboard unavailable".into());
+        }
+        Ok(self.text.clone())
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), String> {
+        if self.unavailable {
+            return Err("clipboard unavailable".into());
+        }
+        self.text = Some(text.to_string());
+        Ok(())
+    }
+}
+
+pub fn system() -> Box<dyn Clipboard> {
+    #[cfg(test)]
+    {
+        Box::new(MemoryClipboard::default())
+    }
+    #[cfg(not(test))]
+    {
+        Box::new(SystemClipboard::default())
+    }
+}
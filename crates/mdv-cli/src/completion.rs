@@ -0,0 +1,196 @@
+use std::fs;
+use std::path::{Path, is_separator};
+
+use crate::app::expand_tilde;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompletionResult {
+    /// Nothing in the directory starts with the typed name.
+    NoMatch,
+    /// Exactly one entry matched; directories end with `/`.
+    Unique(String),
+    /// Several entries matched. `common` extends the query as far as they
+    /// agree and `candidates` lists each full completion, sorted.
+    Ambiguous {
+        common: String,
+        candidates: Vec<String>,
+    },
+}
+
+/// Complete the last component of `partial` against the filesystem, the way
+/// a shell does: `~` is the home directory and relative paths start in the
+/// current directory. Hidden entries only match a name starting with `.`.
+pub fn complete_path(partial: &str) -> CompletionResult {
+    if partial == "~" {
+        return CompletionResult::Unique("~/".into());
+    }
+    let split = partial.rfind(is_separator).map_or(0, |idx| idx + 1);
+    let (dir, prefix) = partial.split_at(split);
+    let search = if dir.is_empty() {
+        Path::new(".").to_path_buf()
+    } else {
+        expand_tilde(dir)
+    };
+    let Ok(entries) = fs::read_dir(&search) else {
+        return CompletionResult::NoMatch;
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            // Follow symlinks so a link to a directory completes like one.
+            let is_dir = fs::metadata(entry.path()).is_ok_and(|meta| meta.is_dir());
+            Some(if is_dir { format!("{name}/") } else { name })
+        })
+        .collect();
+    names.sort();
+
+    match names.len() {
+        0 => CompletionResult::NoMatch,
+        1 => CompletionResult::Unique(format!("{dir}{}", names[0])),
+        _ => {
+            let common = common_prefix(&names);
+            CompletionResult::Ambiguous {
+                common: format!("{dir}{common}"),
+                candidates: names.iter().map(|name| format!("{dir}{name}")).collect(),
+            }
+        }
+    }
+}
+
+/// The last component of a completion, keeping a directory's `/`.
+pub fn file_part(path: &str) -> &str {
+    let start = path
+        .trim_end_matches(is_separator)
+        .rfind(is_separator)
+        .map_or(0, |idx| idx + 1);
+    &path[start..]
+}
+
+fn common_prefix(names: &[String]) -> &str {
+    let first = &names[0];
+    let mut end = first.len();
+    for name in &names[1..] {
+        end = first[..end]
+            .char_indices()
+            .zip(name.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(end.min(name.len()), |((idx, _), _)| idx);
+    }
+    &first[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::{CompletionResult, complete_path, file_part};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("mdv-complete-{name}-{nanos}"));
+        fs::create_dir_all(&dir).expect("mkdir");
+        dir
+    }
+
+    fn query(dir: &std::path::Path, rest: &str) -> String {
+        format!("{}/{rest}", dir.display())
+    }
+
+    #[test]
+    fn completes_a_unique_file() {
+        let dir = temp_dir("unique");
+        fs::write(dir.join("notes.md"), "").expect("seed");
+        fs::write(dir.join("todo.md"), "").expect("seed");
+
+        assert_eq!(
+            complete_path(&query(&dir, "no")),
+            CompletionResult::Unique(query(&dir, "notes.md"))
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn multiple_matches_extend_to_the_common_prefix() {
+        let dir = temp_dir("ambiguous");
+        fs::write(dir.join("notes-2024.md"), "").expect("seed");
+        fs::write(dir.join("notes-2025.md"), "").expect("seed");
+        fs::create_dir(dir.join("notes-old")).expect("mkdir");
+
+        assert_eq!(
+            complete_path(&query(&dir, "no")),
+            CompletionResult::Ambiguous {
+                common: query(&dir, "notes-"),
+                candidates: vec![
+                    query(&dir, "notes-2024.md"),
+                    query(&dir, "notes-2025.md"),
+                    query(&dir, "notes-old/"),
+                ],
+            }
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reports_no_match() {
+        let dir = temp_dir("none");
+        fs::write(dir.join("notes.md"), "").expect("seed");
+
+        assert_eq!(
+            complete_path(&query(&dir, "zzz")),
+            CompletionResult::NoMatch
+        );
+        assert_eq!(
+            complete_path(&query(&dir, "missing/no")),
+            CompletionResult::NoMatch
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn directories_get_a_trailing_slash_and_hidden_entries_need_a_dot() {
+        let dir = temp_dir("dirs");
+        fs::create_dir(dir.join("docs")).expect("mkdir");
+        fs::write(dir.join("docs").join("guide.md"), "").expect("seed");
+        fs::write(dir.join(".hidden.md"), "").expect("seed");
+
+        assert_eq!(
+            complete_path(&query(&dir, "d")),
+            CompletionResult::Unique(query(&dir, "docs/"))
+        );
+        assert_eq!(
+            complete_path(&query(&dir, "docs/")),
+            CompletionResult::Unique(query(&dir, "docs/guide.md"))
+        );
+        assert_eq!(
+            complete_path(&query(&dir, "")),
+            CompletionResult::Unique(query(&dir, "docs/"))
+        );
+        assert_eq!(
+            complete_path(&query(&dir, ".h")),
+            CompletionResult::Unique(query(&dir, ".hidden.md"))
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn file_part_keeps_the_directory_slash() {
+        assert_eq!(file_part("a/b/notes.md"), "notes.md");
+        assert_eq!(file_part("a/docs/"), "docs/");
+        assert_eq!(file_part("top.md"), "top.md");
+    }
+
+    #[test]
+    fn tilde_is_the_home_directory() {
+        assert_eq!(complete_path("~"), CompletionResult::Unique("~/".into()));
+    }
+}
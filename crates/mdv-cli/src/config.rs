@@ -0,0 +1,634 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use mdv_core::SavePolicy;
+use ratatui::style::{Color, Modifier};
+
+use crate::app::keymap::{Chord, Command, KeyMap};
+use crate::app::snippets::Snippet;
+use crate::ui::theme::{StyleOverride, ThemeOverrides};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    pub theme: ThemeOverrides,
+    pub editor: EditorConfig,
+    pub keys: KeyMap,
+    /// `[snippets]`: palette entries after the built-in ones.
+    pub snippets: Vec<Snippet>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditorConfig {
+    /// Spaces inserted by `Tab` and removed by `Shift+Tab`.
+    pub indent: usize,
+    /// Whether `Enter` repeats list and blockquote markers.
+    pub continue_lists: bool,
+    /// Documents bigger than this open in large-file mode.
+    pub large_file_bytes: usize,
+    /// Quiet time before an external change to the file is applied.
+    pub watch_debounce: Duration,
+    /// Shell command `Ctrl+Alt+F` pipes the buffer through.
+    pub formatter: Option<String>,
+    /// Word list spell checking uses instead of the system one.
+    pub spell_dictionary: Option<PathBuf>,
+    /// Whitespace cleanups on save, unless `.editorconfig` says otherwise.
+    pub save_policy: SavePolicy,
+    /// Keep the text an external update replaced, for `Alt+R` to restore.
+    pub keep_before_external: bool,
+    /// Copy the text `Ctrl+R` throws away to `backups` in the config dir first.
+    pub reload_backups: bool,
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            continue_lists: true,
+            large_file_bytes: 2 * MB,
+            watch_debounce: Duration::from_millis(150),
+            formatter: None,
+            spell_dictionary: None,
+            save_policy: SavePolicy::default(),
+            keep_before_external: false,
+            reload_backups: true,
+        }
+    }
+}
+
+const MAX_INDENT: usize = 8;
+const MB: usize = 1024 * 1024;
+const MAX_WATCH_DEBOUNCE_MS: i64 = 10_000;
+
+/// Settings plus anything worth warning about; problems never abort startup.
+#[derive(Debug, Clone, Default)]
+pub struct LoadedConfig {
+    pub config: Config,
+    pub warnings: Vec<String>,
+}
+
+/// Read `explicit`, or the platform config file when none is given. A
+/// missing default file is normal; a missing explicit one is a warning.
+pub fn load(explicit: Option<&Path>) -> LoadedConfig {
+    let Some(path) = explicit.map(Path::to_path_buf).or_else(default_path) else {
+        return LoadedConfig::default();
+    };
+    match fs::read_to_string(&path) {
+        Ok(text) => {
+            let mut loaded = parse(&text);
+            for warning in &mut loaded.warnings {
+                *warning = format!("{}: {warning}", path.display());
+            }
+            loaded
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound && explicit.is_none() => {
+            LoadedConfig::default()
+        }
+        Err(err) => LoadedConfig {
+            config: Config::default(),
+            warnings: vec![format!("{}: {err}", path.display())],
+        },
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config.toml"))
+}
+
+/// `$MDV_CONFIG_DIR` when set, else `$XDG_CONFIG_HOME/mdv`, else
+/// `%APPDATA%\mdv` on Windows, else `~/.config/mdv`.
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("MDV_CONFIG_DIR").filter(|value| !value.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            if cfg!(windows) {
+                std::env::var_os("APPDATA").map(PathBuf::from)
+            } else {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+            }
+        })?;
+    Some(base.join("mdv"))
+}
+
+pub fn parse(text: &str) -> LoadedConfig {
+    let mut loaded = LoadedConfig::default();
+    let table = match text.parse::<toml::Table>() {
+        Ok(table) => table,
+        Err(err) => {
+            loaded
+                .warnings
+                .push(format!("invalid TOML: {}", err.message()));
+            return loaded;
+        }
+    };
+
+    for (section, value) in table {
+        match (section.as_str(), value) {
+            ("theme", toml::Value::Table(theme)) => {
+                for (key, value) in theme {
+                    match parse_style(&value) {
+                        Ok(style) => {
+                            if !loaded.config.theme.set(&key, style) {
+                                loaded.warnings.push(format!("unknown theme key `{key}`"));
+                            }
+                        }
+                        Err(err) => loaded.warnings.push(format!("theme.{key}: {err}")),
+                    }
+                }
+            }
+            ("theme", _) => loaded.warnings.push("`theme` must be a table".into()),
+            ("editor", toml::Value::Table(editor)) => {
+                for (key, value) in editor {
+                    match (key.as_str(), value) {
+                        ("indent", toml::Value::Integer(width))
+                            if (1..=MAX_INDENT as i64).contains(&width) =>
+                        {
+                            loaded.config.editor.indent = width as usize;
+                        }
+                        ("indent", _) => loaded
+                            .warnings
+                            .push(format!("editor.indent must be 1-{MAX_INDENT}")),
+                        ("continue_lists", toml::Value::Boolean(on)) => {
+                            loaded.config.editor.continue_lists = on;
+                        }
+                        ("large_file_mb", toml::Value::Integer(mb)) if mb > 0 => {
+                            loaded.config.editor.large_file_bytes =
+                                usize::try_from(mb).unwrap_or(usize::MAX).saturating_mul(MB);
+                        }
+                        ("large_file_mb", _) => loaded
+                            .warnings
+                            .push("editor.large_file_mb must be a positive integer".into()),
+                        ("watch_debounce_ms", toml::Value::Integer(ms))
+                            if (0..=MAX_WATCH_DEBOUNCE_MS).contains(&ms) =>
+                        {
+                            loaded.config.editor.watch_debounce = Duration::from_millis(ms as u64);
+                        }
+                        ("watch_debounce_ms", _) => loaded.warnings.push(format!(
+                            "editor.watch_debounce_ms must be 0-{MAX_WATCH_DEBOUNCE_MS}"
+                        )),
+                        ("formatter", toml::Value::String(command))
+                            if !command.trim().is_empty() =>
+                        {
+                            loaded.config.editor.formatter = Some(command);
+                        }
+                        ("formatter", _) => loaded
+                            .warnings
+                            .push("editor.formatter must be a non-empty command".into()),
+                        ("spell_dictionary", toml::Value::String(path)) if !path.is_empty() => {
+                            loaded.config.editor.spell_dictionary = Some(PathBuf::from(path));
+                        }
+                        ("spell_dictionary", _) => loaded
+                            .warnings
+                            .push("editor.spell_dictionary must be a file path".into()),
+                        ("final_newline", toml::Value::Boolean(on)) => {
+                            loaded.config.editor.save_policy.final_newline = on;
+                        }
+                        ("trim_trailing_whitespace", toml::Value::Boolean(on)) => {
+                            loaded.config.editor.save_policy.trim_trailing_whitespace = on;
+                        }
+                        ("keep_before_external", toml::Value::Boolean(on)) => {
+                            loaded.config.editor.keep_before_external = on;
+                        }
+                        ("reload_backups", toml::Value::Boolean(on)) => {
+                            loaded.config.editor.reload_backups = on;
+                        }
+                        (
+                            key @ ("continue_lists"
+                            | "final_newline"
+                            | "trim_trailing_whitespace"
+                            | "keep_before_external"
+                            | "reload_backups"),
+                            _,
+                        ) => {
+                            loaded
+                                .warnings
+                                .push(format!("editor.{key} must be true or false"));
+                        }
+                        (other, _) => loaded
+                            .warnings
+                            .push(format!("unknown editor key `{other}`")),
+                    }
+                }
+            }
+            ("editor", _) => loaded.warnings.push("`editor` must be a table".into()),
+            ("keys", toml::Value::Table(keys)) => {
+                for (name, value) in keys {
+                    let Some(command) = Command::from_name(&name) else {
+                        loaded.warnings.push(format!("unknown key action `{name}`"));
+                        continue;
+                    };
+                    let bound = parse_chords(&value)
+                        .and_then(|chords| loaded.config.keys.bind(command, &chords));
+                    if let Err(err) = bound {
+                        loaded.warnings.push(format!("keys.{name}: {err}"));
+                    }
+                }
+            }
+            ("keys", _) => loaded.warnings.push("`keys` must be a table".into()),
+            ("snippets", toml::Value::Table(snippets)) => {
+                for (name, value) in snippets {
+                    match value {
+                        toml::Value::String(body) if !body.is_empty() => {
+                            loaded.config.snippets.push(Snippet::text(name, body));
+                        }
+                        _ => loaded
+                            .warnings
+                            .push(format!("snippets.{name} must be a non-empty string")),
+                    }
+                }
+            }
+            ("snippets", _) => loaded.warnings.push("`snippets` must be a table".into()),
+            (other, _) => loaded.warnings.push(format!("unknown section `{other}`")),
+        }
+    }
+    loaded
+}
+
+/// Either `"ctrl+s"` or a list such as `["f3", "ctrl+n"]`.
+fn parse_chords(value: &toml::Value) -> Result<Vec<Chord>, String> {
+    match value {
+        toml::Value::String(chord) => Ok(vec![Chord::parse(chord)?]),
+        toml::Value::Array(items) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .ok_or_else(|| "keys must be strings".to_string())
+                    .and_then(Chord::parse)
+            })
+            .collect(),
+        _ => Err("expected a key such as \"ctrl+s\" or a list of them".into()),
+    }
+}
+
+/// Either `"cyan bold"` (foreground plus modifiers) or
+/// `{ fg = "#87cefa", bg = "black", modifiers = ["bold"] }`.
+fn parse_style(value: &toml::Value) -> Result<StyleOverride, String> {
+    match value {
+        toml::Value::String(words) => parse_style_words(words),
+        toml::Value::Table(table) => {
+            let mut style = StyleOverride::default();
+            for (key, value) in table {
+                match (key.as_str(), value) {
+                    ("fg", toml::Value::String(color)) => style.fg = Some(parse_color(color)?),
+                    ("bg", toml::Value::String(color)) => style.bg = Some(parse_color(color)?),
+                    ("modifiers", toml::Value::String(words)) => {
+                        for word in words.split_whitespace() {
+                            style.modifiers |= parse_modifier(word)?;
+                        }
+                    }
+                    ("modifiers", toml::Value::Array(items)) => {
+                        for item in items {
+                            let word = item
+                                .as_str()
+                                .ok_or_else(|| "modifiers must be strings".to_string())?;
+                            style.modifiers |= parse_modifier(word)?;
+                        }
+                    }
+                    (other, _) => return Err(format!("unexpected `{other}`")),
+                }
+            }
+            Ok(style)
+        }
+        _ => Err("expected a string or table".into()),
+    }
+}
+
+fn parse_style_words(words: &str) -> Result<StyleOverride, String> {
+    let mut style = StyleOverride::default();
+    for word in words.split_whitespace() {
+        if let Ok(modifier) = parse_modifier(word) {
+            style.modifiers |= modifier;
+        } else if style.fg.is_none() {
+            style.fg = Some(parse_color(word)?);
+        } else {
+            return Err(format!("unexpected `{word}`"));
+        }
+    }
+    Ok(style)
+}
+
+fn parse_color(value: &str) -> Result<Color, String> {
+    if let Some(hex) = value.strip_prefix('#') {
+        let channel = |idx: usize| {
+            hex.get(idx..idx + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+        };
+        return match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb(r, g, b)),
+            _ => Err(format!("invalid hex color `{value}`")),
+        };
+    }
+    value
+        .parse::<Color>()
+        .map_err(|_| format!("unknown color `{value}`"))
+}
+
+fn parse_modifier(value: &str) -> Result<Modifier, String> {
+    let modifier = match value.to_ascii_lowercase().as_str() {
+        "bold" => Modifier::BOLD,
+        "dim" => Modifier::DIM,
+        "italic" => Modifier::ITALIC,
+        "underline" | "underlined" => Modifier::UNDERLINED,
+        "reversed" | "reverse" => Modifier::REVERSED,
+        "crossed_out" | "strikethrough" => Modifier::CROSSED_OUT,
+        _ => return Err(format!("unknown modifier `{value}`")),
+    };
+    Ok(modifier)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use mdv_core::SavePolicy;
+    use ratatui::style::{Color, Modifier};
+
+    use super::{load, parse};
+    use crate::app::keymap::{Chord, Command};
+    use crate::app::snippets::Snippet;
+
+    #[test]
+    fn parses_named_and_hex_colors_with_modifiers() {
+        let loaded = parse(
+            r##"
+[theme]
+heading = "#87cefa bold underlined"
+code = { fg = "cyan", bg = "#101010", modifiers = ["italic"] }
+link = "LightBlue"
+"##,
+        );
+        assert!(loaded.warnings.is_empty(), "{:?}", loaded.warnings);
+        let theme = &loaded.config.theme;
+
+        let heading = theme.get("heading").expect("heading");
+        assert_eq!(heading.fg, Some(Color::Rgb(0x87, 0xce, 0xfa)));
+        assert_eq!(heading.modifiers, Modifier::BOLD | Modifier::UNDERLINED);
+
+        let code = theme.get("code").expect("code");
+        assert_eq!(code.fg, Some(Color::Cyan));
+        assert_eq!(code.bg, Some(Color::Rgb(0x10, 0x10, 0x10)));
+        assert_eq!(code.modifiers, Modifier::ITALIC);
+
+        assert_eq!(theme.get("link").expect("link").fg, Some(Color::LightBlue));
+    }
+
+    #[test]
+    fn unknown_keys_and_bad_colors_warn_but_keep_valid_overrides() {
+        let loaded = parse(
+            r##"
+[theme]
+heading = "red"
+headline = "blue"
+code = "#12345"
+quote = "bold sparkly"
+
+[editor]
+tabs = 4
+
+[keys]
+x = 1
+
+[colors]
+x = 1
+"##,
+        );
+        let theme = &loaded.config.theme;
+        assert_eq!(theme.get("heading").expect("heading").fg, Some(Color::Red));
+        assert!(theme.get("code").is_none());
+        assert!(theme.get("quote").is_none());
+
+        let warnings = loaded.warnings.join("\n");
+        assert!(
+            warnings.contains("unknown theme key `headline`"),
+            "{warnings}"
+        );
+        assert!(
+            warnings.contains("invalid hex color `#12345`"),
+            "{warnings}"
+        );
+        assert!(warnings.contains("unknown color `sparkly`"), "{warnings}");
+        assert!(warnings.contains("unknown editor key `tabs`"), "{warnings}");
+        assert!(warnings.contains("unknown key action `x`"), "{warnings}");
+        assert!(warnings.contains("unknown section `colors`"), "{warnings}");
+    }
+
+    #[test]
+    fn editor_indent_is_read_and_bounded() {
+        assert_eq!(parse("").config.editor.indent, 2);
+        assert_eq!(parse("[editor]\nindent = 4\n").config.editor.indent, 4);
+
+        let loaded = parse("[editor]\nindent = 0\n");
+        assert_eq!(loaded.config.editor.indent, 2);
+        assert_eq!(loaded.warnings, vec!["editor.indent must be 1-8"]);
+    }
+
+    #[test]
+    fn editor_large_file_threshold_is_in_megabytes() {
+        assert_eq!(parse("").config.editor.large_file_bytes, 2 * 1024 * 1024);
+        let loaded = parse("[editor]\nlarge_file_mb = 16\n");
+        assert_eq!(loaded.config.editor.large_file_bytes, 16 * 1024 * 1024);
+
+        let loaded = parse("[editor]\nlarge_file_mb = 0\n");
+        assert_eq!(loaded.config.editor.large_file_bytes, 2 * 1024 * 1024);
+        assert_eq!(
+            loaded.warnings,
+            vec!["editor.large_file_mb must be a positive integer"]
+        );
+    }
+
+    #[test]
+    fn editor_watch_debounce_is_in_milliseconds() {
+        let default = parse("").config.editor.watch_debounce;
+        assert_eq!(default, Duration::from_millis(150));
+        let loaded = parse("[editor]\nwatch_debounce_ms = 0\n");
+        assert_eq!(loaded.config.editor.watch_debounce, Duration::ZERO);
+        assert!(loaded.warnings.is_empty());
+
+        let loaded = parse("[editor]\nwatch_debounce_ms = -5\n");
+        assert_eq!(loaded.config.editor.watch_debounce, default);
+        assert_eq!(
+            loaded.warnings,
+            vec!["editor.watch_debounce_ms must be 0-10000"]
+        );
+    }
+
+    #[test]
+    fn editor_formatter_is_a_shell_command() {
+        assert_eq!(parse("").config.editor.formatter, None);
+        let loaded = parse("[editor]\nformatter = \"prettier --parser markdown\"\n");
+        assert_eq!(
+            loaded.config.editor.formatter.as_deref(),
+            Some("prettier --parser markdown")
+        );
+
+        let loaded = parse("[editor]\nformatter = \" \"\n");
+        assert_eq!(loaded.config.editor.formatter, None);
+        assert_eq!(
+            loaded.warnings,
+            vec!["editor.formatter must be a non-empty command"]
+        );
+    }
+
+    #[test]
+    fn editor_spell_dictionary_is_a_path() {
+        assert_eq!(parse("").config.editor.spell_dictionary, None);
+        let loaded = parse("[editor]\nspell_dictionary = \"/usr/share/dict/british-english\"\n");
+        assert_eq!(
+            loaded.config.editor.spell_dictionary,
+            Some(PathBuf::from("/usr/share/dict/british-english"))
+        );
+
+        let loaded = parse("[editor]\nspell_dictionary = 3\n");
+        assert_eq!(loaded.config.editor.spell_dictionary, None);
+        assert_eq!(
+            loaded.warnings,
+            vec!["editor.spell_dictionary must be a file path"]
+        );
+    }
+
+    #[test]
+    fn editor_list_continuation_can_be_turned_off() {
+        assert!(parse("").config.editor.continue_lists);
+        let loaded = parse("[editor]\ncontinue_lists = false\n");
+        assert!(!loaded.config.editor.continue_lists);
+        assert!(loaded.warnings.is_empty());
+
+        let loaded = parse("[editor]\ncontinue_lists = \"no\"\n");
+        assert!(loaded.config.editor.continue_lists);
+        assert_eq!(
+            loaded.warnings,
+            vec!["editor.continue_lists must be true or false"]
+        );
+    }
+
+    #[test]
+    fn editor_save_cleanups_are_booleans() {
+        assert_eq!(parse("").config.editor.save_policy, SavePolicy::default());
+        let loaded = parse("[editor]\nfinal_newline = true\ntrim_trailing_whitespace = 1\n");
+        assert!(loaded.config.editor.save_policy.final_newline);
+        assert!(!loaded.config.editor.save_policy.trim_trailing_whitespace);
+        assert_eq!(
+            loaded.warnings,
+            vec!["editor.trim_trailing_whitespace must be true or false"]
+        );
+    }
+
+    #[test]
+    fn keep_before_external_is_an_opt_in_boolean() {
+        assert!(!parse("").config.editor.keep_before_external);
+        let loaded = parse("[editor]\nkeep_before_external = true\n");
+        assert!(loaded.config.editor.keep_before_external);
+        let loaded = parse("[editor]\nkeep_before_external = \"yes\"\n");
+        assert!(!loaded.config.editor.keep_before_external);
+        assert_eq!(
+            loaded.warnings,
+            vec!["editor.keep_before_external must be true or false"]
+        );
+    }
+
+    #[test]
+    fn reload_backups_are_on_unless_turned_off() {
+        assert!(parse("").config.editor.reload_backups);
+        let loaded = parse("[editor]\nreload_backups = false\n");
+        assert!(!loaded.config.editor.reload_backups);
+        let loaded = parse("[editor]\nreload_backups = 0\n");
+        assert!(loaded.config.editor.reload_backups);
+        assert_eq!(
+            loaded.warnings,
+            vec!["editor.reload_backups must be true or false"]
+        );
+    }
+
+    #[test]
+    fn keys_rebind_commands_and_reject_bad_entries() {
+        let loaded = parse(
+            r#"
+[keys]
+save = "f2"
+search = ["ctrl+alt+s", "f4"]
+undo = "f2"
+quit = "q"
+teleport = "ctrl+t"
+"#,
+        );
+        let keys = &loaded.config.keys;
+        let chord = |text| Chord::parse(text).expect(text);
+        assert_eq!(keys.chords(Command::Save), [chord("f2")]);
+        assert_eq!(
+            keys.chords(Command::Search),
+            [chord("ctrl+alt+s"), chord("f4")]
+        );
+        // Refused entries keep the defaults.
+        assert_eq!(keys.chords(Command::Undo), [chord("ctrl+z")]);
+        assert_eq!(keys.chords(Command::Quit), [chord("ctrl+q")]);
+        assert_eq!(
+            loaded.warnings,
+            vec![
+                "keys.quit: `q` needs ctrl, alt or super; plain keys are for typing",
+                "unknown key action `teleport`",
+                "keys.undo: F2 is already bound to save",
+            ]
+        );
+    }
+
+    #[test]
+    fn snippets_are_named_bodies() {
+        let loaded = parse(
+            r#"
+[snippets]
+note = "> **Note:** $CURSOR"
+empty = ""
+count = 3
+"#,
+        );
+        assert_eq!(
+            loaded.config.snippets,
+            [Snippet::text("note".into(), "> **Note:** $CURSOR".into())]
+        );
+        assert_eq!(
+            loaded.warnings,
+            vec![
+                "snippets.count must be a non-empty string",
+                "snippets.empty must be a non-empty string",
+            ]
+        );
+    }
+
+    #[test]
+    fn partial_and_broken_files_never_fail() {
+        let loaded = parse("");
+        assert!(loaded.config.theme.is_empty());
+        assert!(loaded.warnings.is_empty());
+
+        let loaded = parse("[theme\nheading = ");
+        assert!(loaded.config.theme.is_empty());
+        assert!(loaded.warnings[0].starts_with("invalid TOML"));
+    }
+
+    #[test]
+    fn load_reports_missing_explicit_file() {
+        let dir = std::env::temp_dir().join(format!("mdv-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("mkdir");
+        let missing = dir.join("missing.toml");
+        let loaded = load(Some(&missing));
+        assert_eq!(loaded.warnings.len(), 1);
+
+        let path = dir.join("config.toml");
+        fs::write(&path, "[theme]\nplain = \"white\"\nbogus = \"red\"\n").expect("write");
+        let loaded = load(Some(&path));
+        assert_eq!(
+            loaded.config.theme.get("plain").expect("plain").fg,
+            Some(Color::White)
+        );
+        assert!(loaded.warnings[0].starts_with(&path.display().to_string()));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
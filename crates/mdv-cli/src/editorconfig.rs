@@ -0,0 +1,266 @@
+use std::fs;
+use std::path::Path;
+
+use mdv_core::SavePolicy;
+
+/// `policy` with the `insert_final_newline` and `trim_trailing_whitespace`
+/// keys of the `.editorconfig` files governing `path` applied on top. Files
+/// nearer the document win, and the search stops at one with `root = true`.
+pub fn save_policy(path: &Path, mut policy: SavePolicy) -> SavePolicy {
+    let Some(file_name) = path.file_name() else {
+        return policy;
+    };
+    let mut relative = vec![file_name.to_string_lossy().into_owned()];
+    let mut found = Vec::new();
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        if let Ok(text) = fs::read_to_string(current.join(".editorconfig")) {
+            let root = is_root(&text);
+            found.push((text, relative.join("/")));
+            if root {
+                break;
+            }
+        }
+        let Some(name) = current.file_name() else {
+            break;
+        };
+        relative.insert(0, name.to_string_lossy().into_owned());
+        dir = current
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty());
+    }
+
+    // Farthest first, so nearer files override.
+    for (text, relative) in found.iter().rev() {
+        for (key, value) in matching_properties(text, relative) {
+            let on = match value.as_str() {
+                "true" => Some(true),
+                "false" => Some(false),
+                _ => None,
+            };
+            match key.as_str() {
+                "insert_final_newline" => {
+                    policy.final_newline = on.unwrap_or(policy.final_newline);
+                }
+                "trim_trailing_whitespace" => {
+                    policy.trim_trailing_whitespace = on.unwrap_or(policy.trim_trailing_whitespace);
+                }
+                _ => {}
+            }
+        }
+    }
+    policy
+}
+
+fn is_root(text: &str) -> bool {
+    text.lines()
+        .map(str::trim)
+        .take_while(|line| !line.starts_with('['))
+        .filter_map(|line| line.split_once('='))
+        .any(|(key, value)| {
+            key.trim().eq_ignore_ascii_case("root") && value.trim().eq_ignore_ascii_case("true")
+        })
+}
+
+/// Lowercased `key = value` pairs from the sections whose glob matches
+/// `relative`, the file's path from the `.editorconfig` directory.
+fn matching_properties(text: &str, relative: &str) -> Vec<(String, String)> {
+    let mut properties = Vec::new();
+    let mut matches = false;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with(['#', ';']) {
+            continue;
+        }
+        if let Some(section) = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            matches = section_matches(section, relative);
+        } else if matches && let Some((key, value)) = line.split_once('=') {
+            properties.push((key.trim().to_lowercase(), value.trim().to_lowercase()));
+        }
+    }
+    properties
+}
+
+/// A glob without `/` matches the file name in any directory; one with a
+/// `/` is anchored to the `.editorconfig` directory.
+fn section_matches(section: &str, relative: &str) -> bool {
+    let pattern = if section.contains('/') {
+        section.trim_start_matches('/').to_string()
+    } else {
+        format!("**/{section}")
+    };
+    expand_braces(&pattern)
+        .iter()
+        .any(|pattern| glob_match(pattern.as_bytes(), relative.as_bytes()))
+}
+
+/// `{a,b}` alternatives spelled out, outermost first.
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let Some(open) = pattern.find('{') else {
+        return vec![pattern.to_string()];
+    };
+    let mut depth = 0;
+    let mut close = None;
+    let mut splits = Vec::new();
+    for (offset, byte) in pattern.bytes().enumerate().skip(open) {
+        match byte {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(offset);
+                    break;
+                }
+            }
+            b',' if depth == 1 => splits.push(offset),
+            _ => {}
+        }
+    }
+    let Some(close) = close.filter(|_| !splits.is_empty()) else {
+        return vec![pattern.to_string()];
+    };
+    let (head, tail) = (&pattern[..open], &pattern[close + 1..]);
+    let mut start = open + 1;
+    let mut expanded = Vec::new();
+    for end in splits.into_iter().chain([close]) {
+        expanded.extend(expand_braces(&format!(
+            "{head}{}{tail}",
+            &pattern[start..end]
+        )));
+        start = end + 1;
+    }
+    expanded
+}
+
+/// `*` stays within a path segment, `**` crosses them, `?` is one
+/// character and `[abc]` / `[!abc]` a set.
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', b'/', rest @ ..] => {
+            glob_match(rest, text)
+                || (0..text.len())
+                    .filter(|&at| text[at] == b'/')
+                    .any(|at| glob_match(rest, &text[at + 1..]))
+        }
+        [b'*', b'*', rest @ ..] => (0..=text.len()).any(|at| glob_match(rest, &text[at..])),
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|&at| at == 0 || text[at - 1] != b'/')
+            .any(|at| glob_match(rest, &text[at..])),
+        [b'?', rest @ ..] => text
+            .first()
+            .is_some_and(|&byte| byte != b'/' && glob_match(rest, &text[1..])),
+        [b'[', rest @ ..] if rest.contains(&b']') => {
+            let close = rest.iter().position(|&byte| byte == b']').unwrap_or(0);
+            let (negated, set) = match &rest[..close] {
+                [b'!', set @ ..] => (true, set),
+                set => (false, set),
+            };
+            text.first().is_some_and(|&byte| {
+                byte != b'/'
+                    && in_set(set, byte) != negated
+                    && glob_match(&rest[close + 1..], &text[1..])
+            })
+        }
+        [byte, rest @ ..] => text.first() == Some(byte) && glob_match(rest, &text[1..]),
+    }
+}
+
+fn in_set(set: &[u8], byte: u8) -> bool {
+    let mut index = 0;
+    while index < set.len() {
+        if index + 2 < set.len() && set[index + 1] == b'-' {
+            if (set[index]..=set[index + 2]).contains(&byte) {
+                return true;
+            }
+            index += 3;
+        } else {
+            if set[index] == byte {
+                return true;
+            }
+            index += 1;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use mdv_core::SavePolicy;
+
+    use super::{expand_braces, glob_match, save_policy, section_matches};
+
+    #[test]
+    fn globs_follow_editorconfig_rules() {
+        assert!(section_matches("*", "docs/notes.md"));
+        assert!(section_matches("*.md", "docs/notes.md"));
+        assert!(section_matches("*.{md,markdown}", "README.markdown"));
+        assert!(section_matches("docs/*.md", "docs/notes.md"));
+        assert!(!section_matches("docs/*.md", "docs/deep/notes.md"));
+        assert!(section_matches("docs/**.md", "docs/deep/notes.md"));
+        assert!(section_matches("/docs/[a-n]*.md", "docs/notes.md"));
+        assert!(!section_matches("[!n]*.md", "notes.md"));
+        assert!(!section_matches("*.txt", "notes.md"));
+        assert!(glob_match(b"a?c", b"abc"));
+        assert!(!glob_match(b"a?c", b"a/c"));
+        assert_eq!(expand_braces("{a,b{c,d}}.md"), ["a.md", "bc.md", "bd.md"]);
+        assert_eq!(expand_braces("{single}.md"), ["{single}.md"]);
+    }
+
+    #[test]
+    fn nearer_files_override_and_root_stops_the_search() {
+        let dir = std::env::temp_dir().join(format!("mdv-editorconfig-{}", std::process::id()));
+        let nested = dir.join("project").join("docs");
+        fs::create_dir_all(&nested).expect("dirs");
+        fs::write(
+            dir.join(".editorconfig"),
+            "[*]\ninsert_final_newline = true\ntrim_trailing_whitespace = true\n",
+        )
+        .expect("outer");
+        fs::write(
+            dir.join("project").join(".editorconfig"),
+            "# project\n[*.md]\ntrim_trailing_whitespace = false\n[docs/*.md]\nINSERT_FINAL_NEWLINE = False\n",
+        )
+        .expect("project");
+        let both = SavePolicy {
+            final_newline: true,
+            trim_trailing_whitespace: true,
+        };
+
+        let policy = save_policy(&nested.join("notes.md"), SavePolicy::default());
+        assert_eq!(policy, SavePolicy::default());
+        let policy = save_policy(
+            &dir.join("project").join("README.md"),
+            SavePolicy::default(),
+        );
+        assert_eq!(
+            policy,
+            SavePolicy {
+                final_newline: true,
+                trim_trailing_whitespace: false,
+            }
+        );
+        assert_eq!(
+            save_policy(&nested.join("notes.txt"), SavePolicy::default()),
+            both
+        );
+
+        fs::write(
+            nested.join(".editorconfig"),
+            "root = true\n[*.md]\ntrim_trailing_whitespace = unset\n",
+        )
+        .expect("root");
+        // The root file sets nothing usable, and the ones above are skipped.
+        assert_eq!(save_policy(&nested.join("notes.md"), both), both);
+        assert_eq!(
+            save_policy(&nested.join("notes.md"), SavePolicy::default()),
+            SavePolicy::default()
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
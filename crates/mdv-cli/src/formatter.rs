@@ -0,0 +1,135 @@
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How long a formatter may run before it is killed.
+pub const FORMAT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The formatted text, or a one-line reason there is none.
+pub type FormatResult = Result<String, String>;
+
+/// Run `command` through the shell with `input` on stdin, off the UI thread.
+/// The receiver gets one result once the command exits or is killed after
+/// `timeout`.
+pub fn start(command: &str, input: String, timeout: Duration) -> Receiver<FormatResult> {
+    let (tx, rx) = mpsc::channel();
+    let command = command.to_string();
+    thread::spawn(move || {
+        let _ = tx.send(run(&command, input, timeout));
+    });
+    rx
+}
+
+/// `sh -c` everywhere but Windows, where it is `cmd /C`.
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    }
+}
+
+fn run(command: &str, input: String, timeout: Duration) -> FormatResult {
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("{command}: {err}"))?;
+
+    // Feed and drain the pipes on their own threads, so a formatter that
+    // writes before it has read everything cannot wedge on a full pipe.
+    let stdin = child.stdin.take();
+    let writer = thread::spawn(move || {
+        if let Some(mut stdin) = stdin {
+            // A formatter may exit without reading it all; its status says
+            // whether that was a failure.
+            let _ = stdin.write_all(input.as_bytes());
+        }
+    });
+    let stdout = read_to_end(child.stdout.take());
+    let stderr = read_to_end(child.stderr.take());
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|err| err.to_string())? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("timed out after {}s", timeout.as_secs_f32()));
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    let _ = writer.join();
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        return Err(stderr
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map_or_else(|| status.to_string(), str::to_string));
+    }
+    let text = String::from_utf8(stdout).map_err(|_| "output is not UTF-8".to_string())?;
+    Ok(text.replace("\r\n", "\n"))
+}
+
+fn read_to_end<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        bytes
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::time::Duration;
+
+    use super::{FORMAT_TIMEOUT, run, start};
+
+    #[test]
+    fn output_replaces_the_input_on_success() {
+        assert_eq!(
+            run("tr a-z A-Z", "# title\r\n".into(), FORMAT_TIMEOUT),
+            Ok("# TITLE\n".into())
+        );
+        let rx = start("cat", "unchanged\n".into(), FORMAT_TIMEOUT);
+        assert_eq!(rx.recv().expect("result"), Ok("unchanged\n".into()));
+    }
+
+    #[test]
+    fn failures_report_the_first_stderr_line_or_the_status() {
+        assert_eq!(
+            run(
+                "echo >&2; echo 'bad input' >&2; echo more >&2; exit 2",
+                "text".into(),
+                FORMAT_TIMEOUT
+            ),
+            Err("bad input".into())
+        );
+        let silent = run("exit 3", String::new(), FORMAT_TIMEOUT).expect_err("exit 3");
+        assert!(silent.contains('3'), "{silent}");
+        let missing = run("mdv-no-such-formatter", String::new(), FORMAT_TIMEOUT)
+            .expect_err("missing command");
+        assert!(missing.contains("not found"), "{missing}");
+    }
+
+    #[test]
+    fn slow_formatters_are_killed_at_the_timeout() {
+        let err = run("sleep 5", String::new(), Duration::from_millis(50)).expect_err("timeout");
+        assert_eq!(err, "timed out after 0.05s");
+    }
+}
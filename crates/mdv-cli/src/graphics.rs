@@ -0,0 +1,332 @@
+/// Ways a terminal can draw a picture in its cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    /// kitty's graphics protocol, also spoken by Ghostty.
+    Kitty,
+    /// iTerm2 inline images, also spoken by WezTerm.
+    Iterm2,
+    Sixel,
+}
+
+impl GraphicsProtocol {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "kitty" => Some(GraphicsProtocol::Kitty),
+            "iterm2" => Some(GraphicsProtocol::Iterm2),
+            "sixel" => Some(GraphicsProtocol::Sixel),
+            _ => None,
+        }
+    }
+}
+
+/// Where the hints about the terminal come from: the process environment
+/// when running, a fixed list in tests.
+pub trait TerminalEnv {
+    fn var(&self, name: &str) -> Option<String>;
+}
+
+pub struct ProcessEnv;
+
+impl TerminalEnv for ProcessEnv {
+    fn var(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok().filter(|value| !value.is_empty())
+    }
+}
+
+/// The protocol the terminal is known to draw with. `MDV_GRAPHICS` (`kitty`,
+/// `iterm2`, `sixel` or `none`) overrides the guess; inside tmux or screen
+/// nothing is guessed, since they drop the escape sequences.
+pub fn detect(env: &dyn TerminalEnv) -> Option<GraphicsProtocol> {
+    if let Some(forced) = env.var("MDV_GRAPHICS") {
+        return GraphicsProtocol::from_name(forced.trim());
+    }
+    let term = env.var("TERM").unwrap_or_default();
+    if env.var("TMUX").is_some() || term.starts_with("screen") || term.starts_with("tmux") {
+        return None;
+    }
+    let program = env.var("TERM_PROGRAM").unwrap_or_default();
+    if env.var("KITTY_WINDOW_ID").is_some()
+        || term == "xterm-kitty"
+        || term == "xterm-ghostty"
+        || program == "ghostty"
+    {
+        Some(GraphicsProtocol::Kitty)
+    } else if program == "iTerm.app"
+        || program == "WezTerm"
+        || env.var("LC_TERMINAL").as_deref() == Some("iTerm2")
+    {
+        Some(GraphicsProtocol::Iterm2)
+    } else if term.contains("sixel") || term.starts_with("foot") || term == "mlterm" {
+        Some(GraphicsProtocol::Sixel)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+}
+
+impl ImageFormat {
+    /// The format named by the file's first bytes, whatever its extension.
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(ImageFormat::Png)
+        } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+            Some(ImageFormat::Jpeg)
+        } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+            Some(ImageFormat::Gif)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "PNG",
+            ImageFormat::Jpeg => "JPEG",
+            ImageFormat::Gif => "GIF",
+        }
+    }
+}
+
+/// Deletes every image kitty has placed on screen.
+pub const KITTY_CLEAR: &str = "\x1b_Ga=d,q=2\x1b\\";
+
+/// Payload bytes per kitty escape sequence, as the protocol asks.
+const KITTY_CHUNK: usize = 4096;
+
+/// The escape sequence drawing `bytes` at the cursor, scaled to fit
+/// `cols` x `rows` cells. Errors are one line for the status bar.
+pub fn image_sequence(
+    protocol: GraphicsProtocol,
+    bytes: &[u8],
+    cols: u16,
+    rows: u16,
+) -> Result<String, String> {
+    let format = ImageFormat::sniff(bytes).ok_or("unsupported image format")?;
+    match protocol {
+        GraphicsProtocol::Kitty if format == ImageFormat::Png => {
+            Ok(kitty_sequence(bytes, cols, rows))
+        }
+        GraphicsProtocol::Kitty => Err(format!("kitty needs a PNG, not a {}", format.name())),
+        GraphicsProtocol::Iterm2 => Ok(iterm2_sequence(bytes, cols, rows)),
+        GraphicsProtocol::Sixel => {
+            Err("sixel needs the image decoded, which this build does not do".into())
+        }
+    }
+}
+
+/// A PNG sent whole and placed at the cursor without moving it, in
+/// base64 chunks. Only the wider side is given, so kitty keeps the aspect
+/// ratio; cells are taken to be twice as tall as they are wide.
+fn kitty_sequence(png: &[u8], cols: u16, rows: u16) -> String {
+    let size = match png_size(png) {
+        Some((width, height)) if u64::from(cols) * height > u64::from(rows) * 2 * width => {
+            format!("r={rows}")
+        }
+        _ => format!("c={cols}"),
+    };
+    let payload = base64(png);
+    let chunks: Vec<&str> = payload
+        .as_bytes()
+        .chunks(KITTY_CHUNK)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+    let mut out = String::with_capacity(payload.len() + chunks.len() * 16 + 32);
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(index + 1 < chunks.len());
+        if index == 0 {
+            out.push_str(&format!("\x1b_Ga=T,f=100,{size},C=1,q=2,m={more};"));
+        } else {
+            out.push_str(&format!("\x1b_Gm={more};"));
+        }
+        out.push_str(chunk);
+        out.push_str("\x1b\\");
+    }
+    out
+}
+
+fn iterm2_sequence(bytes: &[u8], cols: u16, rows: u16) -> String {
+    format!(
+        "\x1b]1337;File=inline=1;size={};width={cols};height={rows};preserveAspectRatio=1:{}\x07",
+        bytes.len(),
+        base64(bytes)
+    )
+}
+
+/// Width and height from the PNG header.
+fn png_size(png: &[u8]) -> Option<(u64, u64)> {
+    let header = png.get(16..24)?;
+    let width = u32::from_be_bytes(header[..4].try_into().ok()?);
+    let height = u32::from_be_bytes(header[4..].try_into().ok()?);
+    (width > 0 && height > 0).then_some((u64::from(width), u64::from(height)))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |acc, (index, &byte)| {
+            acc | u32::from(byte) << (16 - 8 * index)
+        });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                out.push(ALPHABET[(group >> (18 - 6 * index) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        GraphicsProtocol, ImageFormat, KITTY_CHUNK, TerminalEnv, base64, detect, image_sequence,
+    };
+
+    struct Env(&'static [(&'static str, &'static str)]);
+
+    impl TerminalEnv for Env {
+        fn var(&self, name: &str) -> Option<String> {
+            self.0
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    /// A PNG signature and header for a `width` x `height` image, padded
+    /// to `len` bytes.
+    fn png(width: u32, height: u32, len: usize) -> Vec<u8> {
+        let mut bytes = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        bytes.extend(width.to_be_bytes());
+        bytes.extend(height.to_be_bytes());
+        bytes.resize(len, 0);
+        bytes
+    }
+
+    #[test]
+    fn detection_reads_the_terminal_from_the_environment() {
+        type Case = (
+            &'static [(&'static str, &'static str)],
+            Option<GraphicsProtocol>,
+        );
+        let cases: [Case; 10] = [
+            (&[("TERM", "xterm-kitty")], Some(GraphicsProtocol::Kitty)),
+            (
+                &[("TERM", "xterm-256color"), ("KITTY_WINDOW_ID", "3")],
+                Some(GraphicsProtocol::Kitty),
+            ),
+            (
+                &[("TERM_PROGRAM", "ghostty")],
+                Some(GraphicsProtocol::Kitty),
+            ),
+            (
+                &[("TERM_PROGRAM", "iTerm.app")],
+                Some(GraphicsProtocol::Iterm2),
+            ),
+            (&[("LC_TERMINAL", "iTerm2")], Some(GraphicsProtocol::Iterm2)),
+            (
+                &[("TERM_PROGRAM", "WezTerm")],
+                Some(GraphicsProtocol::Iterm2),
+            ),
+            (&[("TERM", "foot")], Some(GraphicsProtocol::Sixel)),
+            (&[("TERM", "xterm-256color")], None),
+            (
+                &[("TERM", "xterm-kitty"), ("TMUX", "/tmp/tmux-1/default")],
+                None,
+            ),
+            (
+                &[("TERM", "screen-256color"), ("LC_TERMINAL", "iTerm2")],
+                None,
+            ),
+        ];
+        for (env, expected) in cases {
+            assert_eq!(detect(&Env(env)), expected, "{env:?}");
+        }
+    }
+
+    #[test]
+    fn mdv_graphics_overrides_the_guess() {
+        let env = Env(&[("MDV_GRAPHICS", "iTerm2"), ("TERM", "xterm-kitty")]);
+        assert_eq!(detect(&env), Some(GraphicsProtocol::Iterm2));
+        let env = Env(&[("MDV_GRAPHICS", "kitty"), ("TMUX", "1")]);
+        assert_eq!(detect(&env), Some(GraphicsProtocol::Kitty));
+        let env = Env(&[("MDV_GRAPHICS", "none"), ("TERM", "xterm-kitty")]);
+        assert_eq!(detect(&env), None);
+    }
+
+    #[test]
+    fn base64_pads_partial_groups() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar\xff"), "Zm9vYmFy/w==");
+    }
+
+    #[test]
+    fn kitty_sequence_matches_the_protocol() {
+        // Wide image: the width is fixed and kitty works out the rows.
+        let wide = png(200, 50, 24);
+        assert_eq!(
+            image_sequence(GraphicsProtocol::Kitty, &wide, 40, 10).expect("kitty"),
+            "\x1b_Ga=T,f=100,c=40,C=1,q=2,m=0;iVBORw0KGgoAAAANSUhEUgAAAMgAAAAy\x1b\\"
+        );
+        // Tall image: the rows are fixed instead.
+        let tall = png(50, 200, 24);
+        assert!(
+            image_sequence(GraphicsProtocol::Kitty, &tall, 40, 10)
+                .expect("kitty")
+                .starts_with("\x1b_Ga=T,f=100,r=10,C=1,q=2,m=0;")
+        );
+    }
+
+    #[test]
+    fn kitty_sends_large_images_in_chunks() {
+        let big = png(100, 10, 4000);
+        let out = image_sequence(GraphicsProtocol::Kitty, &big, 40, 10).expect("kitty");
+        let parts: Vec<&str> = out.split_terminator("\x1b\\").collect();
+        assert_eq!(parts.len(), 2);
+        assert!(parts[0].starts_with("\x1b_Ga=T,f=100,c=40,C=1,q=2,m=1;"));
+        assert_eq!(
+            parts[0].split_once(';').expect("payload").1.len(),
+            KITTY_CHUNK
+        );
+        assert!(parts[1].starts_with("\x1b_Gm=0;"));
+        let payload: String = parts
+            .iter()
+            .map(|part| part.split_once(';').expect("payload").1)
+            .collect();
+        assert_eq!(payload, base64(&big));
+    }
+
+    #[test]
+    fn unsupported_formats_and_protocols_are_errors() {
+        let jpeg = [0xff, 0xd8, 0xff, 0xe0, 0, 0];
+        assert_eq!(ImageFormat::sniff(&jpeg), Some(ImageFormat::Jpeg));
+        assert_eq!(
+            image_sequence(GraphicsProtocol::Kitty, &jpeg, 10, 5),
+            Err("kitty needs a PNG, not a JPEG".into())
+        );
+        assert_eq!(
+            image_sequence(GraphicsProtocol::Iterm2, b"<svg/>", 10, 5),
+            Err("unsupported image format".into())
+        );
+        assert!(
+            image_sequence(GraphicsProtocol::Sixel, &png(1, 1, 24), 10, 5)
+                .expect_err("sixel")
+                .starts_with("sixel needs")
+        );
+        assert_eq!(
+            image_sequence(GraphicsProtocol::Iterm2, b"GIF89a", 10, 5).expect("iterm2"),
+            "\x1b]1337;File=inline=1;size=6;width=10;height=5;preserveAspectRatio=1:R0lGODlh\x07"
+        );
+    }
+}
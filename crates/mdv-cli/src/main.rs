@@ -1,21 +1,42 @@
 mod app;
-mod stream;
+mod bench;
+mod clipboard;
+mod completion;
+mod config;
+mod editorconfig;
+mod formatter;
+mod graphics;
+mod opener;
+mod outline;
+mod prefs;
+mod resume;
+mod spelling;
 mod ui;
-mod watcher;
 
-use std::fs;
-use std::io::{self, IsTerminal, Read, Write};
-use std::path::PathBuf;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Result, bail};
-use clap::{CommandFactory, Parser, ValueEnum};
-use mdv_core::render_preview_lines;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum};
+use mdv_core::{
+    FrontMatterStyle, LintKind, LoadedText, RenderOptions, RenderedLine, SavePolicy,
+    StreamRenderer, decode_text, lint_bytes, read_text, render, render_html,
+};
+
+use crate::ui::ansi;
+use crate::ui::theme::{
+    ThemeOverrides, ThemeTokens, build_theme, detect_background, resolve_auto, style_for_segment,
+};
+
+const MIN_PREVIEW_WIDTH: u16 = 8;
 
 #[derive(Debug, Parser)]
 #[command(name = "mdv", bin_name = "mdv", about = "Terminal markdown visualizer")]
 struct Cli {
-    /// Markdown file path
-    path: Option<PathBuf>,
+    /// Markdown file paths; switch between them with Ctrl+PageUp/Ctrl+PageDown
+    path: Vec<PathBuf>,
 
     /// Stream markdown from stdin
     #[arg(long, default_value_t = false)]
@@ -29,6 +50,27 @@ struct Cli {
     #[arg(long, default_value_t = false)]
     no_watch: bool,
 
+    /// Say when a local .md file the document links to changes (F5 opens it)
+    #[arg(long, default_value_t = false, conflicts_with = "no_watch")]
+    watch_links: bool,
+
+    /// Edit what stdin sent once it closes, as an unsaved buffer
+    #[arg(
+        long,
+        default_value_t = false,
+        requires = "stream",
+        conflicts_with = "readonly"
+    )]
+    edit_stream: bool,
+
+    /// Most stdin to keep in stream mode, e.g. 512k or 10m; older lines are dropped
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, requires = "stream")]
+    stream_buffer: Option<usize>,
+
+    /// Keep the view on the end of the file as it grows (Ctrl+End toggles)
+    #[arg(long, default_value_t = false)]
+    follow: bool,
+
     /// Show perf info in status line
     #[arg(long, default_value_t = false)]
     perf: bool,
@@ -41,9 +83,140 @@ struct Cli {
     #[arg(long, default_value_t = false)]
     no_color: bool,
 
+    /// When to emit ANSI color
+    #[arg(long, value_enum, default_value_t = CliColor::Auto)]
+    color: CliColor,
+
     /// Initial view mode
     #[arg(long, value_enum, default_value_t = CliFocus::Editor)]
     focus: CliFocus,
+
+    /// Autosave a dirty file every N seconds (0 disables)
+    #[arg(long, value_name = "SECONDS", default_value_t = 0)]
+    autosave: u64,
+
+    /// Hide the editor and show only the preview
+    #[arg(long, default_value_t = false)]
+    view_only: bool,
+
+    /// Open the file at this line (1-based); also written as `path:line[:col]`
+    #[arg(long, value_name = "N", conflicts_with = "stream")]
+    line: Option<usize>,
+
+    /// Read one file in a minimal scroller instead of the editor (q quits, / searches)
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["stream", "check", "export_html", "outline", "bench_render"]
+    )]
+    pager: bool,
+
+    /// Wrap long lines in the editor pane instead of scrolling sideways
+    #[arg(long, default_value_t = false)]
+    wrap: bool,
+
+    /// Config file path (defaults to $XDG_CONFIG_HOME/mdv/config.toml)
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Render width for non-TTY output (overrides COLUMNS)
+    #[arg(long, value_parser = parse_width)]
+    width: Option<u16>,
+
+    /// First source line to print when piped (1-based); the TUI starts there
+    #[arg(long, value_name = "LINE")]
+    from: Option<usize>,
+
+    /// Last source line to print when piped (1-based, inclusive)
+    #[arg(long, value_name = "LINE")]
+    to: Option<usize>,
+
+    /// Stop piped output after N rendered lines, without rendering the rest
+    #[arg(long, value_name = "N", value_parser = parse_max_lines)]
+    max_lines: Option<usize>,
+
+    /// Show :shortcode: emoji in piped output too (the preview always does)
+    #[arg(long, default_value_t = false)]
+    emoji: bool,
+
+    /// Keep setext underlines and {#id} heading attributes in piped output
+    #[arg(long, default_value_t = false)]
+    source_headings: bool,
+
+    /// Do not reopen files at the cursor and scroll position they were left at
+    #[arg(long, default_value_t = false)]
+    no_session: bool,
+
+    /// Shell command Ctrl+Alt+F pipes the buffer through (overrides [editor] formatter)
+    #[arg(long, value_name = "CMD")]
+    formatter: Option<String>,
+
+    /// Underline words missing from the word list (F7 next, Alt+A add word)
+    #[arg(long, default_value_t = false)]
+    spell: bool,
+
+    /// End the file with exactly one newline when saving
+    #[arg(long, default_value_t = false)]
+    final_newline: bool,
+
+    /// Strip trailing spaces when saving (not in code blocks or hard breaks)
+    #[arg(long, default_value_t = false)]
+    trim_trailing_whitespace: bool,
+
+    /// Lint the files and print `file:line: message` for each problem, exiting
+    /// 1 if there are any; pick checks with --check=fences,links,anchors,tables,utf8
+    #[arg(
+        long,
+        value_name = "CHECKS",
+        num_args = 0..=1,
+        require_equals = true,
+        value_delimiter = ',',
+        value_parser = parse_check,
+        conflicts_with_all = ["stream", "export_html"]
+    )]
+    check: Option<Vec<LintKind>>,
+
+    /// Print one `LINE:## Heading` line per heading and exit; with several
+    /// files each line starts with the file
+    #[arg(
+        long,
+        conflicts_with_all = ["stream", "check", "export_html", "bench_render"]
+    )]
+    outline: bool,
+
+    /// Deepest heading level --outline prints (1-6)
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        value_parser = clap::value_parser!(u8).range(1..=6),
+        requires = "outline"
+    )]
+    max_depth: Option<u8>,
+
+    /// How --outline prints the headings
+    #[arg(long, value_enum, default_value_t = OutlineFormat::Text, requires = "outline")]
+    format: OutlineFormat,
+
+    /// Write the file as a standalone HTML page to OUTPUT (`-` for stdout)
+    #[arg(long, value_name = "OUTPUT", conflicts_with_all = ["stream", "from", "to"])]
+    export_html: Option<PathBuf>,
+
+    /// Render the file N times without the TUI and print timings (--width, default 80)
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = parse_iterations,
+        conflicts_with_all = ["stream", "check", "export_html"]
+    )]
+    bench_render: Option<usize>,
+
+    /// Benchmark a generated document of this size, e.g. 512k, instead of a file
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, requires = "bench_render")]
+    bench_size: Option<usize>,
+
+    /// How --bench-render prints its timings
+    #[arg(long, value_enum, default_value_t = BenchFormat::Text, requires = "bench_render")]
+    bench_format: BenchFormat,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
@@ -51,6 +224,14 @@ enum CliTheme {
     Auto,
     Default,
     HighContrast,
+    Light,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+enum CliColor {
+    Auto,
+    Always,
+    Never,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
@@ -60,28 +241,134 @@ enum CliFocus {
     View,
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+enum BenchFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+enum OutlineFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone)]
+struct PrintOptions {
+    width: Option<u16>,
+    color: bool,
+    theme: app::ThemeChoice,
+    overrides: ThemeOverrides,
+    config_warnings: Vec<String>,
+    /// Zero-based source lines whose output is printed.
+    lines: RangeInclusive<usize>,
+    source_headings: bool,
+    emoji: bool,
+    max_lines: Option<usize>,
+}
+
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
     let force_tui = std::env::var("MDV_FORCE_TUI").ok().as_deref() == Some("1");
+    let mut loaded = config::load(cli.config.as_deref());
+    if let Some(command) = &cli.formatter {
+        loaded.config.editor.formatter = Some(command.clone());
+    }
+
+    // Saved prefs fill in whatever was not passed explicitly on this run.
+    let prefs_path = prefs::state_path();
+    let saved = prefs_path.as_deref().map(prefs::load).unwrap_or_default();
+    let explicit = explicit_ui_flags(&cli, &matches);
+    let ui_prefs = saved.overlaid_with(explicit);
+    let theme = ui_prefs.theme.unwrap_or(theme_choice(cli.theme));
+    let no_color = ui_prefs.no_color.unwrap_or(false);
+    let focus = ui_prefs.focus.unwrap_or(focus_choice(cli.focus));
+    let lines = source_lines(cli.from, cli.to)?;
+
+    let print_opts = PrintOptions {
+        width: cli.width,
+        color: color_enabled(cli.color, no_color, io::stdout().is_terminal()),
+        theme,
+        overrides: loaded.config.theme.clone(),
+        config_warnings: loaded.warnings.clone(),
+        lines,
+        source_headings: cli.source_headings,
+        emoji: cli.emoji,
+        max_lines: cli.max_lines,
+    };
+
+    if let Some(checks) = &cli.check {
+        if cli.path.is_empty() {
+            bail!("--check needs at least one path");
+        }
+        let checks = if checks.is_empty() {
+            &LintKind::ALL[..]
+        } else {
+            checks.as_slice()
+        };
+        if !check_files(&cli.path, checks)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if cli.outline {
+        if cli.path.is_empty() {
+            bail!("--outline needs at least one path");
+        }
+        return print_outlines(&cli.path, cli.max_depth, cli.format);
+    }
+
+    if let Some(output) = &cli.export_html {
+        let [path] = cli.path.as_slice() else {
+            bail!("--export-html needs exactly one input path");
+        };
+        return export_html(path, output);
+    }
+
+    if let Some(iterations) = cli.bench_render {
+        return bench_render(&cli, iterations);
+    }
 
     if cli.stream {
-        if cli.path.is_some() {
+        if !cli.path.is_empty() {
             bail!("path arg not allowed with --stream");
         }
 
         if !io::stdout().is_terminal() && !force_tui {
-            let mut buf = String::new();
-            io::stdin().read_to_string(&mut buf)?;
-            print_preview(&buf)?;
+            print_stream(io::stdin().lock(), print_opts)?;
             return Ok(());
         }
 
-        let mut app = app::App::new_stream(cli.perf)?;
-        apply_ui_flags(&mut app, cli.theme, cli.no_color, cli.focus);
+        let mut app = app::App::new_stream(cli.perf, cli.stream_buffer)?;
+        app.set_edit_stream(cli.edit_stream);
+        apply_ui_flags(
+            &mut app,
+            theme,
+            no_color,
+            focus,
+            ui_prefs.split,
+            cli.view_only,
+            cli.wrap,
+        );
+        apply_config(&mut app, &loaded, prefs_path);
+        app.set_follow(cli.follow);
         return app.run();
     }
 
-    let Some(path) = cli.path else {
+    if cli.pager && (io::stdout().is_terminal() || force_tui) {
+        let [path] = cli.path.as_slice() else {
+            bail!("--pager takes exactly one path");
+        };
+        let file = read_initial_text(path)?;
+        let mut pager = app::pager::Pager::new(path.display().to_string(), file.text);
+        return pager.run(theme, no_color, &loaded.config.theme);
+    }
+
+    let save_policy = forced_save_policy(&cli);
+    let mut paths = cli.path.into_iter();
+    let Some((path, position)) = paths.next().map(|path| open_position(path, cli.line)) else {
         if (!io::stdin().is_terminal() || !io::stdout().is_terminal()) && !force_tui {
             let mut cmd = Cli::command();
             cmd.print_help()?;
@@ -90,41 +377,281 @@ fn main() -> Result<()> {
         }
 
         let mut app = app::App::new_home(cli.readonly, !cli.no_watch, cli.perf)?;
-        apply_ui_flags(&mut app, cli.theme, cli.no_color, cli.focus);
+        apply_ui_flags(
+            &mut app,
+            theme,
+            no_color,
+            focus,
+            ui_prefs.split,
+            cli.view_only,
+            cli.wrap,
+        );
+        apply_config(&mut app, &loaded, prefs_path);
+        apply_resume(&mut app, cli.no_session, explicit.focus, cli.view_only);
+        app.set_spell(cli.spell);
+        app.set_save_policy(loaded.config.editor.save_policy, save_policy);
+        app.set_autosave(cli.autosave);
+        app.set_watch_links(cli.watch_links);
         return app.run();
     };
 
-    let text = read_initial_text(&path)?;
+    let file = read_initial_text(&path)?;
+    let mut others = Vec::new();
+    for path in paths {
+        let (path, _) = split_position(path);
+        let file = read_initial_text(&path)?;
+        others.push((path, file));
+    }
     if (!io::stdin().is_terminal() || !io::stdout().is_terminal()) && !force_tui {
-        print_preview(&text)?;
+        print_preview(&file.text, print_opts.clone())?;
+        for (_, other) in &others {
+            print!("\n\n");
+            print_preview(&other.text, print_opts.clone())?;
+        }
         return Ok(());
     }
 
-    let mut app = app::App::new_file(path, cli.readonly, !cli.no_watch, cli.perf, text)?;
-    apply_ui_flags(&mut app, cli.theme, cli.no_color, cli.focus);
+    let mut app = app::App::new_file(path, cli.readonly, !cli.no_watch, cli.perf, file.text)?;
+    apply_ui_flags(
+        &mut app,
+        theme,
+        no_color,
+        focus,
+        ui_prefs.split,
+        cli.view_only,
+        cli.wrap,
+    );
+    apply_config(&mut app, &loaded, prefs_path);
+    app.set_file_encoding(file.line_ending, file.invalid_sequences);
+    apply_resume(&mut app, cli.no_session, explicit.focus, cli.view_only);
+    app.set_spell(cli.spell);
+    app.set_save_policy(loaded.config.editor.save_policy, save_policy);
+    if let Some((line, col)) = position {
+        app.open_at(line, col);
+    } else if let Some(line) = cli.from.or(cli.to) {
+        app.set_initial_line(line);
+    }
+    app.set_follow(cli.follow);
+    for (path, file) in others {
+        app.add_file(path, file)?;
+    }
+    app.set_autosave(cli.autosave);
+    app.set_watch_links(cli.watch_links);
     app.run()
 }
 
-fn apply_ui_flags(app: &mut app::App, theme: CliTheme, no_color: bool, focus: CliFocus) {
-    let theme = match theme {
+fn forced_save_policy(cli: &Cli) -> SavePolicy {
+    SavePolicy {
+        final_newline: cli.final_newline,
+        trim_trailing_whitespace: cli.trim_trailing_whitespace,
+    }
+}
+
+fn theme_choice(theme: CliTheme) -> app::ThemeChoice {
+    match theme {
         CliTheme::Auto => app::ThemeChoice::Auto,
         CliTheme::Default => app::ThemeChoice::Default,
         CliTheme::HighContrast => app::ThemeChoice::HighContrast,
-    };
-    let focus = match focus {
+        CliTheme::Light => app::ThemeChoice::Light,
+    }
+}
+
+fn color_enabled(color: CliColor, no_color: bool, stdout_is_tty: bool) -> bool {
+    if no_color {
+        return false;
+    }
+    match color {
+        CliColor::Auto => stdout_is_tty,
+        CliColor::Always => true,
+        CliColor::Never => false,
+    }
+}
+
+fn focus_choice(focus: CliFocus) -> app::PaneFocus {
+    match focus {
         CliFocus::Editor => app::PaneFocus::Editor,
         CliFocus::View => app::PaneFocus::Preview,
-    };
+    }
+}
+
+/// UI flags the user actually typed; clap defaults do not count.
+fn explicit_ui_flags(cli: &Cli, matches: &ArgMatches) -> prefs::Prefs {
+    let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    prefs::Prefs {
+        theme: from_cli("theme").then(|| theme_choice(cli.theme)),
+        no_color: (from_cli("no_color") || from_cli("color"))
+            .then_some(cli.no_color || cli.color == CliColor::Never),
+        focus: from_cli("focus").then(|| focus_choice(cli.focus)),
+        split: None,
+    }
+}
+
+fn apply_ui_flags(
+    app: &mut app::App,
+    theme: app::ThemeChoice,
+    no_color: bool,
+    focus: app::PaneFocus,
+    split: Option<u16>,
+    view_only: bool,
+    soft_wrap: bool,
+) {
     app.set_theme(theme);
     app.set_no_color(no_color);
     app.set_initial_focus(focus);
+    if let Some(split) = split {
+        app.set_split_percent(split);
+    }
+    app.set_view_only(view_only);
+    app.set_soft_wrap(soft_wrap);
 }
 
-fn print_preview(text: &str) -> io::Result<()> {
-    let width = preview_width_from_env();
-    let stdout = io::stdout();
-    let lock = stdout.lock();
-    print_preview_to(text, width, io::BufWriter::new(lock))
+fn apply_config(app: &mut app::App, loaded: &config::LoadedConfig, prefs_path: Option<PathBuf>) {
+    app.set_theme_overrides(loaded.config.theme.clone());
+    app.set_indent_width(loaded.config.editor.indent);
+    app.set_continue_lists(loaded.config.editor.continue_lists);
+    app.set_keymap(loaded.config.keys.clone());
+    app.set_snippets(loaded.config.snippets.clone());
+    app.set_keep_before_external(loaded.config.editor.keep_before_external);
+    app.set_backup_dir(
+        loaded
+            .config
+            .editor
+            .reload_backups
+            .then(config::config_dir)
+            .flatten()
+            .map(|dir| dir.join("backups")),
+    );
+    app.set_large_file_threshold(loaded.config.editor.large_file_bytes);
+    app.set_watch_debounce(loaded.config.editor.watch_debounce);
+    app.set_formatter(loaded.config.editor.formatter.clone());
+    app.set_spell_dictionaries(
+        loaded.config.editor.spell_dictionary.clone(),
+        spelling::user_dictionary_path(),
+    );
+    app.set_prefs_path(prefs_path);
+    app.show_config_warnings(&loaded.warnings);
+}
+
+/// Reopen files where they were left unless `--no-session`. A `--focus`
+/// typed on this run still wins over the pane a file was left in.
+fn apply_resume(
+    app: &mut app::App,
+    no_session: bool,
+    focus: Option<app::PaneFocus>,
+    view_only: bool,
+) {
+    if no_session {
+        return;
+    }
+    app.set_resume_path(resume::state_path());
+    if let Some(focus) = focus.filter(|_| !view_only) {
+        app.set_initial_focus(focus);
+    }
+}
+
+/// `--from`/`--to` as zero-based source lines. Values past either end are
+/// clamped; a start after the end is an error.
+fn source_lines(from: Option<usize>, to: Option<usize>) -> Result<RangeInclusive<usize>> {
+    if let (Some(from), Some(to)) = (from, to)
+        && from > to
+    {
+        bail!("--from {from} is after --to {to}");
+    }
+    let start = from.map_or(0, |line| line.saturating_sub(1));
+    let end = to.map_or(usize::MAX, |line| line.saturating_sub(1));
+    Ok(start..=end)
+}
+
+fn parse_width(value: &str) -> Result<u16, String> {
+    let width = value
+        .parse::<u16>()
+        .map_err(|_| format!("invalid width: {value}"))?;
+    if width < MIN_PREVIEW_WIDTH {
+        return Err(format!("width must be at least {MIN_PREVIEW_WIDTH}"));
+    }
+    Ok(width)
+}
+
+fn parse_max_lines(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("max lines must be at least 1".into()),
+        Ok(count) => Ok(count),
+        Err(_) => Err(format!("invalid line count: {value}")),
+    }
+}
+
+/// Bytes with an optional `k`, `m` or `g` suffix (powers of 1024).
+fn parse_size(value: &str) -> Result<usize, String> {
+    let invalid = || format!("invalid size: {value} (use bytes or a k/m/g suffix)");
+    let lower = value.trim().to_ascii_lowercase();
+    let (digits, scale) = match lower.char_indices().last() {
+        Some((idx, 'k')) => (&lower[..idx], 1 << 10),
+        Some((idx, 'm')) => (&lower[..idx], 1 << 20),
+        Some((idx, 'g')) => (&lower[..idx], 1 << 30),
+        _ => (lower.as_str(), 1),
+    };
+    let size = digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|count| count.checked_mul(scale))
+        .ok_or_else(invalid)?;
+    if size == 0 {
+        return Err("size must be more than 0".into());
+    }
+    Ok(size)
+}
+
+fn print_preview(text: &str, opts: PrintOptions) -> io::Result<()> {
+    let options = print_render_options(&opts);
+    let out = io::BufWriter::new(io::stdout().lock());
+    match print_theme(&opts) {
+        Some(theme) => print_preview_colored_to(text, &options, &opts.lines, &theme, out),
+        None => print_preview_to(text, &options, &opts.lines, out),
+    }
+}
+
+/// `--stream` into a pipe: print each block as soon as the text after it
+/// shows it is complete, rather than once stdin closes. What `--max-lines`
+/// keeps depends on the whole text, so with it stdin is read to the end
+/// first.
+fn print_stream(mut input: impl BufRead, opts: PrintOptions) -> io::Result<()> {
+    if opts.max_lines.is_some() {
+        let mut text = String::new();
+        input.read_to_string(&mut text)?;
+        return print_preview(&text, opts);
+    }
+    let options = print_render_options(&opts);
+    let theme = print_theme(&opts);
+    let out = io::BufWriter::new(io::stdout().lock());
+    print_stream_to(input, &options, &opts.lines, theme.as_ref(), out)
+}
+
+fn print_render_options(opts: &PrintOptions) -> RenderOptions {
+    // Front matter is printed as written, so piped output round-trips.
+    RenderOptions::new(resolve_preview_width(opts.width))
+        .with_front_matter(FrontMatterStyle::Expanded)
+        .with_source_headings(opts.source_headings)
+        .with_emoji(opts.emoji)
+        .with_max_lines(opts.max_lines)
+}
+
+/// The palette for colored output, or `None` when color is off.
+fn print_theme(opts: &PrintOptions) -> Option<ThemeTokens> {
+    if !opts.color {
+        return None;
+    }
+    // The config only styles colored output, so only then is it worth a warning.
+    for warning in &opts.config_warnings {
+        eprintln!("mdv: config warning: {warning}");
+    }
+    // Piped output cannot answer a terminal query, so only COLORFGBG applies.
+    let colorfgbg = std::env::var("COLORFGBG").ok();
+    let choice = resolve_auto(opts.theme, detect_background(colorfgbg.as_deref(), None));
+    Some(build_theme(choice, false, Some(&opts.overrides)))
+}
+
+fn resolve_preview_width(flag: Option<u16>) -> u16 {
+    flag.unwrap_or_else(preview_width_from_env)
 }
 
 fn preview_width_from_env() -> u16 {
@@ -134,21 +661,258 @@ fn preview_width_from_env() -> u16 {
         .unwrap_or(80)
 }
 
-fn read_initial_text(path: &PathBuf) -> Result<String> {
-    match fs::read_to_string(path) {
-        Ok(text) => Ok(text),
-        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+/// 1-based line and column to open a file at.
+type Position = (usize, Option<usize>);
+
+/// Split the `:line` or `:line:col` that grep and compilers print off
+/// `path`, but only when `path` does not exist and the file without the
+/// suffix does, so a file with a colon in its name still opens.
+fn split_position(path: PathBuf) -> (PathBuf, Option<Position>) {
+    if path.exists() {
+        return (path, None);
+    }
+    let Some(text) = path.to_str() else {
+        return (path, None);
+    };
+    let number = |digits: &str| {
+        (!digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit()))
+            .then(|| digits.parse().ok())
+            .flatten()
+    };
+    if let Some((rest, last)) = text.rsplit_once(':')
+        && let Some(last) = number(last)
+    {
+        if Path::new(rest).exists() {
+            return (PathBuf::from(rest), Some((last, None)));
+        }
+        if let Some((base, line)) = rest.rsplit_once(':')
+            && let Some(line) = number(line)
+            && Path::new(base).exists()
+        {
+            return (PathBuf::from(base), Some((line, Some(last))));
+        }
+    }
+    (path, None)
+}
+
+/// The first path and where to open it: `--line` wins over a suffix, which
+/// is still split off.
+fn open_position(path: PathBuf, line: Option<usize>) -> (PathBuf, Option<Position>) {
+    let (path, position) = split_position(path);
+    (path, line.map(|line| (line, None)).or(position))
+}
+
+fn read_initial_text(path: &Path) -> Result<LoadedText> {
+    match read_text(path) {
+        Ok(file) => Ok(file),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(decode_text(b"")),
         Err(err) => Err(err.into()),
     }
 }
 
-fn print_preview_to<W: Write>(text: &str, width: u16, mut out: W) -> io::Result<()> {
-    let lines = render_preview_lines(text, width);
-    for (i, line) in lines.iter().enumerate() {
-        if i > 0 {
+fn parse_iterations(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("iterations must be at least 1".into()),
+        Ok(count) => Ok(count),
+        Err(_) => Err(format!("invalid iteration count: {value}")),
+    }
+}
+
+fn parse_check(value: &str) -> Result<LintKind, String> {
+    LintKind::from_name(value.trim()).ok_or_else(|| {
+        let names: Vec<&str> = LintKind::ALL.iter().map(|kind| kind.name()).collect();
+        format!("unknown check: {value} (use {})", names.join(", "))
+    })
+}
+
+/// Print the `checks` problems of every file as `file:line: message`, and
+/// unreadable files on stderr. Returns whether all of them were clean.
+fn check_files(paths: &[PathBuf], checks: &[LintKind]) -> Result<bool> {
+    let mut clean = true;
+    let mut stdout = io::stdout().lock();
+    for path in paths {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                eprintln!("{}: {err}", path.display());
+                clean = false;
+                continue;
+            }
+        };
+        for issue in lint_bytes(&bytes) {
+            if checks.contains(&issue.kind) {
+                writeln!(
+                    stdout,
+                    "{}:{}: {}",
+                    path.display(),
+                    issue.line,
+                    issue.message
+                )?;
+                clean = false;
+            }
+        }
+    }
+    stdout.flush()?;
+    Ok(clean)
+}
+
+fn print_outlines(paths: &[PathBuf], max_depth: Option<u8>, format: OutlineFormat) -> Result<()> {
+    let mut outlines = Vec::with_capacity(paths.len());
+    for path in paths {
+        let file = match read_text(path) {
+            Ok(file) => file,
+            Err(err) => bail!("cannot read {}: {err}", path.display()),
+        };
+        outlines.push(outline::FileOutline::new(
+            path.display().to_string(),
+            &file.text,
+            max_depth,
+        ));
+    }
+    let stdout = io::stdout().lock();
+    match format {
+        OutlineFormat::Text => outline::write_text(&outlines, stdout)?,
+        OutlineFormat::Json => outline::write_json(&outlines, stdout)?,
+    }
+    Ok(())
+}
+
+fn bench_render(cli: &Cli, iterations: usize) -> Result<()> {
+    let (source, markdown) = match (cli.path.as_slice(), cli.bench_size) {
+        ([], Some(size)) => ("synthetic".to_string(), bench::synthetic_document(size)),
+        ([path], None) => match read_text(path) {
+            Ok(file) => (path.display().to_string(), file.text),
+            Err(err) => bail!("cannot read {}: {err}", path.display()),
+        },
+        ([], None) => bail!("--bench-render needs a path or --bench-size"),
+        (_, Some(_)) => bail!("--bench-size generates the document; leave out the path"),
+        (_, None) => bail!("--bench-render takes exactly one path"),
+    };
+    let report = bench::run(&source, &markdown, cli.width.unwrap_or(80), iterations);
+    let stdout = io::stdout().lock();
+    match cli.bench_format {
+        BenchFormat::Text => bench::write_text(&report, stdout)?,
+        BenchFormat::Json => bench::write_json(&report, stdout)?,
+    }
+    Ok(())
+}
+
+fn export_html(input: &Path, output: &Path) -> Result<()> {
+    let file = match read_text(input) {
+        Ok(file) => file,
+        Err(err) => bail!("cannot read {}: {err}", input.display()),
+    };
+    let html = render_html(&file.text);
+    if output == Path::new("-") {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(html.as_bytes())?;
+        stdout.flush()?;
+    } else if let Err(err) = std::fs::write(output, html) {
+        bail!("cannot write {}: {err}", output.display());
+    }
+    Ok(())
+}
+
+/// The rendered lines that come from the source `lines`, at most
+/// `options.max_lines` of them. The text before the range is rendered too so
+/// a range starting inside a code fence keeps its styling; the budget only
+/// cuts rendering short when the range starts at the top.
+fn picked_lines(
+    text: &str,
+    options: &RenderOptions,
+    lines: &RangeInclusive<usize>,
+) -> Vec<RenderedLine> {
+    let limit = options.max_lines.unwrap_or(usize::MAX);
+    let options = if *lines.start() == 0 {
+        options.clone()
+    } else {
+        options.clone().with_max_lines(None)
+    };
+    render(text, &options)
+        .lines
+        .into_iter()
+        .filter(|line| lines.contains(&line.source_line))
+        .take(limit)
+        .collect()
+}
+
+/// Print the rendered lines that come from the source `lines`.
+fn print_preview_to<W: Write>(
+    text: &str,
+    options: &RenderOptions,
+    lines: &RangeInclusive<usize>,
+    mut out: W,
+) -> io::Result<()> {
+    let picked = picked_lines(text, &options.clone().with_segments(false), lines);
+    write_lines(&mut out, &picked, None, &mut 0)
+}
+
+fn print_preview_colored_to<W: Write>(
+    text: &str,
+    options: &RenderOptions,
+    lines: &RangeInclusive<usize>,
+    theme: &ThemeTokens,
+    mut out: W,
+) -> io::Result<()> {
+    let picked = picked_lines(text, options, lines);
+    write_lines(&mut out, &picked, Some(theme), &mut 0)
+}
+
+/// What [`print_preview_to`], or with a theme [`print_preview_colored_to`],
+/// prints for the whole of `input`, written as `input` settles each block.
+/// `options.max_lines` is not applied.
+fn print_stream_to<R: BufRead, W: Write>(
+    mut input: R,
+    options: &RenderOptions,
+    lines: &RangeInclusive<usize>,
+    theme: Option<&ThemeTokens>,
+    mut out: W,
+) -> io::Result<()> {
+    let options = options.clone().with_segments(theme.is_some());
+    let mut renderer = StreamRenderer::new(options);
+    let mut printed = 0;
+    let mut chunk = String::new();
+    let picked = |rendered: Vec<RenderedLine>| -> Vec<RenderedLine> {
+        rendered
+            .into_iter()
+            .filter(|line| lines.contains(&line.source_line))
+            .collect()
+    };
+    while input.read_line(&mut chunk)? > 0 {
+        let settled = picked(renderer.push(&chunk));
+        chunk.clear();
+        write_lines(&mut out, &settled, theme, &mut printed)?;
+    }
+    write_lines(&mut out, &picked(renderer.finish()), theme, &mut printed)
+}
+
+/// Write `lines` after the `printed` ones already out, a newline between
+/// each, and flush. A theme colors each segment.
+fn write_lines<W: Write>(
+    out: &mut W,
+    lines: &[RenderedLine],
+    theme: Option<&ThemeTokens>,
+    printed: &mut usize,
+) -> io::Result<()> {
+    for line in lines {
+        if *printed > 0 {
             out.write_all(b"\n")?;
         }
-        out.write_all(line.as_bytes())?;
+        *printed += 1;
+        let Some(theme) = theme else {
+            out.write_all(line.text.as_bytes())?;
+            continue;
+        };
+        for segment in &line.segments {
+            match ansi::sgr(style_for_segment(theme, segment.kind)) {
+                Some(start) => {
+                    out.write_all(start.as_bytes())?;
+                    out.write_all(segment.text.as_bytes())?;
+                    out.write_all(ansi::RESET.as_bytes())?;
+                }
+                None => out.write_all(segment.text.as_bytes())?,
+            }
+        }
     }
     out.flush()
 }
@@ -161,11 +925,18 @@ mod tests {
     use std::sync::Mutex;
     use std::time::{SystemTime, UNIX_EPOCH};
 
+    use mdv_core::RenderOptions;
+
     use super::{
-        CliFocus, CliTheme, apply_ui_flags, preview_width_from_env, print_preview_to,
-        read_initial_text,
+        CliColor, PrintOptions, apply_ui_flags, color_enabled, open_position, parse_size,
+        parse_width, preview_width_from_env, print_preview_colored_to, print_preview_to,
+        print_render_options, print_stream_to, read_initial_text, resolve_preview_width,
+        source_lines, split_position,
     };
+
+    const ALL_LINES: std::ops::RangeInclusive<usize> = 0..=usize::MAX;
     use crate::app::{App, PaneFocus, ThemeChoice};
+    use crate::ui::theme::build_theme;
 
     static ENV_LOCK: Mutex<()> = Mutex::new(());
 
@@ -177,6 +948,59 @@ mod tests {
         std::env::temp_dir().join(format!("mdv-main-test-{name}-{nanos}.md"))
     }
 
+    #[test]
+    fn path_suffixes_split_only_when_the_stripped_file_exists() {
+        let dir =
+            std::env::temp_dir().join(format!("mdv-main-test-position-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("dir");
+        let notes = dir.join("notes.md");
+        fs::write(&notes, "x").expect("notes");
+        let at = |suffix: &str| PathBuf::from(format!("{}{suffix}", notes.display()));
+
+        assert_eq!(
+            split_position(at(":120")),
+            (notes.clone(), Some((120, None)))
+        );
+        assert_eq!(
+            split_position(at(":120:7")),
+            (notes.clone(), Some((120, Some(7))))
+        );
+        assert_eq!(split_position(notes.clone()), (notes.clone(), None));
+        for odd in [":", ":x", ":12:", ":+3", ":-3", ":12:x"] {
+            assert_eq!(split_position(at(odd)), (at(odd), None), "{odd}");
+        }
+        // Missing without the suffix too: a new file with that name.
+        let missing = dir.join("missing.md:3");
+        assert_eq!(split_position(missing.clone()), (missing, None));
+        // Drive letters are not a line number.
+        let windows = PathBuf::from(r"C:\foo.md");
+        assert_eq!(split_position(windows.clone()), (windows, None));
+
+        // A file really named `a:1` opens as itself, and takes a line
+        // suffix before `a` does.
+        let literal = dir.join("a:1");
+        fs::write(&literal, "x").expect("literal");
+        fs::write(dir.join("a"), "x").expect("a");
+        assert_eq!(split_position(literal.clone()), (literal.clone(), None));
+        let deeper = dir.join("a:1:2");
+        assert_eq!(split_position(deeper), (literal, Some((2, None))));
+
+        // `--line` wins, and the suffix still comes off.
+        assert_eq!(
+            open_position(at(":5:2"), Some(9)),
+            (notes.clone(), Some((9, None)))
+        );
+        assert_eq!(
+            open_position(at(":5:2"), None),
+            (notes.clone(), Some((5, Some(2))))
+        );
+        assert_eq!(
+            open_position(notes.clone(), Some(9)),
+            (notes, Some((9, None)))
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn preview_width_from_env_handles_valid_invalid_and_missing() {
         let _guard = ENV_LOCK.lock().expect("env lock");
@@ -193,16 +1017,75 @@ mod tests {
         unsafe { std::env::remove_var("COLUMNS") };
     }
 
+    #[test]
+    fn width_flag_takes_precedence_over_columns() {
+        let _guard = ENV_LOCK.lock().expect("env lock");
+
+        unsafe { std::env::set_var("COLUMNS", "60") };
+        assert_eq!(resolve_preview_width(Some(120)), 120);
+        assert_eq!(resolve_preview_width(None), 60);
+
+        unsafe { std::env::remove_var("COLUMNS") };
+        assert_eq!(resolve_preview_width(None), 80);
+    }
+
+    #[test]
+    fn parse_size_accepts_suffixes() {
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size("512k"), Ok(512 * 1024));
+        assert_eq!(parse_size("10M"), Ok(10 * 1024 * 1024));
+        assert_eq!(parse_size("1g"), Ok(1024 * 1024 * 1024));
+        assert!(parse_size("0k").expect_err("zero").contains("more than 0"));
+        assert!(
+            parse_size("ten")
+                .expect_err("word")
+                .contains("invalid size")
+        );
+        assert!(
+            parse_size("5mb")
+                .expect_err("unit")
+                .contains("invalid size")
+        );
+        assert!(parse_size("").expect_err("empty").contains("invalid size"));
+    }
+
+    #[test]
+    fn parse_width_rejects_values_below_renderer_minimum() {
+        assert_eq!(parse_width("8"), Ok(8));
+        assert_eq!(parse_width("120"), Ok(120));
+        assert!(
+            parse_width("7")
+                .expect_err("too small")
+                .contains("at least 8")
+        );
+        assert!(
+            parse_width("wide")
+                .expect_err("not a number")
+                .contains("invalid width")
+        );
+    }
+
     #[test]
     fn read_initial_text_allows_missing_and_errors_on_dir() {
         let missing = temp_path("missing");
-        assert_eq!(read_initial_text(&missing).expect("missing ok"), "");
+        assert_eq!(read_initial_text(&missing).expect("missing ok").text, "");
 
         let existing = temp_path("existing");
         fs::write(&existing, "# hello\n").expect("write file");
-        assert_eq!(read_initial_text(&existing).expect("existing"), "# hello\n");
+        assert_eq!(
+            read_initial_text(&existing).expect("existing").text,
+            "# hello\n"
+        );
         let _ = fs::remove_file(&existing);
 
+        let legacy = temp_path("legacy");
+        fs::write(&legacy, b"caf\xe9\r\nbar\r\n").expect("write file");
+        let file = read_initial_text(&legacy).expect("invalid utf-8 still opens");
+        assert_eq!(file.text, "caf\u{fffd}\nbar\n");
+        assert_eq!(file.line_ending, mdv_core::LineEnding::CrLf);
+        assert_eq!(file.invalid_sequences, 1);
+        let _ = fs::remove_file(&legacy);
+
         let dir = temp_path("dir");
         fs::create_dir(&dir).expect("mkdir");
         let err = read_initial_text(&dir).expect_err("dir err");
@@ -213,15 +1096,147 @@ mod tests {
     #[test]
     fn print_preview_to_writes_newline_separated_lines() {
         let mut out = Vec::new();
-        print_preview_to("# a\nb\n", 80, &mut out).expect("print");
+        print_preview_to("# a\nb\n", &RenderOptions::new(80), &ALL_LINES, &mut out).expect("print");
         let s = String::from_utf8(out).expect("utf8");
         assert_eq!(s, "# a\nb");
 
         let mut out2 = Vec::new();
-        print_preview_to("", 80, &mut out2).expect("print2");
+        print_preview_to("", &RenderOptions::new(80), &ALL_LINES, &mut out2).expect("print2");
         assert_eq!(String::from_utf8(out2).expect("utf8"), "");
     }
 
+    #[test]
+    fn piped_output_keeps_links_and_images_literal() {
+        let text = "See [docs](https://docs.dev).\n\n![logo](logo.png)\n";
+        let opts = PrintOptions {
+            width: Some(80),
+            color: false,
+            theme: ThemeChoice::Default,
+            overrides: Default::default(),
+            config_warnings: Vec::new(),
+            lines: ALL_LINES,
+            source_headings: false,
+            emoji: false,
+            max_lines: None,
+        };
+        let mut out = Vec::new();
+        print_preview_to(text, &print_render_options(&opts), &ALL_LINES, &mut out).expect("print");
+        assert_eq!(
+            String::from_utf8(out).expect("utf8"),
+            "See [docs](https://docs.dev).\n![logo](logo.png)"
+        );
+    }
+
+    #[test]
+    fn color_enabled_honors_mode_tty_and_no_color() {
+        assert!(color_enabled(CliColor::Auto, false, true));
+        assert!(!color_enabled(CliColor::Auto, false, false));
+        assert!(color_enabled(CliColor::Always, false, false));
+        assert!(!color_enabled(CliColor::Never, false, true));
+        assert!(!color_enabled(CliColor::Always, true, true));
+    }
+
+    #[test]
+    fn print_preview_colored_to_emits_heading_and_code_escapes() {
+        let theme = build_theme(ThemeChoice::Default, false, None);
+        let mut out = Vec::new();
+        print_preview_colored_to(
+            "# Title\n\n```rs\nlet x = 1;\n```\n",
+            &RenderOptions::new(80),
+            &ALL_LINES,
+            &theme,
+            &mut out,
+        )
+        .expect("print");
+        let s = String::from_utf8(out).expect("utf8");
+        let lines: Vec<&str> = s.split('\n').collect();
+
+        assert_eq!(lines[0], "\x1b[1;38;2;224;108;117m# Title\x1b[0m");
+        assert_eq!(lines[1], "\x1b[38;2;229;192;123;48;2;40;44;52m```rs\x1b[0m");
+        assert!(lines[2].contains("let x = 1;"));
+        assert!(lines[2].starts_with("\x1b[38;2;229;192;123;48;2;40;44;52m"));
+    }
+
+    #[test]
+    fn line_range_inside_a_fence_keeps_code_styling() {
+        let text = "# Title\n\n```rs\nlet x = 1;\nlet y = 2;\n```\n\nafter\n";
+        let theme = build_theme(ThemeChoice::Default, false, None);
+        let range = source_lines(Some(5), Some(5)).expect("range");
+        let mut out = Vec::new();
+        print_preview_colored_to(text, &RenderOptions::new(80), &range, &theme, &mut out)
+            .expect("print");
+        let s = String::from_utf8(out).expect("utf8");
+
+        assert!(!s.contains('\n'));
+        assert!(s.starts_with("\x1b[38;2;229;192;123;48;2;40;44;52m"));
+        assert!(s.contains("let y = 2;"));
+
+        let mut plain = Vec::new();
+        let range = source_lines(Some(4), Some(100)).expect("range");
+        print_preview_to(text, &RenderOptions::new(80), &range, &mut plain).expect("print");
+        assert_eq!(
+            String::from_utf8(plain).expect("utf8"),
+            "let x = 1;\nlet y = 2;\n```\nafter"
+        );
+    }
+
+    #[test]
+    fn source_lines_clamps_and_rejects_reversed_ranges() {
+        assert_eq!(source_lines(None, None).expect("all"), ALL_LINES);
+        assert_eq!(source_lines(Some(0), Some(3)).expect("zero"), 0..=2);
+        let err = source_lines(Some(10), Some(5)).expect_err("reversed");
+        assert_eq!(err.to_string(), "--from 10 is after --to 5");
+    }
+
+    #[test]
+    fn streamed_output_matches_the_all_at_once_print() {
+        let doc = "# Title\n\nA paragraph long enough to wrap at forty columns.\n\n- a\n\n- b\n\n\
+                   ```rs\nlet x = 1;\n\nlet y = 2;\n```\n\nSee [docs][d] and a note[^n].\n\n\
+                   [d]: https://example.com\n\n[^n]: The note.\n\n> done\n";
+        let options = RenderOptions::new(40);
+        let theme = build_theme(ThemeChoice::Default, false, None);
+        for range in [ALL_LINES, source_lines(Some(3), Some(12)).expect("range")] {
+            let mut all = Vec::new();
+            print_preview_to(doc, &options, &range, &mut all).expect("print");
+            let mut streamed = Vec::new();
+            print_stream_to(doc.as_bytes(), &options, &range, None, &mut streamed).expect("stream");
+            assert_eq!(String::from_utf8(streamed), String::from_utf8(all));
+
+            let mut all = Vec::new();
+            print_preview_colored_to(doc, &options, &range, &theme, &mut all).expect("print");
+            let mut streamed = Vec::new();
+            print_stream_to(
+                doc.as_bytes(),
+                &options,
+                &range,
+                Some(&theme),
+                &mut streamed,
+            )
+            .expect("stream");
+            assert_eq!(String::from_utf8(streamed), String::from_utf8(all));
+        }
+
+        let mut empty = Vec::new();
+        print_stream_to(&b""[..], &options, &ALL_LINES, None, &mut empty).expect("empty");
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn print_preview_colored_to_monochrome_theme_keeps_modifiers() {
+        let theme = build_theme(ThemeChoice::Default, true, None);
+        let mut out = Vec::new();
+        print_preview_colored_to(
+            "# Title\nplain",
+            &RenderOptions::new(80),
+            &ALL_LINES,
+            &theme,
+            &mut out,
+        )
+        .expect("print");
+        let s = String::from_utf8(out).expect("utf8");
+        assert_eq!(s, "\x1b[1m# Title\x1b[0m\nplain");
+    }
+
     #[test]
     fn print_preview_to_propagates_write_errors() {
         struct WriteFailWriter;
@@ -236,7 +1251,8 @@ mod tests {
 
         let mut write_fail = WriteFailWriter;
         io::Write::flush(&mut write_fail).expect("flush ok");
-        let err = print_preview_to("x", 80, WriteFailWriter).expect_err("expected write err");
+        let err = print_preview_to("x", &RenderOptions::new(80), &ALL_LINES, WriteFailWriter)
+            .expect_err("expected write err");
         assert!(err.to_string().contains("write fail"));
 
         struct FlushFailWriter {
@@ -252,22 +1268,57 @@ mod tests {
             }
         }
 
-        let err = print_preview_to("x", 80, FlushFailWriter { buf: Vec::new() })
-            .expect_err("expected flush err");
+        let err = print_preview_to(
+            "x",
+            &RenderOptions::new(80),
+            &ALL_LINES,
+            FlushFailWriter { buf: Vec::new() },
+        )
+        .expect_err("expected flush err");
         assert!(err.to_string().contains("flush fail"));
     }
 
     #[test]
     fn apply_ui_flags_maps_theme_focus_and_color_options() {
-        let mut app = App::new_stream(false).expect("stream app");
-        apply_ui_flags(&mut app, CliTheme::Default, true, CliFocus::View);
+        let mut app = App::new_stream(false, None).expect("stream app");
+        apply_ui_flags(
+            &mut app,
+            ThemeChoice::Default,
+            true,
+            PaneFocus::Preview,
+            None,
+            false,
+            false,
+        );
         assert_eq!(app.ui_state().theme, ThemeChoice::Default);
         assert!(app.ui_state().no_color);
         assert_eq!(app.ui_state().focus, PaneFocus::Preview);
 
-        apply_ui_flags(&mut app, CliTheme::HighContrast, false, CliFocus::Editor);
+        apply_ui_flags(
+            &mut app,
+            ThemeChoice::HighContrast,
+            false,
+            PaneFocus::Editor,
+            Some(65),
+            false,
+            true,
+        );
         assert_eq!(app.ui_state().theme, ThemeChoice::HighContrast);
         assert!(!app.ui_state().no_color);
         assert_eq!(app.ui_state().focus, PaneFocus::Editor);
+        assert_eq!(app.ui_state().split_percent, 65);
+        assert!(app.ui_state().soft_wrap);
+
+        apply_ui_flags(
+            &mut app,
+            ThemeChoice::Default,
+            false,
+            PaneFocus::Editor,
+            None,
+            true,
+            false,
+        );
+        assert!(app.ui_state().view_only);
+        assert_eq!(app.ui_state().focus, PaneFocus::Preview);
     }
 }
@@ -0,0 +1,82 @@
+use std::process::{Command, Stdio};
+use std::thread;
+
+#[cfg(test)]
+use std::cell::RefCell;
+#[cfg(test)]
+use std::rc::Rc;
+
+/// Hands a URL or file to the desktop: the browser, mail client or whatever
+/// app the system picks for it.
+pub trait Opener {
+    fn open(&mut self, target: &str) -> Result<(), String>;
+}
+
+/// `open` on macOS, `start` on Windows and `xdg-open` elsewhere.
+#[derive(Default)]
+#[cfg_attr(test, allow(dead_code))]
+pub struct SystemOpener;
+
+#[cfg_attr(test, allow(dead_code))]
+fn open_command(target: &str) -> Command {
+    if cfg!(target_os = "macos") {
+        let mut cmd = Command::new("open");
+        cmd.arg(target);
+        cmd
+    } else if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        // The empty argument is the window title `start` expects first.
+        cmd.args(["/C", "start", ""]).arg(target);
+        cmd
+    } else {
+        let mut cmd = Command::new("xdg-open");
+        cmd.arg(target);
+        cmd
+    }
+}
+
+impl Opener for SystemOpener {
+    fn open(&mut self, target: &str) -> Result<(), String> {
+        let mut cmd = open_command(target);
+        let mut child = cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| format!("{}: {err}", cmd.get_program().to_string_lossy()))?;
+        // Reap the helper without holding up the UI.
+        thread::spawn(move || child.wait());
+        Ok(())
+    }
+}
+
+/// Records what would have been opened; `unavailable` simulates a host
+/// without a desktop.
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryOpener {
+    pub opened: Rc<RefCell<Vec<String>>>,
+    pub unavailable: bool,
+}
+
+#[cfg(test)]
+impl Opener for MemoryOpener {
+    fn open(&mut self, target: &str) -> Result<(), String> {
+        if self.unavailable {
+            return Err("xdg-open: not found".into());
+        }
+        self.opened.borrow_mut().push(target.to_string());
+        Ok(())
+    }
+}
+
+pub fn system() -> Box<dyn Opener> {
+    #[cfg(test)]
+    {
+        Box::new(MemoryOpener::default())
+    }
+    #[cfg(not(test))]
+    {
+        Box::new(SystemOpener)
+    }
+}
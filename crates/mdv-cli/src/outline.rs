@@ -0,0 +1,129 @@
+use std::io::{self, Write};
+
+use mdv_core::{OutlineEntry, extract_outline};
+
+use crate::bench::json_string;
+
+/// The headings `--outline` prints for one file.
+#[derive(Debug)]
+pub struct FileOutline {
+    /// The path as given on the command line.
+    pub file: String,
+    pub entries: Vec<OutlineEntry>,
+}
+
+impl FileOutline {
+    /// Headings down to `max_depth`, or all of them.
+    pub fn new(file: String, markdown: &str, max_depth: Option<u8>) -> Self {
+        let mut entries = extract_outline(markdown);
+        if let Some(depth) = max_depth {
+            entries.retain(|entry| entry.level <= depth);
+        }
+        Self { file, entries }
+    }
+}
+
+/// One `LINE:## Heading` line per heading, with a one-based line. With
+/// several files each line starts with `file:` as in `--check` output.
+pub fn write_text(outlines: &[FileOutline], mut out: impl Write) -> io::Result<()> {
+    let prefix = outlines.len() > 1;
+    for outline in outlines {
+        for entry in &outline.entries {
+            if prefix {
+                write!(out, "{}:", outline.file)?;
+            }
+            writeln!(
+                out,
+                "{}:{} {}",
+                entry.source_line + 1,
+                "#".repeat(entry.level.into()),
+                entry.text
+            )?;
+        }
+    }
+    out.flush()
+}
+
+/// Every heading of every file as one JSON array of
+/// `{"file","line","level","text"}` objects.
+pub fn write_json(outlines: &[FileOutline], mut out: impl Write) -> io::Result<()> {
+    out.write_all(b"[")?;
+    let entries = outlines
+        .iter()
+        .flat_map(|outline| outline.entries.iter().map(move |entry| (outline, entry)));
+    for (index, (outline, entry)) in entries.enumerate() {
+        if index > 0 {
+            out.write_all(b",")?;
+        }
+        write!(
+            out,
+            "{{\"file\":{},\"line\":{},\"level\":{},\"text\":{}}}",
+            json_string(&outline.file),
+            entry.source_line + 1,
+            entry.level,
+            json_string(&entry.text)
+        )?;
+    }
+    out.write_all(b"]\n")?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileOutline, write_json, write_text};
+
+    const DOC: &str = "Guide\n=====\n\nIntro.\n\n## Install `mdv`\n\n> ### Quoted\n\n\
+                       Usage\n-----\n\n#### Deep\n";
+
+    fn text(outlines: &[FileOutline]) -> String {
+        let mut out = Vec::new();
+        write_text(outlines, &mut out).expect("text");
+        String::from_utf8(out).expect("utf8")
+    }
+
+    #[test]
+    fn atx_and_setext_headings_print_with_source_lines() {
+        let outline = FileOutline::new("guide.md".into(), DOC, None);
+        assert_eq!(
+            text(&[outline]),
+            "1:# Guide\n6:## Install mdv\n8:### Quoted\n10:## Usage\n13:#### Deep\n"
+        );
+    }
+
+    #[test]
+    fn max_depth_drops_deeper_headings() {
+        let outline = FileOutline::new("guide.md".into(), DOC, Some(2));
+        assert_eq!(
+            text(&[outline]),
+            "1:# Guide\n6:## Install mdv\n10:## Usage\n"
+        );
+    }
+
+    #[test]
+    fn several_files_prefix_each_line() {
+        let outlines = [
+            FileOutline::new("a.md".into(), "# A\n", None),
+            FileOutline::new("b.md".into(), "text\n\n## B\n", None),
+        ];
+        assert_eq!(text(&outlines), "a.md:1:# A\nb.md:3:## B\n");
+    }
+
+    #[test]
+    fn json_lists_every_heading_as_an_object() {
+        let outlines = [
+            FileOutline::new("a.md".into(), "# A \"quoted\"\n", None),
+            FileOutline::new("b.md".into(), "## B\n", None),
+        ];
+        let mut out = Vec::new();
+        write_json(&outlines, &mut out).expect("json");
+        assert_eq!(
+            String::from_utf8(out).expect("utf8"),
+            "[{\"file\":\"a.md\",\"line\":1,\"level\":1,\"text\":\"A \\\"quoted\\\"\"},\
+             {\"file\":\"b.md\",\"line\":1,\"level\":2,\"text\":\"B\"}]\n"
+        );
+
+        let mut out = Vec::new();
+        write_json(&[FileOutline::new("c.md".into(), "none\n", None)], &mut out).expect("json");
+        assert_eq!(out, b"[]\n");
+    }
+}
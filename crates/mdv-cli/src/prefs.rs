@@ -0,0 +1,189 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::app::state::{MAX_SPLIT_PERCENT, MIN_SPLIT_PERCENT};
+use crate::app::{PaneFocus, ThemeChoice};
+use crate::config;
+
+/// UI choices remembered between sessions. `None` means "not saved".
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Prefs {
+    pub theme: Option<ThemeChoice>,
+    pub no_color: Option<bool>,
+    pub focus: Option<PaneFocus>,
+    /// Editor share of the split view, in percent.
+    pub split: Option<u16>,
+}
+
+impl Prefs {
+    /// Fields set in `other` win over the ones saved here.
+    pub fn overlaid_with(self, other: Prefs) -> Prefs {
+        Prefs {
+            theme: other.theme.or(self.theme),
+            no_color: other.no_color.or(self.no_color),
+            focus: other.focus.or(self.focus),
+            split: other.split.or(self.split),
+        }
+    }
+}
+
+pub fn state_path() -> Option<PathBuf> {
+    config::config_dir().map(|dir| dir.join("state.toml"))
+}
+
+/// Missing, unreadable, or corrupted files all load as empty prefs.
+pub fn load(path: &Path) -> Prefs {
+    fs::read_to_string(path)
+        .map(|text| parse(&text))
+        .unwrap_or_default()
+}
+
+pub fn save(path: &Path, prefs: &Prefs) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, render(prefs))
+}
+
+fn parse(text: &str) -> Prefs {
+    let Ok(table) = text.parse::<toml::Table>() else {
+        return Prefs::default();
+    };
+    Prefs {
+        theme: table
+            .get("theme")
+            .and_then(toml::Value::as_str)
+            .and_then(theme_from_name),
+        no_color: table.get("no_color").and_then(toml::Value::as_bool),
+        focus: table
+            .get("focus")
+            .and_then(toml::Value::as_str)
+            .and_then(focus_from_name),
+        split: table
+            .get("split")
+            .and_then(toml::Value::as_integer)
+            .and_then(|split| u16::try_from(split).ok())
+            .filter(|split| (MIN_SPLIT_PERCENT..=MAX_SPLIT_PERCENT).contains(split)),
+    }
+}
+
+fn render(prefs: &Prefs) -> String {
+    let mut out = String::new();
+    if let Some(theme) = prefs.theme {
+        out.push_str(&format!("theme = \"{}\"\n", theme_name(theme)));
+    }
+    if let Some(no_color) = prefs.no_color {
+        out.push_str(&format!("no_color = {no_color}\n"));
+    }
+    if let Some(focus) = prefs.focus {
+        out.push_str(&format!("focus = \"{}\"\n", focus_name(focus)));
+    }
+    if let Some(split) = prefs.split {
+        out.push_str(&format!("split = {split}\n"));
+    }
+    out
+}
+
+pub fn theme_name(theme: ThemeChoice) -> &'static str {
+    match theme {
+        ThemeChoice::Auto => "auto",
+        ThemeChoice::Default => "default",
+        ThemeChoice::HighContrast => "high-contrast",
+        ThemeChoice::Light => "light",
+    }
+}
+
+fn theme_from_name(name: &str) -> Option<ThemeChoice> {
+    match name {
+        "auto" => Some(ThemeChoice::Auto),
+        "default" => Some(ThemeChoice::Default),
+        "high-contrast" => Some(ThemeChoice::HighContrast),
+        "light" => Some(ThemeChoice::Light),
+        _ => None,
+    }
+}
+
+pub fn focus_name(focus: PaneFocus) -> &'static str {
+    match focus {
+        PaneFocus::Editor => "editor",
+        PaneFocus::Preview => "view",
+    }
+}
+
+fn focus_from_name(name: &str) -> Option<PaneFocus> {
+    match name {
+        "editor" => Some(PaneFocus::Editor),
+        "view" => Some(PaneFocus::Preview),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::app::{PaneFocus, ThemeChoice};
+
+    use super::{Prefs, load, parse, render, save};
+
+    #[test]
+    fn render_and_parse_round_trip() {
+        let prefs = Prefs {
+            theme: Some(ThemeChoice::HighContrast),
+            no_color: Some(true),
+            focus: Some(PaneFocus::Preview),
+            split: Some(65),
+        };
+        assert_eq!(parse(&render(&prefs)), prefs);
+        assert_eq!(parse(&render(&Prefs::default())), Prefs::default());
+    }
+
+    #[test]
+    fn corrupted_or_unknown_values_are_ignored() {
+        assert_eq!(parse("theme = [broken"), Prefs::default());
+        let prefs = parse("theme = \"neon\"\nno_color = \"yes\"\nfocus = \"view\"\n");
+        assert_eq!(prefs.theme, None);
+        assert_eq!(prefs.no_color, None);
+        assert_eq!(prefs.focus, Some(PaneFocus::Preview));
+        assert_eq!(parse("split = 95\n").split, None);
+        assert_eq!(parse("split = \"wide\"\n").split, None);
+        assert_eq!(parse("split = 35\n").split, Some(35));
+    }
+
+    #[test]
+    fn overlay_prefers_explicit_values() {
+        let saved = Prefs {
+            theme: Some(ThemeChoice::HighContrast),
+            no_color: Some(true),
+            focus: None,
+            split: None,
+        };
+        let flags = Prefs {
+            theme: Some(ThemeChoice::Default),
+            ..Prefs::default()
+        };
+        let merged = saved.overlaid_with(flags);
+        assert_eq!(merged.theme, Some(ThemeChoice::Default));
+        assert_eq!(merged.no_color, Some(true));
+        assert_eq!(merged.focus, None);
+    }
+
+    #[test]
+    fn load_and_save_use_the_given_path() {
+        let dir = std::env::temp_dir().join(format!("mdv-prefs-{}", std::process::id()));
+        let path = dir.join("nested").join("state.toml");
+        assert_eq!(load(&path), Prefs::default());
+
+        let prefs = Prefs {
+            theme: Some(ThemeChoice::Light),
+            no_color: Some(false),
+            focus: Some(PaneFocus::Editor),
+            split: None,
+        };
+        save(&path, &prefs).expect("save");
+        assert_eq!(load(&path), prefs);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,208 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::app::PaneFocus;
+use crate::config;
+use crate::prefs::focus_name;
+
+/// Files remembered at most; the least recently used are dropped first.
+pub const MAX_ENTRIES: usize = 200;
+
+/// Where a file was left: the cursor, both scroll offsets and the focused
+/// pane, plus the file's modification time when it was recorded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilePosition {
+    /// Canonical path, see [`file_key`].
+    pub path: PathBuf,
+    /// Milliseconds since the Unix epoch, if the file existed.
+    pub modified: Option<u64>,
+    pub line: usize,
+    pub column: usize,
+    pub editor_scroll: usize,
+    pub preview_scroll: usize,
+    pub focus: PaneFocus,
+}
+
+pub fn state_path() -> Option<PathBuf> {
+    config::config_dir().map(|dir| dir.join("resume.toml"))
+}
+
+/// The path a file is remembered under: canonical when it can be resolved,
+/// so `./notes.md` and `/home/me/notes.md` share one entry.
+pub fn file_key(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+pub fn modified_millis(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    let millis = modified.duration_since(UNIX_EPOCH).ok()?.as_millis();
+    u64::try_from(millis).ok()
+}
+
+/// Entries, most recently used first. Missing, unreadable, or corrupted
+/// files load as empty; malformed entries are skipped.
+pub fn load(store: &Path) -> Vec<FilePosition> {
+    fs::read_to_string(store)
+        .map(|text| parse(&text))
+        .unwrap_or_default()
+}
+
+pub fn find(store: &Path, file: &Path) -> Option<FilePosition> {
+    let key = file_key(file);
+    load(store).into_iter().find(|entry| entry.path == key)
+}
+
+/// Record `position` as the most recent entry, replacing any older one for
+/// the same file and evicting past [`MAX_ENTRIES`].
+pub fn remember(store: &Path, position: FilePosition) -> io::Result<()> {
+    let mut entries = load(store);
+    entries.retain(|entry| entry.path != position.path);
+    entries.insert(0, position);
+    entries.truncate(MAX_ENTRIES);
+    if let Some(parent) = store.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(store, render(&entries))
+}
+
+fn parse(text: &str) -> Vec<FilePosition> {
+    let Ok(table) = text.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+    let Some(files) = table.get("file").and_then(toml::Value::as_array) else {
+        return Vec::new();
+    };
+    files
+        .iter()
+        .filter_map(toml::Value::as_table)
+        .filter_map(parse_entry)
+        .collect()
+}
+
+fn parse_entry(entry: &toml::Table) -> Option<FilePosition> {
+    let count = |key: &str| {
+        entry
+            .get(key)
+            .and_then(toml::Value::as_integer)
+            .and_then(|value| usize::try_from(value).ok())
+    };
+    Some(FilePosition {
+        path: PathBuf::from(entry.get("path")?.as_str()?),
+        modified: entry
+            .get("modified")
+            .and_then(toml::Value::as_integer)
+            .and_then(|value| u64::try_from(value).ok()),
+        line: count("line")?,
+        column: count("column").unwrap_or(0),
+        editor_scroll: count("editor_scroll").unwrap_or(0),
+        preview_scroll: count("preview_scroll").unwrap_or(0),
+        focus: match entry.get("focus").and_then(toml::Value::as_str) {
+            Some("view") => PaneFocus::Preview,
+            _ => PaneFocus::Editor,
+        },
+    })
+}
+
+fn render(entries: &[FilePosition]) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        let path = toml::Value::String(entry.path.to_string_lossy().into_owned());
+        out.push_str(&format!("[[file]]\npath = {path}\n"));
+        if let Some(modified) = entry.modified {
+            out.push_str(&format!("modified = {modified}\n"));
+        }
+        out.push_str(&format!(
+            "line = {}\ncolumn = {}\neditor_scroll = {}\npreview_scroll = {}\nfocus = \"{}\"\n",
+            entry.line,
+            entry.column,
+            entry.editor_scroll,
+            entry.preview_scroll,
+            focus_name(entry.focus)
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use crate::app::PaneFocus;
+
+    use super::{FilePosition, MAX_ENTRIES, find, load, parse, remember, render};
+
+    fn position(path: &str, line: usize) -> FilePosition {
+        FilePosition {
+            path: PathBuf::from(path),
+            modified: Some(1_700_000_000_123),
+            line,
+            column: 4,
+            editor_scroll: line.saturating_sub(5),
+            preview_scroll: line * 2,
+            focus: PaneFocus::Preview,
+        }
+    }
+
+    #[test]
+    fn render_and_parse_round_trip() {
+        let mut unsaved = position("/tmp/new \"draft\".md", 0);
+        unsaved.modified = None;
+        unsaved.focus = PaneFocus::Editor;
+        let entries = vec![position("/docs/notes.md", 12), unsaved];
+        assert_eq!(parse(&render(&entries)), entries);
+        assert!(parse(&render(&[])).is_empty());
+    }
+
+    #[test]
+    fn corrupted_files_and_malformed_entries_are_ignored() {
+        assert!(parse("[[file]\npath = ").is_empty());
+        assert!(parse("file = 3\n").is_empty());
+        let entries = parse(
+            "[[file]]\npath = \"/a.md\"\nline = -1\n\n[[file]]\nline = 2\n\n\
+             [[file]]\npath = \"/b.md\"\nline = 3\nfocus = \"sideways\"\n",
+        );
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, PathBuf::from("/b.md"));
+        assert_eq!(entries[0].line, 3);
+        assert_eq!(entries[0].modified, None);
+        assert_eq!(entries[0].focus, PaneFocus::Editor);
+    }
+
+    #[test]
+    fn remember_keeps_the_most_recent_entries() {
+        let dir = std::env::temp_dir().join(format!("mdv-resume-{}", std::process::id()));
+        let store = dir.join("nested").join("resume.toml");
+        assert!(load(&store).is_empty());
+
+        for index in 0..=MAX_ENTRIES {
+            remember(&store, position(&format!("/docs/{index}.md"), index)).expect("remember");
+        }
+        remember(&store, position("/docs/1.md", 99)).expect("remember again");
+
+        let entries = load(&store);
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0], position("/docs/1.md", 99));
+        assert_eq!(
+            entries[1].path,
+            PathBuf::from(format!("/docs/{MAX_ENTRIES}.md"))
+        );
+        assert!(
+            entries
+                .iter()
+                .all(|entry| entry.path != Path::new("/docs/0.md"))
+        );
+        assert_eq!(
+            find(&store, &PathBuf::from("/docs/7.md")).map(|e| e.line),
+            Some(7)
+        );
+        assert_eq!(find(&store, &PathBuf::from("/docs/0.md")), None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,77 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use mdv_core::SpellChecker;
+
+use crate::config;
+
+/// Word lists tried, in order, when `[editor] spell_dictionary` is not set.
+const SYSTEM_WORD_LISTS: [&str; 2] = ["/usr/share/dict/words", "/usr/dict/words"];
+
+/// Words added with `Alt+A`, one per line.
+pub fn user_dictionary_path() -> Option<PathBuf> {
+    config::config_dir().map(|dir| dir.join("dictionary.txt"))
+}
+
+/// A checker knowing `dictionary` (or the first system word list found)
+/// plus the user's own words. Errors are one line for the status bar.
+pub fn load_checker(
+    dictionary: Option<&Path>,
+    user: Option<&Path>,
+) -> Result<SpellChecker, String> {
+    let list = match dictionary {
+        Some(path) => {
+            fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?
+        }
+        None => SYSTEM_WORD_LISTS
+            .iter()
+            .find_map(|path| fs::read_to_string(path).ok())
+            .ok_or("no word list found; set [editor] spell_dictionary")?,
+    };
+    let mut checker = SpellChecker::from_word_list(&list);
+    if let Some(words) = user.and_then(|path| fs::read_to_string(path).ok()) {
+        checker.add_word_list(&words);
+    }
+    Ok(checker)
+}
+
+pub fn add_to_user_dictionary(path: &Path, word: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{word}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{add_to_user_dictionary, load_checker};
+
+    #[test]
+    fn user_words_persist_across_loads() {
+        let dir = std::env::temp_dir().join(format!("mdv-spelling-{}", std::process::id()));
+        let words = dir.join("words");
+        let user = dir.join("nested").join("dictionary.txt");
+        fs::create_dir_all(&dir).expect("dir");
+        fs::write(&words, "the\ncat\n").expect("words");
+
+        let checker = load_checker(Some(&words), Some(&user)).expect("load");
+        assert!(checker.knows("cat"));
+        assert!(!checker.knows("mdv"));
+
+        add_to_user_dictionary(&user, "mdv").expect("add");
+        add_to_user_dictionary(&user, "Ratatui").expect("add again");
+        assert_eq!(fs::read_to_string(&user).expect("read"), "mdv\nRatatui\n");
+        let checker = load_checker(Some(&words), Some(&user)).expect("reload");
+        assert!(checker.knows("mdv"));
+        assert!(checker.knows("ratatui"));
+
+        let missing = load_checker(Some(&dir.join("nope")), None).expect_err("missing list");
+        assert!(missing.starts_with(&dir.join("nope").display().to_string()));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,97 @@
+use ratatui::style::{Color, Modifier, Style};
+
+pub const RESET: &str = "\x1b[0m";
+
+/// SGR escape sequence for a style, or `None` when the style is empty
+pub fn sgr(style: Style) -> Option<String> {
+    let mut codes: Vec<String> = Vec::new();
+
+    let modifiers = [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::CROSSED_OUT, "9"),
+    ];
+    for (modifier, code) in modifiers {
+        if style.add_modifier.contains(modifier) {
+            codes.push(code.to_string());
+        }
+    }
+    if let Some(fg) = style.fg {
+        codes.push(color_code(fg, false));
+    }
+    if let Some(bg) = style.bg {
+        codes.push(color_code(bg, true));
+    }
+
+    if codes.is_empty() {
+        None
+    } else {
+        Some(format!("\x1b[{}m", codes.join(";")))
+    }
+}
+
+fn color_code(color: Color, background: bool) -> String {
+    let base = if background { 40 } else { 30 };
+    let bright = if background { 100 } else { 90 };
+    match color {
+        Color::Reset => (base + 9).to_string(),
+        Color::Black => base.to_string(),
+        Color::Red => (base + 1).to_string(),
+        Color::Green => (base + 2).to_string(),
+        Color::Yellow => (base + 3).to_string(),
+        Color::Blue => (base + 4).to_string(),
+        Color::Magenta => (base + 5).to_string(),
+        Color::Cyan => (base + 6).to_string(),
+        Color::Gray => (base + 7).to_string(),
+        Color::DarkGray => bright.to_string(),
+        Color::LightRed => (bright + 1).to_string(),
+        Color::LightGreen => (bright + 2).to_string(),
+        Color::LightYellow => (bright + 3).to_string(),
+        Color::LightBlue => (bright + 4).to_string(),
+        Color::LightMagenta => (bright + 5).to_string(),
+        Color::LightCyan => (bright + 6).to_string(),
+        Color::White => (bright + 7).to_string(),
+        Color::Rgb(r, g, b) => format!("{};2;{r};{g};{b}", base + 8),
+        Color::Indexed(i) => format!("{};5;{i}", base + 8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::{Color, Modifier, Style};
+
+    use super::sgr;
+
+    #[test]
+    fn empty_style_has_no_sequence() {
+        assert_eq!(sgr(Style::default()), None);
+    }
+
+    #[test]
+    fn encodes_modifiers_then_fg_and_bg() {
+        let style = Style::default()
+            .fg(Color::Rgb(1, 2, 3))
+            .bg(Color::Black)
+            .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+        assert_eq!(sgr(style).as_deref(), Some("\x1b[1;4;38;2;1;2;3;40m"));
+    }
+
+    #[test]
+    fn encodes_named_bright_and_indexed_colors() {
+        assert_eq!(
+            sgr(Style::default().fg(Color::Yellow)).as_deref(),
+            Some("\x1b[33m")
+        );
+        assert_eq!(
+            sgr(Style::default().fg(Color::White).bg(Color::DarkGray)).as_deref(),
+            Some("\x1b[97;100m")
+        );
+        assert_eq!(
+            sgr(Style::default().fg(Color::Indexed(208))).as_deref(),
+            Some("\x1b[38;5;208m")
+        );
+    }
+}
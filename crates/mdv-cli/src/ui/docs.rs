@@ -287,7 +287,7 @@ mod tests {
 
     #[test]
     fn renderer_supports_heading_bullet_callout_and_inline_code() {
-        let theme = build_theme(ThemeChoice::Default, false);
+        let theme = build_theme(ThemeChoice::Default, false, None);
         let section = super::DocSection {
             id: "t",
             title: "t",
@@ -307,7 +307,7 @@ mod tests {
 
     #[test]
     fn renderer_supports_code_fences_ordered_lists_and_empty_sections() {
-        let theme = build_theme(ThemeChoice::Default, false);
+        let theme = build_theme(ThemeChoice::Default, false, None);
         let section = super::DocSection {
             id: "t2",
             title: "t2",
@@ -8,8 +8,20 @@ pub enum LayoutKind {
     Split,
     /// Single pane mode - only show focused pane
     Single,
-    /// Compact terminal (<80x24) - single pane with reduced chrome
+    /// Compact terminal (<80x24) too short to stack - single pane with
+    /// reduced chrome
     Compact,
+    /// Compact terminal (<80x24) - editor above preview, reduced chrome
+    Stacked,
+    /// The focused pane has the whole area until maximize is toggled off
+    Maximized,
+}
+
+impl LayoutKind {
+    /// Layouts for terminals under 80x24, which get the reduced chrome.
+    pub fn is_compact(self) -> bool {
+        matches!(self, Self::Compact | Self::Stacked)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -19,13 +31,28 @@ pub struct PaneLayout {
     pub preview: Rect,
 }
 
+/// The preview pane split into the two columns of the conflict view.
+#[derive(Debug, Clone, Copy)]
+pub struct ConflictColumns {
+    pub local: Rect,
+    pub external: Rect,
+}
+
 /// Minimum width for split view (each pane needs ~50 cols for comfortable editing)
 const MIN_SPLIT_WIDTH: u16 = 120;
 /// Minimum width/height for compact mode
 const MIN_NORMAL_WIDTH: u16 = 80;
 const MIN_NORMAL_HEIGHT: u16 = 24;
+/// Minimum height for stacking both panes: borders plus three rows each
+const MIN_STACKED_HEIGHT: u16 = 10;
 
-pub fn compute_pane_layout(area: Rect, focus: PaneFocus) -> PaneLayout {
+pub fn compute_pane_layout(
+    area: Rect,
+    focus: PaneFocus,
+    view_only: bool,
+    maximized: bool,
+    split_percent: u16,
+) -> PaneLayout {
     let zero = Rect {
         x: area.x,
         y: area.y,
@@ -33,37 +60,63 @@ pub fn compute_pane_layout(area: Rect, focus: PaneFocus) -> PaneLayout {
         height: 0,
     };
 
-    // Compact mode for very small terminals
-    if area.width < MIN_NORMAL_WIDTH || area.height < MIN_NORMAL_HEIGHT {
-        return if focus == PaneFocus::Editor {
+    // View-only always gives the whole area to the preview
+    let focus = if view_only { PaneFocus::Preview } else { focus };
+    let only_focused = |kind| {
+        if focus == PaneFocus::Editor {
             PaneLayout {
-                kind: LayoutKind::Compact,
+                kind,
                 editor: area,
                 preview: zero,
             }
         } else {
             PaneLayout {
-                kind: LayoutKind::Compact,
+                kind,
                 editor: zero,
                 preview: area,
             }
+        }
+    };
+
+    if maximized && !view_only {
+        return only_focused(LayoutKind::Maximized);
+    }
+
+    // Compact mode for very small terminals
+    if area.width < MIN_NORMAL_WIDTH || area.height < MIN_NORMAL_HEIGHT {
+        if view_only || area.height < MIN_STACKED_HEIGHT {
+            return only_focused(LayoutKind::Compact);
+        }
+        // Editor on top; the preview gets the odd row
+        let top = area.height / 2;
+        return PaneLayout {
+            kind: LayoutKind::Stacked,
+            editor: Rect {
+                height: top,
+                ..area
+            },
+            preview: Rect {
+                y: area.y + top,
+                height: area.height - top,
+                ..area
+            },
         };
     }
 
     // Split view for wide terminals
-    if area.width >= MIN_SPLIT_WIDTH {
-        // 50/50 split with 1 char divider
-        let half = area.width / 2;
+    if area.width >= MIN_SPLIT_WIDTH && !view_only {
+        // The editor takes `split_percent` of the width, the preview the rest
+        let editor_width = (u32::from(area.width) * u32::from(split_percent) / 100) as u16;
         let editor_rect = Rect {
             x: area.x,
             y: area.y,
-            width: half,
+            width: editor_width,
             height: area.height,
         };
         let preview_rect = Rect {
-            x: area.x + half,
+            x: area.x + editor_width,
             y: area.y,
-            width: area.width - half,
+            width: area.width - editor_width,
             height: area.height,
         };
         return PaneLayout {
@@ -74,18 +127,23 @@ pub fn compute_pane_layout(area: Rect, focus: PaneFocus) -> PaneLayout {
     }
 
     // Single pane mode for medium width terminals
-    if focus == PaneFocus::Editor {
-        PaneLayout {
-            kind: LayoutKind::Single,
-            editor: area,
-            preview: zero,
-        }
-    } else {
-        PaneLayout {
-            kind: LayoutKind::Single,
-            editor: zero,
-            preview: area,
-        }
+    only_focused(LayoutKind::Single)
+}
+
+/// Local on the left, external on the right; the left column gets the odd
+/// column when the width does not split evenly.
+pub fn compute_conflict_columns(area: Rect) -> ConflictColumns {
+    let external_width = area.width / 2;
+    ConflictColumns {
+        local: Rect {
+            width: area.width - external_width,
+            ..area
+        },
+        external: Rect {
+            x: area.x + area.width - external_width,
+            width: external_width,
+            ..area
+        },
     }
 }
 
@@ -95,7 +153,7 @@ mod tests {
 
     use crate::app::state::PaneFocus;
 
-    use super::{LayoutKind, MIN_SPLIT_WIDTH, compute_pane_layout};
+    use super::{LayoutKind, MIN_SPLIT_WIDTH, compute_conflict_columns, compute_pane_layout};
 
     #[test]
     fn wide_terminal_uses_split_layout() {
@@ -107,6 +165,9 @@ mod tests {
                 height: 40,
             },
             PaneFocus::Editor,
+            false,
+            false,
+            50,
         );
         assert_eq!(layout.kind, LayoutKind::Split);
         assert_eq!(layout.editor.width, 80);
@@ -125,6 +186,9 @@ mod tests {
                 height: 30,
             },
             PaneFocus::Preview,
+            false,
+            false,
+            50,
         );
         assert_eq!(layout.kind, LayoutKind::Split);
         assert!(layout.editor.width > 0);
@@ -141,6 +205,9 @@ mod tests {
                 height: 30,
             },
             PaneFocus::Editor,
+            false,
+            false,
+            50,
         );
         assert_eq!(layout.kind, LayoutKind::Single);
         assert_eq!(layout.editor.width, 100);
@@ -157,6 +224,9 @@ mod tests {
                 height: 26,
             },
             PaneFocus::Preview,
+            false,
+            false,
+            50,
         );
         assert_eq!(layout.kind, LayoutKind::Single);
         assert_eq!(layout.preview.width, 90);
@@ -165,34 +235,122 @@ mod tests {
     }
 
     #[test]
-    fn compact_layout_for_small_terminal() {
+    fn compact_terminal_stacks_editor_above_preview() {
+        let area = Rect::new(0, 1, 70, 20);
+        let layout = compute_pane_layout(area, PaneFocus::Preview, false, false, 50);
+        assert_eq!(layout.kind, LayoutKind::Stacked);
+        assert!(layout.kind.is_compact());
+        assert_eq!(layout.editor, Rect::new(0, 1, 70, 10));
+        assert_eq!(layout.preview, Rect::new(0, 11, 70, 10));
+
+        let odd = compute_pane_layout(Rect::new(0, 1, 60, 17), PaneFocus::Editor, false, false, 50);
+        assert_eq!(odd.editor, Rect::new(0, 1, 60, 8));
+        assert_eq!(odd.preview, Rect::new(0, 9, 60, 9));
+    }
+
+    #[test]
+    fn compact_layout_too_short_to_stack_shows_the_focused_pane() {
+        let area = Rect::new(0, 1, 60, 8);
+        let layout = compute_pane_layout(area, PaneFocus::Editor, false, false, 50);
+        assert_eq!(layout.kind, LayoutKind::Compact);
+        assert_eq!(layout.editor, area);
+        assert_eq!(layout.preview, Rect::new(0, 1, 0, 0));
+
+        let view_only =
+            compute_pane_layout(Rect::new(0, 1, 70, 20), PaneFocus::Editor, true, false, 50);
+        assert_eq!(view_only.kind, LayoutKind::Compact);
+        assert_eq!(view_only.preview, Rect::new(0, 1, 70, 20));
+    }
+
+    #[test]
+    fn split_layout_at_120x40_halves_the_width() {
         let layout = compute_pane_layout(
-            Rect {
-                x: 0,
-                y: 0,
-                width: 70,
-                height: 20,
-            },
-            PaneFocus::Preview,
+            Rect::new(0, 1, 120, 40),
+            PaneFocus::Editor,
+            false,
+            false,
+            50,
         );
-        assert_eq!(layout.kind, LayoutKind::Compact);
-        assert_eq!(layout.editor.width, 0);
-        assert_eq!(layout.preview.width, 70);
+        assert_eq!(layout.kind, LayoutKind::Split);
+        assert!(!layout.kind.is_compact());
+        assert_eq!(layout.editor, Rect::new(0, 1, 60, 40));
+        assert_eq!(layout.preview, Rect::new(60, 1, 60, 40));
+    }
+
+    #[test]
+    fn split_percent_sets_the_editor_share_of_the_width() {
+        let area = Rect::new(0, 1, 160, 40);
+        let wide = compute_pane_layout(area, PaneFocus::Editor, false, false, 60);
+        assert_eq!(wide.kind, LayoutKind::Split);
+        assert_eq!(wide.editor, Rect::new(0, 1, 96, 40));
+        assert_eq!(wide.preview, Rect::new(96, 1, 64, 40));
+
+        let narrow = compute_pane_layout(area, PaneFocus::Preview, false, false, 20);
+        assert_eq!(narrow.editor, Rect::new(0, 1, 32, 40));
+        assert_eq!(narrow.preview, Rect::new(32, 1, 128, 40));
+
+        let odd = compute_pane_layout(
+            Rect::new(0, 1, 125, 30),
+            PaneFocus::Editor,
+            false,
+            false,
+            55,
+        );
+        assert_eq!(odd.editor.width, 68);
+        assert_eq!(odd.preview, Rect::new(68, 1, 57, 30));
     }
 
     #[test]
-    fn compact_layout_editor_focus() {
+    fn maximize_gives_the_focused_pane_the_whole_area() {
+        for area in [Rect::new(0, 1, 120, 40), Rect::new(0, 1, 70, 20)] {
+            let editor = compute_pane_layout(area, PaneFocus::Editor, false, true, 50);
+            assert_eq!(editor.kind, LayoutKind::Maximized);
+            assert_eq!(editor.editor, area);
+            assert_eq!(editor.preview, Rect::new(0, 1, 0, 0));
+
+            let preview = compute_pane_layout(area, PaneFocus::Preview, false, true, 50);
+            assert_eq!(preview.editor, Rect::new(0, 1, 0, 0));
+            assert_eq!(preview.preview, area);
+        }
+
+        let view_only =
+            compute_pane_layout(Rect::new(0, 1, 160, 40), PaneFocus::Editor, true, true, 50);
+        assert_eq!(view_only.kind, LayoutKind::Single);
+        assert_eq!(view_only.preview, Rect::new(0, 1, 160, 40));
+    }
+
+    #[test]
+    fn conflict_columns_split_the_preview_in_two() {
+        let columns = compute_conflict_columns(Rect {
+            x: 60,
+            y: 1,
+            width: 61,
+            height: 28,
+        });
+        assert_eq!(columns.local.x, 60);
+        assert_eq!(columns.local.width, 31);
+        assert_eq!(columns.external.x, 91);
+        assert_eq!(columns.external.width, 30);
+        assert_eq!(columns.external.y, 1);
+        assert_eq!(columns.external.height, 28);
+    }
+
+    #[test]
+    fn view_only_gives_wide_terminal_to_preview() {
         let layout = compute_pane_layout(
             Rect {
                 x: 0,
                 y: 0,
-                width: 60,
-                height: 18,
+                width: 160,
+                height: 40,
             },
             PaneFocus::Editor,
+            true,
+            false,
+            50,
         );
-        assert_eq!(layout.kind, LayoutKind::Compact);
-        assert_eq!(layout.editor.width, 60);
-        assert_eq!(layout.preview.width, 0);
+        assert_eq!(layout.kind, LayoutKind::Single);
+        assert_eq!(layout.editor.width, 0);
+        assert_eq!(layout.preview.width, 160);
     }
 }
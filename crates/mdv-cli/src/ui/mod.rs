@@ -1,3 +1,4 @@
+pub mod ansi;
 pub mod docs;
 pub mod layout;
 pub mod render;
@@ -1,6 +1,8 @@
 use std::borrow::Cow;
 
+use mdv_core::LineEnding;
 use ratatui::text::{Line, Span};
+use unicode_width::UnicodeWidthChar;
 
 use crate::ui::theme::ThemeTokens;
 
@@ -14,6 +16,10 @@ pub struct StatusBarConfig<'a> {
     pub line: usize,
     pub col: usize,
     pub total_lines: usize,
+    /// `None` in large-file mode, where words are not counted.
+    pub words: Option<usize>,
+    /// Shown only when it is not the usual `LF`.
+    pub line_ending: LineEnding,
     pub scroll_percent: u8,
     pub message: &'a str,
     pub hint: &'a str,
@@ -68,9 +74,25 @@ pub fn build_status_bar<'a>(config: &StatusBarConfig<'_>, theme: &ThemeTokens) -
     // Calculate used width so far
     let used_width: usize = spans.iter().map(|s| s.content.chars().count()).sum();
 
-    // Right side: position info
-    let position_text = format!(" Ln {}, Col {} ", config.line + 1, config.col + 1);
+    // Right side: position info, plus document stats when they fit
     let scroll_text = format!(" {}% ", config.scroll_percent);
+    let fits =
+        |text: &str| used_width + text.chars().count() + scroll_text.chars().count() < config.width;
+    let mut stats = position_stats(config.line, config.col, config.total_lines, config.words);
+    if config.line_ending != LineEnding::Lf {
+        stats = format!("{stats} | {}", config.line_ending.label());
+    }
+    let position_text = if fits(&format!(" {stats} ")) {
+        format!(" {stats} ")
+    } else if config.words.is_none() {
+        format!(
+            " Ln {}, Col {} | large file mode ",
+            config.line + 1,
+            config.col + 1
+        )
+    } else {
+        format!(" Ln {}, Col {} ", config.line + 1, config.col + 1)
+    };
     let right_width = position_text.chars().count() + scroll_text.chars().count() + 1;
 
     // Fill middle with spaces
@@ -110,24 +132,133 @@ pub fn truncate_middle<'a>(value: &'a str, max_chars: usize) -> Cow<'a, str> {
     Cow::Owned(format!("{start}...{end}"))
 }
 
-pub fn compose_status(left: &str, right: &str, width: usize) -> String {
+/// `left` plus a right-aligned `hint | stats`. When the row is too narrow
+/// the stats go first, then the hint; `left` is always kept.
+pub fn compose_status(left: &str, hint: &str, stats: &str, width: usize) -> String {
     let left_chars = left.chars().count();
-    let right_chars = right.chars().count();
+    let joined = match (hint.is_empty(), stats.is_empty()) {
+        (true, _) => stats.to_string(),
+        (_, true) => hint.to_string(),
+        _ => format!("{hint} | {stats}"),
+    };
 
-    if right.is_empty() || left_chars + 1 + right_chars >= width {
+    let Some(right) = [joined.as_str(), hint]
+        .into_iter()
+        .find(|right| !right.is_empty() && left_chars + 1 + right.chars().count() < width)
+    else {
         return left.to_string();
-    }
+    };
 
-    let spaces = width.saturating_sub(left_chars + right_chars);
+    let spaces = width.saturating_sub(left_chars + right.chars().count());
     format!("{left}{}{}", " ".repeat(spaces), right)
 }
 
+/// `Ln 12, Col 4 | 214 lines | 1.2k words`, one-based like the editor gutter.
+/// Without a word count it ends in `large file mode` instead.
+pub fn position_stats(line: usize, col: usize, total_lines: usize, words: Option<usize>) -> String {
+    let words = match words {
+        Some(words) => format!("{} words", format_count(words)),
+        None => "large file mode".into(),
+    };
+    format!(
+        "Ln {}, Col {} | {} lines | {words}",
+        line + 1,
+        col + 1,
+        format_count(total_lines),
+    )
+}
+
+/// Short counts for the status bar: `999`, `1.2k`, `3.4M`.
+pub fn format_count(count: usize) -> String {
+    let short = |value: f64, suffix: &str| {
+        let text = format!("{value:.1}");
+        format!("{}{suffix}", text.strip_suffix(".0").unwrap_or(&text))
+    };
+    match count {
+        0..1_000 => count.to_string(),
+        1_000..1_000_000 => short(count as f64 / 1_000.0, "k"),
+        _ => short(count as f64 / 1_000_000.0, "M"),
+    }
+}
+
+/// The part of `text` that lands in display columns `start..start + width`.
+/// A wide glyph cut by the left edge becomes spaces so later columns stay put;
+/// one cut by the right edge is dropped.
+pub fn slice_columns(text: &str, start: usize, width: usize) -> Cow<'_, str> {
+    let end = start.saturating_add(width);
+    let mut col = 0usize;
+    let mut pad = 0usize;
+    let mut from = None;
+    let mut to = text.len();
+    for (idx, ch) in text.char_indices() {
+        let next = col + ch.width().unwrap_or(0);
+        if from.is_none() {
+            if col >= start {
+                from = Some(idx);
+            } else if next > start {
+                pad = (next - start).min(width);
+            }
+        }
+        if from.is_some() && next > end {
+            to = idx;
+            break;
+        }
+        col = next;
+    }
+    let visible = &text[from.unwrap_or(text.len()).min(to)..to];
+    if pad == 0 {
+        Cow::Borrowed(visible)
+    } else {
+        Cow::Owned(format!("{}{visible}", " ".repeat(pad)))
+    }
+}
+
+/// Char position a click on display column `column` lands on.
+pub fn char_at_column(text: &str, column: usize) -> usize {
+    let mut col = 0usize;
+    for (count, ch) in text.chars().enumerate() {
+        col += ch.width().unwrap_or(0);
+        if col > column {
+            return count;
+        }
+    }
+    text.chars().count()
+}
+
 #[cfg(test)]
 mod tests {
+    use mdv_core::LineEnding;
+
     use crate::app::ThemeChoice;
     use crate::ui::theme::build_theme;
 
-    use super::{StatusBarConfig, build_status_bar, compose_status, truncate_middle};
+    use super::{
+        StatusBarConfig, build_status_bar, char_at_column, compose_status, format_count,
+        position_stats, slice_columns, truncate_middle,
+    };
+
+    #[test]
+    fn slice_columns_handles_ascii_and_wide_glyphs() {
+        assert_eq!(slice_columns("hello world", 0, 5), "hello");
+        assert_eq!(slice_columns("hello world", 6, 20), "world");
+        assert_eq!(slice_columns("hello", 9, 4), "");
+        assert_eq!(slice_columns("", 0, 4), "");
+
+        // Each CJK glyph is two columns wide.
+        assert_eq!(slice_columns("日本語abc", 2, 4), "本語");
+        assert_eq!(slice_columns("日本語abc", 1, 4), " 本");
+        assert_eq!(slice_columns("日本語abc", 5, 3), " ab");
+        assert_eq!(slice_columns("日本語abc", 0, 3), "日");
+        assert_eq!(slice_columns("aé日x", 1, 3), "é日");
+    }
+
+    #[test]
+    fn char_at_column_lands_on_wide_glyphs() {
+        assert_eq!(char_at_column("ab日c", 2), 2);
+        assert_eq!(char_at_column("ab日c", 3), 2);
+        assert_eq!(char_at_column("ab日c", 4), 3);
+        assert_eq!(char_at_column("ab日c", 40), 4);
+    }
 
     #[test]
     fn truncates_middle() {
@@ -138,11 +269,41 @@ mod tests {
 
     #[test]
     fn composes_right_hint_when_space_exists() {
-        let out = compose_status("left", "right", 20);
+        let out = compose_status("left", "right", "", 20);
         assert!(out.starts_with("left"));
         assert!(out.ends_with("right"));
     }
 
+    #[test]
+    fn compose_status_drops_stats_before_hint() {
+        let stats = position_stats(11, 3, 214, Some(1_234));
+        assert_eq!(stats, "Ln 12, Col 4 | 214 lines | 1.2k words");
+        assert_eq!(
+            position_stats(0, 0, 250_000, None),
+            "Ln 1, Col 1 | 250k lines | large file mode"
+        );
+
+        let wide = compose_status("compact | Saved", "Esc close", &stats, 80);
+        assert!(wide.ends_with("Esc close | Ln 12, Col 4 | 214 lines | 1.2k words"));
+
+        let narrow = compose_status("compact | Saved", "Esc close", &stats, 40);
+        assert_eq!(narrow.chars().count(), 40);
+        assert!(narrow.ends_with("Esc close"), "{narrow}");
+        assert!(!narrow.contains("Ln 12"), "{narrow}");
+
+        let tight = compose_status("compact | a long status message", "Esc close", &stats, 40);
+        assert_eq!(tight, "compact | a long status message");
+    }
+
+    #[test]
+    fn format_count_shortens_large_numbers() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(1_000), "1k");
+        assert_eq!(format_count(1_234), "1.2k");
+        assert_eq!(format_count(2_500_000), "2.5M");
+    }
+
     #[test]
     fn truncate_middle_handles_short_caps() {
         assert_eq!(truncate_middle("abcdef", 3), "...");
@@ -158,13 +319,13 @@ mod tests {
 
     #[test]
     fn compose_status_returns_left_when_no_space_or_empty_right() {
-        assert_eq!(compose_status("left", "", 20), "left");
-        assert_eq!(compose_status("left", "right", 8), "left");
+        assert_eq!(compose_status("left", "", "", 20), "left");
+        assert_eq!(compose_status("left", "right", "", 8), "left");
     }
 
     #[test]
     fn build_status_bar_creates_line_with_mode_badge() {
-        let theme = build_theme(ThemeChoice::Default, false);
+        let theme = build_theme(ThemeChoice::Default, false, None);
         let config = StatusBarConfig {
             mode: "normal",
             filename: "test.md",
@@ -173,6 +334,8 @@ mod tests {
             line: 0,
             col: 0,
             total_lines: 10,
+            words: Some(0),
+            line_ending: LineEnding::Lf,
             scroll_percent: 0,
             message: "Ready",
             hint: "",
@@ -189,7 +352,7 @@ mod tests {
 
     #[test]
     fn build_status_bar_shows_dirty_indicator() {
-        let theme = build_theme(ThemeChoice::Default, false);
+        let theme = build_theme(ThemeChoice::Default, false, None);
         let config = StatusBarConfig {
             mode: "normal",
             filename: "test.md",
@@ -198,6 +361,8 @@ mod tests {
             line: 5,
             col: 10,
             total_lines: 100,
+            words: Some(1_500),
+            line_ending: LineEnding::CrLf,
             scroll_percent: 50,
             message: "",
             hint: "",
@@ -210,12 +375,13 @@ mod tests {
         assert!(text.contains("[+]"));
         assert!(text.contains("Ln 6"));
         assert!(text.contains("Col 11"));
+        assert!(text.contains("100 lines | 1.5k words | CRLF"));
         assert!(text.contains("50%"));
     }
 
     #[test]
     fn build_status_bar_shows_readonly_badge() {
-        let theme = build_theme(ThemeChoice::Default, false);
+        let theme = build_theme(ThemeChoice::Default, false, None);
         let config = StatusBarConfig {
             mode: "normal",
             filename: "test.md",
@@ -224,6 +390,8 @@ mod tests {
             line: 0,
             col: 0,
             total_lines: 10,
+            words: Some(0),
+            line_ending: LineEnding::Lf,
             scroll_percent: 0,
             message: "",
             hint: "",
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use mdv_core::SegmentKind;
 use ratatui::style::{Color, Modifier, Style};
 
@@ -38,17 +40,258 @@ pub struct ThemeTokens {
     pub hr: Style,
     pub task_done: Style,
     pub task_pending: Style,
+    /// Raw HTML blocks.
+    pub html: Style,
+    pub footnote: Style,
+    /// A footnote reference with no definition.
+    pub footnote_missing: Style,
+    pub selection: Style,
+    /// Matches of the last search in the editor.
+    pub search_match: Style,
+    /// The match the cursor is on.
+    pub current_match: Style,
+    /// Words spell checking does not know.
+    pub misspelled: Style,
+}
+
+/// Per-token style tweaks from the config file, layered over a base theme.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StyleOverride {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub modifiers: Modifier,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThemeOverrides {
+    tokens: Vec<(String, StyleOverride)>,
 }
 
-pub fn build_theme(choice: ThemeChoice, no_color: bool) -> ThemeTokens {
+impl ThemeOverrides {
+    /// Record an override; returns `false` when `token` is not a theme field.
+    pub fn set(&mut self, token: &str, style: StyleOverride) -> bool {
+        if !is_token_name(token) {
+            return false;
+        }
+        self.tokens.retain(|(name, _)| name != token);
+        self.tokens.push((token.to_string(), style));
+        true
+    }
+
+    #[cfg(test)]
+    pub fn get(&self, token: &str) -> Option<&StyleOverride> {
+        self.tokens
+            .iter()
+            .find(|(name, _)| name == token)
+            .map(|(_, style)| style)
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    fn apply(&self, tokens: &mut ThemeTokens) {
+        for (name, over) in &self.tokens {
+            let Some(style) = token_mut(tokens, name) else {
+                continue;
+            };
+            if let Some(fg) = over.fg {
+                *style = style.fg(fg);
+            }
+            if let Some(bg) = over.bg {
+                *style = style.bg(bg);
+            }
+            *style = style.add_modifier(over.modifiers);
+        }
+    }
+}
+
+pub fn build_theme(
+    choice: ThemeChoice,
+    no_color: bool,
+    overrides: Option<&ThemeOverrides>,
+) -> ThemeTokens {
+    // Overrides are mostly colors, so `--no-color` ignores them wholesale.
     if no_color {
         return monochrome_theme();
     }
 
-    match choice {
+    let mut tokens = match choice {
         ThemeChoice::Auto | ThemeChoice::Default => default_theme(),
         ThemeChoice::HighContrast => high_contrast_theme(),
+        ThemeChoice::Light => light_theme(),
+    };
+    if let Some(overrides) = overrides {
+        overrides.apply(&mut tokens);
     }
+    tokens
+}
+
+fn is_token_name(name: &str) -> bool {
+    token_mut(&mut monochrome_theme(), name).is_some()
+}
+
+fn token_mut<'a>(tokens: &'a mut ThemeTokens, name: &str) -> Option<&'a mut Style> {
+    let style = match name {
+        "top_bar" => &mut tokens.top_bar,
+        "status_ok" => &mut tokens.status_ok,
+        "status_warn" => &mut tokens.status_warn,
+        "status_error" => &mut tokens.status_error,
+        "status_bg" => &mut tokens.status_bg,
+        "status_mode" => &mut tokens.status_mode,
+        "status_position" => &mut tokens.status_position,
+        "status_file" => &mut tokens.status_file,
+        "status_dirty" => &mut tokens.status_dirty,
+        "status_clean" => &mut tokens.status_clean,
+        "status_separator" => &mut tokens.status_separator,
+        "pane_border" => &mut tokens.pane_border,
+        "pane_focus" => &mut tokens.pane_focus,
+        "help" => &mut tokens.help,
+        "heading" => &mut tokens.heading,
+        "list_bullet" => &mut tokens.list_bullet,
+        "link" => &mut tokens.link,
+        "code" => &mut tokens.code,
+        "quote" => &mut tokens.quote,
+        "table_header" => &mut tokens.table_header,
+        "conflict_local" => &mut tokens.conflict_local,
+        "conflict_external" => &mut tokens.conflict_external,
+        "plain" => &mut tokens.plain,
+        "line_number" => &mut tokens.line_number,
+        "line_number_current" => &mut tokens.line_number_current,
+        "scroll_indicator" => &mut tokens.scroll_indicator,
+        "emphasis" => &mut tokens.emphasis,
+        "strong" => &mut tokens.strong,
+        "strikethrough" => &mut tokens.strikethrough,
+        "hr" => &mut tokens.hr,
+        "task_done" => &mut tokens.task_done,
+        "task_pending" => &mut tokens.task_pending,
+        "html" => &mut tokens.html,
+        "footnote" => &mut tokens.footnote,
+        "footnote_missing" => &mut tokens.footnote_missing,
+        "selection" => &mut tokens.selection,
+        "search_match" => &mut tokens.search_match,
+        "current_match" => &mut tokens.current_match,
+        "misspelled" => &mut tokens.misspelled,
+        _ => return None,
+    };
+    Some(style)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Background {
+    Dark,
+    Light,
+}
+
+/// How long to wait for the terminal to answer the OSC 11 color query.
+const OSC11_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Pick the theme `Auto` should use; explicit choices pass through untouched.
+pub fn resolve_auto(choice: ThemeChoice, background: Option<Background>) -> ThemeChoice {
+    match (choice, background) {
+        (ThemeChoice::Auto, Some(Background::Light)) => ThemeChoice::Light,
+        _ => choice,
+    }
+}
+
+/// Classify the terminal background from an OSC 11 reply, falling back to
+/// `COLORFGBG`. `None` means unknown, which keeps the dark default.
+pub fn detect_background(colorfgbg: Option<&str>, osc11_reply: Option<&str>) -> Option<Background> {
+    osc11_reply
+        .and_then(parse_osc11_reply)
+        .or_else(|| colorfgbg.and_then(parse_colorfgbg))
+}
+
+/// Detect the live terminal background. Only call while raw mode is on so the
+/// terminal's reply is not echoed.
+pub fn detect_terminal_background() -> Option<Background> {
+    let colorfgbg = std::env::var("COLORFGBG").ok();
+    if let Some(background) = detect_background(colorfgbg.as_deref(), None) {
+        return Some(background);
+    }
+    let reply = query_osc11(OSC11_TIMEOUT);
+    detect_background(None, reply.as_deref())
+}
+
+/// `COLORFGBG` is `fg;bg` (some terminals add a middle field); the last field
+/// is an ANSI color index.
+fn parse_colorfgbg(value: &str) -> Option<Background> {
+    let bg = value.rsplit(';').next()?.trim().parse::<u8>().ok()?;
+    match bg {
+        7 | 15 => Some(Background::Light),
+        0..=15 => Some(Background::Dark),
+        _ => None,
+    }
+}
+
+/// Parse `ESC ] 11 ; rgb:RRRR/GGGG/BBBB` terminated by BEL or ST.
+fn parse_osc11_reply(reply: &str) -> Option<Background> {
+    let start = reply.find("rgb:")? + 4;
+    let body = reply[start..].trim_end_matches(['\u{7}', '\u{1b}', '\\']);
+    let mut channels = body.split('/').map(|part| {
+        let digits = part.get(..part.len().min(4))?;
+        let value = u32::from_str_radix(digits, 16).ok()?;
+        let max = (1u32 << (4 * digits.len())) - 1;
+        Some(value as f32 / max as f32)
+    });
+    let (r, g, b) = (channels.next()??, channels.next()??, channels.next()??);
+    let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    Some(if luminance > 0.5 {
+        Background::Light
+    } else {
+        Background::Dark
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn query_osc11(timeout: Duration) -> Option<String> {
+    use std::fs::OpenOptions;
+    use std::io::{ErrorKind, Read, Write};
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::time::Instant;
+
+    #[cfg(target_os = "linux")]
+    const O_NONBLOCK: i32 = 0o4000;
+    #[cfg(target_os = "macos")]
+    const O_NONBLOCK: i32 = 0x0004;
+
+    // A separate non-blocking handle so a silent terminal cannot stall startup.
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(O_NONBLOCK)
+        .open("/dev/tty")
+        .ok()?;
+    tty.write_all(b"\x1b]11;?\x1b\\").ok()?;
+    tty.flush().ok()?;
+
+    let deadline = Instant::now() + timeout;
+    let mut reply = Vec::new();
+    let mut buf = [0u8; 64];
+    while Instant::now() < deadline {
+        match tty.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                reply.extend_from_slice(&buf[..n]);
+                if reply.ends_with(b"\x07") || reply.ends_with(b"\x1b\\") {
+                    break;
+                }
+            }
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            Err(_) => break,
+        }
+    }
+    String::from_utf8(reply)
+        .ok()
+        .filter(|reply| !reply.is_empty())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn query_osc11(_timeout: Duration) -> Option<String> {
+    None
 }
 
 pub fn style_for_segment(tokens: &ThemeTokens, kind: SegmentKind) -> Style {
@@ -62,6 +305,18 @@ pub fn style_for_segment(tokens: &ThemeTokens, kind: SegmentKind) -> Style {
         SegmentKind::TableHeader => tokens.table_header,
         SegmentKind::ConflictLocal => tokens.conflict_local,
         SegmentKind::ConflictExternal => tokens.conflict_external,
+        SegmentKind::ConflictContext => tokens.plain.add_modifier(Modifier::DIM),
+        SegmentKind::Emphasis => tokens.emphasis,
+        SegmentKind::Strong => tokens.strong,
+        SegmentKind::Strikethrough => tokens.strikethrough,
+        SegmentKind::Rule => tokens.hr,
+        SegmentKind::TaskDone => tokens.task_done,
+        SegmentKind::TaskPending => tokens.task_pending,
+        SegmentKind::Html => tokens.html,
+        SegmentKind::FootnoteRef => tokens.footnote,
+        SegmentKind::FootnoteMissing => tokens.footnote_missing,
+        SegmentKind::FrontMatter => tokens.plain.add_modifier(Modifier::DIM),
+        _ => tokens.plain,
     }
 }
 
@@ -145,6 +400,21 @@ fn default_theme() -> ThemeTokens {
         hr: Style::default().fg(Color::Rgb(92, 99, 112)),
         task_done: Style::default().fg(Color::Rgb(152, 195, 121)),
         task_pending: Style::default().fg(Color::Rgb(229, 192, 123)),
+        html: Style::default()
+            .fg(Color::Rgb(92, 99, 112))
+            .add_modifier(Modifier::DIM),
+        footnote: Style::default().fg(Color::Rgb(86, 182, 194)),
+        footnote_missing: Style::default()
+            .fg(Color::Rgb(229, 192, 123))
+            .add_modifier(Modifier::UNDERLINED),
+        selection: Style::default().bg(Color::Rgb(62, 68, 81)),
+        search_match: Style::default().bg(Color::Rgb(92, 80, 48)),
+        current_match: Style::default()
+            .fg(Color::Rgb(40, 44, 52))
+            .bg(Color::Rgb(229, 192, 123)),
+        misspelled: Style::default()
+            .fg(Color::Rgb(224, 108, 117))
+            .add_modifier(Modifier::UNDERLINED),
     }
 }
 
@@ -218,6 +488,117 @@ fn high_contrast_theme() -> ThemeTokens {
         hr: Style::default().fg(Color::White),
         task_done: Style::default().fg(Color::Green),
         task_pending: Style::default().fg(Color::Yellow),
+        html: Style::default().fg(Color::Gray),
+        footnote: Style::default().fg(Color::Cyan),
+        footnote_missing: Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::UNDERLINED),
+        selection: Style::default().fg(Color::Black).bg(Color::Cyan),
+        search_match: Style::default().fg(Color::Black).bg(Color::Yellow),
+        current_match: Style::default()
+            .fg(Color::Black)
+            .bg(Color::LightGreen)
+            .add_modifier(Modifier::BOLD),
+        misspelled: Style::default()
+            .fg(Color::LightRed)
+            .add_modifier(Modifier::UNDERLINED),
+    }
+}
+
+/// Dark text on light backgrounds; same roles as the default palette.
+fn light_theme() -> ThemeTokens {
+    ThemeTokens {
+        top_bar: Style::default()
+            .fg(Color::Rgb(56, 58, 66))
+            .bg(Color::Rgb(229, 229, 230))
+            .add_modifier(Modifier::BOLD),
+        status_ok: Style::default().fg(Color::Rgb(80, 161, 79)),
+        status_warn: Style::default().fg(Color::Rgb(152, 104, 1)),
+        status_error: Style::default().fg(Color::Rgb(202, 18, 67)),
+        status_bg: Style::default()
+            .fg(Color::Rgb(56, 58, 66))
+            .bg(Color::Rgb(229, 229, 230)),
+        status_mode: Style::default()
+            .fg(Color::Rgb(250, 250, 250))
+            .bg(Color::Rgb(64, 120, 242))
+            .add_modifier(Modifier::BOLD),
+        status_position: Style::default()
+            .fg(Color::Rgb(250, 250, 250))
+            .bg(Color::Rgb(166, 38, 164))
+            .add_modifier(Modifier::BOLD),
+        status_file: Style::default()
+            .fg(Color::Rgb(56, 58, 66))
+            .bg(Color::Rgb(208, 208, 210)),
+        status_dirty: Style::default()
+            .fg(Color::Rgb(152, 104, 1))
+            .bg(Color::Rgb(208, 208, 210))
+            .add_modifier(Modifier::BOLD),
+        status_clean: Style::default()
+            .fg(Color::Rgb(80, 161, 79))
+            .bg(Color::Rgb(208, 208, 210)),
+        status_separator: Style::default()
+            .fg(Color::Rgb(160, 161, 167))
+            .bg(Color::Rgb(229, 229, 230)),
+        pane_border: Style::default().fg(Color::Rgb(160, 161, 167)),
+        pane_focus: Style::default()
+            .fg(Color::Rgb(64, 120, 242))
+            .add_modifier(Modifier::BOLD),
+        help: Style::default().fg(Color::Rgb(56, 58, 66)),
+        heading: Style::default()
+            .fg(Color::Rgb(202, 18, 67))
+            .add_modifier(Modifier::BOLD),
+        list_bullet: Style::default().fg(Color::Rgb(166, 38, 164)),
+        link: Style::default()
+            .fg(Color::Rgb(64, 120, 242))
+            .add_modifier(Modifier::UNDERLINED),
+        code: Style::default()
+            .fg(Color::Rgb(152, 104, 1))
+            .bg(Color::Rgb(240, 240, 241)),
+        quote: Style::default()
+            .fg(Color::Rgb(105, 108, 119))
+            .add_modifier(Modifier::ITALIC),
+        table_header: Style::default()
+            .fg(Color::Rgb(1, 132, 188))
+            .add_modifier(Modifier::BOLD),
+        conflict_local: Style::default()
+            .fg(Color::Rgb(166, 38, 164))
+            .add_modifier(Modifier::BOLD),
+        conflict_external: Style::default()
+            .fg(Color::Rgb(80, 161, 79))
+            .add_modifier(Modifier::BOLD),
+        plain: Style::default().fg(Color::Rgb(56, 58, 66)),
+        line_number: Style::default().fg(Color::Rgb(160, 161, 167)),
+        line_number_current: Style::default()
+            .fg(Color::Rgb(152, 104, 1))
+            .add_modifier(Modifier::BOLD),
+        scroll_indicator: Style::default()
+            .fg(Color::Rgb(64, 120, 242))
+            .add_modifier(Modifier::BOLD),
+        emphasis: Style::default()
+            .fg(Color::Rgb(56, 58, 66))
+            .add_modifier(Modifier::ITALIC),
+        strong: Style::default()
+            .fg(Color::Rgb(24, 24, 27))
+            .add_modifier(Modifier::BOLD),
+        strikethrough: Style::default()
+            .fg(Color::Rgb(160, 161, 167))
+            .add_modifier(Modifier::CROSSED_OUT),
+        hr: Style::default().fg(Color::Rgb(160, 161, 167)),
+        task_done: Style::default().fg(Color::Rgb(80, 161, 79)),
+        task_pending: Style::default().fg(Color::Rgb(152, 104, 1)),
+        html: Style::default()
+            .fg(Color::Rgb(160, 161, 167))
+            .add_modifier(Modifier::DIM),
+        footnote: Style::default().fg(Color::Rgb(1, 132, 188)),
+        footnote_missing: Style::default()
+            .fg(Color::Rgb(152, 104, 1))
+            .add_modifier(Modifier::UNDERLINED),
+        selection: Style::default().bg(Color::Rgb(208, 216, 240)),
+        search_match: Style::default().bg(Color::Rgb(255, 236, 163)),
+        current_match: Style::default().bg(Color::Rgb(255, 184, 92)),
+        misspelled: Style::default()
+            .fg(Color::Rgb(202, 18, 67))
+            .add_modifier(Modifier::UNDERLINED),
     }
 }
 
@@ -258,6 +639,17 @@ fn monochrome_theme() -> ThemeTokens {
         hr: base,
         task_done: base,
         task_pending: base,
+        html: base.add_modifier(Modifier::DIM),
+        footnote: base,
+        footnote_missing: base.add_modifier(Modifier::UNDERLINED),
+        selection: base.add_modifier(Modifier::REVERSED),
+        search_match: base.add_modifier(Modifier::UNDERLINED),
+        current_match: base
+            .add_modifier(Modifier::UNDERLINED)
+            .add_modifier(Modifier::BOLD),
+        misspelled: base
+            .add_modifier(Modifier::UNDERLINED)
+            .add_modifier(Modifier::ITALIC),
     }
 }
 
@@ -268,26 +660,127 @@ mod tests {
 
     use crate::app::state::ThemeChoice;
 
-    use super::{build_theme, style_for_segment};
+    use super::{
+        Background, StyleOverride, ThemeOverrides, build_theme, detect_background, resolve_auto,
+        style_for_segment,
+    };
 
     #[test]
     fn no_color_theme_is_bold_for_heading() {
-        let theme = build_theme(ThemeChoice::Default, true);
+        let theme = build_theme(ThemeChoice::Default, true, None);
         let style = style_for_segment(&theme, SegmentKind::Heading);
         assert!(style.add_modifier.contains(ratatui::style::Modifier::BOLD));
     }
 
     #[test]
     fn auto_and_default_themes_match() {
-        let auto = build_theme(ThemeChoice::Auto, false);
-        let default = build_theme(ThemeChoice::Default, false);
+        let auto = build_theme(ThemeChoice::Auto, false, None);
+        let default = build_theme(ThemeChoice::Default, false, None);
         assert_eq!(auto.heading.fg, default.heading.fg);
         assert_eq!(auto.link.fg, default.link.fg);
     }
 
+    #[test]
+    fn detect_background_reads_colorfgbg() {
+        assert_eq!(
+            detect_background(Some("0;15"), None),
+            Some(Background::Light)
+        );
+        assert_eq!(
+            detect_background(Some("15;default;7"), None),
+            Some(Background::Light)
+        );
+        assert_eq!(
+            detect_background(Some("15;0"), None),
+            Some(Background::Dark)
+        );
+        assert_eq!(detect_background(Some("garbage"), None), None);
+        assert_eq!(detect_background(None, None), None);
+    }
+
+    #[test]
+    fn detect_background_prefers_osc11_reply() {
+        let light = "\u{1b}]11;rgb:ffff/ffff/ffff\u{1b}\\";
+        let dark = "\u{1b}]11;rgb:2828/2c2c/3434\u{7}";
+        assert_eq!(
+            detect_background(Some("15;0"), Some(light)),
+            Some(Background::Light)
+        );
+        assert_eq!(
+            detect_background(Some("0;15"), Some(dark)),
+            Some(Background::Dark)
+        );
+        assert_eq!(
+            detect_background(None, Some("\u{1b}]11;rgb:ee/ee/ee\u{7}")),
+            Some(Background::Light)
+        );
+        assert_eq!(
+            detect_background(Some("0;15"), Some("no reply")),
+            Some(Background::Light)
+        );
+        assert_eq!(
+            detect_background(None, Some("\u{1b}]11;rgb:zz/00/00")),
+            None
+        );
+    }
+
+    #[test]
+    fn resolve_auto_only_changes_auto() {
+        let light = Some(Background::Light);
+        assert_eq!(resolve_auto(ThemeChoice::Auto, light), ThemeChoice::Light);
+        assert_eq!(
+            resolve_auto(ThemeChoice::Auto, Some(Background::Dark)),
+            ThemeChoice::Auto
+        );
+        assert_eq!(resolve_auto(ThemeChoice::Auto, None), ThemeChoice::Auto);
+        assert_eq!(
+            resolve_auto(ThemeChoice::Default, light),
+            ThemeChoice::Default
+        );
+        assert_eq!(
+            resolve_auto(ThemeChoice::HighContrast, light),
+            ThemeChoice::HighContrast
+        );
+    }
+
+    #[test]
+    fn light_theme_uses_dark_text() {
+        let theme = build_theme(ThemeChoice::Light, false, None);
+        assert_eq!(theme.plain.fg, Some(Color::Rgb(56, 58, 66)));
+        assert!(theme.selection.bg.is_some());
+        assert_ne!(
+            theme.heading.fg,
+            build_theme(ThemeChoice::Default, false, None).heading.fg
+        );
+    }
+
+    #[test]
+    fn overrides_merge_over_base_theme() {
+        let mut overrides = ThemeOverrides::default();
+        assert!(overrides.set(
+            "heading",
+            StyleOverride {
+                fg: Some(Color::Cyan),
+                bg: None,
+                modifiers: Modifier::UNDERLINED,
+            },
+        ));
+        assert!(!overrides.set("nope", StyleOverride::default()));
+
+        let base = build_theme(ThemeChoice::Default, false, None);
+        let theme = build_theme(ThemeChoice::Default, false, Some(&overrides));
+        assert_eq!(theme.heading.fg, Some(Color::Cyan));
+        assert!(theme.heading.add_modifier.contains(Modifier::BOLD));
+        assert!(theme.heading.add_modifier.contains(Modifier::UNDERLINED));
+        assert_eq!(theme.code, base.code);
+
+        let mono = build_theme(ThemeChoice::Default, true, Some(&overrides));
+        assert_eq!(mono.heading.fg, None);
+    }
+
     #[test]
     fn high_contrast_theme_sets_bg_and_focus() {
-        let theme = build_theme(ThemeChoice::HighContrast, false);
+        let theme = build_theme(ThemeChoice::HighContrast, false, None);
         assert_eq!(theme.top_bar.bg, Some(Color::Black));
         assert_eq!(theme.status_warn.bg, Some(Color::Black));
         assert!(theme.pane_focus.add_modifier.contains(Modifier::BOLD));
@@ -295,7 +788,7 @@ mod tests {
 
     #[test]
     fn no_color_overrides_selected_theme() {
-        let theme = build_theme(ThemeChoice::HighContrast, true);
+        let theme = build_theme(ThemeChoice::HighContrast, true, None);
         assert_eq!(theme.plain.fg, None);
         assert!(theme.top_bar.add_modifier.contains(Modifier::BOLD));
         assert!(theme.link.add_modifier.contains(Modifier::UNDERLINED));
@@ -303,7 +796,7 @@ mod tests {
 
     #[test]
     fn style_for_segment_maps_all_kinds() {
-        let theme = build_theme(ThemeChoice::Default, false);
+        let theme = build_theme(ThemeChoice::Default, false, None);
         let kinds = [
             SegmentKind::Plain,
             SegmentKind::Heading,
@@ -314,6 +807,17 @@ mod tests {
             SegmentKind::TableHeader,
             SegmentKind::ConflictLocal,
             SegmentKind::ConflictExternal,
+            SegmentKind::ConflictContext,
+            SegmentKind::Emphasis,
+            SegmentKind::Strong,
+            SegmentKind::Strikethrough,
+            SegmentKind::Rule,
+            SegmentKind::TaskDone,
+            SegmentKind::TaskPending,
+            SegmentKind::Html,
+            SegmentKind::FootnoteRef,
+            SegmentKind::FootnoteMissing,
+            SegmentKind::FrontMatter,
         ];
 
         for kind in kinds {
@@ -325,7 +829,7 @@ mod tests {
 
     #[test]
     fn default_theme_has_line_numbers_styled() {
-        let theme = build_theme(ThemeChoice::Default, false);
+        let theme = build_theme(ThemeChoice::Default, false, None);
         assert!(theme.line_number.fg.is_some());
         assert!(theme.line_number_current.fg.is_some());
         assert!(
@@ -338,7 +842,9 @@ mod tests {
 
     #[test]
     fn monochrome_theme_uses_modifiers_only() {
-        let theme = build_theme(ThemeChoice::Default, true);
+        let theme = build_theme(ThemeChoice::Default, true, None);
+        assert!(theme.selection.add_modifier.contains(Modifier::REVERSED));
+        assert_eq!(theme.selection.bg, None);
         assert!(theme.emphasis.add_modifier.contains(Modifier::ITALIC));
         assert!(theme.strong.add_modifier.contains(Modifier::BOLD));
         assert!(
@@ -121,6 +121,53 @@ fn path_mode_non_tty_renders_once_and_exits() {
     assert!(stdout.contains("body"), "stdout: {stdout}");
 }
 
+#[test]
+fn several_paths_non_tty_render_each_file() {
+    let readme = temp_file("multi-readme", "# Readme\n");
+    let changelog = temp_file("multi-changelog", "# Changelog\n");
+    let child = mdv_cmd()
+        .arg(&readme)
+        .arg(&changelog)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("spawn mdv");
+
+    let output = wait_with_timeout(child, test_timeout(1200));
+    let stdout = String::from_utf8(output.stdout).expect("utf8 stdout");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(stdout, "# Readme\n\n# Changelog");
+}
+
+#[test]
+fn a_line_suffix_is_ignored_when_piped() {
+    let path = temp_file("line-suffix", "# Title\n\nbody\n");
+    let child = mdv_cmd()
+        .arg(format!("{}:3:2", path.display()))
+        .args(["--line", "2"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("spawn mdv");
+
+    let output = wait_with_timeout(child, test_timeout(1200));
+    let stdout = String::from_utf8(output.stdout).expect("utf8 stdout");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(stdout, "# Title\nbody");
+}
+
 #[test]
 fn path_mode_non_tty_large_file_exits() {
     let content = large_markdown_fixture(1024 * 1024);
@@ -141,6 +188,146 @@ fn path_mode_non_tty_large_file_exits() {
     );
 }
 
+#[test]
+fn width_flag_overrides_columns_for_non_tty_render() {
+    let long = "word ".repeat(40);
+    let path = temp_file("width-flag", &long);
+    let output = mdv_cmd()
+        .arg("--width")
+        .arg("120")
+        .arg(&path)
+        .env("COLUMNS", "20")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .expect("run mdv");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).expect("utf8 stdout");
+    let first = stdout.lines().next().expect("first line");
+    let cols = first.chars().count();
+    assert!(cols > 20 && cols <= 120, "stdout: {stdout}");
+}
+
+#[test]
+fn width_flag_below_minimum_is_rejected() {
+    let path = temp_file("width-flag-small", "# x\n");
+    let output = mdv_cmd()
+        .arg("--width")
+        .arg("4")
+        .arg(&path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .expect("run mdv");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).expect("utf8 stderr");
+    assert!(
+        stderr.contains("width must be at least 8"),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn color_always_emits_ansi_for_non_tty_render() {
+    let path = temp_file("color-always", "# Title\n\nbody\n");
+    let output = mdv_cmd()
+        .arg("--color")
+        .arg("always")
+        .arg(&path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .expect("run mdv");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).expect("utf8 stdout");
+    assert!(stdout.starts_with("\x1b["), "stdout: {stdout:?}");
+    assert!(stdout.contains("# Title\x1b[0m"), "stdout: {stdout:?}");
+}
+
+#[test]
+fn color_always_picks_light_palette_from_colorfgbg_only_for_auto_theme() {
+    let path = temp_file("color-light", "# Title\n");
+    let render = |theme: &str| {
+        let output = mdv_cmd()
+            .env("COLORFGBG", "0;15")
+            .arg("--color")
+            .arg("always")
+            .arg("--theme")
+            .arg(theme)
+            .arg(&path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .expect("run mdv");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).expect("utf8 stdout")
+    };
+
+    let auto = render("auto");
+    assert!(auto.contains("38;2;202;18;67"), "stdout: {auto:?}");
+    let default = render("default");
+    assert!(default.contains("38;2;224;108;117"), "stdout: {default:?}");
+}
+
+#[test]
+fn config_flag_overrides_theme_colors_and_warns_on_bad_keys() {
+    let path = temp_file("config-theme", "# Title\n");
+    let config = temp_file(
+        "config-theme-toml",
+        "[theme]\nheading = \"#87cefa bold\"\nbogus = \"red\"\n",
+    );
+    let output = mdv_cmd()
+        .env_remove("COLORFGBG")
+        .arg("--color")
+        .arg("always")
+        .arg("--config")
+        .arg(&config)
+        .arg(&path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .expect("run mdv");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).expect("utf8 stdout");
+    assert!(stdout.contains("38;2;135;206;250"), "stdout: {stdout:?}");
+    let stderr = String::from_utf8(output.stderr).expect("utf8 stderr");
+    assert!(
+        stderr.contains("unknown theme key `bogus`"),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn color_auto_and_never_keep_non_tty_render_plain() {
+    let path = temp_file("color-plain", "# Title\n");
+    for mode in ["auto", "never"] {
+        let output = mdv_cmd()
+            .arg("--color")
+            .arg(mode)
+            .arg(&path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .expect("run mdv");
+
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).expect("utf8 stdout");
+        assert!(!stdout.contains('\x1b'), "{mode}: {stdout:?}");
+    }
+}
+
 #[test]
 fn stream_mode_reads_stdin_non_tty_and_exits() {
     let mut child = mdv_cmd()
@@ -168,6 +355,62 @@ fn stream_mode_reads_stdin_non_tty_and_exits() {
     assert!(stdout.contains("ok"), "stdout: {stdout}");
 }
 
+#[test]
+fn stream_mode_non_tty_prints_settled_blocks_before_stdin_closes() {
+    let early = "# Early\n\nfirst paragraph\n\n";
+    let late = "- a\n- b\n\nlast\n";
+    let mut child = mdv_cmd()
+        .args(["--stream", "--width", "60"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("spawn mdv stream");
+    let mut stdout = child.stdout.take().expect("stdout");
+    let (tx, rx) = std::sync::mpsc::channel();
+    let reader = thread::spawn(move || {
+        let mut buf = [0u8; 256];
+        loop {
+            match std::io::Read::read(&mut stdout, &mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => {
+                    let _ = tx.send(buf[..read].to_vec());
+                }
+            }
+        }
+    });
+
+    let mut stdin = child.stdin.take().expect("stdin");
+    stdin.write_all(early.as_bytes()).expect("write early");
+    stdin.flush().expect("flush");
+    let mut seen = Vec::new();
+    let deadline = std::time::Instant::now() + test_timeout(3000);
+    while !String::from_utf8_lossy(&seen).contains("# Early") {
+        let left = deadline.saturating_duration_since(std::time::Instant::now());
+        seen.extend(rx.recv_timeout(left).expect("output before stdin closes"));
+    }
+    assert!(
+        child.try_wait().expect("try_wait").is_none(),
+        "still reading"
+    );
+
+    stdin.write_all(late.as_bytes()).expect("write late");
+    drop(stdin);
+    let output = wait_with_timeout(child, test_timeout(1200));
+    assert!(output.status.success());
+    reader.join().expect("reader");
+    seen.extend(rx.try_iter().flatten());
+
+    let path = temp_file("stream-same", &format!("{early}{late}"));
+    let whole = mdv_cmd()
+        .args(["--width", "60"])
+        .arg(&path)
+        .output()
+        .expect("run mdv");
+    let _ = fs::remove_file(&path);
+    assert_eq!(String::from_utf8(seen), String::from_utf8(whole.stdout));
+}
+
 #[test]
 fn stream_mode_invalid_utf8_hits_error_path_and_exits() {
     let mut child = mdv_cmd()
@@ -208,6 +451,436 @@ fn stream_with_path_errors() {
     );
 }
 
+#[test]
+fn export_html_writes_a_standalone_page() {
+    let input = temp_file("export-in", "# Notes\n\n- [x] done\n\n$x^2$\n");
+    let output_path = input.with_extension("html");
+    let output = mdv_cmd()
+        .arg(&input)
+        .arg("--export-html")
+        .arg(&output_path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .expect("run mdv");
+    assert!(output.status.success(), "stderr: {:?}", output.stderr);
+    assert!(output.stdout.is_empty());
+
+    let html = fs::read_to_string(&output_path).expect("read export");
+    assert!(html.starts_with("<!DOCTYPE html>"), "html: {html}");
+    assert!(html.contains("<title>Notes</title>"), "html: {html}");
+    assert!(html.contains("type=\"checkbox\" checked"), "html: {html}");
+    assert!(html.contains("<span class=\"math math-inline\">x^2</span>"));
+
+    let stdout = mdv_cmd()
+        .arg(&input)
+        .arg("--export-html")
+        .arg("-")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .expect("run mdv");
+    assert!(stdout.status.success());
+    assert_eq!(String::from_utf8(stdout.stdout).expect("utf8 stdout"), html);
+
+    let _ = fs::remove_file(&input);
+    let _ = fs::remove_file(&output_path);
+}
+
+#[test]
+fn export_html_rejects_stream_and_bad_inputs() {
+    let conflict = mdv_cmd()
+        .args(["--stream", "--export-html", "-"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .expect("run mdv");
+    assert_eq!(conflict.status.code(), Some(2));
+    let stderr = String::from_utf8(conflict.stderr).expect("utf8 stderr");
+    assert!(stderr.contains("cannot be used with"), "stderr: {stderr}");
+
+    let no_path = mdv_cmd()
+        .args(["--export-html", "-"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .expect("run mdv");
+    assert_eq!(no_path.status.code(), Some(1));
+    let stderr = String::from_utf8(no_path.stderr).expect("utf8 stderr");
+    assert!(
+        stderr.contains("--export-html needs exactly one input path"),
+        "stderr: {stderr}"
+    );
+
+    let missing = std::env::temp_dir().join("mdv-export-missing-input.md");
+    let missing_output = mdv_cmd()
+        .arg(&missing)
+        .args(["--export-html", "-"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .expect("run mdv");
+    assert_eq!(missing_output.status.code(), Some(1));
+    let stderr = String::from_utf8(missing_output.stderr).expect("utf8 stderr");
+    assert!(stderr.contains("cannot read"), "stderr: {stderr}");
+}
+
+#[test]
+fn source_headings_keep_setext_underlines_when_piped() {
+    let input = temp_file(
+        "setext",
+        "Title\n=====\n\nSection\n---\n\n## Usage {#usage}\n",
+    );
+    let render = |extra: &[&str]| {
+        let output = mdv_cmd()
+            .arg(&input)
+            .args(extra)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .expect("run mdv");
+        assert!(output.status.success(), "stderr: {:?}", output.stderr);
+        String::from_utf8(output.stdout).expect("utf8 stdout")
+    };
+
+    assert_eq!(
+        render(&["--source-headings"]).trim_end(),
+        "Title\n=====\nSection\n-------\n## Usage {#usage}"
+    );
+    assert_eq!(
+        render(&[]).trim_end(),
+        "# Title\n## Section\n## Usage {#usage}"
+    );
+}
+
+#[test]
+fn from_and_to_print_a_line_range() {
+    let input = temp_file("range", "# Title\n\n```\none\ntwo\n```\n\nafter\n");
+    let output = mdv_cmd()
+        .arg(&input)
+        .args(["--from", "5", "--to", "8"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .expect("run mdv");
+    assert!(output.status.success(), "stderr: {:?}", output.stderr);
+    let stdout = String::from_utf8(output.stdout).expect("utf8 stdout");
+    assert_eq!(stdout.trim_end(), "two\n```\nafter");
+
+    let reversed = mdv_cmd()
+        .arg(&input)
+        .args(["--from", "10", "--to", "5"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .expect("run mdv");
+    assert_eq!(reversed.status.code(), Some(1));
+    let stderr = String::from_utf8(reversed.stderr).expect("utf8 stderr");
+    assert!(
+        stderr.contains("--from 10 is after --to 5"),
+        "stderr: {stderr}"
+    );
+
+    let _ = fs::remove_file(&input);
+}
+
+#[test]
+fn check_lints_every_path_and_exits_one_on_problems() {
+    let broken = temp_file(
+        "check-broken",
+        "# Intro\n\n[docs][nowhere]\n\n| a | b |\n|---|---|\n| 1 |\n\n# Intro\n\n```sh\nnever closed\n",
+    );
+    let clean = temp_file(
+        "check-clean",
+        "# Fine\n\n[docs][d]\n\n[d]: https://d.example\n",
+    );
+    let bad_utf8 = temp_file("check-utf8", "");
+    fs::write(&bad_utf8, b"# Bytes\n\nbad \xff\n").expect("write bytes");
+    let run = |args: &[&str], paths: &[&PathBuf]| {
+        mdv_cmd()
+            .args(args)
+            .args(paths)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .expect("run mdv")
+    };
+
+    let output = run(&["--check"], &[&broken, &clean, &bad_utf8]);
+    assert_eq!(output.status.code(), Some(1));
+    let (broken_name, utf8_name) = (broken.display(), bad_utf8.display());
+    assert_eq!(
+        String::from_utf8(output.stdout).expect("utf8 stdout"),
+        format!(
+            "{broken_name}:3: no definition for reference link [nowhere]\n\
+             {broken_name}:7: table row has 1 cell, the header has 2\n\
+             {broken_name}:9: heading anchor #intro is already used on line 1\n\
+             {broken_name}:11: code block opened here is never closed\n\
+             {utf8_name}:3: invalid UTF-8\n"
+        )
+    );
+
+    let output = run(&["--check=fences,utf8"], &[&broken]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).expect("utf8 stdout"),
+        format!("{broken_name}:11: code block opened here is never closed\n")
+    );
+
+    let output = run(&["--check"], &[&clean]);
+    assert!(output.status.success(), "stderr: {:?}", output.stderr);
+    assert!(output.stdout.is_empty());
+
+    let output = run(&["--check=spelling"], &[&clean]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown check: spelling"));
+
+    for path in [broken, clean, bad_utf8] {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[test]
+fn outline_prints_headings_per_file_as_text_or_json() {
+    let guide = temp_file(
+        "outline-guide",
+        "Guide\n=====\n\n## Install\n\n> ### Quoted\n\n#### Deep\n",
+    );
+    let notes = temp_file("outline-notes", "intro\n\n# Notes\n");
+    let run = |args: &[&str], paths: &[&PathBuf]| {
+        mdv_cmd()
+            .args(args)
+            .args(paths)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .expect("run mdv")
+    };
+
+    let output = run(&["--outline"], &[&guide]);
+    assert!(output.status.success(), "stderr: {:?}", output.stderr);
+    assert_eq!(
+        String::from_utf8(output.stdout).expect("utf8 stdout"),
+        "1:# Guide\n4:## Install\n6:### Quoted\n8:#### Deep\n"
+    );
+
+    let output = run(&["--outline", "--max-depth", "2"], &[&guide, &notes]);
+    assert!(output.status.success(), "stderr: {:?}", output.stderr);
+    let (guide_name, notes_name) = (guide.display(), notes.display());
+    assert_eq!(
+        String::from_utf8(output.stdout).expect("utf8 stdout"),
+        format!("{guide_name}:1:# Guide\n{guide_name}:4:## Install\n{notes_name}:3:# Notes\n")
+    );
+
+    let output = run(&["--outline", "--format", "json"], &[&notes]);
+    assert!(output.status.success(), "stderr: {:?}", output.stderr);
+    let json = String::from_utf8(output.stdout).expect("utf8 stdout");
+    assert_eq!(skip_json_value(&json).map(str::trim), Some(""), "{json}");
+    assert!(
+        json.ends_with(",\"line\":3,\"level\":1,\"text\":\"Notes\"}]\n"),
+        "{json}"
+    );
+
+    for (args, error) in [
+        (&["--outline"][..], "--outline needs at least one path"),
+        (&["--max-depth", "2"][..], "--outline"),
+        (&["--outline", "--max-depth", "7"][..], "7"),
+    ] {
+        let output = run(args, &[]);
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains(error), "{args:?}: {stderr}");
+    }
+
+    for path in [guide, notes] {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Skip one JSON value at the start of `text`, returning the rest, or
+/// `None` if it is not valid JSON.
+fn skip_json_value(text: &str) -> Option<&str> {
+    let text = text.trim_start();
+    match text.chars().next()? {
+        '{' => skip_json_list(&text[1..], '}', true),
+        '[' => skip_json_list(&text[1..], ']', false),
+        '"' => {
+            let mut escaped = false;
+            let end = text[1..].find(|ch| {
+                let end = ch == '"' && !escaped;
+                escaped = ch == '\\' && !escaped;
+                end
+            })?;
+            Some(&text[end + 2..])
+        }
+        _ => {
+            let end = text
+                .find(|ch: char| !(ch.is_ascii_alphanumeric() || "+-.".contains(ch)))
+                .unwrap_or(text.len());
+            let token = &text[..end];
+            (["true", "false", "null"].contains(&token) || token.parse::<f64>().is_ok())
+                .then_some(&text[end..])
+        }
+    }
+}
+
+/// The rest of an object or array after its opening bracket.
+fn skip_json_list(mut rest: &str, close: char, object: bool) -> Option<&str> {
+    rest = rest.trim_start();
+    if let Some(rest) = rest.strip_prefix(close) {
+        return Some(rest);
+    }
+    loop {
+        if object {
+            rest = skip_json_value(rest)?.trim_start().strip_prefix(':')?;
+        }
+        rest = skip_json_value(rest)?.trim_start();
+        match rest.strip_prefix(',') {
+            Some(next) => rest = next,
+            None => return rest.strip_prefix(close),
+        }
+    }
+}
+
+#[test]
+fn bench_render_times_each_pass_and_prints_json() {
+    let input = temp_file("bench", "# Bench\n\n- one\n- two\n\n```\ncode\n```\n");
+    let run = |args: &[&str]| {
+        mdv_cmd()
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .expect("run mdv")
+    };
+
+    let path = input.to_str().expect("utf8 path");
+    let output = run(&[
+        "--bench-render",
+        "3",
+        "--width",
+        "40",
+        "--bench-format",
+        "json",
+        path,
+    ]);
+    assert!(output.status.success(), "stderr: {:?}", output.stderr);
+    let json = String::from_utf8(output.stdout).expect("utf8 stdout");
+    assert_eq!(skip_json_value(&json).map(str::trim), Some(""), "{json}");
+    assert!(json.contains("\"width\":40,\"iterations\":3,"), "{json}");
+    for pass in ["lines", "segments", "cached"] {
+        assert!(json.contains(&format!("{{\"name\":\"{pass}\",")), "{json}");
+    }
+
+    let output = run(&["--bench-render", "2", "--bench-size", "16k"]);
+    assert!(output.status.success(), "stderr: {:?}", output.stderr);
+    let text = String::from_utf8(output.stdout).expect("utf8 stdout");
+    assert!(text.starts_with("source=synthetic bytes=16384 "), "{text}");
+    assert!(
+        text.contains(" width=80 iterations=2\npass=lines "),
+        "{text}"
+    );
+    assert_eq!(text.lines().count(), 4);
+
+    for (args, error) in [
+        (
+            &["--bench-render", "0"][..],
+            "iterations must be at least 1",
+        ),
+        (
+            &["--bench-render", "many"][..],
+            "invalid iteration count: many",
+        ),
+        (&["--bench-size", "1k"][..], "--bench-render"),
+        (&["--bench-render", "1"][..], "needs a path or --bench-size"),
+    ] {
+        let output = run(args);
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains(error), "{args:?}: {stderr}");
+    }
+
+    let _ = fs::remove_file(input);
+}
+
+#[test]
+fn max_lines_prints_only_the_top_of_the_preview() {
+    let input = temp_file(
+        "max-lines",
+        "# Title\n\n```\none\ntwo\nthree\n```\n\nafter\n",
+    );
+    let run = |args: &[&str]| {
+        let output = mdv_cmd()
+            .arg(&input)
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .expect("run mdv");
+        assert!(output.status.success(), "stderr: {:?}", output.stderr);
+        String::from_utf8(output.stdout).expect("utf8 stdout")
+    };
+
+    assert_eq!(run(&["--max-lines", "4"]), "# Title\n```\none\n```");
+    assert_eq!(run(&["--max-lines", "2", "--from", "5"]), "two\nthree");
+
+    let zero = mdv_cmd()
+        .arg(&input)
+        .args(["--max-lines", "0"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .expect("run mdv");
+    assert!(!zero.status.success());
+    let stderr = String::from_utf8(zero.stderr).expect("utf8 stderr");
+    assert!(
+        stderr.contains("max lines must be at least 1"),
+        "stderr: {stderr}"
+    );
+
+    let _ = fs::remove_file(&input);
+}
+
+#[test]
+fn piped_output_prints_front_matter_as_written() {
+    let input = temp_file(
+        "front-matter",
+        "---\ntitle: Notes\ntags: [a, b]\n---\n# Body\n",
+    );
+    let output = mdv_cmd()
+        .arg(&input)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .expect("run mdv");
+    assert!(output.status.success(), "stderr: {:?}", output.stderr);
+    let stdout = String::from_utf8(output.stdout).expect("utf8 stdout");
+    assert_eq!(stdout, "---\ntitle: Notes\ntags: [a, b]\n---\n# Body");
+
+    let _ = fs::remove_file(&input);
+}
+
+#[test]
+fn piped_output_keeps_shortcodes_unless_emoji_is_passed() {
+    let input = temp_file("emoji", "Shipped :tada: with `:tada:`\n");
+    let run = |args: &[&str]| {
+        let output = mdv_cmd()
+            .arg(&input)
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .expect("run mdv");
+        assert!(output.status.success(), "stderr: {:?}", output.stderr);
+        String::from_utf8(output.stdout).expect("utf8 stdout")
+    };
+
+    assert_eq!(run(&[]), "Shipped :tada: with `:tada:`");
+    assert_eq!(run(&["--emoji"]), "Shipped 🎉 with `:tada:`");
+
+    let _ = fs::remove_file(&input);
+}
+
 #[test]
 fn no_args_shows_help_and_exits_zero() {
     let output = mdv_cmd()
@@ -306,6 +979,58 @@ fn stream_mode_force_tui_exits_after_stdin_close() {
     assert_force_tui_exit_or_known_io_error(output);
 }
 
+#[cfg(unix)]
+#[test]
+fn sigterm_leaves_the_alternate_screen_and_exits_cleanly() {
+    let mut child = mdv_cmd()
+        .arg("--stream")
+        .env("MDV_FORCE_TUI", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("spawn mdv");
+    child
+        .stdin
+        .as_mut()
+        .expect("stdin")
+        .write_all(b"# still streaming\n")
+        .expect("write");
+    thread::sleep(test_timeout(300));
+    let status = Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .expect("kill");
+    assert!(status.success());
+
+    let output = wait_with_timeout(child, test_timeout(2000));
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\x1b[?1049l"));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn panic_restores_the_terminal_before_printing() {
+    let path = temp_file("force-panic", "# title\n");
+    let output = mdv_cmd()
+        .arg(&path)
+        .env("MDV_FORCE_TUI", "1")
+        .env("MDV_FORCE_PANIC", "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .expect("run mdv");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("MDV_FORCE_PANIC is set"));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\x1b[?1049l"));
+    let _ = fs::remove_file(&path);
+}
+
 #[test]
 fn stream_mode_force_tui_invalid_utf8_hits_stream_error_branch() {
     let mut child = mdv_cmd()
@@ -399,6 +1124,7 @@ fn pty_force_tui_interactive_accepts_new_ui_shortcuts() {
         stdin.write_all(&[0x1f]).expect("ctrl+/");
         stdin.write_all(&[0x1f]).expect("ctrl+/");
         stdin.write_all(&[0x11]).expect("ctrl+q");
+        stdin.write_all(b"q").expect("discard unsaved tab");
     }
     let _ = child.stdin.take();
 
@@ -0,0 +1,28 @@
+//! Render Markdown to plain lines with nothing but `mdv-core`.
+//!
+//! This only uses the renderer, so it builds for targets without a file
+//! system too:
+//!
+//! ```sh
+//! cargo run -p mdv-core --example render_lines -- 40
+//! cargo check -p mdv-core --no-default-features --example render_lines \
+//!     --target wasm32-unknown-unknown
+//! ```
+
+use mdv_core::render_preview_lines;
+
+const SAMPLE: &str = "# Playground\n\n\
+Some **bold** text, a [link](https://example.com) and `code`.\n\n\
+- [x] render to lines\n\
+- [ ] draw them somewhere\n\n\
+| Width | Wraps |\n|---|---|\n| 40 | yes |\n";
+
+fn main() {
+    let width = std::env::args()
+        .nth(1)
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(60);
+    for line in render_preview_lines(SAMPLE, width) {
+        println!("{line}");
+    }
+}
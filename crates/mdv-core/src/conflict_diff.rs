@@ -1,122 +1,293 @@
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use similar::{Algorithm, DiffTag, capture_diff_slices_deadline};
+
+/// Unchanged lines [`compute_conflict_hunks`] keeps on each side of a hunk.
+pub const DEFAULT_HUNK_CONTEXT: usize = 2;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ConflictHunk {
+    /// Numbered from 0 in document order when computed;
+    /// [`EditorBuffer`](crate::EditorBuffer) keeps the id of a hunk that is
+    /// still there when the hunks are computed again.
+    pub id: usize,
+    pub kind: HunkKind,
+    /// For a [`HunkKind::Move`], the index of the other half in the same
+    /// list; applying either half applies both.
+    pub moved_with: Option<usize>,
     pub local_start: usize,
     pub external_start: usize,
     pub local_lines: Vec<String>,
     pub external_lines: Vec<String>,
+    /// Local lines just before the hunk; fewer at the start of the document.
+    pub context_before: Vec<String>,
+    /// Local lines just after the hunk; fewer at the end of the document.
+    pub context_after: Vec<String>,
 }
 
-#[derive(Clone, Copy)]
-enum Op {
-    Equal,
-    Delete,
+/// What a hunk does to the local text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HunkKind {
+    /// Local lines the external text has in a different form.
+    Change,
+    /// Lines only the external text has.
     Insert,
+    /// Lines only the local text has.
+    Delete,
+    /// Half of a block the external text moved: the lines it took away or
+    /// the same lines where it put them. See [`ConflictHunk::moved_with`].
+    Move,
+}
+
+/// Hunks separated by fewer unchanged lines than this become one hunk.
+pub const MERGE_GAP: usize = 2;
+
+/// How long the diff may search for the smallest set of hunks before it
+/// settles for a bigger one.
+const DIFF_DEADLINE: Duration = Duration::from_secs(1);
+
+/// [`DIFF_DEADLINE`] from now, except on `wasm32-unknown-unknown`, which has
+/// no clock for `Instant::now` to read.
+fn diff_deadline() -> Option<Instant> {
+    if cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+        None
+    } else {
+        Some(Instant::now() + DIFF_DEADLINE)
+    }
 }
 
 pub fn compute_conflict_hunks(local: &str, external: &str) -> Vec<ConflictHunk> {
+    compute_conflict_hunks_with_context(local, external, DEFAULT_HUNK_CONTEXT)
+}
+
+/// Like [`compute_conflict_hunks`], with up to `context` unchanged lines on
+/// each side of every hunk.
+pub fn compute_conflict_hunks_with_context(
+    local: &str,
+    external: &str,
+    context: usize,
+) -> Vec<ConflictHunk> {
     let local_lines = split_lines(local);
+    // The empty piece after a trailing newline is not a line of its own.
+    let content_len = local_lines.len() - usize::from(local.ends_with('\n'));
     let external_lines = split_lines(external);
-    let ops = diff_ops(&local_lines, &external_lines);
-
-    let mut hunks = Vec::new();
-    let mut local_idx = 0usize;
-    let mut external_idx = 0usize;
-    let mut i = 0usize;
-
-    while i < ops.len() {
-        match ops[i] {
-            Op::Equal => {
-                local_idx += 1;
-                external_idx += 1;
-                i += 1;
-            }
-            Op::Delete | Op::Insert => {
-                let start_local = local_idx;
-                let start_external = external_idx;
-                let mut local_chunk = Vec::new();
-                let mut external_chunk = Vec::new();
-
-                while i < ops.len() {
-                    match ops[i] {
-                        Op::Delete => {
-                            local_chunk.push(local_lines[local_idx].clone());
-                            local_idx += 1;
-                            i += 1;
-                        }
-                        Op::Insert => {
-                            external_chunk.push(external_lines[external_idx].clone());
-                            external_idx += 1;
-                            i += 1;
-                        }
-                        Op::Equal => break,
-                    }
-                }
-
-                hunks.push(ConflictHunk {
-                    local_start: start_local,
-                    external_start: start_external,
-                    local_lines: local_chunk,
-                    external_lines: external_chunk,
-                });
+    let spans = merge_spans(
+        changed_spans(&local_lines, &external_lines),
+        &local_lines,
+        &external_lines,
+    );
+
+    spans
+        .iter()
+        .enumerate()
+        .map(|(id, span)| {
+            let before = span.local.start.min(content_len);
+            let after = span.local.end.min(content_len);
+            let kind = if span.moved_with.is_some() {
+                HunkKind::Move
+            } else if span.local.is_empty() {
+                HunkKind::Insert
+            } else if span.external.is_empty() {
+                HunkKind::Delete
+            } else {
+                HunkKind::Change
+            };
+            ConflictHunk {
+                id,
+                kind,
+                moved_with: span.moved_with,
+                local_start: span.local.start,
+                external_start: span.external.start,
+                local_lines: local_lines[span.local.clone()].to_vec(),
+                external_lines: external_lines[span.external.clone()].to_vec(),
+                context_before: local_lines[before.saturating_sub(context)..before].to_vec(),
+                context_after: local_lines[after..(after + context).min(content_len)].to_vec(),
             }
-        }
-    }
+        })
+        .collect()
+}
 
-    hunks
+/// Byte ranges of the part of `local` and `external` that differs, between
+/// their common start and common end.
+pub fn changed_span(local: &str, external: &str) -> (Range<usize>, Range<usize>) {
+    let prefix: usize = local
+        .chars()
+        .zip(external.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum();
+    let suffix: usize = local[prefix..]
+        .chars()
+        .rev()
+        .zip(external[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum();
+    (
+        prefix..local.len() - suffix,
+        prefix..external.len() - suffix,
+    )
 }
 
-fn split_lines(text: &str) -> Vec<String> {
-    text.split('\n').map(ToString::to_string).collect()
+impl ConflictHunk {
+    /// Both sides have the same lines apart from spaces and tabs, such as a
+    /// re-indented block.
+    pub fn is_whitespace_only(&self) -> bool {
+        let squeeze = |lines: &[String]| -> Vec<String> {
+            lines
+                .iter()
+                .map(|line| line.split_whitespace().collect())
+                .collect()
+        };
+        self.kind == HunkKind::Change && squeeze(&self.local_lines) == squeeze(&self.external_lines)
+    }
 }
 
-fn diff_ops(local: &[String], external: &[String]) -> Vec<Op> {
-    let n = local.len();
-    let m = external.len();
-    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+/// Lines `local` of the local text that the external text has as `external`.
+#[derive(Debug, Clone)]
+struct Span {
+    local: Range<usize>,
+    external: Range<usize>,
+    /// Index of the other half of a moved block.
+    moved_with: Option<usize>,
+}
 
-    for i in (0..n).rev() {
-        for j in (0..m).rev() {
-            lcs[i][j] = if local[i] == external[j] {
-                lcs[i + 1][j + 1] + 1
-            } else {
-                lcs[i + 1][j].max(lcs[i][j + 1])
-            };
+/// Patience diff of the two line lists, as the runs that differ.
+fn changed_spans(local: &[String], external: &[String]) -> Vec<Span> {
+    let ops = capture_diff_slices_deadline(Algorithm::Patience, local, external, diff_deadline());
+    let mut spans: Vec<Span> = Vec::new();
+    for op in ops {
+        let (tag, old, new) = op.as_tag_tuple();
+        if tag == DiffTag::Equal {
+            continue;
+        }
+        match spans.last_mut() {
+            // Delete, insert and replace runs that touch are one change.
+            Some(last) if last.local.end == old.start && last.external.end == new.start => {
+                last.local.end = old.end;
+                last.external.end = new.end;
+            }
+            _ => spans.push(Span {
+                local: old,
+                external: new,
+                moved_with: None,
+            }),
         }
     }
+    spans
+}
 
-    let mut i = 0usize;
-    let mut j = 0usize;
-    let mut ops = Vec::new();
-
-    while i < n && j < m {
-        if local[i] == external[j] {
-            ops.push(Op::Equal);
-            i += 1;
-            j += 1;
-        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
-            ops.push(Op::Delete);
-            i += 1;
-        } else {
-            ops.push(Op::Insert);
-            j += 1;
+/// Pair each removed block with an added block of the same lines, then join
+/// the other spans that are less than [`MERGE_GAP`] lines apart.
+fn merge_spans(mut spans: Vec<Span>, local: &[String], external: &[String]) -> Vec<Span> {
+    for removed in 0..spans.len() {
+        if !spans[removed].external.is_empty() || spans[removed].moved_with.is_some() {
+            continue;
+        }
+        let lines = &local[spans[removed].local.clone()];
+        if lines.iter().all(|line| line.trim().is_empty()) {
+            continue;
+        }
+        let added = (0..spans.len()).find(|&index| {
+            let span = &spans[index];
+            span.local.is_empty()
+                && span.moved_with.is_none()
+                && external[span.external.clone()] == *lines
+        });
+        if let Some(added) = added {
+            spans[removed].moved_with = Some(added);
+            spans[added].moved_with = Some(removed);
         }
     }
 
-    while i < n {
-        ops.push(Op::Delete);
-        i += 1;
+    // Moves keep their own hunks; indexes are fixed up once the rest merged.
+    let mut merged: Vec<Span> = Vec::with_capacity(spans.len());
+    let mut new_index = Vec::with_capacity(spans.len());
+    for span in spans {
+        match merged.last_mut() {
+            Some(last)
+                if last.moved_with.is_none()
+                    && span.moved_with.is_none()
+                    && span.local.start - last.local.end < MERGE_GAP =>
+            {
+                last.local.end = span.local.end;
+                last.external.end = span.external.end;
+            }
+            _ => merged.push(span),
+        }
+        new_index.push(merged.len() - 1);
     }
-    while j < m {
-        ops.push(Op::Insert);
-        j += 1;
+    for span in &mut merged {
+        if let Some(other) = span.moved_with.as_mut() {
+            *other = new_index[*other];
+        }
     }
+    merged
+}
+
+/// `local` with a `<<<<<<< local` / `=======` / `>>>>>>> external` block
+/// around each hunk; the unchanged lines between hunks are kept as they are.
+/// `hunks` must come from [`compute_conflict_hunks`] for the same two texts.
+pub fn render_hunk_markers(local: &str, external: &str, hunks: &[ConflictHunk]) -> String {
+    let local_lines: Vec<&str> = local.split('\n').collect();
+    let external_lines: Vec<&str> = external.split('\n').collect();
+    let mut out = Vec::with_capacity(local_lines.len() + hunks.len() * 3);
+    let mut next = 0usize;
 
-    ops
+    for hunk in hunks {
+        let start = hunk.local_start.clamp(next, local_lines.len());
+        let end = (start + hunk.local_lines.len()).min(local_lines.len());
+        let external_start = hunk.external_start.min(external_lines.len());
+        let external_end = (external_start + hunk.external_lines.len()).min(external_lines.len());
+
+        out.extend_from_slice(&local_lines[next..start]);
+        out.push("<<<<<<< local");
+        out.extend_from_slice(&local_lines[start..end]);
+        out.push("=======");
+        out.extend_from_slice(&external_lines[external_start..external_end]);
+        out.push(">>>>>>> external");
+        next = end;
+    }
+    out.extend_from_slice(&local_lines[next..]);
+    out.join("\n")
+}
+
+/// Give each of `hunks` the id of the matching hunk in `previous`, taken
+/// in document order, and the ones that are new ids from `next_id` up.
+pub(crate) fn keep_hunk_ids(
+    previous: &[ConflictHunk],
+    hunks: &mut [ConflictHunk],
+    next_id: &mut usize,
+) {
+    let mut unmatched = previous;
+    for hunk in hunks {
+        let found = unmatched.iter().position(|old| {
+            old.local_lines == hunk.local_lines && old.external_lines == hunk.external_lines
+        });
+        match found {
+            Some(index) => {
+                hunk.id = unmatched[index].id;
+                unmatched = &unmatched[index + 1..];
+            }
+            None => {
+                hunk.id = *next_id;
+                *next_id += 1;
+            }
+        }
+    }
+}
+
+fn split_lines(text: &str) -> Vec<String> {
+    text.split('\n').map(ToString::to_string).collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::compute_conflict_hunks;
+    use super::{
+        HunkKind, changed_span, compute_conflict_hunks, compute_conflict_hunks_with_context,
+        keep_hunk_ids, render_hunk_markers,
+    };
 
     #[test]
     fn returns_empty_for_identical_text() {
@@ -125,14 +296,93 @@ mod tests {
 
     #[test]
     fn returns_insert_delete_replace_hunks() {
-        let hunks = compute_conflict_hunks("a\nb\nc", "a\nB\nc\nd");
+        let hunks = compute_conflict_hunks("a\nb\nc\nd", "a\nB\nc\nd\ne");
         assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].kind, HunkKind::Change);
         assert_eq!(hunks[0].local_start, 1);
         assert_eq!(hunks[0].external_start, 1);
         assert_eq!(hunks[0].local_lines, vec!["b".to_string()]);
         assert_eq!(hunks[0].external_lines, vec!["B".to_string()]);
+        assert_eq!(hunks[1].kind, HunkKind::Insert);
         assert!(hunks[1].local_lines.is_empty());
-        assert_eq!(hunks[1].external_lines, vec!["d".to_string()]);
+        assert_eq!(hunks[1].external_lines, vec!["e".to_string()]);
+    }
+
+    #[test]
+    fn hunk_markers_wrap_only_the_changed_lines() {
+        let local = "# Title\nkeep 1\nlocal a\nkeep 2\nkeep 3\nlocal b\nkeep 4\nkeep 5\nlocal c\n";
+        let external =
+            "# Title\nkeep 1\nexternal a\nkeep 2\nkeep 3\nkeep 4\nkeep 5\nexternal c\nextra\n";
+        let hunks = compute_conflict_hunks(local, external);
+        assert_eq!(hunks.len(), 3);
+
+        let merged = render_hunk_markers(local, external, &hunks);
+        assert_eq!(merged.matches("<<<<<<< local").count(), 3);
+        assert_eq!(merged.matches("=======").count(), 3);
+        assert_eq!(merged.matches(">>>>>>> external").count(), 3);
+        assert_eq!(
+            merged,
+            "# Title\nkeep 1\n\
+             <<<<<<< local\nlocal a\n=======\nexternal a\n>>>>>>> external\n\
+             keep 2\nkeep 3\n\
+             <<<<<<< local\nlocal b\n=======\n>>>>>>> external\n\
+             keep 4\nkeep 5\n\
+             <<<<<<< local\nlocal c\n=======\nexternal c\nextra\n>>>>>>> external\n"
+        );
+    }
+
+    #[test]
+    fn hunk_markers_without_hunks_return_the_local_text() {
+        assert_eq!(render_hunk_markers("a\nb\n", "a\nb\n", &[]), "a\nb\n");
+    }
+
+    #[test]
+    fn context_stops_at_the_document_boundaries() {
+        let local = "one\ntwo\nthree\nfour\nfive\n";
+        let hunks = compute_conflict_hunks(local, "ONE\ntwo\nthree\nfour\nFIVE\n");
+        assert_eq!(hunks.len(), 2);
+        assert_eq!((hunks[0].id, hunks[1].id), (0, 1));
+        assert!(hunks[0].context_before.is_empty());
+        assert_eq!(hunks[0].context_after, ["two", "three"]);
+        assert_eq!(hunks[1].context_before, ["three", "four"]);
+        assert!(hunks[1].context_after.is_empty());
+
+        let wide = compute_conflict_hunks_with_context(local, "one\ntwo\nTHREE\nfour\nfive\n", 5);
+        assert_eq!(wide[0].context_before, ["one", "two"]);
+        assert_eq!(wide[0].context_after, ["four", "five"]);
+        let none = compute_conflict_hunks_with_context(local, "one\ntwo\nTHREE\nfour\nfive\n", 0);
+        assert!(none[0].context_before.is_empty() && none[0].context_after.is_empty());
+    }
+
+    #[test]
+    fn kept_ids_survive_an_earlier_hunk_going_away() {
+        let external = "a\nB\nc1\nc2\nD\ne1\ne2\nF";
+        let previous = compute_conflict_hunks("a\nb\nc1\nc2\nd\ne1\ne2\nf", external);
+        let mut next_id = previous.len();
+        // The first hunk was applied and a new one showed up before it.
+        let mut hunks = compute_conflict_hunks("x\nB\nc1\nc2\nd\ne1\ne2\nf", external);
+        keep_hunk_ids(&previous, &mut hunks, &mut next_id);
+        let ids: Vec<_> = hunks.iter().map(|hunk| hunk.id).collect();
+        assert_eq!(ids, [3, 1, 2]);
+        assert_eq!(next_id, 4);
+        assert_eq!(hunks[1].local_lines, ["d"]);
+    }
+
+    #[test]
+    fn hunks_one_unchanged_line_apart_are_merged() {
+        let hunks = compute_conflict_hunks("a\nb\nc\nd\ne", "a\nB\nc\nD\ne");
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].kind, HunkKind::Change);
+        assert_eq!(hunks[0].local_lines, ["b", "c", "d"]);
+        assert_eq!(hunks[0].external_lines, ["B", "c", "D"]);
+    }
+
+    #[test]
+    fn changed_span_trims_the_shared_start_and_end() {
+        assert_eq!(changed_span("the cat sat", "the dog sat"), (4..7, 4..7));
+        assert_eq!(changed_span("  - item", "    - item"), (2..2, 2..4));
+        assert_eq!(changed_span("café", "cafés"), (5..5, 5..6));
+        assert_eq!(changed_span("same", "same"), (4..4, 4..4));
     }
 
     #[test]
@@ -1,8 +1,21 @@
-use std::fs;
+use std::borrow::Cow;
+use std::cell::OnceCell;
+#[cfg(feature = "fs")]
 use std::io;
+use std::ops::Range;
+#[cfg(feature = "fs")]
 use std::path::Path;
 
-use crate::conflict_diff::{ConflictHunk, compute_conflict_hunks};
+use unicode_width::UnicodeWidthStr;
+
+use crate::conflict_diff::{
+    ConflictHunk, compute_conflict_hunks, keep_hunk_ids, render_hunk_markers,
+};
+use crate::load::LineEnding;
+use crate::markdown::{FenceLine, FenceTracker};
+#[cfg(feature = "fs")]
+use crate::save::SaveOutcome;
+use crate::save::{self, SavePolicy};
 
 const MAX_HISTORY_ENTRIES: usize = 128;
 const MAX_HISTORY_BYTES: usize = 8 * 1024 * 1024;
@@ -11,16 +24,59 @@ const MAX_HISTORY_BYTES: usize = 8 * 1024 * 1024;
 pub struct ConflictState {
     pub external: String,
     pub hunks: Vec<ConflictHunk>,
+    /// Id the next hunk that was not there before gets.
+    next_hunk_id: usize,
+}
+
+impl ConflictState {
+    /// Hunks between `local` and `external`, keeping the ids of the ones in
+    /// `previous` that are still there.
+    fn new(local: &str, external: String, previous: Option<ConflictState>) -> Self {
+        let mut hunks = compute_conflict_hunks(local, &external);
+        let next_hunk_id = match previous {
+            Some(previous) => {
+                let mut next_id = previous.next_hunk_id;
+                keep_hunk_ids(&previous.hunks, &mut hunks, &mut next_id);
+                next_id
+            }
+            None => hunks.len(),
+        };
+        Self {
+            external,
+            hunks,
+            next_hunk_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub ignore_case: bool,
+    pub whole_word: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct EditorBuffer {
     text: String,
     cursor: usize,
+    /// Secondary cursors, sorted and never equal to `cursor` or each other.
+    /// Typing and backspace apply at each of them; any other edit drops them.
+    extra_cursors: Vec<usize>,
+    /// Revision after the latest edit at several cursors, so the edits that
+    /// follow it there share its undo entry.
+    multi_edit: Option<u64>,
+    selection_anchor: Option<usize>,
     pub dirty: bool,
     conflict: Option<ConflictState>,
     undo_stack: Vec<HistoryState>,
     redo_stack: Vec<HistoryState>,
+    /// Byte offset of every line start, rebuilt lazily after an edit.
+    line_starts: OnceCell<Vec<usize>>,
+    revision: u64,
+    /// Revision where the latest run of [`append_external`](Self::append_external)
+    /// calls began; any other edit clears it.
+    append_start: Option<u64>,
+    line_ending: LineEnding,
 }
 
 #[derive(Debug, Clone)]
@@ -32,6 +88,12 @@ struct HistoryState {
 }
 
 impl HistoryState {
+    /// Whether going back to `other` would change nothing visible: only the
+    /// cursor or the saved flag differ.
+    fn same_content(&self, other: &HistoryState) -> bool {
+        self.text == other.text && self.conflict == other.conflict
+    }
+
     fn approx_bytes(&self) -> usize {
         let mut total =
             self.text.len() + std::mem::size_of::<usize>() + std::mem::size_of::<bool>();
@@ -53,10 +115,17 @@ impl EditorBuffer {
         Self {
             text,
             cursor,
+            extra_cursors: Vec::new(),
+            multi_edit: None,
+            selection_anchor: None,
             dirty: false,
             conflict: None,
             undo_stack: Vec::new(),
             redo_stack: Vec::new(),
+            line_starts: OnceCell::new(),
+            revision: 0,
+            append_start: None,
+            line_ending: LineEnding::Lf,
         }
     }
 
@@ -64,10 +133,152 @@ impl EditorBuffer {
         &self.text
     }
 
+    /// The ending `save_to_path` writes lines with; the text itself always
+    /// uses `\n`.
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+    }
+
+    /// Bumped on every change to the text, so callers can cache derived data.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Whether the text at `revision` is a prefix of the current text, with
+    /// only [`append_external`](Self::append_external) calls since.
+    pub fn only_appended_since(&self, revision: u64) -> bool {
+        revision == self.revision || self.append_start.is_some_and(|start| start <= revision)
+    }
+
+    /// Lines as split on `\n`; an empty buffer or trailing newline still
+    /// counts the empty last line.
+    pub fn line_count(&self) -> usize {
+        self.line_starts().len()
+    }
+
+    /// Byte offset where `line` starts, or the text length past the end.
+    pub fn line_start(&self, line: usize) -> usize {
+        self.line_starts()
+            .get(line)
+            .copied()
+            .unwrap_or(self.text.len())
+    }
+
+    /// Text of `line` without its newline; empty past the end.
+    pub fn line_text(&self, line: usize) -> &str {
+        let start = self.line_start(line);
+        let end = match self.line_starts().get(line + 1) {
+            Some(next) => next - 1,
+            None => self.text.len(),
+        };
+        &self.text[start..end]
+    }
+
     pub fn cursor(&self) -> usize {
         self.cursor
     }
 
+    /// The secondary cursors, in text order.
+    pub fn extra_cursors(&self) -> &[usize] {
+        &self.extra_cursors
+    }
+
+    pub fn has_extra_cursors(&self) -> bool {
+        !self.extra_cursors.is_empty()
+    }
+
+    /// Add a cursor on the line above the topmost cursor, at the primary
+    /// cursor's column or the end of a shorter line. Returns `false` on the
+    /// first line.
+    pub fn add_cursor_above(&mut self) -> bool {
+        let top = self
+            .extra_cursors
+            .first()
+            .map_or(self.cursor, |&first| first.min(self.cursor));
+        let (line, _) = self.line_col_at(top);
+        if line == 0 {
+            return false;
+        }
+        self.add_cursor_on_line(line - 1);
+        true
+    }
+
+    /// Add a cursor on the line below the bottommost cursor, like
+    /// [`add_cursor_above`](Self::add_cursor_above). Returns `false` on the
+    /// last line.
+    pub fn add_cursor_below(&mut self) -> bool {
+        let bottom = self
+            .extra_cursors
+            .last()
+            .map_or(self.cursor, |&last| last.max(self.cursor));
+        let (line, _) = self.line_col_at(bottom);
+        if line + 1 >= self.line_count() {
+            return false;
+        }
+        self.add_cursor_on_line(line + 1);
+        true
+    }
+
+    /// Drop the secondary cursors, keeping the primary one.
+    pub fn collapse_cursors(&mut self) {
+        self.extra_cursors.clear();
+    }
+
+    fn add_cursor_on_line(&mut self, line: usize) {
+        let (_, col) = self.line_col_at(self.cursor);
+        let added = self.index_at_line_col(line, col);
+        self.selection_anchor = None;
+        self.multi_edit = None;
+        if added != self.cursor && !self.extra_cursors.contains(&added) {
+            self.extra_cursors.push(added);
+            self.extra_cursors.sort_unstable();
+        }
+    }
+
+    /// Every cursor, primary or not, in text order, and the primary's index
+    /// among them.
+    fn all_cursors(&self) -> (Vec<usize>, usize) {
+        let primary = self
+            .extra_cursors
+            .partition_point(|&extra| extra < self.cursor);
+        let mut cursors = self.extra_cursors.clone();
+        cursors.insert(primary, self.cursor);
+        (cursors, primary)
+    }
+
+    /// Replace the text from `back(cursor)` up to each cursor with `insert`.
+    /// Edits go last to first so the earlier offsets still hold. A run of
+    /// these with the same cursors is one undo entry.
+    fn edit_at_cursors(&mut self, insert: &str, back: impl Fn(&Self, usize) -> usize) {
+        let (cursors, primary) = self.all_cursors();
+        let starts: Vec<usize> = cursors.iter().map(|&cursor| back(self, cursor)).collect();
+        if self.multi_edit != Some(self.revision) {
+            self.push_undo_snapshot();
+        }
+        self.redo_stack.clear();
+        for (&start, &cursor) in starts.iter().zip(&cursors).rev() {
+            self.text_mut().replace_range(start..cursor, insert);
+        }
+        let mut shift = 0isize;
+        let mut moved = Vec::with_capacity(cursors.len());
+        for (&start, &cursor) in starts.iter().zip(&cursors) {
+            shift += insert.len() as isize - (cursor - start) as isize;
+            moved.push((cursor as isize + shift) as usize);
+        }
+        self.cursor = moved[primary];
+        moved.remove(primary);
+        moved.dedup();
+        moved.retain(|&extra| extra != self.cursor);
+        self.extra_cursors = moved;
+        self.multi_edit = Some(self.revision);
+        self.selection_anchor = None;
+        self.dirty = true;
+    }
+
     pub fn conflict(&self) -> Option<&ConflictState> {
         self.conflict.as_ref()
     }
@@ -76,10 +287,67 @@ impl EditorBuffer {
         self.conflict.is_some()
     }
 
+    pub fn selection_anchor(&self) -> Option<usize> {
+        self.selection_anchor
+    }
+
+    pub fn set_selection_anchor(&mut self, anchor: usize) {
+        self.selection_anchor = Some(self.clamp_to_char_boundary(anchor));
+    }
+
+    /// Anchor the selection at the cursor unless one is already active.
+    pub fn start_selection(&mut self) {
+        if self.selection_anchor.is_none() {
+            self.selection_anchor = Some(self.cursor);
+        }
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection_anchor = None;
+    }
+
+    pub fn selection_range(&self) -> Option<(usize, usize)> {
+        let anchor = self.clamp_to_char_boundary(self.selection_anchor?);
+        match anchor.cmp(&self.cursor) {
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Less => Some((anchor, self.cursor)),
+            std::cmp::Ordering::Greater => Some((self.cursor, anchor)),
+        }
+    }
+
+    pub fn copy_selection(&self) -> Option<String> {
+        let (start, end) = self.selection_range()?;
+        Some(self.text[start..end].to_string())
+    }
+
+    pub fn cut_selection(&mut self) -> Option<String> {
+        let copied = self.copy_selection()?;
+        let (start, end) = self.selection_range()?;
+        self.replace_range(start, end, "");
+        self.selection_anchor = None;
+        Some(copied)
+    }
+
+    /// Insert `text` at the cursor, replacing the selection if there is one.
+    pub fn paste(&mut self, text: &str) {
+        match self.selection_range() {
+            Some((start, end)) => {
+                self.replace_range(start, end, text);
+            }
+            None => self.insert_str(text),
+        }
+        self.selection_anchor = None;
+    }
+
     pub fn insert_char(&mut self, c: char) {
+        if self.has_extra_cursors() {
+            self.edit_at_cursors(c.encode_utf8(&mut [0; 4]), |_, cursor| cursor);
+            return;
+        }
         self.push_undo_snapshot();
         self.redo_stack.clear();
-        self.text.insert(self.cursor, c);
+        let cursor = self.cursor;
+        self.text_mut().insert(cursor, c);
         self.cursor += c.len_utf8();
         self.dirty = true;
     }
@@ -90,7 +358,8 @@ impl EditorBuffer {
         }
         self.push_undo_snapshot();
         self.redo_stack.clear();
-        self.text.insert_str(self.cursor, s);
+        let cursor = self.cursor;
+        self.text_mut().insert_str(cursor, s);
         self.cursor += s.len();
         self.dirty = true;
     }
@@ -99,14 +368,47 @@ impl EditorBuffer {
         self.insert_char('\n');
     }
 
+    /// Newline that carries the current line's indentation, blockquote and
+    /// list markers forward. On an item with no text the innermost marker is
+    /// dropped instead, which ends the list.
+    pub fn insert_newline_smart(&mut self) {
+        let line_start = self.text[..self.cursor]
+            .rfind('\n')
+            .map_or(0, |idx| idx + 1);
+        let line_end = self.text[self.cursor..]
+            .find('\n')
+            .map_or(self.text.len(), |offset| self.cursor + offset);
+        let line = &self.text[line_start..line_end];
+        let Some(prefix) = line_prefix(line) else {
+            self.insert_newline();
+            return;
+        };
+        if self.cursor < line_start + prefix.len {
+            self.insert_newline();
+            return;
+        }
+
+        if prefix.has_marker() && line[prefix.len..].trim().is_empty() {
+            let kept = line[..prefix.outer_len].to_string();
+            self.replace_range(line_start, line_end, &kept);
+        } else {
+            self.insert_str(&format!("\n{}", prefix.continuation));
+        }
+    }
+
     pub fn backspace(&mut self) {
+        if self.has_extra_cursors() {
+            self.edit_at_cursors("", Self::prev_char_boundary);
+            return;
+        }
         if self.cursor == 0 {
             return;
         }
         self.push_undo_snapshot();
         self.redo_stack.clear();
         let prev = self.prev_char_boundary(self.cursor);
-        self.text.replace_range(prev..self.cursor, "");
+        let cursor = self.cursor;
+        self.text_mut().replace_range(prev..cursor, "");
         self.cursor = prev;
         self.dirty = true;
     }
@@ -136,7 +438,8 @@ impl EditorBuffer {
             start = prev;
         }
 
-        self.text.replace_range(start..self.cursor, "");
+        let cursor = self.cursor;
+        self.text_mut().replace_range(start..cursor, "");
         self.cursor = start;
         self.dirty = true;
     }
@@ -148,7 +451,8 @@ impl EditorBuffer {
         self.push_undo_snapshot();
         self.redo_stack.clear();
         let next = self.next_char_boundary(self.cursor);
-        self.text.replace_range(self.cursor..next, "");
+        let cursor = self.cursor;
+        self.text_mut().replace_range(cursor..next, "");
         self.dirty = true;
     }
 
@@ -177,122 +481,155 @@ impl EditorBuffer {
             end = next;
         }
 
-        self.text.replace_range(self.cursor..end, "");
+        let cursor = self.cursor;
+        self.text_mut().replace_range(cursor..end, "");
         self.dirty = true;
     }
 
+    /// Go back to the latest state with different text or conflict; entries
+    /// that only moved the cursor are dropped on the way.
     pub fn undo(&mut self) -> bool {
-        let Some(prev) = self.undo_stack.pop() else {
+        let snapshot = self.snapshot();
+        let Some(prev) = Self::pop_changed(&mut self.undo_stack, &snapshot) else {
             return false;
         };
-        let snapshot = self.snapshot();
         Self::push_history(&mut self.redo_stack, snapshot);
         self.restore(prev);
         true
     }
 
     pub fn redo(&mut self) -> bool {
-        let Some(next) = self.redo_stack.pop() else {
+        let snapshot = self.snapshot();
+        let Some(next) = Self::pop_changed(&mut self.redo_stack, &snapshot) else {
             return false;
         };
-        let snapshot = self.snapshot();
         Self::push_history(&mut self.undo_stack, snapshot);
         self.restore(next);
         true
     }
 
-    pub fn find_next(&mut self, needle: &str) -> bool {
-        if needle.is_empty() {
-            return false;
-        }
+    /// Steps [`undo`](Self::undo) can take; entries it would skip are not
+    /// counted.
+    pub fn undo_depth(&self) -> usize {
+        self.changed_steps(&self.undo_stack)
+    }
 
-        let start = if self.cursor >= self.text.len() {
-            0
-        } else {
-            self.next_char_boundary(self.cursor)
-        };
+    /// Steps [`redo`](Self::redo) can take; entries it would skip are not
+    /// counted.
+    pub fn redo_depth(&self) -> usize {
+        self.changed_steps(&self.redo_stack)
+    }
 
-        if let Some(offset) = self.text[start..].find(needle) {
-            self.cursor = start + offset;
-            return true;
-        }
+    /// Entries of `stack`, popped from the top, that change the text or the
+    /// conflict from the one before; the rest only move the cursor.
+    fn changed_steps(&self, stack: &[HistoryState]) -> usize {
+        let mut shown = (self.text.as_str(), &self.conflict);
+        stack
+            .iter()
+            .rev()
+            .filter(|state| {
+                let next = (state.text.as_str(), &state.conflict);
+                let changed = next != shown;
+                shown = next;
+                changed
+            })
+            .count()
+    }
 
-        if let Some(offset) = self.text[..start].find(needle) {
-            self.cursor = offset;
-            return true;
-        }
+    pub fn find_next(&mut self, needle: &str) -> bool {
+        self.find_next_with(needle, SearchOptions::default())
+    }
 
-        false
+    pub fn find_next_with(&mut self, needle: &str, options: SearchOptions) -> bool {
+        self.collapse_cursors();
+        match self.next_match(needle, options) {
+            Some((start, _)) => {
+                self.cursor = start;
+                true
+            }
+            None => false,
+        }
     }
 
     pub fn find_prev(&mut self, needle: &str) -> bool {
-        if needle.is_empty() {
-            return false;
-        }
+        self.find_prev_with(needle, SearchOptions::default())
+    }
 
+    pub fn find_prev_with(&mut self, needle: &str, options: SearchOptions) -> bool {
+        self.collapse_cursors();
         let start = if self.cursor == 0 {
             self.text.len()
         } else {
             self.prev_char_boundary(self.cursor)
         };
-
-        if let Some(offset) = self.text[..start].rfind(needle) {
-            self.cursor = offset;
-            return true;
-        }
-
-        if let Some(offset) = self.text[start..].rfind(needle) {
-            self.cursor = start + offset;
-            return true;
+        let matches = self.match_ranges(needle, options);
+        let found = matches
+            .iter()
+            .rev()
+            .find(|(_, end)| *end <= start)
+            .or_else(|| matches.last());
+
+        match found {
+            Some((match_start, _)) => {
+                self.cursor = *match_start;
+                true
+            }
+            None => false,
         }
+    }
 
-        false
+    /// How many matches [`replace_all_with`](Self::replace_all_with) would
+    /// replace.
+    pub fn count_matches(&self, needle: &str, options: SearchOptions) -> usize {
+        self.match_ranges(needle, options).len()
     }
 
     pub fn replace_next(&mut self, needle: &str, replacement: &str) -> bool {
-        if needle.is_empty() {
-            return false;
-        }
-
-        let start = if self.cursor >= self.text.len() {
-            0
-        } else {
-            self.next_char_boundary(self.cursor)
-        };
-
-        let found = self.text[start..]
-            .find(needle)
-            .map(|offset| start + offset)
-            .or_else(|| self.text[..start].find(needle));
+        self.replace_next_with(needle, replacement, SearchOptions::default())
+    }
 
-        let Some(match_start) = found else {
+    pub fn replace_next_with(
+        &mut self,
+        needle: &str,
+        replacement: &str,
+        options: SearchOptions,
+    ) -> bool {
+        let Some((match_start, match_end)) = self.next_match(needle, options) else {
             return false;
         };
 
         self.push_undo_snapshot();
         self.redo_stack.clear();
-        let match_end = match_start + needle.len();
-        self.text.replace_range(match_start..match_end, replacement);
+        self.text_mut()
+            .replace_range(match_start..match_end, replacement);
         self.cursor = match_start + replacement.len();
         self.dirty = true;
         true
     }
 
     pub fn replace_all(&mut self, needle: &str, replacement: &str) -> usize {
-        if needle.is_empty() {
-            return 0;
-        }
-        if !self.text.contains(needle) {
+        self.replace_all_with(needle, replacement, SearchOptions::default())
+    }
+
+    pub fn replace_all_with(
+        &mut self,
+        needle: &str,
+        replacement: &str,
+        options: SearchOptions,
+    ) -> usize {
+        let matches = self.match_ranges(needle, options);
+        if matches.is_empty() {
             return 0;
         }
 
-        let count = self.text.matches(needle).count();
         self.push_undo_snapshot();
         self.redo_stack.clear();
-        self.text = self.text.replace(needle, replacement);
+        for (start, end) in matches.iter().rev() {
+            self.text_mut().replace_range(*start..*end, replacement);
+        }
         self.cursor = self.text.len();
         self.dirty = true;
-        count
+        matches.len()
     }
 
     pub fn move_left(&mut self) {
@@ -327,51 +664,21 @@ impl EditorBuffer {
     }
 
     pub fn move_word_left(&mut self) {
-        if self.cursor == 0 {
-            return;
-        }
-        let mut start = self.cursor;
-        while start > 0 {
-            let prev = self.prev_char_boundary(start);
-            let ch = self.text[prev..start].chars().next().expect("char");
-            if !ch.is_whitespace() {
-                break;
-            }
-            start = prev;
-        }
-        while start > 0 {
-            let prev = self.prev_char_boundary(start);
-            let ch = self.text[prev..start].chars().next().expect("char");
-            if ch.is_whitespace() {
-                break;
-            }
-            start = prev;
+        let mut chars = self.text[..self.cursor].char_indices().rev().peekable();
+        while chars.next_if(|(_, ch)| !is_word_char(*ch)).is_some() {}
+        let mut start = chars.peek().map_or(0, |(idx, ch)| idx + ch.len_utf8());
+        while let Some((idx, _)) = chars.next_if(|(_, ch)| is_word_char(*ch)) {
+            start = idx;
         }
         self.cursor = start;
     }
 
     pub fn move_word_right(&mut self) {
-        if self.cursor >= self.text.len() {
-            return;
-        }
-        let mut end = self.cursor;
-        while end < self.text.len() {
-            let next = self.next_char_boundary(end);
-            let ch = self.text[end..next].chars().next().expect("char");
-            if ch.is_whitespace() {
-                break;
-            }
-            end = next;
-        }
-        while end < self.text.len() {
-            let next = self.next_char_boundary(end);
-            let ch = self.text[end..next].chars().next().expect("char");
-            if !ch.is_whitespace() || ch == '\n' {
-                break;
-            }
-            end = next;
-        }
-        self.cursor = end;
+        let base = self.cursor;
+        let mut chars = self.text[base..].char_indices().peekable();
+        while chars.next_if(|(_, ch)| !is_word_char(*ch)).is_some() {}
+        while chars.next_if(|(_, ch)| is_word_char(*ch)).is_some() {}
+        self.cursor = chars.peek().map_or(self.text.len(), |(idx, _)| base + idx);
     }
 
     pub fn move_line_start(&mut self) {
@@ -379,6 +686,21 @@ impl EditorBuffer {
         self.cursor = self.index_at_line_col(line, 0);
     }
 
+    /// Toggle between the first non-whitespace char of the line and column 0.
+    pub fn move_line_start_smart(&mut self) {
+        let (line, _) = self.line_col_at(self.cursor);
+        let line_start = self.index_at_line_col(line, 0);
+        let indent = self.text[line_start..]
+            .find(|ch: char| ch == '\n' || !ch.is_whitespace())
+            .unwrap_or(self.text.len() - line_start);
+        let first_non_ws = line_start + indent;
+        self.cursor = if self.cursor == first_non_ws {
+            line_start
+        } else {
+            first_non_ws
+        };
+    }
+
     pub fn move_line_end(&mut self) {
         let (line, _) = self.line_col_at(self.cursor);
         let line_start = self.index_at_line_col(line, 0);
@@ -442,19 +764,91 @@ impl EditorBuffer {
         }
     }
 
+    /// First heading line after `from_line`, without wrapping around.
+    pub fn find_next_heading(&self, from_line: usize) -> Option<usize> {
+        self.heading_lines()
+            .into_iter()
+            .find(|&line| line > from_line)
+    }
+
+    /// Last heading line before `from_line`, without wrapping around.
+    pub fn find_prev_heading(&self, from_line: usize) -> Option<usize> {
+        self.heading_lines()
+            .into_iter()
+            .rev()
+            .find(|&line| line < from_line)
+    }
+
+    /// Lines that start a heading: `#` to `######` followed by a space or
+    /// the end of the line, or the first line of a paragraph underlined with
+    /// `=` or `-`. Fenced code is skipped.
+    fn heading_lines(&self) -> Vec<usize> {
+        let mut headings = Vec::new();
+        let mut fences = FenceTracker::default();
+        let mut paragraph: Option<usize> = None;
+        for (idx, line) in self.text.split('\n').enumerate() {
+            match fences.line(line) {
+                FenceLine::Outside => {}
+                FenceLine::Open => {
+                    paragraph = None;
+                    continue;
+                }
+                FenceLine::Inside | FenceLine::Close => continue,
+            }
+
+            let trimmed = line.trim_start_matches(' ');
+            let indented = line.len() - trimmed.len() > 3;
+            if trimmed.trim().is_empty() {
+                paragraph = None;
+            } else if indented {
+                // Indented code, or a continuation of the paragraph above.
+            } else if is_atx_heading(trimmed) {
+                headings.push(idx);
+                paragraph = None;
+            } else if let Some(start) = paragraph.filter(|_| is_setext_underline(trimmed)) {
+                headings.push(start);
+                paragraph = None;
+            } else if paragraph.is_none() {
+                // An underline below a list item or quote is a thematic
+                // break, not a heading.
+                let block = line_prefix(line).is_some_and(|p| p.list_marker || p.quote_marker);
+                paragraph = (!block).then_some(idx);
+            }
+        }
+        headings
+    }
+
+    pub fn current_line(&self) -> &str {
+        let (line, _) = self.line_col_at(self.cursor);
+        self.line_text(line)
+    }
+
     pub fn line_col_at_cursor(&self) -> (usize, usize) {
         self.line_col_at(self.cursor)
     }
 
+    /// Terminal cells between the start of the cursor's line and the cursor.
+    pub fn display_col_at_cursor(&self) -> usize {
+        let line_start = self.text[..self.cursor]
+            .rfind('\n')
+            .map_or(0, |idx| idx + 1);
+        self.text[line_start..self.cursor].width()
+    }
+
     pub fn goto_line(&mut self, line_number: usize) -> bool {
+        self.goto_line_col(line_number, 1)
+    }
+
+    /// Move to the one-based `line_number` and `column` (in characters),
+    /// stopping at the end of a shorter line. Lines past the end are refused.
+    pub fn goto_line_col(&mut self, line_number: usize, column: usize) -> bool {
         if line_number == 0 {
             return false;
         }
-        let total_lines = self.text.split('\n').count().max(1);
-        if line_number > total_lines {
+        if line_number > self.line_count() {
             return false;
         }
-        self.cursor = self.index_at_line_col(line_number - 1, 0);
+        self.cursor = self.index_at_line_col(line_number - 1, column.saturating_sub(1));
         true
     }
 
@@ -474,7 +868,8 @@ impl EditorBuffer {
         }
         self.push_undo_snapshot();
         self.redo_stack.clear();
-        self.text.replace_range(start..self.cursor, "");
+        let cursor = self.cursor;
+        self.text_mut().replace_range(start..cursor, "");
         self.cursor = start;
         self.dirty = true;
     }
@@ -490,143 +885,511 @@ impl EditorBuffer {
         }
         self.push_undo_snapshot();
         self.redo_stack.clear();
-        self.text.replace_range(self.cursor..line_end, "");
+        let cursor = self.cursor;
+        self.text_mut().replace_range(cursor..line_end, "");
         self.dirty = true;
     }
 
-    pub fn replace_range(&mut self, start: usize, end: usize, replacement: &str) -> bool {
-        let mut a = self.clamp_to_char_boundary(start);
-        let mut b = self.clamp_to_char_boundary(end);
-        if a > b {
-            std::mem::swap(&mut a, &mut b);
-        }
-        if a == b && replacement.is_empty() {
-            return false;
-        }
+    pub fn delete_line(&mut self) {
+        let _ = self.remove_line();
+    }
+
+    pub fn cut_line(&mut self) -> String {
+        self.remove_line().unwrap_or_default()
+    }
 
+    pub fn paste_line(&mut self, line: &str) {
+        let (row, col) = self.line_col_at(self.cursor);
+        let start = self.index_at_line_col(row, 0);
         self.push_undo_snapshot();
         self.redo_stack.clear();
-        self.text.replace_range(a..b, replacement);
-        self.cursor = a + replacement.len();
+        if self.text.is_empty() {
+            self.text_mut().push_str(line);
+            self.cursor = self.text.len();
+        } else {
+            self.text_mut().insert_str(start, &format!("{line}\n"));
+            self.cursor = self.index_at_line_col(row + 1, col);
+        }
         self.dirty = true;
+    }
+
+    /// Swap the cursor's line, or every line the selection touches, with the
+    /// line above. The cursor and selection move with the text. Returns
+    /// `false`, changing nothing, at the top.
+    pub fn move_line_up(&mut self) -> bool {
+        let (first, last) = self.moved_lines();
+        if first == 0 || last >= self.movable_line_count() {
+            return false;
+        }
+        let above = self.line_start(first - 1);
+        let block_start = self.line_start(first);
+        let block_end = block_start + self.block_len(first, last);
+        let moved = format!(
+            "{}\n{}",
+            &self.text[block_start..block_end],
+            &self.text[above..block_start - 1]
+        );
+        self.swap_lines(above..block_end, &moved, |pos| pos - (block_start - above));
         true
     }
 
-    pub fn on_external_change(&mut self, external: String) {
-        if self.dirty {
-            self.conflict = Some(ConflictState {
-                hunks: compute_conflict_hunks(&self.text, &external),
-                external,
-            });
-            return;
+    /// Like [`move_line_up`](Self::move_line_up), with the line below.
+    /// Trailing newline aside, the last line stays put.
+    pub fn move_line_down(&mut self) -> bool {
+        let (first, last) = self.moved_lines();
+        if last + 1 >= self.movable_line_count() {
+            return false;
         }
-        self.set_from_disk(external);
+        let block_start = self.line_start(first);
+        let below = self.line_start(last + 1);
+        let below_end = below + self.line_text(last + 1).len();
+        let moved = format!(
+            "{}\n{}",
+            &self.text[below..below_end],
+            &self.text[block_start..below - 1]
+        );
+        self.swap_lines(block_start..below_end, &moved, |pos| {
+            pos + (below_end - below + 1)
+        });
+        true
     }
 
-    pub fn keep_local(&mut self) {
-        self.conflict = None;
+    /// Copy the cursor's line, or every line the selection touches, below
+    /// itself. The cursor and selection land on the copy. Returns how many
+    /// lines were copied.
+    pub fn duplicate_line(&mut self) -> usize {
+        let (first, last) = self.moved_lines();
+        let end = self.line_start(first) + self.block_len(first, last);
+        let copy = format!("\n{}", &self.text[self.line_start(first)..end]);
+        self.push_undo_snapshot();
+        self.redo_stack.clear();
+        self.text_mut().insert_str(end, &copy);
+        self.cursor += copy.len();
+        self.selection_anchor = self.selection_anchor.map(|pos| pos + copy.len());
+        self.dirty = true;
+        last - first + 1
     }
 
-    pub fn reload_external(&mut self) {
-        if let Some(conflict) = self.conflict.take() {
-            self.set_from_disk(conflict.external);
+    /// First and last line a line move or copy takes: the selected lines,
+    /// or the cursor's.
+    fn moved_lines(&self) -> (usize, usize) {
+        match self.selected_line_starts() {
+            Some(starts) => {
+                let (first, _) = self.line_col_at(starts[0]);
+                (first, first + starts.len() - 1)
+            }
+            None => {
+                let (line, _) = self.line_col_at(self.cursor);
+                (line, line)
+            }
         }
     }
 
-    pub fn merge_external(&mut self) {
-        let Some(conflict) = self.conflict.take() else {
-            return;
-        };
+    /// Lines that can move: the empty one after a trailing newline is left
+    /// where it is so the file keeps ending in one.
+    fn movable_line_count(&self) -> usize {
+        self.line_count() - usize::from(self.text.ends_with('\n'))
+    }
 
-        self.text = format!(
-            "<<<<<<< local\n{}\n=======\n{}\n>>>>>>> external\n",
-            self.text, conflict.external
-        );
-        self.cursor = self.text.len();
+    /// Bytes from the start of `first` to the end of `last`, without the
+    /// newline after it.
+    fn block_len(&self, first: usize, last: usize) -> usize {
+        self.line_start(last) + self.line_text(last).len() - self.line_start(first)
+    }
+
+    fn swap_lines(&mut self, range: Range<usize>, moved: &str, shift: impl Fn(usize) -> usize) {
+        self.push_undo_snapshot();
+        self.redo_stack.clear();
+        self.text_mut().replace_range(range, moved);
+        // A selection ending at the start of the line after the block ends
+        // there again; past a last line with no newline it stops at the end.
+        let len = self.text.len();
+        self.cursor = shift(self.cursor).min(len);
+        self.selection_anchor = self.selection_anchor.map(|pos| shift(pos).min(len));
         self.dirty = true;
     }
 
-    pub fn apply_external_hunk(&mut self, hunk_index: usize) -> bool {
-        let Some(conflict) = self.conflict.clone() else {
-            return false;
-        };
-        let Some(hunk) = conflict.hunks.get(hunk_index).cloned() else {
-            return false;
-        };
+    /// Flip the checkbox of the task item on the cursor line between `[ ]`
+    /// and `[x]`. Returns whether it is now checked, or `None` (changing
+    /// nothing) when the line is not a task item.
+    pub fn toggle_task(&mut self) -> Option<bool> {
+        let (row, _) = self.line_col_at(self.cursor);
+        let task_box = line_prefix(self.line_text(row))?.task_box?;
+        let index = self.line_start(row) + task_box;
+        let checked = self.text.as_bytes()[index] == b' ';
 
-        let mut lines: Vec<String> = self.text.split('\n').map(ToString::to_string).collect();
-        let start = hunk.local_start.min(lines.len());
-        let end = start
-            .saturating_add(hunk.local_lines.len())
-            .min(lines.len());
-        lines.splice(start..end, hunk.external_lines);
-        self.text = lines.join("\n");
-        self.cursor = self.index_at_line_col(start, 0);
+        self.push_undo_snapshot();
+        self.redo_stack.clear();
+        let mark = if checked { "x" } else { " " };
+        self.text_mut().replace_range(index..index + 1, mark);
         self.dirty = true;
+        Some(checked)
+    }
 
-        let external = conflict.external;
-        let hunks = compute_conflict_hunks(&self.text, &external);
-        if hunks.is_empty() {
-            self.conflict = None;
-        } else {
-            self.conflict = Some(ConflictState { external, hunks });
+    /// Prefix every non-empty line the selection touches with `indent`.
+    /// Returns how many lines changed; the selection follows the text.
+    pub fn indent_selection(&mut self, indent: &str) -> usize {
+        let Some(starts) = self.selected_line_starts() else {
+            return 0;
+        };
+        let starts = starts
+            .into_iter()
+            .filter(|&start| !matches!(self.text[start..].chars().next(), None | Some('\n')))
+            .collect::<Vec<_>>();
+        if indent.is_empty() || starts.is_empty() {
+            return 0;
         }
 
+        self.push_undo_snapshot();
+        self.redo_stack.clear();
+        let shift =
+            |pos: usize| pos + indent.len() * starts.iter().filter(|&&start| start <= pos).count();
+        let anchor = self.selection_anchor.map(shift);
+        let cursor = shift(self.cursor);
+        for &start in starts.iter().rev() {
+            self.text_mut().insert_str(start, indent);
+        }
+        self.selection_anchor = anchor;
+        self.cursor = cursor;
+        self.dirty = true;
+        starts.len()
+    }
+
+    /// Strip one level of indentation (a tab, or up to `width` spaces) from
+    /// every line the selection touches. Returns how many lines changed.
+    pub fn dedent_selection(&mut self, width: usize) -> usize {
+        let Some(starts) = self.selected_line_starts() else {
+            return 0;
+        };
+        let removals = starts
+            .into_iter()
+            .map(|start| (start, self.leading_indent_len(start, width)))
+            .filter(|&(_, len)| len > 0)
+            .collect::<Vec<_>>();
+        if removals.is_empty() {
+            return 0;
+        }
+
+        self.push_undo_snapshot();
+        self.redo_stack.clear();
+        let shift = |pos: usize| {
+            removals.iter().fold(pos, |out, &(start, len)| {
+                if pos >= start + len {
+                    out - len
+                } else if pos > start {
+                    out - (pos - start)
+                } else {
+                    out
+                }
+            })
+        };
+        let anchor = self.selection_anchor.map(shift);
+        let cursor = shift(self.cursor);
+        for &(start, len) in removals.iter().rev() {
+            self.text_mut().replace_range(start..start + len, "");
+        }
+        self.selection_anchor = anchor;
+        self.cursor = cursor;
+        self.dirty = true;
+        removals.len()
+    }
+
+    pub fn replace_range(&mut self, start: usize, end: usize, replacement: &str) -> bool {
+        let mut a = self.clamp_to_char_boundary(start);
+        let mut b = self.clamp_to_char_boundary(end);
+        if a > b {
+            std::mem::swap(&mut a, &mut b);
+        }
+        if a == b && replacement.is_empty() {
+            return false;
+        }
+
+        self.push_undo_snapshot();
+        self.redo_stack.clear();
+        self.text_mut().replace_range(a..b, replacement);
+        self.cursor = a + replacement.len();
+        self.dirty = true;
+        true
+    }
+
+    /// Take in new text from disk. Local edits turn it into a conflict; a
+    /// clean buffer is replaced as one undoable step, with the cursor kept on
+    /// its line and column as far as the new text allows.
+    pub fn on_external_change(&mut self, external: String) {
+        self.collapse_cursors();
+        if self.dirty {
+            let previous = self.conflict.take();
+            self.conflict = Some(ConflictState::new(&self.text, external, previous));
+            return;
+        }
+        let (line, col) = self.line_col_at(self.cursor);
+        if external != self.text {
+            let mut before = self.snapshot();
+            // Undoing brings back text the file no longer has.
+            before.dirty = true;
+            Self::push_history(&mut self.undo_stack, before);
+            self.redo_stack.clear();
+        }
+        self.set_from_disk(external);
+        let last_line = self.line_count().saturating_sub(1);
+        self.cursor = self.index_at_line_col(line.min(last_line), col);
+    }
+
+    /// Add `chunk` from outside, such as the next piece of a stream, to the
+    /// end of the text. The cursor, selection and undo history stay as they
+    /// are. Returns `false` without changing anything while there are local
+    /// edits or a conflict; the caller then needs a full
+    /// [`on_external_change`](Self::on_external_change) instead.
+    pub fn append_external(&mut self, chunk: &str) -> bool {
+        if self.dirty || self.conflict.is_some() {
+            return false;
+        }
+        if chunk.is_empty() {
+            return true;
+        }
+        let len = self.text.len();
+        if let Some(starts) = self.line_starts.get_mut() {
+            starts.extend(chunk.match_indices('\n').map(|(idx, _)| len + idx + 1));
+        }
+        self.append_start.get_or_insert(self.revision);
+        self.revision += 1;
+        self.text.push_str(chunk);
+        true
+    }
+
+    pub fn keep_local(&mut self) {
+        self.collapse_cursors();
+        self.conflict = None;
+    }
+
+    /// Take the external text and drop the local edits, as one undo step
+    /// that brings back the local text and the conflict.
+    pub fn reload_external(&mut self) {
+        let Some(external) = self
+            .conflict
+            .as_ref()
+            .map(|conflict| conflict.external.clone())
+        else {
+            return;
+        };
+        self.push_undo_snapshot();
+        self.redo_stack.clear();
+        self.set_from_disk(external);
+    }
+
+    /// The buffer with conflict markers around each hunk that differs from
+    /// the external text, or `None` without a conflict.
+    pub fn conflict_markers(&self) -> Option<String> {
+        let conflict = self.conflict.as_ref()?;
+        Some(render_hunk_markers(
+            &self.text,
+            &conflict.external,
+            &conflict.hunks,
+        ))
+    }
+
+    /// Replace the buffer with [`conflict_markers`](Self::conflict_markers)
+    /// and leave the cursor on the first marker.
+    pub fn merge_external(&mut self) {
+        let Some(merged) = self.conflict_markers() else {
+            return;
+        };
+        let first = self
+            .conflict
+            .take()
+            .and_then(|conflict| conflict.hunks.first().map(|hunk| hunk.local_start))
+            .unwrap_or(0);
+
+        *self.text_mut() = merged;
+        self.cursor = self.index_at_line_col(first, 0);
+        self.dirty = true;
+    }
+
+    pub fn apply_external_hunk(&mut self, hunk_index: usize) -> bool {
+        let Some(conflict) = self.conflict.clone() else {
+            return false;
+        };
+        let Some(hunk) = conflict.hunks.get(hunk_index) else {
+            return false;
+        };
+        // A move takes both of its halves, the later one first so the
+        // earlier one's line numbers still hold.
+        let mut parts: Vec<&ConflictHunk> = hunk
+            .moved_with
+            .and_then(|other| conflict.hunks.get(other))
+            .into_iter()
+            .chain([hunk])
+            .collect();
+        parts.sort_by_key(|part| std::cmp::Reverse(part.local_start));
+
+        let mut lines: Vec<String> = self.text.split('\n').map(ToString::to_string).collect();
+        let mut start = 0;
+        for part in parts {
+            start = part.local_start.min(lines.len());
+            let end = start
+                .saturating_add(part.local_lines.len())
+                .min(lines.len());
+            lines.splice(start..end, part.external_lines.iter().cloned());
+        }
+        *self.text_mut() = lines.join("\n");
+        self.cursor = self.index_at_line_col(start, 0);
+        self.dirty = true;
+
+        let external = conflict.external.clone();
+        let next = ConflictState::new(&self.text, external, Some(conflict));
+        self.conflict = (!next.hunks.is_empty()).then_some(next);
+
+        true
+    }
+
+    /// Rewrite the buffer the way `policy` wants it saved, as one undoable
+    /// edit, keeping the cursor on its line. Returns whether anything changed.
+    pub fn apply_save_policy(&mut self, policy: SavePolicy) -> bool {
+        let normalized = match save::normalize_for_save(&self.text, policy) {
+            Cow::Borrowed(_) => return false,
+            Cow::Owned(text) => text,
+        };
+        let (row, col) = self.line_col_at(self.cursor);
+        self.push_undo_snapshot();
+        self.redo_stack.clear();
+        *self.text_mut() = normalized;
+        self.cursor = self.index_at_line_col(row, col);
+        self.selection_anchor = None;
+        self.dirty = true;
         true
     }
 
-    pub fn save_to_path(&mut self, path: &Path) -> io::Result<()> {
-        fs::write(path, &self.text)?;
+    /// Write the text with its original line endings, atomically where the
+    /// file system allows, and mark the buffer clean. Needs the `fs` feature.
+    #[cfg(feature = "fs")]
+    pub fn save_to_path(&mut self, path: &Path) -> io::Result<SaveOutcome> {
+        let outcome = save::write_file(path, &self.line_ending.apply(&self.text))?;
         self.dirty = false;
         self.conflict = None;
-        Ok(())
+        Ok(outcome)
     }
 
     fn set_from_disk(&mut self, text: String) {
-        self.text = text;
+        *self.text_mut() = text;
         self.cursor = self.text.len();
+        self.selection_anchor = None;
         self.dirty = false;
         self.conflict = None;
     }
 
-    fn line_col_at(&self, byte_index: usize) -> (usize, usize) {
-        let clamped = byte_index.min(self.text.len());
-        let mut line = 0usize;
-        let mut col = 0usize;
-        for (idx, ch) in self.text.char_indices() {
-            if idx >= clamped {
-                break;
-            }
-            if ch == '\n' {
-                line += 1;
-                col = 0;
-            } else {
-                col += 1;
+    fn remove_line(&mut self) -> Option<String> {
+        if self.text.is_empty() {
+            return None;
+        }
+        let (row, col) = self.line_col_at(self.cursor);
+        let start = self.index_at_line_col(row, 0);
+        let end = self.text[start..]
+            .find('\n')
+            .map(|offset| start + offset)
+            .unwrap_or(self.text.len());
+        let removed = self.text[start..end].to_string();
+
+        self.push_undo_snapshot();
+        self.redo_stack.clear();
+        if end < self.text.len() {
+            self.text_mut().replace_range(start..=end, "");
+            self.cursor = self.index_at_line_col(row, col);
+        } else if start > 0 {
+            self.text_mut().replace_range(start - 1..end, "");
+            self.cursor = self.index_at_line_col(row - 1, col);
+        } else {
+            self.text_mut().clear();
+            self.cursor = 0;
+        }
+        self.dirty = true;
+        Some(removed)
+    }
+
+    fn next_match(&self, needle: &str, options: SearchOptions) -> Option<(usize, usize)> {
+        let start = if self.cursor >= self.text.len() {
+            0
+        } else {
+            self.next_char_boundary(self.cursor)
+        };
+        let matches = self.match_ranges(needle, options);
+        matches
+            .iter()
+            .find(|(match_start, _)| *match_start >= start)
+            .or_else(|| matches.first())
+            .copied()
+    }
+
+    /// Non-overlapping `(start, end)` byte ranges of `needle` in document order.
+    pub fn match_ranges(&self, needle: &str, options: SearchOptions) -> Vec<(usize, usize)> {
+        match_ranges_in(&self.text, needle, options)
+    }
+
+    /// Start offsets of the lines a selection touches. A selection that ends
+    /// at column 0 leaves that last line alone.
+    fn selected_line_starts(&self) -> Option<Vec<usize>> {
+        let (start, end) = self.selection_range()?;
+        let first = self.text[..start].rfind('\n').map_or(0, |idx| idx + 1);
+        let mut starts = vec![first];
+        for (offset, _) in self.text[start..end].match_indices('\n') {
+            let line_start = start + offset + 1;
+            if line_start < end {
+                starts.push(line_start);
             }
         }
+        Some(starts)
+    }
+
+    fn leading_indent_len(&self, line_start: usize, width: usize) -> usize {
+        let line = &self.text[line_start..];
+        if line.starts_with('\t') {
+            return 1;
+        }
+        line.bytes()
+            .take(width)
+            .take_while(|byte| *byte == b' ')
+            .count()
+    }
+
+    /// Every edit goes through here so the line index never goes stale, nor
+    /// do secondary cursors; [`edit_at_cursors`](Self::edit_at_cursors) puts
+    /// its own back afterwards.
+    fn text_mut(&mut self) -> &mut String {
+        self.line_starts.take();
+        self.extra_cursors.clear();
+        self.revision += 1;
+        self.append_start = None;
+        &mut self.text
+    }
+
+    fn line_starts(&self) -> &[usize] {
+        self.line_starts.get_or_init(|| {
+            std::iter::once(0)
+                .chain(self.text.match_indices('\n').map(|(idx, _)| idx + 1))
+                .collect()
+        })
+    }
+
+    fn line_col_at(&self, byte_index: usize) -> (usize, usize) {
+        let clamped = byte_index.min(self.text.len());
+        let starts = self.line_starts();
+        let line = starts.partition_point(|&start| start <= clamped) - 1;
+        let start = starts[line];
+        let col = self.text[start..]
+            .char_indices()
+            .take_while(|(offset, _)| start + offset < clamped)
+            .count();
         (line, col)
     }
 
     fn index_at_line_col(&self, target_line: usize, target_col: usize) -> usize {
-        let mut line = 0usize;
-        let mut col = 0usize;
-
-        for (idx, ch) in self.text.char_indices() {
-            if line == target_line && col == target_col {
-                return idx;
-            }
-            if ch == '\n' {
-                if line == target_line {
-                    return idx;
-                }
-                line += 1;
-                col = 0;
-            } else {
-                col += 1;
-            }
+        if target_line >= self.line_count() {
+            return self.text.len();
         }
-
-        self.text.len()
+        let start = self.line_start(target_line);
+        let line = self.line_text(target_line);
+        line.char_indices()
+            .nth(target_col)
+            .map_or(start + line.len(), |(offset, _)| start + offset)
     }
 
     fn prev_char_boundary(&self, i: usize) -> usize {
@@ -669,8 +1432,9 @@ impl EditorBuffer {
     }
 
     fn restore(&mut self, state: HistoryState) {
-        self.text = state.text;
+        *self.text_mut() = state.text;
         self.cursor = state.cursor;
+        self.selection_anchor = None;
         self.dirty = state.dirty;
         self.conflict = state.conflict;
     }
@@ -680,7 +1444,21 @@ impl EditorBuffer {
         Self::push_history(&mut self.undo_stack, snapshot);
     }
 
+    /// Pop entries until one differs from `current` in more than the cursor.
+    fn pop_changed(stack: &mut Vec<HistoryState>, current: &HistoryState) -> Option<HistoryState> {
+        while let Some(state) = stack.pop() {
+            if !state.same_content(current) {
+                return Some(state);
+            }
+        }
+        None
+    }
+
+    /// Push `state`, unless the top entry already has the same content.
     fn push_history(stack: &mut Vec<HistoryState>, state: HistoryState) {
+        if stack.last().is_some_and(|top| top.same_content(&state)) {
+            return;
+        }
         stack.push(state);
         if stack.len() > MAX_HISTORY_ENTRIES {
             let overflow = stack.len() - MAX_HISTORY_ENTRIES;
@@ -696,12 +1474,178 @@ impl EditorBuffer {
     }
 }
 
+/// Leading indentation plus any blockquote and list markers of a line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LinePrefix {
+    /// Bytes of the line covered by the prefix.
+    len: usize,
+    /// Bytes kept when an empty item is terminated: the blockquote markers
+    /// around a list item, nothing for a bare quote.
+    outer_len: usize,
+    list_marker: bool,
+    quote_marker: bool,
+    /// Byte of the box character in a `[ ]`/`[x]` task marker.
+    task_box: Option<usize>,
+    /// Text to start the next line with.
+    continuation: String,
+}
+
+impl LinePrefix {
+    fn has_marker(&self) -> bool {
+        self.list_marker || self.quote_marker
+    }
+}
+
+fn line_prefix(line: &str) -> Option<LinePrefix> {
+    let bytes = line.as_bytes();
+    let skip_blank = |mut idx: usize| {
+        while matches!(bytes.get(idx), Some(b' ' | b'\t')) {
+            idx += 1;
+        }
+        idx
+    };
+
+    let mut idx = skip_blank(0);
+    let mut quote_end = 0;
+    while bytes.get(idx) == Some(&b'>') {
+        idx += 1;
+        if bytes.get(idx) == Some(&b' ') {
+            idx += 1;
+        }
+        quote_end = idx;
+        idx = skip_blank(idx);
+    }
+    let mut continuation = line[..idx].to_string();
+    let mut list_marker = false;
+    let mut task_box = None;
+
+    let digits = bytes[idx..]
+        .iter()
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+    let marker = match bytes.get(idx) {
+        Some(b'-' | b'*' | b'+') => Some((idx + 1, line[idx..idx + 1].to_string())),
+        Some(b'0'..=b'9')
+            if digits <= 9 && matches!(bytes.get(idx + digits), Some(b'.' | b')')) =>
+        {
+            let number = line[idx..idx + digits].parse::<u64>().unwrap_or(0) + 1;
+            let delimiter = &line[idx + digits..idx + digits + 1];
+            Some((idx + digits + 1, format!("{number}{delimiter}")))
+        }
+        _ => None,
+    };
+    if let Some((end, text)) = marker
+        && matches!(bytes.get(end), Some(b' ' | b'\t'))
+    {
+        let after = skip_blank(end);
+        continuation.push_str(&text);
+        continuation.push_str(&line[end..after]);
+        idx = after;
+        list_marker = true;
+
+        if matches!(line[idx..].get(..3), Some("[ ]" | "[x]" | "[X]"))
+            && matches!(bytes.get(idx + 3), None | Some(b' ' | b'\t'))
+        {
+            task_box = Some(idx + 1);
+        }
+        let task = line[idx..].get(..4);
+        if matches!(task, Some("[ ] " | "[x] " | "[X] ")) {
+            continuation.push_str("[ ] ");
+            idx += 4;
+        }
+    }
+
+    if idx == 0 {
+        return None;
+    }
+    let outer_len = if list_marker { quote_end } else { 0 };
+    Some(LinePrefix {
+        len: idx,
+        outer_len,
+        list_marker,
+        quote_marker: quote_end > 0,
+        task_box,
+        continuation,
+    })
+}
+
+fn is_atx_heading(line: &str) -> bool {
+    let hashes = line.bytes().take_while(|&byte| byte == b'#').count();
+    (1..=6).contains(&hashes) && matches!(line.as_bytes().get(hashes), None | Some(b' ' | b'\t'))
+}
+
+fn is_setext_underline(line: &str) -> bool {
+    let line = line.trim_end();
+    let Some(first) = line.chars().next() else {
+        return false;
+    };
+    matches!(first, '=' | '-') && line.chars().all(|ch| ch == first)
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric()
+}
+
+/// Byte ranges of the non-overlapping matches of `needle` in `text`, as the
+/// editor's search finds them. The preview searches its rendered lines
+/// with it too.
+pub fn match_ranges_in(text: &str, needle: &str, options: SearchOptions) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    if needle.is_empty() {
+        return ranges;
+    }
+
+    let mut from = 0;
+    while from < text.len() {
+        let hit = if options.ignore_case {
+            text[from..].char_indices().find_map(|(offset, _)| {
+                let at = from + offset;
+                match_len_ignore_case(&text[at..], needle).map(|len| (at, at + len))
+            })
+        } else {
+            text[from..]
+                .find(needle)
+                .map(|offset| (from + offset, from + offset + needle.len()))
+        };
+        let Some((start, end)) = hit else {
+            break;
+        };
+        let before = text[..start].chars().next_back();
+        let after = text[end..].chars().next();
+        if !options.whole_word
+            || !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+        {
+            ranges.push((start, end));
+            from = end;
+        } else {
+            from = start + text[start..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    ranges
+}
+
+/// Byte length of the prefix of `haystack` matching `needle` ignoring case.
+fn match_len_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    let mut hay = haystack.char_indices();
+    for n in needle.chars() {
+        let (_, h) = hay.next()?;
+        if !h.to_lowercase().eq(n.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(hay.next().map_or(haystack.len(), |(idx, _)| idx))
+}
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "fs")]
     use std::time::{SystemTime, UNIX_EPOCH};
 
-    use super::{EditorBuffer, MAX_HISTORY_BYTES, MAX_HISTORY_ENTRIES};
+    use super::{EditorBuffer, MAX_HISTORY_BYTES, MAX_HISTORY_ENTRIES, SavePolicy, SearchOptions};
+    #[cfg(feature = "fs")]
+    use super::{LineEnding, SaveOutcome};
 
+    #[cfg(feature = "fs")]
     fn temp_path(name: &str) -> std::path::PathBuf {
         let nanos = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -710,6 +1654,217 @@ mod tests {
         std::env::temp_dir().join(format!("mdv-editor-test-{name}-{nanos}.md"))
     }
 
+    #[test]
+    fn display_col_counts_terminal_cells() {
+        let mut buf = EditorBuffer::new("ab\n你好世界".into());
+        buf.set_cursor("ab\n你好".len());
+        assert_eq!(buf.line_col_at_cursor(), (1, 2));
+        assert_eq!(buf.display_col_at_cursor(), 4);
+
+        buf.set_cursor(2);
+        assert_eq!(buf.display_col_at_cursor(), 2);
+
+        let mut buf = EditorBuffer::new("e\u{301}x".into());
+        buf.set_cursor("e\u{301}".len());
+        assert_eq!(buf.display_col_at_cursor(), 1);
+    }
+
+    #[test]
+    fn extra_cursors_type_and_backspace_as_one_edit() {
+        let mut buf = EditorBuffer::new("one\ntwo\nthree\nfour".into());
+        buf.set_cursor(0);
+        assert!(buf.add_cursor_below());
+        assert!(buf.add_cursor_below());
+        assert_eq!(buf.extra_cursors(), ["one\n".len(), "one\ntwo\n".len()]);
+        for c in "- ".chars() {
+            buf.insert_char(c);
+        }
+        assert_eq!(buf.text(), "- one\n- two\n- three\nfour");
+        assert_eq!(buf.cursor(), 2);
+        assert_eq!(buf.extra_cursors(), [8, 14]);
+
+        buf.backspace();
+        assert_eq!(buf.text(), "-one\n-two\n-three\nfour");
+        assert_eq!(buf.extra_cursors().len(), 2);
+
+        assert!(buf.undo());
+        assert_eq!(buf.text(), "one\ntwo\nthree\nfour");
+        assert!(!buf.has_extra_cursors());
+        assert!(!buf.undo());
+    }
+
+    #[test]
+    fn extra_cursors_clamp_to_short_lines_and_merge() {
+        let mut buf = EditorBuffer::new("abcd\nab\nabcd".into());
+        buf.set_cursor(3);
+        assert!(!buf.add_cursor_above());
+        assert!(buf.add_cursor_below());
+        assert!(buf.add_cursor_below());
+        assert_eq!(
+            buf.extra_cursors(),
+            ["abcd\nab".len(), "abcd\nab\nabc".len()]
+        );
+        assert!(!buf.add_cursor_below());
+
+        // Backspacing across a line start joins lines without duplicating
+        // a cursor.
+        let mut buf = EditorBuffer::new("a\n\nb".into());
+        buf.set_cursor(2);
+        assert!(buf.add_cursor_above());
+        buf.backspace();
+        buf.backspace();
+        assert_eq!(buf.text(), "\nb");
+        assert_eq!(buf.cursor(), 0);
+        assert!(!buf.has_extra_cursors());
+
+        let mut buf = EditorBuffer::new("a\nb".into());
+        buf.set_cursor(0);
+        assert!(buf.add_cursor_below());
+        buf.insert_str("x");
+        assert!(!buf.has_extra_cursors());
+        assert!(buf.add_cursor_below());
+        assert!(!buf.find_next_with("zzz", SearchOptions::default()));
+        assert!(!buf.has_extra_cursors());
+    }
+
+    #[test]
+    fn move_line_swaps_with_neighbors_and_stops_at_the_ends() {
+        let mut buf = EditorBuffer::new("one\ntwo\nthree\n".into());
+        buf.set_cursor(1);
+        assert!(!buf.move_line_up());
+        assert!(buf.move_line_down());
+        assert_eq!(buf.text(), "two\none\nthree\n");
+        assert_eq!(buf.cursor(), "two\no".len());
+        assert!(buf.dirty);
+        assert!(buf.move_line_down());
+        assert_eq!(buf.text(), "two\nthree\none\n");
+        // The file still ends in a newline; the empty line after it stays.
+        assert!(!buf.move_line_down());
+        assert_eq!(buf.line_col_at_cursor(), (2, 1));
+
+        assert!(buf.move_line_up());
+        assert_eq!(buf.text(), "two\none\nthree\n");
+        assert!(buf.undo());
+        assert_eq!(buf.text(), "two\nthree\none\n");
+
+        let mut buf = EditorBuffer::new("a\nlast".into());
+        buf.set_cursor(4);
+        assert!(!buf.move_line_down());
+        assert!(buf.move_line_up());
+        assert_eq!(buf.text(), "last\na");
+        assert_eq!(buf.cursor(), 2);
+    }
+
+    #[test]
+    fn move_line_takes_the_selected_block_across_a_fence() {
+        let text = "```rust\nfn main() {}\n```\n- a\n- b\n- c\n";
+        let mut buf = EditorBuffer::new(text.into());
+        let block = text.find("- a").expect("block");
+        buf.set_cursor(block + 1);
+        buf.start_selection();
+        buf.set_cursor(text.len());
+        assert!(buf.move_line_up());
+        assert_eq!(buf.text(), "```rust\nfn main() {}\n- a\n- b\n- c\n```\n");
+        assert_eq!(buf.copy_selection().as_deref(), Some(" a\n- b\n- c\n"));
+        assert!(buf.move_line_up());
+        assert!(buf.move_line_up());
+        assert_eq!(buf.text(), "- a\n- b\n- c\n```rust\nfn main() {}\n```\n");
+        assert!(!buf.move_line_up());
+        assert!(buf.move_line_down());
+        assert!(buf.move_line_down());
+        assert!(buf.move_line_down());
+        assert_eq!(buf.text(), text);
+    }
+
+    #[test]
+    fn duplicate_line_copies_below_and_undoes_exactly() {
+        let text = "one\ntwo\nthree";
+        let mut buf = EditorBuffer::new(text.into());
+        buf.set_cursor("one\ntw".len());
+        assert_eq!(buf.duplicate_line(), 1);
+        assert_eq!(buf.text(), "one\ntwo\ntwo\nthree");
+        assert_eq!(buf.line_col_at_cursor(), (2, 2));
+        assert!(buf.dirty);
+
+        // The last line has no newline; exactly one line is added.
+        buf.move_doc_end();
+        buf.duplicate_line();
+        assert_eq!(buf.text(), "one\ntwo\ntwo\nthree\nthree");
+        assert_eq!(buf.cursor(), buf.text().len());
+
+        assert!(buf.undo());
+        assert!(buf.undo());
+        assert_eq!(buf.text(), text);
+        assert!(!buf.dirty);
+    }
+
+    #[test]
+    fn duplicate_line_copies_the_selected_block() {
+        let text = "- a\n- b\n- c\n";
+        let mut buf = EditorBuffer::new(text.into());
+        buf.set_cursor(0);
+        buf.start_selection();
+        buf.set_cursor("- a\n- b\n".len());
+        assert_eq!(buf.duplicate_line(), 2);
+        assert_eq!(buf.text(), "- a\n- b\n- a\n- b\n- c\n");
+        assert_eq!(buf.selection_range(), Some((8, 16)));
+        assert_eq!(buf.copy_selection().as_deref(), Some("- a\n- b\n"));
+        assert!(buf.undo());
+        assert_eq!(buf.text(), text);
+    }
+
+    #[test]
+    fn indent_selection_prefixes_touched_lines() {
+        let mut buf = EditorBuffer::new("- a\n- b\n\n- c\n".into());
+        buf.set_cursor(2);
+        buf.start_selection();
+        buf.set_cursor("- a\n- b\n\n- c".len());
+        assert_eq!(buf.indent_selection("  "), 3);
+        assert_eq!(buf.text(), "  - a\n  - b\n\n  - c\n");
+        assert_eq!(
+            buf.selection_range(),
+            Some((4, "  - a\n  - b\n\n  - c".len()))
+        );
+
+        assert!(buf.undo());
+        assert_eq!(buf.text(), "- a\n- b\n\n- c\n");
+    }
+
+    #[test]
+    fn selection_ending_at_line_start_skips_that_line() {
+        let mut buf = EditorBuffer::new("a\nb\nc".into());
+        buf.set_cursor(0);
+        buf.start_selection();
+        buf.set_cursor(4);
+        assert_eq!(buf.indent_selection("\t"), 2);
+        assert_eq!(buf.text(), "\ta\n\tb\nc");
+    }
+
+    #[test]
+    fn dedent_selection_handles_partially_indented_block() {
+        let mut buf = EditorBuffer::new("    a\n  b\nc\n\td".into());
+        buf.set_cursor(5);
+        buf.start_selection();
+        buf.set_cursor(buf.text().len());
+        assert_eq!(buf.dedent_selection(2), 3);
+        assert_eq!(buf.text(), "  a\nb\nc\nd");
+        assert_eq!(buf.selection_range(), Some((3, buf.text().len())));
+
+        assert_eq!(buf.dedent_selection(2), 1);
+        assert_eq!(buf.text(), "a\nb\nc\nd");
+        assert_eq!(buf.dedent_selection(2), 0);
+        assert!(buf.undo());
+        assert_eq!(buf.text(), "  a\nb\nc\nd");
+    }
+
+    #[test]
+    fn indent_and_dedent_need_a_selection() {
+        let mut buf = EditorBuffer::new("  a".into());
+        assert_eq!(buf.indent_selection("  "), 0);
+        assert_eq!(buf.dedent_selection(2), 0);
+        assert_eq!(buf.text(), "  a");
+    }
+
     #[test]
     fn insert_and_backspace_work() {
         let mut buf = EditorBuffer::new("abc".into());
@@ -773,6 +1928,141 @@ mod tests {
         assert_eq!(buf.cursor(), 0);
     }
 
+    #[test]
+    fn line_and_doc_movement_handles_empty_last_line() {
+        let mut buf = EditorBuffer::new("alpha\n  - item\n".into());
+        buf.set_cursor_line_col(1, 4);
+        buf.move_line_start();
+        assert_eq!(buf.line_col_at_cursor(), (1, 0));
+        buf.move_line_end();
+        assert_eq!(buf.line_col_at_cursor(), (1, 8));
+        buf.move_doc_end();
+        assert_eq!(buf.line_col_at_cursor(), (2, 0));
+        buf.move_line_start();
+        assert_eq!(buf.line_col_at_cursor(), (2, 0));
+        buf.move_line_end();
+        assert_eq!(buf.line_col_at_cursor(), (2, 0));
+        buf.move_doc_start();
+        assert_eq!(buf.cursor(), 0);
+        buf.move_line_end();
+        assert_eq!(buf.line_col_at_cursor(), (0, 5));
+    }
+
+    #[test]
+    fn smart_line_start_toggles_between_indent_and_column_zero() {
+        let mut buf = EditorBuffer::new("  - item\nplain\n".into());
+        buf.set_cursor_line_col(0, 6);
+        buf.move_line_start_smart();
+        assert_eq!(buf.line_col_at_cursor(), (0, 2));
+        buf.move_line_start_smart();
+        assert_eq!(buf.line_col_at_cursor(), (0, 0));
+        buf.move_line_start_smart();
+        assert_eq!(buf.line_col_at_cursor(), (0, 2));
+
+        buf.set_cursor_line_col(1, 3);
+        buf.move_line_start_smart();
+        assert_eq!(buf.line_col_at_cursor(), (1, 0));
+        buf.move_doc_end();
+        buf.move_line_start_smart();
+        assert_eq!(buf.line_col_at_cursor(), (2, 0));
+    }
+
+    #[test]
+    fn word_navigation_skips_punctuation_runs() {
+        let mut buf = EditorBuffer::new("foo.,;bar -- baz".into());
+        buf.move_doc_start();
+        buf.move_word_right();
+        assert_eq!(buf.cursor(), 3);
+        buf.move_word_right();
+        assert_eq!(buf.cursor(), 9);
+        buf.move_word_right();
+        assert_eq!(buf.cursor(), 16);
+        buf.move_word_right();
+        assert_eq!(buf.cursor(), 16);
+
+        buf.move_word_left();
+        assert_eq!(buf.cursor(), 13);
+        buf.move_word_left();
+        assert_eq!(buf.cursor(), 6);
+        buf.move_word_left();
+        assert_eq!(buf.cursor(), 0);
+        buf.move_word_left();
+        assert_eq!(buf.cursor(), 0);
+    }
+
+    #[test]
+    fn word_navigation_keeps_multibyte_chars_whole() {
+        let text = "café, 日本語 naïve!";
+        let mut buf = EditorBuffer::new(text.into());
+        buf.move_doc_start();
+        buf.move_word_right();
+        assert_eq!(&text[..buf.cursor()], "café");
+        buf.move_word_right();
+        assert_eq!(&text[..buf.cursor()], "café, 日本語");
+        buf.move_word_right();
+        assert_eq!(&text[..buf.cursor()], "café, 日本語 naïve");
+        buf.move_word_left();
+        assert_eq!(&text[buf.cursor()..], "naïve!");
+        buf.move_word_left();
+        assert_eq!(&text[buf.cursor()..], "日本語 naïve!");
+        assert!(text.is_char_boundary(buf.cursor()));
+    }
+
+    #[test]
+    fn find_with_ignore_case_matches_mixed_case_and_wraps() {
+        let options = SearchOptions {
+            ignore_case: true,
+            whole_word: false,
+        };
+        let mut buf = EditorBuffer::new("Table one\nsome table\nTABLE end".into());
+        buf.move_doc_start();
+        assert!(!buf.clone().find_next("TaBlE"));
+        assert!(buf.find_next_with("table", options));
+        assert_eq!(buf.line_col_at_cursor(), (1, 5));
+        assert!(buf.find_next_with("table", options));
+        assert_eq!(buf.line_col_at_cursor(), (2, 0));
+        assert!(buf.find_next_with("table", options));
+        assert_eq!(buf.cursor(), 0);
+        assert!(buf.find_prev_with("TABLE", options));
+        assert_eq!(buf.line_col_at_cursor(), (2, 0));
+    }
+
+    #[test]
+    fn whole_word_rejects_substring_hits() {
+        let options = SearchOptions {
+            ignore_case: false,
+            whole_word: true,
+        };
+        let mut buf = EditorBuffer::new("cat concat cat's cats (cat)".into());
+        buf.move_doc_start();
+        assert!(buf.find_next_with("cat", options));
+        assert_eq!(buf.cursor(), 11);
+        assert!(buf.find_next_with("cat", options));
+        assert_eq!(buf.cursor(), 23);
+        assert!(!buf.find_next_with("conc", options));
+
+        assert_eq!(buf.count_matches("cat", options), 3);
+        assert_eq!(buf.count_matches("cat", SearchOptions::default()), 5);
+        assert_eq!(buf.count_matches("", options), 0);
+        assert_eq!(buf.replace_all_with("cat", "dog", options), 3);
+        assert_eq!(buf.text(), "dog concat dog's cats (dog)");
+    }
+
+    #[test]
+    fn replace_with_options_honors_case_and_word_bounds() {
+        let options = SearchOptions {
+            ignore_case: true,
+            whole_word: true,
+        };
+        let mut buf = EditorBuffer::new("Foo food FOO".into());
+        buf.move_doc_start();
+        assert!(buf.replace_next_with("foo", "bar", options));
+        assert_eq!(buf.text(), "Foo food bar");
+        assert_eq!(buf.replace_all_with("FOO", "x", options), 1);
+        assert_eq!(buf.text(), "x food bar");
+        assert!(!buf.replace_next_with("", "x", options));
+    }
+
     #[test]
     fn paragraph_navigation_moves_between_blank_line_blocks() {
         let mut buf = EditorBuffer::new("a\nb\n\nc\nd\n\nz".into());
@@ -787,6 +2077,24 @@ mod tests {
         assert_eq!(buf.line_col_at_cursor(), (0, 0));
     }
 
+    #[test]
+    fn heading_navigation_skips_fenced_code_and_does_not_wrap() {
+        let buf = EditorBuffer::new(
+            "# Title\n\nIntro\n\n```sh\n# not a heading\n```\n\nUsage\n-----\n\n\
+             - item\n---\n\n    # indented code\n###### Deep\n####### Too deep\n#Tight\n"
+                .into(),
+        );
+        assert_eq!(buf.find_next_heading(0), Some(8));
+        assert_eq!(buf.find_next_heading(8), Some(15));
+        assert_eq!(buf.find_next_heading(15), None);
+        assert_eq!(buf.find_prev_heading(15), Some(8));
+        assert_eq!(buf.find_prev_heading(8), Some(0));
+        assert_eq!(buf.find_prev_heading(0), None);
+
+        let unclosed = EditorBuffer::new("~~~\n# inside\n".into());
+        assert_eq!(unclosed.find_next_heading(0), None);
+    }
+
     #[test]
     fn forward_and_range_delete_behaviors_work() {
         let mut buf = EditorBuffer::new("alpha beta".into());
@@ -806,6 +2114,209 @@ mod tests {
         assert_eq!(line_buf.text(), "\nnext");
     }
 
+    #[test]
+    fn selection_range_orders_anchor_and_cursor_across_lines() {
+        let mut buf = EditorBuffer::new("one\ntwo\nthree".into());
+        buf.set_cursor_line_col(2, 2);
+        buf.start_selection();
+        buf.move_up();
+        buf.move_up();
+        assert_eq!(buf.selection_range(), Some((2, 10)));
+        assert_eq!(buf.copy_selection().as_deref(), Some("e\ntwo\nth"));
+        buf.start_selection();
+        assert_eq!(buf.selection_anchor(), Some(10));
+
+        buf.set_cursor(10);
+        assert_eq!(buf.selection_range(), None);
+        assert_eq!(buf.copy_selection(), None);
+        buf.clear_selection();
+        assert_eq!(buf.selection_anchor(), None);
+    }
+
+    #[test]
+    fn smart_newline_continues_lists_and_quotes() {
+        let mut editor = EditorBuffer::new("3. foo".into());
+        editor.insert_newline_smart();
+        assert_eq!(editor.text(), "3. foo\n4. ");
+
+        let mut editor = EditorBuffer::new("> - [x] x".into());
+        editor.insert_newline_smart();
+        assert_eq!(editor.text(), "> - [x] x\n> - [ ] ");
+
+        let mut editor = EditorBuffer::new("  * item".into());
+        editor.insert_newline_smart();
+        assert_eq!(editor.text(), "  * item\n  * ");
+
+        let mut editor = EditorBuffer::new("plain".into());
+        editor.insert_newline_smart();
+        assert_eq!(editor.text(), "plain\n");
+    }
+
+    #[test]
+    fn smart_newline_on_empty_item_ends_the_list() {
+        let mut editor = EditorBuffer::new("- a\n- ".into());
+        editor.insert_newline_smart();
+        assert_eq!(editor.text(), "- a\n");
+        assert_eq!(editor.cursor(), 4);
+
+        let mut editor = EditorBuffer::new("> - ".into());
+        editor.insert_newline_smart();
+        assert_eq!(editor.text(), "> ");
+        editor.insert_newline_smart();
+        assert_eq!(editor.text(), "");
+
+        editor.undo();
+        assert_eq!(editor.text(), "> ");
+    }
+
+    #[test]
+    fn smart_newline_inside_marker_is_a_plain_newline() {
+        let mut editor = EditorBuffer::new("12. foo".into());
+        editor.set_cursor(1);
+        editor.insert_newline_smart();
+        assert_eq!(editor.text(), "1\n2. foo");
+
+        let mut editor = EditorBuffer::new("- ab".into());
+        editor.set_cursor(3);
+        editor.insert_newline_smart();
+        assert_eq!(editor.text(), "- a\n- b");
+    }
+
+    #[test]
+    fn toggle_task_flips_the_checkbox_on_the_cursor_line() {
+        let mut editor = EditorBuffer::new("intro\n  - [ ] item\n".into());
+        editor.set_cursor(9);
+        assert_eq!(editor.toggle_task(), Some(true));
+        assert_eq!(editor.text(), "intro\n  - [x] item\n");
+        assert_eq!(editor.cursor(), 9);
+        assert!(editor.dirty);
+        assert_eq!(editor.toggle_task(), Some(false));
+        assert_eq!(editor.text(), "intro\n  - [ ] item\n");
+
+        editor.undo();
+        assert_eq!(editor.text(), "intro\n  - [x] item\n");
+
+        let mut editor = EditorBuffer::new("> 1. [X]".into());
+        assert_eq!(editor.toggle_task(), Some(false));
+        assert_eq!(editor.text(), "> 1. [ ]");
+    }
+
+    #[test]
+    fn toggle_task_ignores_other_lines() {
+        for src in ["- item", "[ ] not a list", "- [ ]done", "plain"] {
+            let mut editor = EditorBuffer::new(src.into());
+            assert_eq!(editor.toggle_task(), None);
+            assert_eq!(editor.text(), src);
+            assert!(!editor.dirty);
+            assert!(!editor.undo());
+        }
+    }
+
+    #[test]
+    fn line_index_tracks_edits_and_undo() {
+        let mut editor = EditorBuffer::new("ab\ncd\n".into());
+        assert_eq!(editor.line_count(), 3);
+        assert_eq!(editor.line_text(1), "cd");
+        assert_eq!(editor.line_text(2), "");
+        assert_eq!(editor.line_text(9), "");
+        assert_eq!(editor.line_start(9), 6);
+        let revision = editor.revision();
+
+        editor.set_cursor(1);
+        editor.insert_newline();
+        assert!(editor.revision() > revision);
+        assert_eq!(editor.line_count(), 4);
+        assert_eq!(editor.line_col_at_cursor(), (1, 0));
+        editor.set_cursor_line_col(2, 5);
+        assert_eq!(editor.cursor(), 6);
+
+        editor.undo();
+        assert_eq!(editor.line_count(), 3);
+        assert_eq!(editor.line_text(0), "ab");
+    }
+
+    #[test]
+    fn current_line_returns_text_without_newline() {
+        let mut buf = EditorBuffer::new("one\ntwo\n".into());
+        buf.set_cursor_line_col(1, 1);
+        assert_eq!(buf.current_line(), "two");
+        buf.move_doc_end();
+        assert_eq!(buf.current_line(), "");
+    }
+
+    #[test]
+    fn cut_selection_and_paste_replace_text_with_undo() {
+        let mut buf = EditorBuffer::new("alpha beta\ngamma".into());
+        buf.set_selection_anchor(6);
+        buf.set_cursor_line_col(1, 2);
+        let cut = buf.cut_selection();
+        assert_eq!(cut.as_deref(), Some("beta\nga"));
+        assert_eq!(buf.text(), "alpha mma");
+        assert_eq!(buf.cursor(), 6);
+        assert!(buf.dirty);
+        assert_eq!(buf.selection_anchor(), None);
+        assert_eq!(buf.cut_selection(), None);
+
+        buf.paste("BETA ");
+        assert_eq!(buf.text(), "alpha BETA mma");
+        buf.set_selection_anchor(0);
+        buf.set_cursor(5);
+        buf.paste("é");
+        assert_eq!(buf.text(), "é BETA mma");
+        assert_eq!(buf.selection_anchor(), None);
+
+        assert!(buf.undo());
+        assert_eq!(buf.text(), "alpha BETA mma");
+        assert!(buf.undo());
+        assert!(buf.undo());
+        assert_eq!(buf.text(), "alpha beta\ngamma");
+    }
+
+    #[test]
+    fn delete_line_removes_current_line_and_clamps_column() {
+        let mut buf = EditorBuffer::new("first line\nab\nlast".into());
+        buf.set_cursor_line_col(0, 8);
+        buf.delete_line();
+        assert_eq!(buf.text(), "ab\nlast");
+        assert_eq!(buf.line_col_at_cursor(), (0, 2));
+        assert!(buf.dirty);
+        assert!(buf.undo());
+        assert_eq!(buf.text(), "first line\nab\nlast");
+    }
+
+    #[test]
+    fn delete_line_on_last_and_only_line_leaves_no_dangling_newline() {
+        let mut buf = EditorBuffer::new("one\ntwo".into());
+        buf.delete_line();
+        assert_eq!(buf.text(), "one");
+        assert_eq!(buf.line_col_at_cursor(), (0, 3));
+        buf.delete_line();
+        assert_eq!(buf.text(), "");
+        assert_eq!(buf.cursor(), 0);
+        buf.delete_line();
+        assert_eq!(buf.text(), "");
+    }
+
+    #[test]
+    fn cut_line_returns_text_and_paste_line_reinserts_above() {
+        let mut buf = EditorBuffer::new("a\nb\nc".into());
+        buf.set_cursor_line_col(1, 1);
+        let cut = buf.cut_line();
+        assert_eq!(cut, "b");
+        assert_eq!(buf.text(), "a\nc");
+        assert_eq!(buf.line_col_at_cursor(), (1, 1));
+
+        buf.move_doc_start();
+        buf.paste_line(&cut);
+        assert_eq!(buf.text(), "b\na\nc");
+        assert_eq!(buf.line_col_at_cursor(), (1, 0));
+
+        let mut empty = EditorBuffer::new(String::new());
+        assert_eq!(empty.cut_line(), "");
+        empty.paste_line("x");
+        assert_eq!(empty.text(), "x");
+    }
+
     #[test]
     fn external_change_reloads_when_clean() {
         let mut buf = EditorBuffer::new("old".into());
@@ -815,6 +2326,44 @@ mod tests {
         assert!(!buf.is_conflicted());
     }
 
+    #[test]
+    fn goto_line_col_clamps_the_column_to_the_line() {
+        let mut buf = EditorBuffer::new("first\nnaïve\n".into());
+        assert!(buf.goto_line_col(2, 4));
+        assert_eq!(buf.line_col_at_cursor(), (1, 3));
+        assert_eq!(&buf.text()[buf.cursor()..], "ve\n");
+        assert!(buf.goto_line_col(1, 80));
+        assert_eq!(buf.line_col_at_cursor(), (0, 5));
+        assert!(buf.goto_line_col(3, 9));
+        assert_eq!(buf.cursor(), buf.text().len());
+        assert!(!buf.goto_line_col(4, 1));
+        assert!(!buf.goto_line_col(0, 1));
+    }
+
+    #[test]
+    fn external_change_keeps_the_cursor_position_and_is_undoable() {
+        let mut buf = EditorBuffer::new("a\nbb\ncccc\n".into());
+        buf.set_cursor_line_col(2, 3);
+        buf.on_external_change("a\nbb\ncccc!\nmore\n".into());
+        assert_eq!(buf.line_col_at_cursor(), (2, 3));
+
+        buf.on_external_change("a\nb\n".into());
+        assert_eq!(buf.line_col_at_cursor(), (2, 0));
+        buf.set_cursor_line_col(1, 1);
+        buf.on_external_change("abc\n".into());
+        assert_eq!(buf.line_col_at_cursor(), (1, 0));
+
+        assert!(buf.undo());
+        assert_eq!(buf.text(), "a\nb\n");
+        assert!(buf.dirty);
+        assert!(buf.redo());
+        assert_eq!(buf.text(), "abc\n");
+        assert!(!buf.dirty);
+        assert!(buf.undo() && buf.undo() && buf.undo());
+        assert_eq!(buf.text(), "a\nbb\ncccc\n");
+        assert!(!buf.undo());
+    }
+
     #[test]
     fn external_change_sets_conflict_when_dirty() {
         let mut buf = EditorBuffer::new("old".into());
@@ -830,6 +2379,28 @@ mod tests {
         assert_eq!(buf.text(), "old!");
     }
 
+    #[test]
+    fn append_external_keeps_cursor_and_tracks_lines() {
+        let mut buf = EditorBuffer::new("one\ntwo".into());
+        buf.set_cursor(2);
+        assert_eq!(buf.line_count(), 2);
+        let revision = buf.revision();
+
+        assert!(buf.append_external(" more\nthree\n"));
+        assert!(buf.append_external("four"));
+        assert_eq!(buf.text(), "one\ntwo more\nthree\nfour");
+        assert_eq!(buf.cursor(), 2);
+        assert!(!buf.dirty);
+        assert_eq!(buf.line_count(), 4);
+        assert_eq!(buf.line_start(3), "one\ntwo more\nthree\n".len());
+        assert!(buf.only_appended_since(revision));
+
+        buf.insert_char('!');
+        assert!(!buf.only_appended_since(revision));
+        assert!(!buf.append_external("five"));
+        assert_eq!(buf.text(), "on!e\ntwo more\nthree\nfour");
+    }
+
     #[test]
     fn reload_external_accepts_disk_state() {
         let mut buf = EditorBuffer::new("old".into());
@@ -840,6 +2411,13 @@ mod tests {
         assert_eq!(buf.text(), "disk");
         assert!(!buf.dirty);
         assert!(!buf.is_conflicted());
+
+        assert!(buf.undo());
+        assert_eq!(buf.text(), "old!");
+        assert!(buf.dirty);
+        assert_eq!(buf.conflict().expect("conflict").external, "disk");
+        assert!(buf.redo());
+        assert_eq!(buf.text(), "disk");
     }
 
     #[test]
@@ -856,6 +2434,26 @@ mod tests {
         assert!(buf.dirty);
     }
 
+    #[test]
+    fn merge_external_marks_each_hunk_and_keeps_shared_lines() {
+        let mut buf = EditorBuffer::new("same\nlocal\nshared\nshared too\n".into());
+        buf.move_doc_end();
+        buf.insert_char('!');
+        buf.on_external_change("same\nexternal\nshared\nshared too\n".into());
+        assert_eq!(
+            buf.conflict_markers().as_deref(),
+            Some(
+                "same\n<<<<<<< local\nlocal\n=======\nexternal\n>>>>>>> external\nshared\n\
+                 shared too\n<<<<<<< local\n!\n=======\n\n>>>>>>> external"
+            )
+        );
+
+        buf.merge_external();
+        assert!(buf.text().starts_with("same\n<<<<<<< local\nlocal\n"));
+        assert_eq!(buf.line_col_at_cursor(), (1, 0));
+        assert!(buf.conflict_markers().is_none());
+    }
+
     #[test]
     fn move_up_down_keeps_column_when_possible() {
         let mut buf = EditorBuffer::new("ab\n1234\nxy".into());
@@ -934,6 +2532,7 @@ mod tests {
         assert_eq!(buf.text(), "x");
     }
 
+    #[cfg(feature = "fs")]
     #[test]
     fn save_to_path_persists_text_and_clears_state() {
         let path = temp_path("save");
@@ -950,6 +2549,140 @@ mod tests {
         let _ = std::fs::remove_file(&path);
     }
 
+    #[test]
+    fn save_policy_adds_one_final_newline_and_trims_prose_lines() {
+        let policy = SavePolicy {
+            final_newline: true,
+            trim_trailing_whitespace: true,
+        };
+        let mut buf = EditorBuffer::new("# Title".into());
+        assert!(buf.apply_save_policy(policy));
+        assert_eq!(buf.text(), "# Title\n");
+        assert!(!buf.apply_save_policy(policy));
+
+        let text = "line one  \nline two\t \n\n```sh\necho hi   \n\n```  \nlast \n  \n\n\n";
+        let mut buf = EditorBuffer::new(text.into());
+        buf.goto_line(5);
+        assert!(buf.apply_save_policy(policy));
+        // The hard break before `line two` stays; code keeps its spaces.
+        assert_eq!(
+            buf.text(),
+            "line one  \nline two\n\n```sh\necho hi   \n\n```\nlast\n"
+        );
+        assert_eq!(buf.line_col_at_cursor(), (4, 0));
+        assert!(buf.dirty);
+        buf.undo();
+        assert_eq!(buf.text(), text);
+
+        let mut buf = EditorBuffer::new("a  \n\n\n".into());
+        let newline_only = SavePolicy {
+            final_newline: true,
+            ..SavePolicy::default()
+        };
+        assert!(buf.apply_save_policy(newline_only));
+        assert_eq!(buf.text(), "a  \n");
+        let mut empty = EditorBuffer::new(String::new());
+        assert!(!empty.apply_save_policy(policy));
+        assert!(!buf.apply_save_policy(SavePolicy::default()));
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn save_to_path_restores_crlf_line_endings() {
+        let path = temp_path("save-crlf");
+        std::fs::write(&path, "# Title\r\n\r\nfirst line\r\nsecond line\r\n").expect("seed");
+        let loaded = crate::read_text(&path).expect("read");
+        let mut buf = EditorBuffer::new(loaded.text);
+        buf.set_line_ending(loaded.line_ending);
+        assert_eq!(buf.line_ending(), LineEnding::CrLf);
+
+        buf.set_cursor(buf.line_start(2) + "first line".len());
+        buf.insert_char('!');
+        buf.save_to_path(&path).expect("save");
+        assert_eq!(
+            std::fs::read(&path).expect("read"),
+            b"# Title\r\n\r\nfirst line!\r\nsecond line\r\n"
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn save_to_path_replaces_atomically_and_keeps_permissions() {
+        let path = temp_path("save-atomic");
+        std::fs::write(&path, "old").expect("seed");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).expect("chmod");
+        }
+
+        let mut buf = EditorBuffer::new("new contents".into());
+        let outcome = buf.save_to_path(&path).expect("save");
+        assert_eq!(outcome, SaveOutcome::Atomic);
+        assert_eq!(
+            std::fs::read_to_string(&path).expect("read"),
+            "new contents"
+        );
+        assert!(!crate::save::temp_path_for(&path).exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).expect("meta").permissions().mode();
+            assert_eq!(mode & 0o777, 0o640);
+        }
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn save_to_path_falls_back_to_direct_write_when_temp_is_blocked() {
+        let path = temp_path("save-fallback");
+        std::fs::write(&path, "old").expect("seed");
+        let tmp = crate::save::temp_path_for(&path);
+        std::fs::create_dir(&tmp).expect("block temp path");
+
+        let mut buf = EditorBuffer::new("fresh".into());
+        buf.insert_char('!');
+        let outcome = buf.save_to_path(&path).expect("save");
+        assert!(matches!(outcome, SaveOutcome::DirectWrite { .. }));
+        assert_eq!(std::fs::read_to_string(&path).expect("read"), "fresh!");
+        assert!(!buf.dirty);
+
+        let _ = std::fs::remove_dir(&tmp);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(feature = "fs")]
+    #[cfg(unix)]
+    #[test]
+    fn save_to_path_in_readonly_directory_writes_in_place() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_path("save-readonly-dir");
+        std::fs::create_dir(&dir).expect("mkdir");
+        let path = dir.join("note.md");
+        std::fs::write(&path, "old").expect("seed");
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o555)).expect("chmod");
+
+        let mut buf = EditorBuffer::new("kept".into());
+        let result = buf.save_to_path(&path);
+        let probe_allowed = std::fs::write(dir.join("probe"), "").is_ok();
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).expect("restore");
+
+        let outcome = result.expect("save");
+        assert_eq!(std::fs::read_to_string(&path).expect("read"), "kept");
+        if !probe_allowed {
+            // Privileged users can still create files, so only assert when denied.
+            assert!(matches!(outcome, SaveOutcome::DirectWrite { .. }));
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(feature = "fs")]
     #[test]
     fn save_to_path_returns_error_for_directory() {
         let path = temp_path("save-dir");
@@ -978,6 +2711,42 @@ mod tests {
         assert_eq!(buf.cursor(), 3);
     }
 
+    #[test]
+    fn undo_and_redo_always_change_the_text_or_conflict() {
+        let mut buf = EditorBuffer::new("a\nb\nc".into());
+        buf.dirty = true;
+        buf.on_external_change("a\nB\nc".into());
+        buf.insert_char('!');
+        assert!(buf.apply_external_hunk(0));
+        assert!(buf.apply_external_hunk(0));
+        assert_eq!(buf.text(), "a\nB\nc");
+        assert!(!buf.is_conflicted());
+        // Rewriting text with itself leaves one entry that changes nothing.
+        assert!(buf.replace_range(0, 1, "a"));
+        assert!(buf.replace_range(0, 1, "a"));
+        buf.move_left();
+        // The no-op entry is not counted: one Ctrl+Z reaches the conflict.
+        assert_eq!((buf.undo_depth(), buf.redo_depth()), (1, 0));
+
+        let shown = |buf: &EditorBuffer| (buf.text().to_string(), buf.is_conflicted());
+        let before = shown(&buf);
+        assert!(buf.undo());
+        assert_ne!(shown(&buf), before);
+        assert_eq!(shown(&buf), ("a\nb\nc".to_string(), true));
+        assert_eq!((buf.undo_depth(), buf.redo_depth()), (0, 1));
+        assert!(!buf.undo());
+
+        assert!(buf.redo());
+        assert_eq!(shown(&buf), before);
+        assert_eq!((buf.undo_depth(), buf.redo_depth()), (1, 0));
+
+        buf.insert_char('x');
+        buf.insert_char('y');
+        assert!(buf.undo());
+        assert_eq!((buf.undo_depth(), buf.redo_depth()), (2, 1));
+        assert!(!buf.text().contains('y'));
+    }
+
     #[test]
     fn redo_cleared_after_new_edit() {
         let mut buf = EditorBuffer::new("ab".into());
@@ -1046,9 +2815,9 @@ mod tests {
 
     #[test]
     fn apply_external_hunk_updates_text_and_resolves_when_done() {
-        let mut buf = EditorBuffer::new("a\nb\nc".into());
+        let mut buf = EditorBuffer::new("a\nb\nc\nc".into());
         buf.dirty = true;
-        buf.on_external_change("a\nB\nc\nd".into());
+        buf.on_external_change("a\nB\nc\nc\nd".into());
         assert!(buf.is_conflicted());
         assert_eq!(buf.conflict().expect("conflict").hunks.len(), 2);
 
@@ -1058,7 +2827,7 @@ mod tests {
         assert_eq!(buf.conflict().expect("conflict").hunks.len(), 1);
 
         assert!(buf.apply_external_hunk(0));
-        assert_eq!(buf.text(), "a\nB\nc\nd");
+        assert_eq!(buf.text(), "a\nB\nc\nc\nd");
         assert!(buf.dirty);
         assert!(!buf.is_conflicted());
     }
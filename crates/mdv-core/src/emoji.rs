@@ -0,0 +1,258 @@
+use std::borrow::Cow;
+
+/// GitHub shortcodes and their emoji, sorted by name for binary search.
+/// The common ones only; anything else is left as written.
+const SHORTCODES: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("1234", "🔢"),
+    ("alarm_clock", "⏰"),
+    ("angry", "😠"),
+    ("arrow_down", "⬇️"),
+    ("arrow_forward", "▶️"),
+    ("arrow_left", "⬅️"),
+    ("arrow_right", "➡️"),
+    ("arrow_up", "⬆️"),
+    ("art", "🎨"),
+    ("beer", "🍺"),
+    ("bell", "🔔"),
+    ("blue_heart", "💙"),
+    ("blush", "😊"),
+    ("bomb", "💣"),
+    ("book", "📖"),
+    ("bookmark", "🔖"),
+    ("books", "📚"),
+    ("boom", "💥"),
+    ("broken_heart", "💔"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("calendar", "📆"),
+    ("camera", "📷"),
+    ("chart_with_upwards_trend", "📈"),
+    ("check", "✔️"),
+    ("checkered_flag", "🏁"),
+    ("clap", "👏"),
+    ("clipboard", "📋"),
+    ("clock1", "🕐"),
+    ("cloud", "☁️"),
+    ("coffee", "☕"),
+    ("computer", "💻"),
+    ("confused", "😕"),
+    ("construction", "🚧"),
+    ("cool", "🆒"),
+    ("cry", "😢"),
+    ("crystal_ball", "🔮"),
+    ("dart", "🎯"),
+    ("dash", "💨"),
+    ("disappointed", "😞"),
+    ("dizzy", "💫"),
+    ("dog", "🐶"),
+    ("door", "🚪"),
+    ("email", "📧"),
+    ("exclamation", "❗"),
+    ("eyes", "👀"),
+    ("facepalm", "🤦"),
+    ("file_folder", "📁"),
+    ("fire", "🔥"),
+    ("flashlight", "🔦"),
+    ("gear", "⚙️"),
+    ("ghost", "👻"),
+    ("gift", "🎁"),
+    ("globe_with_meridians", "🌐"),
+    ("green_circle", "🟢"),
+    ("green_heart", "💚"),
+    ("grin", "😁"),
+    ("grinning", "😀"),
+    ("hammer", "🔨"),
+    ("hammer_and_wrench", "🛠️"),
+    ("hand", "✋"),
+    ("heart", "❤️"),
+    ("heavy_check_mark", "✔️"),
+    ("heavy_exclamation_mark", "❗"),
+    ("heavy_minus_sign", "➖"),
+    ("heavy_plus_sign", "➕"),
+    ("hourglass", "⌛"),
+    ("hourglass_flowing_sand", "⏳"),
+    ("house", "🏠"),
+    ("hugs", "🤗"),
+    ("information_source", "ℹ️"),
+    ("joy", "😂"),
+    ("key", "🔑"),
+    ("kiss", "😘"),
+    ("label", "🏷️"),
+    ("lady_beetle", "🐞"),
+    ("large_blue_circle", "🔵"),
+    ("laughing", "😆"),
+    ("link", "🔗"),
+    ("lipstick", "💄"),
+    ("lock", "🔒"),
+    ("loudspeaker", "📢"),
+    ("mag", "🔍"),
+    ("mailbox", "📫"),
+    ("memo", "📝"),
+    ("moon", "🌔"),
+    ("muscle", "💪"),
+    ("new", "🆕"),
+    ("no_entry", "⛔"),
+    ("no_entry_sign", "🚫"),
+    ("note", "🗒️"),
+    ("ok", "🆗"),
+    ("ok_hand", "👌"),
+    ("package", "📦"),
+    ("page_facing_up", "📄"),
+    ("paperclip", "📎"),
+    ("partying_face", "🥳"),
+    ("pencil", "📝"),
+    ("pencil2", "✏️"),
+    ("point_down", "👇"),
+    ("point_left", "👈"),
+    ("point_right", "👉"),
+    ("point_up", "☝️"),
+    ("pray", "🙏"),
+    ("purple_heart", "💜"),
+    ("pushpin", "📌"),
+    ("question", "❓"),
+    ("raised_hands", "🙌"),
+    ("recycle", "♻️"),
+    ("red_circle", "🔴"),
+    ("relaxed", "☺️"),
+    ("repeat", "🔁"),
+    ("rocket", "🚀"),
+    ("rotating_light", "🚨"),
+    ("scream", "😱"),
+    ("see_no_evil", "🙈"),
+    ("shield", "🛡️"),
+    ("shrug", "🤷"),
+    ("skull", "💀"),
+    ("sleeping", "😴"),
+    ("slightly_smiling_face", "🙂"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("smirk", "😏"),
+    ("snail", "🐌"),
+    ("sob", "😭"),
+    ("sparkles", "✨"),
+    ("speech_balloon", "💬"),
+    ("star", "⭐"),
+    ("star2", "🌟"),
+    ("stop_sign", "🛑"),
+    ("stopwatch", "⏱️"),
+    ("sunglasses", "😎"),
+    ("sunny", "☀️"),
+    ("sweat_smile", "😅"),
+    ("tada", "🎉"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("tired_face", "😫"),
+    ("triangular_flag_on_post", "🚩"),
+    ("trophy", "🏆"),
+    ("truck", "🚚"),
+    ("turtle", "🐢"),
+    ("unlock", "🔓"),
+    ("v", "✌️"),
+    ("warning", "⚠️"),
+    ("wave", "👋"),
+    ("white_check_mark", "✅"),
+    ("wink", "😉"),
+    ("wrench", "🔧"),
+    ("x", "❌"),
+    ("yellow_circle", "🟡"),
+    ("yellow_heart", "💛"),
+    ("yum", "😋"),
+    ("zap", "⚡"),
+    ("zzz", "💤"),
+];
+
+/// The emoji GitHub shows for `:name:`, given the name without colons.
+pub fn emoji_for(name: &str) -> Option<&'static str> {
+    SHORTCODES
+        .binary_search_by(|(code, _)| code.cmp(&name))
+        .ok()
+        .map(|idx| SHORTCODES[idx].1)
+}
+
+/// `text` with every known `:shortcode:` replaced by its emoji. Unknown
+/// names stay as written, and so do words that look like URLs, where a `:`
+/// is part of the address.
+pub fn replace_shortcodes(text: &str) -> Cow<'_, str> {
+    if !text.contains(':') {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut changed = false;
+    for word in text.split_inclusive(char::is_whitespace) {
+        if word.contains("://") || word.starts_with("www.") {
+            out.push_str(word);
+            continue;
+        }
+        let mut rest = word;
+        while let Some(open) = rest.find(':') {
+            out.push_str(&rest[..open]);
+            let after = &rest[open + 1..];
+            let name_len = after
+                .find(|c: char| !is_shortcode_char(c))
+                .unwrap_or(after.len());
+            let emoji = (after[name_len..].starts_with(':'))
+                .then(|| emoji_for(&after[..name_len]))
+                .flatten();
+            match emoji {
+                Some(emoji) => {
+                    out.push_str(emoji);
+                    rest = &after[name_len + 1..];
+                    changed = true;
+                }
+                // The closing `:` may open the next shortcode.
+                None => {
+                    out.push(':');
+                    rest = after;
+                }
+            }
+        }
+        out.push_str(rest);
+    }
+    if changed {
+        Cow::Owned(out)
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+fn is_shortcode_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '+' | '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SHORTCODES, emoji_for, replace_shortcodes};
+
+    #[test]
+    fn table_is_sorted_for_lookup() {
+        assert!(SHORTCODES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(emoji_for("tada"), Some("🎉"));
+        assert_eq!(emoji_for("+1"), Some("👍"));
+        assert_eq!(emoji_for("not_an_emoji"), None);
+    }
+
+    #[test]
+    fn known_shortcodes_are_replaced_and_the_rest_kept() {
+        assert_eq!(replace_shortcodes("Done :tada::+1:!"), "Done 🎉👍!");
+        assert_eq!(
+            replace_shortcodes("at 10:30:warning: now"),
+            "at 10:30⚠️ now"
+        );
+        assert_eq!(
+            replace_shortcodes(":unknown: std::mem :TADA:"),
+            ":unknown: std::mem :TADA:"
+        );
+        assert_eq!(
+            replace_shortcodes("see https://example.com/:tada: and :x:"),
+            "see https://example.com/:tada: and ❌"
+        );
+        assert!(matches!(
+            replace_shortcodes("a: b"),
+            std::borrow::Cow::Borrowed(_)
+        ));
+    }
+}
@@ -0,0 +1,152 @@
+use std::borrow::Cow;
+
+/// Which delimiters a front matter block uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontMatterKind {
+    /// `---` lines around YAML.
+    Yaml,
+    /// `+++` lines around TOML.
+    Toml,
+}
+
+impl FrontMatterKind {
+    pub fn delimiter(self) -> &'static str {
+        match self {
+            FrontMatterKind::Yaml => "---",
+            FrontMatterKind::Toml => "+++",
+        }
+    }
+}
+
+/// A metadata block opening the document, as static site generators and
+/// GitHub read it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrontMatter<'a> {
+    pub kind: FrontMatterKind,
+    /// The lines between the delimiters.
+    pub body: &'a str,
+    /// Byte offset just past the closing delimiter's line.
+    pub end: usize,
+}
+
+impl FrontMatter<'_> {
+    /// Top-level keys: unindented `key:` lines for YAML, `key =` lines for
+    /// TOML. Comments, list items, nested values and `[table]` headers do
+    /// not count.
+    pub fn key_count(&self) -> usize {
+        self.body
+            .lines()
+            .filter(|line| is_key_line(self.kind, line))
+            .count()
+    }
+}
+
+/// The front matter at byte 0 of `markdown`: a `---` or `+++` line, lines
+/// starting with a key, and the same delimiter closing them. Anything else,
+/// such as a `---` rule followed by prose, is left to the Markdown parser.
+pub fn front_matter(markdown: &str) -> Option<FrontMatter<'_>> {
+    let first = markdown.split_inclusive('\n').next()?;
+    let kind = match first.trim_end() {
+        "---" => FrontMatterKind::Yaml,
+        "+++" => FrontMatterKind::Toml,
+        _ => return None,
+    };
+    let body_start = first.len();
+    let mut end = body_start;
+    for line in markdown[body_start..].split_inclusive('\n') {
+        let line_start = end;
+        end += line.len();
+        if line.trim_end() == kind.delimiter() {
+            let body = &markdown[body_start..line_start];
+            let opens_with_key = body
+                .lines()
+                .next()
+                .is_some_and(|line| is_key_line(kind, line) || is_table_header(kind, line));
+            return opens_with_key.then_some(FrontMatter { kind, body, end });
+        }
+    }
+    None
+}
+
+/// `markdown` with any front matter turned into blank lines, so a parser
+/// skips it while byte offsets and line numbers stay the same.
+pub(crate) fn blank_front_matter(markdown: &str) -> Cow<'_, str> {
+    let Some(front) = front_matter(markdown) else {
+        return Cow::Borrowed(markdown);
+    };
+    let mut blanked: String = markdown[..front.end]
+        .bytes()
+        .map(|byte| if byte == b'\n' { '\n' } else { ' ' })
+        .collect();
+    blanked.push_str(&markdown[front.end..]);
+    Cow::Owned(blanked)
+}
+
+fn is_key_line(kind: FrontMatterKind, line: &str) -> bool {
+    if line.starts_with(char::is_whitespace) || line.starts_with(['#', '-']) {
+        return false;
+    }
+    let (separator, needs_space) = match kind {
+        FrontMatterKind::Yaml => (':', true),
+        FrontMatterKind::Toml => ('=', false),
+    };
+    line.split_once(separator).is_some_and(|(key, value)| {
+        !key.trim().is_empty()
+            && (!needs_space || value.is_empty() || value.starts_with(char::is_whitespace))
+    })
+}
+
+fn is_table_header(kind: FrontMatterKind, line: &str) -> bool {
+    kind == FrontMatterKind::Toml && line.starts_with('[') && line.trim_end().ends_with(']')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FrontMatterKind, blank_front_matter, front_matter};
+
+    #[test]
+    fn yaml_and_toml_blocks_at_the_start_are_found() {
+        let yaml = "---\ntitle: Notes\ntags:\n  - rust\n# comment\ndate: 2024-01-02\n---\n# Body\n";
+        let front = front_matter(yaml).expect("yaml");
+        assert_eq!(front.kind, FrontMatterKind::Yaml);
+        assert_eq!(
+            front.body,
+            "title: Notes\ntags:\n  - rust\n# comment\ndate: 2024-01-02\n"
+        );
+        assert_eq!(&yaml[front.end..], "# Body\n");
+        assert_eq!(front.key_count(), 3);
+
+        let toml = "+++\ntitle = \"Notes\"\ndraft = true\n[extra]\nmath = true\n+++\n";
+        let front = front_matter(toml).expect("toml");
+        assert_eq!(front.kind, FrontMatterKind::Toml);
+        assert_eq!(front.end, toml.len());
+        assert_eq!(front.key_count(), 3);
+    }
+
+    #[test]
+    fn rules_and_later_blocks_are_not_front_matter() {
+        // A rule followed by prose, closed or not.
+        assert_eq!(front_matter("---\nSome text\n"), None);
+        assert_eq!(front_matter("---\nSome text\n---\n"), None);
+        assert_eq!(front_matter("---\n\ntitle: x\n---\n"), None);
+        // Not at byte 0.
+        assert_eq!(front_matter("\n---\ntitle: x\n---\n"), None);
+        assert_eq!(front_matter(" ---\ntitle: x\n---\n"), None);
+        assert_eq!(front_matter("# Doc\n\n---\ntitle: x\n---\n"), None);
+        // Never closed, or closed by the other delimiter.
+        assert_eq!(front_matter("---\ntitle: x\n"), None);
+        assert_eq!(front_matter("---\ntitle: x\n+++\n"), None);
+        assert_eq!(front_matter("---\nurl:https://x\n---\n"), None);
+    }
+
+    #[test]
+    fn blanking_keeps_offsets_and_lines() {
+        let src = "---\ntitle: Café\n---\nBody\n";
+        let blanked = blank_front_matter(src);
+        assert_eq!(blanked.len(), src.len());
+        assert_eq!(blanked.lines().count(), src.lines().count());
+        assert!(blanked.ends_with("\nBody\n"));
+        assert!(blanked.lines().take(3).all(|line| line.trim().is_empty()));
+        assert_eq!(blank_front_matter("Body\n"), "Body\n");
+    }
+}
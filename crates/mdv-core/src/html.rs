@@ -0,0 +1,75 @@
+use pulldown_cmark::{Parser, html};
+
+use crate::markdown::parser_options;
+use crate::outline::extract_outline;
+
+/// Inline styles roughly matching the default terminal theme.
+const STYLE: &str = "\
+body { margin: 0 auto; max-width: 52rem; padding: 2rem; background: #282c34; color: #abb2bf; \
+font-family: -apple-system, BlinkMacSystemFont, \"Segoe UI\", Helvetica, Arial, sans-serif; line-height: 1.6; }
+h1, h2, h3, h4, h5, h6 { color: #e06c75; line-height: 1.25; }
+a { color: #61afef; }
+code { color: #e5c07b; background: #373b43; padding: 0.1em 0.3em; border-radius: 3px; \
+font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; }
+pre { background: #373b43; padding: 0.8em 1em; border-radius: 4px; overflow-x: auto; }
+pre code { padding: 0; }
+blockquote { margin-left: 0; padding-left: 1em; border-left: 3px solid #5c6370; color: #5c6370; font-style: italic; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #5c6370; padding: 0.3em 0.7em; }
+th { color: #56b6c2; }
+li::marker { color: #c678dd; }
+hr { border: 0; border-top: 1px solid #5c6370; }
+.math { font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; color: #c678dd; }
+.math-display { display: block; text-align: center; margin: 1em 0; }
+.footnote-definition { font-size: 0.9em; }
+.footnote-definition p { display: inline; }
+";
+
+/// Render `markdown` as a standalone HTML page. Math is kept as raw TeX in
+/// `<span class="math ...">` so a page script such as KaTeX can pick it up.
+pub fn render_html(markdown: &str) -> String {
+    let mut body = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut body, Parser::new_ext(markdown, parser_options()));
+
+    let title = extract_outline(markdown)
+        .into_iter()
+        .next()
+        .map(|entry| entry.text)
+        .unwrap_or_else(|| "mdv export".into());
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>\n{STYLE}</style>\n</head>\n<body>\n{body}</body>\n</html>\n",
+        escape_text(&title)
+    )
+}
+
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_html;
+
+    #[test]
+    fn wraps_the_body_in_a_page_titled_by_the_first_heading() {
+        let html = render_html("intro\n\n# Tom & Jerry <3\n");
+        assert!(html.starts_with("<!DOCTYPE html>\n"));
+        assert!(html.contains("<title>Tom &amp; Jerry &lt;3</title>"));
+        assert!(html.contains("<p>intro</p>\n"));
+        assert!(html.ends_with("</body>\n</html>\n"));
+
+        assert!(render_html("no headings").contains("<title>mdv export</title>"));
+    }
+}
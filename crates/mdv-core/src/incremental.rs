@@ -0,0 +1,523 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+use pulldown_cmark::{Event, Parser, Tag};
+
+use crate::front_matter::{blank_front_matter, front_matter};
+use crate::markdown::{
+    PreviewLine, RenderFlags, SegmentKind, footnotes_heading_segments, parser_options,
+    render_block_segments,
+};
+use crate::render::FrontMatterStyle;
+
+/// Renders the preview one top-level block at a time and keeps each block's
+/// lines between calls, so an edit only re-renders the blocks it touched.
+///
+/// The output matches [`render_preview_segments`](crate::render_preview_segments)
+/// for the same text and width.
+#[derive(Debug, Default)]
+pub struct PreviewRenderer {
+    blocks: HashMap<u64, Block>,
+    lines: Vec<PreviewLine>,
+    /// Footnote definition lines of each block, in block order, shown in a
+    /// section after the last block.
+    footnotes: Vec<Vec<PreviewLine>>,
+    tail: Option<Tail>,
+    front_matter: FrontMatterStyle,
+    /// Links and images keep their Markdown source; off means reader mode.
+    literal: bool,
+    #[cfg(test)]
+    renders: usize,
+    #[cfg(test)]
+    parsed_bytes: usize,
+}
+
+/// What one block renders to. Source lines count from the block's first
+/// line, so a block that moves can be reused as is.
+#[derive(Debug, Clone)]
+struct Block {
+    lines: Vec<PreviewLine>,
+    footnotes: Vec<PreviewLine>,
+}
+
+/// The last block of the previous input, where [`PreviewRenderer::render_appended`]
+/// starts parsing again.
+#[derive(Debug)]
+struct Tail {
+    width: u16,
+    /// Length of the previous input.
+    len: usize,
+    /// Start of the line the last block begins on.
+    start: usize,
+    /// Source line number of `start`.
+    start_line: usize,
+    /// Index of the last block's first line in `lines`.
+    line: usize,
+    /// Index of the last block in `footnotes`.
+    block: usize,
+    key: Option<u64>,
+    definitions: String,
+    footnotes: Vec<String>,
+}
+
+impl PreviewRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show front matter folded or expanded from the next render on.
+    pub fn set_front_matter(&mut self, style: FrontMatterStyle) {
+        if style != self.front_matter {
+            self.front_matter = style;
+            self.blocks.clear();
+            self.tail = None;
+        }
+    }
+
+    /// Show links as their text and images as `🖼 alt` (the default), or
+    /// both as their Markdown source, from the next render on.
+    pub fn set_reader(&mut self, on: bool) {
+        if on == self.literal {
+            self.literal = !on;
+            self.blocks.clear();
+            self.tail = None;
+        }
+    }
+
+    fn flags(&self) -> RenderFlags {
+        RenderFlags {
+            emoji: true,
+            front_matter: self.front_matter,
+            reader: !self.literal,
+            ..RenderFlags::default()
+        }
+    }
+
+    pub fn render(&mut self, markdown: &str, width: u16) -> &[PreviewLine] {
+        let (ranges, definitions, footnotes) = self.split_blocks(markdown, true);
+        let mut previous = std::mem::take(&mut self.blocks);
+        self.lines.clear();
+        self.footnotes.clear();
+
+        let mut tail = Tail {
+            width,
+            len: markdown.len(),
+            start: 0,
+            start_line: 0,
+            line: 0,
+            block: 0,
+            key: None,
+            definitions,
+            footnotes,
+        };
+        self.push_blocks(markdown, ranges, &mut previous, &mut tail);
+        self.tail = Some(tail);
+        &self.lines
+    }
+
+    /// Render `markdown`, which must be the input of the previous call with
+    /// text appended. Only the previous last block and what follows it are
+    /// parsed again, so a growing stream costs the size of each chunk rather
+    /// than the whole document.
+    ///
+    /// Returns how many leading lines are unchanged since the previous call,
+    /// along with all lines. Falls back to [`render`](Self::render), keeping
+    /// no lines, when the width changed, the text got shorter or the new text
+    /// defines link references or footnotes that earlier blocks may use.
+    pub fn render_appended(&mut self, markdown: &str, width: u16) -> (usize, &[PreviewLine]) {
+        let Some(mut tail) = self
+            .tail
+            .take()
+            .filter(|tail| tail.width == width && tail.len <= markdown.len())
+        else {
+            return (0, self.render(markdown, width));
+        };
+        let (ranges, definitions, footnotes) =
+            self.split_blocks(&markdown[tail.start..], tail.start == 0);
+        if (!definitions.is_empty() && !tail.definitions.ends_with(&definitions))
+            || !tail.footnotes.ends_with(&footnotes)
+        {
+            return (0, self.render(markdown, width));
+        }
+
+        let kept = tail.line;
+        self.lines.truncate(kept);
+        self.footnotes.truncate(tail.block);
+        // The old last block is usually about to grow; drop it unless the
+        // new text left it as it was.
+        let mut previous: HashMap<_, _> = tail
+            .key
+            .and_then(|key| self.blocks.remove_entry(&key))
+            .into_iter()
+            .collect();
+        let offset = tail.start;
+        let ranges = ranges
+            .into_iter()
+            .map(|range| range.start + offset..range.end + offset)
+            .collect();
+        tail.len = markdown.len();
+        self.push_blocks(markdown, ranges, &mut previous, &mut tail);
+        self.tail = Some(tail);
+        (kept, &self.lines)
+    }
+
+    /// Append the lines of each block in `ranges`, reusing renders from
+    /// `blocks` or `previous`, and leave `tail` on the last block. The
+    /// footnotes section follows.
+    fn push_blocks(
+        &mut self,
+        markdown: &str,
+        ranges: Vec<Range<usize>>,
+        previous: &mut HashMap<u64, Block>,
+        tail: &mut Tail,
+    ) {
+        let (definitions, footnotes, width, flags) = (
+            tail.definitions.as_str(),
+            tail.footnotes.as_slice(),
+            tail.width,
+            self.flags(),
+        );
+        let (mut counted_to, mut line) = (tail.start, tail.start_line);
+        for range in ranges {
+            let source = &markdown[range.clone()];
+            let key = block_key(source, definitions, footnotes, width);
+            line += markdown[counted_to..range.start].matches('\n').count();
+            counted_to = range.start;
+            tail.start = markdown[..range.start].rfind('\n').map_or(0, |idx| idx + 1);
+            tail.start_line = line;
+            tail.line = self.lines.len();
+            tail.block = self.footnotes.len();
+            tail.key = Some(key);
+            let block = self.blocks.entry(key).or_insert_with(|| {
+                previous.remove(&key).unwrap_or_else(|| {
+                    #[cfg(test)]
+                    {
+                        self.renders += 1;
+                    }
+                    render_block(source, definitions, footnotes, width, flags)
+                })
+            });
+            self.lines.extend(shifted(&block.lines, line));
+            self.footnotes
+                .push(shifted(&block.footnotes, line).collect());
+        }
+
+        if let Some(first) = self.footnotes.iter().flatten().next() {
+            self.lines
+                .extend(footnotes_heading_segments(width, first.source_line));
+            self.lines.extend(self.footnotes.iter().flatten().cloned());
+        } else if self.lines.is_empty() {
+            self.lines.push(PreviewLine::new("", SegmentKind::Plain));
+        }
+    }
+
+    fn split_blocks(
+        &mut self,
+        markdown: &str,
+        at_start: bool,
+    ) -> (Vec<Range<usize>>, String, Vec<String>) {
+        #[cfg(test)]
+        {
+            self.parsed_bytes += markdown.len();
+        }
+        split_blocks(markdown, at_start)
+    }
+}
+
+/// Byte ranges of the top-level blocks, plus the source of every link
+/// reference definition and the lowercased name of every footnote, both in
+/// document order.
+///
+/// Reference definitions produce no events of their own but can be used from
+/// any block, so each block is rendered with all of them in front of it.
+/// Footnote definitions render into their own section instead, so blocks
+/// only get their names, to tell references from undefined ones.
+///
+/// Front matter is the first block when `markdown` starts the document.
+fn split_blocks(markdown: &str, at_start: bool) -> (Vec<Range<usize>>, String, Vec<String>) {
+    let front_end = front_matter(markdown)
+        .filter(|_| at_start)
+        .map(|front| front.end);
+    let parsed = match front_end {
+        Some(_) => blank_front_matter(markdown),
+        None => markdown.into(),
+    };
+    let mut iter = Parser::new_ext(&parsed, parser_options()).into_offset_iter();
+    let mut spans: Vec<Range<usize>> = iter
+        .reference_definitions()
+        .iter()
+        .map(|(_, def)| def.span.clone())
+        .collect();
+    spans.sort_by_key(|span| span.start);
+    let mut definitions = String::new();
+    for span in spans {
+        definitions.push_str(markdown[span].trim_end());
+        definitions.push('\n');
+    }
+
+    let mut ranges: Vec<Range<usize>> = front_end.map(|end| 0..end).into_iter().collect();
+    let mut footnotes = Vec::new();
+    let mut depth = 0usize;
+    for (event, range) in iter.by_ref() {
+        if let Event::Start(Tag::FootnoteDefinition(name)) = &event {
+            footnotes.push(name.to_lowercase());
+        }
+        match event {
+            Event::Start(_) => {
+                if depth == 0 {
+                    ranges.push(range);
+                }
+                depth += 1;
+            }
+            Event::End(_) => depth = depth.saturating_sub(1),
+            _ if depth == 0 => ranges.push(range),
+            _ => {}
+        }
+    }
+    (ranges, definitions, footnotes)
+}
+
+fn block_key(source: &str, definitions: &str, footnotes: &[String], width: u16) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    definitions.hash(&mut hasher);
+    footnotes.hash(&mut hasher);
+    width.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn render_block(
+    source: &str,
+    definitions: &str,
+    footnotes: &[String],
+    width: u16,
+    flags: RenderFlags,
+) -> Block {
+    // Front matter has no links, and must stay at the start to be found.
+    if definitions.is_empty() || front_matter(source).is_some() {
+        let (lines, footnotes) = render_block_segments(source, width, flags, footnotes);
+        return Block { lines, footnotes };
+    }
+    let (mut lines, mut footnotes) =
+        render_block_segments(&format!("{definitions}\n{source}"), width, flags, footnotes);
+    // The definitions in front render nothing but still take up lines.
+    let prefix = definitions.matches('\n').count() + 1;
+    for line in lines.iter_mut().chain(footnotes.iter_mut()) {
+        line.source_line = line.source_line.saturating_sub(prefix);
+    }
+    Block { lines, footnotes }
+}
+
+/// Block lines moved down to a block starting on source line `first`.
+fn shifted(lines: &[PreviewLine], first: usize) -> impl Iterator<Item = PreviewLine> + '_ {
+    lines.iter().map(move |line| PreviewLine {
+        source_line: line.source_line + first,
+        ..line.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PreviewRenderer;
+    use crate::{FrontMatterStyle, RenderOptions, render, render_preview_segments};
+
+    const DOC: &str = "# Title\n\nIntro paragraph with *emphasis* and a [link](https://x.dev).\n\n\
+        - one\n- two\n  - nested\n\n> quoted\n> text\n\n```rust\nfn main() {}\n```\n\n\
+        | a | b |\n|---|---|\n| 1 | 2 |\n\n---\n\n1. first\n2. second\n\n$$\nx^2\n$$\n";
+
+    fn assert_matches_full_render(src: &str, width: u16) {
+        let mut renderer = PreviewRenderer::new();
+        assert_eq!(
+            renderer.render(src, width),
+            render_preview_segments(src, width)
+        );
+    }
+
+    #[test]
+    fn matches_a_full_render() {
+        assert_matches_full_render(DOC, 80);
+        assert_matches_full_render(DOC, 12);
+        assert_matches_full_render("", 80);
+        assert_matches_full_render("\n\n", 80);
+        assert_matches_full_render("plain\ntext\n\n<div>\nhtml\n</div>\n", 80);
+    }
+
+    #[test]
+    fn front_matter_is_its_own_block_in_either_style() {
+        let src = "---\ntitle: Notes\n\ntags: [a]\n---\n[x]: https://x.dev\n\n# Body [x]\n";
+        assert_matches_full_render(src, 80);
+
+        let mut renderer = PreviewRenderer::new();
+        assert_eq!(
+            renderer.render(src, 80)[0].text(),
+            "--- front matter: 2 keys ---"
+        );
+        renderer.set_front_matter(FrontMatterStyle::Expanded);
+        let expanded = RenderOptions::new(80)
+            .with_emoji(true)
+            .with_reader(true)
+            .with_front_matter(FrontMatterStyle::Expanded);
+        let full: Vec<_> = render(src, &expanded)
+            .lines
+            .into_iter()
+            .map(|line| (line.text, line.source_line))
+            .collect();
+        let lines: Vec<_> = renderer
+            .render(src, 80)
+            .iter()
+            .map(|line| (line.text(), line.source_line))
+            .collect();
+        assert_eq!(lines, full);
+        assert_eq!(lines[4], ("---".to_string(), 4));
+
+        // Text appended after a rule is never taken for front matter.
+        let mut renderer = PreviewRenderer::new();
+        renderer.render("---\n", 80);
+        let (_, lines) = renderer.render_appended("---\ntitle: x\n---\n", 80);
+        assert_eq!(lines, render_preview_segments("---\ntitle: x\n---\n", 80));
+    }
+
+    #[test]
+    fn editing_one_block_rerenders_only_that_block() {
+        let mut renderer = PreviewRenderer::new();
+        renderer.render(DOC, 80);
+        let first = renderer.renders;
+        assert_eq!(first, 9);
+
+        renderer.render(DOC, 80);
+        assert_eq!(renderer.renders, first);
+
+        let edited = DOC.replace("fn main() {}", "fn main() { run(); }");
+        assert_eq!(
+            renderer.render(&edited, 80),
+            render_preview_segments(&edited, 80)
+        );
+        assert_eq!(renderer.renders, first + 1);
+
+        renderer.render(&edited, 40);
+        assert_eq!(renderer.renders, 2 * first + 1);
+    }
+
+    #[test]
+    fn reused_blocks_follow_their_source_lines() {
+        let mut renderer = PreviewRenderer::new();
+        renderer.render(DOC, 80);
+        let first = renderer.renders;
+
+        let moved = format!("Preface\nover two lines.\n\n{DOC}");
+        let lines = renderer.render(&moved, 80).to_vec();
+        assert_eq!(renderer.renders, first + 1);
+        assert_eq!(lines, render_preview_segments(&moved, 80));
+        let title = lines.iter().find(|line| line.text() == "# Title");
+        assert_eq!(title.map(|line| line.source_line), Some(3));
+    }
+
+    #[test]
+    fn appended_text_renders_like_the_full_document() {
+        for split in 0..=DOC.len() {
+            let mut renderer = PreviewRenderer::new();
+            let before = renderer.render(&DOC[..split], 80).to_vec();
+            let (kept, lines) = renderer.render_appended(DOC, 80);
+            assert_eq!(lines, render_preview_segments(DOC, 80), "split at {split}");
+            assert_eq!(lines[..kept], before[..kept]);
+        }
+
+        let mut renderer = PreviewRenderer::new();
+        renderer.render("", 80);
+        for end in (7..DOC.len()).step_by(7).chain([DOC.len()]) {
+            let (_, lines) = renderer.render_appended(&DOC[..end], 80);
+            assert_eq!(lines, render_preview_segments(&DOC[..end], 80));
+        }
+    }
+
+    #[test]
+    fn appended_reference_definitions_render_everything_again() {
+        let mut renderer = PreviewRenderer::new();
+        renderer.render("See [docs].\n\nMore text.\n", 80);
+        let src = "See [docs].\n\nMore text.\n\n[docs]: https://docs.dev\n";
+        let (kept, lines) = renderer.render_appended(src, 80);
+        assert_eq!(kept, 0);
+        assert_eq!(lines, render_preview_segments(src, 80));
+
+        let (kept, _) = renderer.render_appended(src, 40);
+        assert_eq!(kept, 0);
+    }
+
+    #[test]
+    fn streaming_parses_each_chunk_about_once() {
+        let mut doc = String::new();
+        let mut item = 0;
+        while doc.len() < 5 * 1024 * 1024 {
+            item += 1;
+            doc.push_str(&format!(
+                "## Entry {item}\n\nSome *streamed* text for entry {item}, long enough to wrap \
+                 across a couple of preview lines at this width.\n\n- one\n- two\n\n"
+            ));
+        }
+
+        let mut renderer = PreviewRenderer::new();
+        renderer.render("", 80);
+        let mut chunks = 0;
+        for end in (1024..doc.len()).step_by(1024).chain([doc.len()]) {
+            let (kept, lines) = renderer.render_appended(&doc[..end], 80);
+            assert!(lines.len() - kept < 80);
+            chunks += 1;
+        }
+        assert!(renderer.parsed_bytes < 2 * doc.len());
+        assert!(renderer.renders < 4 * item + 2 * chunks);
+    }
+
+    #[test]
+    fn reference_links_resolve_across_blocks() {
+        let src = "See [the docs][docs].\n\n# Heading\n\n[docs]: https://docs.dev \"Docs\"\n";
+        assert_matches_full_render(src, 80);
+
+        // Reader mode hides the destination, so look at the literal links.
+        let mut renderer = PreviewRenderer::new();
+        renderer.set_reader(false);
+        let before = renderer.render(src, 80).to_vec();
+        let changed = src.replace("https://docs.dev", "https://other.dev");
+        let after = renderer.render(&changed, 80).to_vec();
+        assert_ne!(before, after);
+        assert_eq!(after[0].text(), "See [the docs](https://other.dev).");
+    }
+
+    #[test]
+    fn footnotes_render_like_the_full_document() {
+        let src = "Claim[^1] here[^x].\n\n## Notes\n\n[^1]: The source.\n\n    More.\n\nEnd.\n";
+        assert_matches_full_render(src, 80);
+        assert_matches_full_render(src, 12);
+
+        // A definition arriving later turns an earlier reference from
+        // missing into a match.
+        let mut renderer = PreviewRenderer::new();
+        renderer.render_appended("See[^late].\n\n", 80);
+        let (kept, lines) = renderer.render_appended("See[^late].\n\n[^late]: Here.\n", 80);
+        assert_eq!(kept, 0);
+        assert_eq!(
+            lines,
+            render_preview_segments("See[^late].\n\n[^late]: Here.\n", 80)
+        );
+        let (kept, lines) = renderer.render_appended("See[^late].\n\n[^late]: Here.\n\nMore\n", 80);
+        assert_eq!(kept, 1);
+        assert_eq!(
+            lines,
+            render_preview_segments("See[^late].\n\n[^late]: Here.\n\nMore\n", 80)
+        );
+    }
+
+    #[test]
+    fn repeated_blocks_share_one_render() {
+        let mut renderer = PreviewRenderer::new();
+        let src = "same\n\nsame\n\nsame\n";
+        let texts: Vec<_> = renderer
+            .render(src, 80)
+            .iter()
+            .map(|line| line.text())
+            .collect();
+        assert_eq!(texts, ["same", "same", "same"]);
+        assert_eq!(renderer.renders, 1);
+    }
+}
@@ -1,9 +1,82 @@
+//! Markdown rendering and editing for the `mdv` terminal viewer.
+//!
+//! To embed the renderer, call [`render`] with [`RenderOptions`]: it returns
+//! a [`RenderedDoc`] whose lines carry their text, styled segments tagged
+//! with a [`SegmentKind`], and the source line each came from.
+//!
+//! ```
+//! use mdv_core::{RenderOptions, render};
+//!
+//! let doc = render("- [x] ship it", &RenderOptions::new(60));
+//! for line in &doc.lines {
+//!     for segment in &line.segments {
+//!         // Map `segment.kind` to your own colors here.
+//!         print!("{}", segment.text);
+//!     }
+//!     println!();
+//! }
+//! ```
+//!
+//! # Features
+//!
+//! `fs` (on by default) adds `read_text`, `EditorBuffer::save_to_path` and
+//! `SaveOutcome`. Without it the crate does no file IO and builds for
+//! `wasm32-unknown-unknown`; see `examples/render_lines.rs`.
+//!
+//! # Stability
+//!
+//! [`render`], [`RenderOptions`], [`WrapMode`], [`FrontMatterStyle`],
+//! [`RenderedDoc`], [`RenderedLine`], [`PreviewSegment`] and [`SegmentKind`]
+//! follow semver: output for the same input and options only changes in
+//! minor releases, and new options, fields and segment kinds are added in
+//! minor releases (the types are `#[non_exhaustive]`). Everything else is
+//! used by the `mdv` CLI and may change in any release.
+
 pub mod conflict_diff;
 pub mod editor;
+pub mod emoji;
+pub mod front_matter;
+pub mod html;
+pub mod incremental;
+pub mod links;
+pub mod lint;
+pub mod lists;
+pub mod load;
 pub mod markdown;
+pub mod outline;
+pub mod render;
+pub mod save;
+pub mod spell;
+pub mod stats;
+pub mod streaming;
 
-pub use conflict_diff::{ConflictHunk, compute_conflict_hunks};
-pub use editor::{ConflictState, EditorBuffer};
+pub use conflict_diff::{
+    ConflictHunk, DEFAULT_HUNK_CONTEXT, HunkKind, MERGE_GAP, changed_span, compute_conflict_hunks,
+    compute_conflict_hunks_with_context, render_hunk_markers,
+};
+pub use editor::{ConflictState, EditorBuffer, SearchOptions, match_ranges_in};
+pub use emoji::{emoji_for, replace_shortcodes};
+pub use front_matter::{FrontMatter, FrontMatterKind, front_matter};
+pub use html::render_html;
+pub use incremental::PreviewRenderer;
+pub use links::{
+    LinkRef, ReferenceKind, ReferenceUse, References, collect_images, collect_links,
+    collect_references, link_at,
+};
+pub use lint::{LintIssue, LintKind, lint, lint_bytes};
+pub use lists::renumber_ordered_list;
+#[cfg(feature = "fs")]
+pub use load::read_text;
+pub use load::{LineEnding, LoadedText, decode_text};
 pub use markdown::{
-    PreviewLine, PreviewSegment, SegmentKind, render_preview_lines, render_preview_segments,
+    PreviewLine, PreviewSegment, SegmentKind, render_preview_lines, render_preview_lines_bounded,
+    render_preview_segments, unfenced_line_start,
 };
+pub use outline::{OutlineEntry, extract_outline, heading_slug};
+pub use render::{FrontMatterStyle, RenderOptions, RenderedDoc, RenderedLine, WrapMode, render};
+#[cfg(feature = "fs")]
+pub use save::SaveOutcome;
+pub use save::{SavePolicy, normalize_for_save};
+pub use spell::{SpellChecker, prose_words};
+pub use stats::word_count;
+pub use streaming::StreamRenderer;
@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use pulldown_cmark::{BrokenLink, CowStr, Event, LinkType, Parser, Tag, TagEnd};
+
+use crate::markdown::parser_options;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkRef {
+    /// Link text as rendered (an image inside gives its alt text), or the
+    /// destination when there is no text.
+    pub text: String,
+    /// Where the link points; reference links are resolved and email
+    /// autolinks get a `mailto:` prefix.
+    pub dest: String,
+    /// Zero-based line in the source where the link starts.
+    pub source_line: usize,
+}
+
+/// Links in document order, for opening them from the viewer.
+pub fn collect_links(markdown: &str) -> Vec<LinkRef> {
+    collect(markdown, false)
+        .into_iter()
+        .map(|(link, _)| link)
+        .collect()
+}
+
+/// Images in document order, with their alt text, for showing them in
+/// terminals that can draw pictures.
+pub fn collect_images(markdown: &str) -> Vec<LinkRef> {
+    collect(markdown, true)
+        .into_iter()
+        .map(|(link, _)| link)
+        .collect()
+}
+
+/// The link or image whose source covers byte `offset`, the innermost one
+/// when an image sits inside a link. Reader mode shows only link text, so
+/// this is where the destination can still be seen.
+pub fn link_at(markdown: &str, offset: usize) -> Option<LinkRef> {
+    collect(markdown, false)
+        .into_iter()
+        .chain(collect(markdown, true))
+        .filter(|(_, range)| range.contains(&offset))
+        .min_by_key(|(_, range)| range.len())
+        .map(|(link, _)| link)
+}
+
+/// Whether a reference label names a link definition or a footnote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReferenceKind {
+    Link,
+    Footnote,
+}
+
+/// A reference link (`[text][label]`, `[label][]` or `[label]`) or a
+/// footnote reference (`[^label]`) in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferenceUse {
+    pub kind: ReferenceKind,
+    /// The label as written.
+    pub label: String,
+    /// Byte range of the whole reference in the source.
+    pub range: Range<usize>,
+}
+
+/// The reference definitions of a document and the places that use them.
+#[derive(Debug, Clone, Default)]
+pub struct References {
+    /// Zero-based source line of each definition, by normalized label.
+    definitions: HashMap<(ReferenceKind, String), usize>,
+    /// In document order; uses without a definition are included.
+    pub uses: Vec<ReferenceUse>,
+}
+
+impl References {
+    /// The innermost reference whose source covers byte `offset`.
+    pub fn use_at(&self, offset: usize) -> Option<&ReferenceUse> {
+        self.uses
+            .iter()
+            .filter(|reference| reference.range.contains(&offset))
+            .min_by_key(|reference| reference.range.len())
+    }
+
+    /// Zero-based line of the definition `reference` points to.
+    pub fn definition_line(&self, reference: &ReferenceUse) -> Option<usize> {
+        self.definitions
+            .get(&(reference.kind, normalize_label(&reference.label)))
+            .copied()
+    }
+}
+
+/// Reference definitions and the links and footnote references that use
+/// them, for jumping from one to the other.
+pub fn collect_references(markdown: &str) -> References {
+    let mut uses = Vec::new();
+    // An explicit `[text][label]` or `[label][]` with no definition still
+    // counts as a use; a bare `[word]` is just text.
+    let callback = |link: BrokenLink<'_>| -> Option<(CowStr<'_>, CowStr<'_>)> {
+        if matches!(link.link_type, LinkType::Reference | LinkType::Collapsed) {
+            uses.push(ReferenceUse {
+                kind: ReferenceKind::Link,
+                label: link.reference.to_string(),
+                range: link.span,
+            });
+        }
+        None
+    };
+    let parser = Parser::new_with_broken_link_callback(markdown, parser_options(), Some(callback));
+    let mut definitions = HashMap::new();
+    let line_of = |offset: usize| markdown[..offset].matches('\n').count();
+    for (label, definition) in parser.reference_definitions().iter() {
+        definitions.insert(
+            (ReferenceKind::Link, normalize_label(label)),
+            line_of(definition.span.start),
+        );
+    }
+    let mut found = Vec::new();
+    // Code, and then references already found, where a bare `[^label]`
+    // does not count.
+    let mut taken = Vec::new();
+    for (event, range) in parser.into_offset_iter() {
+        match event {
+            Event::Code(_) | Event::Start(Tag::CodeBlock(_)) => taken.push(range),
+            Event::Start(Tag::Link {
+                link_type: LinkType::Reference | LinkType::Collapsed | LinkType::Shortcut,
+                id,
+                ..
+            }) => found.push(ReferenceUse {
+                kind: ReferenceKind::Link,
+                label: id.to_string(),
+                range,
+            }),
+            Event::FootnoteReference(label) => found.push(ReferenceUse {
+                kind: ReferenceKind::Footnote,
+                label: label.to_string(),
+                range,
+            }),
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                definitions
+                    .entry((ReferenceKind::Footnote, normalize_label(&label)))
+                    .or_insert_with(|| line_of(range.start));
+            }
+            _ => {}
+        }
+    }
+    uses.extend(found);
+    // The parser leaves a footnote reference with no definition as text.
+    taken.extend(uses.iter().map(|used| used.range.clone()));
+    for range in bare_footnotes(markdown) {
+        if !taken.iter().any(|other| other.contains(&range.start)) {
+            uses.push(ReferenceUse {
+                kind: ReferenceKind::Footnote,
+                label: markdown[range.start + 2..range.end - 1].to_string(),
+                range,
+            });
+        }
+    }
+    uses.sort_by_key(|reference| reference.range.start);
+    References { definitions, uses }
+}
+
+/// Every `[^label]` in `markdown` that is not a definition.
+fn bare_footnotes(markdown: &str) -> Vec<Range<usize>> {
+    markdown
+        .match_indices("[^")
+        .filter_map(|(start, _)| {
+            let rest = &markdown[start + 2..];
+            let len = rest.find(|c: char| c == ']' || c == '[' || c.is_whitespace())?;
+            let end = start + 2 + len + 1;
+            (len > 0 && rest[len..].starts_with(']') && !markdown[end..].starts_with(':'))
+                .then_some(start..end)
+        })
+        .collect()
+}
+
+/// Labels match case-insensitively with runs of whitespace as one space, as
+/// CommonMark has it.
+fn normalize_label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Links or images with the byte range of each one's source.
+fn collect(markdown: &str, images: bool) -> Vec<(LinkRef, Range<usize>)> {
+    let mut links = Vec::new();
+    let mut current: Option<(LinkRef, Range<usize>)> = None;
+    let mut line = 0;
+    let mut counted_to = 0;
+
+    for (event, range) in Parser::new_ext(markdown, parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(
+                ref tag @ (Tag::Link {
+                    link_type,
+                    ref dest_url,
+                    ..
+                }
+                | Tag::Image {
+                    link_type,
+                    ref dest_url,
+                    ..
+                }),
+            ) if matches!(tag, Tag::Image { .. }) == images => {
+                line += markdown[counted_to..range.start].matches('\n').count();
+                counted_to = range.start;
+                let dest = if link_type == LinkType::Email {
+                    format!("mailto:{dest_url}")
+                } else {
+                    dest_url.to_string()
+                };
+                current = Some((
+                    LinkRef {
+                        text: String::new(),
+                        dest,
+                        source_line: line,
+                    },
+                    range,
+                ));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((link, _)) = current.as_mut() {
+                    link.text.push_str(&text);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some((link, _)) = current.as_mut() {
+                    link.text.push(' ');
+                }
+            }
+            Event::End(end @ (TagEnd::Link | TagEnd::Image))
+                if (end == TagEnd::Image) == images =>
+            {
+                if let Some((mut link, range)) = current.take() {
+                    link.text = link.text.trim().to_string();
+                    if link.text.is_empty() {
+                        link.text.clone_from(&link.dest);
+                    }
+                    links.push((link, range));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        LinkRef, ReferenceKind, collect_images, collect_links, collect_references, link_at,
+    };
+
+    fn link(text: &str, dest: &str, source_line: usize) -> LinkRef {
+        LinkRef {
+            text: text.into(),
+            dest: dest.into(),
+            source_line,
+        }
+    }
+
+    #[test]
+    fn collects_links_with_their_lines() {
+        let src = "# [Home](index.md)\n\nSee [the *guide*](docs/guide.md#setup) and\n\
+                   <https://example.com> or <me@example.com>.\n\n\
+                   - [ref][r]\n\n```\n[not](a-link)\n```\n\n[r]: https://ref.example\n";
+        assert_eq!(
+            collect_links(src),
+            vec![
+                link("Home", "index.md", 0),
+                link("the guide", "docs/guide.md#setup", 2),
+                link("https://example.com", "https://example.com", 3),
+                link("me@example.com", "mailto:me@example.com", 3),
+                link("ref", "https://ref.example", 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn links_without_text_use_the_destination() {
+        let src = "intro\n[](https://example.com) [![logo](logo.png)](https://logo.example)\n";
+        assert_eq!(
+            collect_links(src),
+            vec![
+                link("https://example.com", "https://example.com", 1),
+                link("logo", "https://logo.example", 1),
+            ]
+        );
+        assert!(collect_links("no links here").is_empty());
+    }
+
+    #[test]
+    fn images_are_collected_apart_from_links() {
+        let src = "# Shots\n\n![Main window](shots/main.png)\n\n\
+                   [![](logo.svg)](https://logo.example) ![remote](https://x.example/a.gif)\n";
+        assert_eq!(
+            collect_images(src),
+            vec![
+                link("Main window", "shots/main.png", 2),
+                link("logo.svg", "logo.svg", 4),
+                link("remote", "https://x.example/a.gif", 4),
+            ]
+        );
+        assert_eq!(
+            collect_links(src),
+            vec![link("https://logo.example", "https://logo.example", 4)]
+        );
+    }
+
+    #[test]
+    fn link_at_finds_the_innermost_link_under_an_offset() {
+        let src = "See [docs](https://docs.dev) and [![logo](logo.png)](https://logo.dev).\n";
+        let at = |needle: &str| link_at(src, src.find(needle).expect("needle"));
+        assert_eq!(
+            at("docs]").map(|link| link.dest),
+            Some("https://docs.dev".into())
+        );
+        assert_eq!(
+            at("(https://docs").map(|link| link.dest),
+            Some("https://docs.dev".into())
+        );
+        assert_eq!(at("logo]").map(|link| link.dest), Some("logo.png".into()));
+        assert_eq!(
+            at("(https://logo").map(|link| link.dest),
+            Some("https://logo.dev".into())
+        );
+        assert_eq!(at("See"), None);
+        assert_eq!(at(" and"), None);
+    }
+
+    #[test]
+    fn references_resolve_every_form_to_their_definition_line() {
+        let src = "Read [the spec][RFC], [rfc][] or [Rfc] and [gone][nope].\n\
+                   A note[^n] and [^missing], not `[^code]`.\n\n\
+                   [rfc]: https://rfc.example\n\n\
+                   [^N]: The note.\n";
+        let refs = collect_references(src);
+        let at = |needle: &str| refs.use_at(src.find(needle).expect("needle"));
+        for needle in ["the spec", "rfc][]", "Rfc]"] {
+            let reference = at(needle).expect(needle);
+            assert_eq!(reference.kind, ReferenceKind::Link);
+            assert_eq!(refs.definition_line(reference), Some(3), "{needle}");
+        }
+        let full = at("RFC]").expect("label");
+        assert_eq!(full.label, "RFC");
+
+        let broken = at("gone").expect("broken");
+        assert_eq!(broken.label, "nope");
+        assert_eq!(refs.definition_line(broken), None);
+
+        let note = at("^n]").expect("footnote");
+        assert_eq!(note.kind, ReferenceKind::Footnote);
+        assert_eq!(refs.definition_line(note), Some(5));
+        let missing = at("^missing").expect("missing footnote");
+        assert_eq!(missing.label, "missing");
+        assert_eq!(refs.definition_line(missing), None);
+        assert_eq!(at("^code"), None);
+        assert_eq!(at("Read"), None);
+        assert_eq!(at("https://rfc"), None);
+    }
+}
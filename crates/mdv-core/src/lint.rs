@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+
+use pulldown_cmark::{BrokenLink, CowStr, Event, LinkType, Parser, Tag};
+
+use crate::markdown::{FenceLine, FenceTracker, parser_options};
+use crate::outline::{extract_outline, heading_slug};
+
+/// The structural problems [`lint`] looks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintKind {
+    /// A code fence that is never closed, swallowing the rest of the file.
+    UnclosedFence,
+    /// `[text][ref]` or `[ref][]` with no `[ref]: url` anywhere.
+    MissingReference,
+    /// Two headings that GitHub would give the same `#anchor`.
+    DuplicateAnchor,
+    /// A table row with more or fewer cells than the header.
+    TableColumns,
+    /// Bytes that are not UTF-8, found by [`lint_bytes`].
+    InvalidUtf8,
+}
+
+impl LintKind {
+    pub const ALL: [LintKind; 5] = [
+        LintKind::UnclosedFence,
+        LintKind::MissingReference,
+        LintKind::DuplicateAnchor,
+        LintKind::TableColumns,
+        LintKind::InvalidUtf8,
+    ];
+
+    /// Short name used to pick checks, such as `--check=fences,links`.
+    pub fn name(self) -> &'static str {
+        match self {
+            LintKind::UnclosedFence => "fences",
+            LintKind::MissingReference => "links",
+            LintKind::DuplicateAnchor => "anchors",
+            LintKind::TableColumns => "tables",
+            LintKind::InvalidUtf8 => "utf8",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintIssue {
+    /// One-based source line, as editors and `file:line` output count.
+    pub line: usize,
+    pub kind: LintKind,
+    pub message: String,
+}
+
+/// Structural problems in `markdown`, ordered by line.
+pub fn lint(markdown: &str) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    unclosed_fences(markdown, &mut issues);
+    missing_references(markdown, &mut issues);
+    duplicate_anchors(markdown, &mut issues);
+    table_columns(markdown, &mut issues);
+    issues.sort_by_key(|issue| issue.line);
+    issues
+}
+
+/// [`lint`] for raw file contents, adding one issue per line holding bytes
+/// that are not UTF-8; the rest is checked with those bytes replaced.
+pub fn lint_bytes(bytes: &[u8]) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    let mut offset = 0;
+    while let Err(err) = std::str::from_utf8(&bytes[offset..]) {
+        let at = offset + err.valid_up_to();
+        let line = line_at(bytes, at);
+        if issues
+            .last()
+            .is_none_or(|issue: &LintIssue| issue.line != line)
+        {
+            issues.push(LintIssue {
+                line,
+                kind: LintKind::InvalidUtf8,
+                message: "invalid UTF-8".into(),
+            });
+        }
+        offset = at + err.error_len().unwrap_or(bytes.len() - at);
+    }
+    issues.extend(lint(&String::from_utf8_lossy(bytes)));
+    issues.sort_by_key(|issue| issue.line);
+    issues
+}
+
+fn line_at(text: &[u8], offset: usize) -> usize {
+    text[..offset].iter().filter(|&&byte| byte == b'\n').count() + 1
+}
+
+fn unclosed_fences(markdown: &str, issues: &mut Vec<LintIssue>) {
+    let mut fences = FenceTracker::default();
+    let mut opened = None;
+    for (index, line) in markdown.lines().enumerate() {
+        match fences.line(line) {
+            FenceLine::Open => opened = Some(index + 1),
+            FenceLine::Close => opened = None,
+            FenceLine::Inside | FenceLine::Outside => {}
+        }
+    }
+    if let Some(line) = opened {
+        issues.push(LintIssue {
+            line,
+            kind: LintKind::UnclosedFence,
+            message: "code block opened here is never closed".into(),
+        });
+    }
+}
+
+fn missing_references(markdown: &str, issues: &mut Vec<LintIssue>) {
+    let mut broken = Vec::new();
+    let callback = |link: BrokenLink<'_>| -> Option<(CowStr<'_>, CowStr<'_>)> {
+        // A bare `[word]` is just text; only explicit references count.
+        if matches!(link.link_type, LinkType::Reference | LinkType::Collapsed) {
+            broken.push((link.span.start, link.reference.to_string()));
+        }
+        None
+    };
+    Parser::new_with_broken_link_callback(markdown, parser_options(), Some(callback))
+        .for_each(drop);
+    for (offset, reference) in broken {
+        issues.push(LintIssue {
+            line: line_at(markdown.as_bytes(), offset),
+            kind: LintKind::MissingReference,
+            message: format!("no definition for reference link [{reference}]"),
+        });
+    }
+}
+
+fn duplicate_anchors(markdown: &str, issues: &mut Vec<LintIssue>) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for entry in extract_outline(markdown) {
+        let slug = heading_slug(&entry.text);
+        let line = entry.source_line + 1;
+        match seen.get(&slug) {
+            Some(first) => issues.push(LintIssue {
+                line,
+                kind: LintKind::DuplicateAnchor,
+                message: format!("heading anchor #{slug} is already used on line {first}"),
+            }),
+            None => {
+                seen.insert(slug, line);
+            }
+        }
+    }
+}
+
+fn table_columns(markdown: &str, issues: &mut Vec<LintIssue>) {
+    let mut columns = 0;
+    for (event, range) in Parser::new_ext(markdown, parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Table(alignments)) => columns = alignments.len(),
+            Event::Start(Tag::TableHead | Tag::TableRow) => {
+                let row = markdown[range.start..].lines().next().unwrap_or_default();
+                let cells = cell_count(row);
+                if cells != columns {
+                    issues.push(LintIssue {
+                        line: line_at(markdown.as_bytes(), range.start),
+                        kind: LintKind::TableColumns,
+                        message: format!(
+                            "table row has {cells} cell{}, the header has {columns}",
+                            if cells == 1 { "" } else { "s" }
+                        ),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Cells in a source table row: unescaped pipes split it, and a pipe at
+/// either end only closes the row. As in GitHub tables, code spans do not
+/// protect pipes.
+fn cell_count(row: &str) -> usize {
+    let row = row.trim();
+    let bytes = row.as_bytes();
+    let mut pipes = Vec::new();
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\\' => index += 1,
+            b'|' => pipes.push(index),
+            _ => {}
+        }
+        index += 1;
+    }
+    let leading = pipes.first() == Some(&0);
+    let trailing = row.len() > 1 && pipes.last() == Some(&(row.len() - 1));
+    pipes.len() + 1 - usize::from(leading) - usize::from(trailing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LintKind, cell_count, lint, lint_bytes};
+
+    fn kinds_and_lines(markdown: &str) -> Vec<(LintKind, usize)> {
+        lint(markdown)
+            .into_iter()
+            .map(|issue| (issue.kind, issue.line))
+            .collect()
+    }
+
+    #[test]
+    fn clean_documents_have_no_issues() {
+        let src = "# Title\n\nSee [docs][d] and [home][].\n\n```rust\nlet x = [y][z];\n```\n\n\
+                   | a | b |\n|---|---|\n| 1 | `x \\| y` |\n\n## Usage\n\n[d]: https://d.example\n\
+                   [home]: /\n";
+        assert_eq!(lint(src), Vec::new());
+        assert_eq!(lint_bytes(src.as_bytes()), Vec::new());
+        assert_eq!(lint(""), Vec::new());
+    }
+
+    #[test]
+    fn unclosed_fences_point_at_the_opening_line() {
+        let src = "# Doc\n\n~~~\nclosed\n~~~\n\n````md\n```\nstill inside\n";
+        let issues = lint(src);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].kind, LintKind::UnclosedFence);
+        assert_eq!(issues[0].line, 7);
+        assert_eq!(issues[0].message, "code block opened here is never closed");
+    }
+
+    #[test]
+    fn reference_links_need_definitions() {
+        let src = "Intro\n\n[text][missing] and [gone][] but [just brackets].\n\n[ok][def]\n\n\
+                   [def]: https://ok.example\n";
+        let issues = lint(src);
+        assert_eq!(
+            kinds_and_lines(src),
+            [
+                (LintKind::MissingReference, 3),
+                (LintKind::MissingReference, 3)
+            ]
+        );
+        assert_eq!(
+            issues[0].message,
+            "no definition for reference link [missing]"
+        );
+        assert_eq!(issues[1].message, "no definition for reference link [gone]");
+    }
+
+    #[test]
+    fn repeated_heading_anchors_are_reported_once_per_repeat() {
+        let src = "# Setup\n\ntext\n\n## Setup!\n\nSetup\n-----\n\n## Other\n";
+        let issues = lint(src);
+        assert_eq!(
+            kinds_and_lines(src),
+            [
+                (LintKind::DuplicateAnchor, 5),
+                (LintKind::DuplicateAnchor, 7)
+            ]
+        );
+        assert_eq!(
+            issues[0].message,
+            "heading anchor #setup is already used on line 1"
+        );
+    }
+
+    #[test]
+    fn table_rows_must_match_the_header() {
+        let src = "| a | b |\n|---|---|\n| 1 | 2 | 3 |\n| only |\n| x | y |\n";
+        let issues = lint(src);
+        assert_eq!(
+            kinds_and_lines(src),
+            [(LintKind::TableColumns, 3), (LintKind::TableColumns, 4)]
+        );
+        assert_eq!(issues[0].message, "table row has 3 cells, the header has 2");
+        assert_eq!(issues[1].message, "table row has 1 cell, the header has 2");
+
+        assert_eq!(cell_count("a | b"), 2);
+        assert_eq!(cell_count("| a \\| b |"), 1);
+        assert_eq!(cell_count("|a|b|c|"), 3);
+    }
+
+    #[test]
+    fn invalid_utf8_is_reported_per_line() {
+        let bytes = b"fine\nbad \xff and \xfe\nok\n\xc3\n";
+        let issues = lint_bytes(bytes);
+        assert_eq!(
+            issues
+                .iter()
+                .map(|issue| (issue.kind, issue.line))
+                .collect::<Vec<_>>(),
+            [(LintKind::InvalidUtf8, 2), (LintKind::InvalidUtf8, 4)]
+        );
+        assert_eq!(issues[0].message, "invalid UTF-8");
+    }
+
+    #[test]
+    fn kinds_round_trip_through_their_names() {
+        for kind in LintKind::ALL {
+            assert_eq!(LintKind::from_name(kind.name()), Some(kind));
+        }
+        assert_eq!(LintKind::from_name("spelling"), None);
+    }
+}
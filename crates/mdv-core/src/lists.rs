@@ -0,0 +1,164 @@
+use std::ops::Range;
+
+use crate::markdown::FenceTracker;
+
+/// Indent and number of an ordered list item: `3. ` or `3) ` after any
+/// spaces or tabs. The byte range is that of the digits.
+fn ordered_item(line: &str) -> Option<(usize, Range<usize>)> {
+    let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+    let rest = &line[indent..];
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    let after = rest.as_bytes().get(digits..digits + 2)?;
+    (digits > 0
+        && digits <= 9
+        && matches!(after[0], b'.' | b')')
+        && matches!(after[1], b' ' | b'\t'))
+    .then_some((indent, indent..indent + digits))
+}
+
+/// Number the ordered list around zero-based `cursor_line` again: each
+/// level counts up from its first item's number, so `1. 1. 1.` becomes
+/// `1. 2. 3.` and a list starting at `3.` keeps starting there. Nested
+/// lists are told apart by indentation and restart under each parent.
+///
+/// The list is the run of item lines, lines indented under them and blank
+/// lines between them. Lines in fenced code are left alone, including a
+/// fence inside an item. Returns the byte range of the list and the text to
+/// put there, or `None` when the cursor is not in an ordered list.
+pub fn renumber_ordered_list(text: &str, cursor_line: usize) -> Option<(Range<usize>, String)> {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let mut fences = FenceTracker::default();
+    let fenced: Vec<bool> = lines
+        .iter()
+        .map(|line| fences.line(line).is_code())
+        .collect();
+    let blank = |index: usize| lines[index].trim().is_empty();
+    let item = |index: usize| {
+        (!fenced[index])
+            .then(|| ordered_item(lines[index]))
+            .flatten()
+    };
+    // What keeps a list going: an item, or a line indented under one,
+    // fenced code included.
+    let belongs = |index: usize| {
+        item(index).is_some() || (!blank(index) && lines[index].starts_with([' ', '\t']))
+    };
+    if cursor_line >= lines.len() || blank(cursor_line) || !belongs(cursor_line) {
+        return None;
+    }
+
+    // A blank line only joins the list when more of it follows.
+    let mut first = cursor_line;
+    while first > 0 {
+        let mut above = first - 1;
+        while above > 0 && blank(above) {
+            above -= 1;
+        }
+        if !belongs(above) {
+            break;
+        }
+        first = above;
+    }
+    let mut last = cursor_line;
+    loop {
+        let mut below = last + 1;
+        while below < lines.len() && blank(below) {
+            below += 1;
+        }
+        if below >= lines.len() || !belongs(below) {
+            break;
+        }
+        last = below;
+    }
+    // Indented lines ahead of the first item are not part of it.
+    while first <= last && item(first).is_none() {
+        first += 1;
+    }
+    if first > cursor_line {
+        return None;
+    }
+
+    // Open levels as (indent, next number), outermost first.
+    let mut levels: Vec<(usize, u64)> = Vec::new();
+    let mut renumbered = String::new();
+    for (index, line) in lines.iter().enumerate().take(last + 1).skip(first) {
+        let Some((indent, digits)) = item(index) else {
+            renumbered.push_str(line);
+            continue;
+        };
+        while levels.last().is_some_and(|&(open, _)| open > indent) {
+            levels.pop();
+        }
+        let number = match levels.last_mut() {
+            Some((open, next)) if *open == indent => {
+                *next += 1;
+                *next - 1
+            }
+            _ => {
+                let start = line[digits.clone()].parse().unwrap_or(1);
+                levels.push((indent, start + 1));
+                start
+            }
+        };
+        renumbered.push_str(&line[..digits.start]);
+        renumbered.push_str(&number.to_string());
+        renumbered.push_str(&line[digits.end..]);
+    }
+    let start: usize = lines[..first].iter().map(|line| line.len()).sum();
+    let end = start
+        + lines[first..=last]
+            .iter()
+            .map(|line| line.len())
+            .sum::<usize>();
+    Some((start..end, renumbered))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::renumber_ordered_list;
+
+    fn renumber(text: &str, cursor_line: usize) -> Option<String> {
+        renumber_ordered_list(text, cursor_line).map(|(range, list)| {
+            let mut out = text.to_string();
+            out.replace_range(range, &list);
+            out
+        })
+    }
+
+    #[test]
+    fn nested_levels_count_on_their_own_and_restart_under_each_parent() {
+        let text = "Intro\n\n1. one\n1. two\n   1. a\n   7. b\n      - note\n1. three\n   5. c\n   5. d\n\nAfter\n";
+        assert_eq!(
+            renumber(text, 5).as_deref(),
+            Some(
+                "Intro\n\n1. one\n2. two\n   1. a\n   2. b\n      - note\n3. three\n   5. c\n   6. d\n\nAfter\n"
+            )
+        );
+        assert_eq!(renumber(text, 0), None);
+        assert_eq!(renumber(text, 10), None);
+    }
+
+    #[test]
+    fn a_list_keeps_its_first_number_across_blank_lines() {
+        let text = "3) start\n\n9) next\n1) last";
+        let (range, list) = renumber_ordered_list(text, 2).expect("list");
+        assert_eq!(range, 0..text.len());
+        assert_eq!(list, "3) start\n\n4) next\n5) last");
+    }
+
+    #[test]
+    fn fenced_lines_are_never_renumbered() {
+        let text = "```\n1. not\n1. a list\n```\n";
+        assert_eq!(renumber(text, 1), None);
+
+        let text = "1. run\n   ```sh\n   1. echo\n   ```\n1. done\n";
+        assert_eq!(
+            renumber(text, 4).as_deref(),
+            Some("1. run\n   ```sh\n   1. echo\n   ```\n2. done\n")
+        );
+        assert_eq!(
+            renumber(text, 2).as_deref(),
+            Some("1. run\n   ```sh\n   1. echo\n   ```\n2. done\n")
+        );
+    }
+}
@@ -0,0 +1,111 @@
+#[cfg(feature = "fs")]
+use std::borrow::Cow;
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::io;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+/// How a file ended its lines. Buffers always hold `\n`; saving writes the
+/// file's own ending back.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Lf => "LF",
+            Self::CrLf => "CRLF",
+        }
+    }
+
+    /// `text` as written to disk with this ending.
+    #[cfg(feature = "fs")]
+    pub(crate) fn apply(self, text: &str) -> Cow<'_, str> {
+        match self {
+            Self::Lf => Cow::Borrowed(text),
+            Self::CrLf => Cow::Owned(text.replace('\n', "\r\n")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadedText {
+    /// The file's text with `\r\n` normalized to `\n`.
+    pub text: String,
+    /// The ending most lines used.
+    pub line_ending: LineEnding,
+    /// Invalid UTF-8 sequences replaced with `U+FFFD`.
+    pub invalid_sequences: usize,
+}
+
+#[cfg(feature = "fs")]
+pub fn read_text(path: &Path) -> io::Result<LoadedText> {
+    fs::read(path).map(|bytes| decode_text(&bytes))
+}
+
+/// Decode file bytes, replacing invalid UTF-8 instead of failing, and
+/// normalize line endings to `\n`.
+pub fn decode_text(bytes: &[u8]) -> LoadedText {
+    let mut invalid_sequences = 0;
+    let mut text = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        text.push_str(chunk.valid());
+        if !chunk.invalid().is_empty() {
+            invalid_sequences += 1;
+            text.push(char::REPLACEMENT_CHARACTER);
+        }
+    }
+
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    let line_ending = if crlf > lf {
+        LineEnding::CrLf
+    } else {
+        LineEnding::Lf
+    };
+    if crlf > 0 {
+        text = text.replace("\r\n", "\n");
+    }
+
+    LoadedText {
+        text,
+        line_ending,
+        invalid_sequences,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineEnding, decode_text};
+
+    #[test]
+    fn detects_the_dominant_line_ending() {
+        let loaded = decode_text(b"a\r\nb\r\nc\n");
+        assert_eq!(loaded.text, "a\nb\nc\n");
+        assert_eq!(loaded.line_ending, LineEnding::CrLf);
+
+        let loaded = decode_text(b"a\nb\nc\r\n");
+        assert_eq!(loaded.text, "a\nb\nc\n");
+        assert_eq!(loaded.line_ending, LineEnding::Lf);
+
+        assert_eq!(decode_text(b"").line_ending, LineEnding::Lf);
+        assert_eq!(decode_text(b"lone\rcr").text, "lone\rcr");
+    }
+
+    #[test]
+    fn replaces_invalid_utf8() {
+        let loaded = decode_text(b"ok \xff\xfe and \xc3 end");
+        assert_eq!(loaded.text, "ok \u{fffd}\u{fffd} and \u{fffd} end");
+        assert_eq!(loaded.invalid_sequences, 3);
+
+        let loaded = decode_text("caf\u{e9}".as_bytes());
+        assert_eq!(loaded.text, "caf\u{e9}");
+        assert_eq!(loaded.invalid_sequences, 0);
+    }
+}
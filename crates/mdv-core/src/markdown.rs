@@ -1,6 +1,36 @@
-use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::ops::Range;
 
+use pulldown_cmark::{
+    Alignment, CodeBlockKind, CowStr, Event, HeadingLevel, Options, Parser, Tag, TagEnd,
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::emoji::replace_shortcodes;
+use crate::front_matter::{FrontMatter, blank_front_matter, front_matter};
+use crate::render::{FrontMatterStyle, RenderOptions, render};
+
+/// Narrower widths are rendered at this width.
+pub(crate) const MIN_WIDTH: u16 = 8;
+
+/// How the renderer treats markup, beyond the width it wraps at.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct RenderFlags {
+    /// Keep emphasis as literal `*`/`**`/`~~` instead of spans.
+    pub markers: bool,
+    /// Keep setext underlines and heading attributes as written.
+    pub source_headings: bool,
+    /// Show `:shortcode:` emoji as the emoji.
+    pub emoji: bool,
+    pub front_matter: FrontMatterStyle,
+    /// Show links as their text and images as `🖼 alt` instead of their
+    /// Markdown source.
+    pub reader: bool,
+}
+
+/// What produced a piece of preview text. New kinds may be added in minor
+/// releases, so matches need a fallback arm.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum SegmentKind {
     Plain,
     Heading,
@@ -11,6 +41,22 @@ pub enum SegmentKind {
     TableHeader,
     ConflictLocal,
     ConflictExternal,
+    /// Unchanged lines shown around a conflict hunk.
+    ConflictContext,
+    Emphasis,
+    Strong,
+    Strikethrough,
+    Rule,
+    TaskDone,
+    TaskPending,
+    /// Lines of a raw HTML block.
+    Html,
+    /// A `[^name]` footnote reference, or a label in the footnotes section.
+    FootnoteRef,
+    /// A `[^name]` reference with no matching definition.
+    FootnoteMissing,
+    /// Front matter opening the document, folded or line by line.
+    FrontMatter,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -22,6 +68,49 @@ pub struct PreviewSegment {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PreviewLine {
     pub segments: Vec<PreviewSegment>,
+    /// Zero-based source line the line was rendered from.
+    pub source_line: usize,
+}
+
+impl PreviewLine {
+    /// A line made of one segment, anchored at source line 0.
+    pub fn new(text: impl Into<String>, kind: SegmentKind) -> Self {
+        Self {
+            segments: vec![PreviewSegment {
+                text: text.into(),
+                kind,
+            }],
+            source_line: 0,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.segments
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect()
+    }
+}
+
+/// A kind applied to a byte range of a line while it is being built.
+#[derive(Debug, Clone)]
+struct Span {
+    range: Range<usize>,
+    kind: SegmentKind,
+}
+
+#[derive(Debug)]
+pub(crate) struct StyledLine {
+    pub(crate) text: String,
+    spans: Vec<Span>,
+    /// Zero-based source line the output line came from.
+    pub(crate) source_line: usize,
+}
+
+impl StyledLine {
+    pub(crate) fn segments(&self) -> Vec<PreviewSegment> {
+        split_spans(&self.text, &self.spans)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -38,34 +127,112 @@ enum LinkState {
 
 struct Renderer {
     width: usize,
-    lines: Vec<String>,
+    /// Keep emphasis as literal markers instead of spans.
+    markers: bool,
+    lines: Vec<StyledLine>,
     current: String,
+    current_spans: Vec<Span>,
+    inline_stack: Vec<SegmentKind>,
+    in_heading: bool,
+    /// Keep setext underlines and `{#id}` attributes as the source has them.
+    source_headings: bool,
+    /// Show `:shortcode:` emoji in prose as the emoji.
+    emoji: bool,
+    /// Links and images show as text rather than as Markdown.
+    reader: bool,
+    /// Underline character of the setext heading being read.
+    setext: Option<char>,
+    /// ` {#id .class}` to append to the heading being read.
+    heading_attrs: String,
+    /// Inside the text of a checked task item, which renders struck through.
+    in_done_task: bool,
+    current_prefix_len: usize,
+    continuation_prefix: String,
     blockquote_depth: usize,
     pending_prefix: Option<String>,
     list_stack: Vec<ListState>,
     link_stack: Vec<LinkState>,
     in_code_block: bool,
-    table_head_needs_separator: bool,
+    /// Index in `lines` of the last code block's opening fence, and of its
+    /// closing fence once written.
+    code_block_lines: Option<(usize, Option<usize>)>,
+    table_alignments: Vec<Alignment>,
+    /// Rows of the table being read, header first; emitted at its end once
+    /// column widths are known.
+    table_rows: Vec<Vec<String>>,
     in_table_cell: bool,
     table_row: Vec<String>,
     table_cell: String,
+    /// Source line of the event being handled.
+    source_line: usize,
+    /// Source line where the prose line in `current` started.
+    current_source: usize,
+    /// Source line of each buffered table row.
+    table_sources: Vec<usize>,
+    /// Lowercased names of footnotes defined outside the text being
+    /// rendered, when a block is rendered on its own.
+    known_footnotes: Vec<String>,
+    /// End of a `[^name]` already rendered from its bracket text events.
+    skip_text_to: usize,
+    /// Label waiting to start the first line of a footnote or a definition
+    /// list definition, which the rest of it hangs under.
+    hanging_label: Option<String>,
+    hanging_kind: SegmentKind,
+    /// Indent of the lines after the hanging label.
+    hanging_indent: usize,
+    /// Length of the hanging label at the start of `current`.
+    current_label_len: usize,
+    in_html_block: bool,
+    /// The last HTML block line was blank; runs of them show as one.
+    html_blank: bool,
+    /// Index in `lines` where the open footnote definition started.
+    footnote_start: Option<usize>,
+    /// Lines of every footnote definition, in definition order.
+    footnotes: Vec<StyledLine>,
 }
 
 impl Renderer {
-    fn new(width: usize) -> Self {
+    fn new(width: usize, markers: bool) -> Self {
         Self {
             width,
+            markers,
             lines: Vec::new(),
             current: String::new(),
+            current_spans: Vec::new(),
+            inline_stack: Vec::new(),
+            in_heading: false,
+            source_headings: false,
+            emoji: false,
+            reader: false,
+            setext: None,
+            heading_attrs: String::new(),
+            in_done_task: false,
+            current_prefix_len: 0,
+            continuation_prefix: String::new(),
             blockquote_depth: 0,
             pending_prefix: None,
             list_stack: Vec::new(),
             link_stack: Vec::new(),
             in_code_block: false,
-            table_head_needs_separator: false,
+            code_block_lines: None,
+            table_alignments: Vec::new(),
+            table_rows: Vec::new(),
             in_table_cell: false,
             table_row: Vec::new(),
             table_cell: String::new(),
+            source_line: 0,
+            current_source: 0,
+            table_sources: Vec::new(),
+            known_footnotes: Vec::new(),
+            skip_text_to: 0,
+            hanging_label: None,
+            hanging_kind: SegmentKind::Plain,
+            hanging_indent: 0,
+            current_label_len: 0,
+            in_html_block: false,
+            html_blank: false,
+            footnote_start: None,
+            footnotes: Vec::new(),
         }
     }
 
@@ -75,13 +242,35 @@ impl Renderer {
 
     fn take_line_prefix(&mut self) -> String {
         let mut prefix = self.quote_prefix();
+        let indent = " ".repeat(self.hanging_indent);
+        self.continuation_prefix = format!("{prefix}{indent}");
+        self.current_label_len = 0;
+        if let Some(label) = self.hanging_label.take() {
+            self.current_label_len = label.len();
+            prefix.push_str(&label);
+        } else {
+            prefix.push_str(&indent);
+        }
         if let Some(item_prefix) = self.pending_prefix.take() {
+            let indent = item_prefix.chars().count();
             prefix.push_str(&item_prefix);
+            self.continuation_prefix.push_str(&" ".repeat(indent));
         }
         prefix
     }
 
     fn append_text(&mut self, text: &str) {
+        let kind = if self.in_heading {
+            Some(SegmentKind::Heading)
+        } else {
+            let quote = (self.blockquote_depth > 0).then_some(SegmentKind::Quote);
+            let task = self.in_done_task.then_some(SegmentKind::Strikethrough);
+            self.inline_stack.last().copied().or(task).or(quote)
+        };
+        self.append_span(text, kind);
+    }
+
+    fn append_span(&mut self, text: &str, kind: Option<SegmentKind>) {
         if let Some(link) = self.link_stack.last_mut() {
             match link {
                 LinkState::Link { text: inner, .. } => inner.push_str(text),
@@ -96,10 +285,51 @@ impl Renderer {
         }
 
         if self.current.is_empty() {
+            self.current_source = self.source_line;
             let prefix = self.take_line_prefix();
+            let quote_len = self.blockquote_depth * 2;
             self.current.push_str(&prefix);
+            self.current_prefix_len = prefix.len();
+            self.mark(0..quote_len, SegmentKind::Quote);
+            let label_end = quote_len + self.current_label_len;
+            self.mark(quote_len..label_end, self.hanging_kind);
+            let bullet_start =
+                label_end + (prefix[label_end..].len() - prefix[label_end..].trim_start().len());
+            self.mark(bullet_start..prefix.len(), SegmentKind::ListBullet);
         }
+        let start = self.current.len();
         self.current.push_str(text);
+        if let Some(kind) = kind {
+            self.mark(start..self.current.len(), kind);
+        }
+    }
+
+    fn mark(&mut self, range: Range<usize>, kind: SegmentKind) {
+        if range.is_empty() {
+            return;
+        }
+        match self.current_spans.last_mut() {
+            Some(last) if last.kind == kind && last.range.end == range.start => {
+                last.range.end = range.end;
+            }
+            _ => self.current_spans.push(Span { range, kind }),
+        }
+    }
+
+    fn open_inline(&mut self, kind: SegmentKind, marker: &str) {
+        if self.markers {
+            self.append_text(marker);
+        } else {
+            self.inline_stack.push(kind);
+        }
+    }
+
+    fn close_inline(&mut self, marker: &str) {
+        if self.markers {
+            self.append_text(marker);
+        } else {
+            self.inline_stack.pop();
+        }
     }
 
     fn flush_current(&mut self) {
@@ -107,15 +337,262 @@ impl Renderer {
             return;
         }
         let line = std::mem::take(&mut self.current);
-        for wrapped in wrap_line(&line, self.width) {
-            self.lines.push(wrapped);
+        let spans = std::mem::take(&mut self.current_spans);
+        let prefix_len = self.current_prefix_len.min(line.len());
+        let (prefix, body) = line.split_at(prefix_len);
+        let indent = wrap_indent(&self.continuation_prefix, self.width);
+        for (idx, range) in wrap_word_ranges(prefix, body, indent, self.width)
+            .into_iter()
+            .enumerate()
+        {
+            let leader = if idx == 0 { prefix } else { indent };
+            let mut line_spans = Vec::new();
+            if idx == 0 {
+                line_spans.extend(
+                    spans
+                        .iter()
+                        .filter(|span| span.range.start < prefix_len)
+                        .map(|span| Span {
+                            range: span.range.start..span.range.end.min(prefix_len),
+                            kind: span.kind,
+                        }),
+                );
+            } else {
+                let quote_len = (self.blockquote_depth * 2).min(leader.len());
+                if quote_len > 0 {
+                    line_spans.push(Span {
+                        range: 0..quote_len,
+                        kind: SegmentKind::Quote,
+                    });
+                }
+            }
+            // Body spans are offsets into `line`; re-base them onto this piece.
+            line_spans.extend(spans.iter().filter_map(|span| {
+                let start = span.range.start.max(prefix_len) - prefix_len;
+                let start = start.max(range.start);
+                let end = span.range.end.saturating_sub(prefix_len).min(range.end);
+                (start < end).then(|| Span {
+                    range: leader.len() + start - range.start..leader.len() + end - range.start,
+                    kind: span.kind,
+                })
+            }));
+            self.lines.push(StyledLine {
+                text: format!("{leader}{}", &body[range]),
+                spans: line_spans,
+                source_line: self.current_source,
+            });
+        }
+        self.current_prefix_len = 0;
+    }
+
+    /// Start a block whose first line begins with `label` and whose other
+    /// lines are indented to line up after it.
+    fn open_hanging(&mut self, label: String, kind: SegmentKind) {
+        self.flush_current();
+        self.hanging_indent = label.width();
+        self.hanging_label = Some(label);
+        self.hanging_kind = kind;
+    }
+
+    /// End the hanging block, returning its label if no line used it.
+    fn close_hanging(&mut self) -> Option<String> {
+        self.flush_current();
+        self.hanging_indent = 0;
+        self.hanging_label.take()
+    }
+
+    /// Start a footnote definition; its lines move to the footnotes section
+    /// when it ends.
+    fn open_footnote(&mut self, name: &str) {
+        self.open_hanging(format!("[^{name}]: "), SegmentKind::FootnoteRef);
+        self.footnote_start = Some(self.lines.len());
+    }
+
+    fn close_footnote(&mut self) {
+        // A definition with no prose still lists its label.
+        if let Some(label) = self.close_hanging() {
+            self.push_line(label.trim_end().to_string(), SegmentKind::FootnoteRef);
+        }
+        if let Some(start) = self.footnote_start.take() {
+            if self.code_block_lines.is_some_and(|(open, _)| open >= start) {
+                self.code_block_lines = None;
+            }
+            let lines = self.lines.split_off(start);
+            self.footnotes.extend(lines);
         }
     }
 
-    fn push_line(&mut self, line: String) {
+    /// Finish a heading: its attributes, then the underline of a setext one,
+    /// as wide as the widest line of the heading.
+    fn close_heading(&mut self) {
+        let attrs = std::mem::take(&mut self.heading_attrs);
+        if !attrs.is_empty() {
+            self.append_text(&attrs);
+        }
+        let first = self.lines.len();
         self.flush_current();
-        for wrapped in wrap_line(&line, self.width) {
-            self.lines.push(wrapped);
+        self.in_heading = false;
+        if let Some(underline) = self.setext.take() {
+            let prefix = self.quote_prefix();
+            let cells = self.lines[first..]
+                .iter()
+                .map(|line| line.text.width().saturating_sub(prefix.width()))
+                .max()
+                .unwrap_or(0)
+                .max(1);
+            let line = format!("{prefix}{}", underline.to_string().repeat(cells));
+            self.push_line(line, SegmentKind::Heading);
+        }
+    }
+
+    /// Push the lines of raw HTML from a block, collapsing runs of blank
+    /// ones.
+    fn push_html(&mut self, html: &str) {
+        for (idx, line) in html.lines().enumerate() {
+            let blank = line.trim().is_empty();
+            if blank && self.html_blank {
+                continue;
+            }
+            self.html_blank = blank;
+            let source_line = self.source_line;
+            self.source_line += idx;
+            let mut text = self.quote_prefix();
+            text.push_str(line);
+            self.push_line(text, SegmentKind::Html);
+            self.source_line = source_line;
+        }
+    }
+
+    /// Cut the output to `max` lines. A cut inside a code block ends with a
+    /// closing fence so the output stays well-formed; one too short to hold
+    /// both fences is dropped.
+    fn truncate_lines(&mut self, max: usize) {
+        let cut_block = self
+            .code_block_lines
+            .filter(|&(open, close)| open < max && close.is_none_or(|close| close >= max));
+        match cut_block {
+            Some((open, _)) if open + 1 < max => {
+                let opening = &self.lines[open].text;
+                let fence =
+                    opening[..opening.find("```").map_or(opening.len(), |idx| idx + 3)].to_string();
+                self.lines.truncate(max - 1);
+                let source_line = self.lines.last().map_or(0, |line| line.source_line);
+                self.lines.push(StyledLine {
+                    spans: vec![Span {
+                        range: 0..fence.len(),
+                        kind: SegmentKind::Code,
+                    }],
+                    text: fence,
+                    source_line,
+                });
+            }
+            Some((open, _)) => self.lines.truncate(open),
+            None => self.lines.truncate(max),
+        }
+    }
+
+    /// Push front matter as one summary line, or line by line between its
+    /// delimiters.
+    fn push_front_matter(&mut self, front: &FrontMatter<'_>, style: FrontMatterStyle) {
+        let delimiter = front.kind.delimiter();
+        self.source_line = 0;
+        if style == FrontMatterStyle::Expanded {
+            self.push_line(delimiter.to_string(), SegmentKind::FrontMatter);
+            for line in front.body.lines() {
+                self.source_line += 1;
+                self.push_line(line.trim_end().to_string(), SegmentKind::FrontMatter);
+            }
+            self.source_line += 1;
+            self.push_line(delimiter.to_string(), SegmentKind::FrontMatter);
+            return;
+        }
+        let keys = front.key_count();
+        let plural = if keys == 1 { "" } else { "s" };
+        let summary = format!("{delimiter} front matter: {keys} key{plural} {delimiter}");
+        self.push_line(summary, SegmentKind::FrontMatter);
+    }
+
+    /// Push a line that is not prose, hard-wrapped and styled as `kind`.
+    fn push_line(&mut self, line: String, kind: SegmentKind) {
+        self.flush_current();
+        for text in wrap_line(&line, self.width) {
+            let spans = vec![Span {
+                range: 0..text.len(),
+                kind,
+            }];
+            self.lines.push(StyledLine {
+                text,
+                spans,
+                source_line: self.source_line,
+            });
+        }
+    }
+
+    /// Emit the buffered table with padded, aligned columns. Columns shrink
+    /// (widest first) to fit the width, truncating cells with an ellipsis.
+    fn push_table(&mut self) {
+        let rows = std::mem::take(&mut self.table_rows);
+        let sources = std::mem::take(&mut self.table_sources);
+        let alignments = std::mem::take(&mut self.table_alignments);
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+        let alignment = |col: usize| alignments.get(col).copied().unwrap_or(Alignment::None);
+        let mut widths: Vec<usize> = (0..columns)
+            .map(|col| {
+                let min = match alignment(col) {
+                    Alignment::Center => 3,
+                    Alignment::Left | Alignment::Right => 2,
+                    Alignment::None => 1,
+                };
+                rows.iter()
+                    .filter_map(|row| row.get(col))
+                    .map(|cell| cell.width())
+                    .fold(min, usize::max)
+            })
+            .collect();
+
+        let quote = self.quote_prefix();
+        let borders = 3 * columns + 1;
+        let room = self.width.saturating_sub(quote.width() + borders);
+        while widths.iter().sum::<usize>() > room {
+            let Some(widest) =
+                (0..columns).reduce(|a, b| if widths[b] > widths[a] { b } else { a })
+            else {
+                break;
+            };
+            if widths[widest] <= 1 {
+                break;
+            }
+            widths[widest] -= 1;
+        }
+
+        for (idx, row) in rows.iter().enumerate() {
+            if let Some(&source) = sources.get(idx) {
+                self.source_line = source;
+            }
+            let cells: Vec<String> = (0..columns)
+                .map(|col| {
+                    let cell = row.get(col).map_or("", String::as_str);
+                    pad_cell(cell, widths[col], alignment(col))
+                })
+                .collect();
+            let kind = if idx == 0 {
+                SegmentKind::TableHeader
+            } else {
+                SegmentKind::Plain
+            };
+            self.push_line(format!("{quote}| {} |", cells.join(" | ")), kind);
+            if idx == 0 {
+                let separator: Vec<String> = (0..columns)
+                    .map(|col| separator_cell(widths[col], alignment(col)))
+                    .collect();
+                self.push_line(
+                    format!("{quote}| {} |", separator.join(" | ")),
+                    SegmentKind::Plain,
+                );
+            }
         }
     }
 
@@ -127,27 +604,341 @@ impl Renderer {
             }
             let mut line = self.quote_prefix();
             line.push_str(chunk);
-            self.push_line(line);
+            self.push_line(line, SegmentKind::Code);
+            self.source_line += 1;
         }
     }
 }
 
+/// Plain preview text. Emphasis keeps its `**`/`*`/`~~` markers so piped
+/// output still reads as markdown.
 pub fn render_preview_lines(markdown: &str, width: u16) -> Vec<String> {
-    let max_width = width.max(8) as usize;
-    let mut renderer = Renderer::new(max_width);
+    render(markdown, &RenderOptions::new(width).with_segments(false))
+        .lines
+        .into_iter()
+        .map(|line| line.text)
+        .collect()
+}
+
+/// [`render_preview_lines`] stopping after `max_lines` lines, and whether
+/// anything was left out. Only as much of `markdown` as fills the budget is
+/// parsed, and a code block cut short still gets its closing fence.
+pub fn render_preview_lines_bounded(
+    markdown: &str,
+    width: u16,
+    max_lines: usize,
+) -> (Vec<String>, bool) {
+    let options = RenderOptions::new(width)
+        .with_segments(false)
+        .with_max_lines(Some(max_lines));
+    let doc = render(markdown, &options);
+    let lines = doc.lines.into_iter().map(|line| line.text).collect();
+    (lines, doc.truncated)
+}
+
+/// Preview lines split into segments by what produced them, with emphasis
+/// carried as segment kinds instead of markers, links and images shown the
+/// reader way and `:shortcode:` emoji shown as the emoji.
+pub fn render_preview_segments(markdown: &str, width: u16) -> Vec<PreviewLine> {
+    let options = RenderOptions::new(width).with_emoji(true).with_reader(true);
+    render(markdown, &options)
+        .lines
+        .into_iter()
+        .map(|line| PreviewLine {
+            segments: line.segments,
+            source_line: line.source_line,
+        })
+        .collect()
+}
+
+pub(crate) fn parser_options() -> Options {
     let mut options = Options::empty();
     options.insert(Options::ENABLE_TABLES);
     options.insert(Options::ENABLE_TASKLISTS);
     options.insert(Options::ENABLE_STRIKETHROUGH);
     options.insert(Options::ENABLE_FOOTNOTES);
     options.insert(Options::ENABLE_MATH);
+    options.insert(Options::ENABLE_DEFINITION_LIST);
+    options
+}
+
+/// Start of the first line at or after byte `offset` that is outside fenced
+/// code, so text cut there does not begin inside a code block. When the fence
+/// open at `offset` never closes, the next line start is used anyway.
+pub fn unfenced_line_start(markdown: &str, offset: usize) -> usize {
+    let bytes = markdown.as_bytes();
+    let offset = offset.min(bytes.len());
+    let line_start = if offset == 0 || bytes[offset - 1] == b'\n' {
+        offset
+    } else {
+        bytes[offset..]
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .map_or(bytes.len(), |idx| offset + idx + 1)
+    };
+
+    let mut fences = FenceTracker::default();
+    let mut end = 0;
+    for line in markdown.split_inclusive('\n') {
+        if end >= line_start && !fences.is_open() {
+            return end;
+        }
+        end += line.len();
+        fences.line(line);
+    }
+    if fences.is_open() { line_start } else { end }
+}
+
+/// Where a line stands relative to fenced code, from [`FenceTracker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FenceLine {
+    /// Ordinary text.
+    Outside,
+    /// The fence that opens a code block.
+    Open,
+    /// A line of code.
+    Inside,
+    /// The fence that closes the code block.
+    Close,
+}
+
+impl FenceLine {
+    /// Whether the line is a fence or code.
+    pub(crate) fn is_code(self) -> bool {
+        self != FenceLine::Outside
+    }
+}
+
+/// Follows fenced code blocks through a document one line at a time. A
+/// block closes at a line of the same fence character, at least as long as
+/// the opening run and with nothing after it; indentation of up to three
+/// spaces is allowed on both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct FenceTracker {
+    open: Option<(u8, usize)>,
+}
+
+impl FenceTracker {
+    /// Classify the next line; its line ending and trailing spaces are
+    /// ignored.
+    pub(crate) fn line(&mut self, line: &str) -> FenceLine {
+        match (self.open, fence_marker(line.trim_end())) {
+            (Some((ch, len)), Some((close_ch, close_len, rest)))
+                if close_ch == ch && close_len >= len && rest.trim().is_empty() =>
+            {
+                self.open = None;
+                FenceLine::Close
+            }
+            (Some(_), _) => FenceLine::Inside,
+            (None, Some((ch, len, _))) => {
+                self.open = Some((ch, len));
+                FenceLine::Open
+            }
+            (None, None) => FenceLine::Outside,
+        }
+    }
 
-    for event in Parser::new_ext(markdown, options) {
+    /// Whether a code block is open after the lines seen so far.
+    pub(crate) fn is_open(&self) -> bool {
+        self.open.is_some()
+    }
+}
+
+/// A line opening or closing a fence: its character, run length, and the
+/// text after the run.
+pub(crate) fn fence_marker(line: &str) -> Option<(u8, usize, &str)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let ch = *trimmed.as_bytes().first()?;
+    if ch != b'`' && ch != b'~' {
+        return None;
+    }
+    let len = trimmed.bytes().take_while(|byte| *byte == ch).count();
+    (len >= 3).then(|| (ch, len, &trimmed[len..]))
+}
+
+/// Segmented preview lines of one block, without the blank placeholder an
+/// empty document gets, so block outputs can be concatenated. The second
+/// list holds the block's footnote definitions, which belong in the
+/// footnotes section at the end. `known_footnotes` are the lowercased names
+/// defined elsewhere in the document.
+pub(crate) fn render_block_segments(
+    markdown: &str,
+    width: u16,
+    flags: RenderFlags,
+    known_footnotes: &[String],
+) -> (Vec<PreviewLine>, Vec<PreviewLine>) {
+    let (lines, footnotes, _) = render_parts(
+        markdown,
+        usize::from(width.max(MIN_WIDTH)),
+        flags,
+        known_footnotes,
+        None,
+    );
+    let segments = |lines: Vec<StyledLine>| {
+        lines
+            .into_iter()
+            .map(|line| PreviewLine {
+                segments: line.segments(),
+                source_line: line.source_line,
+            })
+            .collect()
+    };
+    (segments(lines), segments(footnotes))
+}
+
+/// The heading over the footnotes section, as [`render_block_segments`]
+/// output, anchored at the first definition's `source_line`.
+pub(crate) fn footnotes_heading_segments(width: u16, source_line: usize) -> Vec<PreviewLine> {
+    footnotes_heading(usize::from(width.max(MIN_WIDTH)), source_line)
+        .into_iter()
+        .map(|line| PreviewLine {
+            segments: line.segments(),
+            source_line,
+        })
+        .collect()
+}
+
+pub(crate) fn footnotes_heading(width: usize, source_line: usize) -> Vec<StyledLine> {
+    wrap_line("Footnotes", width)
+        .into_iter()
+        .map(|text| StyledLine {
+            spans: vec![Span {
+                range: 0..text.len(),
+                kind: SegmentKind::Heading,
+            }],
+            text,
+            source_line,
+        })
+        .collect()
+}
+
+/// Render `markdown` at `width` terminal cells. Footnote definitions are
+/// gathered into a section at the end.
+pub(crate) fn render_lines(markdown: &str, width: usize, flags: RenderFlags) -> Vec<StyledLine> {
+    let (lines, footnotes, _) = render_parts(markdown, width, flags, &[], None);
+    with_footnotes(lines, footnotes, width)
+}
+
+/// [`render_lines`], stopping once `max_lines` lines are out, and whether it
+/// stopped before the end. Longer and longer starts of `markdown`, cut at
+/// blank lines, are rendered until one fills the budget, so the rest of a
+/// big document is never parsed. Reference links and footnotes defined past
+/// the cut render as if undefined.
+pub(crate) fn render_lines_bounded(
+    markdown: &str,
+    width: usize,
+    flags: RenderFlags,
+    max_lines: usize,
+) -> (Vec<StyledLine>, bool) {
+    let mut source_lines = max_lines.saturating_mul(2).max(64);
+    loop {
+        let end = blank_line_end_after(markdown, source_lines);
+        let (lines, footnotes, truncated) =
+            render_parts(&markdown[..end], width, flags, &[], Some(max_lines));
+        if truncated {
+            return (lines, true);
+        }
+        if end == markdown.len() {
+            let mut lines = with_footnotes(lines, footnotes, width);
+            let truncated = lines.len() > max_lines;
+            lines.truncate(max_lines);
+            return (lines, truncated);
+        }
+        source_lines = source_lines.saturating_mul(4);
+    }
+}
+
+fn with_footnotes(
+    mut lines: Vec<StyledLine>,
+    footnotes: Vec<StyledLine>,
+    width: usize,
+) -> Vec<StyledLine> {
+    if let Some(first) = footnotes.first() {
+        lines.extend(footnotes_heading(width, first.source_line));
+        lines.extend(footnotes);
+    }
+    lines
+}
+
+/// The end of the first blank line outside front matter and fenced code at
+/// or after source line `line`, or the end of `markdown`. Blocks before it
+/// render the same whether or not the text after it is there.
+fn blank_line_end_after(markdown: &str, line: usize) -> usize {
+    let front_end = front_matter(markdown).map_or(0, |front| front.end);
+    let mut fences = FenceTracker::default();
+    let mut end = 0;
+    for (idx, text) in markdown.split_inclusive('\n').enumerate() {
+        end += text.len();
+        if end <= front_end {
+            continue;
+        }
+        if fences.line(text) == FenceLine::Outside && idx >= line && text.trim().is_empty() {
+            return end;
+        }
+    }
+    markdown.len()
+}
+
+/// The rendered lines of `markdown` and, separately, those of its footnote
+/// definitions. With `max_lines`, parsing stops once more lines than that
+/// are out, the lines are cut to it, and the flag is set.
+pub(crate) fn render_parts(
+    markdown: &str,
+    width: usize,
+    flags: RenderFlags,
+    known_footnotes: &[String],
+    max_lines: Option<usize>,
+) -> (Vec<StyledLine>, Vec<StyledLine>, bool) {
+    let mut renderer = Renderer::new(width, flags.markers);
+    renderer.source_headings = flags.source_headings;
+    renderer.emoji = flags.emoji;
+    renderer.reader = flags.reader;
+    renderer.known_footnotes = known_footnotes.to_vec();
+    let mut options = parser_options();
+    if flags.source_headings {
+        options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+    }
+    if let Some(front) = front_matter(markdown) {
+        renderer.push_front_matter(&front, flags.front_matter);
+    }
+    // The parser sees front matter as blank lines, keeping offsets as they are.
+    let parsed = blank_front_matter(markdown);
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(markdown.match_indices('\n').map(|(idx, _)| idx + 1))
+        .collect();
+    let line_of = |offset: usize| line_starts.partition_point(|&start| start <= offset) - 1;
+
+    for (event, range) in Parser::new_ext(&parsed, options).into_offset_iter() {
+        // End events span the whole element, so they belong to its last line.
+        renderer.source_line = match event {
+            Event::End(_) => line_of(range.end.saturating_sub(1).max(range.start)),
+            _ => line_of(range.start),
+        };
         match event {
             Event::Start(tag) => match tag {
-                Tag::Heading { level, .. } => {
+                Tag::Heading {
+                    level,
+                    id,
+                    classes,
+                    attrs,
+                } => {
                     renderer.flush_current();
-                    renderer.append_text(heading_prefix(level));
+                    renderer.in_heading = true;
+                    let atx = markdown[range.start..]
+                        .trim_start_matches(' ')
+                        .starts_with('#');
+                    if renderer.source_headings && !atx {
+                        let underline = if level == HeadingLevel::H1 { '=' } else { '-' };
+                        renderer.setext = Some(underline);
+                    } else {
+                        renderer.append_text(heading_prefix(level));
+                    }
+                    if renderer.source_headings {
+                        renderer.heading_attrs = heading_attributes(id, classes, attrs);
+                    }
                 }
                 Tag::BlockQuote(_) => {
                     renderer.flush_current();
@@ -162,6 +953,7 @@ pub fn render_preview_lines(markdown: &str, width: u16) -> Vec<String> {
                 }
                 Tag::Item => {
                     renderer.flush_current();
+                    renderer.in_done_task = false;
                     let list = renderer
                         .list_stack
                         .last_mut()
@@ -180,6 +972,7 @@ pub fn render_preview_lines(markdown: &str, width: u16) -> Vec<String> {
                 Tag::CodeBlock(kind) => {
                     renderer.flush_current();
                     renderer.in_code_block = true;
+                    renderer.code_block_lines = Some((renderer.lines.len(), None));
                     let mut fence = renderer.quote_prefix();
                     fence.push_str("```");
                     if let CodeBlockKind::Fenced(lang) = kind {
@@ -188,18 +981,18 @@ pub fn render_preview_lines(markdown: &str, width: u16) -> Vec<String> {
                             fence.push_str(lang);
                         }
                     }
-                    renderer.push_line(fence);
+                    renderer.push_line(fence, SegmentKind::Code);
                 }
-                Tag::Table(_) => {
+                Tag::Table(alignments) => {
                     renderer.flush_current();
+                    renderer.table_alignments = alignments;
+                    renderer.table_rows.clear();
+                    renderer.table_sources.clear();
                 }
-                Tag::TableHead => {
-                    renderer.flush_current();
-                    renderer.table_head_needs_separator = true;
-                }
-                Tag::TableRow => {
+                Tag::TableHead | Tag::TableRow => {
                     renderer.flush_current();
                     renderer.table_row.clear();
+                    renderer.table_sources.push(renderer.source_line);
                 }
                 Tag::TableCell => {
                     renderer.in_table_cell = true;
@@ -217,10 +1010,24 @@ pub fn render_preview_lines(markdown: &str, width: u16) -> Vec<String> {
                         dest: dest_url.to_string(),
                     });
                 }
+                Tag::Emphasis => renderer.open_inline(SegmentKind::Emphasis, "*"),
+                Tag::Strong => renderer.open_inline(SegmentKind::Strong, "**"),
+                Tag::Strikethrough => renderer.open_inline(SegmentKind::Strikethrough, "~~"),
+                Tag::FootnoteDefinition(name) => renderer.open_footnote(&name),
+                Tag::HtmlBlock => {
+                    renderer.flush_current();
+                    renderer.in_html_block = true;
+                    renderer.html_blank = false;
+                }
+                Tag::DefinitionListTitle => renderer.flush_current(),
+                Tag::DefinitionListDefinition => {
+                    renderer.open_hanging(": ".to_string(), SegmentKind::ListBullet);
+                }
                 _ => {}
             },
             Event::End(tag_end) => match tag_end {
-                TagEnd::Heading(_) | TagEnd::Paragraph => renderer.flush_current(),
+                TagEnd::Heading(_) => renderer.close_heading(),
+                TagEnd::Paragraph => renderer.flush_current(),
                 TagEnd::BlockQuote(_) => {
                     renderer.flush_current();
                     renderer.blockquote_depth = renderer.blockquote_depth.saturating_sub(1);
@@ -229,37 +1036,25 @@ pub fn render_preview_lines(markdown: &str, width: u16) -> Vec<String> {
                     renderer.flush_current();
                     renderer.list_stack.pop();
                 }
-                TagEnd::Item => renderer.flush_current(),
+                TagEnd::Item => {
+                    renderer.flush_current();
+                    renderer.in_done_task = false;
+                }
                 TagEnd::CodeBlock => {
                     renderer.flush_current();
                     renderer.in_code_block = false;
+                    if let Some((_, close)) = renderer.code_block_lines.as_mut() {
+                        *close = Some(renderer.lines.len());
+                    }
                     let mut fence = renderer.quote_prefix();
                     fence.push_str("```");
-                    renderer.push_line(fence);
-                }
-                TagEnd::TableHead => {
-                    if renderer.table_head_needs_separator && !renderer.table_row.is_empty() {
-                        let row = format!("| {} |", renderer.table_row.join(" | "));
-                        let mut line = renderer.quote_prefix();
-                        line.push_str(&row);
-                        renderer.push_line(line);
-
-                        let sep =
-                            format!("| {} |", vec!["-"; renderer.table_row.len()].join(" | "));
-                        let mut sep_line = renderer.quote_prefix();
-                        sep_line.push_str(&sep);
-                        renderer.push_line(sep_line);
-
-                        renderer.table_row.clear();
-                        renderer.table_head_needs_separator = false;
-                    }
+                    renderer.push_line(fence, SegmentKind::Code);
                 }
-                TagEnd::TableRow => {
-                    let row = format!("| {} |", renderer.table_row.join(" | "));
-                    let mut line = renderer.quote_prefix();
-                    line.push_str(&row);
-                    renderer.push_line(line);
+                TagEnd::TableHead | TagEnd::TableRow => {
+                    let row = std::mem::take(&mut renderer.table_row);
+                    renderer.table_rows.push(row);
                 }
+                TagEnd::Table => renderer.push_table(),
                 TagEnd::TableCell => {
                     renderer.in_table_cell = false;
                     renderer.table_row.push(renderer.table_cell.clone());
@@ -267,24 +1062,60 @@ pub fn render_preview_lines(markdown: &str, width: u16) -> Vec<String> {
                 }
                 TagEnd::Link => {
                     if let Some(LinkState::Link { text, dest }) = renderer.link_stack.pop() {
-                        renderer.append_text(&format!("[{text}]({dest})"));
+                        let shown = match (renderer.reader, text.is_empty()) {
+                            (false, _) => format!("[{text}]({dest})"),
+                            (true, false) => text,
+                            (true, true) => dest,
+                        };
+                        renderer.append_span(&shown, Some(SegmentKind::Link));
                     }
                 }
                 TagEnd::Image => {
                     if let Some(LinkState::Image { alt, dest }) = renderer.link_stack.pop() {
-                        renderer.append_text(&format!("![{alt}]({dest})"));
+                        let shown = match (renderer.reader, alt.is_empty()) {
+                            (false, _) => format!("![{alt}]({dest})"),
+                            (true, false) => format!("🖼 {alt}"),
+                            (true, true) => format!("🖼 {dest}"),
+                        };
+                        renderer.append_span(&shown, Some(SegmentKind::Link));
                     }
                 }
+                TagEnd::Emphasis => renderer.close_inline("*"),
+                TagEnd::Strong => renderer.close_inline("**"),
+                TagEnd::Strikethrough => renderer.close_inline("~~"),
+                TagEnd::FootnoteDefinition => renderer.close_footnote(),
+                TagEnd::HtmlBlock => renderer.in_html_block = false,
+                TagEnd::DefinitionListTitle | TagEnd::DefinitionList => renderer.flush_current(),
+                TagEnd::DefinitionListDefinition => {
+                    renderer.close_hanging();
+                }
                 _ => {}
             },
             Event::Text(text) => {
+                let unmatched = (&*text == "[")
+                    .then(|| footnote_name_at(&markdown[range.start..]))
+                    .flatten();
                 if renderer.in_code_block {
                     renderer.push_code_text(&text);
+                } else if range.start < renderer.skip_text_to {
+                    // The rest of a `[^name]` rendered from its `[`.
+                } else if let Some(name) = unmatched {
+                    renderer.skip_text_to = range.start + name.len() + 3;
+                    let kind = if renderer.known_footnotes.contains(&name.to_lowercase()) {
+                        SegmentKind::FootnoteRef
+                    } else {
+                        SegmentKind::FootnoteMissing
+                    };
+                    renderer.append_span(&format!("[^{name}]"), Some(kind));
+                } else if renderer.emoji {
+                    renderer.append_text(&replace_shortcodes(&text));
                 } else {
                     renderer.append_text(&text);
                 }
             }
-            Event::Code(code) => renderer.append_text(&format!("`{code}`")),
+            Event::Code(code) => {
+                renderer.append_span(&format!("`{code}`"), Some(SegmentKind::Code));
+            }
             Event::SoftBreak => {
                 if renderer.link_stack.last().is_some() {
                     renderer.append_text(" ");
@@ -293,39 +1124,89 @@ pub fn render_preview_lines(markdown: &str, width: u16) -> Vec<String> {
                 }
             }
             Event::HardBreak => renderer.flush_current(),
-            Event::TaskListMarker(done) => renderer.append_text(if done { "[x] " } else { "[ ] " }),
+            Event::TaskListMarker(true) => {
+                renderer.append_span("[x] ", Some(SegmentKind::TaskDone));
+                renderer.in_done_task = true;
+            }
+            Event::TaskListMarker(false) => {
+                renderer.append_span("[ ] ", Some(SegmentKind::TaskPending));
+            }
             Event::Rule => {
                 renderer.flush_current();
                 let mut line = renderer.quote_prefix();
                 line.push_str("---");
-                renderer.push_line(line);
+                renderer.push_line(line, SegmentKind::Rule);
             }
+            Event::Html(html) if renderer.in_html_block => renderer.push_html(&html),
             Event::Html(html) | Event::InlineHtml(html) => renderer.append_text(&html),
-            Event::FootnoteReference(name) => renderer.append_text(&format!("[^{name}]")),
+            Event::FootnoteReference(name) => {
+                renderer.append_span(&format!("[^{name}]"), Some(SegmentKind::FootnoteRef));
+            }
             Event::InlineMath(math) => renderer.append_text(&format!("${math}$")),
             Event::DisplayMath(math) => {
                 renderer.flush_current();
                 let mut open = renderer.quote_prefix();
                 open.push_str("$$");
-                renderer.push_line(open);
+                renderer.push_line(open, SegmentKind::Code);
+                if math.starts_with('\n') {
+                    renderer.source_line += 1;
+                }
                 renderer.push_code_text(math.trim_matches('\n'));
                 let mut close = renderer.quote_prefix();
                 close.push_str("$$");
-                renderer.push_line(close);
+                renderer.push_line(close, SegmentKind::Code);
             }
         }
+        // An open footnote's lines move to the end, so they do not count.
+        if let Some(max) = max_lines
+            && renderer.footnote_start.unwrap_or(renderer.lines.len()) > max
+        {
+            renderer.truncate_lines(max);
+            return (renderer.lines, Vec::new(), true);
+        }
     }
 
     renderer.flush_current();
-    if renderer.lines.is_empty() {
-        renderer.lines.push(String::new());
+    if let Some(max) = max_lines
+        && renderer.lines.len() > max
+    {
+        renderer.truncate_lines(max);
+        return (renderer.lines, Vec::new(), true);
     }
-    renderer.lines
+    (renderer.lines, renderer.footnotes, false)
 }
 
-pub fn render_preview_segments(markdown: &str, width: u16) -> Vec<PreviewLine> {
-    let lines = render_preview_lines(markdown, width);
-    classify_lines(lines)
+/// The name in a `[^name]` at the start of `source`. pulldown-cmark leaves a
+/// reference without a definition in the text as `[`, `^name` and `]`.
+pub(crate) fn footnote_name_at(source: &str) -> Option<&str> {
+    let rest = source.strip_prefix("[^")?;
+    let name = &rest[..rest.find(']')?];
+    let plain = !name.is_empty()
+        && !name.contains(|c: char| {
+            c.is_whitespace() || matches!(c, '[' | '\\' | '*' | '_' | '`' | '<' | '&')
+        });
+    plain.then_some(name)
+}
+
+/// `{#id .class key=value}` as written after a heading, with a leading
+/// space, or nothing when the heading has no attributes.
+fn heading_attributes(
+    id: Option<CowStr<'_>>,
+    classes: Vec<CowStr<'_>>,
+    attrs: Vec<(CowStr<'_>, Option<CowStr<'_>>)>,
+) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    parts.extend(id.map(|id| format!("#{id}")));
+    parts.extend(classes.iter().map(|class| format!(".{class}")));
+    parts.extend(attrs.iter().map(|(key, value)| match value {
+        Some(value) => format!("{key}={value}"),
+        None => key.to_string(),
+    }));
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!(" {{{}}}", parts.join(" "))
+    }
 }
 
 fn heading_prefix(level: HeadingLevel) -> &'static str {
@@ -339,20 +1220,24 @@ fn heading_prefix(level: HeadingLevel) -> &'static str {
     }
 }
 
+/// Hard-wrap at `width` terminal cells; wide glyphs never straddle a break.
 fn wrap_line(input: &str, width: usize) -> Vec<String> {
-    if input.chars().count() <= width {
+    if input.width() <= width {
         return vec![input.to_string()];
     }
 
     let mut chunks = Vec::new();
     let mut buf = String::new();
+    let mut buf_width = 0;
 
     for ch in input.chars() {
-        buf.push(ch);
-        if buf.chars().count() == width {
-            chunks.push(buf.clone());
-            buf.clear();
+        let ch_width = ch.width().unwrap_or(0);
+        if buf_width + ch_width > width && !buf.is_empty() {
+            chunks.push(std::mem::take(&mut buf));
+            buf_width = 0;
         }
+        buf.push(ch);
+        buf_width += ch_width;
     }
 
     if !buf.is_empty() {
@@ -362,108 +1247,197 @@ fn wrap_line(input: &str, width: usize) -> Vec<String> {
     chunks
 }
 
-fn classify_lines(lines: Vec<String>) -> Vec<PreviewLine> {
-    let mut out = Vec::with_capacity(lines.len());
-    let mut in_code_block = false;
-    let mut pending_table_header = false;
+/// `text` fitted to exactly `width` cells: cut with an ellipsis when too
+/// wide, padded on the side(s) `alignment` leaves open.
+fn pad_cell(text: &str, width: usize, alignment: Alignment) -> String {
+    let text = if text.width() > width {
+        let cut = split_at_width(text, width.saturating_sub(1));
+        format!("{}…", &text[..cut])
+    } else {
+        text.to_string()
+    };
+    let pad = width.saturating_sub(text.width());
+    let left = match alignment {
+        Alignment::Right => pad,
+        Alignment::Center => pad / 2,
+        Alignment::Left | Alignment::None => 0,
+    };
+    format!("{}{text}{}", " ".repeat(left), " ".repeat(pad - left))
+}
 
-    for line in lines {
-        let trimmed = line.trim_start();
-        if trimmed.starts_with("```") || trimmed == "$$" {
-            in_code_block = !in_code_block;
-            out.push(single(line, SegmentKind::Code));
-            continue;
-        }
+/// A separator cell of `width` dashes, with colons marking the alignment.
+fn separator_cell(width: usize, alignment: Alignment) -> String {
+    let mut cell = vec!['-'; width.max(1)];
+    if matches!(alignment, Alignment::Left | Alignment::Center) {
+        cell[0] = ':';
+    }
+    if matches!(alignment, Alignment::Right | Alignment::Center) {
+        let last = cell.len() - 1;
+        cell[last] = ':';
+    }
+    cell.into_iter().collect()
+}
 
-        if in_code_block {
-            out.push(single(line, SegmentKind::Code));
-            continue;
+/// Byte index where the first `cells` terminal cells of `text` end.
+fn split_at_width(text: &str, cells: usize) -> usize {
+    let mut used = 0;
+    for (idx, ch) in text.char_indices() {
+        used += ch.width().unwrap_or(0);
+        if used > cells {
+            return idx;
         }
+    }
+    text.len()
+}
 
-        if trimmed.starts_with("| ") && trimmed.ends_with(" |") {
-            if pending_table_header {
-                out.push(single(line, SegmentKind::TableHeader));
-                pending_table_header = false;
-            } else if is_table_separator(trimmed) {
-                out.push(single(line, SegmentKind::Plain));
-            } else {
-                out.push(single(line, SegmentKind::Plain));
-                pending_table_header = true;
-            }
-            continue;
-        } else {
-            pending_table_header = false;
-        }
+/// The continuation indent actually used when wrapping: dropped when it
+/// would leave no room for text.
+fn wrap_indent(indent: &str, width: usize) -> &str {
+    if indent.width() < width { indent } else { "" }
+}
 
-        if trimmed.starts_with("# ") || trimmed.starts_with("## ") || trimmed.starts_with("### ") {
-            out.push(single(line, SegmentKind::Heading));
-            continue;
-        }
-        if trimmed.starts_with("> ") {
-            out.push(single(line, SegmentKind::Quote));
-            continue;
-        }
-        if let Some(rest) = trimmed.strip_prefix("- ") {
-            let segments = vec![
-                PreviewSegment {
-                    text: "- ".to_string(),
-                    kind: SegmentKind::ListBullet,
-                },
-                PreviewSegment {
-                    text: rest.to_string(),
-                    kind: SegmentKind::Plain,
-                },
-            ];
-            out.push(PreviewLine { segments });
-            continue;
-        }
-        if let Some((idx, _)) = trimmed.char_indices().find(|(_, c)| !c.is_ascii_digit())
-            && idx > 0
-            && trimmed[idx..].starts_with(". ")
-        {
-            let segments = vec![
-                PreviewSegment {
-                    text: trimmed[..idx + 2].to_string(),
-                    kind: SegmentKind::ListBullet,
-                },
-                PreviewSegment {
-                    text: trimmed[idx + 2..].to_string(),
-                    kind: SegmentKind::Plain,
-                },
-            ];
-            out.push(PreviewLine { segments });
+/// Wrap prose at word boundaries, returning the byte range of `body` that
+/// lands on each line. `prefix` starts the first line and `indent` every
+/// continuation line; neither is a break point. Tokens longer than the
+/// available width are hard-split.
+fn wrap_word_ranges(prefix: &str, body: &str, indent: &str, width: usize) -> Vec<Range<usize>> {
+    if prefix.width() + body.width() <= width {
+        #[allow(clippy::single_range_in_vec_init)]
+        return vec![0..body.len()];
+    }
+
+    let indent_width = indent.width();
+    let mut ranges = Vec::new();
+    let mut line = 0..0;
+    let mut line_len = prefix.width();
+    let mut has_word = false;
+    let mut offset = 0;
+
+    for word in body.split(' ') {
+        let word_start = offset;
+        offset += word.len() + 1;
+        if word.is_empty() && !has_word {
             continue;
         }
-        if trimmed.contains("](") && trimmed.contains('[') && trimmed.ends_with(')') {
-            out.push(single(line, SegmentKind::Link));
-            continue;
+        let word_len = word.width();
+        if has_word {
+            if line_len + 1 + word_len <= width {
+                line.end = word_start + word.len();
+                line_len += 1 + word_len;
+                continue;
+            }
+            ranges.push(line.clone());
+            line_len = indent_width;
+            has_word = false;
+            if word.is_empty() {
+                continue;
+            }
         }
 
-        out.push(single(line, SegmentKind::Plain));
+        line = word_start..word_start;
+        let mut rest = word_start..word_start + word.len();
+        while line_len + body[rest.clone()].width() > width {
+            let room = width.saturating_sub(line_len);
+            let mut split = split_at_width(&body[rest.clone()], room);
+            if split == 0 {
+                if line_len > indent_width {
+                    ranges.push(line.clone());
+                    line_len = indent_width;
+                    continue;
+                }
+                // Not even one glyph fits on an empty line; take it anyway.
+                split = body[rest.clone()]
+                    .chars()
+                    .next()
+                    .map_or(rest.len(), char::len_utf8);
+            }
+            let head = rest.start..rest.start + split;
+            rest.start = head.end;
+            line.end = head.end;
+            if rest.is_empty() {
+                line_len += body[head].width();
+                break;
+            }
+            ranges.push(line.clone());
+            line = rest.start..rest.start;
+            line_len = indent_width;
+        }
+        line.end = rest.end;
+        line_len += body[rest].width();
+        has_word = true;
     }
 
-    out
-}
-
-fn single(line: String, kind: SegmentKind) -> PreviewLine {
-    PreviewLine {
-        segments: vec![PreviewSegment { text: line, kind }],
+    if has_word || ranges.is_empty() {
+        ranges.push(line);
     }
+    ranges
 }
 
-fn is_table_separator(line: &str) -> bool {
-    let inner = line.trim_start_matches('|').trim_end_matches('|').trim();
-    if inner.is_empty() {
-        return false;
+/// Segments covering all of `line`: the given spans, plain text between
+/// them, neighbours of the same kind merged. Always yields at least one
+/// segment.
+fn split_spans(line: &str, spans: &[Span]) -> Vec<PreviewSegment> {
+    let mut segments: Vec<PreviewSegment> = Vec::new();
+    let mut push = |text: &str, kind: SegmentKind| match segments.last_mut() {
+        Some(last) if last.kind == kind => last.text.push_str(text),
+        _ => segments.push(PreviewSegment {
+            text: text.to_string(),
+            kind,
+        }),
+    };
+    let mut pos = 0;
+    for span in spans {
+        let start = span.range.start.max(pos);
+        if start >= span.range.end {
+            continue;
+        }
+        if pos < start {
+            push(&line[pos..start], SegmentKind::Plain);
+        }
+        push(&line[start..span.range.end], span.kind);
+        pos = span.range.end;
+    }
+    if pos < line.len() || pos == 0 {
+        push(&line[pos..], SegmentKind::Plain);
     }
-    inner
-        .split('|')
-        .all(|cell| !cell.trim().is_empty() && cell.trim().chars().all(|c| c == '-' || c == ':'))
+    segments
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{SegmentKind, render_preview_lines, render_preview_segments};
+    use unicode_width::UnicodeWidthStr;
+
+    use super::{
+        FenceLine, FenceTracker, PreviewLine, SegmentKind, render_preview_lines,
+        render_preview_lines_bounded, render_preview_segments, unfenced_line_start, wrap_indent,
+        wrap_line, wrap_word_ranges,
+    };
+
+    #[test]
+    fn fences_close_only_on_a_matching_bare_run() {
+        let text = "a\n  ````rust\n```\n~~~~\n````` x\n   `````  \nb\n~~~\n    ~~~\n";
+        let mut fences = FenceTracker::default();
+        let kinds: Vec<FenceLine> = text.lines().map(|line| fences.line(line)).collect();
+        use FenceLine::{Close, Inside, Open, Outside};
+        assert_eq!(
+            kinds,
+            [
+                Outside, Open, Inside, Inside, Inside, Close, Outside, Open, Inside
+            ]
+        );
+        assert!(fences.is_open());
+    }
+
+    fn wrap_words(prefix: &str, body: &str, indent: &str, width: usize) -> Vec<String> {
+        let indent = wrap_indent(indent, width);
+        wrap_word_ranges(prefix, body, indent, width)
+            .into_iter()
+            .enumerate()
+            .map(|(idx, range)| {
+                format!("{}{}", if idx == 0 { prefix } else { indent }, &body[range])
+            })
+            .collect()
+    }
 
     #[test]
     fn renders_heading_and_list() {
@@ -481,6 +1455,30 @@ mod tests {
         assert_eq!(lines[1], "- [ ] todo");
     }
 
+    #[test]
+    fn wraps_wide_glyphs_by_display_width() {
+        assert_eq!(wrap_line("你好世界", 8), vec!["你好世界"]);
+        assert_eq!(wrap_line("你好世界", 5), vec!["你好", "世界"]);
+        assert_eq!(wrap_line("你好世界", 6), vec!["你好世", "界"]);
+
+        let lines = render_preview_lines("你好世界你好世界", 10);
+        assert_eq!(lines, vec!["你好世界你", "好世界"]);
+        assert!(lines.iter().all(|line| line.width() <= 10));
+    }
+
+    #[test]
+    fn wrap_words_measures_cells_not_chars() {
+        let wrapped = wrap_words("", "日本語 テキスト です", "", 10);
+        assert_eq!(wrapped, vec!["日本語", "テキスト", "です"]);
+
+        // A glyph wider than the whole line still makes progress.
+        assert_eq!(wrap_words("", "日本", "", 1), vec!["日", "本"]);
+
+        // Combining marks add no width.
+        let accented = "cafe\u{301} ole\u{301}";
+        assert_eq!(wrap_words("", accented, "", 9), vec![accented]);
+    }
+
     #[test]
     fn wraps_long_lines() {
         let src = "abcdefghij";
@@ -488,6 +1486,44 @@ mod tests {
         assert_eq!(lines, vec!["abcdefgh", "ij"]);
     }
 
+    #[test]
+    fn wraps_paragraph_at_word_boundaries() {
+        let src = "the quick brown fox jumps over the lazy dog";
+        let lines = render_preview_lines(src, 16);
+        assert_eq!(
+            lines,
+            vec!["the quick brown", "fox jumps over", "the lazy dog"]
+        );
+    }
+
+    #[test]
+    fn wraps_nested_list_item_with_hanging_indent() {
+        let src = "- parent\n  - child item that wraps";
+        let lines = render_preview_lines(src, 16);
+        assert_eq!(lines, vec!["- parent", "  - child item", "    that wraps"]);
+    }
+
+    #[test]
+    fn wraps_blockquote_across_three_lines_with_prefix() {
+        let src = "> alpha beta gamma delta epsilon";
+        let lines = render_preview_lines(src, 14);
+        assert_eq!(lines, vec!["> alpha beta", "> gamma delta", "> epsilon"]);
+    }
+
+    #[test]
+    fn hard_splits_tokens_longer_than_width_after_words() {
+        let src = "ok abcdefghijklmnop";
+        let lines = render_preview_lines(src, 8);
+        assert_eq!(lines, vec!["ok", "abcdefgh", "ijklmnop"]);
+    }
+
+    #[test]
+    fn keeps_code_block_lines_hard_split() {
+        let src = "```\nlet a = b + c;\n```";
+        let lines = render_preview_lines(src, 8);
+        assert_eq!(lines, vec!["```", "let a = ", "b + c;", "```"]);
+    }
+
     #[test]
     fn renders_blockquote_and_ordered_list() {
         let src = "> quoted\n\n1. first\n2. second";
@@ -537,10 +1573,53 @@ mod tests {
     }
 
     #[test]
-    fn renders_inline_code_and_strike_text_fallback() {
-        let src = "a `code` and ~~gone~~";
+    fn plain_output_keeps_emphasis_markers() {
+        let src = "a `code` and ~~gone~~, **bold** _it_ [**x**](https://x)";
         let lines = render_preview_lines(src, 80);
-        assert_eq!(lines, vec!["a `code` and gone"]);
+        assert_eq!(
+            lines,
+            vec!["a `code` and ~~gone~~, **bold** *it* [**x**](https://x)"]
+        );
+    }
+
+    fn kinds(line: &PreviewLine) -> Vec<(&str, SegmentKind)> {
+        line.segments
+            .iter()
+            .map(|segment| (segment.text.as_str(), segment.kind))
+            .collect()
+    }
+
+    #[test]
+    fn segments_drop_emphasis_markers() {
+        let lines = render_preview_segments("say **very *loud*** now", 80);
+        assert_eq!(
+            kinds(&lines[0]),
+            vec![
+                ("say ", SegmentKind::Plain),
+                ("very ", SegmentKind::Strong),
+                ("loud", SegmentKind::Emphasis),
+                (" now", SegmentKind::Plain),
+            ]
+        );
+    }
+
+    #[test]
+    fn emphasis_segments_follow_wrapped_text() {
+        let lines = render_preview_segments("plain **bold words that wrap** end", 16);
+        assert_eq!(
+            kinds(&lines[0]),
+            vec![
+                ("plain ", SegmentKind::Plain),
+                ("bold words", SegmentKind::Strong)
+            ]
+        );
+        assert_eq!(
+            kinds(&lines[1]),
+            vec![
+                ("that wrap", SegmentKind::Strong),
+                (" end", SegmentKind::Plain)
+            ]
+        );
     }
 
     #[test]
@@ -582,6 +1661,94 @@ mod tests {
         assert!(lines.join("\n").contains("$y$"));
     }
 
+    #[test]
+    fn footnotes_gather_in_a_section_at_the_end() {
+        let src = "Claim[^b] and[^a], not[^gone].\n\n\
+                   [^a]: First, with text that wraps.\n\n    More of it.\n\n\
+                   [^b]: Second.\n\nAfter.\n";
+        let lines = render_preview_segments(src, 32);
+        let texts: Vec<String> = lines.iter().map(PreviewLine::text).collect();
+        assert_eq!(
+            texts,
+            [
+                "Claim[^b] and[^a], not[^gone].",
+                "After.",
+                "Footnotes",
+                "[^a]: First, with text that",
+                "      wraps.",
+                "      More of it.",
+                "[^b]: Second.",
+            ]
+        );
+        assert_eq!(
+            kinds(&lines[0]),
+            vec![
+                ("Claim", SegmentKind::Plain),
+                ("[^b]", SegmentKind::FootnoteRef),
+                (" and", SegmentKind::Plain),
+                ("[^a]", SegmentKind::FootnoteRef),
+                (", not", SegmentKind::Plain),
+                ("[^gone]", SegmentKind::FootnoteMissing),
+                (".", SegmentKind::Plain),
+            ]
+        );
+        assert_eq!(kinds(&lines[2]), vec![("Footnotes", SegmentKind::Heading)]);
+        assert_eq!(
+            kinds(&lines[6]),
+            vec![
+                ("[^b]: ", SegmentKind::FootnoteRef),
+                ("Second.", SegmentKind::Plain)
+            ]
+        );
+        assert_eq!(render_preview_lines("x[^y]", 80), ["x[^y]"]);
+    }
+
+    #[test]
+    fn html_blocks_render_line_by_line() {
+        let src = "Intro\n\n<details>\n<summary>More</summary>\n<ul>\n  <li>one</li>\n  \
+                   <li>two</li>\n  <li>three</li>\n</ul>\n<p>Closing words</p>\n\
+                   <em>end</em>\n</details>\n\n<!--\na\n\n\n\nb\n-->\n";
+        let lines = render_preview_segments(src, 30);
+        let texts: Vec<String> = lines.iter().map(PreviewLine::text).collect();
+        assert_eq!(texts[0], "Intro");
+        assert_eq!(texts[1], "<details>");
+        assert_eq!(texts[4], "  <li>one</li>");
+        assert_eq!(texts[10], "</details>");
+        assert_eq!(texts[11..], ["<!--", "a", "", "b", "-->"]);
+        for line in &lines[1..11] {
+            assert!(line.text().width() <= 30);
+            assert_eq!(kinds(line), vec![(line.text().as_str(), SegmentKind::Html)]);
+        }
+        assert_eq!(render_preview_lines("a <b>x</b> c", 80), ["a <b>x</b> c"]);
+    }
+
+    #[test]
+    fn definitions_hang_under_their_term() {
+        let src = "Term\n: A definition long enough to wrap.\n: Another.\n\n\
+                   Loose\n\n: First paragraph.\n\n  Second paragraph.\n";
+        let lines = render_preview_segments(src, 24);
+        let texts: Vec<String> = lines.iter().map(PreviewLine::text).collect();
+        assert_eq!(
+            texts,
+            [
+                "Term",
+                ": A definition long",
+                "  enough to wrap.",
+                ": Another.",
+                "Loose",
+                ": First paragraph.",
+                "  Second paragraph.",
+            ]
+        );
+        assert_eq!(
+            kinds(&lines[1]),
+            vec![
+                (": ", SegmentKind::ListBullet),
+                ("A definition long", SegmentKind::Plain)
+            ]
+        );
+    }
+
     #[test]
     fn renders_display_math_block_with_fences() {
         let src = "$$\na+b\n$$";
@@ -593,22 +1760,256 @@ mod tests {
 
     #[test]
     fn renders_segment_kinds_for_heading_list_link_code_and_quote() {
-        let src = "# Title\n- item\n[site](https://x)\n```rs\nlet x = 1;\n```\n> q";
+        let src = "# Title\n- item\n\n[site](https://x)\n```rs\nlet x = 1;\n```\n> q\n\n---";
         let lines = render_preview_segments(src, 80);
-        assert_eq!(lines[0].segments[0].kind, SegmentKind::Heading);
-        assert_eq!(lines[1].segments[0].kind, SegmentKind::ListBullet);
+        assert_eq!(kinds(&lines[0]), vec![("# Title", SegmentKind::Heading)]);
+        assert_eq!(
+            kinds(&lines[1]),
+            vec![
+                ("- ", SegmentKind::ListBullet),
+                ("item", SegmentKind::Plain)
+            ]
+        );
         assert_eq!(lines[2].segments[0].kind, SegmentKind::Link);
         assert_eq!(lines[3].segments[0].kind, SegmentKind::Code);
         assert_eq!(lines[4].segments[0].kind, SegmentKind::Code);
-        assert_eq!(lines[6].segments[0].kind, SegmentKind::Quote);
+        assert_eq!(kinds(&lines[6]), vec![("> q", SegmentKind::Quote)]);
+        assert_eq!(kinds(&lines[7]), vec![("---", SegmentKind::Rule)]);
+    }
+
+    #[test]
+    fn segment_kinds_come_from_structure_not_text() {
+        let src =
+            "1\\. not a list\n\n    ```\n    still code\n\nafter\n\n> | a |\n> |---|\n> | 1 |";
+        let lines = render_preview_segments(src, 80);
+        let texts: Vec<_> = lines.iter().map(PreviewLine::text).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "1. not a list",
+                "```",
+                "```",
+                "still code",
+                "```",
+                "after",
+                "> | a |",
+                "> | - |",
+                "> | 1 |",
+            ]
+        );
+        assert_eq!(
+            kinds(&lines[0]),
+            vec![("1. not a list", SegmentKind::Plain)]
+        );
+        for line in &lines[1..5] {
+            assert_eq!(line.segments[0].kind, SegmentKind::Code);
+        }
+        assert_eq!(kinds(&lines[5]), vec![("after", SegmentKind::Plain)]);
+        assert_eq!(lines[6].segments[0].kind, SegmentKind::TableHeader);
+        assert_eq!(lines[8].segments[0].kind, SegmentKind::Plain);
+    }
+
+    #[test]
+    fn quote_and_task_prefixes_get_their_own_kinds() {
+        let lines = render_preview_segments(
+            "> long quoted text wraps here\n\n- [x] done\n- [ ] *todo*\n  - [ ] sub",
+            16,
+        );
+        assert_eq!(
+            kinds(&lines[0]),
+            vec![("> long quoted", SegmentKind::Quote)]
+        );
+        assert_eq!(kinds(&lines[1]), vec![("> text wraps", SegmentKind::Quote)]);
+        assert_eq!(
+            kinds(&lines[3]),
+            vec![
+                ("- ", SegmentKind::ListBullet),
+                ("[x] ", SegmentKind::TaskDone),
+                ("done", SegmentKind::Strikethrough),
+            ]
+        );
+        assert_eq!(
+            kinds(&lines[4]),
+            vec![
+                ("- ", SegmentKind::ListBullet),
+                ("[ ] ", SegmentKind::TaskPending),
+                ("todo", SegmentKind::Emphasis),
+            ]
+        );
+        assert_eq!(
+            kinds(&lines[5]),
+            vec![
+                ("  ", SegmentKind::Plain),
+                ("- ", SegmentKind::ListBullet),
+                ("[ ] ", SegmentKind::TaskPending),
+                ("sub", SegmentKind::Plain),
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_inline_segment_kinds_on_one_line() {
+        let src = "**bold** then *it* with `code` and [site](https://x) ~~old~~";
+        let lines = render_preview_segments(src, 80);
+        let segments: Vec<_> = lines[0]
+            .segments
+            .iter()
+            .map(|segment| (segment.text.as_str(), segment.kind))
+            .collect();
+        assert_eq!(
+            segments,
+            vec![
+                ("bold", SegmentKind::Strong),
+                (" then ", SegmentKind::Plain),
+                ("it", SegmentKind::Emphasis),
+                (" with ", SegmentKind::Plain),
+                ("`code`", SegmentKind::Code),
+                (" and ", SegmentKind::Plain),
+                ("site", SegmentKind::Link),
+                (" ", SegmentKind::Plain),
+                ("old", SegmentKind::Strikethrough),
+            ]
+        );
+
+        let item = render_preview_segments("- a **b**", 80);
+        assert_eq!(item[0].segments[0].kind, SegmentKind::ListBullet);
+        assert_eq!(item[0].segments[2].text, "b");
+        assert_eq!(item[0].segments[2].kind, SegmentKind::Strong);
+    }
+
+    #[test]
+    fn aligns_table_columns() {
+        let src = "| l | c | r |\n|:--|:-:|--:|\n| long | mid | 1 |";
+        assert_eq!(
+            render_preview_lines(src, 80),
+            vec![
+                "| l    |  c  |  r |",
+                "| :--- | :-: | -: |",
+                "| long | mid |  1 |",
+            ]
+        );
+    }
+
+    #[test]
+    fn pads_table_cells_by_display_width() {
+        let src = "| 名前 | x |\n|---|---|\n| 山田太郎 | y |";
+        let lines = render_preview_lines(src, 80);
+        assert_eq!(
+            lines,
+            vec!["| 名前     | x |", "| -------- | - |", "| 山田太郎 | y |"]
+        );
+        assert!(lines.iter().all(|line| line.width() == lines[0].width()));
+    }
+
+    #[test]
+    fn truncates_tables_wider_than_the_width() {
+        let src = "| aaaaaaaaaa | bbbbbbbbbb |\n|---|---|\n| 1 | 2 |";
+        let lines = render_preview_lines(src, 20);
+        assert_eq!(
+            lines,
+            vec![
+                "| aaaaa… | bbbbbb… |",
+                "| ------ | ------- |",
+                "| 1      | 2       |",
+            ]
+        );
+        assert!(lines.iter().all(|line| line.width() <= 20));
     }
 
     #[test]
     fn renders_segment_kind_for_table_header() {
         let src = "| a | b |\n| - | - |\n| 1 | 2 |";
         let lines = render_preview_segments(src, 80);
-        assert_eq!(lines[0].segments[0].kind, SegmentKind::Plain);
-        assert_eq!(lines[1].segments[0].kind, SegmentKind::TableHeader);
+        assert_eq!(lines[0].segments[0].kind, SegmentKind::TableHeader);
+        assert_eq!(lines[1].segments[0].kind, SegmentKind::Plain);
         assert_eq!(lines[2].segments[0].kind, SegmentKind::Plain);
     }
+
+    #[test]
+    fn unfenced_line_start_skips_past_open_fences() {
+        let src = "intro\n```rs\ncode\n~~~\n```\nafter\n````\nunclosed\n";
+        assert_eq!(unfenced_line_start(src, 0), 0);
+        assert_eq!(unfenced_line_start(src, 2), 6);
+        assert_eq!(unfenced_line_start(src, 6), 6);
+        // Inside the first fence: the cut moves past its closing line.
+        assert_eq!(unfenced_line_start(src, 7), 25);
+        assert_eq!(unfenced_line_start(src, 14), 25);
+        assert_eq!(&src[25..], "after\n````\nunclosed\n");
+        // A fence that never closes keeps the plain line start.
+        assert_eq!(unfenced_line_start(src, 32), 36);
+        assert_eq!(unfenced_line_start(src, 100), src.len());
+        assert_eq!(unfenced_line_start("", 0), 0);
+    }
+
+    #[test]
+    fn bounded_render_stops_early_in_huge_documents() {
+        let mut src = String::new();
+        for idx in 0..100_000 {
+            if idx % 5 == 4 {
+                src.push('\n');
+            } else {
+                src.push_str(&format!("Line {idx} with *some* words.\n"));
+            }
+        }
+        let started = std::time::Instant::now();
+        let (lines, truncated) = render_preview_lines_bounded(&src, 80, 50);
+        let elapsed = started.elapsed();
+        assert!(truncated);
+        assert_eq!(lines.len(), 50);
+        assert!(elapsed.as_millis() < 50, "{elapsed:?}");
+
+        let head: String = src
+            .lines()
+            .take(200)
+            .map(|line| format!("{line}\n"))
+            .collect();
+        assert_eq!(lines, render_preview_lines(&head, 80)[..50]);
+
+        let short = "# Title\n\nBody[^a].\n\n[^a]: Note.\n";
+        let full = render_preview_lines(short, 80);
+        assert_eq!(
+            render_preview_lines_bounded(short, 80, full.len()),
+            (full.clone(), false)
+        );
+        assert_eq!(
+            render_preview_lines_bounded(short, 80, 2),
+            (full[..2].to_vec(), true)
+        );
+    }
+
+    #[test]
+    fn preview_segments_show_emoji_and_plain_lines_keep_shortcodes() {
+        let src = "Done :tada: with `:tada:`\n";
+        assert_eq!(
+            render_preview_segments(src, 80)[0].text(),
+            "Done 🎉 with `:tada:`"
+        );
+        assert_eq!(render_preview_lines(src, 80), ["Done :tada: with `:tada:`"]);
+    }
+
+    #[test]
+    fn bounded_render_closes_a_code_block_it_cuts() {
+        let src = "# Code\n\n> ```rust\n> one\n> two\n> three\n> ```\n\nAfter.\n";
+        assert_eq!(
+            render_preview_lines_bounded(src, 80, 4),
+            (
+                vec![
+                    "# Code".into(),
+                    "> ```rust".into(),
+                    "> one".into(),
+                    "> ```".into()
+                ],
+                true
+            )
+        );
+        // No room for both fences: the block is left out.
+        assert_eq!(
+            render_preview_lines_bounded(src, 80, 2),
+            (vec!["# Code".to_string()], true)
+        );
+        let (lines, truncated) = render_preview_lines_bounded(src, 80, 6);
+        assert!(truncated);
+        assert_eq!(lines[5], "> ```");
+        assert_eq!(render_preview_lines_bounded(src, 80, 7).0.len(), 7);
+    }
 }
@@ -0,0 +1,136 @@
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+use crate::front_matter::blank_front_matter;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineEntry {
+    /// Heading depth, `1` for `#` through `6` for `######`.
+    pub level: u8,
+    pub text: String,
+    /// Zero-based line in the source where the heading starts.
+    pub source_line: usize,
+}
+
+/// The anchor GitHub gives a heading: lowercase, spaces as `-`, and
+/// punctuation other than `-` and `_` dropped.
+pub fn heading_slug(text: &str) -> String {
+    text.chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Headings in document order, for jumping around long files.
+pub fn extract_outline(markdown: &str) -> Vec<OutlineEntry> {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_TASKLISTS);
+
+    let mut entries = Vec::new();
+    let mut current: Option<OutlineEntry> = None;
+    let mut line = 0;
+    let mut counted_to = 0;
+    // A `title:` line over the closing `---` is not a heading.
+    let parsed = blank_front_matter(markdown);
+
+    for (event, range) in Parser::new_ext(&parsed, options).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                line += markdown[counted_to..range.start].matches('\n').count();
+                counted_to = range.start;
+                current = Some(OutlineEntry {
+                    level: level as u8,
+                    text: String::new(),
+                    source_line: line,
+                });
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(entry) = current.as_mut() {
+                    entry.text.push_str(&text);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some(entry) = current.as_mut() {
+                    entry.text.push(' ');
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some(mut entry) = current.take() {
+                    entry.text = entry.text.trim().to_string();
+                    entries.push(entry);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OutlineEntry, extract_outline, heading_slug};
+
+    fn entry(level: u8, text: &str, source_line: usize) -> OutlineEntry {
+        OutlineEntry {
+            level,
+            text: text.into(),
+            source_line,
+        }
+    }
+
+    #[test]
+    fn collects_atx_headings_with_lines() {
+        let src = "# Title\n\nintro\n\n## Setup\ntext\n### Deep `code`\n";
+        assert_eq!(
+            extract_outline(src),
+            vec![
+                entry(1, "Title", 0),
+                entry(2, "Setup", 4),
+                entry(3, "Deep code", 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn handles_setext_quoted_and_duplicate_headings() {
+        let src =
+            "Title\n=====\n\n> ## Quoted\n\nSub\n---\n\n## Sub\n\n```\n# not a heading\n```\n";
+        assert_eq!(
+            extract_outline(src),
+            vec![
+                entry(1, "Title", 0),
+                entry(2, "Quoted", 3),
+                entry(2, "Sub", 5),
+                entry(2, "Sub", 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn front_matter_keys_are_not_headings() {
+        let src = "---\ntitle: Notes\n---\n# Real\n\n---\nLater\n---\n";
+        assert_eq!(
+            extract_outline(src),
+            vec![entry(1, "Real", 3), entry(2, "Later", 6)]
+        );
+    }
+
+    #[test]
+    fn heading_slugs_match_github_anchors() {
+        assert_eq!(heading_slug("Getting Started"), "getting-started");
+        assert_eq!(heading_slug("What's new in v2.0?"), "whats-new-in-v20");
+        assert_eq!(heading_slug("snake_case-and-dash"), "snake_case-and-dash");
+    }
+
+    #[test]
+    fn empty_document_has_no_outline() {
+        assert!(extract_outline("").is_empty());
+        assert!(extract_outline("just text\n").is_empty());
+    }
+}
@@ -0,0 +1,498 @@
+use crate::markdown::{
+    MIN_WIDTH, PreviewSegment, RenderFlags, SegmentKind, StyledLine, render_lines,
+    render_lines_bounded,
+};
+
+/// How lines longer than [`RenderOptions::width`] are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WrapMode {
+    /// Break prose at spaces, keeping list and quote indents on continuation
+    /// lines. Code, tables and words longer than the width are hard-split.
+    #[default]
+    Words,
+    /// Never wrap; `width` is ignored and tables keep their natural width.
+    None,
+}
+
+/// How a YAML (`---`) or TOML (`+++`) front matter block opening the
+/// document is shown. Only a block at the very start counts; a `---` rule
+/// anywhere else is still a rule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FrontMatterStyle {
+    /// One line counting its keys, such as `--- front matter: 3 keys ---`.
+    #[default]
+    Folded,
+    /// Every line as written, between its delimiters.
+    Expanded,
+}
+
+/// Options for [`render`].
+///
+/// Build with [`RenderOptions::new`] or [`Default`] and the `with_*`
+/// methods; fields may be added in minor releases.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RenderOptions {
+    /// Line width in terminal cells, measured with Unicode display width.
+    /// Widths below 8 render as 8.
+    pub width: u16,
+    pub wrap: WrapMode,
+    /// Fill [`RenderedLine::segments`]. Without segments, emphasis keeps its
+    /// `*`, `**` and `~~` markers in the text so it still reads as Markdown.
+    pub include_segments: bool,
+    /// Render headings the way the source writes them: setext headings keep
+    /// their `===`/`---` underline instead of becoming `# Title`, and
+    /// `{#id .class}` attributes are parsed and printed after the text.
+    /// Without it, attributes stay part of the heading text.
+    pub source_headings: bool,
+    /// Show GitHub `:shortcode:` emoji in prose, such as `:tada:`, as the
+    /// emoji. Code and URLs are left alone, and unknown names stay as
+    /// written.
+    pub emoji: bool,
+    /// Front matter lines are [`SegmentKind::FrontMatter`] either way.
+    pub front_matter: FrontMatterStyle,
+    /// Reader mode: a link shows only its text and an image shows as
+    /// `🖼 alt`, both as [`SegmentKind::Link`]. Without it they keep their
+    /// `[text](url)` and `![alt](url)` source, as piped output does.
+    pub reader: bool,
+    /// Stop after this many lines instead of rendering the whole document,
+    /// parsing only as much of it as that takes, and set
+    /// [`RenderedDoc::truncated`] when something was left out. A code block
+    /// cut short still ends with its closing fence. Budgets below 1 count as 1.
+    pub max_lines: Option<usize>,
+}
+
+impl Default for RenderOptions {
+    /// 80 cells, word wrap, with segments.
+    fn default() -> Self {
+        Self::new(80)
+    }
+}
+
+impl RenderOptions {
+    pub fn new(width: u16) -> Self {
+        Self {
+            width,
+            wrap: WrapMode::Words,
+            include_segments: true,
+            source_headings: false,
+            emoji: false,
+            front_matter: FrontMatterStyle::Folded,
+            reader: false,
+            max_lines: None,
+        }
+    }
+
+    pub fn with_wrap(mut self, wrap: WrapMode) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn with_segments(mut self, include_segments: bool) -> Self {
+        self.include_segments = include_segments;
+        self
+    }
+
+    pub fn with_source_headings(mut self, source_headings: bool) -> Self {
+        self.source_headings = source_headings;
+        self
+    }
+
+    pub fn with_emoji(mut self, emoji: bool) -> Self {
+        self.emoji = emoji;
+        self
+    }
+
+    pub fn with_front_matter(mut self, front_matter: FrontMatterStyle) -> Self {
+        self.front_matter = front_matter;
+        self
+    }
+
+    pub fn with_reader(mut self, reader: bool) -> Self {
+        self.reader = reader;
+        self
+    }
+
+    pub fn with_max_lines(mut self, max_lines: Option<usize>) -> Self {
+        self.max_lines = max_lines;
+        self
+    }
+
+    /// Cells the renderer wraps at.
+    pub(crate) fn wrap_width(&self) -> usize {
+        match self.wrap {
+            WrapMode::Words => usize::from(self.width.max(MIN_WIDTH)),
+            WrapMode::None => usize::MAX,
+        }
+    }
+
+    pub(crate) fn flags(&self) -> RenderFlags {
+        RenderFlags {
+            markers: !self.include_segments,
+            source_headings: self.source_headings,
+            emoji: self.emoji,
+            front_matter: self.front_matter,
+            reader: self.reader,
+        }
+    }
+}
+
+/// One line of rendered output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RenderedLine {
+    pub text: String,
+    /// Pieces of `text` in order, each tagged with what produced it. Empty
+    /// unless [`RenderOptions::include_segments`] is set.
+    pub segments: Vec<PreviewSegment>,
+    /// Zero-based line of the Markdown source this line was rendered from.
+    /// Wrapped pieces of one source line share it.
+    pub source_line: usize,
+}
+
+/// The output of [`render`]: always at least one line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RenderedDoc {
+    pub lines: Vec<RenderedLine>,
+    /// [`RenderOptions::max_lines`] stopped rendering before the end.
+    pub truncated: bool,
+}
+
+impl RenderedDoc {
+    /// Index of the first rendered line that comes from `source_line` or a
+    /// later source line, for keeping a source view and the output in step.
+    pub fn line_for_source(&self, source_line: usize) -> Option<usize> {
+        self.lines
+            .iter()
+            .position(|line| line.source_line >= source_line)
+    }
+}
+
+/// Render `markdown` (CommonMark plus tables, task lists, strikethrough,
+/// footnotes and math) as terminal lines.
+///
+/// ```
+/// use mdv_core::{RenderOptions, SegmentKind, WrapMode, render};
+///
+/// let doc = render("# Title\n\nSome **bold** text.", &RenderOptions::new(40));
+/// assert_eq!(doc.lines[0].text, "# Title");
+/// assert_eq!(doc.lines[0].segments[0].kind, SegmentKind::Heading);
+/// assert_eq!(doc.lines[1].text, "Some bold text.");
+/// assert_eq!(doc.lines[1].source_line, 2);
+///
+/// let plain = RenderOptions::new(12)
+///     .with_wrap(WrapMode::None)
+///     .with_segments(false);
+/// let doc = render("Some **bold** text.", &plain);
+/// assert_eq!(doc.lines[0].text, "Some **bold** text.");
+/// assert!(doc.lines[0].segments.is_empty());
+/// ```
+pub fn render(markdown: &str, options: &RenderOptions) -> RenderedDoc {
+    let (width, flags) = (options.wrap_width(), options.flags());
+    let (styled, truncated) = match options.max_lines {
+        Some(max) => render_lines_bounded(markdown, width, flags, max.max(1)),
+        None => (render_lines(markdown, width, flags), false),
+    };
+    let mut lines: Vec<RenderedLine> = styled
+        .into_iter()
+        .map(|line| rendered_line(line, options))
+        .collect();
+
+    if lines.is_empty() {
+        lines.push(blank_line(options));
+    }
+    RenderedDoc { lines, truncated }
+}
+
+pub(crate) fn rendered_line(line: StyledLine, options: &RenderOptions) -> RenderedLine {
+    RenderedLine {
+        segments: if options.include_segments {
+            line.segments()
+        } else {
+            Vec::new()
+        },
+        text: line.text,
+        source_line: line.source_line,
+    }
+}
+
+/// The one line an empty document renders to.
+pub(crate) fn blank_line(options: &RenderOptions) -> RenderedLine {
+    RenderedLine {
+        text: String::new(),
+        segments: if options.include_segments {
+            vec![PreviewSegment {
+                text: String::new(),
+                kind: SegmentKind::Plain,
+            }]
+        } else {
+            Vec::new()
+        },
+        source_line: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FrontMatterStyle, RenderOptions, WrapMode, render};
+    use crate::SegmentKind;
+
+    const DOC: &str = "# Notes\n\nA *long* line of prose that wraps.\n\n```\ncode one\ncode two\n```\n\n\
+        | a | b |\n|---|---|\n| 1 | 2 |\n\n- item\n";
+
+    fn texts(options: &RenderOptions) -> Vec<String> {
+        render(DOC, options)
+            .lines
+            .into_iter()
+            .map(|line| line.text)
+            .collect()
+    }
+
+    #[test]
+    fn default_options_wrap_at_80_with_segments() {
+        assert_eq!(RenderOptions::default(), RenderOptions::new(80));
+        let doc = render(DOC, &RenderOptions::default());
+        assert!(doc.lines.iter().all(|line| !line.segments.is_empty()));
+        assert_eq!(doc.lines[1].text, "A long line of prose that wraps.");
+    }
+
+    #[test]
+    fn word_wrap_with_segments() {
+        let doc = render(DOC, &RenderOptions::new(16));
+        let prose: Vec<_> = doc
+            .lines
+            .iter()
+            .filter(|line| line.source_line == 2)
+            .collect();
+        assert_eq!(prose.len(), 3);
+        assert_eq!(prose[0].text, "A long line of");
+        assert_eq!(prose[0].segments[1].kind, SegmentKind::Emphasis);
+        for line in &doc.lines {
+            let joined: String = line.segments.iter().map(|s| s.text.as_str()).collect();
+            assert_eq!(joined, line.text);
+        }
+    }
+
+    #[test]
+    fn word_wrap_without_segments_keeps_markers() {
+        let lines = texts(&RenderOptions::new(16).with_segments(false));
+        assert_eq!(lines[1], "A *long* line of");
+        assert!(
+            render(DOC, &RenderOptions::new(16).with_segments(false))
+                .lines
+                .iter()
+                .all(|line| line.segments.is_empty())
+        );
+    }
+
+    #[test]
+    fn reader_mode_shows_link_text_and_image_alt() {
+        let src = "Read [the guide](https://example.com/a/very/long/path) and \
+                   <https://mdv.dev>.\n\n![A cat](cat.png) ![](dog.png)\n";
+        let lines = |options: &RenderOptions| -> Vec<String> {
+            render(src, options)
+                .lines
+                .into_iter()
+                .map(|line| line.text)
+                .collect()
+        };
+        let literal = lines(&RenderOptions::new(120));
+        assert_eq!(
+            literal,
+            [
+                "Read [the guide](https://example.com/a/very/long/path) and \
+                 [https://mdv.dev](https://mdv.dev).",
+                "![A cat](cat.png) ![](dog.png)",
+            ]
+        );
+        let reader = RenderOptions::new(120).with_reader(true);
+        assert_eq!(
+            lines(&reader),
+            ["Read the guide and https://mdv.dev.", "🖼 A cat 🖼 dog.png"]
+        );
+        let doc = render(src, &reader);
+        assert_eq!(doc.lines[0].segments[1].text, "the guide");
+        assert_eq!(doc.lines[0].segments[1].kind, SegmentKind::Link);
+    }
+
+    #[test]
+    fn no_wrap_ignores_the_width() {
+        let options = RenderOptions::new(8).with_wrap(WrapMode::None);
+        let lines = texts(&options);
+        assert_eq!(lines[1], "A long line of prose that wraps.");
+        assert!(lines.contains(&"| a | b |".to_string()));
+
+        let plain = texts(&options.with_segments(false));
+        assert_eq!(plain[1], "A *long* line of prose that wraps.");
+    }
+
+    #[test]
+    fn widths_below_the_minimum_render_at_eight() {
+        assert_eq!(texts(&RenderOptions::new(1)), texts(&RenderOptions::new(8)));
+    }
+
+    #[test]
+    fn lines_point_back_at_their_source() {
+        let doc = render(DOC, &RenderOptions::default());
+        let anchors: Vec<_> = doc
+            .lines
+            .iter()
+            .map(|line| (line.text.as_str(), line.source_line))
+            .collect();
+        assert_eq!(
+            anchors,
+            [
+                ("# Notes", 0),
+                ("A long line of prose that wraps.", 2),
+                ("```", 4),
+                ("code one", 5),
+                ("code two", 6),
+                ("```", 7),
+                ("| a | b |", 9),
+                ("| - | - |", 9),
+                ("| 1 | 2 |", 11),
+                ("- item", 13),
+            ]
+        );
+        assert_eq!(doc.line_for_source(5), Some(3));
+        assert_eq!(doc.line_for_source(10), Some(8));
+        assert_eq!(doc.line_for_source(99), None);
+    }
+
+    #[test]
+    fn source_headings_keep_setext_underlines_and_attributes() {
+        let src = "Title\n=====\n\nSection\n---\n\n## Usage {#usage .wide}\n\n\
+                   > Quoted\n> ===\n\n# Plain #\n";
+        let options = RenderOptions::new(40).with_source_headings(true);
+        let doc = render(src, &options);
+        let lines: Vec<_> = doc
+            .lines
+            .iter()
+            .map(|line| (line.text.as_str(), line.source_line))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                ("Title", 0),
+                ("=====", 1),
+                ("Section", 3),
+                ("-------", 4),
+                ("## Usage {#usage .wide}", 6),
+                ("> Quoted", 8),
+                ("> ======", 9),
+                ("# Plain", 11),
+            ]
+        );
+        for line in &doc.lines[..5] {
+            assert_eq!(line.segments.len(), 1);
+            assert_eq!(line.segments[0].kind, SegmentKind::Heading);
+        }
+
+        let plain = render(src, &options.with_segments(false));
+        assert_eq!(plain.lines[1].text, "=====");
+
+        let default: Vec<_> = render(src, &RenderOptions::new(40))
+            .lines
+            .into_iter()
+            .map(|line| line.text)
+            .collect();
+        assert_eq!(
+            default[..3],
+            ["# Title", "## Section", "## Usage {#usage .wide}"]
+        );
+    }
+
+    #[test]
+    fn emoji_option_replaces_shortcodes_in_prose_only() {
+        let src = "Ship it :tada: `:tada:` [go :rocket:](https://a.b/:x:)\n\n```\n:tada:\n```\n";
+        let options = RenderOptions::new(80).with_segments(false);
+        assert_eq!(
+            texts_of(src, &options.clone().with_emoji(true)),
+            [
+                "Ship it 🎉 `:tada:` [go 🚀](https://a.b/:x:)",
+                "```",
+                ":tada:",
+                "```"
+            ]
+        );
+        assert_eq!(
+            texts_of(src, &options)[0],
+            "Ship it :tada: `:tada:` [go :rocket:](https://a.b/:x:)"
+        );
+
+        // Each emoji takes two cells when wrapping.
+        let narrow = RenderOptions::new(8).with_emoji(true);
+        assert_eq!(
+            texts_of(":tada: :tada: :tada: :tada:", &narrow),
+            ["🎉 🎉 🎉", "🎉"]
+        );
+    }
+
+    fn texts_of(src: &str, options: &RenderOptions) -> Vec<String> {
+        render(src, options)
+            .lines
+            .into_iter()
+            .map(|line| line.text)
+            .collect()
+    }
+
+    #[test]
+    fn front_matter_folds_to_a_key_count_or_expands() {
+        let yaml = "---\ntitle: Notes\nauthor: Me\ntags:\n  - a\n---\n# Body\n";
+        let doc = render(yaml, &RenderOptions::new(80));
+        assert_eq!(doc.lines[0].text, "--- front matter: 3 keys ---");
+        assert_eq!(doc.lines[0].segments[0].kind, SegmentKind::FrontMatter);
+        assert_eq!(
+            (doc.lines[1].text.as_str(), doc.lines[1].source_line),
+            ("# Body", 6)
+        );
+
+        let expanded = RenderOptions::new(80).with_front_matter(FrontMatterStyle::Expanded);
+        let lines: Vec<_> = render(yaml, &expanded)
+            .lines
+            .into_iter()
+            .map(|line| (line.text, line.source_line))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                ("---".to_string(), 0),
+                ("title: Notes".into(), 1),
+                ("author: Me".into(), 2),
+                ("tags:".into(), 3),
+                ("  - a".into(), 4),
+                ("---".into(), 5),
+                ("# Body".into(), 6),
+            ]
+        );
+
+        let toml = "+++\ntitle = \"Notes\"\n+++\nBody\n";
+        assert_eq!(
+            texts_of(toml, &RenderOptions::new(80)),
+            ["+++ front matter: 1 key +++", "Body"]
+        );
+    }
+
+    #[test]
+    fn a_leading_rule_is_still_a_rule() {
+        assert_eq!(
+            texts_of(
+                "---\nSome text\n\n---\ntitle: x\n---\n",
+                &RenderOptions::new(80)
+            ),
+            ["---", "Some text", "---", "## title: x"]
+        );
+    }
+
+    #[test]
+    fn empty_markdown_renders_one_blank_line() {
+        let doc = render("", &RenderOptions::default());
+        assert_eq!(doc.lines.len(), 1);
+        assert_eq!(doc.lines[0].segments[0].kind, SegmentKind::Plain);
+        let plain = render("", &RenderOptions::default().with_segments(false));
+        assert!(plain.lines[0].segments.is_empty());
+    }
+}
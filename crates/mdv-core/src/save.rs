@@ -0,0 +1,138 @@
+use std::borrow::Cow;
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::io::{self, Write};
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+
+use crate::markdown::{FenceLine, FenceTracker};
+
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveOutcome {
+    /// Written to a sibling temp file and renamed over the target.
+    Atomic,
+    /// The atomic path failed, so the target was overwritten in place.
+    DirectWrite { reason: String },
+}
+
+#[cfg(feature = "fs")]
+pub(crate) fn write_file(path: &Path, contents: &str) -> io::Result<SaveOutcome> {
+    let target = resolve_symlink(path);
+    match write_atomic(&target, contents) {
+        Ok(()) => Ok(SaveOutcome::Atomic),
+        Err(err) => {
+            fs::write(&target, contents)?;
+            Ok(SaveOutcome::DirectWrite {
+                reason: err.to_string(),
+            })
+        }
+    }
+}
+
+#[cfg(feature = "fs")]
+pub(crate) fn temp_path_for(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.mdv-tmp"))
+}
+
+#[cfg(feature = "fs")]
+fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let tmp = temp_path_for(path);
+    let result = write_temp(path, &tmp, contents).and_then(|()| fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+#[cfg(feature = "fs")]
+fn write_temp(path: &Path, tmp: &Path, contents: &str) -> io::Result<()> {
+    let mut file = fs::File::create(tmp)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    if let Ok(meta) = fs::metadata(path) {
+        fs::set_permissions(tmp, meta.permissions())?;
+    }
+    Ok(())
+}
+
+/// Save through symlinks instead of replacing the link with a regular file.
+#[cfg(feature = "fs")]
+fn resolve_symlink(path: &Path) -> PathBuf {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_symlink() => {
+            fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Whitespace cleanups applied to the text before it is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SavePolicy {
+    /// End a non-empty file with exactly one newline.
+    pub final_newline: bool,
+    /// Drop spaces and tabs at line ends, except inside fenced code and
+    /// the two or more spaces of a hard line break.
+    pub trim_trailing_whitespace: bool,
+}
+
+/// `text` as `policy` wants it saved, borrowed when nothing changes.
+pub fn normalize_for_save(text: &str, policy: SavePolicy) -> Cow<'_, str> {
+    let mut out = if policy.trim_trailing_whitespace {
+        trim_trailing_whitespace(text)
+    } else {
+        Cow::Borrowed(text)
+    };
+    if policy.final_newline && !out.is_empty() {
+        let body = out.trim_end_matches('\n');
+        if body.len() + 1 != out.len() {
+            let mut owned = body.to_string();
+            owned.push('\n');
+            out = Cow::Owned(owned);
+        }
+    }
+    out
+}
+
+fn trim_trailing_whitespace(text: &str) -> Cow<'_, str> {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let mut fences = FenceTracker::default();
+    let mut kept = Vec::with_capacity(lines.len());
+    for (index, &line) in lines.iter().enumerate() {
+        match fences.line(line) {
+            FenceLine::Inside => {
+                kept.push(line);
+                continue;
+            }
+            FenceLine::Close => {
+                kept.push(line.trim_end_matches([' ', '\t']));
+                continue;
+            }
+            FenceLine::Open | FenceLine::Outside => {}
+        }
+        let trimmed = line.trim_end_matches([' ', '\t']);
+        let spaces = line.len() - line.trim_end_matches(' ').len();
+        let hard_break = spaces >= 2
+            && spaces == line.len() - trimmed.len()
+            && !trimmed.is_empty()
+            && lines
+                .get(index + 1)
+                .is_some_and(|next| !next.trim().is_empty());
+        kept.push(if hard_break { line } else { trimmed });
+    }
+    if kept
+        .iter()
+        .zip(&lines)
+        .all(|(kept, line)| kept.len() == line.len())
+    {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(kept.join("\n"))
+    }
+}
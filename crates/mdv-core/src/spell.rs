@@ -0,0 +1,282 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use crate::markdown::FenceTracker;
+
+/// Dictionary lookups for Markdown prose, remembering each line's result so
+/// only new or edited lines are checked again.
+#[derive(Debug, Default)]
+pub struct SpellChecker {
+    /// Known words, lowercased.
+    words: HashSet<String>,
+    /// Misspellings of every line seen in the last [`check`](Self::check),
+    /// keyed by a hash of the line and relative to its start.
+    lines: HashMap<u64, Vec<(usize, usize)>>,
+    checked_lines: usize,
+}
+
+impl SpellChecker {
+    /// A checker knowing the words of `list`, one per line. Hunspell `.dic`
+    /// files work too: the leading count and `/FLAGS` suffixes are ignored.
+    pub fn from_word_list(list: &str) -> Self {
+        let mut checker = Self::default();
+        checker.add_word_list(list);
+        checker
+    }
+
+    pub fn add_word_list(&mut self, list: &str) {
+        for line in list.lines() {
+            let word = line.split('/').next().unwrap_or(line).trim();
+            if !word.is_empty() && !word.bytes().all(|byte| byte.is_ascii_digit()) {
+                self.words.insert(word.to_lowercase());
+            }
+        }
+        self.lines.clear();
+    }
+
+    pub fn add_word(&mut self, word: &str) {
+        self.words.insert(word.to_lowercase());
+        self.lines.clear();
+    }
+
+    pub fn knows(&self, word: &str) -> bool {
+        self.words.contains(&word.to_lowercase())
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Byte ranges of unknown words in `markdown`, in document order.
+    /// Fenced code is skipped, and lines unchanged since the previous call
+    /// reuse its result.
+    pub fn check(&mut self, markdown: &str) -> Vec<(usize, usize)> {
+        let mut misspelled = Vec::new();
+        let mut seen = HashMap::new();
+        let mut fences = FenceTracker::default();
+        let mut line_start = 0;
+        self.checked_lines = 0;
+
+        for line in markdown.split('\n') {
+            let start = line_start;
+            line_start += line.len() + 1;
+            if fences.line(line).is_code() {
+                continue;
+            }
+
+            let mut hasher = DefaultHasher::new();
+            line.hash(&mut hasher);
+            let key = hasher.finish();
+            let found = match self.lines.remove(&key) {
+                Some(found) => found,
+                None => match seen.get(&key) {
+                    Some(found) => Vec::clone(found),
+                    None => {
+                        self.checked_lines += 1;
+                        self.check_line(line)
+                    }
+                },
+            };
+            misspelled.extend(found.iter().map(|&(s, e)| (start + s, start + e)));
+            seen.insert(key, found);
+        }
+        self.lines = seen;
+        misspelled
+    }
+
+    /// Lines the last [`check`](Self::check) had to look up instead of
+    /// reusing.
+    pub fn checked_lines(&self) -> usize {
+        self.checked_lines
+    }
+
+    fn check_line(&self, line: &str) -> Vec<(usize, usize)> {
+        prose_words(line)
+            .into_iter()
+            .filter(|&(start, end)| !self.knows(&line[start..end]))
+            .collect()
+    }
+}
+
+/// Byte ranges of the words in one line worth spell checking: letters with
+/// inner apostrophes. Inline code, URLs, email addresses, link destinations,
+/// `<tags>`, and words with digits, underscores, or capitals past the first
+/// letter (acronyms and identifiers) are left out.
+pub fn prose_words(line: &str) -> Vec<(usize, usize)> {
+    let skipped = skipped_spans(line);
+    let mut words = Vec::new();
+    let mut chunk_start = 0;
+    for piece in line.split_inclusive(char::is_whitespace) {
+        let start = chunk_start;
+        chunk_start += piece.len();
+        let chunk = piece.trim_end_matches(char::is_whitespace);
+        if chunk.contains("://") || chunk.contains('@') || chunk.starts_with("www.") {
+            continue;
+        }
+        for (token_start, token) in tokens(chunk) {
+            let token_start = start + token_start;
+            let token_end = token_start + token.len();
+            if skipped
+                .iter()
+                .any(|&(s, e)| token_start < e && s < token_end)
+            {
+                continue;
+            }
+            // `_emphasis_` and 'quoted' words keep their letters.
+            let trim = |c: char| c == '_' || is_apostrophe(c);
+            let word = token.trim_matches(trim);
+            let word_start = token_start + (token.len() - token.trim_start_matches(trim).len());
+            if is_checkable(word) {
+                words.push((word_start, word_start + word.len()));
+            }
+        }
+    }
+    words
+}
+
+/// Runs of letters, digits, underscores, and apostrophes in `chunk`.
+fn tokens(chunk: &str) -> Vec<(usize, &str)> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (idx, c) in chunk.char_indices() {
+        let part = c.is_alphanumeric() || c == '_' || is_apostrophe(c);
+        match (start, part) {
+            (None, true) => start = Some(idx),
+            (Some(from), false) => {
+                tokens.push((from, &chunk[from..idx]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(from) = start {
+        tokens.push((from, &chunk[from..]));
+    }
+    tokens
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '\u{2019}'
+}
+
+fn is_checkable(word: &str) -> bool {
+    let mut chars = word.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    first.is_alphabetic()
+        && chars.clone().next().is_some()
+        && chars.all(|c| is_apostrophe(c) || (c.is_alphabetic() && !c.is_uppercase()))
+}
+
+/// Inline code spans, `<...>` autolinks and tags, and `](...)` link
+/// destinations, as byte ranges.
+fn skipped_spans(line: &str) -> Vec<(usize, usize)> {
+    let bytes = line.as_bytes();
+    let mut spans = Vec::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'`' => {
+                let run = bytes[idx..].iter().take_while(|&&b| b == b'`').count();
+                let fence = &line[idx..idx + run];
+                let close = line[idx + run..].match_indices(fence).find(|&(at, _)| {
+                    let after = idx + run + at + run;
+                    bytes.get(after) != Some(&b'`') && bytes[..idx + run + at].last() != Some(&b'`')
+                });
+                match close {
+                    Some((at, _)) => {
+                        let end = idx + run + at + run;
+                        spans.push((idx, end));
+                        idx = end;
+                    }
+                    None => idx += run,
+                }
+            }
+            b'<' => match line[idx..].find('>') {
+                Some(len) if !line[idx..idx + len].contains(char::is_whitespace) => {
+                    spans.push((idx, idx + len + 1));
+                    idx += len + 1;
+                }
+                _ => idx += 1,
+            },
+            b']' if bytes.get(idx + 1) == Some(&b'(') => match line[idx..].find(')') {
+                Some(len) => {
+                    spans.push((idx + 1, idx + len + 1));
+                    idx += len + 1;
+                }
+                None => idx += 1,
+            },
+            _ => idx += 1,
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SpellChecker, prose_words};
+
+    fn words(line: &str) -> Vec<&str> {
+        prose_words(line)
+            .into_iter()
+            .map(|(start, end)| &line[start..end])
+            .collect()
+    }
+
+    #[test]
+    fn prose_words_skip_code_urls_and_identifiers() {
+        assert_eq!(
+            words("Don't run `cargo biuld` before 'reading' the docs."),
+            ["Don't", "run", "before", "reading", "the", "docs"]
+        );
+        assert_eq!(
+            words(
+                "See [the guide](docs/guide.md) or https://exmaple.com, <https://a.b> and me@exmaple.com"
+            ),
+            ["See", "the", "guide", "or", "and"]
+        );
+        assert_eq!(
+            words("An HTML camelCase snake_case v2 x `` a `b` c`` word <br/>"),
+            ["An", "word"]
+        );
+        assert_eq!(words("- [x] **bold** and _emph_"), ["bold", "and", "emph"]);
+    }
+
+    #[test]
+    fn check_flags_unknown_words_outside_code_fences() {
+        let mut checker = SpellChecker::from_word_list("2\nthe/S\ncat\nsat\n");
+        assert!(checker.knows("The"));
+        assert_eq!(checker.word_count(), 3);
+        let text = "The cat sta\n```\nthe dgo\n```\nteh `dgo` cat\n";
+        let misspelled: Vec<_> = checker
+            .check(text)
+            .into_iter()
+            .map(|(start, end)| &text[start..end])
+            .collect();
+        assert_eq!(misspelled, ["sta", "teh"]);
+
+        checker.add_word("teh");
+        assert_eq!(checker.check(text).len(), 1);
+    }
+
+    #[test]
+    fn check_only_looks_up_lines_that_changed() {
+        let mut checker = SpellChecker::from_word_list("one\ntwo\n");
+        let text = "one two\ntwo one\none\none\nthree\n";
+        assert_eq!(checker.check(text).len(), 1);
+        // Four distinct lines plus the empty last one; the repeat is reused.
+        assert_eq!(checker.checked_lines(), 5);
+
+        let edited = "one two\ntwo one\nfour\none\none\nthree\n";
+        assert_eq!(checker.check(edited).len(), 2);
+        assert_eq!(checker.checked_lines(), 1);
+        assert_eq!(checker.check(edited).len(), 2);
+        assert_eq!(checker.checked_lines(), 0);
+
+        checker.add_word("four");
+        assert_eq!(checker.check(edited).len(), 1);
+        assert_eq!(checker.checked_lines(), 6);
+    }
+}
@@ -0,0 +1,36 @@
+use crate::markdown::FenceTracker;
+
+/// Whitespace-separated words, skipping the contents of fenced code blocks
+/// so the count reflects prose rather than code samples.
+pub fn word_count(markdown: &str) -> usize {
+    let mut fences = FenceTracker::default();
+    markdown
+        .lines()
+        .filter(|line| !fences.line(line).is_code())
+        .map(|line| line.split_whitespace().count())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::word_count;
+
+    #[test]
+    fn counts_whitespace_separated_tokens() {
+        assert_eq!(word_count(""), 0);
+        assert_eq!(word_count("one two\tthree\n\n  four  "), 4);
+        assert_eq!(word_count("# Title\n- item one"), 5);
+    }
+
+    #[test]
+    fn skips_fenced_code_contents() {
+        let src = "intro text\n```rust\nfn main() {}\n```\noutro\n~~~~\n```\nstill code\n~~~~\nend";
+        assert_eq!(word_count(src), 4);
+    }
+
+    #[test]
+    fn unclosed_fence_hides_the_rest() {
+        assert_eq!(word_count("a b\n```\nc d\n"), 2);
+        assert_eq!(word_count("    ```\nindented code is not a fence"), 7);
+    }
+}
@@ -0,0 +1,380 @@
+use std::ops::Range;
+
+use pulldown_cmark::{BrokenLink, CowStr, Event, Options, Parser, Tag, TagEnd};
+
+use crate::front_matter::{blank_front_matter, front_matter};
+use crate::markdown::{
+    FenceLine, FenceTracker, footnote_name_at, footnotes_heading, parser_options, render_parts,
+};
+use crate::render::{RenderOptions, RenderedLine, blank_line, rendered_line};
+
+/// Renders Markdown that arrives in pieces, such as stdin piped to
+/// `mdv --stream`, and hands out each line once no text still to come can
+/// change it. The lines from every [`push`](Self::push) and then
+/// [`finish`](Self::finish), in order, are the lines [`render`](crate::render)
+/// gives for the whole text.
+///
+/// The text is looked at after each blank line outside fenced code. The
+/// last block before it is held back, since more text can still extend it,
+/// such as another list item. So is a block using a link reference or
+/// footnote that is not defined yet, with everything after it, until the
+/// definition arrives or the text ends. [`RenderOptions::max_lines`] is not
+/// applied.
+#[derive(Debug)]
+pub struct StreamRenderer {
+    options: RenderOptions,
+    text: String,
+    /// Start of the first line whose output is not handed out yet.
+    start: usize,
+    /// Source line of `start`.
+    start_line: usize,
+    /// Reference definitions before `start`, rendered in front of the rest
+    /// as [`PreviewRenderer`](crate::PreviewRenderer) does for each block.
+    definitions: String,
+    /// Lowercased names of the footnotes defined before `start`.
+    footnotes: Vec<String>,
+    /// Footnote definitions handed-out blocks rendered, held for the section
+    /// after the last block.
+    footnote_lines: Vec<RenderedLine>,
+    /// End of the complete lines already looked at for blank lines.
+    scanned: usize,
+    fences: FenceTracker,
+    handed_out: usize,
+}
+
+/// What parsing the text after `start` found, in offsets from `start`.
+#[derive(Debug, Default)]
+struct Window {
+    /// Starts of the top-level blocks.
+    blocks: Vec<usize>,
+    definitions: Vec<Range<usize>>,
+    /// Footnote definitions and their lowercased names.
+    footnotes: Vec<(usize, String)>,
+    /// The first link reference or footnote reference with no definition
+    /// yet.
+    undefined: Option<usize>,
+}
+
+impl StreamRenderer {
+    pub fn new(options: RenderOptions) -> Self {
+        Self {
+            options,
+            text: String::new(),
+            start: 0,
+            start_line: 0,
+            definitions: String::new(),
+            footnotes: Vec::new(),
+            footnote_lines: Vec::new(),
+            scanned: 0,
+            fences: FenceTracker::default(),
+            handed_out: 0,
+        }
+    }
+
+    /// Add `chunk` to the text and return the lines it settled.
+    pub fn push(&mut self, chunk: &str) -> Vec<RenderedLine> {
+        self.text.push_str(chunk);
+        match self.last_blank_line_end() {
+            Some(end) => self.render_settled(end),
+            None => Vec::new(),
+        }
+    }
+
+    /// The lines not handed out yet, now that the text is complete.
+    pub fn finish(mut self) -> Vec<RenderedLine> {
+        let window = self.text[self.start..].to_string();
+        let mut lines = Vec::new();
+        if !window.is_empty() {
+            let (body, footnotes) = self.render_window(&window);
+            lines = body;
+            self.footnote_lines.extend(footnotes);
+        }
+        if let Some(first) = self.footnote_lines.first() {
+            let heading = footnotes_heading(self.options.wrap_width(), first.source_line);
+            lines.extend(
+                heading
+                    .into_iter()
+                    .map(|line| rendered_line(line, &self.options)),
+            );
+            lines.append(&mut self.footnote_lines);
+        }
+        if self.handed_out == 0 && lines.is_empty() {
+            lines.push(blank_line(&self.options));
+        }
+        lines
+    }
+
+    /// End of the last blank line outside fenced code and front matter among
+    /// the complete lines added since the previous call.
+    fn last_blank_line_end(&mut self) -> Option<usize> {
+        let mut end = self.scanned;
+        let mut found = None;
+        for line in self.text[self.scanned..].split_inclusive('\n') {
+            if !line.ends_with('\n') {
+                break;
+            }
+            end += line.len();
+            if self.fences.line(line) == FenceLine::Outside && line.trim_end().is_empty() {
+                found = Some(end);
+            }
+        }
+        self.scanned = end;
+        found.filter(|&end| !self.in_front_matter(end))
+    }
+
+    /// Whether `end` may still be inside front matter: the text opens with
+    /// a delimiter that is not closed before `end`.
+    fn in_front_matter(&self, end: usize) -> bool {
+        let opens = self
+            .text
+            .split_inclusive('\n')
+            .next()
+            .is_some_and(|first| matches!(first.trim_end(), "---" | "+++"));
+        self.start == 0 && opens && front_matter(&self.text).is_none_or(|front| end <= front.end)
+    }
+
+    /// Hand out the lines of the blocks before `end` that nothing after
+    /// them can change.
+    fn render_settled(&mut self, end: usize) -> Vec<RenderedLine> {
+        let window = self.text[self.start..end].to_string();
+        let scan = self.scan(&window);
+        let Some(&last) = scan.blocks.last() else {
+            return Vec::new();
+        };
+        let held = match scan.undefined {
+            Some(offset) => scan
+                .blocks
+                .iter()
+                .rev()
+                .find(|&&block| block <= offset)
+                .map_or(0, |&block| block.min(last)),
+            None => last,
+        };
+        let cut = window[..held].rfind('\n').map_or(0, |idx| idx + 1);
+        if cut == 0 {
+            return Vec::new();
+        }
+
+        let cut_line = self.start_line + window[..cut].matches('\n').count();
+        let (mut lines, footnotes) = self.render_window(&window);
+        lines.retain(|line| line.source_line < cut_line);
+        self.footnote_lines.extend(
+            footnotes
+                .into_iter()
+                .filter(|line| line.source_line < cut_line),
+        );
+        for range in scan.definitions.iter().filter(|range| range.start < cut) {
+            self.definitions.push_str(window[range.clone()].trim_end());
+            self.definitions.push('\n');
+        }
+        self.footnotes.extend(
+            scan.footnotes
+                .into_iter()
+                .filter(|(offset, _)| *offset < cut)
+                .map(|(_, name)| name),
+        );
+        self.start += cut;
+        self.start_line = cut_line;
+        self.handed_out += lines.len();
+        lines
+    }
+
+    /// `window` with the reference definitions before it in front, and the
+    /// length of what was put in front.
+    fn source(&self, window: &str) -> (String, usize) {
+        if self.start == 0 {
+            return (window.to_string(), 0);
+        }
+        // The blank line also keeps a `---` at the start of `window` from
+        // passing for front matter.
+        let source = format!("{}\n{window}", self.definitions);
+        (source, self.definitions.len() + 1)
+    }
+
+    fn parser_options(&self) -> Options {
+        let mut options = parser_options();
+        if self.options.source_headings {
+            options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+        }
+        options
+    }
+
+    fn scan(&self, window: &str) -> Window {
+        let (source, prefix) = self.source(window);
+        let parsed = blank_front_matter(&source);
+        let mut broken = Vec::new();
+        let callback = |link: BrokenLink<'_>| -> Option<(CowStr<'_>, CowStr<'_>)> {
+            // `[^name]` of a footnote defined earlier; checked below.
+            if !link.reference.starts_with('^') {
+                broken.push(link.span.start);
+            }
+            None
+        };
+        let mut iter =
+            Parser::new_with_broken_link_callback(&parsed, self.parser_options(), Some(callback))
+                .into_offset_iter();
+        let mut scan = Window {
+            definitions: iter
+                .reference_definitions()
+                .iter()
+                .map(|(_, def)| def.span.clone())
+                .filter(|span| span.start >= prefix)
+                .map(|span| span.start - prefix..span.end - prefix)
+                .collect(),
+            ..Window::default()
+        };
+        let mut missing_footnotes = Vec::new();
+        let (mut depth, mut in_code) = (0usize, false);
+        for (event, range) in iter.by_ref() {
+            let offset = range.start.saturating_sub(prefix);
+            match &event {
+                Event::Start(Tag::FootnoteDefinition(name)) => {
+                    scan.footnotes.push((offset, name.to_lowercase()));
+                }
+                Event::Start(Tag::CodeBlock(_)) => in_code = true,
+                Event::End(TagEnd::CodeBlock) => in_code = false,
+                Event::Text(text) if !in_code && &**text == "[" => {
+                    // How the renderer finds a reference to a footnote this
+                    // text does not define.
+                    if let Some(name) = footnote_name_at(&parsed[range.start..])
+                        && !self.footnotes.contains(&name.to_lowercase())
+                    {
+                        missing_footnotes.push(offset);
+                    }
+                }
+                _ => {}
+            }
+            match event {
+                Event::Start(_) => {
+                    if depth == 0 && range.start >= prefix {
+                        scan.blocks.push(offset);
+                    }
+                    depth += 1;
+                }
+                Event::End(_) => depth = depth.saturating_sub(1),
+                _ if depth == 0 && range.start >= prefix => scan.blocks.push(offset),
+                _ => {}
+            }
+        }
+        drop(iter);
+        scan.undefined = broken
+            .into_iter()
+            .map(|start| start.saturating_sub(prefix))
+            .chain(missing_footnotes)
+            .min();
+        scan
+    }
+
+    /// The lines and footnote lines of `window`, numbered as source lines of
+    /// the whole text.
+    fn render_window(&self, window: &str) -> (Vec<RenderedLine>, Vec<RenderedLine>) {
+        let (source, prefix) = self.source(window);
+        let prefix_lines = source[..prefix].matches('\n').count();
+        let (lines, footnotes, _) = render_parts(
+            &source,
+            self.options.wrap_width(),
+            self.options.flags(),
+            &self.footnotes,
+            None,
+        );
+        let number = |lines: Vec<_>| {
+            lines
+                .into_iter()
+                .map(|line| {
+                    let mut line = rendered_line(line, &self.options);
+                    line.source_line =
+                        line.source_line.saturating_sub(prefix_lines) + self.start_line;
+                    line
+                })
+                .collect()
+        };
+        (number(lines), number(footnotes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StreamRenderer;
+    use crate::render::{RenderOptions, RenderedLine, render};
+
+    /// Feed `markdown` a line at a time, returning every line handed out and
+    /// how many came before the last push.
+    fn streamed(markdown: &str, options: &RenderOptions) -> (Vec<RenderedLine>, usize) {
+        let mut renderer = StreamRenderer::new(options.clone());
+        let mut lines = Vec::new();
+        for line in markdown.split_inclusive('\n') {
+            lines.extend(renderer.push(line));
+        }
+        let early = lines.len();
+        lines.extend(renderer.finish());
+        (lines, early)
+    }
+
+    #[test]
+    fn streamed_lines_match_a_whole_render() {
+        let docs = [
+            "",
+            "no newline",
+            "# Title\n\nSome *prose* that wraps at a narrow width, twice over.\n\n- a\n\n- b\n\n\
+             ```rust\nlet x = 1;\n\nlet y = 2;\n```\n\n> quote\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\nend\n",
+            "---\ntitle: Notes\n\ntags: [a]\n---\n\n# After front matter\n\ntext\n",
+            "Early [link][later] and [shortcut].\n\nmore\n\n[later]: https://example.com\n\n\
+             [shortcut]: /s\n\ntail\n",
+            "[def]: https://example.com\n\nuses [it][def]\n\nagain [def]\n\n",
+            "Note[^1] and[^late].\n\nmiddle\n\n[^1]: first\n\nafter[^1]\n\n[^late]: second\n\nlast\n",
+            "1. one\n\n2. two\n\n   still two\n\npara\n\n    indented\n\n    code\n\n***\n\n<!-- a\n\nb -->\n\nx\n",
+            "para\n---\n\n---\nkey: v\n---\n\n~~~\nunclosed\n\nfence\n",
+            "Setext\n===\n\nterm\n: definition\n\n$$\nx\n$$\n\n- [x] task\n- [ ] open\n",
+        ];
+        let options = [
+            RenderOptions::new(24),
+            RenderOptions::new(40).with_segments(false),
+            RenderOptions::new(80)
+                .with_source_headings(true)
+                .with_emoji(true),
+        ];
+        for doc in docs {
+            for options in &options {
+                assert_eq!(
+                    streamed(doc, options).0,
+                    render(doc, options).lines,
+                    "{doc:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn settled_blocks_come_out_before_the_end() {
+        let options = RenderOptions::new(80).with_segments(false);
+        let mut renderer = StreamRenderer::new(options);
+        assert!(renderer.push("# Title\n").is_empty());
+        assert!(renderer.push("\n").is_empty(), "the heading could be all");
+        let lines = renderer.push("first\nparagraph\n\n");
+        assert_eq!(
+            lines.iter().map(|line| &*line.text).collect::<Vec<_>>(),
+            ["# Title"]
+        );
+        assert_eq!(renderer.push("- a\n\n").len(), 2, "the paragraph is done");
+        assert!(renderer.push("- b\n\n").is_empty(), "the list may grow");
+        let rest: Vec<_> = renderer
+            .finish()
+            .into_iter()
+            .map(|line| line.text)
+            .collect();
+        assert_eq!(rest, ["- a", "- b"]);
+    }
+
+    #[test]
+    fn undefined_references_hold_what_follows() {
+        let (_, early) = streamed("a [x][ref]\n\nb\n\nc\n\n", &RenderOptions::new(80));
+        assert_eq!(early, 0);
+        let (_, early) = streamed(
+            "a [x][ref]\n\nb\n\n[ref]: /x\n\nc\n\n",
+            &RenderOptions::new(80),
+        );
+        assert_eq!(early, 2, "a and b, once the definition arrives");
+        let (_, early) = streamed("a[^n]\n\nb\n\n", &RenderOptions::new(80));
+        assert_eq!(early, 0);
+    }
+}
@@ -1,9 +1,9 @@
-use mdv_core::{EditorBuffer, compute_conflict_hunks};
+use mdv_core::{EditorBuffer, HunkKind, compute_conflict_hunks};
 
 #[test]
 fn compute_conflict_hunks_returns_changed_blocks_only() {
-    let local = "a\nsame\nb\nc";
-    let external = "a\nsame\nB\nc\nd";
+    let local = "a\nsame\nb\nc\nc";
+    let external = "a\nsame\nB\nc\nc\nd";
     let hunks = compute_conflict_hunks(local, external);
 
     assert_eq!(hunks.len(), 2);
@@ -15,12 +15,69 @@ fn compute_conflict_hunks_returns_changed_blocks_only() {
     assert_eq!(first.external_lines, vec!["B".to_string()]);
 
     let second = &hunks[1];
-    assert_eq!(second.local_start, 4);
-    assert_eq!(second.external_start, 4);
+    assert_eq!(second.local_start, 5);
+    assert_eq!(second.external_start, 5);
     assert!(second.local_lines.is_empty());
     assert_eq!(second.external_lines, vec!["d".to_string()]);
 }
 
+#[test]
+fn moved_paragraph_is_one_move_that_applies_in_one_step() {
+    let local = "# Notes\n\nFirst paragraph.\nIt has two lines.\n\nSecond.\nThird.\nFourth.\n";
+    let external = "# Notes\n\nSecond.\nThird.\nFourth.\n\nFirst paragraph.\nIt has two lines.\n";
+    let hunks = compute_conflict_hunks(local, external);
+
+    assert_eq!(hunks.len(), 2);
+    assert!(hunks.iter().all(|hunk| hunk.kind == HunkKind::Move));
+    assert_eq!(hunks[0].moved_with, Some(1));
+    assert_eq!(hunks[1].moved_with, Some(0));
+    assert_eq!(
+        hunks[0].local_lines,
+        ["First paragraph.", "It has two lines.", ""]
+    );
+    assert!(hunks[0].external_lines.is_empty());
+    assert_eq!(hunks[1].external_lines, hunks[0].local_lines);
+
+    let mut editor = EditorBuffer::new(local.into());
+    editor.dirty = true;
+    editor.on_external_change(external.into());
+    assert!(editor.apply_external_hunk(1));
+    assert_eq!(editor.text(), external);
+    assert!(!editor.is_conflicted());
+}
+
+#[test]
+fn indentation_only_change_is_one_whitespace_change() {
+    let local = "- list\n  - nested\n  - items\n- end\n";
+    let external = "- list\n    - nested\n    - items\n- end\n";
+    let hunks = compute_conflict_hunks(local, external);
+
+    assert_eq!(hunks.len(), 1);
+    assert_eq!(hunks[0].kind, HunkKind::Change);
+    assert_eq!(hunks[0].local_lines, ["  - nested", "  - items"]);
+    assert!(hunks[0].is_whitespace_only());
+    assert!(!compute_conflict_hunks("a\nb\n", "a\nc\n")[0].is_whitespace_only());
+}
+
+#[test]
+fn scattered_edits_in_a_large_file_give_a_handful_of_hunks() {
+    let local: Vec<String> = (0..2_000).map(|n| format!("line {n}")).collect();
+    let mut external = local.clone();
+    // Every other line of three short stretches, which a plain line diff
+    // reports as one hunk per edited line.
+    for start in [100, 900, 1_700] {
+        for n in (start..start + 40).step_by(2) {
+            external[n] = format!("edited {n}");
+        }
+    }
+    let hunks = compute_conflict_hunks(&local.join("\n"), &external.join("\n"));
+
+    assert_eq!(hunks.len(), 3);
+    assert!(hunks.iter().all(|hunk| hunk.kind == HunkKind::Change));
+    assert_eq!(hunks[1].local_start, 900);
+    assert_eq!(hunks[1].local_lines.len(), 39);
+}
+
 #[test]
 fn editor_conflict_state_contains_block_hunks() {
     let mut editor = EditorBuffer::new("one\ntwo\nthree".into());
@@ -48,6 +105,7 @@ fn editor_cursor_accessor_reports_byte_index() {
     assert_eq!(editor.cursor(), 1);
 }
 
+#[cfg(feature = "fs")]
 #[test]
 fn editor_save_to_path_success_and_error_paths() {
     let nanos = std::time::SystemTime::now()
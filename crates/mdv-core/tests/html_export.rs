@@ -0,0 +1,39 @@
+use std::fs;
+use std::path::Path;
+
+use mdv_core::render_html;
+
+/// Compare against the checked-in page; `MDV_UPDATE_SNAPSHOTS=1` rewrites it.
+#[test]
+fn export_fixture_matches_snapshot() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let markdown = fs::read_to_string(fixtures.join("export.md")).expect("read fixture");
+    let html = render_html(&markdown);
+
+    let snapshot = fixtures.join("export.html");
+    if std::env::var_os("MDV_UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&snapshot, &html).expect("write snapshot");
+    }
+    let expected = fs::read_to_string(&snapshot).expect("read snapshot");
+    assert_eq!(html, expected);
+}
+
+#[test]
+fn export_keeps_every_construct() {
+    let markdown =
+        fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/export.md"))
+            .expect("read fixture");
+    let html = render_html(&markdown);
+
+    assert!(html.contains("<table>"));
+    assert!(html.contains("<th style=\"text-align: right\">Count</th>"));
+    assert!(html.contains("<input disabled=\"\" type=\"checkbox\" checked=\"\"/>"));
+    assert!(html.contains("<input disabled=\"\" type=\"checkbox\"/>"));
+    assert!(html.contains("<sup class=\"footnote-reference\"><a href=\"#src\">"));
+    assert!(html.contains("<div class=\"footnote-definition\" id=\"src\">"));
+    assert!(html.contains("<span class=\"math math-inline\">e^{i\\pi} + 1 = 0</span>"));
+    assert!(html.contains("<span class=\"math math-display\">"));
+    assert!(html.contains("\\frac{1}{3}"));
+    assert!(html.contains("&lt;hi&gt; &amp; bye"));
+    assert!(html.contains("<del>struck</del>"));
+}
@@ -0,0 +1,35 @@
+//! File watching and stdin streaming for the `mdv` terminal viewer, as
+//! channels another app can poll from its own event loop.
+//!
+//! [`watch_file`] follows one file through edits and atomic saves that
+//! replace it by a rename, sending its new text as a [`WatchMessage`].
+//! [`stream_stdin`] reads stdin on a thread and sends what arrives as
+//! [`StreamMessage`]s, keeping the text under a size cap.
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use std::time::Duration;
+//!
+//! use mdv_watch::{WatchMessage, WatchOptions, watch_file};
+//!
+//! let options = WatchOptions::default().with_debounce(Duration::from_millis(150));
+//! let (_handle, rx) = watch_file(Path::new("notes.md"), options)?;
+//! for message in rx {
+//!     match message {
+//!         WatchMessage::ExternalUpdate(text) => println!("{} bytes", text.len()),
+//!         WatchMessage::Removed => break,
+//!         other => eprintln!("{other:?}"),
+//!     }
+//! }
+//! # Ok::<(), mdv_watch::notify::Error>(())
+//! ```
+//!
+//! Messages come from worker threads; keep the [`WatcherHandle`] alive for
+//! as long as updates are wanted.
+
+pub mod stream;
+pub mod watch;
+
+pub use notify;
+pub use stream::{StreamMessage, StreamOptions, stream_reader, stream_stdin};
+pub use watch::{WatchMessage, WatchOptions, WatcherHandle, watch_file};
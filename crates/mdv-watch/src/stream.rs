@@ -0,0 +1,309 @@
+use std::io::{self, BufRead, Read};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use mdv_core::unfenced_line_start;
+
+const DEFAULT_STREAM_MAX_BYTES: usize = 4 * 1024 * 1024;
+const DEFAULT_FULL_SYNC_EVERY: usize = 256;
+
+#[derive(Debug)]
+pub enum StreamMessage {
+    /// Text read since the previous message.
+    Append(String),
+    /// Everything kept so far: sent after the head was trimmed to the size
+    /// limit and every [`StreamOptions::full_sync_every`] appends. `dropped`
+    /// counts the bytes trimmed since the stream started.
+    Update {
+        text: String,
+        dropped: usize,
+    },
+    End,
+    Error(String),
+}
+
+/// Options for [`stream_stdin`] and [`stream_reader`].
+///
+/// Build with [`Default`] or [`StreamOptions::from_env`] and the `with_*`
+/// methods; fields may be added in minor releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StreamOptions {
+    /// Most bytes of text kept. Past it whole lines are dropped from the
+    /// front, and a [`StreamMessage::Update`] says how much went.
+    pub max_bytes: usize,
+    /// Appends between full copies of the text, so a reader that missed a
+    /// chunk catches up. Values below 1 count as 1.
+    pub full_sync_every: usize,
+}
+
+impl Default for StreamOptions {
+    /// 4 MiB, with a full copy every 256 appends.
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_STREAM_MAX_BYTES,
+            full_sync_every: DEFAULT_FULL_SYNC_EVERY,
+        }
+    }
+}
+
+impl StreamOptions {
+    /// The defaults, with `max_bytes` from `MDV_STREAM_MAX_BYTES` when that
+    /// is a positive number.
+    pub fn from_env() -> Self {
+        Self::default().with_max_bytes(stream_max_bytes_from_env())
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    pub fn with_full_sync_every(mut self, full_sync_every: usize) -> Self {
+        self.full_sync_every = full_sync_every;
+        self
+    }
+}
+
+/// Read stdin on a thread until it ends. The receiver gets a
+/// [`StreamMessage::End`] or [`StreamMessage::Error`] last.
+pub fn stream_stdin(options: StreamOptions) -> Receiver<StreamMessage> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let stdin = io::stdin();
+        let mut reader = io::BufReader::new(stdin.lock());
+        read_loop(&mut reader, &tx, options);
+    });
+    rx
+}
+
+/// [`stream_stdin`] for any reader, such as a pipe from a child process.
+pub fn stream_reader<R: Read + Send + 'static>(
+    reader: R,
+    options: StreamOptions,
+) -> Receiver<StreamMessage> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut reader = io::BufReader::new(reader);
+        read_loop(&mut reader, &tx, options);
+    });
+    rx
+}
+
+fn read_loop(reader: &mut dyn BufRead, tx: &Sender<StreamMessage>, options: StreamOptions) {
+    let mut acc = String::new();
+    let mut since_sync = 0usize;
+    let mut dropped = 0usize;
+
+    loop {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            Ok(0) => {
+                let _ = tx.send(StreamMessage::End);
+                break;
+            }
+            Ok(_) => {
+                acc.push_str(&line);
+                let trimmed = trim_head_to_max_bytes(&mut acc, options.max_bytes);
+                dropped += trimmed;
+                since_sync += 1;
+                let msg = if trimmed > 0 || since_sync >= options.full_sync_every.max(1) {
+                    since_sync = 0;
+                    StreamMessage::Update {
+                        text: acc.clone(),
+                        dropped,
+                    }
+                } else {
+                    StreamMessage::Append(line)
+                };
+                let _ = tx.send(msg);
+            }
+            Err(err) => {
+                let _ = tx.send(StreamMessage::Error(err.to_string()));
+                break;
+            }
+        }
+    }
+}
+
+fn stream_max_bytes_from_env() -> usize {
+    std::env::var("MDV_STREAM_MAX_BYTES")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_STREAM_MAX_BYTES)
+}
+
+/// Drop whole lines from the front of `text` until it fits in `max_bytes`,
+/// moving past any fenced block the cut would land in, and return how many
+/// bytes went.
+fn trim_head_to_max_bytes(text: &mut String, max_bytes: usize) -> usize {
+    if text.len() <= max_bytes {
+        return 0;
+    }
+    let cut = unfenced_line_start(text, text.len() - max_bytes);
+    text.drain(..cut);
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufReader, Cursor, Read};
+    use std::sync::Mutex;
+    use std::sync::mpsc;
+
+    use super::{
+        DEFAULT_FULL_SYNC_EVERY, DEFAULT_STREAM_MAX_BYTES, StreamMessage, StreamOptions, read_loop,
+        stream_max_bytes_from_env,
+    };
+
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    struct ErrorReader;
+    impl Read for ErrorReader {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("boom"))
+        }
+    }
+
+    #[test]
+    fn stream_message_debug() {
+        let msg = StreamMessage::Update {
+            text: "abc".into(),
+            dropped: 0,
+        };
+        let s = format!("{msg:?}");
+        assert!(s.contains("Update"));
+    }
+
+    #[test]
+    fn read_loop_sends_appends_then_end() {
+        let data = Cursor::new("a\nb\n");
+        let mut reader = BufReader::new(data);
+        let (tx, rx) = mpsc::channel();
+
+        read_loop(&mut reader, &tx, StreamOptions::default());
+
+        let messages: Vec<_> = rx.try_iter().collect();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(format!("{:?}", messages[0]), "Append(\"a\\n\")");
+        assert_eq!(format!("{:?}", messages[1]), "Append(\"b\\n\")");
+        assert_eq!(format!("{:?}", messages[2]), "End");
+    }
+
+    #[test]
+    fn read_loop_sends_a_full_sync_periodically() {
+        let data = Cursor::new("x\n".repeat(DEFAULT_FULL_SYNC_EVERY + 1));
+        let mut reader = BufReader::new(data);
+        let (tx, rx) = mpsc::channel();
+
+        read_loop(&mut reader, &tx, StreamOptions::default());
+
+        let messages: Vec<_> = rx.try_iter().collect();
+        assert_eq!(messages.len(), DEFAULT_FULL_SYNC_EVERY + 2);
+        match &messages[DEFAULT_FULL_SYNC_EVERY - 1] {
+            StreamMessage::Update { text, dropped } => {
+                assert_eq!(*text, "x\n".repeat(DEFAULT_FULL_SYNC_EVERY));
+                assert_eq!(*dropped, 0);
+            }
+            other => panic!("expected update, got {other:?}"),
+        }
+        assert!(
+            matches!(&messages[DEFAULT_FULL_SYNC_EVERY], StreamMessage::Append(line) if line == "x\n")
+        );
+    }
+
+    #[test]
+    fn read_loop_sends_error_on_reader_failure() {
+        let mut reader = BufReader::new(ErrorReader);
+        let (tx, rx) = mpsc::channel();
+        read_loop(&mut reader, &tx, StreamOptions::default());
+
+        let msg = rx.try_iter().next().expect("msg");
+        assert!(matches!(msg, StreamMessage::Error(err) if err.contains("boom")));
+    }
+
+    #[test]
+    fn read_loop_ignores_send_error_for_update_and_end_messages() {
+        let data = Cursor::new("a\n");
+        let mut reader = BufReader::new(data);
+        let (tx, rx) = mpsc::channel::<StreamMessage>();
+        drop(rx);
+        read_loop(&mut reader, &tx, StreamOptions::default());
+    }
+
+    #[test]
+    fn read_loop_ignores_send_error_for_error_message() {
+        let mut reader = BufReader::new(ErrorReader);
+        let (tx, rx) = mpsc::channel::<StreamMessage>();
+        drop(rx);
+        read_loop(&mut reader, &tx, StreamOptions::default());
+    }
+
+    #[test]
+    fn read_loop_truncates_accumulator_to_max_bytes() {
+        let data = Cursor::new("abcd\nxyz\n");
+        let mut reader = BufReader::new(data);
+        let (tx, rx) = mpsc::channel();
+
+        read_loop(&mut reader, &tx, StreamOptions::default().with_max_bytes(5));
+
+        let messages: Vec<_> = rx.try_iter().collect();
+        assert_eq!(messages.len(), 3);
+        assert!(matches!(&messages[0], StreamMessage::Append(line) if line == "abcd\n"));
+        match &messages[1] {
+            StreamMessage::Update { text, dropped } => {
+                assert_eq!(text, "xyz\n");
+                assert_eq!(*dropped, 5);
+            }
+            _ => panic!("expected update"),
+        }
+    }
+
+    #[test]
+    fn read_loop_trims_whole_lines_and_skips_open_fences() {
+        let data = Cursor::new("intro\n```\ncode one\ncode two\n```\nafter\nlast line\n");
+        let mut reader = BufReader::new(data);
+        let (tx, rx) = mpsc::channel();
+
+        read_loop(
+            &mut reader,
+            &tx,
+            StreamOptions::default().with_max_bytes(24),
+        );
+
+        let updates: Vec<_> = rx
+            .try_iter()
+            .filter_map(|msg| match msg {
+                StreamMessage::Update { text, dropped } => Some((text, dropped)),
+                _ => None,
+            })
+            .collect();
+        // A cut inside the fence drops the rest of it rather than starting
+        // on a bare code line.
+        assert_eq!(
+            updates,
+            [
+                ("```\ncode one\ncode two\n".to_string(), 6),
+                (String::new(), 32),
+            ]
+        );
+    }
+
+    #[test]
+    fn stream_max_bytes_from_env_parses_or_defaults() {
+        let _guard = ENV_LOCK.lock().expect("env lock");
+        unsafe { std::env::remove_var("MDV_STREAM_MAX_BYTES") };
+        assert_eq!(stream_max_bytes_from_env(), DEFAULT_STREAM_MAX_BYTES);
+
+        unsafe { std::env::set_var("MDV_STREAM_MAX_BYTES", "1024") };
+        assert_eq!(stream_max_bytes_from_env(), 1024);
+
+        unsafe { std::env::set_var("MDV_STREAM_MAX_BYTES", "bad") };
+        assert_eq!(stream_max_bytes_from_env(), DEFAULT_STREAM_MAX_BYTES);
+
+        unsafe { std::env::set_var("MDV_STREAM_MAX_BYTES", "0") };
+        assert_eq!(stream_max_bytes_from_env(), DEFAULT_STREAM_MAX_BYTES);
+
+        unsafe { std::env::remove_var("MDV_STREAM_MAX_BYTES") };
+    }
+}
@@ -0,0 +1,498 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use mdv_core::read_text;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher, recommended_watcher};
+
+/// Waits before re-reading a file that vanished, in case it is being swapped
+/// in by a rename (the save pattern of vim, VS Code and mdv itself).
+const REREAD_DELAYS_MS: [u64; 4] = [10, 20, 40, 80];
+
+#[derive(Debug)]
+pub enum WatchMessage {
+    /// The file's text after a change, or after it came back.
+    ExternalUpdate(String),
+    /// The file was gone and did not come back within the re-read delays.
+    Removed,
+    /// The file is there but could not be read, e.g. permission denied.
+    Unreadable(String),
+    /// The watch itself failed.
+    Error(String),
+}
+
+/// Options for [`watch_file`].
+///
+/// Build with [`Default`] and the `with_*` methods; fields may be added in
+/// minor releases.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct WatchOptions {
+    /// Read the file once events for it have stopped for this long, so a
+    /// burst of writes sends one update. Zero, the default, reads it on
+    /// every event.
+    pub debounce: Duration,
+}
+
+impl WatchOptions {
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+}
+
+/// Keeps a [`watch_file`] watch running; dropping it stops the watch and
+/// closes the channel.
+#[derive(Debug)]
+pub struct WatcherHandle {
+    _watcher: RecommendedWatcher,
+}
+
+/// Watch the file at `path`, which must exist. Its parent directory is
+/// watched rather than the file, so the watch survives saves that replace
+/// the file by a rename and sees it come back after a delete.
+pub fn watch_file(
+    path: &Path,
+    options: WatchOptions,
+) -> notify::Result<(WatcherHandle, Receiver<WatchMessage>)> {
+    start_with_factory(path, options, |mut handler| {
+        recommended_watcher(move |result: notify::Result<Event>| handler.handle_event(result))
+    })
+}
+
+fn start_with_factory<F>(
+    path: &Path,
+    options: WatchOptions,
+    make_watcher: F,
+) -> notify::Result<(WatcherHandle, Receiver<WatchMessage>)>
+where
+    F: FnOnce(Box<dyn notify::EventHandler>) -> notify::Result<RecommendedWatcher>,
+{
+    fs::metadata(path).map_err(notify::Error::io)?;
+    let watched_path = path.to_path_buf();
+    let (tx, rx) = mpsc::channel();
+    let handler: Box<dyn notify::EventHandler> = if options.debounce.is_zero() {
+        Box::new(move |result: notify::Result<Event>| {
+            handle_notify_result(result, &watched_path, &tx)
+        })
+    } else {
+        Box::new(debounce_events(watched_path, options.debounce, tx))
+    };
+
+    let mut watcher = make_watcher(handler)?;
+
+    // Watch the directory: atomic saves replace the file, which would
+    // orphan a watch on the file itself.
+    watcher.watch(watch_dir(path), RecursiveMode::NonRecursive)?;
+    Ok((WatcherHandle { _watcher: watcher }, rx))
+}
+
+fn handle_notify_result(
+    result: notify::Result<Event>,
+    watched_path: &Path,
+    tx: &Sender<WatchMessage>,
+) {
+    match result {
+        Ok(event) => {
+            if concerns(&event, watched_path) {
+                let _ = tx.send(read_message(watched_path));
+            }
+        }
+        Err(err) => {
+            let _ = tx.send(WatchMessage::Error(err.to_string()));
+        }
+    }
+}
+
+/// A sender for notify events that reads `watched_path` on a thread once
+/// they have been quiet for `debounce`. The thread ends with the watcher.
+fn debounce_events(
+    watched_path: PathBuf,
+    debounce: Duration,
+    tx: Sender<WatchMessage>,
+) -> Sender<notify::Result<Event>> {
+    let (events_tx, events) = mpsc::channel::<notify::Result<Event>>();
+    thread::spawn(move || {
+        let mut due: Option<Instant> = None;
+        loop {
+            let next = match due {
+                Some(at) => events.recv_timeout(at.saturating_duration_since(Instant::now())),
+                None => events.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            let message = match next {
+                Ok(Ok(event)) => {
+                    // Events for other files in the directory do not put
+                    // the read off.
+                    if concerns(&event, &watched_path) {
+                        due = Some(Instant::now() + debounce);
+                    }
+                    continue;
+                }
+                Ok(Err(err)) => WatchMessage::Error(err.to_string()),
+                Err(RecvTimeoutError::Timeout) => {
+                    due = None;
+                    read_message(&watched_path)
+                }
+                Err(RecvTimeoutError::Disconnected) => return,
+            };
+            if tx.send(message).is_err() {
+                return;
+            }
+        }
+    });
+    events_tx
+}
+
+/// Whether `event` changed the file at `watched_path`.
+fn concerns(event: &Event, watched_path: &Path) -> bool {
+    is_relevant(&event.kind)
+        && event
+            .paths
+            .iter()
+            .any(|event_path| same_file(event_path, watched_path))
+}
+
+/// What reading the file after an event found.
+fn read_message(path: &Path) -> WatchMessage {
+    match read_with_retry(path) {
+        Ok(text) => WatchMessage::ExternalUpdate(text),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => WatchMessage::Removed,
+        Err(err) => WatchMessage::Unreadable(err.to_string()),
+    }
+}
+
+/// Read the file, retrying while it is missing: a remove event can arrive
+/// between the old file going away and the new one being renamed in.
+fn read_with_retry(path: &Path) -> io::Result<String> {
+    let mut delays = REREAD_DELAYS_MS.iter();
+    loop {
+        match read_text(path) {
+            Ok(file) => return Ok(file.text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => match delays.next() {
+                Some(ms) => thread::sleep(Duration::from_millis(*ms)),
+                None => return Err(err),
+            },
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+fn watch_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+fn is_relevant(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_)
+    )
+}
+
+/// Compares by name within the canonical directory, so a path still matches
+/// while the file itself is missing mid-rename.
+fn same_file(a: &Path, b: &Path) -> bool {
+    if a == b {
+        return true;
+    }
+
+    let ca = canonical(a);
+    if ca.is_some() && ca == canonical(b) {
+        return true;
+    }
+    a.file_name().is_some()
+        && a.file_name() == b.file_name()
+        && canonical(watch_dir(a)).is_some_and(|dir| Some(dir) == canonical(watch_dir(b)))
+}
+
+fn canonical(path: &Path) -> Option<PathBuf> {
+    fs::canonicalize(path).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use notify::Event;
+    use notify::EventKind;
+    use notify::event::{CreateKind, ModifyKind, RemoveKind};
+
+    use super::{WatchMessage, WatchOptions, handle_notify_result, is_relevant, same_file};
+
+    #[test]
+    fn relevant_event_filter_works() {
+        assert!(is_relevant(&EventKind::Modify(ModifyKind::Any)));
+        assert!(is_relevant(&EventKind::Create(CreateKind::Any)));
+        assert!(!is_relevant(&EventKind::Access(
+            notify::event::AccessKind::Any
+        )));
+    }
+
+    #[test]
+    fn same_file_handles_equal_and_canonical_paths() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("mdv-watch-same-file-test.md");
+        let canonical = std::fs::canonicalize(&dir).expect("canonical temp dir");
+        let alternate = canonical.join("mdv-watch-same-file-test.md");
+
+        std::fs::write(&path, "x").expect("seed");
+        assert!(same_file(&path, &path));
+        assert!(same_file(&path, &alternate));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn handle_notify_result_sends_update() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("mdv-watch-update-test.md");
+        std::fs::write(&path, "new").expect("seed");
+        let event = Event {
+            kind: EventKind::Modify(ModifyKind::Any),
+            paths: vec![path.clone()],
+            attrs: Default::default(),
+        };
+        let (tx, rx) = mpsc::channel();
+
+        handle_notify_result(Ok(event), &path, &tx);
+
+        assert!(matches!(
+            rx.recv().expect("msg"),
+            WatchMessage::ExternalUpdate(text) if text == "new"
+        ));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn handle_notify_result_sends_error_on_notify_error() {
+        let path = std::env::temp_dir().join("mdv-watch-error-test.md");
+        let (tx, rx) = mpsc::channel();
+        handle_notify_result(Err(notify::Error::generic("watch failed")), &path, &tx);
+
+        assert!(matches!(
+            rx.recv().expect("msg"),
+            WatchMessage::Error(err) if err.contains("watch failed")
+        ));
+    }
+
+    #[test]
+    fn handle_notify_result_ignores_irrelevant_or_other_path() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("mdv-watch-ignore-test.md");
+        std::fs::write(&path, "x").expect("seed");
+        let other = dir.join("mdv-watch-ignore-test-other.md");
+        std::fs::write(&other, "y").expect("seed other");
+
+        let irrelevant = Event {
+            kind: EventKind::Access(notify::event::AccessKind::Any),
+            paths: vec![path.clone()],
+            attrs: Default::default(),
+        };
+        let mismatched = Event {
+            kind: EventKind::Modify(ModifyKind::Any),
+            paths: vec![other.clone()],
+            attrs: Default::default(),
+        };
+
+        let (tx, rx) = mpsc::channel();
+        handle_notify_result(Ok(irrelevant), &path, &tx);
+        handle_notify_result(Ok(mismatched), &path, &tx);
+        assert!(rx.try_recv().is_err());
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&other);
+    }
+
+    #[test]
+    fn handle_notify_result_sends_read_error() {
+        let path = std::env::temp_dir().join("mdv-watch-read-error-test.md");
+        std::fs::create_dir_all(&path).expect("dir in place of file");
+        let event = Event {
+            kind: EventKind::Modify(ModifyKind::Any),
+            paths: vec![path.clone()],
+            attrs: Default::default(),
+        };
+        let (tx, rx) = mpsc::channel();
+        handle_notify_result(Ok(event), &path, &tx);
+        let msg = rx.recv().expect("msg");
+        let debug = format!("{msg:?}");
+        assert!(debug.starts_with("Unreadable(\""), "debug: {debug}");
+        let _ = std::fs::remove_dir(&path);
+    }
+
+    #[test]
+    fn handle_notify_result_follows_remove_then_create() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("mdv-watch-remove-create-test.md");
+        let event = |kind| Event {
+            kind,
+            paths: vec![path.clone()],
+            attrs: Default::default(),
+        };
+        let (tx, rx) = mpsc::channel();
+
+        let _ = std::fs::remove_file(&path);
+        handle_notify_result(Ok(event(EventKind::Remove(RemoveKind::File))), &path, &tx);
+        assert!(matches!(rx.recv().expect("msg"), WatchMessage::Removed));
+
+        std::fs::write(&path, "back").expect("recreate");
+        handle_notify_result(Ok(event(EventKind::Create(CreateKind::File))), &path, &tx);
+        assert!(matches!(
+            rx.recv().expect("msg"),
+            WatchMessage::ExternalUpdate(text) if text == "back"
+        ));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn handle_notify_result_rereads_when_the_file_returns_late() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("mdv-watch-late-create-test.md");
+        let _ = std::fs::remove_file(&path);
+        let writer = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(15));
+                std::fs::write(path, "swapped").expect("create");
+            })
+        };
+        let event = Event {
+            kind: EventKind::Remove(RemoveKind::File),
+            paths: vec![path.clone()],
+            attrs: Default::default(),
+        };
+        let (tx, rx) = mpsc::channel();
+        handle_notify_result(Ok(event), &path, &tx);
+        writer.join().expect("writer");
+
+        assert!(matches!(
+            rx.recv().expect("msg"),
+            WatchMessage::ExternalUpdate(text) if text == "swapped"
+        ));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn same_file_matches_a_missing_file_by_name() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("mdv-watch-missing-same-file-test.md");
+        let _ = std::fs::remove_file(&path);
+        let canonical = std::fs::canonicalize(&dir).expect("canonical temp dir");
+
+        assert!(same_file(
+            &canonical.join("mdv-watch-missing-same-file-test.md"),
+            &path
+        ));
+        assert!(!same_file(&canonical.join("other.md"), &path));
+    }
+
+    #[test]
+    fn start_initializes_watcher() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("mdv-watch-start-test.md");
+        std::fs::write(&path, "x").expect("seed");
+        let started = super::watch_file(&path, WatchOptions::default());
+        assert!(started.is_ok());
+        let (_watcher, _rx) = started.expect("watcher");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn start_keeps_reporting_after_file_is_replaced_by_rename() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("mdv-watch-rename-test.md");
+        let tmp = dir.join(".mdv-watch-rename-test.md.mdv-tmp");
+        std::fs::write(&path, "old").expect("seed");
+        let (_watcher, rx) = super::watch_file(&path, WatchOptions::default()).expect("watcher");
+
+        for round in ["first", "second"] {
+            std::fs::write(&tmp, round).expect("write temp");
+            std::fs::rename(&tmp, &path).expect("rename");
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+            loop {
+                let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+                match rx.recv_timeout(remaining).expect("update after rename") {
+                    WatchMessage::ExternalUpdate(text) if text == round => break,
+                    _ => continue,
+                }
+            }
+        }
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn start_follows_a_file_moved_away_and_written_again() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("mdv-watch-swap-test.md");
+        let backup = dir.join("mdv-watch-swap-test.md~");
+        std::fs::write(&path, "old").expect("seed");
+        let (_watcher, rx) = super::watch_file(&path, WatchOptions::default()).expect("watcher");
+
+        std::fs::rename(&path, &backup).expect("move away");
+        std::fs::write(&path, "rewritten").expect("write again");
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        loop {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            match rx.recv_timeout(remaining).expect("update after swap") {
+                WatchMessage::ExternalUpdate(text) if text == "rewritten" => break,
+                _ => continue,
+            }
+        }
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&backup);
+    }
+
+    #[test]
+    fn start_returns_error_for_missing_path() {
+        let path = std::env::temp_dir().join("mdv-watch-missing-start-test.md");
+        let started = super::watch_file(&path, WatchOptions::default());
+        assert!(started.is_err());
+    }
+
+    #[test]
+    fn start_propagates_watcher_constructor_error() {
+        let path = std::env::temp_dir().join("mdv-watch-factory-error-test.md");
+        std::fs::write(&path, "x").expect("seed");
+        let started = super::start_with_factory(&path, WatchOptions::default(), |_handler| {
+            Err::<notify::RecommendedWatcher, _>(notify::Error::generic("factory failed"))
+        });
+        assert!(matches!(started, Err(err) if err.to_string().contains("factory failed")));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn start_callback_emits_update_after_write() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("mdv-watch-callback-test.md");
+        std::fs::write(&path, "b").expect("seed");
+        let event_path = path.clone();
+        let (_watcher, rx) =
+            super::start_with_factory(&path, WatchOptions::default(), move |mut handler| {
+                let event = Event {
+                    kind: EventKind::Modify(ModifyKind::Any),
+                    paths: vec![event_path.clone()],
+                    attrs: Default::default(),
+                };
+                handler.handle_event(Ok(event));
+                notify::recommended_watcher(move |_result: notify::Result<Event>| {})
+            })
+            .expect("start");
+
+        assert!(matches!(
+            rx.recv_timeout(std::time::Duration::from_millis(250)).expect("msg"),
+            WatchMessage::ExternalUpdate(text) if text == "b"
+        ));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
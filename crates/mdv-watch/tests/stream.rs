@@ -0,0 +1,37 @@
+use std::io::Cursor;
+
+use mdv_watch::{StreamMessage, StreamOptions, stream_reader};
+
+#[test]
+fn a_reader_streams_lines_under_the_byte_cap() {
+    let options = StreamOptions::default()
+        .with_max_bytes(12)
+        .with_full_sync_every(3);
+    let rx = stream_reader(Cursor::new("one\ntwo\nthree\nfour\n"), options);
+    let messages: Vec<StreamMessage> = rx.iter().collect();
+    let shown: Vec<String> = messages.iter().map(|msg| format!("{msg:?}")).collect();
+    assert_eq!(
+        shown,
+        [
+            r#"Append("one\n")"#,
+            r#"Append("two\n")"#,
+            r#"Update { text: "two\nthree\n", dropped: 4 }"#,
+            r#"Update { text: "three\nfour\n", dropped: 8 }"#,
+            "End",
+        ]
+    );
+}
+
+#[test]
+fn full_copies_come_every_few_appends() {
+    let options = StreamOptions::default().with_full_sync_every(2);
+    let rx = stream_reader(Cursor::new("a\nb\nc\n"), options);
+    let updates: Vec<String> = rx
+        .iter()
+        .filter_map(|msg| match msg {
+            StreamMessage::Update { text, .. } => Some(text),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(updates, ["a\nb\n"]);
+}
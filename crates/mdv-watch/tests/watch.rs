@@ -0,0 +1,112 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use mdv_watch::{WatchMessage, WatchOptions, watch_file};
+
+/// A fresh directory per test, so events from one do not reach another.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mdv-watch-it-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("temp dir");
+    dir
+}
+
+/// Every update until `text` arrives, failing after two seconds.
+#[cfg(target_os = "linux")]
+fn updates_until(rx: &Receiver<WatchMessage>, text: &str) -> Vec<String> {
+    let deadline = Instant::now() + Duration::from_secs(2);
+    let mut seen = Vec::new();
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(remaining) {
+            Ok(WatchMessage::ExternalUpdate(update)) => {
+                let done = update == text;
+                seen.push(update);
+                if done {
+                    return seen;
+                }
+            }
+            Ok(_) => {}
+            Err(err) => panic!("no {text:?} after {seen:?}: {err}"),
+        }
+    }
+}
+
+/// Save `text` the way editors do: write a temp file, rename it over.
+#[cfg(target_os = "linux")]
+fn rename_over(dir: &std::path::Path, path: &std::path::Path, text: &str) {
+    let tmp = dir.join(".notes.md.tmp");
+    fs::write(&tmp, text).expect("write temp");
+    fs::rename(&tmp, path).expect("rename");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_watch_follows_saves_that_rename_a_new_file_in() {
+    let dir = temp_dir("rename");
+    let path = dir.join("notes.md");
+    fs::write(&path, "old").expect("seed");
+    let (_handle, rx) = watch_file(&path, WatchOptions::default()).expect("watch");
+
+    for round in ["first", "second"] {
+        rename_over(&dir, &path, round);
+        updates_until(&rx, round);
+    }
+    // Moved away and written again, as editors that keep a backup do.
+    fs::rename(&path, dir.join("notes.md~")).expect("move away");
+    fs::write(&path, "rewritten").expect("write again");
+    updates_until(&rx, "rewritten");
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_burst_of_writes_is_read_once_after_the_debounce() {
+    let dir = temp_dir("debounce");
+    let path = dir.join("notes.md");
+    fs::write(&path, "seed").expect("seed");
+    let options = WatchOptions::default().with_debounce(Duration::from_millis(200));
+    let (_handle, rx) = watch_file(&path, options).expect("watch");
+
+    for round in 0..5 {
+        fs::write(&path, format!("build {round}")).expect("write");
+        rename_over(&dir, &path, &format!("build {round} renamed"));
+    }
+    // Files next to it do not put the read off or send anything.
+    fs::write(dir.join("other.md"), "x").expect("other");
+    assert_eq!(updates_until(&rx, "build 4 renamed"), ["build 4 renamed"]);
+    assert!(matches!(
+        rx.recv_timeout(Duration::from_millis(400)),
+        Err(RecvTimeoutError::Timeout)
+    ));
+
+    fs::write(&path, "later").expect("write later");
+    assert_eq!(updates_until(&rx, "later"), ["later"]);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn dropping_the_handle_closes_the_channel() {
+    let dir = temp_dir("drop");
+    let path = dir.join("notes.md");
+    fs::write(&path, "seed").expect("seed");
+    for options in [
+        WatchOptions::default(),
+        WatchOptions::default().with_debounce(Duration::from_millis(50)),
+    ] {
+        let (handle, rx) = watch_file(&path, options).expect("watch");
+        drop(handle);
+        fs::write(&path, "after").expect("write");
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while !matches!(
+            rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            Err(RecvTimeoutError::Disconnected)
+        ) {
+            assert!(Instant::now() < deadline, "channel still open");
+        }
+    }
+    assert!(watch_file(&dir.join("missing.md"), WatchOptions::default()).is_err());
+    let _ = fs::remove_dir_all(&dir);
+}